@@ -0,0 +1,11 @@
+ALTER TABLE http_trigger DROP COLUMN IF EXISTS payload_transform;
+ALTER TABLE websocket_trigger DROP COLUMN IF EXISTS payload_transform;
+ALTER TABLE kafka_trigger DROP COLUMN IF EXISTS payload_transform;
+ALTER TABLE nats_trigger DROP COLUMN IF EXISTS payload_transform;
+ALTER TABLE mqtt_trigger DROP COLUMN IF EXISTS payload_transform;
+ALTER TABLE amqp_trigger DROP COLUMN IF EXISTS payload_transform;
+ALTER TABLE postgres_trigger DROP COLUMN IF EXISTS payload_transform;
+ALTER TABLE sqs_trigger DROP COLUMN IF EXISTS payload_transform;
+ALTER TABLE gcp_trigger DROP COLUMN IF EXISTS payload_transform;
+ALTER TABLE azure_trigger DROP COLUMN IF EXISTS payload_transform;
+ALTER TABLE email_trigger DROP COLUMN IF EXISTS payload_transform;
@@ -0,0 +1,11 @@
+ALTER TABLE http_trigger ADD COLUMN IF NOT EXISTS payload_transform TEXT;
+ALTER TABLE websocket_trigger ADD COLUMN IF NOT EXISTS payload_transform TEXT;
+ALTER TABLE kafka_trigger ADD COLUMN IF NOT EXISTS payload_transform TEXT;
+ALTER TABLE nats_trigger ADD COLUMN IF NOT EXISTS payload_transform TEXT;
+ALTER TABLE mqtt_trigger ADD COLUMN IF NOT EXISTS payload_transform TEXT;
+ALTER TABLE amqp_trigger ADD COLUMN IF NOT EXISTS payload_transform TEXT;
+ALTER TABLE postgres_trigger ADD COLUMN IF NOT EXISTS payload_transform TEXT;
+ALTER TABLE sqs_trigger ADD COLUMN IF NOT EXISTS payload_transform TEXT;
+ALTER TABLE gcp_trigger ADD COLUMN IF NOT EXISTS payload_transform TEXT;
+ALTER TABLE azure_trigger ADD COLUMN IF NOT EXISTS payload_transform TEXT;
+ALTER TABLE email_trigger ADD COLUMN IF NOT EXISTS payload_transform TEXT;
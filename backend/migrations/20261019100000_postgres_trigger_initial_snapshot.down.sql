@@ -0,0 +1,2 @@
+ALTER TABLE postgres_trigger DROP COLUMN IF EXISTS snapshot_completed_at;
+ALTER TABLE postgres_trigger DROP COLUMN IF EXISTS initial_snapshot;
@@ -0,0 +1,2 @@
+ALTER TABLE postgres_trigger ADD COLUMN IF NOT EXISTS initial_snapshot BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE postgres_trigger ADD COLUMN IF NOT EXISTS snapshot_completed_at TIMESTAMPTZ;
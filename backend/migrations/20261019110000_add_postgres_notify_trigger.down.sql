@@ -0,0 +1 @@
+DROP TABLE postgres_notify_trigger;
@@ -0,0 +1 @@
+DROP TABLE mysql_trigger;
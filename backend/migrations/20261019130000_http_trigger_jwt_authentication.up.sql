@@ -0,0 +1,2 @@
+-- Add up migration script here
+ALTER TYPE AUTHENTICATION_METHOD ADD VALUE IF NOT EXISTS 'jwt';
@@ -0,0 +1,4 @@
+-- Add down migration script here
+DROP TABLE IF EXISTS http_trigger_rate_limit_window;
+DROP TABLE IF EXISTS http_trigger_rate_limit_bucket;
+ALTER TABLE http_trigger DROP COLUMN IF EXISTS rate_limit;
@@ -0,0 +1,34 @@
+-- Add up migration script here
+ALTER TABLE http_trigger ADD COLUMN rate_limit JSONB NULL;
+
+CREATE TABLE http_trigger_rate_limit_bucket (
+    workspace_id VARCHAR(50) NOT NULL,
+    trigger_path VARCHAR(255) NOT NULL,
+    client_key VARCHAR(255) NOT NULL,
+    tokens DOUBLE PRECISION NOT NULL,
+    allowed BOOLEAN NOT NULL,
+    refilled_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (workspace_id, trigger_path, client_key),
+    FOREIGN KEY (trigger_path, workspace_id) REFERENCES http_trigger (path, workspace_id)
+        ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+CREATE TABLE http_trigger_rate_limit_window (
+    workspace_id VARCHAR(50) NOT NULL,
+    trigger_path VARCHAR(255) NOT NULL,
+    counter VARCHAR(50) NOT NULL,
+    client_key VARCHAR(255) NOT NULL,
+    window_start TIMESTAMPTZ NOT NULL,
+    request_count BIGINT NOT NULL,
+    PRIMARY KEY (workspace_id, trigger_path, counter, client_key),
+    FOREIGN KEY (trigger_path, workspace_id) REFERENCES http_trigger (path, workspace_id)
+        ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+CREATE INDEX http_trigger_rate_limit_bucket_refilled_at_idx ON http_trigger_rate_limit_bucket (refilled_at);
+CREATE INDEX http_trigger_rate_limit_window_window_start_idx ON http_trigger_rate_limit_window (window_start);
+
+GRANT ALL ON http_trigger_rate_limit_bucket TO windmill_user;
+GRANT ALL ON http_trigger_rate_limit_bucket TO windmill_admin;
+GRANT ALL ON http_trigger_rate_limit_window TO windmill_user;
+GRANT ALL ON http_trigger_rate_limit_window TO windmill_admin;
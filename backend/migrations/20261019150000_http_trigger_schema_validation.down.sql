@@ -0,0 +1,3 @@
+-- Add down migration script here
+ALTER TABLE http_trigger DROP COLUMN IF EXISTS response_mapping;
+ALTER TABLE http_trigger DROP COLUMN IF EXISTS request_validation;
@@ -0,0 +1,3 @@
+-- Add up migration script here
+ALTER TABLE http_trigger ADD COLUMN request_validation JSONB NULL;
+ALTER TABLE http_trigger ADD COLUMN response_mapping JSONB NULL;
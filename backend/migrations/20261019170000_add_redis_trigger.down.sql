@@ -0,0 +1 @@
+DROP TABLE redis_trigger;
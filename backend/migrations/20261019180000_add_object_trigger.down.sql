@@ -0,0 +1,2 @@
+DROP TABLE object_trigger_seen;
+DROP TABLE object_trigger;
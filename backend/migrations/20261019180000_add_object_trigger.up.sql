@@ -0,0 +1,103 @@
+CREATE TABLE object_trigger (
+    storage VARCHAR(255) NULL,
+    prefix TEXT NOT NULL DEFAULT '',
+    delivery VARCHAR(20) NOT NULL DEFAULT 'list',
+    poll_interval_secs INTEGER NOT NULL DEFAULT 60,
+    watermark TIMESTAMPTZ NULL,
+    path VARCHAR(255) NOT NULL,
+    script_path VARCHAR(255) NOT NULL,
+    is_flow BOOLEAN NOT NULL,
+    workspace_id VARCHAR(50) NOT NULL,
+    edited_by VARCHAR(50) NOT NULL,
+    edited_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    extra_perms JSONB NOT NULL DEFAULT '{}',
+    server_id VARCHAR(50) NULL,
+    last_server_ping TIMESTAMPTZ NULL,
+    error TEXT NULL,
+    error_handler_path VARCHAR(255) NULL,
+    error_handler_args JSONB NULL,
+    retry JSONB NULL,
+    mode TRIGGER_MODE NOT NULL DEFAULT 'enabled'::TRIGGER_MODE,
+    permissioned_as VARCHAR(255) NOT NULL,
+    labels TEXT[] NULL,
+    payload_transform TEXT NULL,
+    PRIMARY KEY (path, workspace_id),
+    FOREIGN KEY (workspace_id) REFERENCES workspace(id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_object_trigger_labels ON object_trigger USING gin (labels) WHERE labels IS NOT NULL;
+
+GRANT ALL ON object_trigger TO windmill_user;
+GRANT ALL ON object_trigger TO windmill_admin;
+
+ALTER TABLE object_trigger ENABLE ROW LEVEL SECURITY;
+
+CREATE POLICY admin_policy ON object_trigger FOR ALL TO windmill_admin USING (true);
+
+CREATE POLICY see_folder_extra_perms_user_select ON object_trigger FOR SELECT TO windmill_user
+USING (SPLIT_PART(object_trigger.path, '/', 1) = 'f' AND SPLIT_PART(object_trigger.path, '/', 2) = any((select regexp_split_to_array(current_setting('session.folders_read'), ','))::text[]));
+CREATE POLICY see_folder_extra_perms_user_insert ON object_trigger FOR INSERT TO windmill_user
+WITH CHECK (SPLIT_PART(object_trigger.path, '/', 1) = 'f' AND SPLIT_PART(object_trigger.path, '/', 2) = any((select regexp_split_to_array(current_setting('session.folders_write'), ','))::text[]));
+CREATE POLICY see_folder_extra_perms_user_update ON object_trigger FOR UPDATE TO windmill_user
+USING (SPLIT_PART(object_trigger.path, '/', 1) = 'f' AND SPLIT_PART(object_trigger.path, '/', 2) = any((select regexp_split_to_array(current_setting('session.folders_write'), ','))::text[]));
+CREATE POLICY see_folder_extra_perms_user_delete ON object_trigger FOR DELETE TO windmill_user
+USING (SPLIT_PART(object_trigger.path, '/', 1) = 'f' AND SPLIT_PART(object_trigger.path, '/', 2) = any((select regexp_split_to_array(current_setting('session.folders_write'), ','))::text[]));
+
+CREATE POLICY see_own ON object_trigger FOR ALL TO windmill_user
+USING (SPLIT_PART(object_trigger.path, '/', 1) = 'u' AND SPLIT_PART(object_trigger.path, '/', 2) = (select current_setting('session.user')));
+CREATE POLICY see_member ON object_trigger FOR ALL TO windmill_user
+USING (SPLIT_PART(object_trigger.path, '/', 1) = 'g' AND SPLIT_PART(object_trigger.path, '/', 2) = any((select regexp_split_to_array(current_setting('session.groups'), ','))::text[]));
+
+CREATE POLICY see_extra_perms_user_select ON object_trigger FOR SELECT TO windmill_user
+USING (extra_perms ? (select concat('u/', current_setting('session.user'))));
+CREATE POLICY see_extra_perms_user_insert ON object_trigger FOR INSERT TO windmill_user
+WITH CHECK ((extra_perms ->> (select concat('u/', current_setting('session.user'))))::boolean);
+CREATE POLICY see_extra_perms_user_update ON object_trigger FOR UPDATE TO windmill_user
+USING ((extra_perms ->> (select concat('u/', current_setting('session.user'))))::boolean);
+CREATE POLICY see_extra_perms_user_delete ON object_trigger FOR DELETE TO windmill_user
+USING ((extra_perms ->> (select concat('u/', current_setting('session.user'))))::boolean);
+
+CREATE POLICY see_extra_perms_groups_select ON object_trigger FOR SELECT TO windmill_user
+USING (extra_perms ?| (select regexp_split_to_array(current_setting('session.pgroups'), ','))::text[]);
+CREATE POLICY see_extra_perms_groups_insert ON object_trigger FOR INSERT TO windmill_user
+WITH CHECK (exists(
+    SELECT key, value FROM jsonb_each_text(extra_perms)
+    WHERE SPLIT_PART(key, '/', 1) = 'g' AND key = ANY((select regexp_split_to_array(current_setting('session.pgroups'), ','))::text[])
+    AND value::boolean));
+CREATE POLICY see_extra_perms_groups_update ON object_trigger FOR UPDATE TO windmill_user
+USING (exists(
+    SELECT key, value FROM jsonb_each_text(extra_perms)
+    WHERE SPLIT_PART(key, '/', 1) = 'g' AND key = ANY((select regexp_split_to_array(current_setting('session.pgroups'), ','))::text[])
+    AND value::boolean));
+CREATE POLICY see_extra_perms_groups_delete ON object_trigger FOR DELETE  TO windmill_user
+USING (exists(
+    SELECT key, value FROM jsonb_each_text(extra_perms)
+    WHERE SPLIT_PART(key, '/', 1) = 'g' AND key = ANY((select regexp_split_to_array(current_setting('session.pgroups'), ','))::text[])
+    AND value::boolean));
+
+-- Versions of the objects a trigger already handled, so that a new or changed object
+-- starts exactly one job even when two servers list the storage at once.
+CREATE TABLE object_trigger_seen (
+    workspace_id VARCHAR(50) NOT NULL,
+    trigger_path VARCHAR(255) NOT NULL,
+    object_key TEXT NOT NULL,
+    version TEXT NOT NULL,
+    last_modified TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (workspace_id, trigger_path, object_key, version),
+    FOREIGN KEY (trigger_path, workspace_id) REFERENCES object_trigger(path, workspace_id)
+        ON DELETE CASCADE ON UPDATE CASCADE
+);
+
+CREATE INDEX idx_object_trigger_seen_last_modified ON object_trigger_seen (workspace_id, trigger_path, last_modified);
+
+GRANT ALL ON object_trigger_seen TO windmill_user;
+GRANT ALL ON object_trigger_seen TO windmill_admin;
+
+-- Enum values for the new trigger kind. ALTER TYPE ... ADD VALUE runs inside the
+-- migration transaction on PG >= 14 (Windmill's minimum) as long as the value
+-- isn't used in the same transaction — the object_trigger tables above do not
+-- reference these enum types.
+ALTER TYPE TRIGGER_KIND ADD VALUE IF NOT EXISTS 'object';
+ALTER TYPE job_trigger_kind ADD VALUE IF NOT EXISTS 'object';
+ALTER TYPE draft_kind ADD VALUE IF NOT EXISTS 'trigger_object';
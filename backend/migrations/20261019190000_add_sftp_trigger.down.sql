@@ -0,0 +1,2 @@
+DROP TABLE sftp_trigger_file;
+DROP TABLE sftp_trigger;
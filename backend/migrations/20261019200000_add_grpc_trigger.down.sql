@@ -0,0 +1,2 @@
+DROP SEQUENCE grpc_trigger_version_seq;
+DROP TABLE grpc_trigger;
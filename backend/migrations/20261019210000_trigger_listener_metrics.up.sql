@@ -0,0 +1,48 @@
+-- Rolling throughput of each listener trigger, persisted by the server listening to it
+-- so that the API of any server can report it.
+CREATE TABLE IF NOT EXISTS trigger_listener_metrics (
+    workspace_id VARCHAR(50) NOT NULL REFERENCES workspace(id) ON DELETE CASCADE,
+    -- A trigger's TRIGGER_TYPE ('kafka', 'redis', ...), as in trigger_history.
+    trigger_kind VARCHAR(50) NOT NULL,
+    path VARCHAR(255) NOT NULL,
+    server_id VARCHAR(50) NOT NULL,
+    -- When server_id started listening, the totals below being counted since then.
+    started_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    received BIGINT NOT NULL DEFAULT 0,
+    filtered BIGINT NOT NULL DEFAULT 0,
+    jobs_pushed BIGINT NOT NULL DEFAULT 0,
+    last_message_at TIMESTAMP WITH TIME ZONE,
+    -- NULL when the protocol does not expose it.
+    consumer_lag BIGINT,
+    -- [{minute, received, filtered, jobs_pushed}] for the last hour.
+    buckets JSONB NOT NULL DEFAULT '[]'::jsonb,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (workspace_id, trigger_kind, path)
+);
+
+GRANT ALL ON TABLE trigger_listener_metrics TO windmill_user;
+GRANT ALL ON TABLE trigger_listener_metrics TO windmill_admin;
+
+ALTER TABLE trigger_listener_metrics ENABLE ROW LEVEL SECURITY;
+
+CREATE POLICY admin_all ON trigger_listener_metrics FOR ALL TO windmill_admin USING (true) WITH CHECK (true);
+
+-- Only the listeners write, through the admin pool. Reads follow the path of the trigger,
+-- like trigger_history.
+CREATE POLICY see_own ON trigger_listener_metrics FOR SELECT TO windmill_user
+USING (
+    SPLIT_PART(path::text, '/', 1) = 'u'
+    AND SPLIT_PART(path::text, '/', 2) = current_setting('session.user')
+);
+
+CREATE POLICY see_member ON trigger_listener_metrics FOR SELECT TO windmill_user
+USING (
+    SPLIT_PART(path::text, '/', 1) = 'g'
+    AND SPLIT_PART(path::text, '/', 2) = ANY(regexp_split_to_array(current_setting('session.groups'), ','))
+);
+
+CREATE POLICY see_folder_extra_perms_user ON trigger_listener_metrics FOR SELECT TO windmill_user
+USING (
+    SPLIT_PART(path::text, '/', 1) = 'f'
+    AND SPLIT_PART(path::text, '/', 2) = ANY(regexp_split_to_array(current_setting('session.folders_read'), ','))
+);
@@ -0,0 +1,3 @@
+ALTER TYPE SCRIPT_LANG ADD VALUE IF NOT EXISTS 'wasm';
+UPDATE config SET config = jsonb_set(config, '{worker_tags}', config->'worker_tags' || '["wasm"]'::jsonb) WHERE name = 'worker__default' AND config @> '{"worker_tags": ["deno", "python3", "go", "bash", "powershell", "dependency", "flow", "hub", "other", "bun", "php", "rust", "ansible", "csharp", "nu", "java", "duckdb", "ruby", "rlang", "dbt", "lua", "kotlin", "perl"]}'::jsonb AND NOT config->'worker_tags' @> '"wasm"'::jsonb;
+UPDATE config SET config = jsonb_set(config, '{worker_tags}', config->'worker_tags' || '["wasm"]'::jsonb) WHERE name = 'worker__native' AND config @> '{"worker_tags": ["nativets", "postgresql", "mysql", "graphql", "snowflake", "bigquery", "mssql", "lua"]}'::jsonb AND NOT config->'worker_tags' @> '"wasm"'::jsonb;
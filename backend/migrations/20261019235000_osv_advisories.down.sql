@@ -0,0 +1,2 @@
+ALTER TABLE workspace_settings DROP COLUMN IF EXISTS block_critical_vulnerabilities;
+DROP TABLE IF EXISTS osv_advisory;
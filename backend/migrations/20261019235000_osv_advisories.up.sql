@@ -0,0 +1,25 @@
+-- Offline snapshot of the OSV advisory database (https://osv.dev), imported by a superadmin
+-- and matched against the locks of scripts, flows and apps. One row per advisory and
+-- affected package: an advisory affecting several packages is split on import.
+CREATE TABLE IF NOT EXISTS osv_advisory (
+    -- OSV ecosystem ('PyPI', 'npm', ...) and package name, normalized like the
+    -- names read from lockfiles so that matching is a plain equality.
+    ecosystem VARCHAR(50) NOT NULL,
+    package VARCHAR(500) NOT NULL,
+    id VARCHAR(100) NOT NULL,
+    summary TEXT,
+    -- 'critical', 'high', 'moderate', 'low' or 'unknown'.
+    severity VARCHAR(20) NOT NULL,
+    aliases TEXT[] NOT NULL DEFAULT '{}',
+    -- The OSV `ranges` and `versions` of the affected package.
+    ranges JSONB NOT NULL DEFAULT '[]'::jsonb,
+    versions TEXT[] NOT NULL DEFAULT '{}',
+    modified TIMESTAMPTZ,
+    imported_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (ecosystem, package, id)
+);
+
+GRANT ALL ON TABLE osv_advisory TO windmill_admin;
+GRANT SELECT ON TABLE osv_advisory TO windmill_user;
+
+ALTER TABLE workspace_settings ADD COLUMN IF NOT EXISTS block_critical_vulnerabilities BOOLEAN NOT NULL DEFAULT false;
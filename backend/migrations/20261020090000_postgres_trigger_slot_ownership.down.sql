@@ -0,0 +1 @@
+ALTER TABLE postgres_trigger DROP COLUMN IF EXISTS replication_slot_owned;
@@ -0,0 +1 @@
+ALTER TABLE postgres_trigger ADD COLUMN IF NOT EXISTS replication_slot_owned BOOLEAN NOT NULL DEFAULT FALSE;
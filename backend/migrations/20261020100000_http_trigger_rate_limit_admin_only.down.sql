@@ -0,0 +1,2 @@
+GRANT ALL ON http_trigger_rate_limit_bucket TO windmill_user;
+GRANT ALL ON http_trigger_rate_limit_window TO windmill_user;
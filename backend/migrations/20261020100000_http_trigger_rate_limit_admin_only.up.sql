@@ -0,0 +1,5 @@
+-- The rate limit counters are only read and written by the server itself, never on behalf
+-- of a user, and have no row level security: users must not be able to reset their own
+-- budget or read those of other workspaces.
+REVOKE ALL ON http_trigger_rate_limit_bucket FROM windmill_user;
+REVOKE ALL ON http_trigger_rate_limit_window FROM windmill_user;
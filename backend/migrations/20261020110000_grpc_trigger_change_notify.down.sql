@@ -0,0 +1,2 @@
+DROP TRIGGER IF EXISTS grpc_trigger_change_trigger ON grpc_trigger;
+DROP FUNCTION IF EXISTS notify_grpc_trigger_change();
@@ -0,0 +1,13 @@
+-- Refresh the gRPC routes of every server as soon as a trigger changes.
+CREATE OR REPLACE FUNCTION notify_grpc_trigger_change()
+RETURNS TRIGGER AS $$
+BEGIN
+    INSERT INTO notify_event (channel, payload) VALUES ('notify_grpc_trigger_change', COALESCE(NEW.workspace_id, OLD.workspace_id) || ':' || COALESCE(NEW.path, OLD.path));
+    RETURN COALESCE(NEW, OLD);
+END;
+$$ LANGUAGE plpgsql SECURITY DEFINER;
+
+CREATE TRIGGER grpc_trigger_change_trigger
+AFTER INSERT OR UPDATE OR DELETE ON grpc_trigger
+FOR EACH ROW
+EXECUTE FUNCTION notify_grpc_trigger_change();
@@ -0,0 +1,339 @@
+use anyhow::{anyhow, bail};
+
+#[cfg(not(target_arch = "wasm32"))]
+use regex::Regex;
+#[cfg(target_arch = "wasm32")]
+use regex_lite::Regex;
+
+use serde_json::Value;
+use windmill_parser::{Arg, MainArgSignature, ObjectType, Typ};
+
+lazy_static::lazy_static! {
+    // Only a `main` declared at the top level of the file: the wrapper calls it
+    // as `net.script.main`, which a member function would not be.
+    static ref RE_MAIN: Regex =
+        Regex::new(r"(?m)^((?:[a-z]+[ \t]+)*)fun[ \t]+main[ \t]*\(").unwrap();
+}
+
+/// Parses the parameters of the top-level `main` function of a Kotlin script.
+///
+/// `otyp` keeps each parameter's type as written, since the executor hands it
+/// back to Jackson to deserialize the argument.
+pub fn parse_kotlin_signature(code: &str) -> anyhow::Result<MainArgSignature> {
+    let Some(captures) = RE_MAIN.captures(code) else {
+        return Ok(MainArgSignature {
+            star_args: false,
+            star_kwargs: false,
+            args: vec![],
+            auto_kind: Some("lib".to_string()),
+            has_preprocessor: None,
+            ..Default::default()
+        });
+    };
+    for modifier in captures[1].split_whitespace() {
+        match modifier {
+            "private" => {
+                bail!("main must not be private, the wrapper calling it lives in another file")
+            }
+            "suspend" => bail!("main must not be a suspend function"),
+            _ => {}
+        }
+    }
+
+    let params_start = captures.get(0).unwrap().end();
+    let params = &code[params_start..params_start + closing_paren(&code[params_start..])?];
+
+    let mut args = vec![];
+    for param in split_top_level(params, ',')
+        .into_iter()
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+    {
+        args.push(parse_param(param)?);
+    }
+
+    Ok(MainArgSignature {
+        star_args: false,
+        star_kwargs: false,
+        args,
+        auto_kind: None,
+        has_preprocessor: None,
+        ..Default::default()
+    })
+}
+
+fn parse_param(param: &str) -> anyhow::Result<Arg> {
+    let param = strip_annotations(param);
+    let (name, rest) = split_once_top_level(param, ':')
+        .ok_or_else(|| anyhow!("Missing type for parameter `{param}` of main"))?;
+    let mut name = name.trim();
+    if let Some(modifier) = ["vararg ", "crossinline ", "noinline "]
+        .iter()
+        .find(|m| name.starts_with(*m))
+    {
+        if *modifier == "vararg " {
+            bail!("vararg parameters are not supported in main, use a List instead");
+        }
+        name = name[modifier.len()..].trim();
+    }
+    let name = name.trim_matches('`').to_string();
+
+    let (otyp, default) = match split_once_top_level(rest, '=') {
+        Some((otyp, default)) => (otyp.trim(), Some(default.trim())),
+        None => (rest.trim(), None),
+    };
+    let otyp = otyp.split_whitespace().collect::<Vec<_>>().join(" ");
+    let nullable = otyp.ends_with('?');
+
+    let (has_default, default) = match default {
+        Some(expr) => (true, parse_literal(expr)),
+        None if nullable => (true, Some(Value::Null)),
+        None => (false, None),
+    };
+    Ok(Arg {
+        name,
+        typ: parse_type(&otyp),
+        otyp: Some(otyp),
+        default,
+        has_default,
+        oidx: None,
+        otyp_inferred: false,
+    })
+}
+
+fn strip_annotations(mut param: &str) -> &str {
+    while let Some(rest) = param.strip_prefix('@') {
+        let end = rest
+            .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.' || c == ':'))
+            .unwrap_or(rest.len());
+        param = &rest[end..];
+        if param.starts_with('(') {
+            param = match closing_paren(&param[1..]) {
+                Ok(close) => &param[close + 2..],
+                Err(_) => "",
+            };
+        }
+        param = param.trim_start();
+    }
+    param
+}
+
+fn parse_type(otyp: &str) -> Typ {
+    let t = otyp.strip_suffix('?').unwrap_or(otyp).trim();
+    let (base, generics) = match t.find('<') {
+        Some(i) if t.ends_with('>') => (&t[..i], Some(&t[i + 1..t.len() - 1])),
+        _ => (t, None),
+    };
+    let base = base.trim().rsplit('.').next().unwrap_or(base);
+    let first_generic = || {
+        generics
+            .and_then(|g| split_top_level(g, ',').into_iter().next())
+            .map(|g| {
+                g.trim()
+                    .trim_start_matches("out ")
+                    .trim_start_matches("in ")
+            })
+            .map(parse_type)
+            .unwrap_or(Typ::Unknown)
+    };
+    match base {
+        "String" | "Char" | "CharSequence" => Typ::Str(None),
+        "Int" | "Long" | "Short" | "Byte" | "UInt" | "ULong" | "UShort" | "UByte" => Typ::Int,
+        "Double" | "Float" | "Number" => Typ::Float,
+        "Boolean" => Typ::Bool,
+        "ByteArray" => Typ::Bytes,
+        "IntArray" | "LongArray" | "ShortArray" => Typ::List(Box::new(Typ::Int)),
+        "DoubleArray" | "FloatArray" => Typ::List(Box::new(Typ::Float)),
+        "BooleanArray" => Typ::List(Box::new(Typ::Bool)),
+        "CharArray" => Typ::List(Box::new(Typ::Str(None))),
+        "List" | "MutableList" | "ArrayList" | "Array" | "Set" | "MutableSet" | "Collection"
+        | "Iterable" => Typ::List(Box::new(first_generic())),
+        "Map" | "MutableMap" | "HashMap" | "LinkedHashMap" => {
+            Typ::Object(ObjectType::new(None, None))
+        }
+        _ => Typ::Unknown,
+    }
+}
+
+/// The value of a default made of literals only. Anything else is evaluated by
+/// Kotlin, so the argument still has a default but its value is not known here.
+fn parse_literal(expr: &str) -> Option<Value> {
+    match expr {
+        "true" => return Some(Value::Bool(true)),
+        "false" => return Some(Value::Bool(false)),
+        "null" => return Some(Value::Null),
+        "emptyList()" | "listOf()" | "emptySet()" | "setOf()" => return Some(Value::Array(vec![])),
+        "emptyMap()" | "mapOf()" => return Some(Value::Object(Default::default())),
+        _ => {}
+    }
+    if expr.starts_with('"') {
+        // `$` starts a string template, and `"""` a raw string with other escapes
+        if expr.contains('$') || expr.starts_with("\"\"\"") {
+            return None;
+        }
+        return serde_json::from_str::<String>(expr).ok().map(Value::String);
+    }
+    for list in ["listOf(", "setOf(", "arrayOf("] {
+        if let Some(inner) = expr.strip_prefix(list).and_then(|e| e.strip_suffix(')')) {
+            return split_top_level(inner, ',')
+                .into_iter()
+                .map(|item| parse_literal(item.trim()))
+                .collect::<Option<Vec<_>>>()
+                .map(Value::Array);
+        }
+    }
+    let number = expr.replace('_', "");
+    if let Ok(i) = number.strip_suffix('L').unwrap_or(&number).parse::<i64>() {
+        return Some(Value::from(i));
+    }
+    number
+        .strip_suffix(['f', 'F'])
+        .unwrap_or(&number)
+        .parse::<f64>()
+        .ok()
+        .filter(|f| f.is_finite())
+        .map(Value::from)
+}
+
+/// Byte offset of the `)` closing the parenthesis opened right before `s`.
+fn closing_paren(s: &str) -> anyhow::Result<usize> {
+    let mut depth = 0usize;
+    let mut quote = None;
+    let mut chars = s.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match (c, quote) {
+            ('\\', Some(_)) => {
+                chars.next();
+            }
+            (c, Some(q)) if c == q => quote = None,
+            (_, Some(_)) => {}
+            ('"' | '\'', None) => quote = Some(c),
+            ('(', None) => depth += 1,
+            (')', None) if depth == 0 => return Ok(i),
+            (')', None) => depth -= 1,
+            _ => {}
+        }
+    }
+    Err(anyhow!("Unclosed parameter list for main"))
+}
+
+/// Splits on `sep` outside of brackets, generics and string literals.
+fn split_top_level(s: &str, sep: char) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut depth = 0i32;
+    let mut quote = None;
+    let mut start = 0;
+    let mut prev = ' ';
+    let mut chars = s.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match (c, quote) {
+            ('\\', Some(_)) => {
+                chars.next();
+            }
+            (c, Some(q)) if c == q => quote = None,
+            (_, Some(_)) => {}
+            ('"' | '\'', None) => quote = Some(c),
+            ('(' | '[' | '{' | '<', None) => depth += 1,
+            // `->` in a function type is not a closing generic
+            ('>', None) if prev == '-' => {}
+            (')' | ']' | '}' | '>', None) => depth -= 1,
+            (c, None) if c == sep && depth == 0 => {
+                parts.push(&s[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+        prev = c;
+    }
+    parts.push(&s[start..]);
+    parts
+}
+
+fn split_once_top_level(s: &str, sep: char) -> Option<(&str, &str)> {
+    let first = split_top_level(s, sep).into_iter().next()?;
+    (first.len() < s.len()).then(|| (first, &s[first.len() + sep.len_utf8()..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_kotlin_no_main() {
+        let sig = parse_kotlin_signature("fun helper(x: Int) = x + 1").unwrap();
+        assert_eq!(sig.auto_kind, Some("lib".to_string()));
+        assert!(sig.args.is_empty());
+    }
+
+    #[test]
+    fn test_parse_kotlin_types_and_defaults() {
+        let code = r#"
+//requirements:
+//com.squareup.okhttp3:okhttp:4.12.0
+
+import okhttp3.OkHttpClient
+
+class Helper {
+    fun main(ignored: String) {}
+}
+
+fun main(
+    name: String,
+    times: Int = 3,
+    ratio: Double = 0.5,
+    verbose: Boolean = false,
+    tags: List<String> = listOf("a", "b"),
+    opts: Map<String, Any?> = emptyMap(),
+    label: String? ,
+    greeting: String = "hello, world",
+    computed: Long = System.currentTimeMillis(),
+    callback: (Int) -> String = { it.toString() },
+): Any? {
+    return name
+}
+"#;
+        let sig = parse_kotlin_signature(code).unwrap();
+        let by_name = |n: &str| sig.args.iter().find(|a| a.name == n).unwrap();
+        assert_eq!(sig.args.len(), 10);
+        assert_eq!(by_name("name").typ, Typ::Str(None));
+        assert!(!by_name("name").has_default);
+        assert_eq!(by_name("times").typ, Typ::Int);
+        assert_eq!(by_name("times").default, Some(json!(3)));
+        assert_eq!(by_name("ratio").default, Some(json!(0.5)));
+        assert_eq!(by_name("verbose").default, Some(json!(false)));
+        assert_eq!(by_name("tags").typ, Typ::List(Box::new(Typ::Str(None))));
+        assert_eq!(by_name("tags").otyp.as_deref(), Some("List<String>"));
+        assert_eq!(by_name("tags").default, Some(json!(["a", "b"])));
+        assert_eq!(
+            by_name("opts").typ,
+            Typ::Object(ObjectType::new(None, None))
+        );
+        assert_eq!(by_name("opts").default, Some(json!({})));
+        assert_eq!(by_name("label").otyp.as_deref(), Some("String?"));
+        assert_eq!(by_name("label").default, Some(json!(null)));
+        assert_eq!(by_name("greeting").default, Some(json!("hello, world")));
+        assert!(by_name("computed").has_default);
+        assert_eq!(by_name("computed").default, None);
+        assert_eq!(by_name("callback").typ, Typ::Unknown);
+    }
+
+    #[test]
+    fn test_parse_kotlin_rejects_uncallable_main() {
+        assert!(parse_kotlin_signature("private fun main(x: Int) {}").is_err());
+        assert!(parse_kotlin_signature("suspend fun main(x: Int) {}").is_err());
+        assert!(parse_kotlin_signature("fun main(vararg xs: String) {}").is_err());
+    }
+
+    #[test]
+    fn test_parse_kotlin_annotations() {
+        let code =
+            "fun main(@Suppress(\"UNUSED\") x: kotlin.collections.List<Int>, `when`: Long) {}";
+        let sig = parse_kotlin_signature(code).unwrap();
+        assert_eq!(sig.args[0].name, "x");
+        assert_eq!(sig.args[0].typ, Typ::List(Box::new(Typ::Int)));
+        assert_eq!(sig.args[1].name, "when");
+        assert_eq!(sig.args[1].typ, Typ::Int);
+    }
+}
@@ -0,0 +1,305 @@
+use anyhow::anyhow;
+
+#[cfg(not(target_arch = "wasm32"))]
+use regex::Regex;
+#[cfg(target_arch = "wasm32")]
+use regex_lite::Regex;
+
+use serde_json::Value;
+use windmill_parser::{Arg, MainArgSignature, ObjectType, Typ};
+
+lazy_static::lazy_static! {
+    static ref RE_MAIN: Regex = Regex::new(
+        r"(?m)^[ \t]*(?:(?:local[ \t]+)?function[ \t]+main[ \t]*|(?:local[ \t]+)?main[ \t]*=[ \t]*function[ \t]*)\(([^)]*)\)"
+    ).unwrap();
+    static ref RE_PARAM_ANNOTATION: Regex =
+        Regex::new(r"^---[ \t]*@param[ \t]+([A-Za-z_][A-Za-z0-9_]*)(\??)[ \t]+(.+)$").unwrap();
+    static ref RE_DEFAULT: Regex =
+        Regex::new(r"^([A-Za-z_][A-Za-z0-9_]*)[ \t]*=[ \t]*([A-Za-z_][A-Za-z0-9_]*)[ \t]+or[ \t]+(.+?)[ \t]*;?$").unwrap();
+}
+
+/// Parses the arguments of the `main` function of a Lua script.
+///
+/// Lua is untyped, so types come from the LuaLS annotations right above `main`
+/// (`---@param name type`) and defaults from the `name = name or <literal>`
+/// statements that open its body.
+pub fn parse_lua_signature(code: &str) -> anyhow::Result<MainArgSignature> {
+    let Some(captures) = RE_MAIN.captures(code) else {
+        return Ok(MainArgSignature {
+            star_args: false,
+            star_kwargs: false,
+            args: vec![],
+            auto_kind: Some("lib".to_string()),
+            has_preprocessor: None,
+            ..Default::default()
+        });
+    };
+    let header = captures.get(0).unwrap();
+    let params = captures.get(1).unwrap().as_str();
+
+    let annotations = parse_annotations(&code[..header.start()]);
+    let defaults = parse_defaults(&code[header.end()..]);
+
+    let mut star_args = false;
+    let mut args = vec![];
+    for name in params.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        if name == "..." {
+            star_args = true;
+            continue;
+        }
+        if !is_identifier(name) {
+            return Err(anyhow!("Invalid parameter name in main: {name}"));
+        }
+        let annotation = annotations.iter().find(|(n, ..)| n == name);
+        let default = defaults
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.clone());
+        let (otyp, typ, optional) = match annotation {
+            Some((_, otyp, optional)) => (Some(otyp.clone()), parse_type(otyp), *optional),
+            None => (
+                None,
+                default
+                    .as_ref()
+                    .map(|v| windmill_parser::json_to_typ(v, false))
+                    .unwrap_or(Typ::Unknown),
+                false,
+            ),
+        };
+        args.push(Arg {
+            name: name.to_string(),
+            otyp,
+            typ,
+            has_default: default.is_some() || optional,
+            default,
+            oidx: None,
+            otyp_inferred: false,
+        });
+    }
+
+    Ok(MainArgSignature {
+        star_args,
+        star_kwargs: false,
+        args,
+        auto_kind: None,
+        has_preprocessor: None,
+        ..Default::default()
+    })
+}
+
+fn is_identifier(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// The `---@param` annotations of the comment block that ends right before `main`.
+fn parse_annotations(before_main: &str) -> Vec<(String, String, bool)> {
+    let mut annotations = vec![];
+    for line in before_main.lines().rev().map(str::trim) {
+        if line.is_empty() && annotations.is_empty() {
+            continue;
+        }
+        if !line.starts_with("--") {
+            break;
+        }
+        if let Some(c) = RE_PARAM_ANNOTATION.captures(line) {
+            let typ = split_type(&c[3]);
+            annotations.push((c[1].to_string(), typ.to_string(), !c[2].is_empty()));
+        }
+    }
+    annotations
+}
+
+/// Splits the type of an annotation from its description, which may follow it.
+fn split_type(s: &str) -> &str {
+    let mut depth = 0i32;
+    let mut quote = None;
+    for (i, c) in s.char_indices() {
+        match (c, quote) {
+            ('"' | '\'', None) => quote = Some(c),
+            (c, Some(q)) if c == q => quote = None,
+            (_, Some(_)) => {}
+            ('<' | '(' | '{' | '[', None) => depth += 1,
+            ('>' | ')' | '}' | ']', None) => depth -= 1,
+            (' ' | '\t', None) if depth == 0 => {
+                // `a | b` is still one type
+                let rest = s[i..].trim_start();
+                let prev = s[..i].trim_end();
+                if !rest.starts_with('|') && !prev.ends_with('|') {
+                    return prev;
+                }
+            }
+            _ => {}
+        }
+    }
+    s.trim()
+}
+
+fn parse_type(otyp: &str) -> Typ {
+    let t = otyp.trim();
+    let t = t.strip_suffix('?').unwrap_or(t).trim();
+    if let Some(inner) = t.strip_suffix("[]") {
+        return Typ::List(Box::new(parse_type(inner)));
+    }
+    let variants: Vec<&str> = t.split('|').map(str::trim).collect();
+    if variants.len() > 1 {
+        let literals: Option<Vec<String>> = variants
+            .iter()
+            .map(|v| {
+                v.strip_prefix('"')
+                    .and_then(|v| v.strip_suffix('"'))
+                    .or_else(|| v.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+                    .map(str::to_string)
+            })
+            .collect();
+        return match literals {
+            Some(literals) => Typ::Str(Some(literals)),
+            None => match variants.iter().filter(|v| **v != "nil").collect::<Vec<_>>()[..] {
+                [single] => parse_type(single),
+                _ => Typ::Unknown,
+            },
+        };
+    }
+    match t {
+        "string" => Typ::Str(None),
+        "integer" => Typ::Int,
+        "number" => Typ::Float,
+        "boolean" => Typ::Bool,
+        "table" => Typ::Object(ObjectType::new(None, None)),
+        t if t.starts_with("table<") => Typ::Object(ObjectType::new(None, None)),
+        _ => Typ::Unknown,
+    }
+}
+
+/// Defaults declared by the `x = x or <literal>` statements at the top of `main`.
+fn parse_defaults(body: &str) -> Vec<(String, Value)> {
+    let mut defaults = vec![];
+    for line in body.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with("--") {
+            continue;
+        }
+        let Some(c) = RE_DEFAULT.captures(line) else {
+            break;
+        };
+        if c[1] != c[2] {
+            break;
+        }
+        match parse_literal(&c[3]) {
+            Some(value) => defaults.push((c[1].to_string(), value)),
+            None => break,
+        }
+    }
+    defaults
+}
+
+fn parse_literal(s: &str) -> Option<Value> {
+    match s {
+        "true" => return Some(Value::Bool(true)),
+        "false" => return Some(Value::Bool(false)),
+        "nil" => return Some(Value::Null),
+        _ => {}
+    }
+    if let Some(inner) = s.strip_prefix('\'').and_then(|s| s.strip_suffix('\'')) {
+        return (!inner.contains(['\\', '\''])).then(|| Value::String(inner.to_string()));
+    }
+    if s.starts_with('"') {
+        return serde_json::from_str::<String>(s).ok().map(Value::String);
+    }
+    if let Ok(i) = s.parse::<i64>() {
+        return Some(Value::from(i));
+    }
+    s.parse::<f64>()
+        .ok()
+        .filter(|f| f.is_finite())
+        .map(Value::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_lua_no_main() {
+        let sig = parse_lua_signature("local function helper(x) return x end").unwrap();
+        assert_eq!(sig.auto_kind, Some("lib".to_string()));
+        assert!(sig.args.is_empty());
+    }
+
+    #[test]
+    fn test_parse_lua_untyped_args() {
+        let code = r#"
+function main(a, b, ...)
+  return a + b
+end
+"#;
+        let sig = parse_lua_signature(code).unwrap();
+        assert!(sig.star_args);
+        assert_eq!(
+            sig.args,
+            vec![
+                Arg { name: "a".into(), ..Default::default() },
+                Arg { name: "b".into(), ..Default::default() },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_lua_annotations_and_defaults() {
+        let code = r#"
+local wmill = require("wmill")
+
+--- Greets someone
+---@param name string the name to greet
+---@param times integer
+---@param ratio? number
+---@param tags string[]
+---@param mode "fast" | "slow"
+---@param opts table<string, any>
+local function main(name, times, ratio, tags, mode, opts, verbose)
+  -- defaults
+  times = times or 3
+  verbose = verbose or false
+  name = name or "world"
+  return name
+end
+
+return main
+"#;
+        let sig = parse_lua_signature(code).unwrap();
+        let by_name = |n: &str| sig.args.iter().find(|a| a.name == n).unwrap();
+        assert_eq!(sig.args.len(), 7);
+        assert_eq!(by_name("name").typ, Typ::Str(None));
+        assert_eq!(by_name("name").otyp.as_deref(), Some("string"));
+        assert_eq!(by_name("name").default, Some(json!("world")));
+        assert_eq!(by_name("times").typ, Typ::Int);
+        assert_eq!(by_name("times").default, Some(json!(3)));
+        assert_eq!(by_name("ratio").typ, Typ::Float);
+        assert!(by_name("ratio").has_default);
+        assert_eq!(by_name("ratio").default, None);
+        assert_eq!(by_name("tags").typ, Typ::List(Box::new(Typ::Str(None))));
+        assert_eq!(
+            by_name("mode").typ,
+            Typ::Str(Some(vec!["fast".to_string(), "slow".to_string()]))
+        );
+        assert_eq!(
+            by_name("opts").typ,
+            Typ::Object(ObjectType::new(None, None))
+        );
+        assert_eq!(by_name("verbose").typ, Typ::Bool);
+        assert_eq!(by_name("verbose").default, Some(json!(false)));
+    }
+
+    #[test]
+    fn test_parse_lua_main_assignment() {
+        let code = "main = function(x, y)\n  y = y or 2.5\n  return x * y\nend";
+        let sig = parse_lua_signature(code).unwrap();
+        assert_eq!(sig.args.len(), 2);
+        assert_eq!(sig.args[1].default, Some(json!(2.5)));
+        assert_eq!(sig.args[1].typ, Typ::Float);
+    }
+}
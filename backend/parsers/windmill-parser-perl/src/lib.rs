@@ -0,0 +1,433 @@
+use anyhow::anyhow;
+
+#[cfg(not(target_arch = "wasm32"))]
+use regex::Regex;
+#[cfg(target_arch = "wasm32")]
+use regex_lite::Regex;
+
+use serde_json::Value;
+use windmill_parser::{Arg, MainArgSignature, ObjectType, Typ};
+
+lazy_static::lazy_static! {
+    static ref RE_MAIN: Regex = Regex::new(r"(?m)^[ \t]*sub[ \t]+main\b[^{;]*\{").unwrap();
+    static ref RE_MY: Regex = Regex::new(r"\Amy[ \t]*\(").unwrap();
+    static ref RE_FROM_ARGS: Regex = Regex::new(r"\A\)[ \t]*=[ \t]*@_[ \t]*;").unwrap();
+    static ref RE_VAR: Regex = Regex::new(r"\A([$@%])([A-Za-z_][A-Za-z0-9_]*)").unwrap();
+    static ref RE_REQUIRES: Regex = Regex::new(
+        r#"(?m)^[ \t]*#[ \t]*requires[ \t]+(?:'([^']*)'|"([^"]*)")[ \t]*(?:(?:,|=>)[ \t]*(?:'([^']*)'|"([^"]*)"))?[ \t]*;?[ \t]*$"#
+    ).unwrap();
+    static ref RE_MODULE: Regex = Regex::new(r"\A[A-Za-z_][A-Za-z0-9_]*(?:::[A-Za-z0-9_]+)*\z").unwrap();
+    static ref RE_VERSION: Regex = Regex::new(r"\A[0-9A-Za-z_.<>=!, ]+\z").unwrap();
+}
+
+/// How a parameter of `main` receives its argument: `$x` takes one value, while
+/// a trailing `@x` or `%x` takes the rest of `@_` as a list or as a hash.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sigil {
+    Scalar,
+    Array,
+    Hash,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MainParam {
+    pub name: String,
+    pub sigil: Sigil,
+    /// The comment annotating the parameter, if any: `Type`, `Type = default`
+    /// or `= default`.
+    pub annotation: Option<String>,
+}
+
+/// Parses the arguments of `sub main` of a Perl script.
+///
+/// Arguments are the variables `main` unpacks with the `my (...) = @_;` that
+/// opens its body. Their types and defaults come from the comment that follows
+/// each of them (`$count, # Int = 3`), or, when the list is on one line, from the
+/// comma separated annotations of the comment that ends it.
+pub fn parse_perl_signature(code: &str) -> anyhow::Result<MainArgSignature> {
+    let Some(params) = parse_perl_main_params(code)? else {
+        return Ok(MainArgSignature {
+            star_args: false,
+            star_kwargs: false,
+            args: vec![],
+            auto_kind: Some("lib".to_string()),
+            has_preprocessor: None,
+            ..Default::default()
+        });
+    };
+
+    let mut args = vec![];
+    for MainParam { name, sigil, annotation } in params {
+        let (otyp, default) = match annotation.as_deref().map(split_annotation) {
+            Some((otyp, default)) => (otyp, default),
+            None => (None, None),
+        };
+        let (typ, nullable) = match otyp.as_deref() {
+            Some(otyp) => parse_type(otyp),
+            None => (
+                default
+                    .as_ref()
+                    .and_then(|d| d.as_ref())
+                    .map(|v| windmill_parser::json_to_typ(v, false))
+                    .unwrap_or(Typ::Unknown),
+                false,
+            ),
+        };
+        let typ = match (sigil, typ) {
+            (Sigil::Scalar, typ) => typ,
+            (Sigil::Array, typ @ Typ::List(_)) => typ,
+            (Sigil::Array, typ) => Typ::List(Box::new(typ)),
+            (Sigil::Hash, _) => Typ::Object(ObjectType::new(None, None)),
+        };
+        args.push(Arg {
+            name,
+            otyp,
+            typ,
+            has_default: default.is_some() || nullable || sigil != Sigil::Scalar,
+            default: default.flatten(),
+            oidx: None,
+            otyp_inferred: false,
+        });
+    }
+
+    Ok(MainArgSignature {
+        star_args: false,
+        star_kwargs: false,
+        args,
+        auto_kind: None,
+        has_preprocessor: None,
+        ..Default::default()
+    })
+}
+
+/// The parameters `main` unpacks from `@_`, in order, or `None` when the script
+/// has no `sub main`.
+pub fn parse_perl_main_params(code: &str) -> anyhow::Result<Option<Vec<MainParam>>> {
+    let Some(header) = RE_MAIN.find(code) else {
+        return Ok(None);
+    };
+    let body = skip_blank_and_comments(&code[header.end()..]);
+    let Some(my) = RE_MY.find(body) else {
+        return Ok(Some(vec![]));
+    };
+
+    let mut rest = &body[my.end()..];
+    let mut params: Vec<MainParam> = vec![];
+    let mut multiline = false;
+    // Whether a variable was declared on the current line of the list.
+    let mut annotatable = false;
+    loop {
+        let trimmed = rest.trim_start_matches([' ', '\t', ',']);
+        if let Some(after_newline) = trimmed.strip_prefix('\n') {
+            multiline = true;
+            annotatable = false;
+            rest = after_newline;
+            continue;
+        }
+        if let Some(comment) = trimmed.strip_prefix('#') {
+            let (comment, after) = comment.split_once('\n').unwrap_or((comment, ""));
+            // A comment annotates the variable it follows on the same line.
+            if let Some(last) = params.last_mut().filter(|_| annotatable) {
+                let comment = comment.trim();
+                if !comment.is_empty() {
+                    last.annotation = Some(comment.to_string());
+                }
+            }
+            multiline = true;
+            annotatable = false;
+            rest = after;
+            continue;
+        }
+        if trimmed.starts_with(')') {
+            rest = trimmed;
+            break;
+        }
+        if trimmed.starts_with("undef") {
+            return Err(anyhow!(
+                "`undef` placeholders are not supported in the arguments of main"
+            ));
+        }
+        let Some(c) = RE_VAR.captures(trimmed) else {
+            return Err(anyhow!(
+                "Expected a variable in the `my (...) = @_;` of main, found: {}",
+                trimmed.lines().next().unwrap_or_default()
+            ));
+        };
+        if params.last().is_some_and(|p| p.sigil != Sigil::Scalar) {
+            return Err(anyhow!(
+                "Only the last argument of main can be an array or a hash"
+            ));
+        }
+        let sigil = match &c[1] {
+            "@" => Sigil::Array,
+            "%" => Sigil::Hash,
+            _ => Sigil::Scalar,
+        };
+        let name = c[2].to_string();
+        if params.iter().any(|p| p.name == name) {
+            return Err(anyhow!("Argument {name} of main is declared twice"));
+        }
+        params.push(MainParam { name, sigil, annotation: None });
+        annotatable = true;
+        rest = &trimmed[c.get(0).unwrap().end()..];
+    }
+
+    let Some(from_args) = RE_FROM_ARGS.find(rest) else {
+        // `my (...)` that does not unpack `@_` declares locals, not arguments.
+        return Ok(Some(vec![]));
+    };
+    if !multiline {
+        let trailing = rest[from_args.end()..].lines().next().unwrap_or_default();
+        if let Some(comment) = trailing.trim().strip_prefix('#') {
+            let annotations = split_top_level(comment);
+            if annotations.len() == params.len() {
+                for (p, a) in params.iter_mut().zip(annotations) {
+                    p.annotation = Some(a.to_string()).filter(|a| !a.is_empty());
+                }
+            }
+        }
+    }
+    Ok(Some(params))
+}
+
+/// The cpanfile made of the `# requires 'Module', 'version';` comments of a
+/// script, one normalized line per module, sorted.
+pub fn parse_perl_requirements(code: &str) -> anyhow::Result<String> {
+    let mut requirements = vec![];
+    for c in RE_REQUIRES.captures_iter(code) {
+        let module = c.get(1).or(c.get(2)).unwrap().as_str().trim();
+        if !RE_MODULE.is_match(module) {
+            return Err(anyhow!("Invalid module name in requirements: {module}"));
+        }
+        let line = match c.get(3).or(c.get(4)).map(|v| v.as_str().trim()) {
+            Some(version) if !version.is_empty() => {
+                if !RE_VERSION.is_match(version) {
+                    return Err(anyhow!(
+                        "Invalid version requirement for {module}: {version}"
+                    ));
+                }
+                format!("requires '{module}', '{version}';")
+            }
+            _ => format!("requires '{module}';"),
+        };
+        requirements.push(line);
+    }
+    requirements.sort();
+    requirements.dedup();
+    Ok(requirements.join("\n"))
+}
+
+fn skip_blank_and_comments(mut s: &str) -> &str {
+    loop {
+        s = s.trim_start();
+        match s.strip_prefix('#') {
+            Some(comment) => s = comment.split_once('\n').map(|(_, r)| r).unwrap_or(""),
+            None => return s,
+        }
+    }
+}
+
+/// Splits an annotation into its type and its default. The outer `Option` of
+/// the default is whether there is one, the inner one whether it is a literal
+/// the frontend can show.
+fn split_annotation(annotation: &str) -> (Option<String>, Option<Option<Value>>) {
+    let (otyp, default) = match annotation.split_once('=') {
+        Some((otyp, default)) => (otyp, Some(parse_literal(default.trim()))),
+        None => (annotation, None),
+    };
+    let otyp = otyp.trim();
+    ((!otyp.is_empty()).then(|| otyp.to_string()), default)
+}
+
+/// The type of an annotation, and whether it is nullable (`Maybe[...]`).
+fn parse_type(otyp: &str) -> (Typ, bool) {
+    let t = otyp.trim();
+    if let Some(inner) = generic(t, "Maybe") {
+        return (parse_type(inner).0, true);
+    }
+    if let Some(inner) = generic(t, "Enum") {
+        let variants = split_top_level(inner)
+            .into_iter()
+            .map(|v| unquote(v).unwrap_or(v).to_string())
+            .collect();
+        return (Typ::Str(Some(variants)), false);
+    }
+    for list in ["ArrayRef", "List", "Array"] {
+        if let Some(inner) = generic(t, list) {
+            return (Typ::List(Box::new(parse_type(inner).0)), false);
+        }
+    }
+    for object in ["HashRef", "Hash", "Object"] {
+        if generic(t, object).is_some() {
+            return (Typ::Object(ObjectType::new(None, None)), false);
+        }
+    }
+    let typ = match t.to_lowercase().as_str() {
+        "str" | "string" => Typ::Str(None),
+        "int" | "integer" => Typ::Int,
+        "num" | "number" | "float" => Typ::Float,
+        "bool" | "boolean" => Typ::Bool,
+        "bytes" => Typ::Bytes,
+        "arrayref" | "list" | "array" => Typ::List(Box::new(Typ::Unknown)),
+        "hashref" | "hash" | "object" => Typ::Object(ObjectType::new(None, None)),
+        _ => Typ::Unknown,
+    };
+    (typ, false)
+}
+
+/// The parameter of `Name[...]`.
+fn generic<'a>(t: &'a str, name: &str) -> Option<&'a str> {
+    t.strip_prefix(name)?
+        .trim_start()
+        .strip_prefix('[')?
+        .strip_suffix(']')
+}
+
+fn unquote(s: &str) -> Option<&str> {
+    s.strip_prefix('\'')
+        .and_then(|s| s.strip_suffix('\''))
+        .or_else(|| s.strip_prefix('"').and_then(|s| s.strip_suffix('"')))
+}
+
+fn split_top_level(s: &str) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut depth = 0i32;
+    let mut quote = None;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        match (c, quote) {
+            (c, Some(q)) if c == q => quote = None,
+            (_, Some(_)) => {}
+            ('"' | '\'', None) => quote = Some(c),
+            ('[' | '{' | '(', None) => depth += 1,
+            (']' | '}' | ')', None) => depth -= 1,
+            (',', None) if depth == 0 => {
+                parts.push(s[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(s[start..].trim());
+    parts
+}
+
+fn parse_literal(s: &str) -> Option<Value> {
+    match s {
+        "undef" => return Some(Value::Null),
+        "true" => return Some(Value::Bool(true)),
+        "false" => return Some(Value::Bool(false)),
+        _ => {}
+    }
+    if let Some(inner) = s.strip_prefix('\'').and_then(|s| s.strip_suffix('\'')) {
+        return (!inner.contains(['\\', '\''])).then(|| Value::String(inner.to_string()));
+    }
+    serde_json::from_str::<Value>(s).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_perl_no_main() {
+        let sig = parse_perl_signature("sub helper { return 1; }\n1;").unwrap();
+        assert_eq!(sig.auto_kind, Some("lib".to_string()));
+        assert!(sig.args.is_empty());
+    }
+
+    #[test]
+    fn test_parse_perl_annotated_args() {
+        let code = r#"
+use strict;
+use warnings;
+
+sub main {
+    # unpack the arguments
+    my (
+        $name,     # Str
+        $times,    # Int = 3
+        $ratio,    # Maybe[Num]
+        $mode,     # Enum['fast', 'slow'] = 'fast'
+        $opts,     # HashRef
+        $verbose,  # = false
+        @tags,     # Str
+    ) = @_;
+    return "$name";
+}
+"#;
+        let sig = parse_perl_signature(code).unwrap();
+        let by_name = |n: &str| sig.args.iter().find(|a| a.name == n).unwrap();
+        assert_eq!(sig.args.len(), 7);
+        assert_eq!(by_name("name").typ, Typ::Str(None));
+        assert_eq!(by_name("name").otyp.as_deref(), Some("Str"));
+        assert!(!by_name("name").has_default);
+        assert_eq!(by_name("times").typ, Typ::Int);
+        assert_eq!(by_name("times").default, Some(json!(3)));
+        assert_eq!(by_name("ratio").typ, Typ::Float);
+        assert!(by_name("ratio").has_default);
+        assert_eq!(by_name("ratio").default, None);
+        assert_eq!(
+            by_name("mode").typ,
+            Typ::Str(Some(vec!["fast".to_string(), "slow".to_string()]))
+        );
+        assert_eq!(by_name("mode").default, Some(json!("fast")));
+        assert_eq!(
+            by_name("opts").typ,
+            Typ::Object(ObjectType::new(None, None))
+        );
+        assert_eq!(by_name("verbose").typ, Typ::Bool);
+        assert_eq!(by_name("verbose").otyp, None);
+        assert_eq!(by_name("tags").typ, Typ::List(Box::new(Typ::Str(None))));
+        assert!(by_name("tags").has_default);
+    }
+
+    #[test]
+    fn test_parse_perl_single_line_args() {
+        let code = "sub main {\n  my ($a, $b, %rest) = @_; # Int, ArrayRef[Str] = [\"x\", \"y\"], HashRef\n  $a\n}";
+        let sig = parse_perl_signature(code).unwrap();
+        assert_eq!(sig.args.len(), 3);
+        assert_eq!(sig.args[0].typ, Typ::Int);
+        assert_eq!(sig.args[1].typ, Typ::List(Box::new(Typ::Str(None))));
+        assert_eq!(sig.args[1].default, Some(json!(["x", "y"])));
+        assert_eq!(sig.args[2].typ, Typ::Object(ObjectType::new(None, None)));
+
+        let params = parse_perl_main_params(code).unwrap().unwrap();
+        assert_eq!(
+            params.iter().map(|p| p.sigil).collect::<Vec<_>>(),
+            vec![Sigil::Scalar, Sigil::Scalar, Sigil::Hash]
+        );
+
+        let unannotated = "sub main { my ($x, $y) = @_; return $x + $y; }";
+        let sig = parse_perl_signature(unannotated).unwrap();
+        assert_eq!(
+            sig.args,
+            vec![
+                Arg { name: "x".into(), ..Default::default() },
+                Arg { name: "y".into(), ..Default::default() },
+            ]
+        );
+
+        assert!(parse_perl_signature("sub main { my (@a, $b) = @_; }").is_err());
+        assert!(parse_perl_signature("sub main { my (undef, $b) = @_; }").is_err());
+    }
+
+    #[test]
+    fn test_parse_perl_requirements() {
+        let code = r#"
+# requires 'Try::Tiny';
+# requires "JSON::XS", ">= 4.0, < 5.0";
+#requires 'Try::Tiny'
+# this script requires nothing else
+use Try::Tiny;
+"#;
+        assert_eq!(
+            parse_perl_requirements(code).unwrap(),
+            "requires 'JSON::XS', '>= 4.0, < 5.0';\nrequires 'Try::Tiny';"
+        );
+        assert!(parse_perl_requirements("# requires 'Foo; system(1)';").is_err());
+        assert!(parse_perl_requirements("# requires 'Foo', '1\\';").is_err());
+    }
+}
@@ -212,6 +212,30 @@ pub fn parse_r(code: &str) -> String {
     wrap_sig(windmill_parser_r::parse_r_signature(code))
 }
 
+#[cfg(feature = "lua-parser")]
+#[wasm_bindgen]
+pub fn parse_lua(code: &str) -> String {
+    wrap_sig(windmill_parser_lua::parse_lua_signature(code))
+}
+
+#[cfg(feature = "kotlin-parser")]
+#[wasm_bindgen]
+pub fn parse_kotlin(code: &str) -> String {
+    wrap_sig(windmill_parser_kotlin::parse_kotlin_signature(code))
+}
+
+#[cfg(feature = "perl-parser")]
+#[wasm_bindgen]
+pub fn parse_perl(code: &str) -> String {
+    wrap_sig(windmill_parser_perl::parse_perl_signature(code))
+}
+
+#[cfg(feature = "wit-parser")]
+#[wasm_bindgen]
+pub fn parse_wit(code: &str) -> String {
+    wrap_sig(windmill_parser_wit::parse_wit_signature(code))
+}
+
 #[cfg(feature = "asset-parser")]
 #[wasm_bindgen]
 pub fn parse_assets_sql(code: &str) -> String {
@@ -0,0 +1,254 @@
+use anyhow::{anyhow, bail};
+
+#[cfg(not(target_arch = "wasm32"))]
+use regex::Regex;
+#[cfg(target_arch = "wasm32")]
+use regex_lite::Regex;
+
+use windmill_parser::{Arg, MainArgSignature, ObjectProperty, ObjectType, Typ};
+use wit_parser::{Resolve, Type, TypeDefKind, WorldItem, WorldKey};
+
+lazy_static::lazy_static! {
+    static ref RE_COMPONENT: Regex =
+        Regex::new(r"(?m)^[ \t]*//[ \t]*component:[ \t]*(\S+)[ \t]*$").unwrap();
+    static ref RE_STORAGE: Regex =
+        Regex::new(r"(?m)^[ \t]*//[ \t]*storage:[ \t]*(\S+)[ \t]*$").unwrap();
+}
+
+/// Where the compiled component of a WIT script lives in the object store.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComponentLocation {
+    pub path: String,
+    /// A secondary storage, or the workspace's default one.
+    pub storage: Option<String>,
+}
+
+/// Reads the `// component: <path>` line, and the optional
+/// `// storage: <name>` one, of a WIT script.
+pub fn parse_wit_component(code: &str) -> Option<ComponentLocation> {
+    let path = RE_COMPONENT.captures(code)?[1].to_string();
+    let storage = RE_STORAGE.captures(code).map(|c| c[1].to_string());
+    Some(ComponentLocation { path, storage })
+}
+
+/// Parses the arguments of the `main` function exported by the world of a WIT
+/// script.
+///
+/// The document must hold a single world. Parameters of type `option<T>` may
+/// be left out, and `list<u8>` is shown as bytes.
+pub fn parse_wit_signature(code: &str) -> anyhow::Result<MainArgSignature> {
+    let mut resolve = Resolve::new();
+    let package = resolve
+        .push_str("script.wit", code)
+        .map_err(|e| anyhow!("{e:#}"))?;
+    let world = resolve.select_world(package, None)?;
+    let main = resolve.worlds[world]
+        .exports
+        .iter()
+        .find_map(|(key, item)| match (key, item) {
+            (WorldKey::Name(name), WorldItem::Function(f)) if name == "main" => Some(f),
+            _ => None,
+        });
+    let Some(main) = main else {
+        return Ok(MainArgSignature {
+            star_args: false,
+            star_kwargs: false,
+            args: vec![],
+            auto_kind: Some("lib".to_string()),
+            has_preprocessor: None,
+            ..Default::default()
+        });
+    };
+
+    let args = main
+        .params
+        .iter()
+        .map(|(name, ty)| {
+            let optional = option_of(&resolve, ty);
+            let typ = to_typ(&resolve, optional.unwrap_or(ty))
+                .map_err(|e| anyhow!("Unsupported type for argument {name}: {e}"))?;
+            Ok(Arg {
+                name: name.clone(),
+                otyp: None,
+                typ,
+                default: None,
+                has_default: optional.is_some(),
+                oidx: None,
+                otyp_inferred: false,
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    Ok(MainArgSignature {
+        star_args: false,
+        star_kwargs: false,
+        args,
+        auto_kind: None,
+        has_preprocessor: None,
+        ..Default::default()
+    })
+}
+
+/// `T` if `ty` is an `option<T>`, looking through type aliases.
+fn option_of<'a>(resolve: &'a Resolve, ty: &Type) -> Option<&'a Type> {
+    match ty {
+        Type::Id(id) => match &resolve.types[*id].kind {
+            TypeDefKind::Option(inner) => Some(inner),
+            TypeDefKind::Type(alias) => option_of(resolve, alias),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn to_typ(resolve: &Resolve, ty: &Type) -> anyhow::Result<Typ> {
+    let id = match ty {
+        Type::Bool => return Ok(Typ::Bool),
+        Type::U8 | Type::U16 | Type::U32 | Type::U64 => return Ok(Typ::Int),
+        Type::S8 | Type::S16 | Type::S32 | Type::S64 => return Ok(Typ::Int),
+        Type::F32 | Type::F64 => return Ok(Typ::Float),
+        Type::Char | Type::String => return Ok(Typ::Str(None)),
+        Type::Id(id) => *id,
+    };
+    let typedef = &resolve.types[id];
+    Ok(match &typedef.kind {
+        TypeDefKind::Type(alias) => to_typ(resolve, alias)?,
+        TypeDefKind::List(Type::U8) => Typ::Bytes,
+        TypeDefKind::List(elem) => Typ::List(Box::new(to_typ(resolve, elem)?)),
+        TypeDefKind::Option(inner) => to_typ(resolve, inner)?,
+        TypeDefKind::Record(record) => Typ::Object(ObjectType::new(
+            typedef.name.clone(),
+            Some(
+                record
+                    .fields
+                    .iter()
+                    .map(|field| {
+                        let ty = option_of(resolve, &field.ty).unwrap_or(&field.ty);
+                        Ok(ObjectProperty::new(
+                            field.name.clone(),
+                            Box::new(to_typ(resolve, ty)?),
+                        ))
+                    })
+                    .collect::<anyhow::Result<_>>()?,
+            ),
+        )),
+        TypeDefKind::Enum(enum_) => Typ::Str(Some(
+            enum_.cases.iter().map(|case| case.name.clone()).collect(),
+        )),
+        TypeDefKind::Flags(flags) => Typ::List(Box::new(Typ::Str(Some(
+            flags.flags.iter().map(|flag| flag.name.clone()).collect(),
+        )))),
+        TypeDefKind::Tuple(_) | TypeDefKind::Variant(_) | TypeDefKind::Result(_) => Typ::Unknown,
+        TypeDefKind::Resource | TypeDefKind::Handle(_) => {
+            bail!("resources cannot be passed to main")
+        }
+        kind => bail!("{} is not supported", kind.as_str()),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_wit_component() {
+        let code = "// component: f/wasm/greeter.wasm\n// storage: archive\npackage a:b;\n";
+        assert_eq!(
+            parse_wit_component(code),
+            Some(ComponentLocation {
+                path: "f/wasm/greeter.wasm".to_string(),
+                storage: Some("archive".to_string()),
+            })
+        );
+        assert_eq!(parse_wit_component("package a:b;\n"), None);
+    }
+
+    #[test]
+    fn test_parse_wit_no_main() {
+        let code = "package windmill:script;\nworld script {\n  export helper: func();\n}\n";
+        let sig = parse_wit_signature(code).unwrap();
+        assert_eq!(sig.auto_kind, Some("lib".to_string()));
+        assert!(sig.args.is_empty());
+    }
+
+    #[test]
+    fn test_parse_wit_signature() {
+        let code = r#"
+// component: f/wasm/greeter.wasm
+package windmill:script;
+
+world script {
+  enum mood { happy, grumpy }
+  flags style { bold, italic }
+  type name = string;
+  record options {
+    times: u32,
+    ratio: option<f64>,
+  }
+
+  export main: func(
+    who: name,
+    mood: mood,
+    style: style,
+    options: options,
+    tags: list<string>,
+    avatar: list<u8>,
+    nickname: option<string>,
+  ) -> result<string, string>;
+}
+"#;
+        let sig = parse_wit_signature(code).unwrap();
+        let arg = |name: &str, typ: Typ, has_default: bool| Arg {
+            name: name.to_string(),
+            typ,
+            has_default,
+            ..Default::default()
+        };
+        assert_eq!(
+            sig.args,
+            vec![
+                arg("who", Typ::Str(None), false),
+                arg(
+                    "mood",
+                    Typ::Str(Some(vec!["happy".to_string(), "grumpy".to_string()])),
+                    false
+                ),
+                arg(
+                    "style",
+                    Typ::List(Box::new(Typ::Str(Some(vec![
+                        "bold".to_string(),
+                        "italic".to_string()
+                    ])))),
+                    false
+                ),
+                arg(
+                    "options",
+                    Typ::Object(ObjectType::new(
+                        Some("options".to_string()),
+                        Some(vec![
+                            ObjectProperty::new("times".to_string(), Box::new(Typ::Int)),
+                            ObjectProperty::new("ratio".to_string(), Box::new(Typ::Float)),
+                        ])
+                    )),
+                    false
+                ),
+                arg("tags", Typ::List(Box::new(Typ::Str(None))), false),
+                arg("avatar", Typ::Bytes, false),
+                arg("nickname", Typ::Str(None), true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_wit_errors() {
+        assert!(parse_wit_signature("package a:b;\nworld w { export main: func(").is_err());
+        let resource = r#"
+package a:b;
+world w {
+  resource conn;
+  export main: func(c: conn);
+}
+"#;
+        assert!(parse_wit_signature(resource).is_err());
+    }
+}
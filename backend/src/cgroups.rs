@@ -1,5 +1,7 @@
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+
+use windmill_common::worker::WORKER_CGROUP_LEAF;
 
 #[derive(Debug)]
 pub enum CgroupError {
@@ -112,3 +114,99 @@ pub fn disable_oom_group() -> Result<(), CgroupError> {
         }
     }
 }
+
+const JOB_CONTROLLERS: [&str; 3] = ["memory", "cpu", "pids"];
+const ESRCH: i32 = 3;
+
+fn write_control(path: &Path, value: &str) -> Result<(), CgroupError> {
+    fs::write(path, value).map_err(|e| match e.kind() {
+        std::io::ErrorKind::PermissionDenied => CgroupError::PermissionDenied,
+        _ => CgroupError::Io(e),
+    })
+}
+
+/// Prepares the worker's cgroup so each job can be given a child cgroup with its own limits.
+///
+/// cgroup v2 only lets a cgroup without processes enable controllers for its children, so the
+/// processes of the worker's cgroup are first moved into a `windmill-worker` leaf. Jobs are then
+/// created under the returned `jobs` cgroup, a sibling of that leaf.
+pub fn delegate_to_jobs() -> Result<PathBuf, CgroupError> {
+    let cgroup_info = fs::read_to_string("/proc/self/cgroup")?;
+    let cgroup_rel = cgroup_info
+        .lines()
+        .find_map(|line| line.strip_prefix("0::"))
+        .ok_or(CgroupError::NotSupported)?
+        .trim();
+    let cgroup_rel = cgroup_rel
+        .strip_suffix(&format!("/{WORKER_CGROUP_LEAF}"))
+        .unwrap_or(cgroup_rel);
+
+    let cgroup_path = PathBuf::from(format!("/sys/fs/cgroup{}", cgroup_rel));
+    if !cgroup_path.is_dir() {
+        return Err(CgroupError::PathNotFound(cgroup_path));
+    }
+    // Only the host's root cgroup lacks memory.max (a container's namespaced root has one):
+    // never reshuffle the processes of the whole host.
+    if !cgroup_path.join("memory.max").exists() {
+        return Err(CgroupError::NotSupported);
+    }
+
+    let available = fs::read_to_string(cgroup_path.join("cgroup.controllers"))?;
+    if !JOB_CONTROLLERS
+        .iter()
+        .all(|c| available.split_whitespace().any(|a| a == *c))
+    {
+        tracing::warn!(
+            "cgroup controllers at {:?} are '{}', memory, cpu and pids are all required \
+            for per-job limits",
+            cgroup_path,
+            available.trim()
+        );
+        return Err(CgroupError::NotSupported);
+    }
+
+    let enable = JOB_CONTROLLERS
+        .iter()
+        .map(|c| format!("+{c}"))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let enabled = fs::read_to_string(cgroup_path.join("cgroup.subtree_control"))?;
+    if !JOB_CONTROLLERS
+        .iter()
+        .all(|c| enabled.split_whitespace().any(|e| e == *c))
+    {
+        let leaf = cgroup_path.join(WORKER_CGROUP_LEAF);
+        if !leaf.is_dir() {
+            fs::create_dir(&leaf).map_err(|e| match e.kind() {
+                std::io::ErrorKind::PermissionDenied => CgroupError::PermissionDenied,
+                _ => CgroupError::Io(e),
+            })?;
+        }
+        for pid in fs::read_to_string(cgroup_path.join("cgroup.procs"))?.lines() {
+            // A process may exit between the read and the move
+            if let Err(e) = fs::write(leaf.join("cgroup.procs"), pid) {
+                if e.raw_os_error() != Some(ESRCH) {
+                    return Err(e.into());
+                }
+            }
+        }
+        write_control(&cgroup_path.join("cgroup.subtree_control"), &enable)?;
+    }
+
+    let jobs = cgroup_path.join("jobs");
+    if !jobs.is_dir() {
+        fs::create_dir(&jobs)?;
+    }
+    write_control(&jobs.join("cgroup.subtree_control"), &enable)?;
+
+    // Cgroups left behind by a previous run of the worker
+    for entry in fs::read_dir(&jobs)?.flatten() {
+        if entry.path().is_dir() {
+            let _ = fs::remove_dir(entry.path());
+        }
+    }
+
+    tracing::info!("Per-job cgroups enabled under {:?}", jobs);
+    Ok(jobs)
+}
@@ -87,7 +87,7 @@ use windmill_common::worker::CLOUD_HOSTED;
 use monitor::monitor_mem;
 
 #[cfg(any(target_os = "linux"))]
-use crate::cgroups::disable_oom_group;
+use crate::cgroups::{delegate_to_jobs, disable_oom_group};
 
 #[cfg(all(not(target_env = "msvc"), feature = "jemalloc"))]
 use tikv_jemallocator::Jemalloc;
@@ -115,10 +115,11 @@ use windmill_common::global_settings::OBJECT_STORE_CONFIG_SETTING;
 
 use windmill_worker::{
     get_hub_script_content_and_requirements, init_worker_internal_server_inline_utils,
-    BUN_BUNDLE_CACHE_DIR, BUN_CACHE_DIR, CSHARP_CACHE_DIR, DENO_CACHE_DIR, DENO_CACHE_DIR_DEPS,
-    DENO_CACHE_DIR_NPM, GO_BIN_CACHE_DIR, GO_CACHE_DIR, JAVA_CACHE_DIR, NU_CACHE_DIR,
-    POWERSHELL_CACHE_DIR, PY310_CACHE_DIR, PY311_CACHE_DIR, PY312_CACHE_DIR, PY313_CACHE_DIR,
-    RUBY_CACHE_DIR, RUST_CACHE_DIR, R_CACHE_DIR, TAR_JAVA_CACHE_DIR, UV_CACHE_DIR,
+    BUN_BUNDLE_CACHE_DIR, BUN_CACHE_DIR, COMPILATION_CACHE_DIR, CSHARP_CACHE_DIR, DENO_CACHE_DIR,
+    DENO_CACHE_DIR_DEPS, DENO_CACHE_DIR_NPM, GO_BIN_CACHE_DIR, GO_CACHE_DIR, JAVA_CACHE_DIR,
+    KOTLIN_CACHE_DIR, NU_CACHE_DIR, PERL_CACHE_DIR, POWERSHELL_CACHE_DIR, PY310_CACHE_DIR,
+    PY311_CACHE_DIR, PY312_CACHE_DIR, PY313_CACHE_DIR, RUBY_CACHE_DIR, RUST_CACHE_DIR, R_CACHE_DIR,
+    TAR_JAVA_CACHE_DIR, UV_CACHE_DIR,
 };
 
 use crate::monitor::{
@@ -934,6 +935,23 @@ async fn windmill_main() -> anyhow::Result<()> {
             );
         }
 
+        // Opt-in: the worker's own processes move into a leaf cgroup so that each job can
+        // be given a sibling cgroup with its own memory, cpu and pids limits.
+        #[cfg(any(target_os = "linux"))]
+        if std::env::var("ENABLE_JOB_CGROUPS")
+            .ok()
+            .is_some_and(|x| x == "true" || x == "1")
+        {
+            match delegate_to_jobs() {
+                Ok(jobs_root) => windmill_worker::job_cgroup::enable(jobs_root),
+                Err(e) => tracing::warn!(
+                    "Per-job cgroups are not available: {e:?}. Job resource limits will not be \
+                    applied and jobs will share the worker's memory and CPU. The worker needs a \
+                    writable cgroup v2 hierarchy with the memory, cpu and pids controllers"
+                ),
+            }
+        }
+
         // Lower the worker's oom_score_adj so the OOM killer strongly prefers killing
         // job subprocesses (oom_score_adj=JOB_OOM_SCORE_ADJ) over the worker itself.
         // Kubernetes sets it high for burstable QoS (e.g. 937), leaving a tiny gap vs jobs.
@@ -1837,6 +1855,23 @@ async fn process_notify_event(
                 }
             };
         }
+        #[cfg(feature = "grpc_trigger")]
+        "notify_grpc_trigger_change" => {
+            tracing::info!("gRPC trigger change detected: {}", payload);
+            match windmill_api::triggers::grpc::refresh_routes(db).await {
+                Ok((true, _)) => {
+                    tracing::info!("Refreshed gRPC routes (trigger change)");
+                }
+                Ok((false, _)) => {
+                    tracing::warn!(
+                        "Should have refreshed gRPC routes (trigger change) but did not"
+                    );
+                }
+                Err(err) => {
+                    tracing::error!("Error refreshing gRPC routes (trigger change): {err:#}");
+                }
+            };
+        }
         "notify_token_invalidation" => {
             tracing::info!(
                 "Token invalidation detected for prefix: {}...",
@@ -2233,14 +2268,17 @@ pub async fn run_workers(
         &*BUN_BUNDLE_CACHE_DIR,
         &*GO_CACHE_DIR,
         &*GO_BIN_CACHE_DIR,
+        &*COMPILATION_CACHE_DIR,
         &*RUST_CACHE_DIR,
         &*CSHARP_CACHE_DIR,
         &*NU_CACHE_DIR,
         &*HUB_CACHE_DIR,
         &*POWERSHELL_CACHE_DIR,
         &*JAVA_CACHE_DIR,
+        &*KOTLIN_CACHE_DIR,
         &*RUBY_CACHE_DIR,
         &*R_CACHE_DIR,
+        &*PERL_CACHE_DIR,
         &*TAR_JAVA_CACHE_DIR, // for related places search: ADD_NEW_LANG
     ] {
         DirBuilder::new()
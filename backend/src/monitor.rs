@@ -391,6 +391,9 @@ pub async fn initial_load(
                     additional_python_paths: None,
                     pip_local_dependencies: None,
                     native_mode,
+                    job_resource_limits: windmill_common::worker::JobResourceLimits::from_env(),
+                    job_isolation: None,
+                    egress_allowlist: None,
                 }));
             }
         }
@@ -1602,6 +1605,42 @@ pub async fn delete_expired_items(db: &DB) -> () {
         Err(e) => tracing::error!("Error deleting MCP OAuth refresh tokens: {}", e.to_string()),
     }
 
+    // Windows last at most a day.
+    if let Err(e) = sqlx::query!(
+        "DELETE FROM http_trigger_rate_limit_window WHERE window_start < now() - INTERVAL '1 day'"
+    )
+    .execute(db)
+    .await
+    {
+        tracing::error!("Error deleting HTTP trigger rate limit windows: {:?}", e);
+    }
+
+    // A bucket is only dropped once it has refilled to capacity under the current limit of
+    // its route, the state a missing bucket starts from: dropping a drained one would
+    // hand its client a full budget. Buckets of routes no longer using one go too.
+    if let Err(e) = sqlx::query!(
+        r#"
+        DELETE FROM http_trigger_rate_limit_bucket AS bucket
+        WHERE NOT EXISTS (
+            SELECT 1
+            FROM http_trigger
+            WHERE
+                http_trigger.workspace_id = bucket.workspace_id
+                AND http_trigger.path = bucket.trigger_path
+                AND http_trigger.rate_limit -> 'limit' ->> 'type' = 'token_bucket'
+                AND bucket.tokens
+                    + EXTRACT(EPOCH FROM now() - bucket.refilled_at)::FLOAT8
+                        * (http_trigger.rate_limit -> 'limit' ->> 'refill_per_second')::FLOAT8
+                    < (http_trigger.rate_limit -> 'limit' ->> 'capacity')::FLOAT8
+        )
+        "#
+    )
+    .execute(db)
+    .await
+    {
+        tracing::error!("Error deleting HTTP trigger rate limit buckets: {:?}", e);
+    }
+
     let deleted_cache = sqlx::query_scalar!(
             "DELETE FROM resource WHERE resource_type = 'cache' AND to_timestamp((value->>'expire')::int) < now() RETURNING path",
         )
@@ -2920,6 +2959,12 @@ pub async fn apply_job_isolation_setting(value: Option<serde_json::Value>) {
             Jobs will run without isolation until unshare is installed or the setting is changed."
         );
     }
+    if value == JobIsolationLevel::Landlock && !windmill_worker::is_landlock_available() {
+        tracing::error!(
+            "job_isolation is set to landlock but Landlock is not supported by the kernel of this worker. \
+            All jobs will fail until the worker runs on Linux 5.13+ with Landlock or the setting is changed."
+        );
+    }
 }
 
 
@@ -4902,6 +4947,26 @@ pub async fn reload_worker_config(db: &DB, tx: KillpillSender, kill_if_change: b
                     tracing::info!("Native mode config changed, sending killpill. Expecting to be restarted by supervisor.");
                     let _ = tx.send();
                 }
+
+                if wc.job_isolation != config.job_isolation {
+                    tracing::info!(
+                        "Worker group job_isolation changed from {:?} to {:?}",
+                        wc.job_isolation,
+                        config.job_isolation
+                    );
+                    if config
+                        .job_isolation
+                        .as_deref()
+                        .is_some_and(|x| x != "landlock")
+                    {
+                        tracing::warn!(
+                            "Worker group job_isolation {:?} is ignored: only landlock can be \
+                            selected per worker group, the instance-wide job_isolation setting \
+                            applies",
+                            config.job_isolation
+                        );
+                    }
+                }
             }
             drop(wc);
 
@@ -1434,6 +1434,47 @@ public class Main {
     Ok(())
 }
 
+#[cfg(feature = "kotlin")]
+#[sqlx::test(fixtures("base"))]
+async fn test_kotlin_job(db: Pool<Postgres>) -> anyhow::Result<()> {
+    initialize_tracing().await;
+    let server = ApiServer::start(db.clone()).await?;
+    let port = server.addr.port();
+
+    let content = r#"
+fun main(name: String, times: Int, tags: List<String>): Map<String, Any> {
+    return mapOf("greeting" to "hello $name".repeat(times), "tags" to tags.size)
+}
+"#
+    .to_owned();
+
+    let job = RunJob::from(JobPayload::Code(RawCode {
+        hash: None,
+        content,
+        path: None,
+        lock: None,
+        language: ScriptLang::Kotlin,
+        cache_ttl: None,
+        cache_ignore_s3_path: None,
+        dedicated_worker: None,
+        concurrency_settings: windmill_common::runnable_settings::ConcurrencySettings::default()
+            .into(),
+        debouncing_settings: windmill_common::runnable_settings::DebouncingSettings::default(),
+        modules: None,
+        tag: None,
+    }))
+    .arg("name", json!("world"))
+    .arg("times", json!(2))
+    .arg("tags", json!(["a", "b"]))
+    .run_until_complete(&db, false, port)
+    .await;
+    assert_eq!(
+        job.json_result(),
+        Some(json!({ "greeting": "hello worldhello world", "tags": 2 }))
+    );
+    Ok(())
+}
+
 #[cfg(feature = "deno_core")]
 #[sqlx::test(fixtures("base"))]
 async fn test_nativets_job(db: Pool<Postgres>) -> anyhow::Result<()> {
@@ -3164,6 +3205,163 @@ end
     Ok(())
 }
 
+#[cfg(feature = "perl")]
+#[sqlx::test(fixtures("base"))]
+async fn test_perl_job(db: Pool<Postgres>) -> anyhow::Result<()> {
+    initialize_tracing().await;
+    let server = ApiServer::start(db.clone()).await?;
+    let port = server.addr.port();
+
+    let content = r#"
+sub main {
+    my ($name) = @_; # Str
+    return "hello $name";
+}
+"#
+    .to_owned();
+
+    let result = RunJob::from(JobPayload::Code(RawCode {
+        hash: None,
+        content,
+        path: None,
+        lock: None,
+        language: ScriptLang::Perl,
+        cache_ttl: None,
+        cache_ignore_s3_path: None,
+        dedicated_worker: None,
+        concurrency_settings: windmill_common::runnable_settings::ConcurrencySettings::default()
+            .into(),
+        debouncing_settings: windmill_common::runnable_settings::DebouncingSettings::default(),
+        modules: None,
+        tag: None,
+    }))
+    .arg("name", json!("world"))
+    .run_until_complete(&db, false, port)
+    .await
+    .json_result()
+    .unwrap();
+
+    assert_eq!(result, serde_json::json!("hello world"));
+    Ok(())
+}
+
+#[cfg(feature = "lua")]
+#[sqlx::test(fixtures("base"))]
+async fn test_lua_job(db: Pool<Postgres>) -> anyhow::Result<()> {
+    initialize_tracing().await;
+    let server = ApiServer::start(db.clone()).await?;
+    let port = server.addr.port();
+
+    let content = r#"
+local wmill = require("wmill")
+
+---@param name string
+---@param times integer
+function main(name, times)
+  times = times or 2
+  print("greeting " .. name)
+  return { greeting = string.rep("hello " .. name .. " ", times), workspace = wmill.get_workspace() }
+end
+"#
+    .to_owned();
+
+    let result = RunJob::from(JobPayload::Code(RawCode {
+        hash: None,
+        content,
+        path: None,
+        lock: None,
+        language: ScriptLang::Lua,
+        cache_ttl: None,
+        cache_ignore_s3_path: None,
+        dedicated_worker: None,
+        concurrency_settings: windmill_common::runnable_settings::ConcurrencySettings::default()
+            .into(),
+        debouncing_settings: windmill_common::runnable_settings::DebouncingSettings::default(),
+        modules: None,
+        tag: None,
+    }))
+    .arg("name", json!("world"))
+    .run_until_complete(&db, false, port)
+    .await
+    .json_result()
+    .unwrap();
+
+    assert_eq!(
+        result,
+        serde_json::json!({ "greeting": "hello world hello world ", "workspace": "test-workspace" })
+    );
+    Ok(())
+}
+
+/// The component is read from the workspace object store, here a local directory.
+#[cfg(all(feature = "wasm", feature = "parquet"))]
+#[sqlx::test(fixtures("base"))]
+async fn test_wasm_job(db: Pool<Postgres>) -> anyhow::Result<()> {
+    initialize_tracing().await;
+    let server = ApiServer::start(db.clone()).await?;
+    let port = server.addr.port();
+
+    let storage_dir = tempfile::tempdir()?;
+    std::fs::create_dir_all(storage_dir.path().join("components"))?;
+    std::fs::write(
+        storage_dir.path().join("components/double.wasm"),
+        wat::parse_str(
+            r#"
+(component
+  (core module $m
+    (func (export "main") (param i32) (result i32)
+      local.get 0
+      i32.const 2
+      i32.mul))
+  (core instance $i (instantiate $m))
+  (func (export "main") (param "n" u32) (result u32)
+    (canon lift (core func $i "main"))))
+"#,
+        )?,
+    )?;
+    sqlx::query("UPDATE workspace_settings SET large_file_storage = $1 WHERE workspace_id = $2")
+        .bind(json!({
+            "type": "FilesystemStorage",
+            "root_path": storage_dir.path().to_string_lossy(),
+        }))
+        .bind("test-workspace")
+        .execute(&db)
+        .await?;
+
+    let content = r#"// component: components/double.wasm
+package test:double;
+
+world double {
+  export main: func(n: u32) -> u32;
+}
+"#
+    .to_owned();
+
+    let result = RunJob::from(JobPayload::Code(RawCode {
+        hash: None,
+        content,
+        path: None,
+        lock: None,
+        language: ScriptLang::Wasm,
+        cache_ttl: None,
+        cache_ignore_s3_path: None,
+        dedicated_worker: None,
+        concurrency_settings: windmill_common::runnable_settings::ConcurrencySettings::default()
+            .into(),
+        debouncing_settings: windmill_common::runnable_settings::DebouncingSettings::default(),
+        modules: None,
+        tag: None,
+    }))
+    .arg("n", json!(21))
+    .run_until_complete(&db, false, port)
+    .await
+    .json_result()
+    .unwrap();
+
+    assert_eq!(result, json!(42));
+    Ok(())
+}
+
 #[sqlx::test(fixtures("base"))]
 async fn test_bun_job_datetime(db: Pool<Postgres>) -> anyhow::Result<()> {
     initialize_tracing().await;
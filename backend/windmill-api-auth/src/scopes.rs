@@ -270,6 +270,12 @@ pub enum ScopeDomain {
     NatsTriggers,
     MqttTriggers,
     AmqpTriggers,
+    PostgresNotifyTriggers,
+    MysqlTriggers,
+    RedisTriggers,
+    ObjectTriggers,
+    SftpTriggers,
+    GrpcTriggers,
     SqsTriggers,
     GcpTriggers,
     AzureTriggers,
@@ -334,6 +340,12 @@ impl ScopeDomain {
             Self::NatsTriggers => "nats_triggers",
             Self::MqttTriggers => "mqtt_triggers",
             Self::AmqpTriggers => "amqp_triggers",
+            Self::PostgresNotifyTriggers => "postgres_notify_triggers",
+            Self::MysqlTriggers => "mysql_triggers",
+            Self::RedisTriggers => "redis_triggers",
+            Self::ObjectTriggers => "object_triggers",
+            Self::SftpTriggers => "sftp_triggers",
+            Self::GrpcTriggers => "grpc_triggers",
             Self::SqsTriggers => "sqs_triggers",
             Self::GcpTriggers => "gcp_triggers",
             Self::AzureTriggers => "azure_triggers",
@@ -391,6 +403,12 @@ impl ScopeDomain {
             "nats_triggers" => Some(Self::NatsTriggers),
             "mqtt_triggers" => Some(Self::MqttTriggers),
             "amqp_triggers" => Some(Self::AmqpTriggers),
+            "postgres_notify_triggers" => Some(Self::PostgresNotifyTriggers),
+            "mysql_triggers" => Some(Self::MysqlTriggers),
+            "redis_triggers" => Some(Self::RedisTriggers),
+            "object_triggers" => Some(Self::ObjectTriggers),
+            "sftp_triggers" => Some(Self::SftpTriggers),
+            "grpc_triggers" => Some(Self::GrpcTriggers),
             "sqs_triggers" => Some(Self::SqsTriggers),
             "gcp_triggers" => Some(Self::GcpTriggers),
             "azure_triggers" => Some(Self::AzureTriggers),
@@ -140,12 +140,15 @@ async fn update_config(
 
     #[cfg(not(feature = "enterprise"))]
     let config = if name.starts_with("worker__") {
-        // In CE, only allow setting worker_tags, cache_clear, init_bash, and native_mode
+        // In CE, only allow setting worker_tags, cache_clear, init_bash, native_mode, job_isolation
+        // and egress_allowlist
         serde_json::json!({
             "worker_tags": config.get("worker_tags"),
             "cache_clear": config.get("cache_clear"),
             "init_bash": config.get("init_bash"),
-            "native_mode": config.get("native_mode")
+            "native_mode": config.get("native_mode"),
+            "job_isolation": config.get("job_isolation"),
+            "egress_allowlist": config.get("egress_allowlist")
         })
     } else {
         config
@@ -185,6 +188,14 @@ async fn update_config(
             }
             _ => {}
         }
+
+        if let Some(entries) = config.get("egress_allowlist").filter(|v| !v.is_null()) {
+            let entries = serde_json::from_value::<Vec<String>>(entries.clone()).map_err(|_| {
+                error::Error::BadRequest("egress_allowlist must be a list of strings".to_string())
+            })?;
+            windmill_common::egress::EgressAllowlist::parse(&entries)
+                .map_err(error::Error::BadRequest)?;
+        }
     }
 
     let mut tx = db.begin().await?;
@@ -45,14 +45,28 @@ fn audit_action_prefix_for_acl_kind(kind: &str) -> Option<&'static str> {
         "resource" => Some("resources"),
         "variable" => Some("variables"),
         "schedule" => Some("schedules"),
-        "http_trigger" | "websocket_trigger" | "kafka_trigger" | "nats_trigger"
-        | "postgres_trigger" | "mqtt_trigger" | "amqp_trigger" | "gcp_trigger"
-        | "azure_trigger" | "sqs_trigger" | "email_trigger" => Some("triggers"),
+        "http_trigger"
+        | "websocket_trigger"
+        | "kafka_trigger"
+        | "nats_trigger"
+        | "postgres_trigger"
+        | "mqtt_trigger"
+        | "amqp_trigger"
+        | "postgres_notify_trigger"
+        | "mysql_trigger"
+        | "redis_trigger"
+        | "object_trigger"
+        | "sftp_trigger"
+        | "grpc_trigger"
+        | "gcp_trigger"
+        | "azure_trigger"
+        | "sqs_trigger"
+        | "email_trigger" => Some("triggers"),
         _ => None,
     }
 }
 
-const KINDS: [&str; 21] = [
+const KINDS: [&str; 27] = [
     "script",
     "group_",
     "resource",
@@ -69,6 +83,12 @@ const KINDS: [&str; 21] = [
     "postgres_trigger",
     "mqtt_trigger",
     "amqp_trigger",
+    "postgres_notify_trigger",
+    "mysql_trigger",
+    "redis_trigger",
+    "object_trigger",
+    "sftp_trigger",
+    "grpc_trigger",
     "gcp_trigger",
     "azure_trigger",
     "sqs_trigger",
@@ -0,0 +1,125 @@
+//! Replay of captured trigger payloads against the latest version of a script.
+//!
+//! No worker runs in these tests, the replayed jobs are completed by the test itself.
+
+use std::time::Duration;
+
+use serde_json::{json, Value};
+use sqlx::{Pool, Postgres};
+use windmill_test_utils::*;
+
+const SCRIPT_PATH: &str = "f/test/replayed";
+
+/// Waits for the next queued job of the script and completes it with `result`, as a worker
+/// would have, returning the args it was pushed with.
+async fn complete_next_job(db: &Pool<Postgres>, result: Value) -> anyhow::Result<Value> {
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(20);
+    let (id, args) = loop {
+        let job = sqlx::query_as::<_, (uuid::Uuid, Option<Value>)>(
+            "SELECT v2_job.id, v2_job.args FROM v2_job
+             JOIN v2_job_queue ON v2_job_queue.id = v2_job.id
+             WHERE v2_job.runnable_path = $1
+             LIMIT 1",
+        )
+        .bind(SCRIPT_PATH)
+        .fetch_optional(db)
+        .await?;
+        if let Some(job) = job {
+            break job;
+        }
+        if tokio::time::Instant::now() >= deadline {
+            anyhow::bail!("timed out waiting for the replayed job");
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    };
+
+    sqlx::query("DELETE FROM v2_job_queue WHERE id = $1")
+        .bind(id)
+        .execute(db)
+        .await?;
+    sqlx::query(
+        "INSERT INTO v2_job_completed (id, workspace_id, duration_ms, result, status)
+         VALUES ($1, 'test-workspace', 100, $2, 'success'::job_status)",
+    )
+    .bind(id)
+    .bind(result)
+    .execute(db)
+    .await?;
+
+    Ok(args.unwrap_or_default())
+}
+
+#[sqlx::test(migrations = "../migrations", fixtures("base"))]
+async fn test_replay_capture_runs_job(db: Pool<Postgres>) -> anyhow::Result<()> {
+    initialize_tracing().await;
+
+    sqlx::query(
+        "INSERT INTO script (workspace_id, hash, path, summary, description, content,
+                  created_by, language, kind, lock)
+         VALUES ('test-workspace', $1, $2, '', '', 'def main(name): pass',
+                  'test-user', 'python3', 'script', '')",
+    )
+    .bind(rand::random::<i64>().unsigned_abs() as i64)
+    .bind(SCRIPT_PATH)
+    .execute(&db)
+    .await?;
+    let capture_id: i64 = sqlx::query_scalar(
+        "INSERT INTO capture (workspace_id, path, is_flow, trigger_kind, main_args,
+                  preprocessor_args, created_by)
+         VALUES ('test-workspace', $1, false, 'webhook', $2, NULL, 'test-user')
+         RETURNING id",
+    )
+    .bind(SCRIPT_PATH)
+    .bind(json!({ "name": "alice" }))
+    .fetch_one(&db)
+    .await?;
+
+    let server = ApiServer::start(db.clone()).await?;
+    let url = format!(
+        "http://localhost:{}/api/w/test-workspace/capture/replay",
+        server.addr.port()
+    );
+
+    let (response, args) = tokio::join!(
+        async {
+            reqwest::Client::new()
+                .post(&url)
+                .header("Authorization", "Bearer SECRET_TOKEN")
+                .json(&json!({
+                    "source": { "type": "captures", "ids": [capture_id] },
+                    "target": { "kind": "script", "path": SCRIPT_PATH },
+                    "timeout_secs": 20,
+                }))
+                .send()
+                .await
+        },
+        complete_next_job(&db, json!("hello alice")),
+    );
+    assert_eq!(args?["name"], json!("alice"));
+
+    let response = response?;
+    let status = response.status();
+    let body: Value = response.json().await?;
+    assert_eq!(status, 200, "{body}");
+    assert_eq!(body["path"], json!(SCRIPT_PATH));
+    assert_eq!(body["is_flow"], json!(false));
+
+    let items = body["items"].as_array().expect("items");
+    assert_eq!(items.len(), 1);
+    let item = &items[0];
+    assert_eq!(item["capture_id"], json!(capture_id));
+    assert_eq!(item["replay"]["completed"], json!(true));
+    assert_eq!(item["replay"]["success"], json!(true));
+    assert_eq!(item["replay"]["result"], json!("hello alice"));
+
+    // The reported job is the one pushed for the capture.
+    let job_id: uuid::Uuid = item["replay"]["job_id"].as_str().expect("job_id").parse()?;
+    let runnable_path: Option<String> =
+        sqlx::query_scalar("SELECT runnable_path FROM v2_job WHERE id = $1")
+            .bind(job_id)
+            .fetch_one(&db)
+            .await?;
+    assert_eq!(runnable_path.as_deref(), Some(SCRIPT_PATH));
+
+    Ok(())
+}
@@ -0,0 +1,262 @@
+//! gRPC triggers called by a tonic client, as a service generated from the definition of
+//! the trigger would: unary and server streaming methods, authentication and the
+//! workspace metadata.
+//!
+//! No worker runs in these tests, the jobs of the calls are completed by the test itself.
+//!
+//! Run:
+//! ```bash
+//! cargo test --test grpc_trigger --features grpc_trigger -- --nocapture
+//! ```
+#![cfg(feature = "grpc_trigger")]
+
+use std::time::Duration;
+
+use serde_json::json;
+use sqlx::{Pool, Postgres};
+use tonic::{metadata::MetadataValue, Code, Request};
+
+use windmill_test_utils::*;
+
+const DEFINITION: &str = r#"
+syntax = "proto3";
+package greeter;
+message HelloRequest { string name = 1; }
+message HelloReply { string message = 1; }
+service Greeter {
+    rpc SayHello(HelloRequest) returns (HelloReply);
+    rpc SayHellos(HelloRequest) returns (stream HelloReply);
+}
+"#;
+
+const SCRIPT_PATH: &str = "f/test/grpc_handler";
+
+/// What `tonic-build` generates for the service above, written out to do without a build
+/// script and `protoc`.
+mod greeter {
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct HelloRequest {
+        #[prost(string, tag = "1")]
+        pub name: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct HelloReply {
+        #[prost(string, tag = "1")]
+        pub message: String,
+    }
+
+    pub struct GreeterClient {
+        inner: tonic::client::Grpc<tonic::transport::Channel>,
+    }
+
+    impl GreeterClient {
+        pub async fn connect(url: String) -> Result<Self, tonic::transport::Error> {
+            let channel = tonic::transport::Endpoint::new(url)?.connect().await?;
+            Ok(Self { inner: tonic::client::Grpc::new(channel) })
+        }
+
+        pub async fn say_hello(
+            &mut self,
+            request: tonic::Request<HelloRequest>,
+        ) -> Result<tonic::Response<HelloReply>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| tonic::Status::unknown(format!("Service was not ready: {e}")))?;
+            let path =
+                tonic::codegen::http::uri::PathAndQuery::from_static("/greeter.Greeter/SayHello");
+            self.inner
+                .unary(request, path, tonic::codec::ProstCodec::default())
+                .await
+        }
+
+        pub async fn say_hellos(
+            &mut self,
+            request: tonic::Request<HelloRequest>,
+        ) -> Result<tonic::Response<tonic::codec::Streaming<HelloReply>>, tonic::Status> {
+            self.inner
+                .ready()
+                .await
+                .map_err(|e| tonic::Status::unknown(format!("Service was not ready: {e}")))?;
+            let path =
+                tonic::codegen::http::uri::PathAndQuery::from_static("/greeter.Greeter/SayHellos");
+            self.inner
+                .server_streaming(request, path, tonic::codec::ProstCodec::default())
+                .await
+        }
+    }
+}
+
+use greeter::{GreeterClient, HelloReply, HelloRequest};
+
+async fn insert_trigger(
+    db: &Pool<Postgres>,
+    path: &str,
+    method_name: &str,
+    authentication_method: &str,
+) -> anyhow::Result<()> {
+    sqlx::query(
+        "INSERT INTO grpc_trigger (workspace_id, path, proto_definition, service_name,
+            method_name, authentication_method, script_path, is_flow, edited_by,
+            permissioned_as)
+         VALUES ('test-workspace', $1, $2, 'greeter.Greeter', $3, $4, $5, false, 'test-user',
+            'u/test-user')",
+    )
+    .bind(path)
+    .bind(DEFINITION)
+    .bind(method_name)
+    .bind(authentication_method)
+    .bind(SCRIPT_PATH)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+/// Waits for the next job of the handler and completes it with `result`, as a worker would
+/// have, returning the args it was pushed with.
+async fn complete_next_job(
+    db: &Pool<Postgres>,
+    result: serde_json::Value,
+) -> anyhow::Result<serde_json::Value> {
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(20);
+    let (id, args) = loop {
+        let job = sqlx::query_as::<_, (uuid::Uuid, Option<serde_json::Value>)>(
+            "SELECT v2_job.id, v2_job.args FROM v2_job
+             JOIN v2_job_queue ON v2_job_queue.id = v2_job.id
+             WHERE v2_job.runnable_path = $1 AND v2_job.trigger_kind = 'grpc'::job_trigger_kind
+             LIMIT 1",
+        )
+        .bind(SCRIPT_PATH)
+        .fetch_optional(db)
+        .await?;
+        if let Some(job) = job {
+            break job;
+        }
+        if tokio::time::Instant::now() >= deadline {
+            anyhow::bail!("timed out waiting for the job of the gRPC call");
+        }
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    };
+
+    sqlx::query("DELETE FROM v2_job_queue WHERE id = $1")
+        .bind(id)
+        .execute(db)
+        .await?;
+    sqlx::query(
+        "INSERT INTO v2_job_completed (id, workspace_id, duration_ms, result, status)
+         VALUES ($1, 'test-workspace', 100, $2, 'success'::job_status)",
+    )
+    .bind(id)
+    .bind(result)
+    .execute(db)
+    .await?;
+
+    Ok(args.unwrap_or_default())
+}
+
+fn request(name: &str, workspace_id: Option<&str>, token: Option<&str>) -> Request<HelloRequest> {
+    let mut request = Request::new(HelloRequest { name: name.to_string() });
+    if let Some(workspace_id) = workspace_id {
+        request.metadata_mut().insert(
+            "x-windmill-workspace",
+            MetadataValue::try_from(workspace_id).unwrap(),
+        );
+    }
+    if let Some(token) = token {
+        request.metadata_mut().insert(
+            "authorization",
+            MetadataValue::try_from(format!("Bearer {token}")).unwrap(),
+        );
+    }
+    request
+}
+
+/// A single test, the routes being cached process-wide rather than per database.
+#[sqlx::test(migrations = "../migrations", fixtures("base"))]
+async fn test_grpc_trigger_calls(db: Pool<Postgres>) -> anyhow::Result<()> {
+    initialize_tracing().await;
+
+    sqlx::query(
+        "INSERT INTO script (workspace_id, hash, path, summary, description, content,
+                  created_by, language, kind, lock)
+         VALUES ('test-workspace', $1, $2, '', '', 'def main(name): pass',
+                  'test-user', 'python3', 'script', '')",
+    )
+    .bind(rand::random::<i64>().unsigned_abs() as i64)
+    .bind(SCRIPT_PATH)
+    .execute(&db)
+    .await?;
+    insert_trigger(&db, "f/test/say_hello", "SayHello", "none").await?;
+    insert_trigger(&db, "f/test/say_hellos", "SayHellos", "windmill").await?;
+
+    let server = ApiServer::start(db.clone()).await?;
+    let mut client =
+        GreeterClient::connect(format!("http://localhost:{}", server.addr.port())).await?;
+
+    // Unary call of a public method, the message being the args of the job.
+    let (response, args) = tokio::join!(
+        client.say_hello(request("alice", Some("test-workspace"), None)),
+        complete_next_job(&db, json!({ "message": "hello alice" })),
+    );
+    assert_eq!(args?["name"], json!("alice"));
+    assert_eq!(
+        response?.into_inner(),
+        HelloReply { message: "hello alice".to_string() }
+    );
+
+    // Methods are only unique within a workspace, which every call has to name.
+    let status = client
+        .say_hello(request("bob", None, None))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::InvalidArgument, "{status:?}");
+    let status = client
+        .say_hello(request("bob", Some("other-workspace"), None))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::Unimplemented, "{status:?}");
+
+    // A method requiring Windmill authentication refuses calls without a valid token.
+    let status = client
+        .say_hellos(request("bob", Some("test-workspace"), None))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::Unauthenticated, "{status:?}");
+    let status = client
+        .say_hellos(request(
+            "bob",
+            Some("test-workspace"),
+            Some("INVALID_TOKEN"),
+        ))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::Unauthenticated, "{status:?}");
+
+    // Server streaming: a list result is one response message per item.
+    let (replies, args) = tokio::join!(
+        async {
+            let mut stream = client
+                .say_hellos(request("bob", Some("test-workspace"), Some("SECRET_TOKEN")))
+                .await?
+                .into_inner();
+            let mut replies = vec![];
+            while let Some(reply) = stream.message().await? {
+                replies.push(reply.message);
+            }
+            Ok::<_, tonic::Status>(replies)
+        },
+        complete_next_job(&db, json!([{ "message": "hello" }, { "message": "bob" }])),
+    );
+    assert_eq!(args?["name"], json!("bob"));
+    assert_eq!(replies?, vec!["hello".to_string(), "bob".to_string()]);
+
+    // Refused calls do not push any job.
+    let jobs: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM v2_job WHERE runnable_path = $1")
+        .bind(SCRIPT_PATH)
+        .fetch_one(&db)
+        .await?;
+    assert_eq!(jobs, 2);
+
+    Ok(())
+}
@@ -0,0 +1,238 @@
+//! Streaming uploads of HTTP routes: bodies and multipart files are written to the
+//! workspace storage, and nothing is left behind when a request is refused partway.
+//!
+//! The routes upload to a filesystem workspace storage, so no service is needed, but the
+//! streaming upload itself is only implemented with the `private` feature.
+#![cfg(all(feature = "http_trigger", feature = "parquet", feature = "private"))]
+
+use std::{path::PathBuf, time::Duration};
+
+use reqwest::multipart::{Form, Part};
+use serde_json::json;
+use sqlx::{Pool, Postgres};
+
+use windmill_test_utils::*;
+
+const SCRIPT_PATH: &str = "f/test/upload_handler";
+
+async fn setup(db: &Pool<Postgres>, route_path: &str, upload_config: serde_json::Value) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("windmill_http_upload_{}", rand::random::<u32>()));
+    std::fs::create_dir_all(&root).unwrap();
+
+    sqlx::query(
+        "UPDATE workspace_settings SET large_file_storage = $1 WHERE workspace_id = 'test-workspace'",
+    )
+    .bind(json!({ "type": "FilesystemStorage", "root_path": root.to_string_lossy() }))
+    .execute(db)
+    .await
+    .unwrap();
+
+    sqlx::query(
+        "INSERT INTO script (workspace_id, hash, path, summary, description, content,
+                  created_by, language, kind, lock)
+         VALUES ('test-workspace', $1, $2, '', '', 'def main(): pass',
+                  'test-user', 'python3', 'script', '')
+         ON CONFLICT DO NOTHING",
+    )
+    .bind(rand::random::<i64>().unsigned_abs() as i64)
+    .bind(SCRIPT_PATH)
+    .execute(db)
+    .await
+    .unwrap();
+
+    sqlx::query(
+        "INSERT INTO http_trigger (workspace_id, path, edited_by, edited_at, route_path,
+            route_path_key, script_path, is_flow, http_method, request_type,
+            authentication_method, mode, permissioned_as, upload_config)
+         VALUES ('test-workspace', $1, 'test-user', NOW(), $2, $2, $3, false, 'post', 'async',
+            'none', 'enabled', 'u/test-user', $4)",
+    )
+    .bind(format!("f/test/{route_path}"))
+    .bind(route_path)
+    .bind(SCRIPT_PATH)
+    .bind(upload_config)
+    .execute(db)
+    .await
+    .unwrap();
+
+    root
+}
+
+/// Every object under the upload prefix, once no deletion is pending.
+async fn uploaded_files(root: &PathBuf) -> Vec<PathBuf> {
+    // Objects of a refused request are deleted in the background.
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    let dir = root.join("http_trigger_uploads");
+    match std::fs::read_dir(&dir) {
+        Ok(entries) => entries.map(|entry| entry.unwrap().path()).collect(),
+        Err(_) => vec![],
+    }
+}
+
+async fn job_args(db: &Pool<Postgres>) -> Vec<serde_json::Value> {
+    sqlx::query_scalar(
+        "SELECT COALESCE(args, '{}'::jsonb) FROM v2_job
+         WHERE runnable_path = $1 AND trigger_kind = 'http'::job_trigger_kind",
+    )
+    .bind(SCRIPT_PATH)
+    .fetch_all(db)
+    .await
+    .unwrap()
+}
+
+#[sqlx::test(migrations = "../migrations", fixtures("base"))]
+async fn test_upload_streams_body(db: Pool<Postgres>) -> anyhow::Result<()> {
+    initialize_tracing().await;
+    let root = setup(&db, "upload_body", json!({})).await;
+    let server = ApiServer::start(db.clone()).await?;
+
+    let response = reqwest::Client::new()
+        .post(format!(
+            "http://localhost:{}/api/r/upload_body",
+            server.addr.port()
+        ))
+        .header("content-type", "text/csv")
+        .body("a,b\n1,2\n")
+        .send()
+        .await?;
+    assert_eq!(response.status(), 201, "{}", response.text().await?);
+
+    let files = uploaded_files(&root).await;
+    assert_eq!(files.len(), 1);
+    assert_eq!(std::fs::read_to_string(&files[0])?, "a,b\n1,2\n");
+    let args = job_args(&db).await;
+    assert_eq!(args.len(), 1);
+    assert!(
+        args[0].to_string().contains("http_trigger_uploads/"),
+        "{}",
+        args[0]
+    );
+
+    std::fs::remove_dir_all(&root).ok();
+    Ok(())
+}
+
+#[sqlx::test(migrations = "../migrations", fixtures("base"))]
+async fn test_upload_splits_multipart(db: Pool<Postgres>) -> anyhow::Result<()> {
+    initialize_tracing().await;
+    let root = setup(&db, "upload_multipart", json!({})).await;
+    let server = ApiServer::start(db.clone()).await?;
+
+    let form = Form::new()
+        .text("title", "quarterly")
+        .part(
+            "report",
+            Part::text("a,b\n")
+                .file_name("report.csv")
+                .mime_str("text/csv")?,
+        )
+        .part(
+            "evil",
+            Part::text("x")
+                .file_name("x.sh/../../etc\"\r\n")
+                .mime_str("text/plain")?,
+        );
+    let response = reqwest::Client::new()
+        .post(format!(
+            "http://localhost:{}/api/r/upload_multipart",
+            server.addr.port()
+        ))
+        .multipart(form)
+        .send()
+        .await?;
+    assert_eq!(response.status(), 201, "{}", response.text().await?);
+
+    let mut names = uploaded_files(&root)
+        .await
+        .into_iter()
+        .map(|path| path.file_name().unwrap().to_string_lossy().to_string())
+        .collect::<Vec<_>>();
+    names.sort_by_key(|name| !name.ends_with(".csv"));
+    assert_eq!(names.len(), 2, "{names:?}");
+    assert!(names[0].ends_with(".csv"));
+    // The extension of the client filename is not kept when it is not alphanumeric.
+    assert!(!names[1].contains('.'), "{names:?}");
+
+    let args = job_args(&db).await;
+    assert_eq!(args.len(), 1);
+    let args = args[0].to_string();
+    assert!(args.contains("quarterly"), "{args}");
+    assert!(args.contains("report.csv"), "{args}");
+
+    std::fs::remove_dir_all(&root).ok();
+    Ok(())
+}
+
+#[sqlx::test(migrations = "../migrations", fixtures("base"))]
+async fn test_upload_refused_partway_is_cleaned_up(db: Pool<Postgres>) -> anyhow::Result<()> {
+    initialize_tracing().await;
+    let root = setup(
+        &db,
+        "upload_refused",
+        json!({ "allowed_content_types": ["text/csv"], "max_size_bytes": 64 }),
+    )
+    .await;
+    let server = ApiServer::start(db.clone()).await?;
+    let url = format!(
+        "http://localhost:{}/api/r/upload_refused",
+        server.addr.port()
+    );
+
+    // The first file is uploaded before the second one is refused.
+    let form = Form::new()
+        .part(
+            "ok",
+            Part::text("a,b\n")
+                .file_name("ok.csv")
+                .mime_str("text/csv")?,
+        )
+        .part(
+            "bad",
+            Part::text("#!/bin/sh")
+                .file_name("bad.sh")
+                .mime_str("application/x-sh")?,
+        );
+    let response = reqwest::Client::new()
+        .post(&url)
+        .multipart(form)
+        .send()
+        .await?;
+    assert_eq!(response.status(), 415);
+    assert_eq!(uploaded_files(&root).await, Vec::<PathBuf>::new());
+
+    // Same for a file over the maximum size.
+    let form = Form::new()
+        .part(
+            "ok",
+            Part::text("a,b\n")
+                .file_name("ok.csv")
+                .mime_str("text/csv")?,
+        )
+        .part(
+            "big",
+            Part::text("x".repeat(1024))
+                .file_name("big.csv")
+                .mime_str("text/csv")?,
+        );
+    let response = reqwest::Client::new()
+        .post(&url)
+        .multipart(form)
+        .send()
+        .await?;
+    assert!(response.status().is_client_error(), "{}", response.status());
+    assert_eq!(uploaded_files(&root).await, Vec::<PathBuf>::new());
+
+    // And for too many fields.
+    let form = (0..1001).fold(Form::new(), |form, i| form.text(format!("f{i}"), "x"));
+    let response = reqwest::Client::new()
+        .post(&url)
+        .multipart(form)
+        .send()
+        .await?;
+    assert_eq!(response.status(), 413);
+
+    assert!(job_args(&db).await.is_empty());
+
+    std::fs::remove_dir_all(&root).ok();
+    Ok(())
+}
@@ -83,6 +83,80 @@ async fn poll_for_trigger_job(
     }
 }
 
+/// Poll every 500ms for up to `timeout` until at least `count` jobs match the
+/// `runnable_path` and `trigger_kind`, and return the args of every one of them.
+async fn wait_for_trigger_jobs(
+    db: &Pool<Postgres>,
+    script_path: &str,
+    trigger_kind: &str,
+    count: usize,
+    timeout: Duration,
+) -> anyhow::Result<Vec<serde_json::Value>> {
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        let args = trigger_job_args(db, script_path, trigger_kind).await?;
+        if args.len() >= count {
+            return Ok(args);
+        }
+
+        if tokio::time::Instant::now() >= deadline {
+            anyhow::bail!(
+                "timed out waiting for {} trigger jobs (script_path={}, trigger_kind={}), got {}",
+                count,
+                script_path,
+                trigger_kind,
+                args.len()
+            );
+        }
+
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+}
+
+/// The args of every job matching the `runnable_path` and `trigger_kind`, oldest first.
+async fn trigger_job_args(
+    db: &Pool<Postgres>,
+    script_path: &str,
+    trigger_kind: &str,
+) -> anyhow::Result<Vec<serde_json::Value>> {
+    let args = sqlx::query_scalar(
+        r#"
+        SELECT COALESCE(args, '{}'::jsonb)
+        FROM v2_job
+        WHERE runnable_path = $1
+          AND trigger_kind = $2::job_trigger_kind
+        ORDER BY created_at
+        "#,
+    )
+    .bind(script_path)
+    .bind(trigger_kind)
+    .fetch_all(db)
+    .await?;
+    Ok(args)
+}
+
+/// Point the primary object storage of the test workspace at a fresh directory, for
+/// triggers that read or write the workspace storage.
+async fn use_filesystem_storage(
+    db: &Pool<Postgres>,
+    name: &str,
+) -> anyhow::Result<std::path::PathBuf> {
+    let root = std::env::temp_dir().join(format!("{}_{}", name, rand::random::<u32>()));
+    std::fs::create_dir_all(&root)?;
+
+    sqlx::query(
+        "UPDATE workspace_settings SET large_file_storage = $1 WHERE workspace_id = 'test-workspace'",
+    )
+    .bind(json!({
+        "type": "FilesystemStorage",
+        "root_path": root.to_string_lossy(),
+    }))
+    .execute(db)
+    .await?;
+
+    Ok(root)
+}
+
 /// Insert a minimal test script row that trigger listeners can reference.
 async fn insert_test_script(db: &Pool<Postgres>, path: &str) -> anyhow::Result<i64> {
     let hash: i64 = rand::random::<i64>().unsigned_abs() as i64;
@@ -539,6 +613,375 @@ async fn test_postgres_slot_lsn_advances(db: Pool<Postgres>) -> anyhow::Result<(
     Ok(())
 }
 
+/// Sets up a table holding `rows`, a publication over it and a Postgres trigger with
+/// the initial snapshot on, pointing at `slot_name`. The slot itself is left to the test.
+async fn insert_snapshot_trigger(
+    db: &Pool<Postgres>,
+    trigger_path: &str,
+    script_path: &str,
+    slot_name: &str,
+    pub_name: &str,
+    rows: &[&str],
+) -> anyhow::Result<()> {
+    insert_test_script(db, script_path).await?;
+
+    sqlx::query("CREATE TABLE snapshot_trigger_table (id serial PRIMARY KEY, data text)")
+        .execute(db)
+        .await?;
+    for row in rows {
+        sqlx::query("INSERT INTO snapshot_trigger_table (data) VALUES ($1)")
+            .bind(row)
+            .execute(db)
+            .await?;
+    }
+    sqlx::query(&format!(
+        "CREATE PUBLICATION {pub_name} FOR TABLE snapshot_trigger_table"
+    ))
+    .execute(db)
+    .await?;
+
+    let test_db_name: String = sqlx::query_scalar("SELECT current_database()")
+        .fetch_one(db)
+        .await?;
+
+    insert_resource(
+        db,
+        "u/test-user/pg_snapshot_res",
+        "postgresql",
+        json!({
+            "user": "postgres",
+            "password": "changeme",
+            "host": "localhost",
+            "port": 5432,
+            "dbname": test_db_name,
+            "sslmode": "disable"
+        }),
+    )
+    .await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO postgres_trigger (
+            path, script_path, is_flow, workspace_id, edited_by, permissioned_as,
+            postgres_resource_path, replication_slot_name, publication_name, initial_snapshot
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, true)
+        "#,
+    )
+    .bind(trigger_path)
+    .bind(script_path)
+    .bind(false)
+    .bind("test-workspace")
+    .bind("test-user")
+    .bind("u/test-user")
+    .bind("u/test-user/pg_snapshot_res")
+    .bind(slot_name)
+    .bind(pub_name)
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+/// The `data` of the rows the trigger's jobs were started with, sorted.
+async fn triggered_snapshot_rows(
+    db: &Pool<Postgres>,
+    script_path: &str,
+) -> anyhow::Result<Vec<String>> {
+    let rows = sqlx::query_scalar(
+        r#"
+        SELECT args -> 'row' ->> 'data'
+        FROM v2_job
+        WHERE runnable_path = $1 AND trigger_kind = 'postgres'::job_trigger_kind
+        ORDER BY 1
+        "#,
+    )
+    .bind(script_path)
+    .fetch_all(db)
+    .await?;
+    Ok(rows)
+}
+
+/// Poll every 500ms for up to 30s until the trigger's jobs cover exactly `expected`.
+async fn wait_for_snapshot_rows(
+    db: &Pool<Postgres>,
+    script_path: &str,
+    expected: &[&str],
+) -> anyhow::Result<()> {
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(30);
+    loop {
+        let rows = triggered_snapshot_rows(db, script_path).await?;
+        if rows == expected {
+            return Ok(());
+        }
+        if tokio::time::Instant::now() >= deadline {
+            anyhow::bail!("expected jobs for rows {:?}, got {:?}", expected, rows);
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+}
+
+/// End-to-end test for the initial snapshot of a Postgres trigger: the rows that
+/// exist when the trigger starts are emitted once each, then the slot the snapshot
+/// created hands over to the stream without a gap or a duplicate.
+///
+/// Requires PostgreSQL with `wal_level=logical` (see `test_postgres_e2e`).
+///
+/// Run:
+/// ```bash
+/// cargo test --test trigger_e2e test_postgres_initial_snapshot_e2e \
+///     --features postgres_trigger -- --ignored --nocapture
+/// ```
+#[ignore = "requires PostgreSQL with wal_level=logical"]
+#[sqlx::test(migrations = "../migrations", fixtures("base"))]
+async fn test_postgres_initial_snapshot_e2e(db: Pool<Postgres>) -> anyhow::Result<()> {
+    initialize_tracing().await;
+
+    let script_path = "f/test/pg_snapshot_handler";
+    let trigger_path = "f/test/pg_snapshot_trigger";
+    // Replication slots are server-wide so use a random suffix. The slot is not
+    // created here: the snapshot creates it.
+    let suffix: u32 = rand::random();
+    let slot_name = format!("test_snapshot_slot_{suffix}");
+    let pub_name = format!("test_snapshot_pub_{suffix}");
+
+    insert_snapshot_trigger(
+        &db,
+        trigger_path,
+        script_path,
+        &slot_name,
+        &pub_name,
+        &["first", "second"],
+    )
+    .await?;
+
+    let _server = ApiServer::start_with_listeners(db.clone()).await?;
+
+    wait_for_snapshot_rows(&db, script_path, &["first", "second"]).await?;
+
+    let (snapshot_completed, slot_owned): (bool, bool) = sqlx::query_as(
+        r#"
+        SELECT snapshot_completed_at IS NOT NULL, replication_slot_owned
+        FROM postgres_trigger
+        WHERE workspace_id = 'test-workspace' AND path = $1
+        "#,
+    )
+    .bind(trigger_path)
+    .fetch_one(&db)
+    .await?;
+    assert!(
+        snapshot_completed,
+        "snapshot should be recorded as completed"
+    );
+    assert!(
+        slot_owned,
+        "the slot the snapshot created should be owned by the trigger"
+    );
+
+    // Committed after the snapshot: delivered by the stream, and only by it
+    sqlx::query("INSERT INTO snapshot_trigger_table (data) VALUES ('third')")
+        .execute(&db)
+        .await?;
+
+    wait_for_snapshot_rows(&db, script_path, &["first", "second", "third"]).await?;
+    tokio::time::sleep(Duration::from_secs(3)).await;
+    assert_eq!(
+        triggered_snapshot_rows(&db, script_path).await?,
+        ["first", "second", "third"],
+        "no row should be emitted twice"
+    );
+
+    sqlx::query(&format!("SELECT pg_drop_replication_slot('{slot_name}')"))
+        .execute(&db)
+        .await?;
+
+    Ok(())
+}
+
+/// The initial snapshot recreates its slot, so it must refuse to start from a slot
+/// that already existed and that the trigger did not create: it is left untouched and
+/// the trigger is disabled with an error instead.
+///
+/// Requires PostgreSQL with `wal_level=logical` (see `test_postgres_e2e`).
+///
+/// Run:
+/// ```bash
+/// cargo test --test trigger_e2e test_postgres_initial_snapshot_keeps_foreign_slot \
+///     --features postgres_trigger -- --ignored --nocapture
+/// ```
+#[ignore = "requires PostgreSQL with wal_level=logical"]
+#[sqlx::test(migrations = "../migrations", fixtures("base"))]
+async fn test_postgres_initial_snapshot_keeps_foreign_slot(
+    db: Pool<Postgres>,
+) -> anyhow::Result<()> {
+    initialize_tracing().await;
+
+    let script_path = "f/test/pg_foreign_slot_handler";
+    let trigger_path = "f/test/pg_foreign_slot_trigger";
+    let suffix: u32 = rand::random();
+    let slot_name = format!("test_foreign_slot_{suffix}");
+    let pub_name = format!("test_foreign_pub_{suffix}");
+
+    insert_snapshot_trigger(
+        &db,
+        trigger_path,
+        script_path,
+        &slot_name,
+        &pub_name,
+        &["row"],
+    )
+    .await?;
+    sqlx::query(&format!(
+        "SELECT pg_create_logical_replication_slot('{slot_name}', 'pgoutput')"
+    ))
+    .execute(&db)
+    .await?;
+    let slot_lsn: String = sqlx::query_scalar(
+        "SELECT restart_lsn::text FROM pg_replication_slots WHERE slot_name = $1",
+    )
+    .bind(&slot_name)
+    .fetch_one(&db)
+    .await?;
+
+    let _server = ApiServer::start_with_listeners(db.clone()).await?;
+
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(30);
+    let error = loop {
+        let error: Option<String> = sqlx::query_scalar(
+            "SELECT error FROM postgres_trigger WHERE workspace_id = 'test-workspace' AND path = $1",
+        )
+        .bind(trigger_path)
+        .fetch_one(&db)
+        .await?;
+        if let Some(error) = error {
+            break error;
+        }
+        if tokio::time::Instant::now() >= deadline {
+            anyhow::bail!("timed out waiting for the trigger to report the foreign slot");
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    };
+    assert!(error.contains("already existed"), "got: {error}");
+
+    let current_lsn: Option<String> = sqlx::query_scalar(
+        "SELECT restart_lsn::text FROM pg_replication_slots WHERE slot_name = $1",
+    )
+    .bind(&slot_name)
+    .fetch_optional(&db)
+    .await?;
+    assert_eq!(
+        current_lsn.as_deref(),
+        Some(slot_lsn.as_str()),
+        "the slot should not have been dropped nor recreated"
+    );
+    assert!(triggered_snapshot_rows(&db, script_path).await?.is_empty());
+
+    sqlx::query(&format!("SELECT pg_drop_replication_slot('{slot_name}')"))
+        .execute(&db)
+        .await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// Postgres NOTIFY Trigger E2E
+// ============================================================================
+
+/// End-to-end test for the Postgres NOTIFY trigger: a NOTIFY on a listened channel
+/// starts one job with the parsed payload, one on another channel starts none.
+///
+/// Requires the PostgreSQL server of the test database to accept the same credentials
+/// over TCP on localhost:5432 (the default local setup). No other service is needed.
+///
+/// Run:
+/// ```bash
+/// cargo test --test trigger_e2e test_postgres_notify_e2e --features postgres_notify_trigger \
+///     -- --ignored --nocapture
+/// ```
+#[ignore = "requires PostgreSQL on localhost:5432"]
+#[sqlx::test(migrations = "../migrations", fixtures("base"))]
+async fn test_postgres_notify_e2e(db: Pool<Postgres>) -> anyhow::Result<()> {
+    initialize_tracing().await;
+
+    let script_path = "f/test/pg_notify_e2e_handler";
+    insert_test_script(&db, script_path).await?;
+
+    // Channels are server-wide so use a random suffix.
+    let channel = format!("windmill_e2e_{}", rand::random::<u32>());
+    let other_channel = format!("{channel}_other");
+
+    let test_db_name: String = sqlx::query_scalar("SELECT current_database()")
+        .fetch_one(&db)
+        .await?;
+
+    insert_resource(
+        &db,
+        "u/test-user/pg_notify_res",
+        "postgresql",
+        json!({
+            "user": "postgres",
+            "password": "changeme",
+            "host": "localhost",
+            "port": 5432,
+            "dbname": test_db_name,
+            "sslmode": "disable"
+        }),
+    )
+    .await?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO postgres_notify_trigger (
+            path, script_path, is_flow, workspace_id, edited_by, permissioned_as,
+            postgres_resource_path, channels, parse_json
+        )
+        VALUES ($1, $2, false, 'test-workspace', 'test-user', 'u/test-user', $3, $4, true)
+        "#,
+    )
+    .bind("f/test/pg_notify_e2e_trigger")
+    .bind(script_path)
+    .bind("u/test-user/pg_notify_res")
+    .bind(vec![channel.clone()])
+    .execute(&db)
+    .await?;
+
+    let _server = ApiServer::start_with_listeners(db.clone()).await?;
+
+    // Notifications sent before the listener has issued LISTEN are lost, so keep
+    // notifying until the first job shows up.
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(30);
+    loop {
+        sqlx::query("SELECT pg_notify($1, $2)")
+            .bind(&other_channel)
+            .bind(r#"{"id": 0}"#)
+            .execute(&db)
+            .await?;
+        sqlx::query("SELECT pg_notify($1, $2)")
+            .bind(&channel)
+            .bind(r#"{"id": 1}"#)
+            .execute(&db)
+            .await?;
+        if !trigger_job_args(&db, script_path, "postgres_notify")
+            .await?
+            .is_empty()
+        {
+            break;
+        }
+        if tokio::time::Instant::now() >= deadline {
+            anyhow::bail!("timed out waiting for the postgres_notify trigger to listen");
+        }
+        tokio::time::sleep(Duration::from_secs(1)).await;
+    }
+
+    let args = trigger_job_args(&db, script_path, "postgres_notify").await?;
+    assert!(
+        args.iter().all(|args| args["payload"] == json!({"id": 1})),
+        "only the listened channel should start jobs: {args:?}"
+    );
+
+    Ok(())
+}
+
 // ============================================================================
 // Kafka Trigger E2E (Enterprise)
 // ============================================================================
@@ -894,3 +1337,479 @@ async fn test_gcp_e2e(db: Pool<Postgres>) -> anyhow::Result<()> {
 
     Ok(())
 }
+
+// ============================================================================
+// Redis Streams Trigger E2E
+// ============================================================================
+
+/// End-to-end test for the Redis Streams trigger: a new entry and an entry left pending
+/// on a consumer that died both start a job and are acknowledged.
+///
+/// Requires a running Redis server (5.0 or later, 6.2 or later for XAUTOCLAIM). Setup:
+/// ```bash
+/// docker run --rm -d -p 6379:6379 --name windmill-e2e-redis redis:7
+/// ```
+///
+/// Run:
+/// ```bash
+/// cargo test --test trigger_e2e test_redis_e2e --features redis_trigger \
+///     -- --ignored --nocapture
+/// ```
+#[ignore = "requires running Redis server on localhost:6379"]
+#[sqlx::test(migrations = "../migrations", fixtures("base"))]
+async fn test_redis_e2e(db: Pool<Postgres>) -> anyhow::Result<()> {
+    use redis::{
+        streams::{StreamPendingReply, StreamReadOptions, StreamReadReply},
+        AsyncCommands,
+    };
+
+    initialize_tracing().await;
+
+    let script_path = "f/test/redis_e2e_handler";
+    insert_test_script(&db, script_path).await?;
+
+    insert_resource(
+        &db,
+        "u/test-user/redis_res",
+        "redis",
+        json!({
+            "host": "localhost",
+            "port": 6379
+        }),
+    )
+    .await?;
+
+    // Streams are server-wide so use a random suffix.
+    let stream = format!("windmill-e2e-{}", rand::random::<u32>());
+    let group = "windmill-e2e-group";
+
+    let client = redis::Client::open("redis://localhost:6379/0")?;
+    let mut con = client.get_multiplexed_async_connection().await?;
+
+    // An entry delivered to a consumer that never acknowledges it: the trigger has to take
+    // it over with XAUTOCLAIM.
+    let _: () = con.xgroup_create_mkstream(&stream, group, "0").await?;
+    let _: String = con.xadd(&stream, "*", &[("marker", "stranded")]).await?;
+    let delivered: StreamReadReply = con
+        .xread_options(
+            &[&stream],
+            &[">"],
+            &StreamReadOptions::default()
+                .group(group, "dead-consumer")
+                .count(1),
+        )
+        .await?;
+    assert_eq!(
+        delivered.keys.len(),
+        1,
+        "the dead consumer should own an entry"
+    );
+
+    sqlx::query(
+        r#"
+        INSERT INTO redis_trigger (
+            path, redis_resource_path, streams, consumer_group, options,
+            script_path, is_flow, workspace_id, edited_by, permissioned_as
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+        "#,
+    )
+    .bind("f/test/redis_e2e_trigger")
+    .bind("u/test-user/redis_res")
+    .bind(&[stream.as_str()] as &[&str])
+    .bind(group)
+    .bind(json!({ "claim_min_idle_ms": 100 }))
+    .bind(script_path)
+    .bind(false)
+    .bind("test-workspace")
+    .bind("test-user")
+    .bind("u/test-user")
+    .execute(&db)
+    .await?;
+
+    let _server = ApiServer::start_with_listeners(db.clone()).await?;
+    tokio::time::sleep(Duration::from_secs(3)).await;
+
+    let _: String = con.xadd(&stream, "*", &[("marker", "fresh")]).await?;
+
+    let args = wait_for_trigger_jobs(&db, script_path, "redis", 2, Duration::from_secs(30)).await?;
+    for marker in ["stranded", "fresh"] {
+        assert!(
+            args.iter().any(|args| args.to_string().contains(marker)),
+            "a job should have been started for the {} entry, got {:?}",
+            marker,
+            args
+        );
+    }
+
+    // Both entries are acknowledged once their job is pushed.
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(10);
+    loop {
+        let pending: StreamPendingReply = con.xpending(&stream, group).await?;
+        if pending.count() == 0 {
+            break;
+        }
+        if tokio::time::Instant::now() >= deadline {
+            anyhow::bail!("{} entries are still pending in the group", pending.count());
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+
+    tokio::time::sleep(Duration::from_secs(2)).await;
+    assert_eq!(
+        trigger_job_args(&db, script_path, "redis").await?.len(),
+        2,
+        "no entry should start a second job"
+    );
+
+    let _: () = con.del(&stream).await?;
+
+    Ok(())
+}
+
+// ============================================================================
+// Object Trigger E2E
+// ============================================================================
+
+/// Where the listing of the object trigger at `trigger_path` resumes from.
+async fn object_trigger_watermark(
+    db: &Pool<Postgres>,
+    trigger_path: &str,
+) -> anyhow::Result<Option<sqlx::types::chrono::DateTime<sqlx::types::chrono::Utc>>> {
+    let watermark = sqlx::query_scalar(
+        "SELECT watermark FROM object_trigger WHERE workspace_id = 'test-workspace' AND path = $1",
+    )
+    .bind(trigger_path)
+    .fetch_one(db)
+    .await?;
+    Ok(watermark)
+}
+
+/// End-to-end test for the object trigger: an object put under the prefix starts exactly
+/// one job, and the saved watermark keeps it from starting another after a restart.
+///
+/// Watches a local directory through a filesystem workspace storage, which goes through
+/// the same listing as an S3-compatible bucket, so no service is needed. It waits on
+/// several poll intervals of at least 10s.
+///
+/// Run:
+/// ```bash
+/// cargo test --test trigger_e2e test_object_e2e --features object_trigger \
+///     -- --ignored --nocapture
+/// ```
+#[ignore = "slow: waits on several poll intervals of the object trigger"]
+#[sqlx::test(migrations = "../migrations", fixtures("base"))]
+async fn test_object_e2e(db: Pool<Postgres>) -> anyhow::Result<()> {
+    initialize_tracing().await;
+
+    let script_path = "f/test/object_e2e_handler";
+    let trigger_path = "f/test/object_e2e_trigger";
+    insert_test_script(&db, script_path).await?;
+
+    let root = use_filesystem_storage(&db, "windmill_object_e2e").await?;
+    std::fs::create_dir_all(root.join("incoming"))?;
+    // Already there when the trigger starts: recorded by the first listing, never a job.
+    std::fs::write(root.join("incoming/existing.csv"), "a,b\n")?;
+
+    sqlx::query(
+        r#"
+        INSERT INTO object_trigger (
+            path, prefix, poll_interval_secs,
+            script_path, is_flow, workspace_id, edited_by, permissioned_as
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        "#,
+    )
+    .bind(trigger_path)
+    .bind("incoming/")
+    .bind(10)
+    .bind(script_path)
+    .bind(false)
+    .bind("test-workspace")
+    .bind("test-user")
+    .bind("u/test-user")
+    .execute(&db)
+    .await?;
+
+    let server = ApiServer::start_with_listeners(db.clone()).await?;
+
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(30);
+    while object_trigger_watermark(&db, trigger_path).await?.is_none() {
+        if tokio::time::Instant::now() >= deadline {
+            anyhow::bail!("the first listing never recorded a watermark");
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+
+    std::fs::write(root.join("incoming/report.csv"), "c,d\n")?;
+
+    let job = poll_for_trigger_job(&db, script_path, "object", Duration::from_secs(40)).await?;
+    let args = job.args.expect("job should have args").0;
+    assert!(
+        args.to_string().contains("incoming/report.csv"),
+        "the job should be for the new object, got {}",
+        args
+    );
+
+    // Listed again on the next polls, but already handled.
+    tokio::time::sleep(Duration::from_secs(15)).await;
+    assert_eq!(
+        trigger_job_args(&db, script_path, "object").await?.len(),
+        1,
+        "the object should start a single job"
+    );
+
+    let watermark_before_restart = object_trigger_watermark(&db, trigger_path).await?;
+    server.close().await?;
+
+    // Released as a stopped server would have, so the restarted one picks it up at once.
+    sqlx::query(
+        "UPDATE object_trigger SET server_id = NULL, last_server_ping = NULL
+         WHERE workspace_id = 'test-workspace' AND path = $1",
+    )
+    .bind(trigger_path)
+    .execute(&db)
+    .await?;
+
+    let _server = ApiServer::start_with_listeners(db.clone()).await?;
+
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(30);
+    loop {
+        let listening: bool = sqlx::query_scalar(
+            "SELECT last_server_ping IS NOT NULL FROM object_trigger
+             WHERE workspace_id = 'test-workspace' AND path = $1",
+        )
+        .bind(trigger_path)
+        .fetch_one(&db)
+        .await?;
+        if listening {
+            break;
+        }
+        if tokio::time::Instant::now() >= deadline {
+            anyhow::bail!("the restarted server never picked up the trigger");
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+
+    tokio::time::sleep(Duration::from_secs(15)).await;
+    assert_eq!(
+        trigger_job_args(&db, script_path, "object").await?.len(),
+        1,
+        "the restarted trigger should resume from its watermark"
+    );
+    assert!(
+        object_trigger_watermark(&db, trigger_path).await? >= watermark_before_restart,
+        "the watermark should not go back"
+    );
+
+    std::fs::remove_dir_all(&root).ok();
+
+    Ok(())
+}
+
+// ============================================================================
+// SFTP Trigger E2E
+// ============================================================================
+
+/// Host directory mounted as `/home/windmill/upload` in the SFTP container below.
+const SFTP_E2E_MOUNT: &str = "/tmp/windmill_sftp_e2e";
+
+/// Files the SFTP trigger at `trigger_path` started jobs for, with the ids of those jobs.
+async fn sftp_running_files(
+    db: &Pool<Postgres>,
+    trigger_path: &str,
+) -> anyhow::Result<Vec<(String, Vec<uuid::Uuid>)>> {
+    let files = sqlx::query_as(
+        "SELECT remote_path, job_ids FROM sftp_trigger_file
+         WHERE workspace_id = 'test-workspace' AND trigger_path = $1 AND state = 'running'",
+    )
+    .bind(trigger_path)
+    .fetch_all(db)
+    .await?;
+    Ok(files)
+}
+
+/// Completes the job as a worker would have, with `status` `success` or `failure`.
+async fn complete_trigger_job(
+    db: &Pool<Postgres>,
+    id: uuid::Uuid,
+    status: &str,
+) -> anyhow::Result<()> {
+    sqlx::query("DELETE FROM v2_job_queue WHERE id = $1")
+        .bind(id)
+        .execute(db)
+        .await?;
+    sqlx::query(
+        "INSERT INTO v2_job_completed (id, workspace_id, duration_ms, result, status)
+         VALUES ($1, 'test-workspace', 100, '42'::jsonb, $2::job_status)",
+    )
+    .bind(id)
+    .bind(status)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+/// End-to-end test for the SFTP trigger: an uploaded file starts a job once it is no
+/// longer being written to, and is moved to `processed/` once its job succeeded, or
+/// deleted once it failed.
+///
+/// Requires a local SFTP server whose upload directory is mounted from the host, so that
+/// the test puts and inspects files directly. Setup:
+/// ```bash
+/// mkdir -p /tmp/windmill_sftp_e2e && chmod 777 /tmp/windmill_sftp_e2e
+/// docker run --rm -d -p 2222:22 --name windmill-e2e-sftp \
+///     -v /tmp/windmill_sftp_e2e:/home/windmill/upload atmoz/sftp windmill:windmill:1001
+/// ```
+///
+/// Run:
+/// ```bash
+/// cargo test --test trigger_e2e test_sftp_e2e --features sftp_trigger \
+///     -- --ignored --nocapture
+/// ```
+#[ignore = "requires local sftp server"]
+#[sqlx::test(migrations = "../migrations", fixtures("base"))]
+async fn test_sftp_e2e(db: Pool<Postgres>) -> anyhow::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    initialize_tracing().await;
+
+    let script_path = "f/test/sftp_e2e_handler";
+    let trigger_path = "f/test/sftp_e2e_trigger";
+    insert_test_script(&db, script_path).await?;
+
+    let storage_root = use_filesystem_storage(&db, "windmill_sftp_e2e_storage").await?;
+
+    insert_resource(
+        &db,
+        "u/test-user/sftp_res",
+        "sftp",
+        json!({
+            "host": "localhost",
+            "port": 2222,
+            "user": "windmill",
+            "password": "windmill"
+        }),
+    )
+    .await?;
+
+    // The mount is shared between runs so use a random directory, writable by the user
+    // of the container.
+    let run = format!("run_{}", rand::random::<u32>());
+    let local = std::path::Path::new(SFTP_E2E_MOUNT).join(&run);
+    std::fs::create_dir_all(&local)?;
+    std::fs::set_permissions(&local, std::fs::Permissions::from_mode(0o777))?;
+    let directory = format!("/upload/{}", run);
+
+    sqlx::query(
+        r#"
+        INSERT INTO sftp_trigger (
+            path, sftp_resource_path, directory, poll_interval_secs, on_success, on_failure,
+            script_path, is_flow, workspace_id, edited_by, permissioned_as
+        )
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
+        "#,
+    )
+    .bind(trigger_path)
+    .bind("u/test-user/sftp_res")
+    .bind(&directory)
+    .bind(10)
+    .bind("move")
+    .bind("delete")
+    .bind(script_path)
+    .bind(false)
+    .bind("test-workspace")
+    .bind("test-user")
+    .bind("u/test-user")
+    .execute(&db)
+    .await?;
+
+    let _server = ApiServer::start_with_listeners(db.clone()).await?;
+
+    std::fs::write(local.join("orders.csv"), "id,amount\n1,10\n")?;
+    std::fs::write(local.join("broken.csv"), "not,a,valid\n")?;
+
+    // Dispatched once two listings agree on the file, so after at least one poll interval.
+    let args = wait_for_trigger_jobs(&db, script_path, "sftp", 2, Duration::from_secs(60)).await?;
+    for name in ["orders.csv", "broken.csv"] {
+        assert!(
+            args.iter().any(|args| args.to_string().contains(name)),
+            "a job should have been started for {}, got {:?}",
+            name,
+            args
+        );
+    }
+
+    let object_keys: Vec<String> = sqlx::query_scalar(
+        "SELECT object_key FROM sftp_trigger_file
+         WHERE workspace_id = 'test-workspace' AND trigger_path = $1 AND object_key IS NOT NULL",
+    )
+    .bind(trigger_path)
+    .fetch_all(&db)
+    .await?;
+    assert_eq!(object_keys.len(), 2, "both files should have been copied");
+    for key in &object_keys {
+        assert!(
+            storage_root.join(key).exists(),
+            "the copy {} should be in the workspace storage",
+            key
+        );
+    }
+
+    // Left in place while their jobs run.
+    tokio::time::sleep(Duration::from_secs(12)).await;
+    assert!(local.join("orders.csv").exists() && local.join("broken.csv").exists());
+
+    let files = sftp_running_files(&db, trigger_path).await?;
+    assert_eq!(
+        files.len(),
+        2,
+        "both files should be running, got {:?}",
+        files
+    );
+    for (remote_path, job_ids) in files {
+        let status = if remote_path.ends_with("orders.csv") {
+            "success"
+        } else {
+            "failure"
+        };
+        for id in job_ids {
+            complete_trigger_job(&db, id, status).await?;
+        }
+    }
+
+    let deadline = tokio::time::Instant::now() + Duration::from_secs(40);
+    loop {
+        let moved =
+            local.join("processed/orders.csv").exists() && !local.join("orders.csv").exists();
+        let deleted = !local.join("broken.csv").exists();
+        if moved && deleted {
+            break;
+        }
+        if tokio::time::Instant::now() >= deadline {
+            anyhow::bail!(
+                "the files were not post-processed (moved: {}, deleted: {})",
+                moved,
+                deleted
+            );
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+    assert!(
+        !local.join("failed").exists(),
+        "a failed file is deleted, not moved"
+    );
+
+    // Gone from the watched directory, so never dispatched again.
+    tokio::time::sleep(Duration::from_secs(12)).await;
+    assert_eq!(
+        trigger_job_args(&db, script_path, "sftp").await?.len(),
+        2,
+        "each file should start a single job"
+    );
+
+    std::fs::remove_dir_all(&local).ok();
+    std::fs::remove_dir_all(&storage_root).ok();
+
+    Ok(())
+}
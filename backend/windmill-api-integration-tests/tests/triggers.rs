@@ -655,7 +655,14 @@ async fn test_http_trigger_multiple_methods(db: Pool<Postgres>) -> anyhow::Resul
 
 #[sqlx::test(migrations = "../migrations", fixtures("base"))]
 async fn test_http_trigger_authentication_methods(db: Pool<Postgres>) -> anyhow::Result<()> {
-    let auth_methods = ["none", "windmill", "api_key", "basic_http", "signature"];
+    let auth_methods = [
+        "none",
+        "windmill",
+        "api_key",
+        "basic_http",
+        "signature",
+        "jwt",
+    ];
 
     for (i, auth) in auth_methods.iter().enumerate() {
         sqlx::query!(
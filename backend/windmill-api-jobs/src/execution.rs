@@ -713,27 +713,33 @@ pub async fn run_flow<'c>(
 
     let return_tx = tx_o.is_some();
 
-    let (email, permissioned_as, push_authed, tx) = if let Some(tx) = tx_o {
-        (
+    // A job pushed in the transaction of the caller runs on behalf of the same user as
+    // one pushed on its own.
+    let (email, permissioned_as, push_authed, tx) = match (tx_o, on_behalf_of.as_ref()) {
+        (Some(tx), Some(obo)) => (
+            &obo.email,
+            obo.permissioned_as.clone(),
+            None,
+            PushIsolationLevel::Transaction(tx),
+        ),
+        (Some(tx), None) => (
             &authed.email,
             username_to_permissioned_as(&authed.username),
             Some(authed.clone().into()),
             PushIsolationLevel::Transaction(tx),
-        )
-    } else if let Some(obo) = on_behalf_of.as_ref() {
-        (
+        ),
+        (None, Some(obo)) => (
             &obo.email,
             obo.permissioned_as.clone(),
             None,
             PushIsolationLevel::IsolatedRoot(db.clone()),
-        )
-    } else {
-        (
+        ),
+        (None, None) => (
             &authed.email,
             username_to_permissioned_as(&authed.username),
             Some(authed.clone().into()),
             PushIsolationLevel::Isolated(user_db.clone(), authed.clone().into()),
-        )
+        ),
     };
 
     let (uuid, mut tx) = push(
@@ -935,27 +941,33 @@ pub async fn push_script_job_by_path_into_queue<'c>(
 
     let return_tx = tx_o.is_some();
 
-    let (email, permissioned_as, push_authed, tx) = if let Some(tx) = tx_o {
-        (
+    // A job pushed in the transaction of the caller runs on behalf of the same user as
+    // one pushed on its own.
+    let (email, permissioned_as, push_authed, tx) = match (tx_o, on_behalf_of.as_ref()) {
+        (Some(tx), Some(on_behalf_of)) => (
+            on_behalf_of.email.as_str(),
+            on_behalf_of.permissioned_as.clone(),
+            None,
+            PushIsolationLevel::Transaction(tx),
+        ),
+        (Some(tx), None) => (
             authed.email.as_str(),
             username_to_permissioned_as(&authed.username),
             Some(authed.clone().into()),
             PushIsolationLevel::Transaction(tx),
-        )
-    } else if let Some(on_behalf_of) = on_behalf_of.as_ref() {
-        (
+        ),
+        (None, Some(on_behalf_of)) => (
             on_behalf_of.email.as_str(),
             on_behalf_of.permissioned_as.clone(),
             None,
             PushIsolationLevel::IsolatedRoot(db.clone()),
-        )
-    } else {
-        (
+        ),
+        (None, None) => (
             authed.email.as_str(),
             username_to_permissioned_as(&authed.username),
             Some(authed.clone().into()),
             PushIsolationLevel::Isolated(user_db, authed.clone().into()),
-        )
+        ),
     };
 
     let (uuid, tx) = push(
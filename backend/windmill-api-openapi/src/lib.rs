@@ -12,7 +12,7 @@ use indexmap::IndexMap;
 use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use serde_json::{to_value, Map, Value};
-use sqlx::PgConnection;
+use sqlx::{types::Json as SqlxJson, PgConnection};
 use url::Url;
 use windmill_common::{
     db::UserDB,
@@ -24,7 +24,11 @@ use windmill_store::resources::try_get_resource_from_db_as;
 
 use windmill_api_auth::ApiAuthed;
 use windmill_trigger_http::{
-    http_trigger_auth::ApiKeyAuthentication, AuthenticationMethod, HttpMethod, RequestType,
+    http_trigger_auth::ApiKeyAuthentication,
+    schema_validation::{
+        get_runnable_schema, BodySchema, HttpRequestValidation, HttpResponseMapping,
+    },
+    AuthenticationMethod, HttpMethod, RequestType,
 };
 
 lazy_static::lazy_static! {
@@ -44,6 +48,7 @@ const DEFAULT_ASYNC_RESPONSE_KEY: &'static str = "AsyncResponse";
 const DEFAULT_SYNC_RESPONSE_KEY: &'static str = "SyncResponse";
 const DEFAULT_SYNC_SSE_RESPONSE_KEY: &'static str = "SyncSseResponse";
 const DEFAULT_PAYLOAD_PARAM_KEY: &'static str = "PayloadParam";
+const REQUEST_VALIDATION_ERROR_KEY: &'static str = "RequestValidationError";
 
 pub fn openapi_service() -> Router {
     Router::new()
@@ -165,6 +170,8 @@ pub struct FuturePath {
     description: Option<String>,
     security_scheme: Option<SecurityScheme>,
     args_schema: Option<Value>,
+    request_validation: Option<HttpRequestValidation>,
+    response_mapping: Option<HttpResponseMapping>,
 }
 
 impl FuturePath {
@@ -176,6 +183,8 @@ impl FuturePath {
         description: Option<String>,
         security_scheme: Option<SecurityScheme>,
         args_schema: Option<Value>,
+        request_validation: Option<HttpRequestValidation>,
+        response_mapping: Option<HttpResponseMapping>,
     ) -> FuturePath {
         FuturePath {
             route_path,
@@ -185,6 +194,8 @@ impl FuturePath {
             description,
             security_scheme,
             args_schema,
+            request_validation,
+            response_mapping,
         }
     }
 }
@@ -275,6 +286,81 @@ fn get_servers_component(url: &str, kind: &Kind) -> Server {
     server
 }
 
+/// One parameter per property of a query or header validation schema.
+fn generate_validated_parameters(validation: &HttpRequestValidation) -> Vec<Value> {
+    let mut parameters = Vec::new();
+
+    for (location, schema) in [
+        ("query", &validation.query),
+        ("header", &validation.headers),
+    ] {
+        let Some(schema) = schema else {
+            continue;
+        };
+
+        let required = schema
+            .get("required")
+            .and_then(Value::as_array)
+            .map(|required| required.iter().filter_map(Value::as_str).collect_vec())
+            .unwrap_or_default();
+
+        if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+            for (name, property) in properties {
+                let mut parameter = serde_json::json!({
+                    "name": name,
+                    "in": location,
+                    "required": required.contains(&name.as_str()),
+                    "schema": property
+                });
+                if let Some(description) = property
+                    .get("description")
+                    .and_then(Value::as_str)
+                    .filter(|s| !s.is_empty())
+                {
+                    parameter["description"] = Value::String(description.to_owned());
+                }
+                parameters.push(parameter);
+            }
+        }
+    }
+
+    parameters
+}
+
+/// Responses of a sync route whose status and content type are mapped from the job result.
+/// Results no rule matches keep the default response.
+fn generate_mapped_responses(mapping: &HttpResponseMapping) -> Map<String, Value> {
+    let mut responses = Map::new();
+
+    for rule in &mapping.responses {
+        let response = responses.entry(rule.status.to_string()).or_insert_with(|| {
+            serde_json::json!({
+                "description": rule
+                    .description
+                    .clone()
+                    .unwrap_or_else(|| format!("Response with status {}", rule.status)),
+                "content": {}
+            })
+        });
+
+        let content_type = rule.content_type.as_deref().unwrap_or("application/json");
+        let media_type = match &rule.schema {
+            Some(schema) => serde_json::json!({ "schema": schema }),
+            None => serde_json::json!({}),
+        };
+        response["content"][content_type] = media_type;
+    }
+
+    responses.insert(
+        "default".to_owned(),
+        serde_json::json!({
+            "$ref": format!("#/components/responses/{DEFAULT_SYNC_RESPONSE_KEY}")
+        }),
+    );
+
+    responses
+}
+
 fn generate_paths(
     paths: Vec<FuturePath>,
     url: Option<&Url>,
@@ -431,7 +517,29 @@ fn generate_paths(
                     );
                 }
 
-                method_map.insert("responses", generate_response(request_type));
+                if let Some(validation) = &path.request_validation {
+                    let parameters = generate_validated_parameters(validation);
+                    if !parameters.is_empty() {
+                        method_map.insert("parameters", Value::Array(parameters));
+                    }
+                }
+
+                let mut responses = match &path.response_mapping {
+                    Some(mapping)
+                        if request_type == RequestType::Sync && !mapping.responses.is_empty() =>
+                    {
+                        Value::Object(generate_mapped_responses(mapping))
+                    }
+                    _ => generate_response(request_type),
+                };
+
+                if path.request_validation.is_some() {
+                    responses["400"] = serde_json::json!({
+                        "$ref": format!("#/components/responses/{REQUEST_VALIDATION_ERROR_KEY}")
+                    });
+                }
+
+                method_map.insert("responses", responses);
 
                 path_object.insert(method.to_string().to_lowercase(), to_value(&method_map)?);
             }
@@ -580,7 +688,7 @@ fn generate_components(future_paths: &[FuturePath]) -> Map<String, Value> {
         }
     }));
 
-    components.insert("responses".to_owned(), serde_json::json!({
+    let mut responses = serde_json::json!({
         DEFAULT_ASYNC_RESPONSE_KEY: {
             "description": "Returns a job ID as a UUID string.",
             "content": {
@@ -610,7 +718,40 @@ fn generate_components(future_paths: &[FuturePath]) -> Map<String, Value> {
                 }
             }
         }
-    }));
+    });
+
+    if future_paths
+        .iter()
+        .any(|path| path.request_validation.is_some())
+    {
+        responses[REQUEST_VALIDATION_ERROR_KEY] = serde_json::json!({
+            "description": "The request body, query parameters or headers do not match the route schema.",
+            "content": {
+                "application/json": {
+                    "schema": {
+                        "type": "object",
+                        "properties": {
+                            "error": { "type": "string", "const": "request_validation_failed" },
+                            "errors": {
+                                "type": "array",
+                                "items": {
+                                    "type": "object",
+                                    "properties": {
+                                        "location": { "type": "string", "enum": ["body", "query", "headers"] },
+                                        "message": { "type": "string" }
+                                    },
+                                    "required": ["location", "message"]
+                                }
+                            }
+                        },
+                        "required": ["error", "errors"]
+                    }
+                }
+            }
+        });
+    }
+
+    components.insert("responses".to_owned(), responses);
 
     components
 }
@@ -689,66 +830,6 @@ fn clean_schema_for_openapi(schema: Value) -> Value {
     }
 }
 
-async fn get_runnable_schema(
-    db: &DB,
-    w_id: &str,
-    script_path: &str,
-    is_flow: bool,
-) -> Option<Value> {
-    if is_flow {
-        let row = sqlx::query!(
-            r#"SELECT
-                f.schema AS "schema: serde_json::Value",
-                fv.value->>'preprocessor_module' IS NOT NULL AS "has_preprocessor: bool"
-            FROM flow f
-            LEFT JOIN flow_version fv ON fv.id = f.versions[array_length(f.versions, 1)]
-                AND fv.workspace_id = f.workspace_id
-            WHERE f.path = $1 AND f.workspace_id = $2 AND NOT f.archived"#,
-            script_path,
-            w_id,
-        )
-        .fetch_optional(db)
-        .await
-        .map_err(|e| {
-            tracing::warn!("Failed to fetch flow schema for {script_path}: {e}");
-            e
-        })
-        .ok()
-        .flatten()?;
-
-        if row.has_preprocessor.unwrap_or(false) {
-            return None;
-        }
-        row.schema.map(clean_schema_for_openapi)
-    } else {
-        let row = sqlx::query!(
-            r#"SELECT
-                schema AS "schema: serde_json::Value",
-                has_preprocessor
-            FROM script
-            WHERE path = $1 AND workspace_id = $2
-                AND NOT archived AND NOT deleted
-            ORDER BY created_at DESC
-            LIMIT 1"#,
-            script_path,
-            w_id,
-        )
-        .fetch_optional(db)
-        .await
-        .map_err(|e| {
-            tracing::warn!("Failed to fetch script schema for {script_path}: {e}");
-            e
-        })
-        .ok()
-        .flatten()?;
-
-        if row.has_preprocessor.unwrap_or(false) {
-            return None;
-        }
-        row.schema.map(clean_schema_for_openapi)
-    }
-}
-
 async fn http_routes_to_future_paths(
     db: &DB,
     user_db: UserDB,
@@ -788,6 +869,8 @@ async fn http_routes_to_future_paths(
             script_path: String,
             is_flow: bool,
             wrap_body: bool,
+            request_validation: Option<SqlxJson<HttpRequestValidation>>,
+            response_mapping: Option<SqlxJson<HttpResponseMapping>>,
         }
 
         http_routes = sqlx::query_as!(
@@ -804,7 +887,9 @@ async fn http_routes_to_future_paths(
             authentication_resource_path,
             script_path,
             is_flow,
-            wrap_body
+            wrap_body,
+            request_validation AS "request_validation: _",
+            response_mapping AS "response_mapping: _"
         FROM
             http_trigger
         WHERE
@@ -864,10 +949,16 @@ async fn http_routes_to_future_paths(
             HttpMethod::Delete => Method::DELETE,
         };
 
-        let args_schema = if !http_route.wrap_body {
-            get_runnable_schema(db, w_id, &http_route.script_path, http_route.is_flow).await
-        } else {
-            None
+        let request_validation = http_route.request_validation.map(|v| v.0);
+
+        let args_schema = match request_validation.as_ref().and_then(|v| v.body.as_ref()) {
+            Some(BodySchema::Custom { schema }) => Some(clean_schema_for_openapi(schema.clone())),
+            _ if !http_route.wrap_body => {
+                get_runnable_schema(db, w_id, &http_route.script_path, http_route.is_flow)
+                    .await
+                    .map(clean_schema_for_openapi)
+            }
+            _ => None,
         };
 
         let future_path = FuturePath::new(
@@ -878,6 +969,8 @@ async fn http_routes_to_future_paths(
             http_route.description,
             auth_method,
             args_schema,
+            request_validation,
+            http_route.response_mapping.map(|m| m.0),
         );
 
         openapi_future_paths.push(future_path);
@@ -961,7 +1054,9 @@ async fn webhook_to_future_paths(
         openapi_future_paths.reserve_exact(webhook_scripts.len() + webhook_flows.len());
 
         for webhook in webhook_scripts {
-            let args_schema = get_runnable_schema(db, w_id, &webhook.path, false).await;
+            let args_schema = get_runnable_schema(db, w_id, &webhook.path, false)
+                .await
+                .map(clean_schema_for_openapi);
             openapi_future_paths.push(FuturePath::new(
                 webhook.path,
                 Kind::Webhook(WebhookConfig::new(RunnableKind::Script)),
@@ -970,11 +1065,15 @@ async fn webhook_to_future_paths(
                 webhook.description,
                 Some(SecurityScheme::BearerJwt),
                 args_schema,
+                None,
+                None,
             ));
         }
 
         for webhook in webhook_flows {
-            let args_schema = get_runnable_schema(db, w_id, &webhook.path, true).await;
+            let args_schema = get_runnable_schema(db, w_id, &webhook.path, true)
+                .await
+                .map(clean_schema_for_openapi);
             openapi_future_paths.push(FuturePath::new(
                 webhook.path,
                 Kind::Webhook(WebhookConfig::new(RunnableKind::Flow)),
@@ -983,6 +1082,8 @@ async fn webhook_to_future_paths(
                 webhook.description,
                 Some(SecurityScheme::BearerJwt),
                 args_schema,
+                None,
+                None,
             ));
         }
     }
@@ -1102,6 +1102,9 @@ async fn create_script_internal<'c>(
     ns.concurrency_settings = ns.concurrency_settings.normalized();
 
     guard_script_from_debounce_data(&ns).await?;
+    if let Some(lock) = ns.lock.as_deref() {
+        windmill_common::vulnerabilities::check_deployment(&db, &w_id, &ns.language, lock).await?;
+    }
 
     let codebase = ns.codebase.as_ref();
     #[cfg(not(feature = "enterprise"))]
@@ -1423,7 +1426,9 @@ async fn create_script_internal<'c>(
             || ns.language == ScriptLang::Nu
             || ns.language == ScriptLang::Php
             || ns.language == ScriptLang::Java
+            || ns.language == ScriptLang::Kotlin
             || ns.language == ScriptLang::Ruby
+            || ns.language == ScriptLang::Perl
             || ns.language == ScriptLang::Rlang
             || ns.language == ScriptLang::Powershell
             || ns.language == ScriptLang::Dbt
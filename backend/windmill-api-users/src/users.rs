@@ -1846,6 +1846,12 @@ async fn change_user_email(
                 UNION ALL SELECT 1 FROM gcp_trigger WHERE permissioned_as = $1
                 UNION ALL SELECT 1 FROM email_trigger WHERE permissioned_as = $1
                 UNION ALL SELECT 1 FROM amqp_trigger WHERE permissioned_as = $1
+                UNION ALL SELECT 1 FROM postgres_notify_trigger WHERE permissioned_as = $1
+                UNION ALL SELECT 1 FROM mysql_trigger WHERE permissioned_as = $1
+                UNION ALL SELECT 1 FROM redis_trigger WHERE permissioned_as = $1
+                UNION ALL SELECT 1 FROM object_trigger WHERE permissioned_as = $1
+                UNION ALL SELECT 1 FROM sftp_trigger WHERE permissioned_as = $1
+                UNION ALL SELECT 1 FROM grpc_trigger WHERE permissioned_as = $1
                 UNION ALL SELECT 1 FROM azure_trigger WHERE permissioned_as = $1
                 UNION ALL SELECT 1 FROM folder
                     WHERE default_permissioned_as @> jsonb_build_array(
@@ -2227,6 +2233,54 @@ async fn change_user_email(
     .execute(&mut *tx)
     .await?;
 
+    sqlx::query!(
+        "UPDATE postgres_notify_trigger SET permissioned_as = $1 WHERE permissioned_as = $2",
+        &new_principal,
+        &old_principal
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query!(
+        "UPDATE mysql_trigger SET permissioned_as = $1 WHERE permissioned_as = $2",
+        &new_principal,
+        &old_principal
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query!(
+        "UPDATE redis_trigger SET permissioned_as = $1 WHERE permissioned_as = $2",
+        &new_principal,
+        &old_principal
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query!(
+        "UPDATE object_trigger SET permissioned_as = $1 WHERE permissioned_as = $2",
+        &new_principal,
+        &old_principal
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query!(
+        "UPDATE sftp_trigger SET permissioned_as = $1 WHERE permissioned_as = $2",
+        &new_principal,
+        &old_principal
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query!(
+        "UPDATE grpc_trigger SET permissioned_as = $1 WHERE permissioned_as = $2",
+        &new_principal,
+        &old_principal
+    )
+    .execute(&mut *tx)
+    .await?;
+
     sqlx::query!(
         "UPDATE azure_trigger SET permissioned_as = $1 WHERE permissioned_as = $2",
         &new_principal,
@@ -2410,6 +2464,12 @@ pub async fn delete_workspace_user_internal(
         "postgres_trigger",
         "mqtt_trigger",
         "amqp_trigger",
+        "postgres_notify_trigger",
+        "mysql_trigger",
+        "redis_trigger",
+        "object_trigger",
+        "sftp_trigger",
+        "grpc_trigger",
         "nats_trigger",
         "sqs_trigger",
         "gcp_trigger",
@@ -4918,6 +4918,12 @@ struct UsedTriggers {
     pub postgres_used: bool,
     pub mqtt_used: bool,
     pub amqp_used: bool,
+    pub postgres_notify_used: bool,
+    pub mysql_used: bool,
+    pub redis_used: bool,
+    pub object_used: bool,
+    pub sftp_used: bool,
+    pub grpc_used: bool,
     pub sqs_used: bool,
     pub gcp_used: bool,
     pub azure_used: bool,
@@ -4944,6 +4950,12 @@ async fn get_used_triggers(
             EXISTS(SELECT 1 FROM postgres_trigger WHERE workspace_id = $1) AS "postgres_used!",
             EXISTS(SELECT 1 FROM mqtt_trigger WHERE workspace_id = $1) AS "mqtt_used!",
             EXISTS(SELECT 1 FROM amqp_trigger WHERE workspace_id = $1) AS "amqp_used!",
+            EXISTS(SELECT 1 FROM postgres_notify_trigger WHERE workspace_id = $1) AS "postgres_notify_used!",
+            EXISTS(SELECT 1 FROM mysql_trigger WHERE workspace_id = $1) AS "mysql_used!",
+            EXISTS(SELECT 1 FROM redis_trigger WHERE workspace_id = $1) AS "redis_used!",
+            EXISTS(SELECT 1 FROM object_trigger WHERE workspace_id = $1) AS "object_used!",
+            EXISTS(SELECT 1 FROM sftp_trigger WHERE workspace_id = $1) AS "sftp_used!",
+            EXISTS(SELECT 1 FROM grpc_trigger WHERE workspace_id = $1) AS "grpc_used!",
             EXISTS(SELECT 1 FROM sqs_trigger WHERE workspace_id = $1) AS "sqs_used!",
             EXISTS(SELECT 1 FROM gcp_trigger WHERE workspace_id = $1) AS "gcp_used!",
             EXISTS(SELECT 1 FROM azure_trigger WHERE workspace_id = $1) AS "azure_used!",
@@ -5566,7 +5578,8 @@ async fn clone_triggers_and_schedules(
             static_asset_config, is_static_website, workspaced_route, wrap_body,
             raw_string, authentication_resource_path, summary, description,
             error_handler_path, error_handler_args, retry, request_type, mode,
-            permissioned_as, labels
+            permissioned_as, labels, payload_transform, rate_limit, request_validation,
+            response_mapping, upload_config
         )
         SELECT
             path, route_path, route_path_key, script_path, is_flow, $1,
@@ -5574,7 +5587,8 @@ async fn clone_triggers_and_schedules(
             static_asset_config, is_static_website, workspaced_route, wrap_body,
             raw_string, authentication_resource_path, summary, description,
             error_handler_path, error_handler_args, retry, request_type, 'disabled'::TRIGGER_MODE,
-            permissioned_as, labels
+            permissioned_as, labels, payload_transform, rate_limit, request_validation,
+            response_mapping, upload_config
         FROM http_trigger
         WHERE workspace_id = $2
             AND (workspaced_route IS TRUE OR $3)"#,
@@ -5591,14 +5605,14 @@ async fn clone_triggers_and_schedules(
             extra_perms, server_id, last_server_ping, error, filters, initial_messages,
             url_runnable_args, can_return_message, error_handler_path, error_handler_args,
             retry, can_return_error_result, mode, permissioned_as, filter_logic, labels,
-            heartbeat
+            heartbeat, payload_transform
         )
         SELECT
             path, url, script_path, is_flow, $1, edited_by, edited_at,
             extra_perms, NULL, NULL, NULL, filters, initial_messages,
             url_runnable_args, can_return_message, error_handler_path, error_handler_args,
             retry, can_return_error_result, 'disabled'::TRIGGER_MODE, permissioned_as, filter_logic, labels,
-            heartbeat
+            heartbeat, payload_transform
         FROM websocket_trigger WHERE workspace_id = $2"#,
         target_workspace_id,
         source_workspace_id,
@@ -5612,14 +5626,14 @@ async fn clone_triggers_and_schedules(
             workspace_id, edited_by, edited_at, extra_perms, server_id,
             last_server_ping, error, error_handler_path, error_handler_args, retry,
             mode, filters, auto_offset_reset, reset_offset, auto_commit,
-            permissioned_as, filter_logic, labels
+            permissioned_as, filter_logic, labels, payload_transform
         )
         SELECT
             path, kafka_resource_path, topics, group_id, script_path, is_flow,
             $1, edited_by, edited_at, extra_perms, NULL,
             NULL, NULL, error_handler_path, error_handler_args, retry,
             'disabled'::TRIGGER_MODE, filters, auto_offset_reset, reset_offset, auto_commit,
-            permissioned_as, filter_logic, labels
+            permissioned_as, filter_logic, labels, payload_transform
         FROM kafka_trigger WHERE workspace_id = $2"#,
         target_workspace_id,
         source_workspace_id,
@@ -5632,13 +5646,13 @@ async fn clone_triggers_and_schedules(
             path, nats_resource_path, subjects, stream_name, consumer_name,
             use_jetstream, script_path, is_flow, workspace_id, edited_by, edited_at,
             extra_perms, server_id, last_server_ping, error, error_handler_path,
-            error_handler_args, retry, mode, permissioned_as, labels
+            error_handler_args, retry, mode, permissioned_as, labels, payload_transform
         )
         SELECT
             path, nats_resource_path, subjects, stream_name, consumer_name,
             use_jetstream, script_path, is_flow, $1, edited_by, edited_at,
             extra_perms, NULL, NULL, NULL, error_handler_path,
-            error_handler_args, retry, 'disabled'::TRIGGER_MODE, permissioned_as, labels
+            error_handler_args, retry, 'disabled'::TRIGGER_MODE, permissioned_as, labels, payload_transform
         FROM nats_trigger WHERE workspace_id = $2"#,
         target_workspace_id,
         source_workspace_id,
@@ -5651,13 +5665,13 @@ async fn clone_triggers_and_schedules(
             path, script_path, is_flow, workspace_id, edited_by, edited_at,
             extra_perms, postgres_resource_path, error, server_id, last_server_ping,
             replication_slot_name, publication_name, error_handler_path,
-            error_handler_args, retry, mode, permissioned_as, labels
+            error_handler_args, retry, mode, permissioned_as, labels, payload_transform
         )
         SELECT
             path, script_path, is_flow, $1, edited_by, edited_at,
             extra_perms, postgres_resource_path, NULL, NULL, NULL,
             replication_slot_name, publication_name, error_handler_path,
-            error_handler_args, retry, 'disabled'::TRIGGER_MODE, permissioned_as, labels
+            error_handler_args, retry, 'disabled'::TRIGGER_MODE, permissioned_as, labels, payload_transform
         FROM postgres_trigger WHERE workspace_id = $2"#,
         target_workspace_id,
         source_workspace_id,
@@ -5670,13 +5684,13 @@ async fn clone_triggers_and_schedules(
             mqtt_resource_path, subscribe_topics, client_version, v5_config, v3_config,
             client_id, path, script_path, is_flow, workspace_id, edited_by, edited_at,
             extra_perms, server_id, last_server_ping, error, error_handler_path,
-            error_handler_args, retry, mode, permissioned_as, labels
+            error_handler_args, retry, mode, permissioned_as, labels, payload_transform
         )
         SELECT
             mqtt_resource_path, subscribe_topics, client_version, v5_config, v3_config,
             client_id, path, script_path, is_flow, $1, edited_by, edited_at,
             extra_perms, NULL, NULL, NULL, error_handler_path,
-            error_handler_args, retry, 'disabled'::TRIGGER_MODE, permissioned_as, labels
+            error_handler_args, retry, 'disabled'::TRIGGER_MODE, permissioned_as, labels, payload_transform
         FROM mqtt_trigger WHERE workspace_id = $2"#,
         target_workspace_id,
         source_workspace_id,
@@ -5688,12 +5702,12 @@ async fn clone_triggers_and_schedules(
         r#"INSERT INTO amqp_trigger (
             amqp_resource_path, queue_name, exchange, options, path, script_path, is_flow,
             workspace_id, edited_by, edited_at, extra_perms, server_id, last_server_ping,
-            error, error_handler_path, error_handler_args, retry, mode, permissioned_as, labels
+            error, error_handler_path, error_handler_args, retry, mode, permissioned_as, labels, payload_transform
         )
         SELECT
             amqp_resource_path, queue_name, exchange, options, path, script_path, is_flow,
             $1, edited_by, edited_at, extra_perms, NULL, NULL,
-            NULL, error_handler_path, error_handler_args, retry, 'disabled'::TRIGGER_MODE, permissioned_as, labels
+            NULL, error_handler_path, error_handler_args, retry, 'disabled'::TRIGGER_MODE, permissioned_as, labels, payload_transform
         FROM amqp_trigger WHERE workspace_id = $2"#,
         target_workspace_id,
         source_workspace_id,
@@ -5701,18 +5715,122 @@ async fn clone_triggers_and_schedules(
     .execute(&mut **tx)
     .await?;
 
+    sqlx::query!(
+        r#"INSERT INTO postgres_notify_trigger (
+            postgres_resource_path, channels, parse_json, path, script_path, is_flow,
+            workspace_id, edited_by, edited_at, extra_perms, server_id, last_server_ping,
+            error, error_handler_path, error_handler_args, retry, mode, permissioned_as, labels, payload_transform
+        )
+        SELECT
+            postgres_resource_path, channels, parse_json, path, script_path, is_flow,
+            $1, edited_by, edited_at, extra_perms, NULL, NULL,
+            NULL, error_handler_path, error_handler_args, retry, 'disabled'::TRIGGER_MODE, permissioned_as, labels, payload_transform
+        FROM postgres_notify_trigger WHERE workspace_id = $2"#,
+        target_workspace_id,
+        source_workspace_id,
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    sqlx::query!(
+        r#"INSERT INTO mysql_trigger (
+            mysql_resource_path, tables_to_track, transaction_to_track, path, script_path, is_flow,
+            workspace_id, edited_by, edited_at, extra_perms, server_id, last_server_ping,
+            error, error_handler_path, error_handler_args, retry, mode, permissioned_as, labels, payload_transform
+        )
+        SELECT
+            mysql_resource_path, tables_to_track, transaction_to_track, path, script_path, is_flow,
+            $1, edited_by, edited_at, extra_perms, NULL, NULL,
+            NULL, error_handler_path, error_handler_args, retry, 'disabled'::TRIGGER_MODE, permissioned_as, labels, payload_transform
+        FROM mysql_trigger WHERE workspace_id = $2"#,
+        target_workspace_id,
+        source_workspace_id,
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    sqlx::query!(
+        r#"INSERT INTO redis_trigger (
+            redis_resource_path, streams, consumer_group, options, filters, filter_logic, path, script_path, is_flow,
+            workspace_id, edited_by, edited_at, extra_perms, server_id, last_server_ping,
+            error, error_handler_path, error_handler_args, retry, mode, permissioned_as, labels, payload_transform
+        )
+        SELECT
+            redis_resource_path, streams, consumer_group, options, filters, filter_logic, path, script_path, is_flow,
+            $1, edited_by, edited_at, extra_perms, NULL, NULL,
+            NULL, error_handler_path, error_handler_args, retry, 'disabled'::TRIGGER_MODE, permissioned_as, labels, payload_transform
+        FROM redis_trigger WHERE workspace_id = $2"#,
+        target_workspace_id,
+        source_workspace_id,
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    sqlx::query!(
+        r#"INSERT INTO object_trigger (
+            storage, prefix, delivery, poll_interval_secs, watermark, path, script_path, is_flow,
+            workspace_id, edited_by, edited_at, extra_perms, server_id, last_server_ping,
+            error, error_handler_path, error_handler_args, retry, mode, permissioned_as, labels, payload_transform
+        )
+        SELECT
+            storage, prefix, delivery, poll_interval_secs, watermark, path, script_path, is_flow,
+            $1, edited_by, edited_at, extra_perms, NULL, NULL,
+            NULL, error_handler_path, error_handler_args, retry, 'disabled'::TRIGGER_MODE, permissioned_as, labels, payload_transform
+        FROM object_trigger WHERE workspace_id = $2"#,
+        target_workspace_id,
+        source_workspace_id,
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    sqlx::query!(
+        r#"INSERT INTO sftp_trigger (
+            sftp_resource_path, protocol, directory, file_pattern, poll_interval_secs, on_success, on_failure,
+            processed_directory, failed_directory, storage, path, script_path, is_flow,
+            workspace_id, edited_by, edited_at, extra_perms, server_id, last_server_ping,
+            error, error_handler_path, error_handler_args, retry, mode, permissioned_as, labels, payload_transform
+        )
+        SELECT
+            sftp_resource_path, protocol, directory, file_pattern, poll_interval_secs, on_success, on_failure,
+            processed_directory, failed_directory, storage, path, script_path, is_flow,
+            $1, edited_by, edited_at, extra_perms, NULL, NULL,
+            NULL, error_handler_path, error_handler_args, retry, 'disabled'::TRIGGER_MODE, permissioned_as, labels, payload_transform
+        FROM sftp_trigger WHERE workspace_id = $2"#,
+        target_workspace_id,
+        source_workspace_id,
+    )
+    .execute(&mut **tx)
+    .await?;
+
+    sqlx::query!(
+        r#"INSERT INTO grpc_trigger (
+            proto_definition, service_name, method_name, authentication_method, path, script_path, is_flow,
+            workspace_id, edited_by, edited_at, extra_perms, error_handler_path, error_handler_args, retry,
+            mode, permissioned_as, labels, payload_transform
+        )
+        SELECT
+            proto_definition, service_name, method_name, authentication_method, path, script_path, is_flow,
+            $1, edited_by, edited_at, extra_perms, error_handler_path, error_handler_args, retry,
+            'disabled'::TRIGGER_MODE, permissioned_as, labels, payload_transform
+        FROM grpc_trigger WHERE workspace_id = $2"#,
+        target_workspace_id,
+        source_workspace_id,
+    )
+    .execute(&mut **tx)
+    .await?;
+
     sqlx::query!(
         r#"INSERT INTO sqs_trigger (
             path, queue_url, aws_resource_path, message_attributes, script_path,
             is_flow, workspace_id, edited_by, edited_at, extra_perms, error,
             server_id, last_server_ping, aws_auth_resource_type, error_handler_path,
-            error_handler_args, retry, mode, permissioned_as, labels
+            error_handler_args, retry, mode, permissioned_as, labels, payload_transform
         )
         SELECT
             path, queue_url, aws_resource_path, message_attributes, script_path,
             is_flow, $1, edited_by, edited_at, extra_perms, NULL,
             NULL, NULL, aws_auth_resource_type, error_handler_path,
-            error_handler_args, retry, 'disabled'::TRIGGER_MODE, permissioned_as, labels
+            error_handler_args, retry, 'disabled'::TRIGGER_MODE, permissioned_as, labels, payload_transform
         FROM sqs_trigger WHERE workspace_id = $2"#,
         target_workspace_id,
         source_workspace_id,
@@ -5726,14 +5844,14 @@ async fn clone_triggers_and_schedules(
             delivery_config, path, script_path, is_flow, workspace_id, edited_by,
             edited_at, extra_perms, server_id, last_server_ping, error,
             subscription_mode, error_handler_path, error_handler_args, retry,
-            auto_acknowledge_msg, ack_deadline, mode, permissioned_as, labels
+            auto_acknowledge_msg, ack_deadline, mode, permissioned_as, labels, payload_transform
         )
         SELECT
             gcp_resource_path, project_id, topic_id, subscription_id, delivery_type,
             delivery_config, path, script_path, is_flow, $1, edited_by,
             edited_at, extra_perms, NULL, NULL, NULL,
             subscription_mode, error_handler_path, error_handler_args, retry,
-            auto_acknowledge_msg, ack_deadline, 'disabled'::TRIGGER_MODE, permissioned_as, labels
+            auto_acknowledge_msg, ack_deadline, 'disabled'::TRIGGER_MODE, permissioned_as, labels, payload_transform
         FROM gcp_trigger WHERE workspace_id = $2"#,
         target_workspace_id,
         source_workspace_id,
@@ -5747,14 +5865,14 @@ async fn clone_triggers_and_schedules(
             subscription_name, event_type_filters, push_auth_config, path, script_path,
             is_flow, workspace_id, edited_by, edited_at, extra_perms, server_id,
             last_server_ping, error, mode, permissioned_as, error_handler_path,
-            error_handler_args, retry, labels
+            error_handler_args, retry, labels, payload_transform
         )
         SELECT
             azure_resource_path, azure_mode, scope_resource_id, topic_name,
             subscription_name, event_type_filters, push_auth_config, path, script_path,
             is_flow, $1, edited_by, edited_at, extra_perms, NULL,
             NULL, NULL, 'disabled'::TRIGGER_MODE, permissioned_as, error_handler_path,
-            error_handler_args, retry, labels
+            error_handler_args, retry, labels, payload_transform
         FROM azure_trigger WHERE workspace_id = $2"#,
         target_workspace_id,
         source_workspace_id,
@@ -5771,12 +5889,12 @@ async fn clone_triggers_and_schedules(
         r#"INSERT INTO email_trigger (
             path, local_part, workspaced_local_part, script_path, is_flow,
             workspace_id, edited_by, edited_at, extra_perms, error_handler_path,
-            error_handler_args, retry, mode, permissioned_as, labels
+            error_handler_args, retry, mode, permissioned_as, labels, payload_transform
         )
         SELECT
             path, local_part, workspaced_local_part, script_path, is_flow,
             $1, edited_by, edited_at, extra_perms, error_handler_path,
-            error_handler_args, retry, 'disabled'::TRIGGER_MODE, permissioned_as, labels
+            error_handler_args, retry, 'disabled'::TRIGGER_MODE, permissioned_as, labels, payload_transform
         FROM email_trigger
         WHERE workspace_id = $2
             AND (workspaced_local_part IS TRUE OR $3)"#,
@@ -11501,6 +11619,12 @@ const TRIGGER_OR_SCHEDULE_TABLES: &[&str] = &[
     "nats_trigger",
     "postgres_trigger",
     "mqtt_trigger",
+    "postgres_notify_trigger",
+    "mysql_trigger",
+    "redis_trigger",
+    "object_trigger",
+    "sftp_trigger",
+    "grpc_trigger",
     "sqs_trigger",
     "gcp_trigger",
     "azure_trigger",
@@ -73,7 +73,7 @@ pub struct WebhookArgs {
 /// One multipart part's value, kept in body order so that repeated field names
 /// can be grouped without dropping any of them.
 #[cfg(any(feature = "parquet", test))]
-enum MultipartValue {
+pub(crate) enum MultipartValue {
     Text(String),
     File(serde_json::Value),
 }
@@ -83,7 +83,7 @@ enum MultipartValue {
 /// body order. A name that carried a file is always an array, even for a single
 /// file, since that is the shape scripts have been typed against since #5002.
 #[cfg(any(feature = "parquet", test))]
-fn collapse_multipart_fields(
+pub(crate) fn collapse_multipart_fields(
     parts: Vec<(String, MultipartValue)>,
 ) -> HashMap<String, Box<RawValue>> {
     let mut grouped: HashMap<String, (Vec<serde_json::Value>, bool)> = HashMap::new();
@@ -52,6 +52,8 @@ use crate::triggers::kafka::KafkaTriggerConfigConnection;
 use crate::triggers::amqp::{AmqpOptions, ExchangeConfig};
 #[cfg(feature = "mqtt_trigger")]
 use crate::triggers::mqtt::{MqttClientVersion, MqttV3Config, MqttV5Config, SubscribeTopic};
+#[cfg(feature = "redis_trigger")]
+use crate::triggers::redis::RedisStreamOptions;
 
 #[cfg(all(feature = "enterprise", feature = "nats", feature = "private"))]
 use crate::triggers::nats::NatsTriggerConfigConnection;
@@ -106,6 +108,7 @@ pub fn workspaced_service() -> Router {
         )
         .route("/{id}", delete(delete_capture))
         .route("/{id}", get(get_capture))
+        .merge(crate::capture_replay::workspaced_service())
 }
 
 pub fn workspaced_unauthed_service() -> Router {
@@ -251,6 +254,51 @@ pub struct AmqpTriggerConfig {
     pub exchange: Option<ExchangeConfig>,
     pub options: Option<AmqpOptions>,
 }
+#[cfg(feature = "postgres_notify_trigger")]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PostgresNotifyTriggerConfig {
+    pub postgres_resource_path: String,
+    pub channels: Vec<String>,
+    pub parse_json: Option<bool>,
+}
+#[cfg(feature = "mysql_trigger")]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MysqlTriggerConfig {
+    pub mysql_resource_path: String,
+    pub tables_to_track: Vec<String>,
+    pub transaction_to_track: Vec<String>,
+}
+#[cfg(feature = "redis_trigger")]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RedisTriggerConfig {
+    pub redis_resource_path: String,
+    pub streams: Vec<String>,
+    pub consumer_group: String,
+    pub options: Option<RedisStreamOptions>,
+}
+#[cfg(feature = "object_trigger")]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ObjectTriggerConfig {
+    pub storage: Option<String>,
+    #[serde(default)]
+    pub prefix: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub delivery: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub poll_interval_secs: Option<i32>,
+}
+#[cfg(feature = "sftp_trigger")]
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SftpTriggerConfig {
+    pub sftp_resource_path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub protocol: Option<String>,
+    pub directory: String,
+    pub file_pattern: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub poll_interval_secs: Option<i32>,
+    pub storage: Option<String>,
+}
 #[cfg(feature = "postgres_trigger")]
 #[derive(Serialize, Deserialize, Debug)]
 pub struct PostgresTriggerConfig {
@@ -290,6 +338,16 @@ enum TriggerConfig {
     Mqtt(MqttTriggerConfig),
     #[cfg(feature = "amqp_trigger")]
     Amqp(AmqpTriggerConfig),
+    #[cfg(feature = "postgres_notify_trigger")]
+    PostgresNotify(PostgresNotifyTriggerConfig),
+    #[cfg(feature = "mysql_trigger")]
+    Mysql(MysqlTriggerConfig),
+    #[cfg(feature = "redis_trigger")]
+    Redis(RedisTriggerConfig),
+    #[cfg(feature = "object_trigger")]
+    Object(ObjectTriggerConfig),
+    #[cfg(feature = "sftp_trigger")]
+    Sftp(SftpTriggerConfig),
     #[cfg(all(feature = "enterprise", feature = "gcp_trigger", feature = "private"))]
     Gcp(GcpTriggerConfig),
     #[cfg(all(feature = "enterprise", feature = "azure_trigger", feature = "private"))]
@@ -1214,6 +1272,7 @@ async fn http_payload(
             &route_path,
             "",
             &params,
+            None,
             headers,
             query,
         )
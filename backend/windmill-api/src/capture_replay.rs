@@ -0,0 +1,628 @@
+/*
+ * Author: Windmill Labs
+ * Copyright: Windmill Labs, Inc 2026
+ * This file and its contents are licensed under the AGPLv3 License.
+ * Please see the included NOTICE for copyright information and
+ * LICENSE-AGPL for a copy of the license.
+ */
+
+//! Bulk replay of captured trigger payloads, or of past trigger-originated jobs, against a
+//! chosen script hash or flow version. Each replayed job is reported next to the outcome of
+//! the original job so a new version of a handler can be regression tested before deploying it.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use axum::{
+    extract::{Extension, Path},
+    routing::post,
+    Json, Router,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+use sqlx::types::Json as SqlxJson;
+use uuid::Uuid;
+
+use windmill_common::{
+    db::{UserDB, UserDbWithAuthed},
+    error::{Error, JsonResult, Result},
+    get_latest_deployed_hash_for_path, get_latest_flow_version_info_for_path,
+    jobs::JobTriggerKind,
+    scripts::ScriptHash,
+    triggers::TriggerKind,
+};
+use windmill_queue::PushArgsOwned;
+
+use crate::{
+    db::{ApiAuthed, DB},
+    jobs::{run_flow_by_version_inner, run_job_by_hash_inner, RunJobQuery},
+    triggers::trigger_helpers::{get_runnable_format, RunnableId},
+};
+
+const MAX_REPLAY_ITEMS: usize = 50;
+const DEFAULT_REPLAY_TIMEOUT_SECS: u64 = 30;
+const MAX_REPLAY_TIMEOUT_SECS: u64 = 300;
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+pub fn workspaced_service() -> Router {
+    Router::new().route("/replay", post(replay_captures))
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ReplaySource {
+    Captures { ids: Vec<i64> },
+    Jobs(JobsSource),
+}
+
+#[derive(Deserialize, Debug)]
+struct JobsSource {
+    runnable_path: String,
+    is_flow: bool,
+    trigger_kind: Option<JobTriggerKind>,
+    created_after: DateTime<Utc>,
+    created_before: Option<DateTime<Utc>>,
+    limit: Option<usize>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum ReplayTarget {
+    Script { path: String, hash: Option<ScriptHash> },
+    Flow { path: String, version: Option<i64> },
+}
+
+#[derive(Deserialize)]
+struct ReplayRequest {
+    source: ReplaySource,
+    target: ReplayTarget,
+    timeout_secs: Option<u64>,
+}
+
+#[derive(Serialize, Debug, Default)]
+struct ReplayOutcome {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    job_id: Option<Uuid>,
+    completed: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    success: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<Box<RawValue>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct ReplayItem {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    capture_id: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    original_job_id: Option<Uuid>,
+    created_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    original: Option<ReplayOutcome>,
+    replay: ReplayOutcome,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    same_result: Option<bool>,
+}
+
+#[derive(Serialize)]
+struct ReplayResponse {
+    path: String,
+    is_flow: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    script_hash: Option<ScriptHash>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    flow_version: Option<i64>,
+    items: Vec<ReplayItem>,
+}
+
+/// A payload to replay, along with what is known about its original run.
+struct ReplayInput {
+    capture_id: Option<i64>,
+    original_job_id: Option<Uuid>,
+    created_at: DateTime<Utc>,
+    original: Option<ReplayOutcome>,
+    args: HashMap<String, Box<RawValue>>,
+    skip_preprocessor: bool,
+}
+
+struct ResolvedTarget {
+    path: String,
+    is_flow: bool,
+    script_hash: Option<ScriptHash>,
+    flow_version: Option<i64>,
+}
+
+impl ResolvedTarget {
+    fn runnable_id(&self) -> RunnableId {
+        match (self.script_hash, self.flow_version) {
+            (Some(hash), _) => RunnableId::from_script_hash(hash),
+            (None, Some(version)) => RunnableId::from_flow_version(version),
+            (None, None) => unreachable!("a resolved target always has a hash or a version"),
+        }
+    }
+}
+
+async fn resolve_target(
+    authed: &ApiAuthed,
+    db: &DB,
+    user_db: &UserDB,
+    w_id: &str,
+    target: ReplayTarget,
+) -> Result<ResolvedTarget> {
+    let userdb_authed = UserDbWithAuthed { db: user_db.clone(), authed: &authed.to_authed_ref() };
+    match target {
+        ReplayTarget::Script { path, hash: Some(hash) } => {
+            let hash_path = sqlx::query_scalar!(
+                "SELECT path FROM script WHERE hash = $1 AND workspace_id = $2",
+                hash.0,
+                w_id
+            )
+            .fetch_optional(db)
+            .await?;
+            if hash_path.as_deref() != Some(path.as_str()) {
+                return Err(Error::BadRequest(format!(
+                    "script version {hash} does not belong to {path}"
+                )));
+            }
+            Ok(
+                ResolvedTarget {
+                    path,
+                    is_flow: false,
+                    script_hash: Some(hash),
+                    flow_version: None,
+                },
+            )
+        }
+        ReplayTarget::Script { path, hash: None } => {
+            let info =
+                get_latest_deployed_hash_for_path(Some(userdb_authed), db.clone(), w_id, &path)
+                    .await?;
+            Ok(ResolvedTarget {
+                path,
+                is_flow: false,
+                script_hash: Some(ScriptHash(info.hash)),
+                flow_version: None,
+            })
+        }
+        ReplayTarget::Flow { path, version: Some(version) } => {
+            let version_path = sqlx::query_scalar!(
+                "SELECT path FROM flow_version WHERE id = $1 AND workspace_id = $2",
+                version,
+                w_id
+            )
+            .fetch_optional(db)
+            .await?;
+            if version_path.as_deref() != Some(path.as_str()) {
+                return Err(Error::BadRequest(format!(
+                    "flow version {version} does not belong to {path}"
+                )));
+            }
+            Ok(ResolvedTarget {
+                path,
+                is_flow: true,
+                script_hash: None,
+                flow_version: Some(version),
+            })
+        }
+        ReplayTarget::Flow { path, version: None } => {
+            let info =
+                get_latest_flow_version_info_for_path(Some(userdb_authed), db, w_id, &path, true)
+                    .await?;
+            Ok(ResolvedTarget {
+                path,
+                is_flow: true,
+                script_hash: None,
+                flow_version: Some(info.version),
+            })
+        }
+    }
+}
+
+struct CaptureRow {
+    id: i64,
+    created_at: DateTime<Utc>,
+    trigger_kind: TriggerKind,
+    main_args: SqlxJson<HashMap<String, Box<RawValue>>>,
+    preprocessor_args: Option<SqlxJson<HashMap<String, Box<RawValue>>>>,
+}
+
+/// Captures hold both the arguments the main function would receive and the ones a
+/// preprocessor would receive, so which one is replayed depends on the target.
+async fn load_captures(
+    authed: &ApiAuthed,
+    db: &DB,
+    user_db: &UserDB,
+    w_id: &str,
+    ids: &[i64],
+    target: &ResolvedTarget,
+) -> Result<Vec<ReplayInput>> {
+    let mut tx = user_db.clone().begin(authed).await?;
+    let captures = sqlx::query_as!(
+        CaptureRow,
+        r#"
+        SELECT
+            id,
+            created_at,
+            trigger_kind AS "trigger_kind: _",
+            main_args AS "main_args!: _",
+            preprocessor_args AS "preprocessor_args: _"
+        FROM
+            capture
+        WHERE
+            workspace_id = $1
+            AND id = ANY($2)
+        ORDER BY
+            created_at DESC
+        "#,
+        w_id,
+        ids,
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    if captures.len() != ids.len() {
+        return Err(Error::NotFound(format!(
+            "{} of the requested captures were not found",
+            ids.len() - captures.len()
+        )));
+    }
+
+    let mut inputs = Vec::with_capacity(captures.len());
+    for capture in captures {
+        let format =
+            get_runnable_format(target.runnable_id(), w_id, db, &capture.trigger_kind).await?;
+        let (args, skip_preprocessor) = match capture.preprocessor_args {
+            Some(SqlxJson(preprocessor_args)) if format.has_preprocessor => {
+                (preprocessor_args, false)
+            }
+            _ => (capture.main_args.0, true),
+        };
+        inputs.push(ReplayInput {
+            capture_id: Some(capture.id),
+            original_job_id: None,
+            created_at: capture.created_at,
+            original: None,
+            args,
+            skip_preprocessor,
+        });
+    }
+    Ok(inputs)
+}
+
+/// The stored args of a job are the ones its main function received (a preprocessor
+/// overwrites them with its output), so they are always replayed without preprocessing.
+async fn load_jobs(
+    authed: &ApiAuthed,
+    user_db: &UserDB,
+    w_id: &str,
+    source: JobsSource,
+) -> Result<Vec<ReplayInput>> {
+    let limit = source
+        .limit
+        .unwrap_or(MAX_REPLAY_ITEMS)
+        .clamp(1, MAX_REPLAY_ITEMS);
+    let mut tx = user_db.clone().begin(authed).await?;
+    let jobs = sqlx::query!(
+        r#"
+        SELECT
+            j.id,
+            j.created_at,
+            j.args AS "args: SqlxJson<HashMap<String, Box<RawValue>>>",
+            c.id IS NOT NULL AS "completed!",
+            c.status = 'success'::job_status AS success,
+            c.result AS "result: SqlxJson<Box<RawValue>>"
+        FROM
+            v2_job j
+            LEFT JOIN v2_job_completed c ON c.id = j.id
+        WHERE
+            j.workspace_id = $1
+            AND j.runnable_path = $2
+            AND j.parent_job IS NULL
+            AND j.kind IN ('script'::job_kind, 'flow'::job_kind)
+            AND (j.kind = 'flow'::job_kind) = $3
+            AND j.trigger_kind IS NOT NULL
+            AND ($4::job_trigger_kind IS NULL OR j.trigger_kind = $4)
+            AND j.created_at >= $5
+            AND ($6::timestamptz IS NULL OR j.created_at < $6)
+        ORDER BY
+            j.created_at DESC
+        LIMIT $7
+        "#,
+        w_id,
+        &source.runnable_path,
+        source.is_flow,
+        source.trigger_kind as Option<JobTriggerKind>,
+        source.created_after,
+        source.created_before,
+        limit as i64,
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    Ok(jobs
+        .into_iter()
+        .map(|job| ReplayInput {
+            capture_id: None,
+            original_job_id: Some(job.id),
+            created_at: job.created_at,
+            original: Some(ReplayOutcome {
+                job_id: Some(job.id),
+                completed: job.completed,
+                success: job.success,
+                result: job.result.map(|r| r.0),
+                error: None,
+            }),
+            args: job.args.map(|a| a.0).unwrap_or_default(),
+            skip_preprocessor: true,
+        })
+        .collect())
+}
+
+async fn push_replay(
+    authed: &ApiAuthed,
+    db: &DB,
+    user_db: &UserDB,
+    w_id: &str,
+    target: &ResolvedTarget,
+    args: HashMap<String, Box<RawValue>>,
+    skip_preprocessor: bool,
+) -> Result<Uuid> {
+    let run_query =
+        RunJobQuery { skip_preprocessor: Some(skip_preprocessor), ..Default::default() };
+    let args = PushArgsOwned { extra: None, args };
+    let uuid = match (target.script_hash, target.flow_version) {
+        (Some(hash), _) => {
+            run_job_by_hash_inner(
+                authed.clone(),
+                db.clone(),
+                user_db.clone(),
+                w_id.to_string(),
+                hash,
+                run_query,
+                args,
+                None,
+            )
+            .await?
+            .0
+        }
+        (None, Some(version)) => {
+            run_flow_by_version_inner(
+                authed.clone(),
+                db.clone(),
+                user_db.clone(),
+                w_id.to_string(),
+                version,
+                run_query,
+                args,
+                None,
+            )
+            .await?
+            .0
+        }
+        (None, None) => unreachable!("a resolved target always has a hash or a version"),
+    };
+    Ok(uuid)
+}
+
+/// Polls the completed jobs table until every replay finished or the deadline passed. Jobs
+/// still running at the deadline are left alone and reported as not completed.
+async fn wait_for_replays(
+    db: &DB,
+    w_id: &str,
+    job_ids: &[Uuid],
+    timeout: Duration,
+) -> Result<HashMap<Uuid, ReplayOutcome>> {
+    let deadline = tokio::time::Instant::now() + timeout;
+    let mut outcomes = HashMap::new();
+    loop {
+        let pending = job_ids
+            .iter()
+            .filter(|id| !outcomes.contains_key(*id))
+            .copied()
+            .collect::<Vec<_>>();
+        if pending.is_empty() {
+            break;
+        }
+        let completed = sqlx::query!(
+            r#"
+            SELECT
+                id,
+                status = 'success'::job_status AS "success!",
+                result AS "result: SqlxJson<Box<RawValue>>"
+            FROM
+                v2_job_completed
+            WHERE
+                id = ANY($1)
+                AND workspace_id = $2
+            "#,
+            &pending,
+            w_id,
+        )
+        .fetch_all(db)
+        .await?;
+        for job in completed {
+            outcomes.insert(
+                job.id,
+                ReplayOutcome {
+                    job_id: Some(job.id),
+                    completed: true,
+                    success: Some(job.success),
+                    result: job.result.map(|r| r.0),
+                    error: None,
+                },
+            );
+        }
+        if outcomes.len() == job_ids.len() || tokio::time::Instant::now() >= deadline {
+            break;
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+    Ok(outcomes)
+}
+
+fn same_result(original: &ReplayOutcome, replay: &ReplayOutcome) -> Option<bool> {
+    if !original.completed || !replay.completed {
+        return None;
+    }
+    let parse = |outcome: &ReplayOutcome| {
+        outcome
+            .result
+            .as_ref()
+            .and_then(|r| serde_json::from_str::<serde_json::Value>(r.get()).ok())
+    };
+    Some(original.success == replay.success && parse(original) == parse(replay))
+}
+
+async fn replay_captures(
+    authed: ApiAuthed,
+    Extension(db): Extension<DB>,
+    Extension(user_db): Extension<UserDB>,
+    Path(w_id): Path<String>,
+    Json(request): Json<ReplayRequest>,
+) -> JsonResult<ReplayResponse> {
+    let timeout = Duration::from_secs(
+        request
+            .timeout_secs
+            .unwrap_or(DEFAULT_REPLAY_TIMEOUT_SECS)
+            .min(MAX_REPLAY_TIMEOUT_SECS),
+    );
+    let target = resolve_target(&authed, &db, &user_db, &w_id, request.target).await?;
+
+    let inputs = match request.source {
+        ReplaySource::Captures { mut ids } => {
+            ids.sort_unstable();
+            ids.dedup();
+            if ids.is_empty() || ids.len() > MAX_REPLAY_ITEMS {
+                return Err(Error::BadRequest(format!(
+                    "between 1 and {MAX_REPLAY_ITEMS} captures can be replayed at once"
+                )));
+            }
+            load_captures(&authed, &db, &user_db, &w_id, &ids, &target).await?
+        }
+        ReplaySource::Jobs(source) => load_jobs(&authed, &user_db, &w_id, source).await?,
+    };
+
+    let mut pushed = Vec::with_capacity(inputs.len());
+    for mut input in inputs {
+        let args = std::mem::take(&mut input.args);
+        let job = push_replay(
+            &authed,
+            &db,
+            &user_db,
+            &w_id,
+            &target,
+            args,
+            input.skip_preprocessor,
+        )
+        .await;
+        pushed.push((input, job));
+    }
+
+    let job_ids = pushed
+        .iter()
+        .filter_map(|(_, job)| job.as_ref().ok().copied())
+        .collect::<Vec<_>>();
+    let mut outcomes = wait_for_replays(&db, &w_id, &job_ids, timeout).await?;
+
+    let items = pushed
+        .into_iter()
+        .map(|(input, job)| {
+            let replay = match job {
+                Ok(job_id) => outcomes.remove(&job_id).unwrap_or_else(|| ReplayOutcome {
+                    job_id: Some(job_id),
+                    ..Default::default()
+                }),
+                Err(e) => ReplayOutcome { error: Some(e.to_string()), ..Default::default() },
+            };
+            let same_result = input
+                .original
+                .as_ref()
+                .and_then(|o| same_result(o, &replay));
+            ReplayItem {
+                capture_id: input.capture_id,
+                original_job_id: input.original_job_id,
+                created_at: input.created_at,
+                original: input.original,
+                replay,
+                same_result,
+            }
+        })
+        .collect();
+
+    Ok(Json(ReplayResponse {
+        path: target.path,
+        is_flow: target.is_flow,
+        script_hash: target.script_hash,
+        flow_version: target.flow_version,
+        items,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outcome(success: bool, result: &str) -> ReplayOutcome {
+        ReplayOutcome {
+            job_id: Some(Uuid::nil()),
+            completed: true,
+            success: Some(success),
+            result: Some(RawValue::from_string(result.to_string()).unwrap()),
+            error: None,
+        }
+    }
+
+    #[test]
+    fn same_result_ignores_formatting() {
+        assert_eq!(
+            same_result(
+                &outcome(true, r#"{"a": 1, "b": [1,2]}"#),
+                &outcome(true, r#"{"b":[1, 2],"a":1}"#)
+            ),
+            Some(true)
+        );
+        assert_eq!(
+            same_result(&outcome(true, "1"), &outcome(true, "2")),
+            Some(false)
+        );
+        assert_eq!(
+            same_result(&outcome(true, "1"), &outcome(false, "1")),
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn same_result_unknown_while_running() {
+        let running = ReplayOutcome { job_id: Some(Uuid::nil()), ..Default::default() };
+        assert_eq!(same_result(&outcome(true, "1"), &running), None);
+    }
+
+    #[test]
+    fn parse_replay_request() {
+        let request: ReplayRequest = serde_json::from_str(
+            r#"{
+                "source": {"type": "jobs", "runnable_path": "f/x/hook", "is_flow": false,
+                           "trigger_kind": "webhook", "created_after": "2026-10-01T00:00:00Z"},
+                "target": {"kind": "flow", "path": "f/x/hook_v2"}
+            }"#,
+        )
+        .unwrap();
+        assert!(matches!(
+            request.source,
+            ReplaySource::Jobs(JobsSource {
+                trigger_kind: Some(JobTriggerKind::Webhook),
+                limit: None,
+                ..
+            })
+        ));
+        assert!(matches!(
+            request.target,
+            ReplayTarget::Flow { version: None, .. }
+        ));
+    }
+}
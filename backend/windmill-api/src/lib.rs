@@ -77,6 +77,7 @@ pub mod auth;
 pub mod azure_proxy_ee;
 mod azure_proxy_oss;
 mod capture;
+mod capture_replay;
 mod concurrency_groups;
 mod db;
 mod db_health;
@@ -142,6 +143,7 @@ mod resources;
 #[cfg(feature = "private")]
 pub mod saml_ee;
 mod saml_oss;
+mod sbom;
 #[cfg(feature = "private")]
 pub mod scim_ee;
 mod scim_oss;
@@ -189,6 +191,7 @@ mod variables;
 #[cfg(feature = "private")]
 pub mod volumes_ee;
 mod volumes_oss;
+mod vulnerabilities;
 pub mod webhook_util;
 mod workspaces;
 #[cfg(feature = "private")]
@@ -527,6 +530,13 @@ pub async fn run_server(
         triggers::http::refresh_routers_loop(&db, http_killpill_rx).await;
     }
 
+    // Initialize gRPC trigger refresh loop
+    #[cfg(feature = "grpc_trigger")]
+    {
+        let grpc_killpill_rx = killpill_rx.resubscribe();
+        triggers::grpc::refresh_routes_loop(&db, grpc_killpill_rx).await;
+    }
+
     let triggers_service = triggers::generate_trigger_routers();
 
     if !*CLOUD_HOSTED && server_mode && !mcp_mode {
@@ -722,6 +732,7 @@ pub async fn run_server(
                             resources::workspaced_service().layer(cors.clone()),
                         )
                         .nest("/shared_ui", workspace_shared_ui::workspaced_service())
+                        .nest("/sbom", sbom::workspaced_service())
                         .nest("/schedules", windmill_api_schedule::workspaced_service())
                         .nest("/scripts", scripts::workspaced_service())
                         .nest("/trash", trash::workspaced_service())
@@ -742,6 +753,7 @@ pub async fn run_server(
                             variables::workspaced_service().layer(cors.clone()),
                         )
                         .nest("/volumes", volumes_oss::workspaced_service())
+                        .nest("/vulnerabilities", vulnerabilities::workspaced_service())
                         .nest("/workers", windmill_api_workers::workspaced_service())
                         .nest("/workspaces", workspaces::workspaced_service())
                         .nest("/hub", hub_publish::workspaced_service())
@@ -788,6 +800,7 @@ pub async fn run_server(
                 .nest("/indexer", indexer_oss::management_service())
                 .nest("/mcp/w/{workspace_id}/list_tools", mcp_list_tools_service)
                 .nest("/db_health", db_health::global_service())
+                .nest("/vulnerabilities", vulnerabilities::global_service())
                 .nest("/health/detailed", health::detailed_service())
                 .nest(
                     "/saml",
@@ -1121,8 +1134,13 @@ pub async fn run_server(
                 get(windmill_api_settings::get_jwks)
             }
         })
-        .fallback(static_assets::static_handler)
-        .layer(middleware_stack);
+        .fallback(static_assets::static_handler);
+
+    // gRPC calls are answered by this layer, no route matching the paths of proto services.
+    #[cfg(feature = "grpc_trigger")]
+    let app = app.layer(axum::middleware::from_fn(triggers::grpc::handler::grpc_middleware));
+
+    let app = app.layer(middleware_stack);
 
     let app = if disable_response_logs {
         app
@@ -191,6 +191,12 @@ async fn get_offboard_preview(
         "postgres_trigger",
         "mqtt_trigger",
         "amqp_trigger",
+        "postgres_notify_trigger",
+        "mysql_trigger",
+        "redis_trigger",
+        "object_trigger",
+        "sftp_trigger",
+        "grpc_trigger",
         "nats_trigger",
         "sqs_trigger",
         "gcp_trigger",
@@ -754,6 +760,12 @@ async fn check_path_conflicts(
         "postgres_trigger",
         "mqtt_trigger",
         "amqp_trigger",
+        "postgres_notify_trigger",
+        "mysql_trigger",
+        "redis_trigger",
+        "object_trigger",
+        "sftp_trigger",
+        "grpc_trigger",
         "nats_trigger",
         "sqs_trigger",
         "gcp_trigger",
@@ -1045,6 +1057,12 @@ async fn offboard_user_from_workspace<'c>(
         "postgres_trigger",
         "mqtt_trigger",
         "amqp_trigger",
+        "postgres_notify_trigger",
+        "mysql_trigger",
+        "redis_trigger",
+        "object_trigger",
+        "sftp_trigger",
+        "grpc_trigger",
         "nats_trigger",
         "sqs_trigger",
         "gcp_trigger",
@@ -0,0 +1,127 @@
+/*
+ * Author: Windmill Labs
+ * Copyright: Windmill Labs, Inc 2026
+ * This file and its contents are licensed under the AGPLv3 License.
+ * Please see the included NOTICE for copyright information and
+ * LICENSE-AGPL for a copy of the license.
+ */
+
+use axum::{
+    extract::{Extension, Json, Path, Query},
+    routing::get,
+    Router,
+};
+use serde::Deserialize;
+use windmill_common::{
+    db::UserDB,
+    error::{Error, JsonResult},
+    sbom::{runnable_sbom, workspace_sbom, Bom, SbomRunnable},
+    utils::StripPath,
+};
+
+use crate::db::{ApiAuthed, DB};
+use crate::vulnerabilities::{runnable_locks, RunnableLock};
+
+pub fn workspaced_service() -> Router {
+    Router::new()
+        .route("/workspace", get(get_workspace_sbom))
+        .route("/{kind}/p/{*path}", get(get_runnable_sbom))
+}
+
+/// Groups the locks of the steps and inline scripts of each flow and app.
+fn sbom_runnables(locks: Vec<RunnableLock>) -> Vec<SbomRunnable> {
+    let mut runnables: Vec<SbomRunnable> = vec![];
+    for lock in locks {
+        match runnables.last_mut() {
+            Some(last) if last.kind == lock.kind && last.path == lock.path => {
+                last.locks.push((lock.language, lock.lock))
+            }
+            _ => runnables.push(SbomRunnable {
+                kind: lock.kind,
+                path: lock.path,
+                locks: vec![(lock.language, lock.lock)],
+            }),
+        }
+    }
+    runnables
+}
+
+async fn get_runnable_sbom(
+    authed: ApiAuthed,
+    Extension(db): Extension<DB>,
+    Extension(user_db): Extension<UserDB>,
+    Path((w_id, kind, path)): Path<(String, String, StripPath)>,
+) -> JsonResult<Bom> {
+    let kind = match kind.as_str() {
+        "script" => "script",
+        "flow" => "flow",
+        "app" => "app",
+        kind => return Err(Error::BadRequest(format!("Unknown kind: {kind}"))),
+    };
+    let path = path.to_path();
+    let locks = runnable_locks(
+        &authed,
+        &db,
+        user_db.clone(),
+        &w_id,
+        &[kind],
+        Some(path),
+        None,
+    )
+    .await?;
+    let runnable = match sbom_runnables(locks).pop() {
+        Some(runnable) => runnable,
+        None => {
+            // Flows and apps without inline scripts have no locks
+            let mut tx = user_db.begin(&authed).await?;
+            let exists = match kind {
+                "flow" => sqlx::query_scalar!(
+                    "SELECT EXISTS(SELECT 1 FROM flow WHERE workspace_id = $1 AND path = $2 AND archived = false)",
+                    w_id,
+                    path
+                )
+                .fetch_one(&mut *tx)
+                .await?,
+                "app" => sqlx::query_scalar!(
+                    "SELECT EXISTS(SELECT 1 FROM app WHERE workspace_id = $1 AND path = $2)",
+                    w_id,
+                    path
+                )
+                .fetch_one(&mut *tx)
+                .await?,
+                _ => None,
+            };
+            tx.commit().await?;
+            if !exists.unwrap_or(false) {
+                return Err(Error::NotFound(format!("{kind} not found at path {path}")));
+            }
+            SbomRunnable { kind, path: path.to_string(), locks: vec![] }
+        }
+    };
+    Ok(Json(runnable_sbom(&runnable)))
+}
+
+#[derive(Deserialize)]
+struct WorkspaceSbomQuery {
+    path_start: Option<String>,
+}
+
+async fn get_workspace_sbom(
+    authed: ApiAuthed,
+    Extension(db): Extension<DB>,
+    Extension(user_db): Extension<UserDB>,
+    Path(w_id): Path<String>,
+    Query(query): Query<WorkspaceSbomQuery>,
+) -> JsonResult<Bom> {
+    let locks = runnable_locks(
+        &authed,
+        &db,
+        user_db,
+        &w_id,
+        &["script", "flow", "app"],
+        None,
+        query.path_start.as_deref(),
+    )
+    .await?;
+    Ok(Json(workspace_sbom(&w_id, &sbom_runnables(locks))))
+}
@@ -25,6 +25,12 @@ fn build_trigger_scope_domains() -> Vec<ScopeDomain> {
         ("nats_triggers", "NATS"),
         ("mqtt_triggers", "MQTT"),
         ("amqp_triggers", "AMQP"),
+        ("postgres_notify_triggers", "PostgreSQL NOTIFY"),
+        ("mysql_triggers", "MySQL"),
+        ("redis_triggers", "Redis Streams"),
+        ("object_triggers", "Object Storage"),
+        ("sftp_triggers", "SFTP / FTP"),
+        ("grpc_triggers", "gRPC"),
         ("sqs_triggers", "AWS SQS"),
         ("gcp_triggers", "GCP Pub/Sub"),
         ("azure_triggers", "Azure Event Grid"),
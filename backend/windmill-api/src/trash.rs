@@ -496,6 +496,12 @@ async fn restore_trigger(tx: &mut sqlx::PgConnection, item: &TrashItemWithData)
         "postgres_trigger",
         "mqtt_trigger",
         "amqp_trigger",
+        "postgres_notify_trigger",
+        "mysql_trigger",
+        "redis_trigger",
+        "object_trigger",
+        "sftp_trigger",
+        "grpc_trigger",
         "sqs_trigger",
         "gcp_trigger",
         "azure_trigger",
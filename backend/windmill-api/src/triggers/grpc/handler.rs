@@ -0,0 +1,533 @@
+use super::{
+    codec::{
+        decode_frames, encode_frame, encode_status_message, is_grpc_content_type, Code,
+        GRPC_CONTENT_TYPE,
+    },
+    proto::{decode_message, encode_message},
+    refresh_routes, GrpcRequest, GrpcRoute, GrpcTrigger, AUTHENTICATION_WINDMILL,
+    GRPC_ROUTES_CACHE, WORKSPACE_METADATA,
+};
+use crate::{
+    auth::{AuthCache, OptTokened},
+    db::{ApiAuthed, DB},
+    jobs::{start_job_update_sse_stream, JobUpdateSSEStream},
+    triggers::trigger_helpers::{
+        trigger_runnable_and_wait_for_raw_result, trigger_runnable_inner, trigger_runnables,
+        TriggerJobArgs,
+    },
+    utils::check_scopes,
+    REQUEST_SIZE_LIMIT,
+};
+use axum::{
+    body::{Body, Bytes},
+    extract::Request,
+    middleware::Next,
+    response::Response,
+};
+use http::{header, HeaderMap, HeaderValue, Method, StatusCode};
+use http_body_util::StreamBody;
+use hyper::body::Frame;
+use prost_reflect::MessageDescriptor;
+use serde_json::{value::RawValue, Value};
+use std::{convert::Infallible, sync::Arc};
+use windmill_api_jobs::execution::{
+    delete_job_metadata_after_use, run_wait_result_internal, schedule_job_deletion,
+};
+use windmill_common::{
+    db::UserDB,
+    error::{Error, Result},
+    jobs::JobTriggerKind,
+    triggers::TriggerMetadata,
+};
+use windmill_queue::PushArgsOwned;
+use windmill_trigger::{transform::PayloadTransform, TriggerMode};
+
+type FrameSender = tokio::sync::mpsc::Sender<std::result::Result<Frame<Bytes>, Infallible>>;
+
+/// Metadata of a request that is not passed to the job.
+const RESERVED_METADATA: [&str; 5] = ["authorization", "cookie", "content-type", "te", "host"];
+
+/// Serves the methods of gRPC triggers next to the API, on its port. A request is told apart
+/// by its content type, its path being `/{package}.{Service}/{Method}`.
+pub async fn grpc_middleware(request: Request, next: Next) -> Response {
+    let is_grpc = request.method() == Method::POST
+        && request
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|content_type| content_type.to_str().ok())
+            .is_some_and(is_grpc_content_type);
+    if !is_grpc {
+        return next.run(request).await;
+    }
+
+    match serve(request).await {
+        Ok(response) => response,
+        Err(err) => status_response(Code::from_error(&err), &err.to_string()),
+    }
+}
+
+fn extension<T: Clone + Send + Sync + 'static>(request: &Request) -> Result<T> {
+    request
+        .extensions()
+        .get::<T>()
+        .cloned()
+        .ok_or_else(|| Error::internal_err("gRPC triggers are not set up".to_string()))
+}
+
+async fn serve(request: Request) -> Result<Response> {
+    let db: DB = extension(&request)?;
+    let user_db: UserDB = extension(&request)?;
+    let auth_cache: Arc<AuthCache> = extension(&request)?;
+
+    let request_path = request.uri().path().to_string();
+    let headers = request.headers().clone();
+    let Some(workspace_id) = headers
+        .get(WORKSPACE_METADATA)
+        .and_then(|w_id| w_id.to_str().ok())
+    else {
+        return Ok(status_response(
+            Code::InvalidArgument,
+            &format!(
+                "Missing the {} metadata, set it to the workspace of the trigger",
+                WORKSPACE_METADATA
+            ),
+        ));
+    };
+
+    // Later changes are picked up on notify and by the refresh loop, only a server that never
+    // loaded the routes goes to the database here.
+    let routes_cache = GRPC_ROUTES_CACHE.read().await;
+    let routes_cache = if routes_cache.version == 0 {
+        tracing::warn!("gRPC routes are not loaded, loading from db");
+        let (_, routes_cache) = refresh_routes(&db).await?;
+        routes_cache
+    } else {
+        routes_cache
+    };
+    let route = match routes_cache.find(&request_path, workspace_id) {
+        Ok(route) => route.clone(),
+        Err(Error::NotFound(message)) => return Ok(status_response(Code::Unimplemented, &message)),
+        Err(err) => return Err(err),
+    };
+    drop(routes_cache);
+
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::to_string);
+    let authed = authenticate(&route, &auth_cache, token, &db, &user_db).await?;
+
+    let limit = *REQUEST_SIZE_LIMIT.read().await;
+    let body = axum::body::to_bytes(request.into_body(), limit)
+        .await
+        .map_err(|e| Error::BadRequest(format!("Could not read the gRPC request: {}", e)))?;
+    let message = match decode_frames(&body).map_err(Error::BadRequest)?.as_slice() {
+        [message] => decode_message(route.method.input(), message).map_err(Error::BadRequest)?,
+        messages => {
+            return Err(Error::BadRequest(format!(
+                "Expected a single request message, got {}",
+                messages.len()
+            )))
+        }
+    };
+
+    let payload = GrpcRequest {
+        service: route.method.parent_service().full_name().to_string(),
+        method: route.method.name().to_string(),
+        message,
+        metadata: headers
+            .iter()
+            .filter(|(name, _)| {
+                !RESERVED_METADATA.contains(&name.as_str()) && !name.as_str().starts_with("grpc-")
+            })
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect(),
+    };
+    let trigger_info = payload.trigger_info();
+    let jobs_args = match PayloadTransform::new(route.payload_transform.as_deref()) {
+        Some(transform) => {
+            transform
+                .apply(GrpcTrigger::build_event(&payload, trigger_info))
+                .await?
+        }
+        None => vec![
+            GrpcTrigger::build_job_args(
+                &route.script_path,
+                route.is_flow,
+                &route.workspace_id,
+                &db,
+                payload,
+                trigger_info,
+            )
+            .await?,
+        ],
+    };
+
+    let trigger_metadata = TriggerMetadata::new(Some(route.path.clone()), JobTriggerKind::Grpc);
+    if route.mode == TriggerMode::Suspended {
+        trigger_runnables(
+            &db,
+            Some(user_db.clone()),
+            authed.clone(),
+            &route.workspace_id,
+            &route.script_path,
+            route.is_flow,
+            jobs_args,
+            route.retry.as_ref(),
+            route.error_handler_path.as_deref(),
+            route.error_handler_args.as_ref(),
+            format!("grpc_trigger/{}", route.path),
+            true,
+            trigger_metadata.clone(),
+        )
+        .await?;
+
+        return Ok(status_response(
+            Code::Unavailable,
+            &format!(
+                "Trigger {} is in suspended mode, jobs are added to the queue but suspended",
+                route.path
+            ),
+        ));
+    }
+
+    // The response is the result of exactly one job, which a transform may not produce.
+    let args = match <[PushArgsOwned; 1]>::try_from(jobs_args) {
+        Ok([args]) => args,
+        Err(jobs_args) => {
+            return Err(Error::BadRequest(format!(
+            "Payload transform of gRPC trigger {} produced {} jobs, but a call runs exactly one",
+            route.path,
+            jobs_args.len()
+        )))
+        }
+    };
+
+    if route.method.is_server_streaming() {
+        return stream_job(route, db, user_db, authed, args, trigger_metadata).await;
+    }
+
+    let (result, success) = trigger_runnable_and_wait_for_raw_result(
+        &db,
+        Some(user_db),
+        authed,
+        &route.workspace_id,
+        &route.script_path,
+        route.is_flow,
+        args,
+        route.retry.as_ref(),
+        route.error_handler_path.as_deref(),
+        route.error_handler_args.as_ref(),
+        format!("grpc_trigger/{}", route.path),
+        trigger_metadata,
+    )
+    .await?;
+
+    if !success {
+        return Ok(status_response(Code::Unknown, &job_error_message(&result)));
+    }
+
+    let value: Value = serde_json::from_str(result.get())?;
+    let message = encode_message(route.method.output(), &value).map_err(Error::internal_err)?;
+    let frames = vec![
+        Ok(Frame::data(encode_frame(&message))),
+        Ok(Frame::trailers(trailers(Code::Ok, None))),
+    ];
+    Ok(grpc_response(Body::new(StreamBody::new(
+        futures::stream::iter(frames),
+    ))))
+}
+
+/// The user the job runs as. A trigger requiring Windmill authentication is only called by
+/// users with access to it, whose name the job is run under.
+async fn authenticate(
+    route: &GrpcRoute,
+    auth_cache: &Arc<AuthCache>,
+    token: Option<String>,
+    db: &DB,
+    user_db: &UserDB,
+) -> Result<ApiAuthed> {
+    let username_override = if route.authentication_method == AUTHENTICATION_WINDMILL {
+        let authed = match token {
+            Some(token) => {
+                auth_cache
+                    .get_authed(Some(route.workspace_id.clone()), &token)
+                    .await
+            }
+            None => None,
+        }
+        .ok_or_else(|| Error::NotAuthorized("Requires authentication".to_string()))?;
+        check_scopes(&authed, || format!("grpc_triggers:read:{}", &route.path))?;
+
+        let mut tx = user_db.clone().begin(&authed).await?;
+        let exists = sqlx::query_scalar!(
+            "SELECT EXISTS(SELECT 1 FROM grpc_trigger WHERE workspace_id = $1 AND path = $2)",
+            route.workspace_id,
+            route.path
+        )
+        .fetch_one(&mut *tx)
+        .await?
+        .unwrap_or(false);
+        tx.commit().await?;
+
+        if !exists {
+            return Err(Error::NotAuthorized("Unauthorized".to_string()));
+        }
+        Some(authed.display_username().to_owned())
+    } else {
+        None
+    };
+
+    let email = windmill_common::users::get_email_from_permissioned_as(
+        &route.permissioned_as,
+        &route.workspace_id,
+        db,
+    )
+    .await?;
+    windmill_api_auth::fetch_api_authed_from_permissioned_as(
+        route.permissioned_as.clone(),
+        email,
+        &route.workspace_id,
+        db,
+        Some(username_override.unwrap_or(format!("gRPC-{}", route.path))),
+    )
+    .await
+}
+
+/// Answers a server streaming call: every line the job streams is a response message, and a
+/// job streaming nothing answers with its result, one message per item when it is a list.
+async fn stream_job(
+    route: GrpcRoute,
+    db: DB,
+    user_db: UserDB,
+    authed: ApiAuthed,
+    args: PushArgsOwned,
+    trigger_metadata: TriggerMetadata,
+) -> Result<Response> {
+    let username = authed.username.clone();
+    let (uuid, delete_secs, early_return, has_failure_module, _) = trigger_runnable_inner(
+        &db,
+        None,
+        Some(user_db),
+        authed.clone(),
+        &route.workspace_id,
+        &route.script_path,
+        route.is_flow,
+        args,
+        route.retry.as_ref(),
+        route.error_handler_path.as_deref(),
+        route.error_handler_args.as_ref(),
+        format!("grpc_trigger/{}", route.path),
+        None,
+        trigger_metadata,
+        None,
+    )
+    .await?;
+
+    let (updates_tx, mut updates_rx) = tokio::sync::mpsc::channel(32);
+    start_job_update_sse_stream(
+        Some(authed),
+        OptTokened { token: None },
+        db.clone(),
+        route.workspace_id.clone(),
+        uuid,
+        None,
+        None,
+        None,
+        None,
+        Some(true),
+        Some(true),
+        None,
+        None,
+        updates_tx,
+        None,
+        early_return.clone(),
+        has_failure_module,
+        false,
+    );
+
+    let (frames_tx, frames_rx) = tokio::sync::mpsc::channel(32);
+    tokio::spawn(async move {
+        let status = forward_job(
+            &db,
+            uuid,
+            &route.workspace_id,
+            early_return,
+            has_failure_module,
+            &username,
+            &route.method.output(),
+            updates_rx,
+            &frames_tx,
+        )
+        .await;
+        let (code, message) = match status {
+            Ok(()) => (Code::Ok, None),
+            Err((code, message)) => (code, Some(message)),
+        };
+        let _ = frames_tx
+            .send(Ok(Frame::trailers(trailers(code, message.as_deref()))))
+            .await;
+
+        let deleted = match delete_secs {
+            Some(0) => delete_job_metadata_after_use(&db, uuid).await,
+            Some(secs) => schedule_job_deletion(&db, uuid, &route.workspace_id, secs).await,
+            None => Ok(()),
+        };
+        if let Err(err) = deleted {
+            tracing::error!(
+                "Error deleting job {} of gRPC trigger {}: {err:#}",
+                uuid,
+                route.path
+            );
+        }
+    });
+
+    Ok(grpc_response(Body::new(StreamBody::new(
+        tokio_stream::wrappers::ReceiverStream::new(frames_rx),
+    ))))
+}
+
+type CallStatus = std::result::Result<(), (Code, String)>;
+
+async fn forward_job(
+    db: &DB,
+    uuid: uuid::Uuid,
+    workspace_id: &str,
+    early_return: Option<String>,
+    has_failure_module: bool,
+    username: &str,
+    output: &MessageDescriptor,
+    mut updates: tokio::sync::mpsc::Receiver<JobUpdateSSEStream>,
+    frames: &FrameSender,
+) -> CallStatus {
+    let mut pending = String::new();
+    let mut streamed = false;
+
+    loop {
+        let update = match updates.recv().await {
+            Some(JobUpdateSSEStream::Update(update)) => update,
+            Some(JobUpdateSSEStream::Ping) => continue,
+            Some(JobUpdateSSEStream::Error { error }) => return Err((Code::Internal, error)),
+            Some(JobUpdateSSEStream::NotFound) => {
+                return Err((Code::NotFound, format!("Job {} not found", uuid)))
+            }
+            Some(JobUpdateSSEStream::Timeout) | None => {
+                return Err((
+                    Code::DeadlineExceeded,
+                    format!("Timed out waiting for job {}", uuid),
+                ))
+            }
+        };
+
+        if let Some(chunk) = update.new_result_stream {
+            pending.push_str(&chunk);
+            while let Some(end) = pending.find('\n') {
+                let line = pending.drain(..=end).collect::<String>();
+                streamed |= send_line(frames, output, &line).await?;
+            }
+        }
+        if update.completed.unwrap_or(false) {
+            break;
+        }
+    }
+    streamed |= send_line(frames, output, &pending).await?;
+
+    let (result, success) = run_wait_result_internal(
+        db,
+        uuid,
+        workspace_id,
+        early_return,
+        has_failure_module,
+        username,
+    )
+    .await
+    .map_err(|err| (Code::from_error(&err), err.to_string()))?;
+
+    if !success {
+        return Err((Code::Unknown, job_error_message(&result)));
+    }
+    if streamed {
+        return Ok(());
+    }
+
+    match serde_json::from_str::<Value>(result.get()) {
+        Ok(Value::Array(items)) => {
+            for item in items {
+                send_message(frames, output, &item).await?;
+            }
+            Ok(())
+        }
+        Ok(Value::Null) => Ok(()),
+        Ok(value) => send_message(frames, output, &value).await,
+        Err(err) => Err((Code::Internal, err.to_string())),
+    }
+}
+
+/// Sends a line the job streamed, blank lines being skipped. A line that is not JSON is sent
+/// as a string, which the wrapper well-known types accept.
+async fn send_line(
+    frames: &FrameSender,
+    output: &MessageDescriptor,
+    line: &str,
+) -> std::result::Result<bool, (Code, String)> {
+    let line = line.trim();
+    if line.is_empty() {
+        return Ok(false);
+    }
+    let value = serde_json::from_str(line).unwrap_or_else(|_| Value::String(line.to_string()));
+    send_message(frames, output, &value).await?;
+    Ok(true)
+}
+
+async fn send_message(
+    frames: &FrameSender,
+    output: &MessageDescriptor,
+    value: &Value,
+) -> CallStatus {
+    let message = encode_message(output.clone(), value).map_err(|err| (Code::Internal, err))?;
+    frames
+        .send(Ok(Frame::data(encode_frame(&message))))
+        .await
+        .map_err(|_| (Code::Cancelled, "The client went away".to_string()))
+}
+
+fn trailers(code: Code, message: Option<&str>) -> HeaderMap {
+    let mut trailers = HeaderMap::new();
+    if let Ok(status) = HeaderValue::from_str(&code.as_header_value()) {
+        trailers.insert("grpc-status", status);
+    }
+    if let Some(Ok(message)) = message.map(|m| HeaderValue::from_str(&encode_status_message(m))) {
+        trailers.insert("grpc-message", message);
+    }
+    trailers
+}
+
+fn grpc_response(body: Body) -> Response {
+    let mut response = Response::new(body);
+    *response.status_mut() = StatusCode::OK;
+    response.headers_mut().insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static(GRPC_CONTENT_TYPE),
+    );
+    response
+}
+
+/// A call that failed before any message, its status being sent with the headers.
+fn status_response(code: Code, message: &str) -> Response {
+    let mut response = grpc_response(Body::empty());
+    response.headers_mut().extend(trailers(code, Some(message)));
+    response
+}
+
+fn job_error_message(result: &RawValue) -> String {
+    match serde_json::from_str::<Value>(result.get()) {
+        Ok(value) => value
+            .get("error")
+            .and_then(|error| error.get("message"))
+            .and_then(Value::as_str)
+            .map(str::to_string)
+            .unwrap_or_else(|| value.to_string()),
+        Err(_) => result.get().to_string(),
+    }
+}
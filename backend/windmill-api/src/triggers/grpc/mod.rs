@@ -0,0 +1,3 @@
+pub use windmill_trigger_grpc::*;
+
+pub mod handler;
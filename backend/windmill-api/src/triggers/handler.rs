@@ -1,4 +1,7 @@
-use axum::{routing::post, Router};
+use axum::{
+    routing::{get, post},
+    Router,
+};
 use serde::{Deserialize, Serialize};
 use windmill_common::{error::JsonResult, DB};
 
@@ -71,6 +74,66 @@ pub fn generate_trigger_routers() -> Router {
         );
     }
 
+    #[cfg(feature = "postgres_notify_trigger")]
+    {
+        use crate::triggers::postgres_notify::PostgresNotifyTrigger;
+
+        router = router.nest(
+            PostgresNotifyTrigger::ROUTE_PREFIX,
+            complete_trigger_routes(PostgresNotifyTrigger),
+        );
+    }
+
+    #[cfg(feature = "mysql_trigger")]
+    {
+        use crate::triggers::mysql::MysqlTrigger;
+
+        router = router.nest(
+            MysqlTrigger::ROUTE_PREFIX,
+            complete_trigger_routes(MysqlTrigger),
+        );
+    }
+
+    #[cfg(feature = "redis_trigger")]
+    {
+        use crate::triggers::redis::RedisTrigger;
+
+        router = router.nest(
+            RedisTrigger::ROUTE_PREFIX,
+            complete_trigger_routes(RedisTrigger),
+        );
+    }
+
+    #[cfg(feature = "object_trigger")]
+    {
+        use crate::triggers::object::ObjectTrigger;
+
+        router = router.nest(
+            ObjectTrigger::ROUTE_PREFIX,
+            complete_trigger_routes(ObjectTrigger),
+        );
+    }
+
+    #[cfg(feature = "sftp_trigger")]
+    {
+        use crate::triggers::sftp::SftpTrigger;
+
+        router = router.nest(
+            SftpTrigger::ROUTE_PREFIX,
+            complete_trigger_routes(SftpTrigger),
+        );
+    }
+
+    #[cfg(feature = "grpc_trigger")]
+    {
+        use crate::triggers::grpc::GrpcTrigger;
+
+        router = router.nest(
+            GrpcTrigger::ROUTE_PREFIX,
+            complete_trigger_routes(GrpcTrigger),
+        );
+    }
+
     #[cfg(all(feature = "enterprise", feature = "sqs_trigger", feature = "private"))]
     {
         use crate::triggers::sqs::SqsTrigger;
@@ -125,6 +188,7 @@ pub fn generate_trigger_routers() -> Router {
         use windmill_trigger::global_handler::{
             cancel_suspended_trigger_jobs, resume_suspended_trigger_jobs,
         };
+        use windmill_trigger::metrics::get_trigger_metrics;
 
         router = router
             .route(
@@ -134,6 +198,10 @@ pub fn generate_trigger_routers() -> Router {
             .route(
                 "/trigger/{trigger_kind}/cancel_suspended_trigger_jobs/{*trigger_path}",
                 post(cancel_suspended_trigger_jobs),
+            )
+            .route(
+                "/triggers/{trigger_kind}/metrics/{*trigger_path}",
+                get(get_trigger_metrics),
             );
     }
 
@@ -154,6 +222,12 @@ pub struct TriggersCount {
     postgres_count: i64,
     mqtt_count: i64,
     amqp_count: i64,
+    postgres_notify_count: i64,
+    mysql_count: i64,
+    redis_count: i64,
+    object_count: i64,
+    sftp_count: i64,
+    grpc_count: i64,
     sqs_count: i64,
     gcp_count: i64,
     azure_count: i64,
@@ -271,6 +345,72 @@ pub async fn get_triggers_count_internal(
     #[cfg(not(feature = "amqp_trigger"))]
     let amqp_count = 0;
 
+    #[cfg(feature = "postgres_notify_trigger")]
+    let postgres_notify_count = {
+        use crate::triggers::postgres_notify::PostgresNotifyTrigger;
+        let count = PostgresNotifyTrigger
+            .trigger_count(&mut conn, w_id, is_flow, path)
+            .await;
+        count
+    };
+    #[cfg(not(feature = "postgres_notify_trigger"))]
+    let postgres_notify_count = 0;
+
+    #[cfg(feature = "mysql_trigger")]
+    let mysql_count = {
+        use crate::triggers::mysql::MysqlTrigger;
+        let count = MysqlTrigger
+            .trigger_count(&mut conn, w_id, is_flow, path)
+            .await;
+        count
+    };
+    #[cfg(not(feature = "mysql_trigger"))]
+    let mysql_count = 0;
+
+    #[cfg(feature = "redis_trigger")]
+    let redis_count = {
+        use crate::triggers::redis::RedisTrigger;
+        let count = RedisTrigger
+            .trigger_count(&mut conn, w_id, is_flow, path)
+            .await;
+        count
+    };
+    #[cfg(not(feature = "redis_trigger"))]
+    let redis_count = 0;
+
+    #[cfg(feature = "object_trigger")]
+    let object_count = {
+        use crate::triggers::object::ObjectTrigger;
+        let count = ObjectTrigger
+            .trigger_count(&mut conn, w_id, is_flow, path)
+            .await;
+        count
+    };
+    #[cfg(not(feature = "object_trigger"))]
+    let object_count = 0;
+
+    #[cfg(feature = "sftp_trigger")]
+    let sftp_count = {
+        use crate::triggers::sftp::SftpTrigger;
+        let count = SftpTrigger
+            .trigger_count(&mut conn, w_id, is_flow, path)
+            .await;
+        count
+    };
+    #[cfg(not(feature = "sftp_trigger"))]
+    let sftp_count = 0;
+
+    #[cfg(feature = "grpc_trigger")]
+    let grpc_count = {
+        use crate::triggers::grpc::GrpcTrigger;
+        let count = GrpcTrigger
+            .trigger_count(&mut conn, w_id, is_flow, path)
+            .await;
+        count
+    };
+    #[cfg(not(feature = "grpc_trigger"))]
+    let grpc_count = 0;
+
     #[cfg(all(feature = "sqs_trigger", feature = "enterprise", feature = "private"))]
     let sqs_count = {
         use crate::triggers::sqs::SqsTrigger;
@@ -393,6 +533,12 @@ pub async fn get_triggers_count_internal(
         postgres_count,
         mqtt_count,
         amqp_count,
+        postgres_notify_count,
+        mysql_count,
+        redis_count,
+        object_count,
+        sftp_count,
+        grpc_count,
         gcp_count,
         azure_count,
         sqs_count,
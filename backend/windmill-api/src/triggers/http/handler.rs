@@ -1,26 +1,34 @@
 use super::{
-    http_trigger_args::RawHttpTriggerArgs, refresh_routers, AuthenticationMethod, HttpMethod,
-    RequestType, TriggerRoute, HTTP_ACCESS_CACHE, HTTP_AUTH_CACHE, HTTP_ROUTERS_CACHE,
+    http_trigger_args::{HttpTriggerArgs, RawHttpTriggerArgs},
+    http_trigger_upload::{upload_request_body, UploadedObjects},
+    refresh_routers,
+    schema_validation::{get_runnable_validator, BodySchema},
+    upload::HttpUploadConfig,
+    AuthenticationMethod, HttpMethod, RequestType, TriggerRoute, HTTP_ACCESS_CACHE,
+    HTTP_AUTH_CACHE, HTTP_ROUTERS_CACHE,
 };
 use crate::{
+    args::{build_headers, build_query, Body},
     auth::{AuthCache, OptTokened},
     db::{ApiAuthed, DB},
     jobs::start_job_update_sse_stream,
     triggers::trigger_helpers::{
-        get_runnable_format, trigger_runnable, trigger_runnable_and_wait_for_result,
-        trigger_runnable_inner, RunnableId,
+        get_runnable_format, trigger_runnable, trigger_runnable_and_wait_for_raw_result,
+        trigger_runnable_and_wait_for_result, trigger_runnable_inner, trigger_runnables,
+        RunnableId,
     },
     utils::{check_scopes, ExpiringCacheEntry},
 };
 use axum::{
-    extract::Path,
+    extract::{FromRequest, Path},
     response::{IntoResponse, Response},
     routing::get,
     Extension, Router,
 };
 use futures::StreamExt;
-use http::{HeaderMap, StatusCode};
+use http::StatusCode;
 use std::{collections::HashMap, sync::Arc};
+use windmill_api_jobs::execution::result_to_response;
 use windmill_common::{
     db::UserDB,
     error::{Error, Result},
@@ -28,8 +36,9 @@ use windmill_common::{
     triggers::{TriggerKind, TriggerMetadata},
     utils::{not_found_if_none, StripPath},
 };
+use windmill_queue::PushArgsOwned;
 use windmill_store::resources::try_get_resource_from_db_as;
-use windmill_trigger::TriggerMode;
+use windmill_trigger::{transform::PayloadTransform, TriggerMode};
 
 #[cfg(feature = "parquet")]
 use {
@@ -231,16 +240,22 @@ async fn get_http_route_trigger(
     Ok((trigger.clone(), route_path.to_string(), params, authed))
 }
 
+enum RouteBody {
+    Parsed(HttpTriggerArgs),
+    Pending(axum::extract::Request, HttpUploadConfig),
+}
+
 async fn route_job(
     Extension(db): Extension<DB>,
     Extension(user_db): Extension<UserDB>,
     Extension(auth_cache): Extension<Arc<AuthCache>>,
     OptTokened { token }: OptTokened,
     Path(route_path): Path<StripPath>,
-    headers: HeaderMap,
-    args: RawHttpTriggerArgs,
+    request: axum::extract::Request,
 ) -> std::result::Result<impl IntoResponse, Response> {
     let route_path = route_path.to_path().trim_end_matches("/");
+    let headers = request.headers().clone();
+    let method = request.method().clone();
 
     let (trigger, called_path, params, authed) = get_http_route_trigger(
         route_path,
@@ -248,7 +263,7 @@ async fn route_job(
         token.as_ref(),
         &db,
         user_db.clone(),
-        &args.0.metadata.method,
+        &method,
     )
     .await
     .map_err(|e| e.into_response())?;
@@ -261,23 +276,33 @@ async fn route_job(
         .into_response());
     }
 
-    let args = args
-        .process_args(
-            &authed,
-            &db,
-            &trigger.workspace_id,
-            match trigger.authentication_method {
-                AuthenticationMethod::CustomScript | AuthenticationMethod::Signature => true,
-                _ => trigger.raw_string,
-            },
-        )
-        .await
-        .map_err(|e| e.into_response())?;
+    // Uploads are only streamed to storage once the request is authenticated and within its
+    // rate limit.
+    let body = match &trigger.upload_config {
+        Some(sqlx::types::Json(config)) => RouteBody::Pending(request, config.clone()),
+        None => RouteBody::Parsed(
+            RawHttpTriggerArgs::from_request(request, &())
+                .await?
+                .process_args(
+                    &authed,
+                    &db,
+                    &trigger.workspace_id,
+                    match trigger.authentication_method {
+                        AuthenticationMethod::CustomScript | AuthenticationMethod::Signature => {
+                            true
+                        }
+                        _ => trigger.raw_string,
+                    },
+                )
+                .await
+                .map_err(|e| e.into_response())?,
+        ),
+    };
 
-    match trigger.authentication_method {
+    let jwt_claims = match trigger.authentication_method {
         AuthenticationMethod::None
         | AuthenticationMethod::Windmill
-        | AuthenticationMethod::CustomScript => {}
+        | AuthenticationMethod::CustomScript => None,
         _ => {
             let resource_path = match trigger.authentication_resource_path {
                 Some(resource_path) => resource_path,
@@ -324,15 +349,32 @@ async fn route_job(
                 }
             };
 
-            let raw_payload = args.0.metadata.raw_string.as_ref();
+            let raw_payload = match &body {
+                RouteBody::Parsed(args) => args.0.metadata.raw_string.as_ref(),
+                RouteBody::Pending(..) => None,
+            };
 
-            let response = authentication_method
+            let authenticated = authentication_method
                 .authenticate_http_request(&headers, raw_payload)
+                .await
                 .map_err(|e| e.into_response())?;
 
-            if let Some(response) = response {
+            if let Some(response) = authenticated.challenge_response {
                 return Ok(response);
             }
+
+            authenticated.jwt_claims
+        }
+    };
+
+    if let Some(sqlx::types::Json(rate_limit)) = &trigger.rate_limit {
+        let client_key = rate_limit.client_key(&headers, jwt_claims.as_ref());
+        if let Some(exceeded) = rate_limit
+            .check(&db, &trigger.workspace_id, &trigger.path, &client_key)
+            .await
+            .map_err(|e| e.into_response())?
+        {
+            return Err(exceeded.into_response());
         }
     }
 
@@ -470,50 +512,125 @@ async fn route_job(
         }
     }
 
-    let runnable_format = get_runnable_format(
-        if trigger.is_flow {
-            RunnableId::from_flow_path(&trigger.script_path)
-        } else {
-            RunnableId::from_script_path(&trigger.script_path)
-        },
-        &trigger.workspace_id,
-        &db,
-        &TriggerKind::Http,
-    )
-    .await
-    .map_err(|e| e.into_response())?;
+    let validation = trigger.request_validation.as_ref().map(|v| &v.0);
 
-    let args = args
-        .to_args_from_format(
-            &trigger.route_path,
-            &called_path,
-            &trigger.path,
-            &params,
-            runnable_format,
-            trigger.wrap_body,
-        )
-        .map_err(|e| e.into_response())?;
+    // The query and headers are checked before a pending body is uploaded, so a request
+    // they reject never reaches the storage.
+    if let (Some(validation), RouteBody::Pending(request, _)) = (validation, &body) {
+        let query = build_query(request.uri().query(), None, true);
+        let headers = build_headers(request.headers(), None, true);
+        validation
+            .check_parameters(&query, &headers)
+            .map_err(|e| e.into_response())?;
+    }
+
+    let (args, uploaded) = match body {
+        RouteBody::Parsed(args) => (args, None),
+        RouteBody::Pending(request, config) => {
+            let (args, uploaded) =
+                upload_request_body(request, &config, &authed, &db, &trigger.workspace_id)
+                    .await
+                    .map_err(|e| e.into_response())?;
+            (args, Some(uploaded))
+        }
+    };
+
+    if let Some(validation) = validation {
+        let runnable_validator = match validation.body {
+            Some(BodySchema::Runnable) if trigger.payload_transform.is_none() => {
+                get_runnable_validator(
+                    &db,
+                    &trigger.workspace_id,
+                    &trigger.script_path,
+                    trigger.is_flow,
+                )
+                .await
+            }
+            _ => None,
+        };
+        let body = match &args.0.body {
+            Body::HashMap(body) => Some(body),
+            Body::NoHashMap(_) => None,
+        };
+        // A rejected request drops `uploaded`, which deletes its objects.
+        let checked = match uploaded {
+            Some(_) => validation.check_body(body, runnable_validator.as_deref()),
+            None => {
+                let query = build_query(args.0.metadata.query.as_deref(), None, true);
+                let headers = build_headers(&args.0.metadata.headers, None, true);
+                validation.check(body, runnable_validator.as_deref(), &query, &headers)
+            }
+        };
+        checked.map_err(|e| e.into_response())?;
+    }
+
+    let transform = PayloadTransform::new(trigger.payload_transform.as_deref());
+    let has_transform = transform.is_some();
+    let jobs_args = match transform {
+        Some(transform) => {
+            let event = args
+                .to_transform_event(
+                    &trigger.route_path,
+                    &called_path,
+                    &trigger.path,
+                    &params,
+                    jwt_claims.as_ref(),
+                )
+                .map_err(|e| e.into_response())?;
+            transform
+                .apply(event)
+                .await
+                .map_err(|e| e.into_response())?
+        }
+        None => {
+            let runnable_format = get_runnable_format(
+                if trigger.is_flow {
+                    RunnableId::from_flow_path(&trigger.script_path)
+                } else {
+                    RunnableId::from_script_path(&trigger.script_path)
+                },
+                &trigger.workspace_id,
+                &db,
+                &TriggerKind::Http,
+            )
+            .await
+            .map_err(|e| e.into_response())?;
+
+            let args = args
+                .to_args_from_format(
+                    &trigger.route_path,
+                    &called_path,
+                    &trigger.path,
+                    &params,
+                    jwt_claims.as_ref(),
+                    runnable_format,
+                    trigger.wrap_body,
+                )
+                .map_err(|e| e.into_response())?;
+            vec![args]
+        }
+    };
 
     let trigger_info = TriggerMetadata::new(Some(trigger.path.clone()), JobTriggerKind::Http);
     if trigger.mode == TriggerMode::Suspended {
-        let _ = trigger_runnable(
+        trigger_runnables(
             &db,
-            Some(user_db),
-            authed,
+            Some(user_db.clone()),
+            authed.clone(),
             &trigger.workspace_id,
             &trigger.script_path,
             trigger.is_flow,
-            args,
+            jobs_args,
             trigger.retry.as_ref(),
             trigger.error_handler_path.as_deref(),
             trigger.error_handler_args.as_ref(),
             format!("http_trigger/{}", trigger.path),
-            None,
             true,
-            trigger_info,
+            trigger_info.clone(),
         )
         .await
         .map_err(|e| e.into_response())?;
+        keep_uploads(uploaded);
 
         return Ok((
             StatusCode::OK,
@@ -525,6 +642,48 @@ async fn route_job(
             .into_response());
     }
 
+    // A transform may drop a request or split it into several jobs. Only an async route
+    // can answer that, with the ids of all the jobs pushed whatever their number: a sync
+    // one replies with the result of exactly one job.
+    if has_transform && trigger.request_type == RequestType::Async {
+        let uuids = trigger_runnables(
+            &db,
+            Some(user_db.clone()),
+            authed.clone(),
+            &trigger.workspace_id,
+            &trigger.script_path,
+            trigger.is_flow,
+            jobs_args,
+            trigger.retry.as_ref(),
+            trigger.error_handler_path.as_deref(),
+            trigger.error_handler_args.as_ref(),
+            format!("http_trigger/{}", trigger.path),
+            false,
+            trigger_info.clone(),
+        )
+        .await
+        .map_err(|e| e.into_response())?;
+        keep_uploads(uploaded);
+        let status = if uuids.is_empty() {
+            StatusCode::OK
+        } else {
+            StatusCode::CREATED
+        };
+        return Ok((status, axum::Json(uuids)).into_response());
+    }
+
+    let args = match <[PushArgsOwned; 1]>::try_from(jobs_args) {
+        Ok([args]) => args,
+        Err(jobs_args) => {
+            return Err(Error::BadRequest(format!(
+                "Payload transform of HTTP route {} produced {} jobs, but a sync route runs exactly one",
+                trigger.path,
+                jobs_args.len()
+            ))
+            .into_response())
+        }
+    };
+
     // Handle execution based on the execution mode
     match trigger.request_type {
         RequestType::SyncSse => {
@@ -548,6 +707,7 @@ async fn route_job(
             )
             .await
             .map_err(|e| e.into_response())?;
+            keep_uploads(uploaded);
 
             // Set up SSE stream
             let opt_authed = Some(authed.clone());
@@ -593,39 +753,78 @@ async fn route_job(
                 .body(body)
                 .map_err(|e| Error::internal_err(e.to_string()).into_response())?)
         }
-        RequestType::Async => trigger_runnable(
-            &db,
-            Some(user_db),
-            authed,
-            &trigger.workspace_id,
-            &trigger.script_path,
-            trigger.is_flow,
-            args,
-            trigger.retry.as_ref(),
-            trigger.error_handler_path.as_deref(),
-            trigger.error_handler_args.as_ref(),
-            format!("http_trigger/{}", trigger.path),
-            None,
-            false,
-            trigger_info,
-        )
-        .await
-        .map_err(|e| e.into_response()),
-        RequestType::Sync => trigger_runnable_and_wait_for_result(
-            &db,
-            Some(user_db),
-            authed,
-            &trigger.workspace_id,
-            &trigger.script_path,
-            trigger.is_flow,
-            args,
-            trigger.retry.as_ref(),
-            trigger.error_handler_path.as_deref(),
-            trigger.error_handler_args.as_ref(),
-            format!("http_trigger/{}", trigger.path),
-            trigger_info,
-        )
-        .await
-        .map_err(|e| e.into_response()),
+        RequestType::Async => {
+            let response = trigger_runnable(
+                &db,
+                Some(user_db),
+                authed,
+                &trigger.workspace_id,
+                &trigger.script_path,
+                trigger.is_flow,
+                args,
+                trigger.retry.as_ref(),
+                trigger.error_handler_path.as_deref(),
+                trigger.error_handler_args.as_ref(),
+                format!("http_trigger/{}", trigger.path),
+                None,
+                false,
+                trigger_info,
+            )
+            .await
+            .map_err(|e| e.into_response())?;
+            keep_uploads(uploaded);
+            Ok(response)
+        }
+        RequestType::Sync => {
+            // The job may still be running when waiting for it fails, so its uploads are
+            // kept from the start.
+            keep_uploads(uploaded);
+            match &trigger.response_mapping {
+                Some(sqlx::types::Json(response_mapping)) => {
+                    let (result, success) = trigger_runnable_and_wait_for_raw_result(
+                        &db,
+                        Some(user_db),
+                        authed,
+                        &trigger.workspace_id,
+                        &trigger.script_path,
+                        trigger.is_flow,
+                        args,
+                        trigger.retry.as_ref(),
+                        trigger.error_handler_path.as_deref(),
+                        trigger.error_handler_args.as_ref(),
+                        format!("http_trigger/{}", trigger.path),
+                        trigger_info,
+                    )
+                    .await
+                    .map_err(|e| e.into_response())?;
+
+                    result_to_response(response_mapping.apply(result, success), success)
+                        .map_err(|e| e.into_response())
+                }
+                None => trigger_runnable_and_wait_for_result(
+                    &db,
+                    Some(user_db),
+                    authed,
+                    &trigger.workspace_id,
+                    &trigger.script_path,
+                    trigger.is_flow,
+                    args,
+                    trigger.retry.as_ref(),
+                    trigger.error_handler_path.as_deref(),
+                    trigger.error_handler_args.as_ref(),
+                    format!("http_trigger/{}", trigger.path),
+                    trigger_info,
+                )
+                .await
+                .map_err(|e| e.into_response()),
+            }
+        }
+    }
+}
+
+/// The jobs of a request were pushed: its uploads are theirs now.
+fn keep_uploads(uploaded: Option<UploadedObjects>) {
+    if let Some(uploaded) = uploaded {
+        uploaded.keep();
     }
 }
@@ -14,7 +14,7 @@ use windmill_common::{
 };
 use windmill_queue::PushArgsOwned;
 
-use super::HttpMethod;
+use super::{http_trigger_auth::JwtClaims, HttpMethod};
 use crate::{
     args::{
         build_headers, build_query, try_from_request_body, Body, RawWebhookArgs, WebhookArgs,
@@ -74,6 +74,8 @@ struct HttpTriggerPreprocessorEvent<'a> {
     headers: HashMap<String, Box<RawValue>>,
     query: HashMap<String, Box<RawValue>>,
     method: HttpMethod,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    jwt_claims: Option<&'a JwtClaims>,
 }
 
 #[derive(Serialize)]
@@ -84,6 +86,8 @@ struct HttpTriggerWmTrigger<'a> {
     query: HashMap<String, Box<RawValue>>,
     headers: HashMap<String, Box<RawValue>>,
     method: HttpMethod,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    jwt_claims: Option<&'a JwtClaims>,
 }
 
 impl HttpTriggerArgs {
@@ -119,6 +123,7 @@ impl HttpTriggerArgs {
         called_path: &str,
         trigger_path: &str,
         params: &HashMap<String, String>,
+        jwt_claims: Option<&JwtClaims>,
         format: RunnableFormat,
         wrap_body: bool,
     ) -> Result<PushArgsOwned, Error> {
@@ -132,6 +137,7 @@ impl HttpTriggerArgs {
                     called_path,
                     trigger_path,
                     params,
+                    jwt_claims,
                     headers,
                     query,
                 )
@@ -141,6 +147,7 @@ impl HttpTriggerArgs {
                     route_path,
                     called_path,
                     params,
+                    jwt_claims,
                     wrap_body,
                     headers,
                     query,
@@ -149,11 +156,40 @@ impl HttpTriggerArgs {
         }
     }
 
+    /// The request as a v2 preprocessor receives it in `event`, which is what a payload
+    /// transform is evaluated on.
+    pub fn to_transform_event(
+        self,
+        route_path: &str,
+        called_path: &str,
+        trigger_path: &str,
+        params: &HashMap<String, String>,
+        jwt_claims: Option<&JwtClaims>,
+    ) -> Result<HashMap<String, Box<RawValue>>, Error> {
+        let headers = build_headers(&self.0.metadata.headers, None, true);
+        let query = build_query(self.0.metadata.query.as_deref(), None, true);
+        let mut args = self.to_v2_preprocessor_args(
+            route_path,
+            called_path,
+            trigger_path,
+            params,
+            jwt_claims,
+            headers,
+            query,
+        )?;
+        let event = args
+            .args
+            .remove("event")
+            .ok_or_else(|| Error::internal_err("Missing HTTP trigger event".to_string()))?;
+        Ok(serde_json::from_str(event.get())?)
+    }
+
     fn to_v1_preprocessor_args(
         self,
         route_path: &str,
         called_path: &str,
         params: &HashMap<String, String>,
+        jwt_claims: Option<&JwtClaims>,
         wrap_body: bool,
         headers: HashMap<String, Box<RawValue>>,
         query: HashMap<String, Box<RawValue>>,
@@ -170,6 +206,7 @@ impl HttpTriggerArgs {
                 params,
                 query,
                 headers,
+                jwt_claims,
             }),
         );
         extra.insert("wm_trigger".to_string(), to_raw_value(&wm_trigger));
@@ -187,6 +224,7 @@ impl HttpTriggerArgs {
         called_path: &str,
         trigger_path: &str,
         params: &HashMap<String, String>,
+        jwt_claims: Option<&JwtClaims>,
         headers: HashMap<String, Box<RawValue>>,
         query: HashMap<String, Box<RawValue>>,
     ) -> Result<PushArgsOwned, Error> {
@@ -204,6 +242,7 @@ impl HttpTriggerArgs {
                 path: called_path,
                 trigger_path,
                 params,
+                jwt_claims,
             }),
         );
         Ok(PushArgsOwned { args, extra: None })
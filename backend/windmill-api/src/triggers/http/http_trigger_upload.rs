@@ -0,0 +1,395 @@
+use axum::extract::Request;
+use windmill_common::{error::Error, DB};
+use windmill_types::s3::S3Object;
+
+use super::{http_trigger_args::HttpTriggerArgs, upload::HttpUploadConfig};
+use crate::db::ApiAuthed;
+
+/// Objects uploaded for a request. They are deleted when this is dropped, so that every
+/// path that gives up on the request, an error partway through the body, a validation,
+/// transform or push failure, cleans them up. Once the jobs are pushed, [`Self::keep`]
+/// hands the objects over to them.
+pub struct UploadedObjects {
+    objects: Vec<S3Object>,
+    config: HttpUploadConfig,
+    authed: ApiAuthed,
+    db: DB,
+    w_id: String,
+}
+
+impl UploadedObjects {
+    pub fn new(config: &HttpUploadConfig, authed: &ApiAuthed, db: &DB, w_id: &str) -> Self {
+        Self {
+            objects: Vec::new(),
+            config: config.clone(),
+            authed: authed.clone(),
+            db: db.clone(),
+            w_id: w_id.to_string(),
+        }
+    }
+
+    #[cfg(feature = "parquet")]
+    fn push(&mut self, object: S3Object) {
+        self.objects.push(object);
+    }
+
+    pub fn keep(mut self) {
+        self.objects.clear();
+    }
+}
+
+impl Drop for UploadedObjects {
+    fn drop(&mut self) {
+        if self.objects.is_empty() {
+            return;
+        }
+        let objects = std::mem::take(&mut self.objects);
+        let config = self.config.clone();
+        let authed = self.authed.clone();
+        let db = self.db.clone();
+        let w_id = self.w_id.clone();
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn(async move {
+                    delete_uploaded_objects(objects, &config, &authed, &db, &w_id).await;
+                });
+            }
+            Err(_) => tracing::warn!(
+                "Could not delete {} uploads of a rejected request: no runtime",
+                objects.len()
+            ),
+        }
+    }
+}
+
+#[cfg(not(feature = "parquet"))]
+pub async fn upload_request_body(
+    _request: Request,
+    _config: &HttpUploadConfig,
+    _authed: &ApiAuthed,
+    _db: &DB,
+    _w_id: &str,
+) -> Result<(HttpTriggerArgs, UploadedObjects), Error> {
+    Err(Error::BadRequest(
+        "Streaming uploads require the parquet feature".to_string(),
+    ))
+}
+
+#[cfg(not(feature = "parquet"))]
+async fn delete_uploaded_objects(
+    _objects: Vec<S3Object>,
+    _config: &HttpUploadConfig,
+    _authed: &ApiAuthed,
+    _db: &DB,
+    _w_id: &str,
+) {
+}
+
+/// Stream the body of `request` to the object storage of the route. A `multipart/form-data`
+/// body is split into its fields: text fields are kept as is, every file is uploaded as its
+/// own object. Any other body is uploaded whole and passed as the `body` argument. The
+/// uploaded objects are returned alongside the arguments, and deleted if the request
+/// fails partway through.
+#[cfg(feature = "parquet")]
+pub async fn upload_request_body(
+    request: Request,
+    config: &HttpUploadConfig,
+    authed: &ApiAuthed,
+    db: &DB,
+    w_id: &str,
+) -> Result<(HttpTriggerArgs, UploadedObjects), Error> {
+    use super::upload::{MAX_MULTIPART_FIELDS, MAX_MULTIPART_FILES};
+    use crate::{
+        args::{collapse_multipart_fields, Body, MultipartValue, WebhookArgs, WebhookArgsMetadata},
+        job_helpers_oss::get_workspace_s3_resource,
+    };
+    use futures::TryStreamExt;
+    use http::header::{CONTENT_LENGTH, CONTENT_TYPE};
+    use windmill_common::worker::to_raw_value;
+    use windmill_object_store::build_object_store_client;
+
+    let (parts, body) = request.into_parts();
+
+    let content_type = parts
+        .headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("application/octet-stream")
+        .to_string();
+
+    let is_multipart = content_type.starts_with("multipart/form-data");
+
+    if let Some(max_size) = config.max_size_bytes {
+        let content_length = parts
+            .headers
+            .get(CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<u64>().ok());
+        // A multipart body also carries its text fields and part headers, so only a plain
+        // body can be refused before reading it.
+        if !is_multipart && content_length.is_some_and(|length| length > max_size) {
+            return Err(Error::Generic(
+                http::StatusCode::PAYLOAD_TOO_LARGE,
+                format!("Request body exceeds the maximum upload size of {max_size} bytes"),
+            ));
+        }
+    }
+
+    let (_, s3_resource) =
+        get_workspace_s3_resource(authed, db, None, w_id, config.storage.clone()).await?;
+    let s3_resource = s3_resource.ok_or_else(|| {
+        windmill_object_store::workspace_storage_misconfigured(config.storage.as_deref())
+    })?;
+    let s3_client = build_object_store_client(&s3_resource).await?;
+
+    let mut objects = UploadedObjects::new(config, authed, db, w_id);
+
+    let body = if is_multipart {
+        let boundary = multer::parse_boundary(&content_type)
+            .map_err(|e| Error::BadRequest(format!("Invalid multipart body: {e}")))?;
+        let text_limit = *crate::REQUEST_SIZE_LIMIT.read().await;
+        let mut size_limit = multer::SizeLimit::new();
+        if let Some(limit) = config.multipart_size_limit(text_limit) {
+            size_limit = size_limit.whole_stream(limit);
+        }
+        let mut multipart = multer::Multipart::with_constraints(
+            body.into_data_stream(),
+            boundary,
+            multer::Constraints::new().size_limit(size_limit),
+        );
+
+        let mut fields: Vec<(String, MultipartValue)> = Vec::new();
+        let mut text_size = 0;
+        let mut files = 0;
+        let mut parts = 0;
+
+        while let Some(mut field) = multipart.next_field().await.map_err(multipart_error)? {
+            parts += 1;
+            if parts > MAX_MULTIPART_FIELDS {
+                return Err(Error::Generic(
+                    http::StatusCode::PAYLOAD_TOO_LARGE,
+                    format!("Multipart body has more than {MAX_MULTIPART_FIELDS} fields"),
+                ));
+            }
+
+            let Some(name) = field.name().map(|name| name.to_string()) else {
+                continue;
+            };
+
+            if field.file_name().is_some() {
+                files += 1;
+                if files > MAX_MULTIPART_FILES {
+                    return Err(Error::Generic(
+                        http::StatusCode::PAYLOAD_TOO_LARGE,
+                        format!("Multipart body has more than {MAX_MULTIPART_FILES} files"),
+                    ));
+                }
+
+                let filename = field.file_name().map(|filename| filename.to_string());
+                let part_content_type = field
+                    .content_type()
+                    .map(|mime| mime.to_string())
+                    .unwrap_or_else(|| "application/octet-stream".to_string());
+                let stream =
+                    field.map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err));
+
+                let object = upload_stream(
+                    &s3_client,
+                    config,
+                    db,
+                    w_id,
+                    stream,
+                    &part_content_type,
+                    filename,
+                )
+                .await?;
+
+                fields.push((
+                    name,
+                    MultipartValue::File(serde_json::to_value(&object).map_err(|e| {
+                        Error::internal_err(format!("Could not serialize S3 object: {e}"))
+                    })?),
+                ));
+                objects.push(object);
+            } else {
+                let mut text = Vec::new();
+                while let Some(chunk) = field.chunk().await.map_err(multipart_error)? {
+                    text_size += chunk.len();
+                    if text_size > text_limit {
+                        return Err(Error::Generic(
+                            http::StatusCode::PAYLOAD_TOO_LARGE,
+                            "Multipart text fields exceed the request size limit".to_string(),
+                        ));
+                    }
+                    text.extend_from_slice(&chunk);
+                }
+                let text = String::from_utf8(text).map_err(|e| {
+                    Error::BadRequest(format!("Multipart field {name} is not valid utf8: {e}"))
+                })?;
+                fields.push((name, MultipartValue::Text(text)));
+            }
+        }
+
+        Body::HashMap(collapse_multipart_fields(fields))
+    } else {
+        let stream = body
+            .into_data_stream()
+            .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err));
+
+        let object =
+            upload_stream(&s3_client, config, db, w_id, stream, &content_type, None).await?;
+
+        let body = Body::NoHashMap(to_raw_value(&object));
+        objects.push(object);
+        body
+    };
+
+    let args = HttpTriggerArgs(WebhookArgs {
+        body,
+        metadata: WebhookArgsMetadata {
+            headers: parts.headers,
+            query: parts.uri.query().map(|query| query.to_string()),
+            method: parts.method,
+            ..Default::default()
+        },
+    });
+
+    Ok((args, objects))
+}
+
+#[cfg(feature = "parquet")]
+fn multipart_error(e: multer::Error) -> Error {
+    match e {
+        multer::Error::StreamSizeExceeded { limit } => Error::Generic(
+            http::StatusCode::PAYLOAD_TOO_LARGE,
+            format!("Multipart body exceeds the limit of {limit} bytes"),
+        ),
+        e => Error::BadRequest(format!("Error reading multipart body: {e}")),
+    }
+}
+
+/// Remove the objects uploaded for a request that was then rejected. Failures are only
+/// logged: the request is refused either way.
+#[cfg(feature = "parquet")]
+async fn delete_uploaded_objects(
+    objects: Vec<S3Object>,
+    config: &HttpUploadConfig,
+    authed: &ApiAuthed,
+    db: &DB,
+    w_id: &str,
+) {
+    use crate::job_helpers_oss::get_workspace_s3_resource;
+    #[cfg(not(feature = "enterprise"))]
+    use crate::job_helpers_oss::spawn_storage_usage_recount_floored;
+    use windmill_object_store::{build_object_store_client, object_store_reexports::Path};
+
+    if objects.is_empty() {
+        return;
+    }
+
+    let s3_client =
+        match get_workspace_s3_resource(authed, db, None, w_id, config.storage.clone()).await {
+            Ok((_, Some(s3_resource))) => build_object_store_client(&s3_resource).await,
+            Ok((_, None)) => Err(windmill_object_store::workspace_storage_misconfigured(
+                config.storage.as_deref(),
+            )),
+            Err(e) => Err(e),
+        };
+    let s3_client = match s3_client {
+        Ok(s3_client) => s3_client,
+        Err(e) => {
+            tracing::warn!("Could not delete the uploads of a rejected request: {e}");
+            return;
+        }
+    };
+
+    for object in objects {
+        if let Err(e) = s3_client.delete(&Path::from(object.s3.as_str())).await {
+            tracing::warn!(
+                "Could not delete upload {} of a rejected request: {e}",
+                object.s3
+            );
+        }
+    }
+
+    #[cfg(not(feature = "enterprise"))]
+    spawn_storage_usage_recount_floored(db, w_id);
+}
+
+#[cfg(feature = "parquet")]
+async fn upload_stream(
+    s3_client: &std::sync::Arc<dyn windmill_object_store::object_store_reexports::ObjectStore>,
+    config: &HttpUploadConfig,
+    db: &DB,
+    w_id: &str,
+    stream: impl futures::Stream<Item = Result<bytes::Bytes, std::io::Error>> + Unpin,
+    content_type: &str,
+    filename: Option<String>,
+) -> Result<windmill_types::s3::S3Object, Error> {
+    use super::upload::{content_disposition, object_extension};
+    use crate::job_helpers_oss::upload_file_internal;
+    #[cfg(not(feature = "enterprise"))]
+    use crate::job_helpers_oss::{
+        bump_storage_usage, ce_storage_quota_remaining, spawn_storage_usage_recount_floored,
+    };
+    use windmill_object_store::object_store_reexports::{Attribute, Attributes};
+
+    if !config.is_content_type_allowed(content_type) {
+        return Err(Error::Generic(
+            http::StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            format!("Content type {content_type} is not accepted by this route"),
+        ));
+    }
+
+    let extension = filename
+        .as_deref()
+        .map(object_extension)
+        .unwrap_or_default();
+    let file_key = config.object_key(&format!("{}{}", uuid::Uuid::new_v4(), extension));
+
+    let options = Attributes::from_iter(vec![
+        (Attribute::ContentType, content_type.to_string()),
+        (
+            Attribute::ContentDisposition,
+            content_disposition(filename.as_deref()),
+        ),
+    ])
+    .into();
+
+    let max_size = config.max_size_bytes.map(|size| size as usize);
+    // file_key is always freshly random here, so this never overwrites an existing
+    // object; the full size is the delta.
+    #[cfg(not(feature = "enterprise"))]
+    let max_size = {
+        let remaining = ce_storage_quota_remaining(db, w_id, None).await? as usize;
+        Some(max_size.map_or(remaining, |size| size.min(remaining)))
+    };
+
+    match upload_file_internal(s3_client.clone(), &file_key, stream, options, max_size).await {
+        Ok((_, _size)) => {
+            #[cfg(not(feature = "enterprise"))]
+            bump_storage_usage(
+                db,
+                w_id,
+                config
+                    .storage
+                    .as_deref()
+                    .unwrap_or(windmill_object_store::DEFAULT_STORAGE),
+                _size as i64,
+            )
+            .await;
+        }
+        Err(e) => {
+            #[cfg(not(feature = "enterprise"))]
+            spawn_storage_usage_recount_floored(db, w_id);
+            return Err(e);
+        }
+    }
+
+    Ok(windmill_types::s3::S3Object {
+        s3: file_key,
+        storage: config.storage.clone(),
+        filename,
+        presigned: None,
+    })
+}
@@ -2,3 +2,4 @@ pub use windmill_trigger_http::*;
 
 pub mod handler;
 pub mod http_trigger_args;
+pub mod http_trigger_upload;
@@ -59,6 +59,50 @@ pub fn start_all_listeners(db: DB, killpill_rx: &tokio::sync::broadcast::Receive
         listen_to(AmqpTrigger, db.clone(), amqp_killpill_rx)
     }
 
+    #[cfg(feature = "postgres_notify_trigger")]
+    {
+        let postgres_notify_killpill_rx = killpill_rx.resubscribe();
+        use crate::triggers::postgres_notify::PostgresNotifyTrigger;
+
+        listen_to(
+            PostgresNotifyTrigger,
+            db.clone(),
+            postgres_notify_killpill_rx,
+        )
+    }
+
+    #[cfg(feature = "mysql_trigger")]
+    {
+        let mysql_killpill_rx = killpill_rx.resubscribe();
+        use crate::triggers::mysql::MysqlTrigger;
+
+        listen_to(MysqlTrigger, db.clone(), mysql_killpill_rx)
+    }
+
+    #[cfg(feature = "redis_trigger")]
+    {
+        let redis_killpill_rx = killpill_rx.resubscribe();
+        use crate::triggers::redis::RedisTrigger;
+
+        listen_to(RedisTrigger, db.clone(), redis_killpill_rx)
+    }
+
+    #[cfg(feature = "object_trigger")]
+    {
+        let object_killpill_rx = killpill_rx.resubscribe();
+        use crate::triggers::object::ObjectTrigger;
+
+        listen_to(ObjectTrigger, db.clone(), object_killpill_rx)
+    }
+
+    #[cfg(feature = "sftp_trigger")]
+    {
+        let sftp_killpill_rx = killpill_rx.resubscribe();
+        use crate::triggers::sftp::SftpTrigger;
+
+        listen_to(SftpTrigger, db.clone(), sftp_killpill_rx)
+    }
+
     #[cfg(feature = "websocket")]
     {
         let mqtt_killpill_rx = killpill_rx.resubscribe();
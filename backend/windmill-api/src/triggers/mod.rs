@@ -7,16 +7,28 @@ pub mod azure;
 pub mod email;
 #[cfg(all(feature = "gcp_trigger", feature = "enterprise", feature = "private"))]
 pub mod gcp;
+#[cfg(feature = "grpc_trigger")]
+pub mod grpc;
 #[cfg(feature = "http_trigger")]
 pub mod http;
 #[cfg(all(feature = "kafka", feature = "enterprise", feature = "private"))]
 pub mod kafka;
 #[cfg(feature = "mqtt_trigger")]
 pub mod mqtt;
+#[cfg(feature = "mysql_trigger")]
+pub mod mysql;
 #[cfg(all(feature = "nats", feature = "enterprise", feature = "private"))]
 pub mod nats;
+#[cfg(feature = "object_trigger")]
+pub mod object;
 #[cfg(feature = "postgres_trigger")]
 pub mod postgres;
+#[cfg(feature = "postgres_notify_trigger")]
+pub mod postgres_notify;
+#[cfg(feature = "redis_trigger")]
+pub mod redis;
+#[cfg(feature = "sftp_trigger")]
+pub mod sftp;
 #[cfg(all(feature = "sqs_trigger", feature = "enterprise", feature = "private"))]
 pub mod sqs;
 #[cfg(feature = "websocket")]
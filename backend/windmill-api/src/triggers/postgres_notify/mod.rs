@@ -0,0 +1 @@
+pub use windmill_trigger_postgres_notify::*;
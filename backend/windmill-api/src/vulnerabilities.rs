@@ -0,0 +1,551 @@
+/*
+ * Author: Windmill Labs
+ * Copyright: Windmill Labs, Inc 2026
+ * This file and its contents are licensed under the AGPLv3 License.
+ * Please see the included NOTICE for copyright information and
+ * LICENSE-AGPL for a copy of the license.
+ */
+
+use std::collections::{HashMap, HashSet};
+
+use axum::{
+    body::Bytes,
+    extract::{DefaultBodyLimit, Extension, Json, Path, Query},
+    routing::{get, post},
+    Router,
+};
+use serde::{Deserialize, Serialize};
+use windmill_audit::{audit_oss::audit_log, ActionKind};
+use windmill_common::{
+    apps::traverse_app_inline_scripts,
+    cache,
+    db::UserDB,
+    error::{Error, JsonResult, Result},
+    flows::{FlowModuleValue, FlowValue},
+    scripts::ScriptLang,
+    utils::require_admin,
+    vulnerabilities::{
+        advisory_rows, find_vulnerabilities, locked_packages, AdvisoryRow, OsvEntry, Severity,
+        Vulnerability,
+    },
+};
+
+use crate::db::{ApiAuthed, DB};
+use crate::utils::require_super_admin;
+
+/// OSV snapshots are large: the `all.zip` of npm alone is several hundred MB.
+const IMPORT_BODY_LIMIT: usize = 2 * 1024 * 1024 * 1024;
+const INSERT_BATCH_SIZE: usize = 1000;
+
+pub fn global_service() -> Router {
+    Router::new()
+        .route(
+            "/import",
+            post(import_advisories).layer(DefaultBodyLimit::max(IMPORT_BODY_LIMIT)),
+        )
+        .route("/status", get(get_status))
+}
+
+pub fn workspaced_service() -> Router {
+    Router::new()
+        .route("/list", get(list_vulnerabilities))
+        .route("/settings", get(get_settings).post(edit_settings))
+}
+
+#[derive(Serialize)]
+struct ImportResult {
+    entries: usize,
+    advisories: usize,
+    skipped: usize,
+    ecosystems: Vec<String>,
+}
+
+/// Imports an OSV snapshot: the `all.zip` of an ecosystem (https://osv.dev/docs/#tag/data-dumps),
+/// a JSON array of entries or newline-delimited entries. The advisories of the ecosystems it
+/// holds replace the previously imported ones.
+async fn import_advisories(
+    authed: ApiAuthed,
+    Extension(db): Extension<DB>,
+    body: Bytes,
+) -> JsonResult<ImportResult> {
+    require_super_admin(&db, &authed).await?;
+
+    let (entries, invalid) = parse_snapshot(&body).await?;
+    let mut rows: HashMap<(String, String, String), AdvisoryRow> = HashMap::new();
+    for row in entries.iter().flat_map(advisory_rows) {
+        rows.insert(
+            (row.ecosystem.clone(), row.package.clone(), row.id.clone()),
+            row,
+        );
+    }
+    let rows = rows.into_values().collect::<Vec<_>>();
+    let mut ecosystems = rows
+        .iter()
+        .map(|r| r.ecosystem.clone())
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+    ecosystems.sort();
+
+    let mut tx = db.begin().await?;
+    sqlx::query!(
+        "DELETE FROM osv_advisory WHERE ecosystem = ANY($1)",
+        &ecosystems
+    )
+    .execute(&mut *tx)
+    .await?;
+    for batch in rows.chunks(INSERT_BATCH_SIZE) {
+        let mut columns = AdvisoryColumns::default();
+        for row in batch {
+            columns.push(row)?;
+        }
+        sqlx::query!(
+            "INSERT INTO osv_advisory (ecosystem, package, id, summary, severity, aliases, ranges, versions, modified)
+            SELECT e, p, i, s, sev, ARRAY(SELECT jsonb_array_elements_text(a)), r, ARRAY(SELECT jsonb_array_elements_text(v)), m
+            FROM UNNEST($1::text[], $2::text[], $3::text[], $4::text[], $5::text[], $6::jsonb[], $7::jsonb[], $8::jsonb[], $9::timestamptz[])
+                AS t(e, p, i, s, sev, a, r, v, m)",
+            &columns.ecosystems,
+            &columns.packages,
+            &columns.ids,
+            &columns.summaries,
+            &columns.severities,
+            &columns.aliases,
+            &columns.ranges,
+            &columns.versions,
+            &columns.modified,
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+    audit_log(
+        &mut *tx,
+        &authed,
+        "vulnerabilities.import",
+        ActionKind::Update,
+        "global",
+        Some(&ecosystems.join(",")),
+        None,
+    )
+    .await?;
+    tx.commit().await?;
+
+    tracing::info!(
+        "Imported {} OSV advisories for {}",
+        rows.len(),
+        ecosystems.join(", ")
+    );
+    Ok(Json(ImportResult {
+        entries: entries.len(),
+        advisories: rows.len(),
+        skipped: invalid,
+        ecosystems,
+    }))
+}
+
+#[derive(Default)]
+struct AdvisoryColumns {
+    ecosystems: Vec<String>,
+    packages: Vec<String>,
+    ids: Vec<String>,
+    summaries: Vec<Option<String>>,
+    severities: Vec<String>,
+    aliases: Vec<serde_json::Value>,
+    ranges: Vec<serde_json::Value>,
+    versions: Vec<serde_json::Value>,
+    modified: Vec<Option<chrono::DateTime<chrono::Utc>>>,
+}
+
+impl AdvisoryColumns {
+    fn push(&mut self, row: &AdvisoryRow) -> Result<()> {
+        self.ecosystems.push(row.ecosystem.clone());
+        self.packages.push(row.package.clone());
+        self.ids.push(row.id.clone());
+        self.summaries.push(row.summary.clone());
+        self.severities.push(row.severity.as_str().to_string());
+        self.aliases.push(serde_json::to_value(&row.aliases)?);
+        self.ranges.push(serde_json::to_value(&row.ranges)?);
+        self.versions.push(serde_json::to_value(&row.versions)?);
+        self.modified.push(row.modified);
+        Ok(())
+    }
+}
+
+/// The entries of a snapshot, and the number of those that aren't valid OSV entries.
+async fn parse_snapshot(body: &[u8]) -> Result<(Vec<OsvEntry>, usize)> {
+    if body.starts_with(b"PK\x03\x04") {
+        return parse_zip_snapshot(body).await;
+    }
+    let text = std::str::from_utf8(body)
+        .map_err(|e| Error::BadRequest(format!("OSV snapshot is not valid UTF-8: {e}")))?
+        .trim();
+    if text.starts_with('[') {
+        let values: Vec<serde_json::Value> = serde_json::from_str(text)
+            .map_err(|e| Error::BadRequest(format!("Invalid OSV snapshot: {e}")))?;
+        return Ok(parse_entries(values));
+    }
+    if let Ok(entry) = serde_json::from_str::<OsvEntry>(text) {
+        return Ok((vec![entry], 0));
+    }
+    let mut invalid = 0;
+    let values = text
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| {
+            let value = serde_json::from_str(line).ok();
+            invalid += value.is_none() as usize;
+            value
+        })
+        .collect::<Vec<_>>();
+    let (entries, invalid_entries) = parse_entries(values);
+    Ok((entries, invalid + invalid_entries))
+}
+
+fn parse_entries(values: Vec<serde_json::Value>) -> (Vec<OsvEntry>, usize) {
+    let total = values.len();
+    let entries = values
+        .into_iter()
+        .filter_map(|v| serde_json::from_value::<OsvEntry>(v).ok())
+        .collect::<Vec<_>>();
+    let invalid = total - entries.len();
+    (entries, invalid)
+}
+
+#[cfg(feature = "zip")]
+async fn parse_zip_snapshot(body: &[u8]) -> Result<(Vec<OsvEntry>, usize)> {
+    let zip_err = |e: async_zip::error::ZipError| Error::BadRequest(format!("Invalid zip: {e}"));
+    let reader = async_zip::base::read::mem::ZipFileReader::new(body.to_vec())
+        .await
+        .map_err(zip_err)?;
+    let mut values = vec![];
+    let mut invalid = 0;
+    for index in 0..reader.file().entries().len() {
+        let entry = &reader.file().entries()[index];
+        if entry.dir().map_err(zip_err)?
+            || !entry
+                .filename()
+                .as_str()
+                .is_ok_and(|name| name.ends_with(".json"))
+        {
+            continue;
+        }
+        let mut content = String::new();
+        reader
+            .reader_with_entry(index)
+            .await
+            .map_err(zip_err)?
+            .read_to_string_checked(&mut content)
+            .await
+            .map_err(zip_err)?;
+        match serde_json::from_str(&content) {
+            Ok(value) => values.push(value),
+            Err(_) => invalid += 1,
+        }
+    }
+    let (entries, invalid_entries) = parse_entries(values);
+    Ok((entries, invalid + invalid_entries))
+}
+
+#[cfg(not(feature = "zip"))]
+async fn parse_zip_snapshot(_body: &[u8]) -> Result<(Vec<OsvEntry>, usize)> {
+    Err(Error::BadRequest(
+        "Importing zipped OSV snapshots requires the zip feature, import them as JSON instead"
+            .to_string(),
+    ))
+}
+
+#[derive(Serialize)]
+struct EcosystemStatus {
+    ecosystem: String,
+    advisories: i64,
+    packages: i64,
+    last_imported_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+async fn get_status(
+    authed: ApiAuthed,
+    Extension(db): Extension<DB>,
+) -> JsonResult<Vec<EcosystemStatus>> {
+    require_super_admin(&db, &authed).await?;
+    let status = sqlx::query_as!(
+        EcosystemStatus,
+        r#"SELECT ecosystem, COUNT(*) AS "advisories!", COUNT(DISTINCT package) AS "packages!",
+            MAX(imported_at) AS last_imported_at
+        FROM osv_advisory GROUP BY ecosystem ORDER BY ecosystem"#
+    )
+    .fetch_all(&db)
+    .await?;
+    Ok(Json(status))
+}
+
+#[derive(Deserialize)]
+struct ListVulnerabilitiesQuery {
+    /// `script`, `flow` or `app`
+    kind: Option<String>,
+    path_start: Option<String>,
+    min_severity: Option<Severity>,
+}
+
+#[derive(Serialize)]
+struct RunnableVulnerabilities {
+    kind: &'static str,
+    path: String,
+    /// The flow step or the app inline script, for flows and apps
+    step: Option<String>,
+    language: ScriptLang,
+    vulnerabilities: Vec<Vulnerability>,
+}
+
+/// The lock of a script, a flow step or an app inline script.
+pub(crate) struct RunnableLock {
+    pub kind: &'static str,
+    pub path: String,
+    /// The flow step or the app inline script, for flows and apps
+    pub step: Option<String>,
+    pub language: ScriptLang,
+    /// Empty for runnables without dependencies
+    pub lock: String,
+}
+
+/// The locks of the latest version of the scripts, flows and apps visible to the user, of the
+/// given kinds and at `path` or under `path_start`.
+pub(crate) async fn runnable_locks(
+    authed: &ApiAuthed,
+    db: &DB,
+    user_db: UserDB,
+    w_id: &str,
+    kinds: &[&str],
+    path: Option<&str>,
+    path_start: Option<&str>,
+) -> Result<Vec<RunnableLock>> {
+    let path_start = path_start.unwrap_or_default();
+    let mut locks = vec![];
+    let mut push =
+        |kind: &'static str, path: &str, step: Option<String>, language: ScriptLang, lock: &str| {
+            locks.push(RunnableLock {
+                kind,
+                path: path.to_string(),
+                step,
+                language,
+                lock: lock.to_string(),
+            })
+        };
+
+    let mut tx = user_db.begin(authed).await?;
+    if kinds.contains(&"script") {
+        let scripts = sqlx::query!(
+            r#"SELECT DISTINCT ON (path) path, language AS "language: ScriptLang", lock
+            FROM script
+            WHERE workspace_id = $1 AND ($2::text IS NULL OR path = $2) AND starts_with(path, $3)
+                AND archived = false AND deleted = false
+            ORDER BY path, created_at DESC"#,
+            w_id,
+            path,
+            path_start
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        for script in scripts {
+            let lock = script.lock.unwrap_or_default();
+            push("script", &script.path, None, script.language, &lock);
+        }
+    }
+    let flows = if kinds.contains(&"flow") {
+        sqlx::query!(
+            "SELECT path, versions[array_upper(versions, 1)] AS version
+            FROM flow
+            WHERE workspace_id = $1 AND ($2::text IS NULL OR path = $2) AND starts_with(path, $3)
+                AND archived = false",
+            w_id,
+            path,
+            path_start
+        )
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .filter_map(|f| Some((f.path, f.version?)))
+        .collect()
+    } else {
+        vec![]
+    };
+    let apps = if kinds.contains(&"app") {
+        sqlx::query!(
+            "SELECT path, versions[array_upper(versions, 1)] AS version
+            FROM app
+            WHERE workspace_id = $1 AND ($2::text IS NULL OR path = $2) AND starts_with(path, $3)",
+            w_id,
+            path,
+            path_start
+        )
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .filter_map(|a| Some((a.path, a.version?)))
+        .collect()
+    } else {
+        vec![]
+    };
+    tx.commit().await?;
+
+    for (path, version) in flows {
+        let flow = cache::flow::fetch_version(db, version).await?;
+        let flow_value = flow.value();
+        let mut modules = flow_value.modules.iter().collect::<Vec<_>>();
+        modules.extend(flow_value.failure_module.as_deref());
+        modules.extend(flow_value.preprocessor_module.as_deref());
+        FlowValue::traverse_leafs(modules, &mut |value, id| {
+            if let FlowModuleValue::RawScript { lock, language, .. } = value {
+                let lock = lock.as_deref().unwrap_or_default();
+                push("flow", &path, Some(id.clone()), *language, lock);
+            }
+            Ok(())
+        })
+        .map_err(|e| Error::internal_err(format!("Reading flow {path}: {e}")))?;
+    }
+    for (path, version) in apps {
+        let value = sqlx::query_scalar!("SELECT value FROM app_version WHERE id = $1", version)
+            .fetch_one(db)
+            .await?;
+        traverse_app_inline_scripts(&value, None, &mut |script, id| {
+            if let Some(language) = script.language {
+                push(
+                    "app",
+                    &path,
+                    id,
+                    language,
+                    script.lock.as_deref().unwrap_or_default(),
+                );
+            }
+            Ok(())
+        })?;
+    }
+    Ok(locks)
+}
+
+/// The vulnerable packages pinned by the locks of the latest version of the scripts, flows and
+/// apps visible to the user.
+async fn list_vulnerabilities(
+    authed: ApiAuthed,
+    Extension(db): Extension<DB>,
+    Extension(user_db): Extension<UserDB>,
+    Path(w_id): Path<String>,
+    Query(query): Query<ListVulnerabilitiesQuery>,
+) -> JsonResult<Vec<RunnableVulnerabilities>> {
+    let kinds = match query.kind.as_deref() {
+        None => vec!["script", "flow", "app"],
+        Some(kind @ ("script" | "flow" | "app")) => vec![kind],
+        Some(kind) => return Err(Error::BadRequest(format!("Unknown kind: {kind}"))),
+    };
+    let runnables = runnable_locks(
+        &authed,
+        &db,
+        user_db,
+        &w_id,
+        &kinds,
+        None,
+        query.path_start.as_deref(),
+    )
+    .await?
+    .into_iter()
+    .map(|runnable| {
+        let packages = locked_packages(&runnable.language, &runnable.lock);
+        (runnable, packages)
+    })
+    .filter(|(_, packages)| !packages.is_empty())
+    .collect::<Vec<_>>();
+
+    let packages = runnables
+        .iter()
+        .flat_map(|(_, packages)| packages.iter().cloned())
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+    let min_severity = query.min_severity.unwrap_or(Severity::Unknown);
+    let vulnerabilities = find_vulnerabilities(&db, &packages)
+        .await?
+        .into_iter()
+        .filter(|v| v.severity >= min_severity)
+        .collect::<Vec<_>>();
+
+    let report = runnables
+        .into_iter()
+        .filter_map(|(runnable, packages)| {
+            let vulnerabilities = vulnerabilities
+                .iter()
+                .filter(|v| {
+                    packages.iter().any(|p| {
+                        p.ecosystem == v.ecosystem && p.name == v.package && p.version == v.version
+                    })
+                })
+                .cloned()
+                .collect::<Vec<_>>();
+            (!vulnerabilities.is_empty()).then(|| RunnableVulnerabilities {
+                kind: runnable.kind,
+                path: runnable.path,
+                step: runnable.step,
+                language: runnable.language,
+                vulnerabilities,
+            })
+        })
+        .collect();
+    Ok(Json(report))
+}
+
+#[derive(Serialize, Deserialize)]
+struct VulnerabilitySettings {
+    block_critical_vulnerabilities: bool,
+}
+
+async fn get_settings(
+    _authed: ApiAuthed,
+    Extension(db): Extension<DB>,
+    Path(w_id): Path<String>,
+) -> JsonResult<VulnerabilitySettings> {
+    let block_critical_vulnerabilities = sqlx::query_scalar!(
+        "SELECT block_critical_vulnerabilities FROM workspace_settings WHERE workspace_id = $1",
+        &w_id
+    )
+    .fetch_optional(&db)
+    .await?
+    .unwrap_or(false);
+    Ok(Json(VulnerabilitySettings {
+        block_critical_vulnerabilities,
+    }))
+}
+
+async fn edit_settings(
+    authed: ApiAuthed,
+    Extension(db): Extension<DB>,
+    Path(w_id): Path<String>,
+    Json(settings): Json<VulnerabilitySettings>,
+) -> Result<String> {
+    require_admin(authed.is_admin, &authed.username)?;
+    let mut tx = db.begin().await?;
+    sqlx::query!(
+        "UPDATE workspace_settings SET block_critical_vulnerabilities = $1 WHERE workspace_id = $2",
+        settings.block_critical_vulnerabilities,
+        &w_id
+    )
+    .execute(&mut *tx)
+    .await?;
+    audit_log(
+        &mut *tx,
+        &authed,
+        "vulnerabilities.edit_settings",
+        ActionKind::Update,
+        &w_id,
+        None,
+        Some(HashMap::from([(
+            "block_critical_vulnerabilities",
+            if settings.block_critical_vulnerabilities {
+                "true"
+            } else {
+                "false"
+            },
+        )])),
+    )
+    .await?;
+    tx.commit().await?;
+    Ok(format!(
+        "Updated vulnerability settings of workspace {w_id}"
+    ))
+}
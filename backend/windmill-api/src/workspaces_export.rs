@@ -20,6 +20,12 @@ use windmill_api_auth::check_scopes;
     feature = "postgres_trigger",
     feature = "mqtt_trigger",
     feature = "amqp_trigger",
+    feature = "postgres_notify_trigger",
+    feature = "mysql_trigger",
+    feature = "redis_trigger",
+    feature = "object_trigger",
+    feature = "sftp_trigger",
+    feature = "grpc_trigger",
     all(
         feature = "enterprise",
         any(
@@ -188,6 +194,12 @@ pub fn is_none_or_false(val: &Option<bool>) -> bool {
     feature = "postgres_trigger",
     feature = "mqtt_trigger",
     feature = "amqp_trigger",
+    feature = "postgres_notify_trigger",
+    feature = "mysql_trigger",
+    feature = "redis_trigger",
+    feature = "object_trigger",
+    feature = "sftp_trigger",
+    feature = "grpc_trigger",
     feature = "native_trigger",
     all(
         feature = "enterprise",
@@ -234,6 +246,12 @@ async fn fork_parent_trigger_modes(
     feature = "postgres_trigger",
     feature = "mqtt_trigger",
     feature = "amqp_trigger",
+    feature = "postgres_notify_trigger",
+    feature = "mysql_trigger",
+    feature = "redis_trigger",
+    feature = "object_trigger",
+    feature = "sftp_trigger",
+    feature = "grpc_trigger",
     feature = "native_trigger",
     all(
         feature = "enterprise",
@@ -821,6 +839,10 @@ pub(crate) async fn tarball_workspace(
                 ScriptLang::Java => "java",
                 ScriptLang::Ruby => "rb",
                 ScriptLang::Rlang => "r",
+                ScriptLang::Lua => "lua",
+                ScriptLang::Kotlin => "kt",
+                ScriptLang::Perl => "pl",
+                ScriptLang::Wasm => "wit",
                 // A dbt script's content path is not `<path>.<ext>` — it is the
                 // descriptor inside the project folder, built below.
                 ScriptLang::Dbt => "",
@@ -1382,6 +1404,183 @@ pub(crate) async fn tarball_workspace(
             }
         }
 
+        #[cfg(feature = "postgres_notify_trigger")]
+        {
+            use crate::triggers::postgres_notify::PostgresNotifyTrigger;
+            let handler = PostgresNotifyTrigger;
+            let postgres_notify_triggers =
+                handler.list_triggers(&mut *tx, &w_id, None, None).await?;
+            let parent_modes = fork_parent_trigger_modes(
+                &db,
+                <PostgresNotifyTrigger as TriggerCrud>::TABLE_NAME,
+                parent_workspace_id.as_deref(),
+            )
+            .await?;
+
+            for trigger in postgres_notify_triggers {
+                let mode_override = trigger_mode_override(&parent_modes, &trigger.base.path);
+                let trigger_str = &to_string_without_metadata_inner(
+                    &trigger,
+                    ExtraPermsBehavior::Drop,
+                    None,
+                    mode_override.as_ref(),
+                )
+                .unwrap();
+                archive
+                    .write_to_archive(
+                        &trigger_str,
+                        &format!("{}.postgres_notify_trigger.json", trigger.base.path),
+                    )
+                    .await?;
+            }
+        }
+
+        #[cfg(feature = "mysql_trigger")]
+        {
+            use crate::triggers::mysql::MysqlTrigger;
+            let handler = MysqlTrigger;
+            let mysql_triggers = handler.list_triggers(&mut *tx, &w_id, None, None).await?;
+            let parent_modes = fork_parent_trigger_modes(
+                &db,
+                <MysqlTrigger as TriggerCrud>::TABLE_NAME,
+                parent_workspace_id.as_deref(),
+            )
+            .await?;
+
+            for trigger in mysql_triggers {
+                let mode_override = trigger_mode_override(&parent_modes, &trigger.base.path);
+                let trigger_str = &to_string_without_metadata_inner(
+                    &trigger,
+                    ExtraPermsBehavior::Drop,
+                    None,
+                    mode_override.as_ref(),
+                )
+                .unwrap();
+                archive
+                    .write_to_archive(
+                        &trigger_str,
+                        &format!("{}.mysql_trigger.json", trigger.base.path),
+                    )
+                    .await?;
+            }
+        }
+        #[cfg(feature = "redis_trigger")]
+        {
+            use crate::triggers::redis::RedisTrigger;
+            let handler = RedisTrigger;
+            let redis_triggers = handler.list_triggers(&mut *tx, &w_id, None, None).await?;
+            let parent_modes = fork_parent_trigger_modes(
+                &db,
+                <RedisTrigger as TriggerCrud>::TABLE_NAME,
+                parent_workspace_id.as_deref(),
+            )
+            .await?;
+
+            for trigger in redis_triggers {
+                let mode_override = trigger_mode_override(&parent_modes, &trigger.base.path);
+                let trigger_str = &to_string_without_metadata_inner(
+                    &trigger,
+                    ExtraPermsBehavior::Drop,
+                    None,
+                    mode_override.as_ref(),
+                )
+                .unwrap();
+                archive
+                    .write_to_archive(
+                        &trigger_str,
+                        &format!("{}.redis_trigger.json", trigger.base.path),
+                    )
+                    .await?;
+            }
+        }
+        #[cfg(feature = "object_trigger")]
+        {
+            use crate::triggers::object::ObjectTrigger;
+            let handler = ObjectTrigger;
+            let object_triggers = handler.list_triggers(&mut *tx, &w_id, None, None).await?;
+            let parent_modes = fork_parent_trigger_modes(
+                &db,
+                <ObjectTrigger as TriggerCrud>::TABLE_NAME,
+                parent_workspace_id.as_deref(),
+            )
+            .await?;
+
+            for trigger in object_triggers {
+                let mode_override = trigger_mode_override(&parent_modes, &trigger.base.path);
+                let trigger_str = &to_string_without_metadata_inner(
+                    &trigger,
+                    ExtraPermsBehavior::Drop,
+                    None,
+                    mode_override.as_ref(),
+                )
+                .unwrap();
+                archive
+                    .write_to_archive(
+                        &trigger_str,
+                        &format!("{}.object_trigger.json", trigger.base.path),
+                    )
+                    .await?;
+            }
+        }
+        #[cfg(feature = "sftp_trigger")]
+        {
+            use crate::triggers::sftp::SftpTrigger;
+            let handler = SftpTrigger;
+            let sftp_triggers = handler.list_triggers(&mut *tx, &w_id, None, None).await?;
+            let parent_modes = fork_parent_trigger_modes(
+                &db,
+                <SftpTrigger as TriggerCrud>::TABLE_NAME,
+                parent_workspace_id.as_deref(),
+            )
+            .await?;
+
+            for trigger in sftp_triggers {
+                let mode_override = trigger_mode_override(&parent_modes, &trigger.base.path);
+                let trigger_str = &to_string_without_metadata_inner(
+                    &trigger,
+                    ExtraPermsBehavior::Drop,
+                    None,
+                    mode_override.as_ref(),
+                )
+                .unwrap();
+                archive
+                    .write_to_archive(
+                        &trigger_str,
+                        &format!("{}.sftp_trigger.json", trigger.base.path),
+                    )
+                    .await?;
+            }
+        }
+        #[cfg(feature = "grpc_trigger")]
+        {
+            use crate::triggers::grpc::GrpcTrigger;
+            let handler = GrpcTrigger;
+            let grpc_triggers = handler.list_triggers(&mut *tx, &w_id, None, None).await?;
+            let parent_modes = fork_parent_trigger_modes(
+                &db,
+                <GrpcTrigger as TriggerCrud>::TABLE_NAME,
+                parent_workspace_id.as_deref(),
+            )
+            .await?;
+
+            for trigger in grpc_triggers {
+                let mode_override = trigger_mode_override(&parent_modes, &trigger.base.path);
+                let trigger_str = &to_string_without_metadata_inner(
+                    &trigger,
+                    ExtraPermsBehavior::Drop,
+                    None,
+                    mode_override.as_ref(),
+                )
+                .unwrap();
+                archive
+                    .write_to_archive(
+                        &trigger_str,
+                        &format!("{}.grpc_trigger.json", trigger.base.path),
+                    )
+                    .await?;
+            }
+        }
+
         #[cfg(all(feature = "enterprise", feature = "smtp", feature = "private"))]
         {
             use crate::triggers::email::EmailTrigger;
@@ -0,0 +1,348 @@
+//! Egress allowlists: the hosts, networks and ports the processes of a job may connect to.
+//!
+//! Scripts declare theirs in `egress_allow` header comments and worker groups in the
+//! `egress_allowlist` config, and the worker enforces both with a network namespace and a
+//! filtering proxy (see `egress_proxy` in windmill-worker). An entry is one of:
+//! - a hostname (`api.github.com`) or a domain wildcard (`*.amazonaws.com`, subdomains only)
+//! - an IP address or a CIDR (`10.0.0.0/8`, `fd00::/8`, `[::1]`)
+//!
+//! optionally followed by a port or a port range (`:443`, `:8000-8100`). Like the SSRF
+//! checks, a hostname entry never lets a job reach a private address: a host resolving to one
+//! is only reachable through an IP or CIDR entry, so DNS can't be used to reach internal
+//! services.
+
+use std::net::IpAddr;
+
+use crate::ssrf::is_private_ip;
+
+#[derive(Clone, Debug, PartialEq)]
+enum HostMatcher {
+    Name(String),
+    /// Strict subdomains of the domain
+    Wildcard(String),
+    Network(IpAddr, u8),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct EgressRule {
+    host: HostMatcher,
+    ports: Option<(u16, u16)>,
+}
+
+impl EgressRule {
+    pub fn parse(entry: &str) -> Result<Self, String> {
+        let invalid = |reason: &str| format!("invalid egress allowlist entry '{entry}': {reason}");
+        let entry = entry.trim();
+        if entry.is_empty() {
+            return Err(invalid("empty entry"));
+        }
+
+        let (host, prefix, ports) = if let Some(rest) = entry.strip_prefix('[') {
+            let (ip, rest) = rest.split_once(']').ok_or_else(|| invalid("missing ']'"))?;
+            let (prefix, ports) = match rest.strip_prefix('/') {
+                Some(rest) => split_port(rest),
+                None if rest.is_empty() => (None, None),
+                None => (
+                    None,
+                    Some(rest.strip_prefix(':').ok_or_else(|| invalid("bad port"))?),
+                ),
+            };
+            (ip, prefix, ports)
+        } else if let Some((ip, rest)) = entry.split_once('/') {
+            let (prefix, ports) = split_port(rest);
+            (ip, prefix, ports)
+        } else if entry.matches(':').count() > 1 {
+            // A bare IPv6 address, which can't carry a port without brackets
+            (entry, None, None)
+        } else {
+            match entry.split_once(':') {
+                Some((host, ports)) => (host, None, Some(ports)),
+                None => (entry, None, None),
+            }
+        };
+
+        let ports = ports
+            .map(|p| parse_ports(p).ok_or_else(|| invalid("bad port")))
+            .transpose()?;
+
+        let host = if let Ok(ip) = host.parse::<IpAddr>() {
+            let max = if ip.is_ipv4() { 32 } else { 128 };
+            let prefix = match prefix {
+                Some(p) => p
+                    .parse::<u8>()
+                    .ok()
+                    .filter(|p| *p <= max)
+                    .ok_or_else(|| invalid("bad prefix length"))?,
+                None => max,
+            };
+            HostMatcher::Network(ip, prefix)
+        } else if prefix.is_some() {
+            return Err(invalid("a prefix length needs an IP address"));
+        } else {
+            let host = host.trim_end_matches('.').to_ascii_lowercase();
+            let (wildcard, name) = match host.strip_prefix("*.") {
+                Some(domain) => (true, domain.to_string()),
+                None => (false, host),
+            };
+            if name.is_empty()
+                || !name.split('.').all(|l| {
+                    !l.is_empty()
+                        && l.chars()
+                            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+                })
+            {
+                return Err(invalid("not a hostname, IP address or CIDR"));
+            }
+            if wildcard {
+                HostMatcher::Wildcard(name)
+            } else {
+                HostMatcher::Name(name)
+            }
+        };
+
+        Ok(Self { host, ports })
+    }
+
+    fn permits(&self, host: &str, port: u16, addrs: &[IpAddr]) -> bool {
+        if self
+            .ports
+            .is_some_and(|(start, end)| port < start || port > end)
+        {
+            return false;
+        }
+        match &self.host {
+            HostMatcher::Name(name) => host == name && addrs.iter().all(|ip| !is_private_ip(ip)),
+            HostMatcher::Wildcard(domain) => {
+                host.strip_suffix(domain.as_str())
+                    .is_some_and(|sub| sub.len() > 1 && sub.ends_with('.'))
+                    && addrs.iter().all(|ip| !is_private_ip(ip))
+            }
+            HostMatcher::Network(net, prefix) => {
+                !addrs.is_empty() && addrs.iter().all(|ip| in_network(ip, net, *prefix))
+            }
+        }
+    }
+}
+
+fn split_port(s: &str) -> (Option<&str>, Option<&str>) {
+    match s.split_once(':') {
+        Some((prefix, ports)) => (Some(prefix), Some(ports)),
+        None => (Some(s), None),
+    }
+}
+
+fn parse_ports(s: &str) -> Option<(u16, u16)> {
+    let (start, end) = match s.split_once('-') {
+        Some((start, end)) => (start.parse().ok()?, end.parse().ok()?),
+        None => {
+            let port = s.parse().ok()?;
+            (port, port)
+        }
+    };
+    (start <= end).then_some((start, end))
+}
+
+fn in_network(ip: &IpAddr, net: &IpAddr, prefix: u8) -> bool {
+    let ip = match (ip, net) {
+        // Compare IPv4-mapped addresses with IPv4 networks
+        (IpAddr::V6(v6), IpAddr::V4(_)) => match v6.to_ipv4_mapped() {
+            Some(v4) => IpAddr::V4(v4),
+            None => return false,
+        },
+        _ => *ip,
+    };
+    match (ip, net) {
+        (IpAddr::V4(ip), IpAddr::V4(net)) => {
+            let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+            u32::from(ip) & mask == u32::from(*net) & mask
+        }
+        (IpAddr::V6(ip), IpAddr::V6(net)) => {
+            let mask = u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0);
+            u128::from(ip) & mask == u128::from(*net) & mask
+        }
+        _ => false,
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EgressAllowlist {
+    pub rules: Vec<EgressRule>,
+}
+
+impl EgressAllowlist {
+    pub fn parse<S: AsRef<str>>(entries: &[S]) -> Result<Self, String> {
+        let rules = entries
+            .iter()
+            .map(|e| EgressRule::parse(e.as_ref()))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { rules })
+    }
+
+    /// Reads the `egress_allow` lines of the leading comments of a script, whatever its comment
+    /// syntax (`#`, `//` or `--`), with entries separated by spaces or commas. `None` when the
+    /// script declares no allowlist.
+    pub fn from_code(code: &str) -> Result<Option<Self>, String> {
+        let mut entries = vec![];
+        let mut declared = false;
+        for line in code.lines() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let Some(comment) = ["//", "--", "#"].iter().find_map(|p| line.strip_prefix(p)) else {
+                break;
+            };
+            if let Some(rest) = comment.trim_start().strip_prefix("egress_allow") {
+                if rest.is_empty() || rest.starts_with(char::is_whitespace) {
+                    declared = true;
+                    entries.extend(rest.split(|c: char| c == ',' || c.is_whitespace()));
+                }
+            }
+        }
+        let entries: Vec<&str> = entries.into_iter().filter(|e| !e.is_empty()).collect();
+        declared.then(|| Self::parse(&entries)).transpose()
+    }
+
+    /// Whether a connection to `host`:`port`, which resolved to `addrs`, is allowed.
+    pub fn permits(&self, host: &str, port: u16, addrs: &[IpAddr]) -> bool {
+        let host = host.trim_end_matches('.').to_ascii_lowercase();
+        self.rules.iter().any(|r| r.permits(&host, port, addrs))
+    }
+}
+
+/// The allowlists a job is subject to: a connection must be allowed by each of them, so a
+/// script can narrow the allowlist of its worker group but not widen it.
+#[derive(Clone, Debug, Default)]
+pub struct EgressPolicy {
+    pub allowlists: Vec<EgressAllowlist>,
+}
+
+impl EgressPolicy {
+    pub fn for_job(code: &str, worker_group: Option<&EgressAllowlist>) -> Result<Self, String> {
+        let allowlists = worker_group
+            .cloned()
+            .into_iter()
+            .chain(EgressAllowlist::from_code(code)?)
+            .collect();
+        Ok(Self { allowlists })
+    }
+
+    /// Without any allowlist, jobs may connect anywhere.
+    pub fn is_restricted(&self) -> bool {
+        !self.allowlists.is_empty()
+    }
+
+    pub fn permits(&self, host: &str, port: u16, addrs: &[IpAddr]) -> bool {
+        self.allowlists.iter().all(|a| a.permits(host, port, addrs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    fn allowlist(entries: &[&str]) -> EgressAllowlist {
+        EgressAllowlist::parse(entries).unwrap()
+    }
+
+    #[test]
+    fn parses_entries() {
+        let rule = |e: &str| EgressRule::parse(e).unwrap();
+        assert_eq!(
+            rule("API.github.com.").host,
+            HostMatcher::Name("api.github.com".into())
+        );
+        assert_eq!(rule("*.amazonaws.com:443").ports, Some((443, 443)));
+        assert_eq!(
+            rule("10.0.0.0/8:5432-5433").host,
+            HostMatcher::Network(ip("10.0.0.0"), 8)
+        );
+        assert_eq!(rule("10.0.0.0/8:5432-5433").ports, Some((5432, 5433)));
+        assert_eq!(rule("fd00::/8").host, HostMatcher::Network(ip("fd00::"), 8));
+        assert_eq!(
+            rule("[::1]:8000").host,
+            HostMatcher::Network(ip("::1"), 128)
+        );
+        assert_eq!(rule("[::1]:8000").ports, Some((8000, 8000)));
+        assert_eq!(
+            rule("1.2.3.4").host,
+            HostMatcher::Network(ip("1.2.3.4"), 32)
+        );
+        for bad in [
+            "",
+            "10.0.0.0/33",
+            "example.com/8",
+            "host:99999",
+            "host:10-5",
+            "a b",
+            "*.",
+        ] {
+            assert!(EgressRule::parse(bad).is_err(), "{bad} should not parse");
+        }
+    }
+
+    #[test]
+    fn matches_hosts_ports_and_networks() {
+        let public = [ip("140.82.112.5")];
+        let list = allowlist(&["api.github.com:443", "*.amazonaws.com", "10.1.0.0/16:5432"]);
+        assert!(list.permits("api.github.com", 443, &public));
+        assert!(list.permits("API.GitHub.com.", 443, &public));
+        assert!(!list.permits("api.github.com", 80, &public));
+        assert!(!list.permits("github.com", 443, &public));
+        assert!(list.permits("s3.eu-west-1.amazonaws.com", 443, &public));
+        assert!(!list.permits("amazonaws.com", 443, &public));
+        assert!(!list.permits("evilamazonaws.com", 443, &public));
+        assert!(list.permits("db.internal", 5432, &[ip("10.1.2.3")]));
+        assert!(!list.permits("db.internal", 5432, &[ip("10.1.2.3"), ip("10.2.0.1")]));
+        assert!(!list.permits("db.internal", 22, &[ip("10.1.2.3")]));
+        assert!(list.permits("::ffff:10.1.0.9", 5432, &[ip("::ffff:10.1.0.9")]));
+    }
+
+    #[test]
+    fn hostnames_never_reach_private_addresses() {
+        let list = allowlist(&["api.github.com", "*.example.com"]);
+        assert!(!list.permits("api.github.com", 443, &[ip("169.254.169.254")]));
+        assert!(!list.permits("a.example.com", 443, &[ip("1.2.3.4"), ip("127.0.0.1")]));
+    }
+
+    #[test]
+    fn reads_allowlist_from_script_header() {
+        let code = "# requirements: requests\n# egress_allow api.github.com, *.s3.amazonaws.com:443\n#egress_allow 10.0.0.0/8\n\nimport requests\n# egress_allow ignored.com\n";
+        let list = EgressAllowlist::from_code(code).unwrap().unwrap();
+        assert_eq!(list.rules.len(), 3);
+        assert!(list.permits("api.github.com", 443, &[ip("1.1.1.1")]));
+        assert!(!list.permits("ignored.com", 443, &[ip("1.1.1.1")]));
+
+        assert_eq!(
+            EgressAllowlist::from_code("// egress_allowed x\nexport function main() {}").unwrap(),
+            None
+        );
+        // An empty declaration denies everything
+        let deny_all = EgressAllowlist::from_code("-- egress_allow\nSELECT 1")
+            .unwrap()
+            .unwrap();
+        assert!(!deny_all.permits("api.github.com", 443, &[ip("1.1.1.1")]));
+        assert!(EgressAllowlist::from_code("// egress_allow not/a/host").is_err());
+    }
+
+    #[test]
+    fn scripts_narrow_the_worker_group_allowlist() {
+        let group = allowlist(&["*.github.com"]);
+        let policy =
+            EgressPolicy::for_job("# egress_allow api.github.com\n", Some(&group)).unwrap();
+        let public = [ip("140.82.112.5")];
+        assert!(policy.permits("api.github.com", 443, &public));
+        assert!(!policy.permits("uploads.github.com", 443, &public));
+
+        let widened = EgressPolicy::for_job("# egress_allow example.com\n", Some(&group)).unwrap();
+        assert!(!widened.permits("example.com", 443, &[ip("93.184.216.34")]));
+
+        assert!(!EgressPolicy::for_job("print(1)", None)
+            .unwrap()
+            .is_restricted());
+    }
+}
@@ -274,6 +274,14 @@ pub const ENV_SETTINGS: &[&str] = &[
     "GEM_PATH",
     "RUBY_CONCURRENT_DOWNLOADS",
     "RSCRIPT_PATH",
+    "LUA_MAX_INSTRUCTIONS",
+    "LUA_MEMORY_LIMIT_MB",
+    "KOTLINC_PATH",
+    "PERL_PATH",
+    "CPANM_PATH",
+    "CPAN_MIRROR",
+    "WASM_MAX_FUEL",
+    "WASM_MEMORY_LIMIT_MB",
     // for related places search: ADD_NEW_LANG
     "GOPRIVATE",
     "GOPROXY",
@@ -51,6 +51,7 @@ pub mod deployment_requests_oss;
 #[cfg(feature = "private")]
 pub mod ee;
 pub mod ee_oss;
+pub mod egress;
 #[cfg(feature = "private")]
 pub mod email_ee;
 pub mod email_oss;
@@ -113,6 +114,7 @@ pub mod query_builders;
 pub mod queue;
 pub mod result_stream;
 pub mod runnable_settings;
+pub mod sbom;
 pub mod schedule;
 pub mod schema;
 pub mod scripts;
@@ -136,6 +138,7 @@ pub mod usernames;
 pub mod users;
 pub mod utils;
 pub mod variables;
+pub mod vulnerabilities;
 pub mod wac;
 pub mod webhook;
 pub mod worker;
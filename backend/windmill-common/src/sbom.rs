@@ -0,0 +1,443 @@
+//! CycloneDX software bills of materials (https://cyclonedx.org/docs/1.5/json/) of scripts,
+//! flows, apps and whole workspaces.
+//!
+//! The components are the packages pinned by the resolved locks (see
+//! [`crate::vulnerabilities::locked_packages`]) with their purl and the archive digests the
+//! lockfiles record, and the language runtimes the runnables run on. Each runnable depends on
+//! the components of its locks, so a workspace SBOM lists every component once and links it to
+//! the runnables using it.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use itertools::Itertools;
+use serde::Serialize;
+
+use crate::{
+    scripts::ScriptLang,
+    utils::GIT_VERSION,
+    vulnerabilities::{locked_packages, LockedPackage, PackageHash},
+    worker::{try_parse_locked_python_version_from_requirements, PyVAlias},
+};
+
+/// A script, flow or app, with the locks of its steps or inline scripts.
+pub struct SbomRunnable {
+    /// `script`, `flow` or `app`
+    pub kind: &'static str,
+    pub path: String,
+    pub locks: Vec<(ScriptLang, String)>,
+}
+
+impl SbomRunnable {
+    fn bom_ref(&self) -> String {
+        format!("{}:{}", self.kind, self.path)
+    }
+
+    fn component(&self) -> BomComponent {
+        BomComponent {
+            typ: "application",
+            bom_ref: Some(self.bom_ref()),
+            group: None,
+            name: self.path.clone(),
+            version: None,
+            purl: None,
+            hashes: vec![],
+            properties: vec![BomProperty { name: "windmill:kind", value: self.kind.to_string() }],
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Bom {
+    bom_format: &'static str,
+    spec_version: &'static str,
+    serial_number: String,
+    version: u32,
+    metadata: BomMetadata,
+    components: Vec<BomComponent>,
+    dependencies: Vec<BomDependency>,
+}
+
+#[derive(Serialize)]
+struct BomMetadata {
+    timestamp: chrono::DateTime<chrono::Utc>,
+    tools: BomTools,
+    component: BomComponent,
+}
+
+#[derive(Serialize)]
+struct BomTools {
+    components: Vec<BomComponent>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+struct BomComponent {
+    #[serde(rename = "type")]
+    typ: &'static str,
+    #[serde(rename = "bom-ref", skip_serializing_if = "Option::is_none")]
+    bom_ref: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    group: Option<String>,
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    purl: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    hashes: Vec<PackageHash>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    properties: Vec<BomProperty>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+struct BomProperty {
+    name: &'static str,
+    value: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BomDependency {
+    #[serde(rename = "ref")]
+    reference: String,
+    depends_on: Vec<String>,
+}
+
+/// The SBOM of a single script, flow or app.
+pub fn runnable_sbom(runnable: &SbomRunnable) -> Bom {
+    let mut builder = BomBuilder::default();
+    builder.add_runnable(runnable);
+    builder.build(runnable.component())
+}
+
+/// The SBOM of a workspace: its runnables are components depending on the packages and
+/// runtimes of their locks, each listed once.
+pub fn workspace_sbom(w_id: &str, runnables: &[SbomRunnable]) -> Bom {
+    let mut builder = BomBuilder::default();
+    let workspace_ref = format!("workspace:{w_id}");
+    for runnable in runnables {
+        let runnable_ref = builder.add_runnable(runnable);
+        builder
+            .components
+            .insert(runnable_ref.clone(), runnable.component());
+        builder.depend(&workspace_ref, runnable_ref);
+    }
+    builder.build(BomComponent {
+        typ: "application",
+        bom_ref: Some(workspace_ref),
+        group: None,
+        name: w_id.to_string(),
+        version: None,
+        purl: None,
+        hashes: vec![],
+        properties: vec![BomProperty { name: "windmill:kind", value: "workspace".to_string() }],
+    })
+}
+
+/// Components and dependencies keyed by bom-ref, so that shared ones are listed once.
+#[derive(Default)]
+struct BomBuilder {
+    components: BTreeMap<String, BomComponent>,
+    dependencies: BTreeMap<String, BTreeSet<String>>,
+}
+
+impl BomBuilder {
+    fn add_runnable(&mut self, runnable: &SbomRunnable) -> String {
+        let runnable_ref = runnable.bom_ref();
+        self.dependencies.entry(runnable_ref.clone()).or_default();
+        for (language, lock) in &runnable.locks {
+            if let Some(runtime) = runtime_component(language, lock) {
+                let runtime_ref = runtime.bom_ref.clone().unwrap_or_default();
+                self.components
+                    .entry(runtime_ref.clone())
+                    .or_insert(runtime);
+                self.depend(&runnable_ref, runtime_ref);
+            }
+            for package in locked_packages(language, lock) {
+                let package_ref = purl(&package);
+                let component = self
+                    .components
+                    .entry(package_ref.clone())
+                    .or_insert_with(|| package_component(&package, &package_ref));
+                // The same package may be pinned with and without digests by different locks
+                for hash in package.hashes {
+                    if !component.hashes.contains(&hash) {
+                        component.hashes.push(hash);
+                    }
+                }
+                self.depend(&runnable_ref, package_ref);
+            }
+        }
+        runnable_ref
+    }
+
+    fn depend(&mut self, from: &str, to: String) {
+        self.dependencies
+            .entry(from.to_string())
+            .or_default()
+            .insert(to);
+    }
+
+    fn build(self, subject: BomComponent) -> Bom {
+        Bom {
+            bom_format: "CycloneDX",
+            spec_version: "1.5",
+            serial_number: format!("urn:uuid:{}", uuid::Uuid::new_v4()),
+            version: 1,
+            metadata: BomMetadata {
+                timestamp: chrono::Utc::now(),
+                tools: BomTools {
+                    components: vec![BomComponent {
+                        typ: "application",
+                        bom_ref: None,
+                        group: None,
+                        name: "windmill".to_string(),
+                        version: Some(GIT_VERSION.to_string()),
+                        purl: None,
+                        hashes: vec![],
+                        properties: vec![],
+                    }],
+                },
+                component: subject,
+            },
+            components: self.components.into_values().collect(),
+            dependencies: self
+                .dependencies
+                .into_iter()
+                .map(|(reference, depends_on)| BomDependency {
+                    reference,
+                    depends_on: depends_on.into_iter().collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+fn package_component(package: &LockedPackage, purl: &str) -> BomComponent {
+    let (group, name) = split_namespace(package);
+    BomComponent {
+        typ: "library",
+        bom_ref: Some(purl.to_string()),
+        group: group.map(str::to_string),
+        name: name.to_string(),
+        version: Some(package.version.clone()),
+        purl: Some(purl.to_string()),
+        hashes: vec![],
+        properties: vec![],
+    }
+}
+
+/// The namespace (npm scope, Maven group, Composer vendor, Go module path) and the name of a
+/// package.
+fn split_namespace(package: &LockedPackage) -> (Option<&str>, &str) {
+    let name = package.name.as_str();
+    let split = match package.ecosystem {
+        "npm" if name.starts_with('@') => name.split_once('/'),
+        "Packagist" => name.split_once('/'),
+        "Maven" => name.split_once(':'),
+        "Go" => name.rsplit_once('/'),
+        _ => None,
+    };
+    match split {
+        Some((namespace, name)) => (Some(namespace), name),
+        None => (None, name),
+    }
+}
+
+/// The package URL (https://github.com/package-url/purl-spec) of a package.
+pub fn purl(package: &LockedPackage) -> String {
+    let typ = match package.ecosystem {
+        "PyPI" => "pypi",
+        "npm" => "npm",
+        "Go" => "golang",
+        "crates.io" => "cargo",
+        "Packagist" => "composer",
+        "Maven" => "maven",
+        "RubyGems" => "gem",
+        "NuGet" => "nuget",
+        _ => "generic",
+    };
+    let (namespace, name) = split_namespace(package);
+    let namespace = namespace
+        .map(|ns| format!("{}/", ns.split('/').map(purl_encode).join("/")))
+        .unwrap_or_default();
+    format!(
+        "pkg:{typ}/{namespace}{}@{}",
+        purl_encode(name),
+        purl_encode(&package.version)
+    )
+}
+
+fn purl_encode(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|b| {
+            if b.is_ascii_alphanumeric() || b"-._~".contains(&b) {
+                (b as char).to_string()
+            } else {
+                format!("%{b:02X}")
+            }
+        })
+        .collect()
+}
+
+/// The runtime a script of `language` runs on, with its version when the lock pins it.
+/// Query languages run on the database they target and have none.
+fn runtime_component(language: &ScriptLang, lock: &str) -> Option<BomComponent> {
+    let (name, version) = match language {
+        ScriptLang::Python3 => {
+            let lines = lock.lines().collect_vec();
+            let version = try_parse_locked_python_version_from_requirements(&lines)
+                // Locks without the version run on the default one, see `PyV::parse_from_requirements`
+                .unwrap_or_else(|| PyVAlias::default().into());
+            ("python", Some(version.to_string()))
+        }
+        ScriptLang::Go => {
+            let go_mod = lock.split("//go.sum\n").next().unwrap_or_default();
+            let version = go_mod
+                .lines()
+                .find_map(|l| l.trim().strip_prefix("go "))
+                .map(|v| v.trim().to_string());
+            ("go", version)
+        }
+        ScriptLang::Postgresql
+        | ScriptLang::Mysql
+        | ScriptLang::Bigquery
+        | ScriptLang::Snowflake
+        | ScriptLang::Mssql
+        | ScriptLang::OracleDB
+        | ScriptLang::Graphql
+        | ScriptLang::DuckDb => return None,
+        ScriptLang::Bun | ScriptLang::Bunnative => ("bun", None),
+        ScriptLang::CSharp => ("dotnet", None),
+        ScriptLang::Powershell => ("pwsh", None),
+        ScriptLang::Rlang => ("r", None),
+        language => (language.as_str(), None),
+    };
+    let bom_ref = match &version {
+        Some(version) => format!("runtime:{name}@{version}"),
+        None => format!("runtime:{name}"),
+    };
+    Some(BomComponent {
+        typ: "platform",
+        bom_ref: Some(bom_ref),
+        group: None,
+        name: name.to_string(),
+        version,
+        purl: None,
+        hashes: vec![],
+        properties: vec![],
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(ecosystem: &'static str, name: &str, version: &str) -> LockedPackage {
+        LockedPackage {
+            ecosystem,
+            name: name.to_string(),
+            version: version.to_string(),
+            hashes: vec![],
+        }
+    }
+
+    #[test]
+    fn test_purl() {
+        for (package, expected) in [
+            (
+                package("PyPI", "flask-cors", "4.0.0"),
+                "pkg:pypi/flask-cors@4.0.0",
+            ),
+            (
+                package("npm", "@types/node", "20.1.0"),
+                "pkg:npm/%40types/node@20.1.0",
+            ),
+            (
+                package("Go", "github.com/a/b", "v1.2.3"),
+                "pkg:golang/github.com/a/b@v1.2.3",
+            ),
+            (
+                package("Maven", "org.apache.logging.log4j:log4j-core", "2.14.1"),
+                "pkg:maven/org.apache.logging.log4j/log4j-core@2.14.1",
+            ),
+            (
+                package("Packagist", "guzzlehttp/guzzle", "7.4.0"),
+                "pkg:composer/guzzlehttp/guzzle@7.4.0",
+            ),
+            (
+                package("crates.io", "time", "0.1.45+build"),
+                "pkg:cargo/time@0.1.45%2Bbuild",
+            ),
+        ] {
+            assert_eq!(purl(&package), expected);
+        }
+    }
+
+    #[test]
+    fn test_workspace_sbom() {
+        let python = |deps: &str| (ScriptLang::Python3, format!("# py: 3.12\n{deps}"));
+        let runnables = [
+            SbomRunnable {
+                kind: "script",
+                path: "f/a/one".to_string(),
+                locks: vec![python("requests==2.31.0\nidna==3.4")],
+            },
+            SbomRunnable {
+                kind: "flow",
+                path: "f/a/flow".to_string(),
+                locks: vec![
+                    python("requests==2.31.0 --hash=sha256:00ff"),
+                    (ScriptLang::Bash, String::new()),
+                    (ScriptLang::Postgresql, String::new()),
+                ],
+            },
+        ];
+        let bom = serde_json::to_value(workspace_sbom("demo", &runnables)).unwrap();
+
+        let refs = bom["components"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|c| c["bom-ref"].as_str().unwrap())
+            .collect_vec();
+        assert_eq!(
+            refs,
+            vec![
+                "flow:f/a/flow",
+                "pkg:pypi/idna@3.4",
+                "pkg:pypi/requests@2.31.0",
+                "runtime:bash",
+                "runtime:python@3.12",
+                "script:f/a/one",
+            ]
+        );
+        assert_eq!(
+            bom["components"][2]["hashes"],
+            serde_json::json!([{"alg": "SHA-256", "content": "00ff"}])
+        );
+        assert_eq!(
+            bom["dependencies"],
+            serde_json::json!([
+                {"ref": "flow:f/a/flow", "dependsOn": ["pkg:pypi/requests@2.31.0", "runtime:bash", "runtime:python@3.12"]},
+                {"ref": "script:f/a/one", "dependsOn": ["pkg:pypi/idna@3.4", "pkg:pypi/requests@2.31.0", "runtime:python@3.12"]},
+                {"ref": "workspace:demo", "dependsOn": ["flow:f/a/flow", "script:f/a/one"]},
+            ])
+        );
+        assert_eq!(bom["metadata"]["component"]["name"], "demo");
+    }
+
+    #[test]
+    fn test_runtime_component() {
+        let python = runtime_component(&ScriptLang::Python3, "requests==2.31.0").unwrap();
+        assert_eq!(python.version.as_deref(), Some("3.12"));
+        let go = runtime_component(
+            &ScriptLang::Go,
+            "module mymod\n\ngo 1.21\n//go.sum\ngithub.com/a/b v1.2.3 h1:x=\n",
+        )
+        .unwrap();
+        assert_eq!(go.bom_ref.as_deref(), Some("runtime:go@1.21"));
+        assert!(runtime_component(&ScriptLang::Postgresql, "").is_none());
+    }
+}
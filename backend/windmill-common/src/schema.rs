@@ -509,6 +509,42 @@ impl SchemaValidator {
         Ok(())
     }
 
+    /// Same checks as [`Self::validate`], but reports every failing argument instead of
+    /// stopping at the first one.
+    pub fn validation_errors(&self, args: &HashMap<String, Box<RawValue>>) -> Vec<String> {
+        let mut errors = vec![];
+
+        for key in &self.required {
+            if !args.contains_key(key) {
+                errors.push(format!("Argument {key} is required"));
+            }
+        }
+
+        for (key, rules) in &self.rules {
+            if let Some(raw_val) = args.get(key) {
+                let parsed_val = match Value::from_str(raw_val.get()) {
+                    Ok(val) => val,
+                    Err(e) => {
+                        errors.push(format!("Failed to parse `{key}` argument: {e}"));
+                        continue;
+                    }
+                };
+                if let Some(e) = rules
+                    .iter()
+                    .map(|rule| rule.apply_rule(key, &parsed_val, self.required.contains(key)))
+                    .find_map(Result::err)
+                {
+                    errors.push(match e {
+                        Error::ArgumentErr(message) => message,
+                        e => e.to_string(),
+                    });
+                }
+            }
+        }
+
+        errors
+    }
+
     pub fn from_schema(schema: &str) -> Result<Self, Error> {
         let schema: Value = serde_json::from_str(schema)?;
 
@@ -161,8 +161,21 @@ pub async fn validate_url_for_ssrf(url: &str) -> Result<ValidatedTarget, SsrfVal
         "https" => 443,
         _ => 80,
     });
-    let resolve_target = format!("{host}:{port}");
-    let addrs: Vec<std::net::SocketAddr> = tokio::net::lookup_host(&resolve_target)
+    let addrs = resolve_host(host, port).await?;
+
+    for addr in &addrs {
+        if is_private_ip(&addr.ip()) {
+            return Err(SsrfValidationError::Private { resolved: true });
+        }
+    }
+
+    Ok(ValidatedTarget { host: host.to_string(), addrs })
+}
+
+/// Resolves `host` once, for callers that check the addresses and then connect to exactly
+/// those (see [`ValidatedTarget`]).
+pub async fn resolve_host(host: &str, port: u16) -> Result<Vec<SocketAddr>, SsrfValidationError> {
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host(format!("{host}:{port}"))
         .await
         .map_err(|e| SsrfValidationError::ResolutionFailed {
             host: host.to_string(),
@@ -173,14 +186,7 @@ pub async fn validate_url_for_ssrf(url: &str) -> Result<ValidatedTarget, SsrfVal
     if addrs.is_empty() {
         return Err(SsrfValidationError::NoAddresses(host.to_string()));
     }
-
-    for addr in &addrs {
-        if is_private_ip(&addr.ip()) {
-            return Err(SsrfValidationError::Private { resolved: true });
-        }
-    }
-
-    Ok(ValidatedTarget { host: host.to_string(), addrs })
+    Ok(addrs)
 }
 
 pub fn allow_private_mcp_server_urls() -> bool {
@@ -268,7 +274,7 @@ pub fn saml_ssrf_error_message(e: &SsrfValidationError) -> String {
     }
 }
 
-fn is_private_ip(ip: &IpAddr) -> bool {
+pub fn is_private_ip(ip: &IpAddr) -> bool {
     match ip {
         IpAddr::V4(ipv4) => is_private_ipv4(ipv4),
         IpAddr::V6(ipv6) => is_private_ipv6(ipv6),
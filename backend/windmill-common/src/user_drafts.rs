@@ -46,6 +46,12 @@ pub enum UserDraftItemKind {
     TriggerNats,
     TriggerMqtt,
     TriggerAmqp,
+    TriggerPostgresNotify,
+    TriggerMysql,
+    TriggerRedis,
+    TriggerObject,
+    TriggerSftp,
+    TriggerGrpc,
     TriggerSqs,
     TriggerGcp,
     TriggerAzure,
@@ -82,6 +88,12 @@ impl UserDraftItemKind {
             UserDraftItemKind::TriggerNats => "trigger_nats",
             UserDraftItemKind::TriggerMqtt => "trigger_mqtt",
             UserDraftItemKind::TriggerAmqp => "trigger_amqp",
+            UserDraftItemKind::TriggerPostgresNotify => "trigger_postgres_notify",
+            UserDraftItemKind::TriggerMysql => "trigger_mysql",
+            UserDraftItemKind::TriggerRedis => "trigger_redis",
+            UserDraftItemKind::TriggerObject => "trigger_object",
+            UserDraftItemKind::TriggerSftp => "trigger_sftp",
+            UserDraftItemKind::TriggerGrpc => "trigger_grpc",
             UserDraftItemKind::TriggerSqs => "trigger_sqs",
             UserDraftItemKind::TriggerGcp => "trigger_gcp",
             UserDraftItemKind::TriggerAzure => "trigger_azure",
@@ -96,7 +108,7 @@ impl UserDraftItemKind {
 
     /// Every variant, for code that must enumerate kinds (e.g. generating
     /// the `draft_only` existence SQL).
-    pub const ALL: [UserDraftItemKind; 26] = [
+    pub const ALL: [UserDraftItemKind; 32] = [
         UserDraftItemKind::Script,
         UserDraftItemKind::Flow,
         UserDraftItemKind::App,
@@ -114,6 +126,12 @@ impl UserDraftItemKind {
         UserDraftItemKind::TriggerNats,
         UserDraftItemKind::TriggerMqtt,
         UserDraftItemKind::TriggerAmqp,
+        UserDraftItemKind::TriggerPostgresNotify,
+        UserDraftItemKind::TriggerMysql,
+        UserDraftItemKind::TriggerRedis,
+        UserDraftItemKind::TriggerObject,
+        UserDraftItemKind::TriggerSftp,
+        UserDraftItemKind::TriggerGrpc,
         UserDraftItemKind::TriggerSqs,
         UserDraftItemKind::TriggerGcp,
         UserDraftItemKind::TriggerAzure,
@@ -148,6 +166,12 @@ impl UserDraftItemKind {
             TriggerNats => Some("nats_trigger"),
             TriggerMqtt => Some("mqtt_trigger"),
             TriggerAmqp => Some("amqp_trigger"),
+            TriggerPostgresNotify => Some("postgres_notify_trigger"),
+            TriggerMysql => Some("mysql_trigger"),
+            TriggerRedis => Some("redis_trigger"),
+            TriggerObject => Some("object_trigger"),
+            TriggerSftp => Some("sftp_trigger"),
+            TriggerGrpc => Some("grpc_trigger"),
             TriggerSqs => Some("sqs_trigger"),
             TriggerGcp => Some("gcp_trigger"),
             TriggerAzure => Some("azure_trigger"),
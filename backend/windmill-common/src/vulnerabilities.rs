@@ -0,0 +1,1099 @@
+//! Offline vulnerability scanning of script lockfiles.
+//!
+//! Superadmins import a snapshot of the OSV advisory database (https://osv.dev) into the
+//! `osv_advisory` table, one row per advisory and affected package. The resolved lock of a
+//! script, flow step or app inline script is turned into the packages it pins, which are
+//! matched against the advisories of their ecosystem with that ecosystem's version ordering.
+//! Workspaces can set `block_critical_vulnerabilities` to refuse locks pinning a package with
+//! a critical advisory.
+
+use std::{cmp::Ordering, str::FromStr};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use itertools::Itertools;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::{Error, Result},
+    scripts::ScriptLang,
+    DB,
+};
+
+lazy_static::lazy_static! {
+    // An entry of the `packages` of a bun.lock: `["name@version", "", {...}, "integrity"]`
+    static ref RE_BUN_LOCK_PACKAGE: Regex = Regex::new(
+        r#"(?m)^\s*"[^"]+":\s*\[\s*"((?:@[^@"/]+/)?[^@"]+)@([^"]+)"(?:.*"(sha\d+-[A-Za-z0-9+/=]+)")?"#
+    )
+    .unwrap();
+    static ref RE_PYPI_SEPARATORS: Regex = Regex::new(r"[-_.]+").unwrap();
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Unknown,
+    Low,
+    Moderate,
+    High,
+    Critical,
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Unknown => "unknown",
+            Severity::Low => "low",
+            Severity::Moderate => "moderate",
+            Severity::High => "high",
+            Severity::Critical => "critical",
+        }
+    }
+
+    fn from_cvss_score(score: f64) -> Self {
+        match score {
+            s if s >= 9.0 => Severity::Critical,
+            s if s >= 7.0 => Severity::High,
+            s if s >= 4.0 => Severity::Moderate,
+            s if s > 0.0 => Severity::Low,
+            _ => Severity::Unknown,
+        }
+    }
+}
+
+impl FromStr for Severity {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "unknown" => Ok(Severity::Unknown),
+            "low" => Ok(Severity::Low),
+            // CVSS calls it medium, GitHub advisories moderate
+            "moderate" | "medium" => Ok(Severity::Moderate),
+            "high" => Ok(Severity::High),
+            "critical" => Ok(Severity::Critical),
+            _ => Err(format!("unknown severity: {s}")),
+        }
+    }
+}
+
+/// The OSV ecosystems lockfiles are matched against.
+pub const SUPPORTED_ECOSYSTEMS: &[&str] = &[
+    "PyPI",
+    "npm",
+    "Go",
+    "crates.io",
+    "Packagist",
+    "Maven",
+    "RubyGems",
+    "NuGet",
+];
+
+/// Normalizes a package name the same way for lockfiles and advisories, so that matching
+/// is a plain equality.
+pub fn normalize_package_name(ecosystem: &str, name: &str) -> String {
+    match ecosystem {
+        // PEP 503
+        "PyPI" => RE_PYPI_SEPARATORS
+            .replace_all(&name.to_lowercase(), "-")
+            .into_owned(),
+        "Packagist" | "NuGet" => name.to_lowercase(),
+        _ => name.to_string(),
+    }
+}
+
+/// A package pinned by a lockfile.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct LockedPackage {
+    pub ecosystem: &'static str,
+    pub name: String,
+    pub version: String,
+    /// The digests of the package archive the lockfile records, if any
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub hashes: Vec<PackageHash>,
+}
+
+/// A digest of a package archive, with its algorithm named like CycloneDX does (`SHA-256`)
+/// and its content in lowercase hex.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct PackageHash {
+    pub alg: &'static str,
+    pub content: String,
+}
+
+impl PackageHash {
+    fn new(alg: &str, content: &[u8]) -> Option<Self> {
+        let alg = match alg.to_lowercase().replace('-', "").as_str() {
+            "sha1" => "SHA-1",
+            "sha256" => "SHA-256",
+            "sha384" => "SHA-384",
+            "sha512" => "SHA-512",
+            _ => return None,
+        };
+        (!content.is_empty()).then(|| PackageHash { alg, content: hex::encode(content) })
+    }
+
+    fn from_hex(alg: &str, content: &str) -> Option<Self> {
+        PackageHash::new(alg, &hex::decode(content.trim()).ok()?)
+    }
+
+    fn from_base64(alg: &str, content: &str) -> Option<Self> {
+        PackageHash::new(alg, &STANDARD.decode(content.trim()).ok()?)
+    }
+
+    /// A subresource integrity string, as npm lockfiles hold: `sha512-<base64>`
+    fn from_integrity(integrity: &str) -> Option<Self> {
+        let (alg, content) = integrity.split_once('-')?;
+        PackageHash::from_base64(alg, content)
+    }
+}
+
+impl LockedPackage {
+    fn new(ecosystem: &'static str, name: &str, version: &str) -> Self {
+        LockedPackage {
+            ecosystem,
+            name: normalize_package_name(ecosystem, name.trim()),
+            version: version.trim().to_string(),
+            hashes: vec![],
+        }
+    }
+
+    fn with_hashes(mut self, hashes: impl IntoIterator<Item = PackageHash>) -> Self {
+        self.hashes.extend(hashes);
+        self
+    }
+}
+
+/// The packages pinned by the lock of a script. Languages without a resolved lock, and
+/// locks that can't be read (e.g. a binary bun.lockb), give none.
+pub fn locked_packages(language: &ScriptLang, lock: &str) -> Vec<LockedPackage> {
+    let packages = match language {
+        ScriptLang::Python3 => python_packages(lock),
+        ScriptLang::Bun | ScriptLang::Bunnative => bun_packages(lock),
+        ScriptLang::Deno => json_after_header(lock)
+            .map(|lock| deno_packages(&lock))
+            .unwrap_or_default(),
+        ScriptLang::Go => go_packages(lock),
+        ScriptLang::Php => lock
+            .split_once("\nLOCK\n")
+            .and_then(|(_, lock)| json_after_header(lock))
+            .map(|lock| composer_packages(&lock))
+            .unwrap_or_default(),
+        ScriptLang::Rust => cargo_packages(lock),
+        ScriptLang::CSharp => json_after_header(lock)
+            .map(|lock| nuget_packages(&lock))
+            .unwrap_or_default(),
+        ScriptLang::Java | ScriptLang::Kotlin => maven_packages(lock),
+        ScriptLang::Ruby => gem_packages(lock),
+        _ => vec![],
+    };
+    packages.into_iter().unique().collect()
+}
+
+/// Locks may be prefixed by a workspace dependencies header.
+fn json_after_header(lock: &str) -> Option<serde_json::Value> {
+    serde_json::from_str(&lock[lock.find('{')?..]).ok()
+}
+
+fn python_packages(lock: &str) -> Vec<LockedPackage> {
+    lock.lines()
+        .filter_map(|line| {
+            let (requirement, options) = line.split_once(' ').unwrap_or((line, ""));
+            let requirement = requirement.split([';', '#']).next()?.trim();
+            let (name, version) = requirement.split_once("==")?;
+            let name = name.split('[').next()?;
+            // `--hash=sha256:<hex>` options, when the lock was compiled with hashes
+            let hashes = options
+                .split_whitespace()
+                .filter_map(|o| o.strip_prefix("--hash="))
+                .filter_map(|h| h.split_once(':'))
+                .filter_map(|(alg, content)| PackageHash::from_hex(alg, content));
+            Some(LockedPackage::new("PyPI", name, version).with_hashes(hashes))
+        })
+        .collect()
+}
+
+fn bun_packages(lock: &str) -> Vec<LockedPackage> {
+    let Some((_, bun_lock)) = lock.split_once("\n//bun.lock\n") else {
+        return vec![];
+    };
+    let packages = bun_lock.find("\"packages\"").map_or("", |i| &bun_lock[i..]);
+    RE_BUN_LOCK_PACKAGE
+        .captures_iter(packages)
+        // Skips workspace, git, file and link dependencies
+        .filter(|c| c[2].starts_with(|c: char| c.is_ascii_digit()))
+        .map(|c| {
+            let integrity = c
+                .get(3)
+                .and_then(|i| PackageHash::from_integrity(i.as_str()));
+            LockedPackage::new("npm", &c[1], &c[2]).with_hashes(integrity)
+        })
+        .collect()
+}
+
+fn deno_packages(lock: &serde_json::Value) -> Vec<LockedPackage> {
+    // `npm` is at the root since lockfile v4, under `packages` before
+    let npm = lock
+        .get("npm")
+        .or_else(|| lock.get("packages").and_then(|p| p.get("npm")));
+    let Some(npm) = npm.and_then(|npm| npm.as_object()) else {
+        return vec![];
+    };
+    npm.iter()
+        .filter_map(|(key, package)| {
+            // `name@version`, with peer dependencies appended as `_peer@version`
+            let at = key.get(1..)?.find('@')? + 1;
+            let version = key[at + 1..].split('_').next()?;
+            let integrity = package
+                .get("integrity")
+                .and_then(|i| i.as_str())
+                .and_then(PackageHash::from_integrity);
+            Some(LockedPackage::new("npm", &key[..at], version).with_hashes(integrity))
+        })
+        .collect()
+}
+
+fn go_packages(lock: &str) -> Vec<LockedPackage> {
+    let go_sum = lock
+        .split_once("//go.sum\n")
+        .map_or("", |(_, go_sum)| go_sum);
+    go_sum
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let (module, version) = (parts.next()?, parts.next()?);
+            // The go.mod only hashes duplicate the module ones
+            if version.ends_with("/go.mod") {
+                return None;
+            }
+            Some(LockedPackage::new("Go", module, version))
+        })
+        .collect()
+}
+
+fn composer_packages(lock: &serde_json::Value) -> Vec<LockedPackage> {
+    ["packages", "packages-dev"]
+        .iter()
+        .filter_map(|key| lock.get(key)?.as_array())
+        .flatten()
+        .filter_map(|package| {
+            let shasum = package
+                .get("dist")
+                .and_then(|d| d.get("shasum"))
+                .and_then(|s| s.as_str())
+                .and_then(|s| PackageHash::from_hex("sha1", s));
+            Some(
+                LockedPackage::new(
+                    "Packagist",
+                    package.get("name")?.as_str()?,
+                    package.get("version")?.as_str()?,
+                )
+                .with_hashes(shasum),
+            )
+        })
+        .collect()
+}
+
+fn cargo_packages(lock: &str) -> Vec<LockedPackage> {
+    let mut packages = vec![];
+    for package in lock.split("[[package]]").skip(1) {
+        let field = |key: &str| {
+            package.lines().find_map(|line| {
+                let (k, v) = line.split_once('=')?;
+                (k.trim() == key).then(|| v.trim().trim_matches('"'))
+            })
+        };
+        // Only crates.io packages have a registry source; path and git ones can't be matched
+        if !field("source").is_some_and(|s| s.starts_with("registry+")) {
+            continue;
+        }
+        if let (Some(name), Some(version)) = (field("name"), field("version")) {
+            let checksum = field("checksum").and_then(|c| PackageHash::from_hex("sha256", c));
+            packages.push(LockedPackage::new("crates.io", name, version).with_hashes(checksum));
+        }
+    }
+    packages
+}
+
+fn nuget_packages(lock: &serde_json::Value) -> Vec<LockedPackage> {
+    let Some(frameworks) = lock.get("dependencies").and_then(|d| d.as_object()) else {
+        return vec![];
+    };
+    frameworks
+        .values()
+        .filter_map(|deps| deps.as_object())
+        .flatten()
+        .filter_map(|(name, dep)| {
+            if dep.get("type").and_then(|t| t.as_str()) == Some("Project") {
+                return None;
+            }
+            let content_hash = dep
+                .get("contentHash")
+                .and_then(|h| h.as_str())
+                .and_then(|h| PackageHash::from_base64("sha512", h));
+            Some(
+                LockedPackage::new("NuGet", name, dep.get("resolved")?.as_str()?)
+                    .with_hashes(content_hash),
+            )
+        })
+        .collect()
+}
+
+fn maven_packages(lock: &str) -> Vec<LockedPackage> {
+    lock.lines()
+        .filter_map(|line| {
+            // `group:artifact:version`, possibly with a type or classifier in between
+            let parts = line.trim().split(':').collect_vec();
+            if parts.len() < 3 || parts.iter().any(|p| p.is_empty() || p.contains(' ')) {
+                return None;
+            }
+            let name = format!("{}:{}", parts[0], parts[1]);
+            Some(LockedPackage::new("Maven", &name, parts[parts.len() - 1]))
+        })
+        .collect()
+}
+
+fn gem_packages(lock: &str) -> Vec<LockedPackage> {
+    let mut packages = vec![];
+    let mut in_gem_specs = false;
+    let mut in_gem_section = false;
+    for line in lock.lines() {
+        if !line.starts_with(' ') {
+            in_gem_section = line.trim() == "GEM";
+            in_gem_specs = false;
+        } else if in_gem_section && line.trim() == "specs:" {
+            in_gem_specs = true;
+        } else if in_gem_specs && line.starts_with("    ") && !line.starts_with("     ") {
+            // `    name (version)`, the version possibly suffixed by a platform
+            let Some((name, version)) = line.trim().split_once(" (") else {
+                continue;
+            };
+            let version = version.trim_end_matches(')');
+            let version = version.split('-').next().unwrap_or(version);
+            packages.push(LockedPackage::new("RubyGems", name, version));
+        }
+    }
+    packages
+}
+
+/// An OSV entry, as found in the `all.zip` of an ecosystem or returned by the OSV API.
+#[derive(Debug, Deserialize)]
+pub struct OsvEntry {
+    pub id: String,
+    #[serde(default)]
+    pub summary: Option<String>,
+    #[serde(default)]
+    pub details: Option<String>,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    #[serde(default)]
+    pub modified: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(default)]
+    pub withdrawn: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(default)]
+    pub severity: Vec<OsvSeverity>,
+    #[serde(default)]
+    pub affected: Vec<OsvAffected>,
+    #[serde(default)]
+    pub database_specific: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OsvSeverity {
+    #[serde(rename = "type")]
+    pub typ: String,
+    pub score: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OsvAffected {
+    pub package: Option<OsvPackage>,
+    #[serde(default)]
+    pub severity: Vec<OsvSeverity>,
+    #[serde(default)]
+    pub ranges: Vec<OsvRange>,
+    #[serde(default)]
+    pub versions: Vec<String>,
+    #[serde(default)]
+    pub database_specific: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OsvPackage {
+    pub ecosystem: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OsvRange {
+    #[serde(rename = "type")]
+    pub typ: String,
+    #[serde(default)]
+    pub events: Vec<OsvEvent>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OsvEvent {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub introduced: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fixed: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_affected: Option<String>,
+}
+
+/// A row of `osv_advisory`.
+#[derive(Debug, Clone)]
+pub struct AdvisoryRow {
+    pub ecosystem: String,
+    pub package: String,
+    pub id: String,
+    pub summary: Option<String>,
+    pub severity: Severity,
+    pub aliases: Vec<String>,
+    pub ranges: Vec<OsvRange>,
+    pub versions: Vec<String>,
+    pub modified: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Splits an OSV entry into one row per affected package of a supported ecosystem.
+/// Withdrawn entries give none.
+pub fn advisory_rows(entry: &OsvEntry) -> Vec<AdvisoryRow> {
+    if entry.withdrawn.is_some() {
+        return vec![];
+    }
+    let summary = entry.summary.clone().filter(|s| !s.is_empty()).or_else(|| {
+        let first_line = entry.details.as_deref()?.lines().next()?.trim();
+        Some(first_line.chars().take(200).collect())
+    });
+    entry
+        .affected
+        .iter()
+        .filter_map(|affected| {
+            let package = affected.package.as_ref()?;
+            if !SUPPORTED_ECOSYSTEMS.contains(&package.ecosystem.as_str()) {
+                return None;
+            }
+            let severity = database_severity(affected.database_specific.as_ref())
+                .or_else(|| database_severity(entry.database_specific.as_ref()))
+                .or_else(|| cvss_severity(&affected.severity))
+                .or_else(|| cvss_severity(&entry.severity))
+                .unwrap_or(Severity::Unknown);
+            Some(AdvisoryRow {
+                ecosystem: package.ecosystem.clone(),
+                package: normalize_package_name(&package.ecosystem, &package.name),
+                id: entry.id.clone(),
+                summary: summary.clone(),
+                severity,
+                aliases: entry.aliases.clone(),
+                // Git ranges are commit hashes, which locks don't pin
+                ranges: affected
+                    .ranges
+                    .iter()
+                    .filter(|r| r.typ != "GIT")
+                    .cloned()
+                    .collect(),
+                versions: affected.versions.clone(),
+                modified: entry.modified,
+            })
+        })
+        .collect()
+}
+
+fn database_severity(database_specific: Option<&serde_json::Value>) -> Option<Severity> {
+    database_specific?.get("severity")?.as_str()?.parse().ok()
+}
+
+fn cvss_severity(severities: &[OsvSeverity]) -> Option<Severity> {
+    severities
+        .iter()
+        .filter(|s| s.typ == "CVSS_V3")
+        .find_map(|s| cvss_v3_base_score(&s.score))
+        .map(Severity::from_cvss_score)
+}
+
+/// The base score of a CVSS v3 vector, e.g. `CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H`.
+pub fn cvss_v3_base_score(vector: &str) -> Option<f64> {
+    let mut metrics = vector.split('/');
+    if !metrics.next()?.starts_with("CVSS:3") {
+        return None;
+    }
+    let metrics: std::collections::HashMap<&str, &str> =
+        metrics.filter_map(|m| m.split_once(':')).collect();
+    let metric = |name: &str| metrics.get(name).copied();
+
+    let scope_changed = match metric("S")? {
+        "U" => false,
+        "C" => true,
+        _ => return None,
+    };
+    let av = match metric("AV")? {
+        "N" => 0.85,
+        "A" => 0.62,
+        "L" => 0.55,
+        "P" => 0.2,
+        _ => return None,
+    };
+    let ac = match metric("AC")? {
+        "L" => 0.77,
+        "H" => 0.44,
+        _ => return None,
+    };
+    let pr = match (metric("PR")?, scope_changed) {
+        ("N", _) => 0.85,
+        ("L", false) => 0.62,
+        ("L", true) => 0.68,
+        ("H", false) => 0.27,
+        ("H", true) => 0.5,
+        _ => return None,
+    };
+    let ui = match metric("UI")? {
+        "N" => 0.85,
+        "R" => 0.62,
+        _ => return None,
+    };
+    let cia = |name: &str| match metric(name)? {
+        "H" => Some(0.56),
+        "L" => Some(0.22),
+        "N" => Some(0.0),
+        _ => None,
+    };
+    let iss = 1.0 - (1.0 - cia("C")?) * (1.0 - cia("I")?) * (1.0 - cia("A")?);
+    let impact = if scope_changed {
+        7.52 * (iss - 0.029) - 3.25 * (iss - 0.02f64).powi(15)
+    } else {
+        6.42 * iss
+    };
+    if impact <= 0.0 {
+        return Some(0.0);
+    }
+    let exploitability = 8.22 * av * ac * pr * ui;
+    let score = if scope_changed {
+        1.08 * (impact + exploitability)
+    } else {
+        impact + exploitability
+    };
+    Some(round_up(score.min(10.0)))
+}
+
+/// The `Roundup` of the CVSS v3.1 specification, which avoids floating point artifacts.
+fn round_up(value: f64) -> f64 {
+    let int_input = (value * 100_000.0).round() as i64;
+    if int_input % 10_000 == 0 {
+        int_input as f64 / 100_000.0
+    } else {
+        ((int_input / 10_000) + 1) as f64 / 10.0
+    }
+}
+
+/// Whether `version` is affected according to the OSV `ranges` and `versions` of an
+/// advisory, compared with the version ordering of the ecosystem.
+pub fn is_affected(
+    ecosystem: &str,
+    version: &str,
+    ranges: &[OsvRange],
+    versions: &[String],
+) -> bool {
+    match ecosystem {
+        "PyPI" => affected_by(version, ranges, versions, |v| {
+            pep440_rs::Version::from_str(v).ok()
+        }),
+        "npm" | "crates.io" => affected_by(version, ranges, versions, |v| {
+            semver::Version::parse(v).ok()
+        }),
+        "Go" => affected_by(version, ranges, versions, |v| {
+            let v = v.strip_prefix('v').unwrap_or(v);
+            semver::Version::parse(v.trim_end_matches("+incompatible")).ok()
+        }),
+        _ => affected_by(version, ranges, versions, |v| {
+            Some(GenericVersion::parse(v))
+        }),
+    }
+}
+
+fn affected_by<V: Ord>(
+    version: &str,
+    ranges: &[OsvRange],
+    versions: &[String],
+    parse: impl Fn(&str) -> Option<V>,
+) -> bool {
+    let Some(version) = parse(version) else {
+        return false;
+    };
+    if versions.iter().any(|v| parse(v).as_ref() == Some(&version)) {
+        return true;
+    }
+    ranges
+        .iter()
+        .any(|range| affected_in_range(&version, range, &parse).unwrap_or(false))
+}
+
+/// Follows the evaluation of the OSV schema: the events are sorted by version, and each
+/// `introduced` at or below the version makes it affected until a later `fixed` or
+/// `last_affected` does not. A range with an unparsable event is skipped.
+fn affected_in_range<V: Ord>(
+    version: &V,
+    range: &OsvRange,
+    parse: &impl Fn(&str) -> Option<V>,
+) -> Option<bool> {
+    enum Event<V> {
+        Introduced(Option<V>),
+        Fixed(V),
+        LastAffected(V),
+    }
+    impl<V> Event<V> {
+        fn version(&self) -> Option<&V> {
+            match self {
+                Event::Introduced(v) => v.as_ref(),
+                Event::Fixed(v) | Event::LastAffected(v) => Some(v),
+            }
+        }
+    }
+    let mut events = range
+        .events
+        .iter()
+        .filter_map(|event| {
+            if let Some(introduced) = &event.introduced {
+                Some(if introduced == "0" {
+                    Some(Event::Introduced(None))
+                } else {
+                    parse(introduced).map(|v| Event::Introduced(Some(v)))
+                })
+            } else if let Some(fixed) = &event.fixed {
+                Some(parse(fixed).map(Event::Fixed))
+            } else {
+                event
+                    .last_affected
+                    .as_ref()
+                    .map(|v| parse(v).map(Event::LastAffected))
+            }
+        })
+        .collect::<Option<Vec<_>>>()?;
+
+    // `None`, the introduction at "0", sorts first
+    events.sort_by(|a, b| a.version().cmp(&b.version()));
+
+    let mut affected = false;
+    for event in &events {
+        match event {
+            Event::Introduced(None) => affected = true,
+            Event::Introduced(Some(v)) if version >= v => affected = true,
+            Event::Fixed(v) if version >= v => affected = false,
+            Event::LastAffected(v) if version > v => affected = false,
+            _ => {}
+        }
+    }
+    Some(affected)
+}
+
+/// A loose version ordering for the ecosystems without a dedicated one (Maven, RubyGems,
+/// Packagist, NuGet): numeric parts compare numerically, and a textual part right after
+/// the numbers marks a pre-release, so `1.0-beta` < `1.0` < `1.0.1`.
+#[derive(Debug, PartialEq, Eq)]
+struct GenericVersion(Vec<VersionToken>);
+
+#[derive(Debug, PartialEq, Eq)]
+enum VersionToken {
+    Num(u64),
+    Text(String),
+}
+
+impl GenericVersion {
+    fn parse(version: &str) -> Self {
+        let version = version.trim().trim_start_matches(['v', 'V']);
+        let mut tokens = vec![];
+        let mut chars = version.chars().peekable();
+        while let Some(&c) = chars.peek() {
+            if c.is_ascii_digit() {
+                let mut n = String::new();
+                while let Some(d) = chars.next_if(|c| c.is_ascii_digit()) {
+                    n.push(d);
+                }
+                tokens.push(VersionToken::Num(n.parse().unwrap_or(u64::MAX)));
+            } else if c.is_alphabetic() {
+                let mut s = String::new();
+                while let Some(a) = chars.next_if(|c| c.is_alphabetic()) {
+                    s.extend(a.to_lowercase());
+                }
+                tokens.push(VersionToken::Text(s));
+            } else {
+                chars.next();
+            }
+        }
+        // Zeros ending the numbers don't matter: 1.0 == 1.0.0 and 1.0-beta == 1-beta
+        let mut normalized = vec![];
+        let mut ends_numbers = true;
+        for token in tokens.into_iter().rev() {
+            if ends_numbers && token == VersionToken::Num(0) {
+                continue;
+            }
+            ends_numbers = matches!(token, VersionToken::Text(_));
+            normalized.push(token);
+        }
+        normalized.reverse();
+        GenericVersion(normalized)
+    }
+}
+
+impl Ord for GenericVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        use VersionToken::*;
+        for (a, b) in self.0.iter().zip(other.0.iter()) {
+            let ordering = match (a, b) {
+                (Num(a), Num(b)) => a.cmp(b),
+                (Text(a), Text(b)) => a.cmp(b),
+                (Num(_), Text(_)) => Ordering::Greater,
+                (Text(_), Num(_)) => Ordering::Less,
+            };
+            if ordering != Ordering::Equal {
+                return ordering;
+            }
+        }
+        // The longer one is greater if it goes on with a number, and a pre-release if it
+        // goes on with text
+        let longer_is_greater = |rest: Option<&VersionToken>| !matches!(rest, Some(Text(_)));
+        match self.0.len().cmp(&other.0.len()) {
+            Ordering::Equal => Ordering::Equal,
+            Ordering::Greater if longer_is_greater(self.0.get(other.0.len())) => Ordering::Greater,
+            Ordering::Greater => Ordering::Less,
+            Ordering::Less if longer_is_greater(other.0.get(self.0.len())) => Ordering::Less,
+            Ordering::Less => Ordering::Greater,
+        }
+    }
+}
+
+impl PartialOrd for GenericVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// An advisory affecting a locked package.
+#[derive(Debug, Clone, Serialize)]
+pub struct Vulnerability {
+    pub ecosystem: String,
+    pub package: String,
+    pub version: String,
+    pub id: String,
+    pub summary: Option<String>,
+    pub severity: Severity,
+    pub aliases: Vec<String>,
+    /// The versions fixing the advisory, to upgrade to
+    pub fixed: Vec<String>,
+}
+
+/// The advisories of the imported OSV snapshot affecting the given packages, the most
+/// severe first.
+pub async fn find_vulnerabilities(
+    db: &DB,
+    packages: &[LockedPackage],
+) -> Result<Vec<Vulnerability>> {
+    if packages.is_empty() {
+        return Ok(vec![]);
+    }
+    let (ecosystems, names): (Vec<String>, Vec<String>) = packages
+        .iter()
+        .map(|p| (p.ecosystem.to_string(), p.name.clone()))
+        .unique()
+        .unzip();
+    let advisories = sqlx::query!(
+        "SELECT ecosystem, package, id, summary, severity, aliases, ranges, versions
+        FROM osv_advisory
+        WHERE (ecosystem, package) IN (SELECT * FROM UNNEST($1::text[], $2::text[]))",
+        &ecosystems,
+        &names
+    )
+    .fetch_all(db)
+    .await?;
+
+    let mut vulnerabilities = vec![];
+    for advisory in advisories {
+        let ranges: Vec<OsvRange> = serde_json::from_value(advisory.ranges).unwrap_or_default();
+        for package in packages
+            .iter()
+            .filter(|p| p.ecosystem == advisory.ecosystem && p.name == advisory.package)
+        {
+            if !is_affected(
+                package.ecosystem,
+                &package.version,
+                &ranges,
+                &advisory.versions,
+            ) {
+                continue;
+            }
+            vulnerabilities.push(Vulnerability {
+                ecosystem: advisory.ecosystem.clone(),
+                package: package.name.clone(),
+                version: package.version.clone(),
+                id: advisory.id.clone(),
+                summary: advisory.summary.clone(),
+                severity: advisory.severity.parse().unwrap_or(Severity::Unknown),
+                aliases: advisory.aliases.clone(),
+                fixed: ranges
+                    .iter()
+                    .flat_map(|r| r.events.iter().filter_map(|e| e.fixed.clone()))
+                    .unique()
+                    .collect(),
+            });
+        }
+    }
+    vulnerabilities.sort_by(|a, b| {
+        b.severity
+            .cmp(&a.severity)
+            .then_with(|| a.package.cmp(&b.package))
+            .then_with(|| a.id.cmp(&b.id))
+    });
+    Ok(vulnerabilities)
+}
+
+/// Refuses a lock pinning a package with a critical advisory when the workspace blocks
+/// them. Called wherever a lock is produced or deployed.
+pub async fn check_deployment(
+    db: &DB,
+    w_id: &str,
+    language: &ScriptLang,
+    lock: &str,
+) -> Result<()> {
+    let packages = locked_packages(language, lock);
+    if packages.is_empty() {
+        return Ok(());
+    }
+    let block = sqlx::query_scalar!(
+        "SELECT block_critical_vulnerabilities FROM workspace_settings WHERE workspace_id = $1",
+        w_id
+    )
+    .fetch_optional(db)
+    .await?
+    .unwrap_or(false);
+    if !block {
+        return Ok(());
+    }
+
+    let critical = find_vulnerabilities(db, &packages)
+        .await?
+        .into_iter()
+        .filter(|v| v.severity == Severity::Critical)
+        .map(|v| format!("{}@{} ({})", v.package, v.version, v.id))
+        .unique()
+        .collect_vec();
+    if critical.is_empty() {
+        Ok(())
+    } else {
+        Err(Error::BadRequest(format!(
+            "The lockfile pins packages with critical vulnerabilities, which this workspace does not allow deploying: {}",
+            critical.join(", ")
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(ecosystem: &'static str, name: &str, version: &str) -> LockedPackage {
+        LockedPackage {
+            ecosystem,
+            name: name.to_string(),
+            version: version.to_string(),
+            hashes: vec![],
+        }
+    }
+
+    #[test]
+    fn test_locked_packages() {
+        let python = "# py: 3.11\nrequests==2.31.0\nFlask_Cors==4.0.0 ; python_version >= '3.8'\nuvicorn[standard]==0.23.2\n";
+        assert_eq!(
+            locked_packages(&ScriptLang::Python3, python),
+            vec![
+                package("PyPI", "requests", "2.31.0"),
+                package("PyPI", "flask-cors", "4.0.0"),
+                package("PyPI", "uvicorn", "0.23.2"),
+            ]
+        );
+
+        let bun = r#"{"dependencies":{"lodash":"^4.17.0"}}
+//bun.lock
+{
+  "lockfileVersion": 1,
+  "workspaces": {
+    "": {
+      "dependencies": { "lodash": "^4.17.0" },
+    },
+  },
+  "packages": {
+    "@types/node": ["@types/node@20.1.0", "", {}, "sha512-a"],
+    "lodash": ["lodash@4.17.20", "", {}, "sha512-b"],
+    "local": ["local@workspace:packages/local"],
+  }
+}"#;
+        assert_eq!(
+            locked_packages(&ScriptLang::Bun, bun),
+            vec![
+                package("npm", "@types/node", "20.1.0"),
+                package("npm", "lodash", "4.17.20"),
+            ]
+        );
+        assert!(locked_packages(&ScriptLang::Bun, "{}\n//bun.lockb\nYmluYXJ5").is_empty());
+
+        let deno = r#"{"version":"4","npm":{"chalk@5.3.0":{},"react-dom@18.2.0_react@18.2.0":{}}}"#;
+        assert_eq!(
+            locked_packages(&ScriptLang::Deno, deno),
+            vec![
+                package("npm", "chalk", "5.3.0"),
+                package("npm", "react-dom", "18.2.0")
+            ]
+        );
+
+        let go = "module mymod\n//go.sum\ngithub.com/a/b v1.2.3 h1:x=\ngithub.com/a/b v1.2.3/go.mod h1:y=\n";
+        assert_eq!(
+            locked_packages(&ScriptLang::Go, go),
+            vec![package("Go", "github.com/a/b", "v1.2.3")]
+        );
+
+        let php = "guzzlehttp/guzzle\nLOCK\n{\"packages\":[{\"name\":\"Guzzlehttp/Guzzle\",\"version\":\"7.4.0\"}]}";
+        assert_eq!(
+            locked_packages(&ScriptLang::Php, php),
+            vec![package("Packagist", "guzzlehttp/guzzle", "7.4.0")]
+        );
+
+        let rust = r#"version = 3
+
+[[package]]
+name = "main"
+version = "0.1.0"
+
+[[package]]
+name = "time"
+version = "0.1.45"
+source = "registry+https://github.com/rust-lang/crates.io-index"
+checksum = "1B797AFAD3F312D1C66A56D11D0316F916356D11BD158FBC6CA6389FF6BF805A"
+"#;
+        assert_eq!(
+            locked_packages(&ScriptLang::Rust, rust),
+            vec![
+                package("crates.io", "time", "0.1.45").with_hashes([PackageHash {
+                    alg: "SHA-256",
+                    content: "1b797afad3f312d1c66a56d11d0316f916356d11bd158fbc6ca6389ff6bf805a"
+                        .to_string()
+                }])
+            ]
+        );
+
+        let csharp = r#"{"version":1,"dependencies":{"net7.0":{"Newtonsoft.Json":{"type":"Direct","resolved":"12.0.1"}}}}"#;
+        assert_eq!(
+            locked_packages(&ScriptLang::CSharp, csharp),
+            vec![package("NuGet", "newtonsoft.json", "12.0.1")]
+        );
+
+        assert_eq!(
+            locked_packages(
+                &ScriptLang::Java,
+                "org.apache.logging.log4j:log4j-core:2.14.1\n"
+            ),
+            vec![package(
+                "Maven",
+                "org.apache.logging.log4j:log4j-core",
+                "2.14.1"
+            )]
+        );
+
+        let ruby = "GEM\n  remote: https://rubygems.org/\n  specs:\n    nokogiri (1.13.0-x86_64-linux)\n      racc (~> 1.4)\n    racc (1.6.2)\n\nPLATFORMS\n  x86_64-linux\n";
+        assert_eq!(
+            locked_packages(&ScriptLang::Ruby, ruby),
+            vec![
+                package("RubyGems", "nokogiri", "1.13.0"),
+                package("RubyGems", "racc", "1.6.2")
+            ]
+        );
+    }
+
+    fn ranges(events: serde_json::Value) -> Vec<OsvRange> {
+        vec![OsvRange {
+            typ: "ECOSYSTEM".to_string(),
+            events: serde_json::from_value(events).unwrap(),
+        }]
+    }
+
+    #[test]
+    fn test_is_affected() {
+        let two_ranges = ranges(serde_json::json!([
+            {"introduced": "0"}, {"fixed": "2.0.0"}, {"introduced": "2.1.0"}, {"last_affected": "2.2.0"}
+        ]));
+        for (version, affected) in [
+            ("1.9.9", true),
+            ("2.0.0", false),
+            ("2.0.5", false),
+            ("2.1.0", true),
+            ("2.2.0", true),
+            ("2.2.1", false),
+        ] {
+            assert_eq!(
+                is_affected("npm", version, &two_ranges, &[]),
+                affected,
+                "{version}"
+            );
+        }
+
+        let pypi = ranges(serde_json::json!([{"introduced": "2.0"}, {"fixed": "2.31.0"}]));
+        assert!(is_affected("PyPI", "2.30.0", &pypi, &[]));
+        assert!(!is_affected("PyPI", "2.31", &pypi, &[]));
+        assert!(is_affected("PyPI", "2.31.0rc1", &pypi, &[]));
+
+        let go = ranges(serde_json::json!([{"introduced": "0"}, {"fixed": "1.2.4"}]));
+        assert!(is_affected("Go", "v1.2.3", &go, &[]));
+        assert!(!is_affected("Go", "v1.2.4+incompatible", &go, &[]));
+
+        let maven = ranges(serde_json::json!([{"introduced": "2.0-beta9"}, {"fixed": "2.15.0"}]));
+        assert!(is_affected("Maven", "2.14.1", &maven, &[]));
+        assert!(is_affected("Maven", "2.0", &maven, &[]));
+        assert!(!is_affected("Maven", "2.0-alpha1", &maven, &[]));
+        assert!(!is_affected("Maven", "2.15", &maven, &[]));
+
+        assert!(is_affected("RubyGems", "1.0.0", &[], &["1.0".to_string()]));
+        assert!(!is_affected("npm", "not-a-version", &two_ranges, &[]));
+    }
+
+    #[test]
+    fn test_advisory_rows() {
+        let entry: OsvEntry = serde_json::from_value(serde_json::json!({
+            "id": "GHSA-jfh8-c2jp-5v3q",
+            "summary": "Remote code injection in Log4j",
+            "aliases": ["CVE-2021-44228"],
+            "severity": [{"type": "CVSS_V3", "score": "CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:C/C:H/I:H/A:H"}],
+            "affected": [
+                {
+                    "package": {"ecosystem": "Maven", "name": "org.apache.logging.log4j:log4j-core"},
+                    "ranges": [
+                        {"type": "ECOSYSTEM", "events": [{"introduced": "2.13.0"}, {"fixed": "2.15.0"}]},
+                        {"type": "GIT", "repo": "https://github.com/apache/logging-log4j2", "events": [{"introduced": "0"}]}
+                    ]
+                },
+                {"package": {"ecosystem": "Debian:11", "name": "apache-log4j2"}}
+            ]
+        }))
+        .unwrap();
+        let rows = advisory_rows(&entry);
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].severity, Severity::Critical);
+        assert_eq!(rows[0].ranges.len(), 1);
+        assert_eq!(rows[0].aliases, vec!["CVE-2021-44228".to_string()]);
+    }
+
+    #[test]
+    fn test_cvss_v3_base_score() {
+        for (vector, score) in [
+            ("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:C/C:H/I:H/A:H", 10.0),
+            ("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H", 9.8),
+            ("CVSS:3.0/AV:N/AC:L/PR:N/UI:R/S:U/C:N/I:N/A:H", 6.5),
+            ("CVSS:3.1/AV:L/AC:H/PR:H/UI:R/S:U/C:L/I:N/A:N", 1.8),
+            ("CVSS:3.1/AV:N/AC:L/PR:L/UI:N/S:C/C:L/I:L/A:N", 6.4),
+            ("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:N/I:N/A:N", 0.0),
+        ] {
+            assert_eq!(cvss_v3_base_score(vector), Some(score), "{vector}");
+        }
+        assert_eq!(
+            cvss_v3_base_score("CVSS:4.0/AV:N/AC:L/AT:N/PR:N/UI:N"),
+            None
+        );
+    }
+}
@@ -345,6 +345,10 @@ lazy_static::lazy_static! {
         "rlang".to_string(),
         "duckdb".to_string(),
         "dbt".to_string(),
+        "lua".to_string(),
+        "kotlin".to_string(),
+        "perl".to_string(),
+        "wasm".to_string(),
         // for related places search: ADD_NEW_LANG
         "dependency".to_string(),
         "flow".to_string(),
@@ -359,7 +363,9 @@ lazy_static::lazy_static! {
         "snowflake".to_string(),
         "mssql".to_string(),
         "bigquery".to_string(),
-        "oracledb".to_string()
+        "oracledb".to_string(),
+        "lua".to_string(),
+        "wasm".to_string()
         // for related places search: ADD_NEW_LANG
     ];
 
@@ -392,6 +398,9 @@ lazy_static::lazy_static! {
         pip_local_dependencies: Default::default(),
         env_vars: Default::default(),
         native_mode: false,
+        job_resource_limits: Default::default(),
+        job_isolation: None,
+        egress_allowlist: None,
     });
 
     pub static ref WORKER_PULL_QUERIES: arc_swap::ArcSwap<Vec<String>> = arc_swap::ArcSwap::from_pointee(vec![]);
@@ -1480,13 +1489,22 @@ pub fn write_binary_file(main_path: &str, byts: &mut bytes::Bytes) -> error::Res
     atomic_write_file_bytes(main_path, byts, true)
 }
 
+/// Leaf cgroup the worker moves its own processes into when it delegates controllers to
+/// per-job cgroups, since cgroup v2 forbids a cgroup that holds processes to do so.
+pub const WORKER_CGROUP_LEAF: &str = "windmill-worker";
+
 #[cfg(not(windows))]
 fn get_cgroupv2_path() -> Option<String> {
     let cgroup_path: String = parse_file("/proc/self/cgroup")?;
 
-    CGROUP_V2_PATH_RE
-        .captures(&cgroup_path)
-        .map(|x| format!("/sys/fs/cgroup{}", x.get(1).unwrap().as_str()))
+    // Limits and usage are those of the cgroup shared with the jobs, not of the worker's leaf.
+    CGROUP_V2_PATH_RE.captures(&cgroup_path).map(|x| {
+        let path = x.get(1).unwrap().as_str();
+        let path = path
+            .strip_suffix(&format!("/{WORKER_CGROUP_LEAF}"))
+            .unwrap_or(path);
+        format!("/sys/fs/cgroup{path}")
+    })
 }
 
 #[cfg(not(windows))]
@@ -2317,6 +2335,19 @@ pub async fn load_worker_config(
     let native_mode = is_native_mode_from_env() || config.native_mode.unwrap_or(false);
     NATIVE_MODE_RESOLVED.store(native_mode, std::sync::atomic::Ordering::Relaxed);
 
+    let job_resource_limits = config
+        .job_resource_limits
+        .unwrap_or_default()
+        .or(JobResourceLimits::from_env());
+
+    // An invalid allowlist denies everything rather than leaving the jobs unrestricted
+    let egress_allowlist = config.egress_allowlist.map(|entries| {
+        crate::egress::EgressAllowlist::parse(&entries).unwrap_or_else(|e| {
+            tracing::error!("Jobs of this worker group may not connect anywhere: {e}");
+            Default::default()
+        })
+    });
+
     Ok(WorkerConfig {
         worker_tags,
         priority_tags_sorted,
@@ -2337,6 +2368,9 @@ pub async fn load_worker_config(
             .or_else(|| load_additional_python_paths_from_env()),
         env_vars: resolved_env_vars,
         native_mode,
+        job_resource_limits,
+        job_isolation: config.job_isolation.filter(|x| !x.trim().is_empty()),
+        egress_allowlist,
     })
 }
 
@@ -2426,6 +2460,9 @@ pub struct WorkerConfigOpt {
     pub env_vars_static: Option<HashMap<String, String>>,
     pub env_vars_allowlist: Option<Vec<String>>,
     pub native_mode: Option<bool>,
+    pub job_resource_limits: Option<JobResourceLimits>,
+    pub job_isolation: Option<String>,
+    pub egress_allowlist: Option<Vec<String>>,
 }
 
 impl Default for WorkerConfigOpt {
@@ -2444,6 +2481,9 @@ impl Default for WorkerConfigOpt {
             env_vars_static: Default::default(),
             env_vars_allowlist: Default::default(),
             native_mode: Default::default(),
+            job_resource_limits: Default::default(),
+            job_isolation: Default::default(),
+            egress_allowlist: Default::default(),
         }
     }
 }
@@ -2462,12 +2502,97 @@ pub struct WorkerConfig {
     pub pip_local_dependencies: Option<Vec<String>>,
     pub env_vars: HashMap<String, String>,
     pub native_mode: bool,
+    pub job_resource_limits: JobResourceLimits,
+    /// `landlock` runs the jobs of this worker group in the Landlock sandbox, unless the
+    /// instance-wide `job_isolation` setting selects nsjail or unshare
+    pub job_isolation: Option<String>,
+    /// Hosts the jobs of this worker group may connect to, unrestricted when unset
+    pub egress_allowlist: Option<crate::egress::EgressAllowlist>,
 }
 
 impl std::fmt::Debug for WorkerConfig {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "WorkerConfig {{ worker_tags: {:?}, priority_tags_sorted: {:?}, dedicated_worker: {:?}, dedicated_workers: {:?}, init_bash: {:?}, periodic_script_bash: {:?}, periodic_script_interval_seconds: {:?}, cache_clear: {:?}, additional_python_paths: {:?}, pip_local_dependencies: {:?}, env_vars: {:?}, native_mode: {:?} }}",
-        self.worker_tags, self.priority_tags_sorted, self.dedicated_worker, self.dedicated_workers, self.init_bash, self.periodic_script_bash, self.periodic_script_interval_seconds, self.cache_clear, self.additional_python_paths, self.pip_local_dependencies, self.env_vars.iter().map(|(k, v)| format!("{}: {}{} ({} chars)", k, &v[..3.min(v.len())], "***", v.len())).collect::<Vec<String>>().join(", "), self.native_mode)
+        write!(f, "WorkerConfig {{ worker_tags: {:?}, priority_tags_sorted: {:?}, dedicated_worker: {:?}, dedicated_workers: {:?}, init_bash: {:?}, periodic_script_bash: {:?}, periodic_script_interval_seconds: {:?}, cache_clear: {:?}, additional_python_paths: {:?}, pip_local_dependencies: {:?}, env_vars: {:?}, native_mode: {:?}, job_resource_limits: {:?}, job_isolation: {:?}, egress_allowlist: {:?} }}",
+        self.worker_tags, self.priority_tags_sorted, self.dedicated_worker, self.dedicated_workers, self.init_bash, self.periodic_script_bash, self.periodic_script_interval_seconds, self.cache_clear, self.additional_python_paths, self.pip_local_dependencies, self.env_vars.iter().map(|(k, v)| format!("{}: {}{} ({} chars)", k, &v[..3.min(v.len())], "***", v.len())).collect::<Vec<String>>().join(", "), self.native_mode, self.job_resource_limits, self.job_isolation, self.egress_allowlist)
+    }
+}
+
+/// Per-job cgroup limits applied to the processes of jobs not sandboxed by nsjail. Set on the
+/// worker group (or through `JOB_MEMORY_MAX_MB`, `JOB_CPU_MAX` and `JOB_PIDS_MAX`), and
+/// lowered per script with `memory_max`, `cpu_max` and `pids_max` header comments.
+#[derive(Serialize, Deserialize, PartialEq, Debug, Clone, Copy, Default)]
+pub struct JobResourceLimits {
+    pub memory_max_mb: Option<u64>,
+    /// Number of CPUs, fractional values are allowed.
+    pub cpu_max: Option<f64>,
+    pub pids_max: Option<u64>,
+}
+
+impl JobResourceLimits {
+    pub fn from_env() -> Self {
+        fn parse<T: FromStr>(name: &str) -> Option<T> {
+            std::env::var(name).ok().and_then(|x| x.trim().parse().ok())
+        }
+        Self {
+            memory_max_mb: parse("JOB_MEMORY_MAX_MB"),
+            cpu_max: parse("JOB_CPU_MAX"),
+            pids_max: parse("JOB_PIDS_MAX"),
+        }
+    }
+
+    /// Reads `memory_max <MB>`, `cpu_max <cpus>` and `pids_max <n>` from the leading comment
+    /// lines of a script, whatever its comment syntax (`#`, `//` or `--`).
+    pub fn from_code(code: &str) -> Self {
+        let mut limits = Self::default();
+        for line in code.lines() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let Some(comment) = ["//", "--", "#"].iter().find_map(|p| line.strip_prefix(p)) else {
+                break;
+            };
+            let mut tokens = comment.split_whitespace();
+            let (Some(key), Some(value), None) = (tokens.next(), tokens.next(), tokens.next())
+            else {
+                continue;
+            };
+            match key {
+                "memory_max" => limits.memory_max_mb = value.parse().ok(),
+                "cpu_max" => limits.cpu_max = value.parse().ok().filter(|x: &f64| *x > 0.0),
+                "pids_max" => limits.pids_max = value.parse().ok(),
+                _ => {}
+            }
+        }
+        limits
+    }
+
+    /// Fills the limits left unset with the ones of `other`.
+    pub fn or(self, other: Self) -> Self {
+        Self {
+            memory_max_mb: self.memory_max_mb.or(other.memory_max_mb),
+            cpu_max: self.cpu_max.or(other.cpu_max),
+            pids_max: self.pids_max.or(other.pids_max),
+        }
+    }
+
+    /// Limits requested by a script, which can tighten the worker group ones but not exceed them.
+    pub fn within(self, ceiling: Self) -> Self {
+        fn min<T: PartialOrd>(a: Option<T>, b: Option<T>) -> Option<T> {
+            match (a, b) {
+                (Some(a), Some(b)) => Some(if a < b { a } else { b }),
+                (a, b) => a.or(b),
+            }
+        }
+        Self {
+            memory_max_mb: min(self.memory_max_mb, ceiling.memory_max_mb),
+            cpu_max: min(self.cpu_max, ceiling.cpu_max),
+            pids_max: min(self.pids_max, ceiling.pids_max),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.memory_max_mb.is_none() && self.cpu_max.is_none() && self.pids_max.is_none()
     }
 }
 
@@ -3295,4 +3420,39 @@ mod tests {
 
         let _ = std::fs::remove_dir_all(&base);
     }
+
+    #[test]
+    fn job_resource_limits_from_script_header() {
+        let limits = JobResourceLimits::from_code(
+            "#!/usr/bin/env python3\n# memory_max 512\n# cpu_max 0.5\n# pids_max 64\nimport os\n# memory_max 1",
+        );
+        assert_eq!(
+            limits,
+            JobResourceLimits { memory_max_mb: Some(512), cpu_max: Some(0.5), pids_max: Some(64) }
+        );
+        let limits =
+            JobResourceLimits::from_code("// memory_max 256\n// memory max is set above\n");
+        assert_eq!(limits.memory_max_mb, Some(256));
+        assert!(JobResourceLimits::from_code("-- cpu_max 0\nSELECT 1").is_empty());
+    }
+
+    #[test]
+    fn script_limits_cannot_exceed_worker_group_limits() {
+        let group =
+            JobResourceLimits { memory_max_mb: Some(1024), cpu_max: Some(1.0), pids_max: None };
+        let script = JobResourceLimits {
+            memory_max_mb: Some(4096),
+            cpu_max: Some(0.25),
+            pids_max: Some(32),
+        };
+        assert_eq!(
+            script.within(group),
+            JobResourceLimits {
+                memory_max_mb: Some(1024),
+                cpu_max: Some(0.25),
+                pids_max: Some(32)
+            }
+        );
+        assert_eq!(JobResourceLimits::default().within(group), group);
+    }
 }
@@ -482,6 +482,42 @@ async fn test_trigger_notify_http_trigger_change(db: Pool<Postgres>) {
     );
 }
 
+#[sqlx::test(migrations = "../migrations", fixtures("base"))]
+async fn test_trigger_notify_grpc_trigger_change(db: Pool<Postgres>) {
+    let before_id = get_latest_event_id(&db).await.unwrap();
+
+    let trigger_path = format!("test_grpc_trigger_{}", uuid::Uuid::new_v4());
+
+    sqlx::query(
+        "INSERT INTO grpc_trigger (path, proto_definition, service_name, method_name, script_path, is_flow, workspace_id, edited_by, permissioned_as)
+         VALUES ($1, '', 'greeter.Greeter', 'SayHello', 'test/script', false, 'test-workspace', 'test-user', 'u/test-user')",
+    )
+    .bind(&trigger_path)
+    .execute(&db)
+    .await
+    .expect("Failed to insert gRPC trigger");
+
+    sqlx::query("DELETE FROM grpc_trigger WHERE path = $1")
+        .bind(&trigger_path)
+        .execute(&db)
+        .await
+        .expect("Failed to delete gRPC trigger");
+
+    let events = poll_notify_events(&db, before_id)
+        .await
+        .expect("Should poll events");
+    let grpc_events = events
+        .iter()
+        .filter(|e| e.channel == "notify_grpc_trigger_change")
+        .filter(|e| e.payload == format!("test-workspace:{}", trigger_path))
+        .count();
+
+    assert_eq!(
+        grpc_events, 2,
+        "Should have a notify_grpc_trigger_change event for the insert and the delete"
+    );
+}
+
 // ============================================================================
 // Script/Flow Version Change Tests
 // ============================================================================
@@ -99,6 +99,30 @@ pub enum DeployedObject {
         path: String,
         parent_path: Option<String>,
     },
+    PostgresNotifyTrigger {
+        path: String,
+        parent_path: Option<String>,
+    },
+    MysqlTrigger {
+        path: String,
+        parent_path: Option<String>,
+    },
+    RedisTrigger {
+        path: String,
+        parent_path: Option<String>,
+    },
+    ObjectTrigger {
+        path: String,
+        parent_path: Option<String>,
+    },
+    SftpTrigger {
+        path: String,
+        parent_path: Option<String>,
+    },
+    GrpcTrigger {
+        path: String,
+        parent_path: Option<String>,
+    },
     SqsTrigger {
         path: String,
         parent_path: Option<String>,
@@ -151,6 +175,12 @@ impl DeployedObject {
             DeployedObject::PostgresTrigger { path, .. } => path.to_owned(),
             DeployedObject::MqttTrigger { path, .. } => path.to_owned(),
             DeployedObject::AmqpTrigger { path, .. } => path.to_owned(),
+            DeployedObject::PostgresNotifyTrigger { path, .. } => path.to_owned(),
+            DeployedObject::MysqlTrigger { path, .. } => path.to_owned(),
+            DeployedObject::RedisTrigger { path, .. } => path.to_owned(),
+            DeployedObject::ObjectTrigger { path, .. } => path.to_owned(),
+            DeployedObject::SftpTrigger { path, .. } => path.to_owned(),
+            DeployedObject::GrpcTrigger { path, .. } => path.to_owned(),
             DeployedObject::SqsTrigger { path, .. } => path.to_owned(),
             DeployedObject::GcpTrigger { path, .. } => path.to_owned(),
             DeployedObject::AzureTrigger { path, .. } => path.to_owned(),
@@ -216,6 +246,12 @@ impl DeployedObject {
             DeployedObject::PostgresTrigger { parent_path, .. } => parent_path.to_owned(),
             DeployedObject::MqttTrigger { parent_path, .. } => parent_path.to_owned(),
             DeployedObject::AmqpTrigger { parent_path, .. } => parent_path.to_owned(),
+            DeployedObject::PostgresNotifyTrigger { parent_path, .. } => parent_path.to_owned(),
+            DeployedObject::MysqlTrigger { parent_path, .. } => parent_path.to_owned(),
+            DeployedObject::RedisTrigger { parent_path, .. } => parent_path.to_owned(),
+            DeployedObject::ObjectTrigger { parent_path, .. } => parent_path.to_owned(),
+            DeployedObject::SftpTrigger { parent_path, .. } => parent_path.to_owned(),
+            DeployedObject::GrpcTrigger { parent_path, .. } => parent_path.to_owned(),
             DeployedObject::SqsTrigger { parent_path, .. } => parent_path.to_owned(),
             DeployedObject::GcpTrigger { parent_path, .. } => parent_path.to_owned(),
             DeployedObject::AzureTrigger { parent_path, .. } => parent_path.to_owned(),
@@ -247,6 +283,12 @@ impl DeployedObject {
             DeployedObject::PostgresTrigger { .. } => "postgres_trigger",
             DeployedObject::MqttTrigger { .. } => "mqtt_trigger",
             DeployedObject::AmqpTrigger { .. } => "amqp_trigger",
+            DeployedObject::PostgresNotifyTrigger { .. } => "postgres_notify_trigger",
+            DeployedObject::MysqlTrigger { .. } => "mysql_trigger",
+            DeployedObject::RedisTrigger { .. } => "redis_trigger",
+            DeployedObject::ObjectTrigger { .. } => "object_trigger",
+            DeployedObject::SftpTrigger { .. } => "sftp_trigger",
+            DeployedObject::GrpcTrigger { .. } => "grpc_trigger",
             DeployedObject::SqsTrigger { .. } => "sqs_trigger",
             DeployedObject::GcpTrigger { .. } => "gcp_trigger",
             DeployedObject::AzureTrigger { .. } => "azure_trigger",
@@ -298,6 +340,12 @@ pub const PATH_KEYED_TABLES: &[&str] = &[
     "postgres_trigger",
     "mqtt_trigger",
     "amqp_trigger",
+    "postgres_notify_trigger",
+    "mysql_trigger",
+    "redis_trigger",
+    "object_trigger",
+    "sftp_trigger",
+    "grpc_trigger",
     "sqs_trigger",
     "gcp_trigger",
     "azure_trigger",
@@ -677,6 +725,30 @@ mod tests {
             DeployedObject::AmqpTrigger { path: "t".to_string(), parent_path: None }.get_kind(),
             "amqp_trigger"
         );
+        assert_eq!(
+            DeployedObject::PostgresNotifyTrigger { path: "t".to_string(), parent_path: None }.get_kind(),
+            "postgres_notify_trigger"
+        );
+        assert_eq!(
+            DeployedObject::MysqlTrigger { path: "t".to_string(), parent_path: None }.get_kind(),
+            "mysql_trigger"
+        );
+        assert_eq!(
+            DeployedObject::RedisTrigger { path: "t".to_string(), parent_path: None }.get_kind(),
+            "redis_trigger"
+        );
+        assert_eq!(
+            DeployedObject::ObjectTrigger { path: "t".to_string(), parent_path: None }.get_kind(),
+            "object_trigger"
+        );
+        assert_eq!(
+            DeployedObject::SftpTrigger { path: "t".to_string(), parent_path: None }.get_kind(),
+            "sftp_trigger"
+        );
+        assert_eq!(
+            DeployedObject::GrpcTrigger { path: "t".to_string(), parent_path: None }.get_kind(),
+            "grpc_trigger"
+        );
         assert_eq!(
             DeployedObject::SqsTrigger { path: "t".to_string(), parent_path: None }.get_kind(),
             "sqs_trigger"
@@ -969,8 +969,7 @@ async fn eval_simple_js_inner(
     globals: &HashMap<String, serde_json::Value>,
     memory_limit: usize,
 ) -> anyhow::Result<Box<RawValue>> {
-    let runtime = AsyncRuntime::new()?;
-    runtime.set_memory_limit(memory_limit).await;
+    let runtime = simple_js_runtime(memory_limit, simple_js_deadline()).await?;
     let context = AsyncContext::full(&runtime).await?;
 
     async_with!(context => |ctx| {
@@ -994,6 +993,49 @@ async fn eval_simple_js_inner(
     .await
 }
 
+/// Builds the runtime of `eval_simple_js`. The tokio timeout around it cannot stop a
+/// running script, so the interrupt handler aborts it at the same deadline and frees
+/// the blocking thread instead of letting e.g. `while (true) {}` spin forever.
+#[cfg(feature = "quickjs")]
+async fn simple_js_runtime(
+    memory_limit: usize,
+    deadline: std::time::Instant,
+) -> anyhow::Result<AsyncRuntime> {
+    let runtime = AsyncRuntime::new()?;
+    runtime.set_memory_limit(memory_limit).await;
+    runtime
+        .set_interrupt_handler(Some(Box::new(move || std::time::Instant::now() > deadline)))
+        .await;
+    Ok(runtime)
+}
+
+#[cfg(feature = "quickjs")]
+fn simple_js_deadline() -> std::time::Instant {
+    std::time::Instant::now() + std::time::Duration::from_millis(EVAL_TIMEOUT_MS)
+}
+
+/// Checks that `expr` compiles as the body of an `eval_simple_js` expression over the
+/// given global names, without evaluating it.
+#[cfg(feature = "quickjs")]
+pub async fn check_simple_js(expr: &str, globals: &[&str]) -> anyhow::Result<()> {
+    let memory_limit = EVAL_SIMPLE_JS_MEMORY_LIMIT_BYTES;
+    let runtime = simple_js_runtime(memory_limit, simple_js_deadline()).await?;
+    let context = AsyncContext::full(&runtime).await?;
+    let code = format!(
+        "(function({}) {{ return ({}); }})",
+        globals.join(", "),
+        expr
+    );
+
+    async_with!(context => |ctx| {
+        ctx.eval::<(), _>(code)
+            .catch(&ctx)
+            .map_err(|e| map_quickjs_error(e, memory_limit, None))?;
+        Ok(())
+    })
+    .await
+}
+
 // ── Fallback stubs when quickjs is disabled ──────────────────────────
 
 #[cfg(not(feature = "quickjs"))]
@@ -1017,6 +1059,11 @@ pub async fn eval_simple_js(
     anyhow::bail!("JavaScript expression evaluation requires the `quickjs` feature. Enable it with: cargo build --features quickjs")
 }
 
+#[cfg(not(feature = "quickjs"))]
+pub async fn check_simple_js(_expr: &str, _globals: &[&str]) -> anyhow::Result<()> {
+    anyhow::bail!("JavaScript expression evaluation requires the `quickjs` feature. Enable it with: cargo build --features quickjs")
+}
+
 // ── Tests ────────────────────────────────────────────────────────────
 
 #[cfg(all(test, feature = "quickjs"))]
@@ -2818,6 +2865,36 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[tokio::test]
+    async fn test_simple_js_runtime_interrupts_at_deadline() {
+        let deadline = std::time::Instant::now() + std::time::Duration::from_millis(100);
+        let runtime = simple_js_runtime(EVAL_SIMPLE_JS_MEMORY_LIMIT_BYTES, deadline)
+            .await
+            .unwrap();
+        let context = AsyncContext::full(&runtime).await.unwrap();
+        let result: Result<(), String> = async_with!(context => |ctx| {
+            ctx.eval::<(), _>("while (true) {}")
+                .catch(&ctx)
+                .map_err(|e| e.to_string())
+        })
+        .await;
+        assert!(result.unwrap_err().contains("interrupted"));
+    }
+
+    #[tokio::test]
+    async fn test_check_simple_js() {
+        check_simple_js("{ ...event, id: event.body.id }", &["event"])
+            .await
+            .unwrap();
+        // Only compiled: an unknown global or a throwing body is a runtime concern.
+        check_simple_js("missing.field", &["event"]).await.unwrap();
+        check_simple_js("(() => { throw new Error('x') })()", &["event"])
+            .await
+            .unwrap();
+        assert!(check_simple_js("event.body.", &["event"]).await.is_err());
+        assert!(check_simple_js("1); (2", &["event"]).await.is_err());
+    }
+
     // =====================================================================
     // MEMORY LIMIT
     // =====================================================================
@@ -2844,6 +2844,11 @@ async fn update_resource_type(
     feature = "postgres_trigger",
     feature = "mqtt_trigger",
     feature = "amqp_trigger",
+    feature = "postgres_notify_trigger",
+    feature = "mysql_trigger",
+    feature = "redis_trigger",
+    feature = "object_trigger",
+    feature = "sftp_trigger",
     all(
         feature = "enterprise",
         any(
@@ -111,6 +111,32 @@ pub struct AmqpConsumer {
     pub queue_name: String,
 }
 
+impl AmqpConsumer {
+    /// Messages ready in the queue, not yet delivered to a consumer. `None` when the broker
+    /// could not be asked.
+    pub async fn ready_messages(&self) -> Option<i64> {
+        let queue = self
+            .channel
+            .queue_declare(
+                &self.queue_name,
+                QueueDeclareOptions { passive: true, ..Default::default() },
+                FieldTable::default(),
+            )
+            .await;
+        match queue {
+            Ok(queue) => Some(queue.message_count() as i64),
+            Err(err) => {
+                tracing::debug!(
+                    "Could not count the messages of queue {}: {}",
+                    self.queue_name,
+                    err
+                );
+                None
+            }
+        }
+    }
+}
+
 pub const CONSUMER_TAG: &str = "windmill";
 
 fn build_uri(resource: &AmqpResource) -> String {
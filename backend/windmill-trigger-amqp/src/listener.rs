@@ -27,6 +27,8 @@ const RECONNECT_BACKOFF_SECS: u64 = 30;
 // Back off after a failed dispatch so a poison message that always fails can't
 // spin a tight redelivery loop; the connection stays up for other messages.
 const DISPATCH_FAILURE_BACKOFF_SECS: u64 = 5;
+// The messages waiting in the queue are counted this often.
+const LAG_INTERVAL_SECS: u64 = 15;
 
 impl AmqpTrigger {
     async fn build_amqp_consumer(
@@ -172,8 +174,19 @@ impl Listener for AmqpTrigger {
             }
 
             // Consume until the stream errors, then break out to reconnect.
+            let mut lag_interval = tokio::time::interval(Duration::from_secs(LAG_INTERVAL_SECS));
+            lag_interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
             loop {
-                match consumer.consumer.next().await {
+                let next = tokio::select! {
+                    _ = lag_interval.tick(), if listening_trigger.trigger_mode => None,
+                    delivery = consumer.consumer.next() => Some(delivery),
+                };
+                let Some(next) = next else {
+                    let lag = consumer.ready_messages().await;
+                    self.record_consumer_lag(listening_trigger, lag);
+                    continue;
+                };
+                match next {
                     Some(Ok(delivery)) => {
                         let trigger_info = HashMap::from([
                             (
@@ -0,0 +1,152 @@
+use bytes::{BufMut, Bytes, BytesMut};
+use windmill_common::error::Error;
+
+/// Every message is prefixed by a compression flag and its length as a big-endian u32.
+const FRAME_HEADER_LEN: usize = 5;
+
+pub const GRPC_CONTENT_TYPE: &str = "application/grpc";
+
+/// The status codes of the gRPC protocol a trigger answers with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Code {
+    Ok = 0,
+    Cancelled = 1,
+    Unknown = 2,
+    InvalidArgument = 3,
+    DeadlineExceeded = 4,
+    NotFound = 5,
+    PermissionDenied = 7,
+    ResourceExhausted = 8,
+    Unimplemented = 12,
+    Internal = 13,
+    Unavailable = 14,
+    Unauthenticated = 16,
+}
+
+impl Code {
+    /// The value of the `grpc-status` trailer.
+    pub fn as_header_value(self) -> String {
+        (self as u8).to_string()
+    }
+
+    pub fn from_error(error: &Error) -> Self {
+        match error {
+            Error::BadRequest(_) | Error::SerdeJson { .. } => Code::InvalidArgument,
+            Error::NotFound(_) => Code::NotFound,
+            Error::NotAuthorized(_) => Code::Unauthenticated,
+            Error::PermissionDenied(_) | Error::RequireAdmin(_) => Code::PermissionDenied,
+            Error::QuotaExceeded(_) | Error::ResultTooLarge(_) => Code::ResourceExhausted,
+            Error::BadGateway(_) | Error::ConnectingToDatabase(_) => Code::Unavailable,
+            Error::ExecutionErr(_) | Error::ExecutionRawError(_) | Error::JsonErr(_) => {
+                Code::Unknown
+            }
+            _ => Code::Internal,
+        }
+    }
+}
+
+/// `application/grpc`, possibly with a codec suffix. gRPC-Web has its own framing and is not
+/// served.
+pub fn is_grpc_content_type(content_type: &str) -> bool {
+    match content_type.strip_prefix(GRPC_CONTENT_TYPE) {
+        Some(suffix) => suffix.is_empty() || suffix.starts_with('+') || suffix.starts_with(';'),
+        None => false,
+    }
+}
+
+/// Splits a request body into its messages.
+pub fn decode_frames(mut body: &[u8]) -> std::result::Result<Vec<&[u8]>, String> {
+    let mut messages = vec![];
+    while !body.is_empty() {
+        if body.len() < FRAME_HEADER_LEN {
+            return Err("Truncated gRPC message header".to_string());
+        }
+        if body[0] != 0 {
+            return Err("Compressed gRPC messages are not supported".to_string());
+        }
+        let len = u32::from_be_bytes([body[1], body[2], body[3], body[4]]) as usize;
+        let end = FRAME_HEADER_LEN + len;
+        if body.len() < end {
+            return Err("Truncated gRPC message".to_string());
+        }
+        messages.push(&body[FRAME_HEADER_LEN..end]);
+        body = &body[end..];
+    }
+    Ok(messages)
+}
+
+/// Prefixes an encoded message to send it in a response.
+pub fn encode_frame(message: &[u8]) -> Bytes {
+    let mut frame = BytesMut::with_capacity(FRAME_HEADER_LEN + message.len());
+    frame.put_u8(0);
+    frame.put_u32(message.len() as u32);
+    frame.put_slice(message);
+    frame.freeze()
+}
+
+/// Percent-encodes the value of the `grpc-message` trailer, as the protocol requires for
+/// anything outside of printable ASCII.
+pub fn encode_status_message(message: &str) -> String {
+    let mut encoded = String::with_capacity(message.len());
+    for byte in message.bytes() {
+        if (0x20..=0x7e).contains(&byte) && byte != b'%' {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frames() {
+        let mut body = encode_frame(b"first").to_vec();
+        body.extend_from_slice(&encode_frame(b""));
+        assert_eq!(
+            decode_frames(&body).unwrap(),
+            vec![b"first".as_slice(), b"".as_slice()]
+        );
+        assert!(decode_frames(&[]).unwrap().is_empty());
+        assert!(decode_frames(&body[..3]).is_err());
+        assert!(decode_frames(&body[..8]).is_err());
+        assert!(decode_frames(&[1, 0, 0, 0, 0]).is_err());
+    }
+
+    #[test]
+    fn test_is_grpc_content_type() {
+        assert!(is_grpc_content_type("application/grpc"));
+        assert!(is_grpc_content_type("application/grpc+proto"));
+        assert!(!is_grpc_content_type("application/grpc-web"));
+        assert!(!is_grpc_content_type("application/json"));
+    }
+
+    #[test]
+    fn test_encode_status_message() {
+        assert_eq!(encode_status_message("Not found: f/a"), "Not found: f/a");
+        assert_eq!(
+            encode_status_message("100%\nébauche"),
+            "100%25%0A%C3%A9bauche"
+        );
+    }
+
+    #[test]
+    fn test_code_from_error() {
+        assert_eq!(
+            Code::from_error(&Error::NotFound("x".to_string())),
+            Code::NotFound
+        );
+        assert_eq!(
+            Code::from_error(&Error::NotAuthorized("x".to_string())),
+            Code::Unauthenticated
+        );
+        assert_eq!(
+            Code::from_error(&Error::ExecutionErr("x".to_string())),
+            Code::Unknown
+        );
+        assert_eq!(Code::Unimplemented.as_header_value(), "12");
+    }
+}
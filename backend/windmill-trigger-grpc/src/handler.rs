@@ -0,0 +1,275 @@
+use async_trait::async_trait;
+use axum::{routing::post, Json, Router};
+use sqlx::PgConnection;
+use windmill_api_auth::ApiAuthed;
+use windmill_common::{
+    error::{Error, Result},
+    DB,
+};
+use windmill_git_sync::DeployedObject;
+use windmill_trigger::{Trigger, TriggerCrud, TriggerData};
+
+use super::{
+    proto::{self, ProtoService},
+    validate_grpc_config, GrpcConfig, GrpcConfigRequest, GrpcTrigger, ParseProto,
+};
+
+pub async fn increase_trigger_version(tx: &mut PgConnection) -> Result<()> {
+    sqlx::query!("SELECT nextval('grpc_trigger_version_seq')")
+        .fetch_one(tx)
+        .await?;
+    Ok(())
+}
+
+/// Lists the services of a definition and their methods, for the UI to map them.
+async fn parse_proto(
+    _authed: ApiAuthed,
+    Json(ParseProto { proto_definition }): Json<ParseProto>,
+) -> Result<Json<Vec<ProtoService>>> {
+    let pool = proto::compile(&proto_definition).map_err(Error::BadRequest)?;
+    Ok(Json(proto::describe(&pool)))
+}
+
+async fn check_method_not_served(
+    db: &DB,
+    workspace_id: &str,
+    config: &GrpcConfigRequest,
+    trigger_path: Option<&str>,
+) -> Result<()> {
+    let served = sqlx::query_scalar!(
+        r#"
+        SELECT EXISTS(
+            SELECT 1
+            FROM grpc_trigger
+            WHERE
+                workspace_id = $1 AND
+                service_name = $2 AND
+                method_name = $3 AND
+                ($4::TEXT IS NULL OR path != $4)
+        )
+        "#,
+        workspace_id,
+        config.service_name,
+        config.method_name,
+        trigger_path
+    )
+    .fetch_one(db)
+    .await?
+    .unwrap_or(false);
+
+    if served {
+        return Err(Error::BadRequest(format!(
+            "{}/{} is already served by another gRPC trigger",
+            config.service_name, config.method_name
+        )));
+    }
+
+    Ok(())
+}
+
+#[async_trait]
+impl TriggerCrud for GrpcTrigger {
+    type TriggerConfig = GrpcConfig;
+    type Trigger = Trigger<Self::TriggerConfig>;
+    type TriggerConfigRequest = GrpcConfigRequest;
+    type TestConnectionConfig = ();
+
+    const TABLE_NAME: &'static str = "grpc_trigger";
+    const TRIGGER_TYPE: &'static str = "grpc";
+    const DRAFT_KIND: windmill_common::user_drafts::UserDraftItemKind =
+        windmill_common::user_drafts::UserDraftItemKind::TriggerGrpc;
+    const SUPPORTS_SERVER_STATE: bool = false;
+    const SUPPORTS_TEST_CONNECTION: bool = false;
+    const ROUTE_PREFIX: &'static str = "/grpc_triggers";
+    const DEPLOYMENT_NAME: &'static str = "gRPC trigger";
+    const ADDITIONAL_SELECT_FIELDS: &[&'static str] = &[
+        "proto_definition",
+        "service_name",
+        "method_name",
+        "authentication_method",
+    ];
+    const IS_ALLOWED_ON_CLOUD: bool = true;
+
+    fn get_deployed_object(path: String, parent_path: Option<String>) -> DeployedObject {
+        DeployedObject::GrpcTrigger { path, parent_path }
+    }
+
+    fn additional_routes(&self) -> Router {
+        Router::new().route("/parse_proto", post(parse_proto))
+    }
+
+    async fn validate_new(
+        &self,
+        db: &DB,
+        workspace_id: &str,
+        new: &Self::TriggerConfigRequest,
+    ) -> Result<()> {
+        self.validate_config(db, new, workspace_id).await?;
+        check_method_not_served(db, workspace_id, new, None).await
+    }
+
+    async fn validate_edit(
+        &self,
+        db: &DB,
+        workspace_id: &str,
+        edit: &Self::TriggerConfigRequest,
+        path: &str,
+    ) -> Result<()> {
+        self.validate_config(db, edit, workspace_id).await?;
+        check_method_not_served(db, workspace_id, edit, Some(path)).await
+    }
+
+    async fn validate_config(
+        &self,
+        _db: &DB,
+        config: &Self::TriggerConfigRequest,
+        _workspace_id: &str,
+    ) -> Result<()> {
+        validate_grpc_config(
+            &config.proto_definition,
+            &config.service_name,
+            &config.method_name,
+            &config.authentication_method,
+        )
+        .map(|_| ())
+        .map_err(Error::BadRequest)
+    }
+
+    async fn create_trigger(
+        &self,
+        _db: &DB,
+        tx: &mut PgConnection,
+        authed: &ApiAuthed,
+        w_id: &str,
+        trigger: TriggerData<Self::TriggerConfigRequest>,
+    ) -> Result<()> {
+        let resolved_edited_by = trigger.base.resolve_edited_by(authed);
+        let resolved_permissioned_as = trigger.base.resolve_permissioned_as(authed);
+
+        sqlx::query!(
+            r#"
+            INSERT INTO grpc_trigger (
+                proto_definition,
+                service_name,
+                method_name,
+                authentication_method,
+                workspace_id,
+                path,
+                script_path,
+                is_flow,
+                permissioned_as,
+                mode,
+                edited_by,
+                error_handler_path,
+                error_handler_args,
+                retry
+            )
+            VALUES (
+                $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14
+            )"#,
+            trigger.config.proto_definition,
+            trigger.config.service_name,
+            trigger.config.method_name,
+            trigger.config.authentication_method,
+            w_id,
+            trigger.base.path,
+            trigger.base.script_path,
+            trigger.base.is_flow,
+            resolved_permissioned_as,
+            trigger.base.mode() as _,
+            &resolved_edited_by,
+            trigger.error_handling.error_handler_path,
+            trigger.error_handling.error_handler_args as _,
+            trigger.error_handling.retry as _
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        increase_trigger_version(tx).await?;
+
+        Ok(())
+    }
+
+    async fn update_trigger(
+        &self,
+        _db: &DB,
+        tx: &mut PgConnection,
+        authed: &ApiAuthed,
+        workspace_id: &str,
+        path: &str,
+        trigger: TriggerData<Self::TriggerConfigRequest>,
+    ) -> Result<()> {
+        let resolved_edited_by = trigger.base.resolve_edited_by(authed);
+        let resolved_permissioned_as = trigger.base.resolve_permissioned_as(authed);
+
+        sqlx::query!(
+            r#"
+            UPDATE
+                grpc_trigger
+            SET
+                proto_definition = $1,
+                service_name = $2,
+                method_name = $3,
+                authentication_method = $4,
+                is_flow = $5,
+                edited_by = $6,
+                permissioned_as = $7,
+                script_path = $8,
+                path = $9,
+                edited_at = now(),
+                error_handler_path = $12,
+                error_handler_args = $13,
+                retry = $14
+            WHERE
+                workspace_id = $10 AND
+                path = $11
+            "#,
+            trigger.config.proto_definition,
+            trigger.config.service_name,
+            trigger.config.method_name,
+            trigger.config.authentication_method,
+            trigger.base.is_flow,
+            &resolved_edited_by,
+            resolved_permissioned_as,
+            trigger.base.script_path,
+            trigger.base.path,
+            workspace_id,
+            path,
+            trigger.error_handling.error_handler_path,
+            trigger.error_handling.error_handler_args as _,
+            trigger.error_handling.retry as _
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        increase_trigger_version(tx).await?;
+
+        Ok(())
+    }
+
+    async fn set_trigger_mode_extra_action(&self, tx: &mut PgConnection) -> Result<()> {
+        increase_trigger_version(tx).await
+    }
+
+    async fn delete_by_path(
+        &self,
+        tx: &mut PgConnection,
+        workspace_id: &str,
+        path: &str,
+    ) -> Result<bool> {
+        // SAFETY: Self::TABLE_NAME is a compile-time constant, not user input.
+        let deleted = sqlx::query(&format!(
+            "DELETE FROM {} WHERE workspace_id = $1 AND path = $2",
+            Self::TABLE_NAME
+        ))
+        .bind(workspace_id)
+        .bind(path)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+
+        increase_trigger_version(tx).await?;
+
+        Ok(deleted > 0)
+    }
+}
@@ -0,0 +1,379 @@
+use std::collections::HashMap;
+
+use prost_reflect::{DescriptorPool, MethodDescriptor};
+use serde::{Deserialize, Serialize};
+use serde_json::{value::RawValue, Map, Value};
+use sqlx::FromRow;
+use tokio::sync::{RwLock, RwLockReadGuard};
+use windmill_common::{
+    error::{Error, Result},
+    flows::Retry,
+    triggers::TriggerKind,
+    worker::to_raw_value,
+    DB,
+};
+use windmill_trigger::{trigger_helpers::TriggerJobArgs, TriggerMode};
+
+pub mod codec;
+pub mod handler;
+pub mod proto;
+
+lazy_static::lazy_static! {
+    pub static ref GRPC_ROUTES_CACHE: RwLock<RoutesCache> = RwLock::new(RoutesCache {
+        routes: HashMap::new(),
+        version: 0,
+    });
+}
+
+/// Requests are public, or need a Windmill token with access to the trigger.
+pub const AUTHENTICATION_NONE: &str = "none";
+pub const AUTHENTICATION_WINDMILL: &str = "windmill";
+
+/// Metadata a client sets to the workspace serving the method it calls. Methods are only
+/// unique within a workspace, so it is required on every call.
+pub const WORKSPACE_METADATA: &str = "x-windmill-workspace";
+
+fn default_authentication_method() -> String {
+    AUTHENTICATION_WINDMILL.to_string()
+}
+
+#[derive(Clone, Copy)]
+pub struct GrpcTrigger;
+
+/// A request to one of the methods of a trigger.
+#[derive(Debug, Clone)]
+pub struct GrpcRequest {
+    pub service: String,
+    pub method: String,
+    pub message: Map<String, Value>,
+    /// Custom metadata of the request, authorization excluded.
+    pub metadata: HashMap<String, String>,
+}
+
+impl TriggerJobArgs for GrpcTrigger {
+    type Payload = GrpcRequest;
+    const TRIGGER_KIND: TriggerKind = TriggerKind::Grpc;
+
+    fn v1_payload_fn(payload: &GrpcRequest) -> HashMap<String, Box<RawValue>> {
+        payload
+            .message
+            .iter()
+            .map(|(field, value)| (field.clone(), to_raw_value(value)))
+            .collect()
+    }
+
+    fn build_event(
+        payload: &GrpcRequest,
+        info: HashMap<String, Box<RawValue>>,
+    ) -> HashMap<String, Box<RawValue>> {
+        let mut event = HashMap::from([
+            (
+                "kind".to_string(),
+                to_raw_value(&Self::TRIGGER_KIND.to_key()),
+            ),
+            ("message".to_string(), to_raw_value(&payload.message)),
+        ]);
+        event.extend(info);
+        event
+    }
+}
+
+impl GrpcRequest {
+    /// What a preprocessor gets to know of the call besides the message.
+    pub fn trigger_info(&self) -> HashMap<String, Box<RawValue>> {
+        HashMap::from([
+            ("service".to_string(), to_raw_value(&self.service)),
+            ("method".to_string(), to_raw_value(&self.method)),
+            ("metadata".to_string(), to_raw_value(&self.metadata)),
+        ])
+    }
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct GrpcConfig {
+    pub proto_definition: String,
+    pub service_name: String,
+    pub method_name: String,
+    #[serde(default = "default_authentication_method")]
+    pub authentication_method: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GrpcConfigRequest {
+    pub proto_definition: String,
+    pub service_name: String,
+    pub method_name: String,
+    #[serde(default = "default_authentication_method")]
+    pub authentication_method: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ParseProto {
+    pub proto_definition: String,
+}
+
+/// Checks the definition compiles and serves the method, returning the method.
+pub fn validate_grpc_config(
+    proto_definition: &str,
+    service_name: &str,
+    method_name: &str,
+    authentication_method: &str,
+) -> std::result::Result<MethodDescriptor, String> {
+    match authentication_method {
+        AUTHENTICATION_NONE | AUTHENTICATION_WINDMILL => {}
+        other => {
+            return Err(format!(
+                "Invalid authentication method {}: expected `{}` or `{}`",
+                other, AUTHENTICATION_NONE, AUTHENTICATION_WINDMILL
+            ))
+        }
+    }
+
+    let pool = proto::compile(proto_definition)?;
+    proto::find_method(&pool, service_name, method_name)
+}
+
+/// The path a method is called at, `/{package}.{Service}/{Method}`.
+pub fn route_key(service_name: &str, method_name: &str) -> String {
+    format!("/{}/{}", service_name, method_name)
+}
+
+struct GrpcTriggerRow {
+    path: String,
+    script_path: String,
+    is_flow: bool,
+    workspace_id: String,
+    proto_definition: String,
+    service_name: String,
+    method_name: String,
+    authentication_method: String,
+    permissioned_as: String,
+    error_handler_path: Option<String>,
+    error_handler_args: Option<sqlx::types::Json<HashMap<String, Value>>>,
+    retry: Option<sqlx::types::Json<Retry>>,
+    mode: TriggerMode,
+    payload_transform: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct GrpcRoute {
+    pub path: String,
+    pub script_path: String,
+    pub is_flow: bool,
+    pub workspace_id: String,
+    pub authentication_method: String,
+    pub permissioned_as: String,
+    pub error_handler_path: Option<String>,
+    pub error_handler_args: Option<sqlx::types::Json<HashMap<String, Value>>>,
+    pub retry: Option<sqlx::types::Json<Retry>>,
+    pub mode: TriggerMode,
+    pub payload_transform: Option<String>,
+    pub method: MethodDescriptor,
+}
+
+pub struct RoutesCache {
+    /// Triggers by the path of their method, one per workspace serving it.
+    pub routes: HashMap<String, Vec<GrpcRoute>>,
+    /// The version of the triggers the routes were loaded at, 0 until they are first loaded.
+    pub version: i64,
+}
+
+impl RoutesCache {
+    pub fn find(&self, request_path: &str, workspace_id: &str) -> Result<&GrpcRoute> {
+        self.routes
+            .get(request_path)
+            .and_then(|routes| {
+                routes
+                    .iter()
+                    .find(|route| route.workspace_id == workspace_id)
+            })
+            .ok_or_else(|| {
+                Error::NotFound(format!(
+                    "No gRPC trigger serves {} in workspace {}",
+                    request_path, workspace_id
+                ))
+            })
+    }
+}
+
+pub async fn refresh_routes(db: &DB) -> Result<(bool, RwLockReadGuard<'_, RoutesCache>)> {
+    let version = sqlx::query_scalar!("SELECT last_value FROM grpc_trigger_version_seq")
+        .fetch_one(db)
+        .await?;
+    let routes_cache = GRPC_ROUTES_CACHE.read().await;
+    if routes_cache.version == 0 || version > routes_cache.version {
+        drop(routes_cache);
+
+        let triggers = sqlx::query_as!(
+            GrpcTriggerRow,
+            r#"
+            SELECT
+                path,
+                script_path,
+                is_flow,
+                workspace_id,
+                proto_definition,
+                service_name,
+                method_name,
+                authentication_method,
+                permissioned_as,
+                error_handler_path,
+                error_handler_args as "error_handler_args: _",
+                retry as "retry: _",
+                mode as "mode: _",
+                payload_transform
+            FROM
+                grpc_trigger
+            WHERE
+                mode = 'enabled'::TRIGGER_MODE OR mode = 'suspended'::TRIGGER_MODE
+            "#
+        )
+        .fetch_all(db)
+        .await?;
+
+        // The methods of a service usually share their definition, only compiled once.
+        let mut pools: HashMap<String, std::result::Result<DescriptorPool, String>> =
+            HashMap::new();
+        let mut routes: HashMap<String, Vec<GrpcRoute>> = HashMap::new();
+
+        for trigger in triggers {
+            let method = pools
+                .entry(trigger.proto_definition.clone())
+                .or_insert_with(|| proto::compile(&trigger.proto_definition))
+                .as_ref()
+                .map_err(Clone::clone)
+                .and_then(|pool| {
+                    proto::find_method(pool, &trigger.service_name, &trigger.method_name)
+                });
+            let method = match method {
+                Ok(method) => method,
+                Err(err) => {
+                    tracing::warn!(
+                        "Failed to consider gRPC trigger {} in workspace {}: {}",
+                        trigger.path,
+                        trigger.workspace_id,
+                        err
+                    );
+                    continue;
+                }
+            };
+
+            routes
+                .entry(route_key(&trigger.service_name, &trigger.method_name))
+                .or_default()
+                .push(GrpcRoute {
+                    path: trigger.path,
+                    script_path: trigger.script_path,
+                    is_flow: trigger.is_flow,
+                    workspace_id: trigger.workspace_id,
+                    authentication_method: trigger.authentication_method,
+                    permissioned_as: trigger.permissioned_as,
+                    error_handler_path: trigger.error_handler_path,
+                    error_handler_args: trigger.error_handler_args,
+                    retry: trigger.retry,
+                    mode: trigger.mode,
+                    payload_transform: trigger.payload_transform,
+                    method,
+                });
+        }
+
+        let mut routes_cache = GRPC_ROUTES_CACHE.write().await;
+        *routes_cache = RoutesCache { routes, version };
+
+        Ok((true, routes_cache.downgrade()))
+    } else {
+        tracing::debug!("No gRPC routes refresh needed");
+        Ok((false, routes_cache))
+    }
+}
+
+pub async fn refresh_routes_loop(db: &DB, mut killpill_rx: tokio::sync::broadcast::Receiver<()>) {
+    match refresh_routes(db).await {
+        Ok(_) => {
+            tracing::info!("Loaded gRPC routes");
+        }
+        Err(err) => {
+            tracing::error!("Error loading gRPC routes: {err:#}");
+        }
+    };
+    let db = db.clone();
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = killpill_rx.recv() => {
+                    break;
+                }
+                _ = tokio::time::sleep(std::time::Duration::from_secs(60)) => {
+                    match refresh_routes(&db).await {
+                        Ok((true, _)) => {
+                            tracing::info!("Refreshed gRPC routes");
+                        }
+                        Err(err) => {
+                            tracing::error!("Error refreshing gRPC routes: {err:#}");
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEFINITION: &str = r#"
+        syntax = "proto3";
+        package greeter;
+        message HelloRequest { string name = 1; }
+        message HelloReply { string message = 1; }
+        service Greeter { rpc SayHello(HelloRequest) returns (HelloReply); }
+    "#;
+
+    fn route(workspace_id: &str) -> GrpcRoute {
+        let pool = proto::compile(DEFINITION).unwrap();
+        GrpcRoute {
+            path: "f/greeter/say_hello".to_string(),
+            script_path: "f/greeter/hello".to_string(),
+            is_flow: false,
+            workspace_id: workspace_id.to_string(),
+            authentication_method: AUTHENTICATION_NONE.to_string(),
+            permissioned_as: "u/admin".to_string(),
+            error_handler_path: None,
+            error_handler_args: None,
+            retry: None,
+            mode: TriggerMode::Enabled,
+            payload_transform: None,
+            method: proto::find_method(&pool, "greeter.Greeter", "SayHello").unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_validate_grpc_config() {
+        assert!(validate_grpc_config(DEFINITION, "greeter.Greeter", "SayHello", "none").is_ok());
+        assert!(
+            validate_grpc_config(DEFINITION, "greeter.Greeter", "SayHello", "api_key").is_err()
+        );
+        assert!(
+            validate_grpc_config(DEFINITION, "greeter.Greeter", "SayGoodbye", "windmill").is_err()
+        );
+    }
+
+    #[test]
+    fn test_find_route() {
+        let key = route_key("greeter.Greeter", "SayHello");
+        assert_eq!(key, "/greeter.Greeter/SayHello");
+
+        let mut cache =
+            RoutesCache { routes: HashMap::from([(key.clone(), vec![route("acme")])]), version: 2 };
+        assert_eq!(cache.find(&key, "acme").unwrap().workspace_id, "acme");
+        assert!(matches!(cache.find(&key, "other"), Err(Error::NotFound(_))));
+        assert!(cache.find("/greeter.Greeter/SayGoodbye", "acme").is_err());
+
+        // Another workspace serving the same method does not change what the first one serves.
+        cache.routes.get_mut(&key).unwrap().push(route("other"));
+        assert_eq!(cache.find(&key, "acme").unwrap().workspace_id, "acme");
+        assert_eq!(cache.find(&key, "other").unwrap().workspace_id, "other");
+    }
+}
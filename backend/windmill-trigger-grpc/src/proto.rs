@@ -0,0 +1,243 @@
+use prost::Message;
+use prost_reflect::{
+    DescriptorPool, DeserializeOptions, DynamicMessage, MessageDescriptor, MethodDescriptor,
+    SerializeOptions,
+};
+use protox::{
+    file::{ChainFileResolver, File, FileResolver, GoogleFileResolver},
+    Compiler,
+};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/// Name the uploaded definition is compiled under, the only file it can be imported as.
+const PROTO_FILE_NAME: &str = "definition.proto";
+
+/// Resolves the uploaded definition, the well-known types being resolved by protox itself.
+struct DefinitionResolver {
+    source: String,
+}
+
+impl FileResolver for DefinitionResolver {
+    fn open_file(&self, name: &str) -> std::result::Result<File, protox::Error> {
+        if name == PROTO_FILE_NAME {
+            File::from_source(name, &self.source)
+        } else {
+            Err(protox::Error::file_not_found(name))
+        }
+    }
+}
+
+/// Compiles a `.proto` definition. Only the well-known `google/protobuf/*.proto` files can be
+/// imported.
+pub fn compile(source: &str) -> std::result::Result<DescriptorPool, String> {
+    let mut resolver = ChainFileResolver::new();
+    resolver.add(DefinitionResolver { source: source.to_string() });
+    resolver.add(GoogleFileResolver::new());
+
+    let mut compiler = Compiler::with_file_resolver(resolver);
+    compiler.include_imports(true);
+    compiler
+        .open_file(PROTO_FILE_NAME)
+        .map_err(|e| format!("Invalid proto definition: {}", e))?;
+
+    Ok(compiler.descriptor_pool())
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProtoService {
+    /// Fully qualified name, with the package.
+    pub name: String,
+    pub methods: Vec<ProtoMethod>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProtoMethod {
+    pub name: String,
+    pub input_type: String,
+    pub output_type: String,
+    pub client_streaming: bool,
+    pub server_streaming: bool,
+}
+
+/// The services of a definition and their methods, for the RPCs to be mapped to runnables.
+pub fn describe(pool: &DescriptorPool) -> Vec<ProtoService> {
+    pool.services()
+        .map(|service| ProtoService {
+            name: service.full_name().to_string(),
+            methods: service
+                .methods()
+                .map(|method| ProtoMethod {
+                    name: method.name().to_string(),
+                    input_type: method.input().full_name().to_string(),
+                    output_type: method.output().full_name().to_string(),
+                    client_streaming: method.is_client_streaming(),
+                    server_streaming: method.is_server_streaming(),
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+/// The method a trigger serves. Client streaming is not supported since a job runs with the
+/// whole request as its args.
+pub fn find_method(
+    pool: &DescriptorPool,
+    service_name: &str,
+    method_name: &str,
+) -> std::result::Result<MethodDescriptor, String> {
+    let service = pool
+        .get_service_by_name(service_name)
+        .ok_or_else(|| format!("Service {} is not defined", service_name))?;
+    let method = service
+        .methods()
+        .find(|method| method.name() == method_name)
+        .ok_or_else(|| format!("Method {} is not defined in {}", method_name, service_name))?;
+
+    if method.is_client_streaming() {
+        return Err(format!(
+            "Method {} of {} is client streaming, which is not supported",
+            method_name, service_name
+        ));
+    }
+
+    Ok(method)
+}
+
+/// Decodes a request message to the JSON object a job receives, with the field names of the
+/// definition and every field set, to their default value if absent.
+pub fn decode_message(
+    descriptor: MessageDescriptor,
+    bytes: &[u8],
+) -> std::result::Result<Map<String, Value>, String> {
+    let message = DynamicMessage::decode(descriptor.clone(), bytes)
+        .map_err(|e| format!("Invalid {} message: {}", descriptor.full_name(), e))?;
+
+    let options = SerializeOptions::new()
+        .use_proto_field_name(true)
+        .stringify_64_bit_integers(false)
+        .skip_default_fields(false);
+    match message.serialize_with_options(serde_json::value::Serializer, &options) {
+        Ok(Value::Object(fields)) => Ok(fields),
+        Ok(_) => Ok(Map::new()),
+        Err(e) => Err(format!(
+            "Could not convert {} message to JSON: {}",
+            descriptor.full_name(),
+            e
+        )),
+    }
+}
+
+/// Encodes a job result as a response message. Fields the message does not define are
+/// ignored, so that a result can carry more than the response.
+pub fn encode_message(
+    descriptor: MessageDescriptor,
+    value: &Value,
+) -> std::result::Result<Vec<u8>, String> {
+    let options = DeserializeOptions::new().deny_unknown_fields(false);
+    let message = match value {
+        Value::Null => DynamicMessage::new(descriptor),
+        value => DynamicMessage::deserialize_with_options(descriptor.clone(), value, &options)
+            .map_err(|e| {
+                format!(
+                    "The result does not match the {} message: {}",
+                    descriptor.full_name(),
+                    e
+                )
+            })?,
+    };
+
+    Ok(message.encode_to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const DEFINITION: &str = r#"
+        syntax = "proto3";
+        package orders.v1;
+
+        import "google/protobuf/timestamp.proto";
+
+        message GetOrderRequest {
+            string id = 1;
+            int64 quantity = 2;
+        }
+
+        message Order {
+            string id = 1;
+            repeated string items = 2;
+            google.protobuf.Timestamp created_at = 3;
+        }
+
+        service Orders {
+            rpc GetOrder(GetOrderRequest) returns (Order);
+            rpc WatchOrders(GetOrderRequest) returns (stream Order);
+            rpc UploadOrders(stream Order) returns (Order);
+        }
+    "#;
+
+    #[test]
+    fn test_describe() {
+        let pool = compile(DEFINITION).unwrap();
+        let services = describe(&pool);
+        assert_eq!(services.len(), 1);
+        assert_eq!(services[0].name, "orders.v1.Orders");
+        let methods = &services[0].methods;
+        assert_eq!(methods.len(), 3);
+        assert_eq!(methods[0].input_type, "orders.v1.GetOrderRequest");
+        assert!(methods[1].server_streaming && !methods[1].client_streaming);
+        assert!(methods[2].client_streaming);
+    }
+
+    #[test]
+    fn test_compile_error() {
+        assert!(compile("syntax = \"proto3\"; message {").is_err());
+        assert!(compile("syntax = \"proto3\"; import \"other.proto\";").is_err());
+    }
+
+    #[test]
+    fn test_find_method() {
+        let pool = compile(DEFINITION).unwrap();
+        assert!(find_method(&pool, "orders.v1.Orders", "GetOrder").is_ok());
+        assert!(find_method(&pool, "orders.v1.Orders", "WatchOrders").is_ok());
+        assert!(find_method(&pool, "Orders", "GetOrder").is_err());
+        assert!(find_method(&pool, "orders.v1.Orders", "DeleteOrder").is_err());
+        assert!(find_method(&pool, "orders.v1.Orders", "UploadOrders").is_err());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let pool = compile(DEFINITION).unwrap();
+        let method = find_method(&pool, "orders.v1.Orders", "GetOrder").unwrap();
+
+        let request = encode_message(method.input(), &json!({ "id": "o-1" })).unwrap();
+        let decoded = decode_message(method.input(), &request).unwrap();
+        assert_eq!(
+            Value::Object(decoded),
+            json!({ "id": "o-1", "quantity": 0 })
+        );
+
+        let response = encode_message(
+            method.output(),
+            &json!({
+                "id": "o-1",
+                "items": ["a", "b"],
+                "created_at": "2024-05-01T00:00:00Z",
+                "status": "ignored"
+            }),
+        )
+        .unwrap();
+        let decoded = decode_message(method.output(), &response).unwrap();
+        assert_eq!(decoded["items"], json!(["a", "b"]));
+        assert_eq!(decoded["created_at"], json!("2024-05-01T00:00:00Z"));
+
+        assert!(encode_message(method.output(), &Value::Null)
+            .unwrap()
+            .is_empty());
+        assert!(encode_message(method.output(), &json!({ "items": 1 })).is_err());
+        assert!(decode_message(method.input(), &[0xff, 0xff]).is_err());
+    }
+}
@@ -1,6 +1,7 @@
 use super::{
-    validate_authentication_method, HttpConfig, HttpConfigRequest, HttpMethod, HttpTrigger,
-    RouteExists, ROUTE_PATH_KEY_RE, VALID_ROUTE_PATH_RE,
+    http_trigger_auth::{self, validate_jwks_url, JwtAuthentication, JwtKeySource},
+    validate_authentication_method, AuthenticationMethod, HttpConfig, HttpConfigRequest,
+    HttpMethod, HttpTrigger, RouteExists, ROUTE_PATH_KEY_RE, VALID_ROUTE_PATH_RE,
 };
 use async_trait::async_trait;
 use axum::{extract::Path, routing::post, Extension, Json, Router};
@@ -17,7 +18,45 @@ use windmill_common::{
     DB,
 };
 use windmill_git_sync::{handle_deployment_metadata, DeployedObject};
-use windmill_trigger::{Trigger, TriggerCrud, TriggerData};
+use windmill_trigger::{transform::PayloadTransform, Trigger, TriggerCrud, TriggerData};
+
+/// Rejects a JWKS URL the requests of the route would refuse to fetch, so a bad one is
+/// reported when the trigger is saved. The resource can still change afterwards: each
+/// fetch checks the URL again.
+async fn validate_jwks_resource(
+    db: &DB,
+    workspace_id: &str,
+    config: &HttpConfigRequest,
+) -> Result<()> {
+    let (AuthenticationMethod::Jwt, Some(resource_path)) = (
+        config.authentication_method,
+        &config.authentication_resource_path,
+    ) else {
+        return Ok(());
+    };
+
+    let value = sqlx::query_scalar!(
+        "SELECT value FROM resource WHERE workspace_id = $1 AND path = $2",
+        workspace_id,
+        resource_path
+    )
+    .fetch_optional(db)
+    .await?
+    .flatten();
+
+    if let Some(Ok(http_trigger_auth::AuthenticationMethod::Jwt(JwtAuthentication {
+        jwt_key_source: JwtKeySource::JwksUrl { url },
+        ..
+    }))) = value.map(serde_json::from_value)
+    {
+        // A `$var:` reference is only resolved, and checked, when a request comes in
+        if !url.starts_with('$') {
+            validate_jwks_url(&url).await?;
+        }
+    }
+
+    Ok(())
+}
 
 pub async fn increase_trigger_version(tx: &mut PgConnection) -> Result<()> {
     sqlx::query!("SELECT nextval('http_trigger_version_seq')")
@@ -204,10 +243,14 @@ pub async fn insert_new_trigger_into_db(
                 is_static_website,
                 error_handler_path,
                 error_handler_args,
-                retry
+                retry,
+                rate_limit,
+                request_validation,
+                response_mapping,
+                upload_config
             )
             VALUES (
-                $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, now(), $20, $21, $22, $23
+                $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, now(), $20, $21, $22, $23, $24, $25, $26, $27
             )
             "#,
             w_id,
@@ -232,7 +275,11 @@ pub async fn insert_new_trigger_into_db(
             trigger.config.is_static_website,
             trigger.error_handling.error_handler_path,
             trigger.error_handling.error_handler_args as _,
-            trigger.error_handling.retry as _
+            trigger.error_handling.retry as _,
+            trigger.config.rate_limit as _,
+            trigger.config.request_validation as _,
+            trigger.config.response_mapping as _,
+            trigger.config.upload_config as _
         )
         .execute(&mut *tx)
         .await?;
@@ -307,6 +354,22 @@ pub async fn create_many_http_triggers(
             .map_err(|err| error_wrapper(&new_http_trigger.config.route_path, err.into()))?;
         }
 
+        if let Some(payload_transform) =
+            PayloadTransform::check(new_http_trigger.base.payload_transform.as_deref())
+                .await
+                .map_err(|err| error_wrapper(&new_http_trigger.config.route_path, err))?
+        {
+            sqlx::query(
+                "UPDATE http_trigger SET payload_transform = $1 WHERE workspace_id = $2 AND path = $3",
+            )
+            .bind(payload_transform)
+            .bind(&w_id)
+            .bind(&new_http_trigger.base.path)
+            .execute(&mut *tx)
+            .await
+            .map_err(|err| error_wrapper(&new_http_trigger.config.route_path, err.into()))?;
+        }
+
         // Bulk create is still authoring, so it records like the single-create
         // route rather than being the one way to make a trigger appear with no
         // history behind it.
@@ -435,6 +498,10 @@ impl TriggerCrud for HttpTrigger {
         "workspaced_route",
         "wrap_body",
         "raw_string",
+        "rate_limit",
+        "request_validation",
+        "response_mapping",
+        "upload_config",
     ];
 
     fn get_deployed_object(path: String, parent_path: Option<String>) -> DeployedObject {
@@ -449,8 +516,8 @@ impl TriggerCrud for HttpTrigger {
 
     async fn validate_new(
         &self,
-        _db: &DB,
-        _workspace_id: &str,
+        db: &DB,
+        workspace_id: &str,
         new: &Self::TriggerConfigRequest,
     ) -> Result<()> {
         if *CLOUD_HOSTED && (new.is_static_website || new.static_asset_config.is_some()) {
@@ -464,14 +531,35 @@ impl TriggerCrud for HttpTrigger {
         }
 
         validate_authentication_method(new.authentication_method, new.raw_string)?;
+        validate_jwks_resource(db, workspace_id, new).await?;
+
+        if let Some(rate_limit) = &new.rate_limit {
+            rate_limit.validate(new.authentication_method)?;
+        }
+
+        if let Some(request_validation) = &new.request_validation {
+            request_validation.validate(new.wrap_body.unwrap_or(false))?;
+        }
+
+        if let Some(response_mapping) = &new.response_mapping {
+            response_mapping.validate(new.request_type)?;
+        }
+
+        if let Some(upload_config) = &new.upload_config {
+            upload_config.validate(
+                new.authentication_method,
+                new.raw_string.unwrap_or(false),
+                new.is_static_website || new.static_asset_config.is_some(),
+            )?;
+        }
 
         Ok(())
     }
 
     async fn validate_edit(
         &self,
-        _db: &DB,
-        _workspace_id: &str,
+        db: &DB,
+        workspace_id: &str,
         edit: &Self::TriggerConfigRequest,
         _path: &str,
     ) -> Result<()> {
@@ -482,6 +570,27 @@ impl TriggerCrud for HttpTrigger {
         }
 
         validate_authentication_method(edit.authentication_method, edit.raw_string)?;
+        validate_jwks_resource(db, workspace_id, edit).await?;
+
+        if let Some(rate_limit) = &edit.rate_limit {
+            rate_limit.validate(edit.authentication_method)?;
+        }
+
+        if let Some(request_validation) = &edit.request_validation {
+            request_validation.validate(edit.wrap_body.unwrap_or(false))?;
+        }
+
+        if let Some(response_mapping) = &edit.response_mapping {
+            response_mapping.validate(edit.request_type)?;
+        }
+
+        if let Some(upload_config) = &edit.upload_config {
+            upload_config.validate(
+                edit.authentication_method,
+                edit.raw_string.unwrap_or(false),
+                edit.is_static_website || edit.static_asset_config.is_some(),
+            )?;
+        }
 
         Ok(())
     }
@@ -562,10 +671,14 @@ impl TriggerCrud for HttpTrigger {
                 is_static_website = $19,
                 error_handler_path = $20,
                 error_handler_args = $21,
-                retry = $22
+                retry = $22,
+                rate_limit = $23,
+                request_validation = $24,
+                response_mapping = $25,
+                upload_config = $26
             WHERE
-                workspace_id = $23 AND
-                path = $24
+                workspace_id = $27 AND
+                path = $28
             "#,
                 route_path,
                 &route_path_key,
@@ -589,6 +702,10 @@ impl TriggerCrud for HttpTrigger {
                 trigger.error_handling.error_handler_path,
                 trigger.error_handling.error_handler_args as _,
                 trigger.error_handling.retry as _,
+                trigger.config.rate_limit as _,
+                trigger.config.request_validation as _,
+                trigger.config.response_mapping as _,
+                trigger.config.upload_config as _,
                 workspace_id,
                 path,
             )
@@ -621,10 +738,14 @@ impl TriggerCrud for HttpTrigger {
                 is_static_website = $16,
                 error_handler_path = $17,
                 error_handler_args = $18,
-                retry = $19
+                retry = $19,
+                rate_limit = $20,
+                request_validation = $21,
+                response_mapping = $22,
+                upload_config = $23
             WHERE
-                workspace_id = $20 AND
-                path = $21
+                workspace_id = $24 AND
+                path = $25
             "#,
                 trigger.config.wrap_body,
                 trigger.config.raw_string,
@@ -645,6 +766,10 @@ impl TriggerCrud for HttpTrigger {
                 trigger.error_handling.error_handler_path,
                 trigger.error_handling.error_handler_args as _,
                 trigger.error_handling.retry as _,
+                trigger.config.rate_limit as _,
+                trigger.config.request_validation as _,
+                trigger.config.response_mapping as _,
+                trigger.config.upload_config as _,
                 workspace_id,
                 path,
             )
@@ -17,8 +17,15 @@ use windmill_types::s3::S3Object;
 use windmill_api_auth::ApiAuthed;
 use windmill_trigger::TriggerMode;
 
+use rate_limit::HttpRateLimit;
+use schema_validation::{HttpRequestValidation, HttpResponseMapping};
+use upload::HttpUploadConfig;
+
 pub mod handler;
 pub mod http_trigger_auth;
+pub mod rate_limit;
+pub mod schema_validation;
+pub mod upload;
 
 lazy_static::lazy_static! {
     pub static ref HTTP_ACCESS_CACHE: Cache<(String, String, ApiAuthed), ExpiringCacheEntry<()>> = Cache::new(100);
@@ -51,6 +58,11 @@ pub struct TriggerRoute {
     pub error_handler_args: Option<sqlx::types::Json<HashMap<String, serde_json::Value>>>,
     pub retry: Option<sqlx::types::Json<Retry>>,
     pub mode: TriggerMode,
+    pub payload_transform: Option<String>,
+    pub rate_limit: Option<sqlx::types::Json<HttpRateLimit>>,
+    pub request_validation: Option<sqlx::types::Json<HttpRequestValidation>>,
+    pub response_mapping: Option<sqlx::types::Json<HttpResponseMapping>>,
+    pub upload_config: Option<sqlx::types::Json<HttpUploadConfig>>,
 }
 
 pub struct RoutersCache {
@@ -102,6 +114,7 @@ pub enum AuthenticationMethod {
     BasicHttp,
     CustomScript,
     Signature,
+    Jwt,
 }
 
 #[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
@@ -119,6 +132,10 @@ pub struct HttpConfig {
     pub workspaced_route: bool,
     pub wrap_body: bool,
     pub raw_string: bool,
+    pub rate_limit: Option<SqlxJson<HttpRateLimit>>,
+    pub request_validation: Option<SqlxJson<HttpRequestValidation>>,
+    pub response_mapping: Option<SqlxJson<HttpResponseMapping>>,
+    pub upload_config: Option<SqlxJson<HttpUploadConfig>>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -136,6 +153,10 @@ pub struct HttpConfigRequest {
     pub workspaced_route: Option<bool>,
     pub wrap_body: Option<bool>,
     pub raw_string: Option<bool>,
+    pub rate_limit: Option<SqlxJson<HttpRateLimit>>,
+    pub request_validation: Option<SqlxJson<HttpRequestValidation>>,
+    pub response_mapping: Option<SqlxJson<HttpResponseMapping>>,
+    pub upload_config: Option<SqlxJson<HttpUploadConfig>>,
 }
 
 #[derive(Deserialize)]
@@ -154,6 +175,10 @@ struct HttpConfigRequestHelper {
     workspaced_route: Option<bool>,
     wrap_body: Option<bool>,
     raw_string: Option<bool>,
+    rate_limit: Option<SqlxJson<HttpRateLimit>>,
+    request_validation: Option<SqlxJson<HttpRequestValidation>>,
+    response_mapping: Option<SqlxJson<HttpResponseMapping>>,
+    upload_config: Option<SqlxJson<HttpUploadConfig>>,
 }
 
 impl<'de> Deserialize<'de> for HttpConfigRequest {
@@ -189,6 +214,10 @@ impl<'de> Deserialize<'de> for HttpConfigRequest {
             workspaced_route: helper.workspaced_route,
             wrap_body: helper.wrap_body,
             raw_string: helper.raw_string,
+            rate_limit: helper.rate_limit,
+            request_validation: helper.request_validation,
+            response_mapping: helper.response_mapping,
+            upload_config: helper.upload_config,
         })
     }
 }
@@ -261,7 +290,12 @@ pub async fn refresh_routers(db: &DB) -> Result<(bool, RwLockReadGuard<'_, Route
                         error_handler_path,
                         error_handler_args as "error_handler_args: _",
                         retry as "retry: _",
-                        mode as "mode: _"
+                        mode as "mode: _",
+                        payload_transform,
+                        rate_limit as "rate_limit: _",
+                        request_validation as "request_validation: _",
+                        response_mapping as "response_mapping: _",
+                        upload_config as "upload_config: _"
                     FROM
                         http_trigger
                     WHERE
@@ -277,6 +311,13 @@ pub async fn refresh_routers(db: &DB) -> Result<(bool, RwLockReadGuard<'_, Route
             let http_route_workspaced = HTTP_ROUTE_WORKSPACED_ROUTE.load(std::sync::atomic::Ordering::Relaxed);
 
             for trigger in triggers {
+                if let Some(SqlxJson(validation)) = &trigger.request_validation {
+                    validation.compile();
+                }
+                if let Some(SqlxJson(mapping)) = &trigger.response_mapping {
+                    mapping.compile();
+                }
+
                 let full_path =
                     if trigger.workspaced_route || *CLOUD_HOSTED || http_route_workspaced {
                         format!("/{}/{}", trigger.workspace_id, trigger.route_path)
@@ -513,6 +554,7 @@ mod tests {
             AuthenticationMethod::BasicHttp,
             AuthenticationMethod::CustomScript,
             AuthenticationMethod::Signature,
+            AuthenticationMethod::Jwt,
         ] {
             let json = serde_json::to_value(method).unwrap();
             let deserialized: AuthenticationMethod = serde_json::from_value(json).unwrap();
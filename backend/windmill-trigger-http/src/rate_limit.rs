@@ -0,0 +1,527 @@
+//! Per-route rate limits and daily quotas of HTTP triggers.
+//!
+//! Counters are kept in Postgres and updated with a single upsert per check, so every API
+//! server of an instance shares the same budget for a given client.
+
+use axum::response::{IntoResponse, Response};
+use http::{header, HeaderMap, HeaderValue, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use windmill_common::{
+    error::{Error, Result},
+    login_rate_limit::extract_client_ip,
+    DB,
+};
+
+use crate::{http_trigger_auth::JwtClaims, AuthenticationMethod};
+
+const SECONDS_PER_DAY: i64 = 24 * 60 * 60;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpRateLimit {
+    pub key: RateLimitKey,
+    #[serde(default)]
+    pub limit: Option<RateLimitAlgorithm>,
+    /// Maximum number of requests per client and per UTC day.
+    #[serde(default)]
+    pub daily_quota: Option<i64>,
+}
+
+/// What identifies a client: each distinct value gets its own budget.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RateLimitKey {
+    /// Address from the `X-Real-IP` or `X-Forwarded-For` header set by the reverse proxy.
+    ClientIp,
+    /// Value of a request header, typically the API key checked by the route.
+    ApiKey { header_name: String },
+    /// `sub` claim of the bearer token, only available on JWT authenticated routes.
+    JwtSubject,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RateLimitAlgorithm {
+    TokenBucket { capacity: u32, refill_per_second: f64 },
+    FixedWindow { max_requests: u32, window_secs: u32 },
+}
+
+impl HttpRateLimit {
+    pub fn validate(&self, authentication_method: AuthenticationMethod) -> Result<()> {
+        if self.limit.is_none() && self.daily_quota.is_none() {
+            return Err(Error::BadRequest(
+                "Rate limit must define a limit, a daily quota or both".to_string(),
+            ));
+        }
+
+        match &self.key {
+            RateLimitKey::ApiKey { header_name } if header_name.trim().is_empty() => {
+                return Err(Error::BadRequest(
+                    "Rate limit by API key requires a header name".to_string(),
+                ));
+            }
+            RateLimitKey::JwtSubject if authentication_method != AuthenticationMethod::Jwt => {
+                return Err(Error::BadRequest(
+                    "Rate limit by JWT subject requires JWT authentication".to_string(),
+                ));
+            }
+            _ => {}
+        }
+
+        match self.limit {
+            Some(RateLimitAlgorithm::TokenBucket { capacity, refill_per_second })
+                if capacity == 0
+                    || !(refill_per_second > 0.0)
+                    || !refill_per_second.is_finite() =>
+            {
+                return Err(Error::BadRequest(
+                    "Token bucket capacity and refill rate must be positive".to_string(),
+                ));
+            }
+            Some(RateLimitAlgorithm::FixedWindow { max_requests, window_secs })
+                if max_requests == 0
+                    || window_secs == 0
+                    || window_secs as i64 > SECONDS_PER_DAY =>
+            {
+                return Err(Error::BadRequest(
+                    "Fixed window must allow at least one request over 1 second to 1 day"
+                        .to_string(),
+                ));
+            }
+            _ => {}
+        }
+
+        if self.daily_quota.is_some_and(|quota| quota <= 0) {
+            return Err(Error::BadRequest(
+                "Daily quota must be positive".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Identifier of the client the request is counted against. Raw values (API keys, token
+    /// subjects) are hashed so that they are never stored in the counters tables. Requests
+    /// without a value share a single budget.
+    pub fn client_key(&self, headers: &HeaderMap, jwt_claims: Option<&JwtClaims>) -> String {
+        let (kind, value) = match &self.key {
+            RateLimitKey::ClientIp => ("ip", extract_client_ip(headers)),
+            RateLimitKey::ApiKey { header_name } => (
+                "api_key",
+                headers
+                    .get(header_name.as_str())
+                    .and_then(|value| value.to_str().ok())
+                    .map(str::to_string),
+            ),
+            RateLimitKey::JwtSubject => (
+                "sub",
+                jwt_claims
+                    .and_then(|claims| claims.get("sub"))
+                    .and_then(|sub| sub.as_str())
+                    .map(str::to_string),
+            ),
+        };
+
+        match value {
+            Some(value) => format!("{}:{}", kind, hex::encode(Sha256::digest(value.as_bytes()))),
+            None => format!("{}:unknown", kind),
+        }
+    }
+
+    /// Counts the request against the limit then the daily quota. Returns why it was rejected
+    /// if either is exhausted.
+    pub async fn check(
+        &self,
+        db: &DB,
+        workspace_id: &str,
+        trigger_path: &str,
+        client_key: &str,
+    ) -> Result<Option<RateLimitExceeded>> {
+        match self.limit {
+            Some(RateLimitAlgorithm::TokenBucket { capacity, refill_per_second }) => {
+                let bucket = sqlx::query!(
+                    r#"
+                    INSERT INTO http_trigger_rate_limit_bucket AS bucket (
+                        workspace_id, trigger_path, client_key, tokens, allowed, refilled_at
+                    )
+                    VALUES ($1, $2, $3, $4::FLOAT8 - 1, true, now())
+                    ON CONFLICT (workspace_id, trigger_path, client_key) DO UPDATE SET
+                        tokens = LEAST($4, bucket.tokens + EXTRACT(EPOCH FROM now() - bucket.refilled_at)::FLOAT8 * $5::FLOAT8)
+                            - CASE
+                                WHEN LEAST($4, bucket.tokens + EXTRACT(EPOCH FROM now() - bucket.refilled_at)::FLOAT8 * $5) >= 1
+                                THEN 1
+                                ELSE 0
+                            END,
+                        allowed = LEAST($4, bucket.tokens + EXTRACT(EPOCH FROM now() - bucket.refilled_at)::FLOAT8 * $5) >= 1,
+                        refilled_at = now()
+                    RETURNING allowed, tokens
+                    "#,
+                    workspace_id,
+                    trigger_path,
+                    client_key,
+                    capacity as f64,
+                    refill_per_second,
+                )
+                .fetch_one(db)
+                .await?;
+
+                if !bucket.allowed {
+                    return Ok(Some(RateLimitExceeded::new(
+                        "rate limit exceeded",
+                        (1.0 - bucket.tokens) / refill_per_second,
+                    )));
+                }
+            }
+            Some(RateLimitAlgorithm::FixedWindow { max_requests, window_secs }) => {
+                let (request_count, reset_in_secs) = increment_window_counter(
+                    db,
+                    workspace_id,
+                    trigger_path,
+                    "window",
+                    client_key,
+                    window_secs as i64,
+                )
+                .await?;
+
+                if request_count > max_requests as i64 {
+                    return Ok(Some(RateLimitExceeded::new(
+                        "rate limit exceeded",
+                        reset_in_secs,
+                    )));
+                }
+            }
+            None => {}
+        }
+
+        if let Some(daily_quota) = self.daily_quota {
+            let (request_count, reset_in_secs) = increment_window_counter(
+                db,
+                workspace_id,
+                trigger_path,
+                "daily_quota",
+                client_key,
+                SECONDS_PER_DAY,
+            )
+            .await?;
+
+            if request_count > daily_quota {
+                return Ok(Some(RateLimitExceeded::new(
+                    "daily quota exceeded",
+                    reset_in_secs,
+                )));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+/// Increments the counter of the current window, windows being aligned on the unix epoch
+/// (daily ones start at midnight UTC). Returns the count and the seconds until the window ends.
+async fn increment_window_counter(
+    db: &DB,
+    workspace_id: &str,
+    trigger_path: &str,
+    counter: &str,
+    client_key: &str,
+    window_secs: i64,
+) -> Result<(i64, f64)> {
+    let window = sqlx::query!(
+        r#"
+        INSERT INTO http_trigger_rate_limit_window AS counter (
+            workspace_id, trigger_path, counter, client_key, window_start, request_count
+        )
+        VALUES (
+            $1, $2, $3, $4,
+            to_timestamp(floor(EXTRACT(EPOCH FROM now()) / $5::BIGINT) * $5::BIGINT),
+            1
+        )
+        ON CONFLICT (workspace_id, trigger_path, counter, client_key) DO UPDATE SET
+            request_count = CASE
+                WHEN counter.window_start = EXCLUDED.window_start THEN counter.request_count + 1
+                ELSE 1
+            END,
+            window_start = EXCLUDED.window_start
+        RETURNING
+            request_count,
+            EXTRACT(EPOCH FROM window_start + make_interval(secs => $5::BIGINT) - now())::FLOAT8 AS "reset_in_secs!"
+        "#,
+        workspace_id,
+        trigger_path,
+        counter,
+        client_key,
+        window_secs,
+    )
+    .fetch_one(db)
+    .await?;
+
+    Ok((window.request_count, window.reset_in_secs))
+}
+
+#[derive(Debug)]
+pub struct RateLimitExceeded {
+    reason: &'static str,
+    retry_after_secs: u64,
+}
+
+impl RateLimitExceeded {
+    fn new(reason: &'static str, retry_after_secs: f64) -> Self {
+        Self { reason, retry_after_secs: retry_after_secs.ceil().max(1.0) as u64 }
+    }
+}
+
+impl IntoResponse for RateLimitExceeded {
+    fn into_response(self) -> Response {
+        let body = json!({ "error": self.reason, "retry_after": self.retry_after_secs });
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::RETRY_AFTER,
+            HeaderValue::from(self.retry_after_secs),
+        );
+        headers.insert(
+            header::CONTENT_TYPE,
+            HeaderValue::from_static("application/json"),
+        );
+
+        (StatusCode::TOO_MANY_REQUESTS, headers, body.to_string()).into_response()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rate_limit(key: RateLimitKey) -> HttpRateLimit {
+        HttpRateLimit {
+            key,
+            limit: Some(RateLimitAlgorithm::TokenBucket { capacity: 10, refill_per_second: 1.0 }),
+            daily_quota: None,
+        }
+    }
+
+    #[test]
+    fn test_rate_limit_serde() {
+        let json = serde_json::json!({
+            "key": { "type": "api_key", "header_name": "X-Api-Key" },
+            "limit": { "type": "fixed_window", "max_requests": 100, "window_secs": 60 },
+            "daily_quota": 10000
+        });
+        let rate_limit: HttpRateLimit = serde_json::from_value(json).unwrap();
+        assert_eq!(
+            rate_limit.key,
+            RateLimitKey::ApiKey { header_name: "X-Api-Key".to_string() }
+        );
+        assert!(matches!(
+            rate_limit.limit,
+            Some(RateLimitAlgorithm::FixedWindow { max_requests: 100, window_secs: 60 })
+        ));
+        assert_eq!(rate_limit.daily_quota, Some(10000));
+    }
+
+    #[test]
+    fn test_validate_rate_limit() {
+        assert!(rate_limit(RateLimitKey::ClientIp)
+            .validate(AuthenticationMethod::None)
+            .is_ok());
+
+        let empty = HttpRateLimit { key: RateLimitKey::ClientIp, limit: None, daily_quota: None };
+        assert!(empty.validate(AuthenticationMethod::None).is_err());
+
+        let mut bucket = rate_limit(RateLimitKey::ClientIp);
+        bucket.limit =
+            Some(RateLimitAlgorithm::TokenBucket { capacity: 10, refill_per_second: 0.0 });
+        assert!(bucket.validate(AuthenticationMethod::None).is_err());
+
+        let mut window = rate_limit(RateLimitKey::ClientIp);
+        window.limit =
+            Some(RateLimitAlgorithm::FixedWindow { max_requests: 10, window_secs: 172800 });
+        assert!(window.validate(AuthenticationMethod::None).is_err());
+
+        let mut quota = rate_limit(RateLimitKey::ClientIp);
+        quota.daily_quota = Some(0);
+        assert!(quota.validate(AuthenticationMethod::None).is_err());
+    }
+
+    #[test]
+    fn test_validate_jwt_subject_requires_jwt_authentication() {
+        let rate_limit = rate_limit(RateLimitKey::JwtSubject);
+        assert!(rate_limit.validate(AuthenticationMethod::ApiKey).is_err());
+        assert!(rate_limit.validate(AuthenticationMethod::Jwt).is_ok());
+    }
+
+    #[test]
+    fn test_client_key_by_ip() {
+        let rate_limit = rate_limit(RateLimitKey::ClientIp);
+        let mut headers = HeaderMap::new();
+        assert_eq!(rate_limit.client_key(&headers, None), "ip:unknown");
+
+        headers.insert("x-forwarded-for", "203.0.113.7, 10.0.0.1".parse().unwrap());
+        let key = rate_limit.client_key(&headers, None);
+        assert!(key.starts_with("ip:"));
+
+        let mut other_headers = HeaderMap::new();
+        other_headers.insert("x-real-ip", "203.0.113.7".parse().unwrap());
+        assert_eq!(rate_limit.client_key(&other_headers, None), key);
+    }
+
+    #[test]
+    fn test_client_key_hashes_api_key() {
+        let rate_limit = rate_limit(RateLimitKey::ApiKey { header_name: "X-Api-Key".to_string() });
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Api-Key", "super-secret".parse().unwrap());
+        let key = rate_limit.client_key(&headers, None);
+        assert!(key.starts_with("api_key:"));
+        assert!(!key.contains("super-secret"));
+    }
+
+    #[test]
+    fn test_client_key_by_jwt_subject() {
+        let rate_limit = rate_limit(RateLimitKey::JwtSubject);
+        let claims: JwtClaims =
+            serde_json::from_value(serde_json::json!({ "sub": "gateway" })).unwrap();
+        let other_claims: JwtClaims =
+            serde_json::from_value(serde_json::json!({ "sub": "mesh" })).unwrap();
+        let headers = HeaderMap::new();
+        assert_ne!(
+            rate_limit.client_key(&headers, Some(&claims)),
+            rate_limit.client_key(&headers, Some(&other_claims))
+        );
+        assert_eq!(rate_limit.client_key(&headers, None), "sub:unknown");
+    }
+
+    const TRIGGER_PATH: &str = "f/test/rate_limited";
+
+    async fn insert_trigger(db: &DB) {
+        sqlx::query(
+            "INSERT INTO workspace (id, name, owner) VALUES ('test-workspace', 'test-workspace', 'test-user')",
+        )
+        .execute(db)
+        .await
+        .unwrap();
+        sqlx::query(
+            "INSERT INTO http_trigger (workspace_id, path, edited_by, edited_at, route_path,
+                route_path_key, script_path, is_flow, http_method, request_type,
+                authentication_method, mode, permissioned_as)
+             VALUES ('test-workspace', $1, 'test-user', NOW(), 'limited', 'limited',
+                'f/test/handler', false, 'post', 'async', 'none', 'enabled', 'u/test-user')",
+        )
+        .bind(TRIGGER_PATH)
+        .execute(db)
+        .await
+        .unwrap();
+    }
+
+    async fn check(db: &DB, rate_limit: &HttpRateLimit) -> Option<RateLimitExceeded> {
+        rate_limit
+            .check(db, "test-workspace", TRIGGER_PATH, "ip:client")
+            .await
+            .unwrap()
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_token_bucket_exhausts_then_refills(db: DB) {
+        insert_trigger(&db).await;
+        let rate_limit = HttpRateLimit {
+            key: RateLimitKey::ClientIp,
+            limit: Some(RateLimitAlgorithm::TokenBucket { capacity: 3, refill_per_second: 1.0 }),
+            daily_quota: None,
+        };
+
+        for _ in 0..3 {
+            assert!(check(&db, &rate_limit).await.is_none());
+        }
+        let exceeded = check(&db, &rate_limit).await.unwrap();
+        assert_eq!(exceeded.reason, "rate limit exceeded");
+        assert_eq!(exceeded.retry_after_secs, 1);
+
+        // Two seconds of refill give back two tokens, not more than the capacity.
+        sqlx::query(
+            "UPDATE http_trigger_rate_limit_bucket SET refilled_at = refilled_at - interval '2 seconds'",
+        )
+        .execute(&db)
+        .await
+        .unwrap();
+        assert!(check(&db, &rate_limit).await.is_none());
+        assert!(check(&db, &rate_limit).await.is_none());
+        assert!(check(&db, &rate_limit).await.is_some());
+
+        sqlx::query(
+            "UPDATE http_trigger_rate_limit_bucket SET refilled_at = refilled_at - interval '1 hour'",
+        )
+        .execute(&db)
+        .await
+        .unwrap();
+        for _ in 0..3 {
+            assert!(check(&db, &rate_limit).await.is_none());
+        }
+        assert!(check(&db, &rate_limit).await.is_some());
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_fixed_window_exhausts_then_rolls_over(db: DB) {
+        insert_trigger(&db).await;
+        let rate_limit = HttpRateLimit {
+            key: RateLimitKey::ClientIp,
+            limit: Some(RateLimitAlgorithm::FixedWindow { max_requests: 2, window_secs: 3600 }),
+            daily_quota: None,
+        };
+
+        assert!(check(&db, &rate_limit).await.is_none());
+        assert!(check(&db, &rate_limit).await.is_none());
+        let exceeded = check(&db, &rate_limit).await.unwrap();
+        assert_eq!(exceeded.reason, "rate limit exceeded");
+        assert!((1..=3600).contains(&exceeded.retry_after_secs));
+
+        // Another client has its own budget.
+        assert!(rate_limit
+            .check(&db, "test-workspace", TRIGGER_PATH, "ip:other")
+            .await
+            .unwrap()
+            .is_none());
+
+        sqlx::query(
+            "UPDATE http_trigger_rate_limit_window SET window_start = window_start - interval '1 hour'
+             WHERE client_key = 'ip:client'",
+        )
+        .execute(&db)
+        .await
+        .unwrap();
+        assert!(check(&db, &rate_limit).await.is_none());
+        let request_count: i64 = sqlx::query_scalar(
+            "SELECT request_count FROM http_trigger_rate_limit_window WHERE client_key = 'ip:client'",
+        )
+        .fetch_one(&db)
+        .await
+        .unwrap();
+        assert_eq!(request_count, 1);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_daily_quota_counts_after_the_limit(db: DB) {
+        insert_trigger(&db).await;
+        let rate_limit = HttpRateLimit {
+            key: RateLimitKey::ClientIp,
+            limit: Some(RateLimitAlgorithm::TokenBucket { capacity: 10, refill_per_second: 1.0 }),
+            daily_quota: Some(2),
+        };
+
+        assert!(check(&db, &rate_limit).await.is_none());
+        assert!(check(&db, &rate_limit).await.is_none());
+        assert_eq!(
+            check(&db, &rate_limit).await.unwrap().reason,
+            "daily quota exceeded"
+        );
+    }
+
+    #[test]
+    fn test_rate_limit_exceeded_response() {
+        let response = RateLimitExceeded::new("rate limit exceeded", 1.2).into_response();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "2");
+
+        let response = RateLimitExceeded::new("rate limit exceeded", 0.0).into_response();
+        assert_eq!(response.headers()[header::RETRY_AFTER], "1");
+    }
+}
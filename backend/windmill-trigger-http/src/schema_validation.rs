@@ -0,0 +1,790 @@
+//! Request validation and response mapping of HTTP triggers.
+//!
+//! Bodies, query parameters and headers are checked with the same [`SchemaValidator`] the
+//! workers use for `schema_validation` scripts, before any job is created. Query parameters
+//! and headers always arrive as strings, so a value whose property is declared with another
+//! type is parsed as JSON first: `?limit=10` satisfies `{"type": "integer"}`.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, OnceLock},
+    time::Duration,
+};
+
+use axum::response::{IntoResponse, Response};
+use http::{header, HeaderValue, StatusCode};
+use quick_cache::sync::Cache;
+use serde::{de::IgnoredAny, Deserialize, Serialize};
+use serde_json::{json, value::RawValue, Value};
+use windmill_common::{
+    error::{Error, Result},
+    schema::SchemaValidator,
+    utils::ExpiringCacheEntry,
+    worker::to_raw_value,
+    DB,
+};
+use windmill_trigger::filter::{CompiledFilters, Filter};
+
+use crate::RequestType;
+
+const JSON_SCHEMA_DRAFT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+lazy_static::lazy_static! {
+    static ref RUNNABLE_VALIDATOR_CACHE: Cache<(String, String, bool), ExpiringCacheEntry<Option<Arc<SchemaValidator>>>> = Cache::new(100);
+}
+
+/// What incoming requests are checked against. Each part is only validated when set.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HttpRequestValidation {
+    #[serde(default)]
+    pub body: Option<BodySchema>,
+    /// JSON schema of the query parameters, an object schema with one property per parameter.
+    #[serde(default)]
+    pub query: Option<Value>,
+    /// JSON schema of the headers, an object schema with one property per header. Header
+    /// names are matched case-insensitively.
+    #[serde(default)]
+    pub headers: Option<Value>,
+    /// Validators built from the schemas, shared by the clones of a loaded route.
+    #[serde(skip)]
+    compiled: Arc<OnceLock<CompiledRequestValidation>>,
+}
+
+/// Validators of each part, or why its schema could not be compiled.
+#[derive(Debug)]
+struct CompiledRequestValidation {
+    body: Option<std::result::Result<SchemaValidator, String>>,
+    query: Option<std::result::Result<SchemaValidator, String>>,
+    headers: Option<std::result::Result<SchemaValidator, String>>,
+}
+
+impl CompiledRequestValidation {
+    fn new(validation: &HttpRequestValidation) -> Self {
+        let compile = |schema: &Value, lowercase_keys: bool| {
+            build_validator(schema, lowercase_keys).map_err(|e| e.to_string())
+        };
+
+        Self {
+            body: match &validation.body {
+                Some(BodySchema::Custom { schema }) => Some(compile(schema, false)),
+                _ => None,
+            },
+            query: validation
+                .query
+                .as_ref()
+                .map(|schema| compile(schema, false)),
+            headers: validation
+                .headers
+                .as_ref()
+                .map(|schema| compile(schema, true)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BodySchema {
+    /// The schema of the script or flow the route runs. Skipped when the runnable has a
+    /// preprocessor or the route applies a payload transform, as the body is then not what
+    /// the runnable receives.
+    Runnable,
+    Custom {
+        schema: Value,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RequestPart {
+    Body,
+    Query,
+    Headers,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ValidationIssue {
+    pub location: RequestPart,
+    pub message: String,
+}
+
+#[derive(Debug)]
+pub struct RequestValidationFailed {
+    pub errors: Vec<ValidationIssue>,
+}
+
+impl IntoResponse for RequestValidationFailed {
+    fn into_response(self) -> Response {
+        let body = json!({ "error": "request_validation_failed", "errors": self.errors });
+
+        (
+            StatusCode::BAD_REQUEST,
+            [(
+                header::CONTENT_TYPE,
+                HeaderValue::from_static("application/json"),
+            )],
+            body.to_string(),
+        )
+            .into_response()
+    }
+}
+
+/// Fill in what [`SchemaValidator`] requires but a hand-written schema commonly omits.
+fn with_schema_defaults(schema: &Value, lowercase_keys: bool) -> Result<Value> {
+    let mut schema = schema
+        .as_object()
+        .cloned()
+        .ok_or_else(|| Error::BadRequest("Validation schema must be an object".to_string()))?;
+
+    schema
+        .entry("$schema")
+        .or_insert_with(|| Value::String(JSON_SCHEMA_DRAFT.to_string()));
+    schema
+        .entry("required")
+        .or_insert_with(|| Value::Array(vec![]));
+    schema
+        .entry("properties")
+        .or_insert_with(|| Value::Object(Default::default()));
+
+    if lowercase_keys {
+        if let Some(Value::Array(required)) = schema.get_mut("required") {
+            for name in required.iter_mut() {
+                if let Value::String(s) = name {
+                    *s = s.to_lowercase();
+                }
+            }
+        }
+        if let Some(Value::Object(properties)) = schema.get_mut("properties") {
+            *properties = std::mem::take(properties)
+                .into_iter()
+                .map(|(k, v)| (k.to_lowercase(), v))
+                .collect();
+        }
+    }
+
+    Ok(Value::Object(schema))
+}
+
+fn build_validator(schema: &Value, lowercase_keys: bool) -> Result<SchemaValidator> {
+    let schema = with_schema_defaults(schema, lowercase_keys)?;
+    SchemaValidator::from_schema(&schema.to_string())
+        .map_err(|e| Error::BadRequest(format!("Invalid validation schema: {e}")))
+}
+
+/// Parse the string values of properties declared with a non-string type, so they are
+/// validated as the type they stand for.
+fn coerce_string_values(
+    schema: &Value,
+    args: &HashMap<String, Box<RawValue>>,
+    lowercase_keys: bool,
+) -> HashMap<String, Box<RawValue>> {
+    let properties = schema.get("properties").and_then(Value::as_object);
+
+    args.iter()
+        .map(|(key, value)| {
+            let property = properties.and_then(|properties| {
+                if lowercase_keys {
+                    properties
+                        .iter()
+                        .find(|(name, _)| name.eq_ignore_ascii_case(key))
+                        .map(|(_, property)| property)
+                } else {
+                    properties.get(key)
+                }
+            });
+            let is_string = property
+                .and_then(|property| property.get("type"))
+                .map_or(true, |typ| typ == "string");
+
+            let coerced = if is_string {
+                None
+            } else {
+                serde_json::from_str::<String>(value.get())
+                    .ok()
+                    .and_then(|s| serde_json::from_str::<Box<RawValue>>(&s).ok())
+            };
+
+            (key.clone(), coerced.unwrap_or_else(|| value.clone()))
+        })
+        .collect()
+}
+
+impl HttpRequestValidation {
+    pub fn validate(&self, wrap_body: bool) -> Result<()> {
+        match &self.body {
+            Some(BodySchema::Runnable) if wrap_body => {
+                return Err(Error::BadRequest(
+                    "Validating the body against the runnable schema is not possible when the body is wrapped, use a custom schema instead".to_string(),
+                ));
+            }
+            Some(BodySchema::Custom { schema }) => {
+                build_validator(schema, false)?;
+            }
+            _ => {}
+        }
+
+        if let Some(query) = &self.query {
+            build_validator(query, false)?;
+        }
+
+        if let Some(headers) = &self.headers {
+            build_validator(headers, true)?;
+        }
+
+        Ok(())
+    }
+
+    /// Build the validators ahead of the first request. Called when the route is loaded,
+    /// otherwise they are built by the first [`Self::check`].
+    pub fn compile(&self) {
+        self.compiled();
+    }
+
+    fn compiled(&self) -> &CompiledRequestValidation {
+        self.compiled
+            .get_or_init(|| CompiledRequestValidation::new(self))
+    }
+
+    /// Check a request. `body` is `None` when it is not a JSON object, `runnable_validator`
+    /// when the body should not be checked against the runnable schema.
+    pub fn check(
+        &self,
+        body: Option<&HashMap<String, Box<RawValue>>>,
+        runnable_validator: Option<&SchemaValidator>,
+        query: &HashMap<String, Box<RawValue>>,
+        headers: &HashMap<String, Box<RawValue>>,
+    ) -> std::result::Result<(), RequestValidationFailed> {
+        let mut errors = self.body_issues(body, runnable_validator);
+        errors.extend(self.parameter_issues(query, headers));
+        into_result(errors)
+    }
+
+    /// Check the query parameters and headers only, for routes that stream their body to
+    /// storage: they are checked before anything is uploaded.
+    pub fn check_parameters(
+        &self,
+        query: &HashMap<String, Box<RawValue>>,
+        headers: &HashMap<String, Box<RawValue>>,
+    ) -> std::result::Result<(), RequestValidationFailed> {
+        into_result(self.parameter_issues(query, headers))
+    }
+
+    /// Check the body only, the counterpart of [`Self::check_parameters`].
+    pub fn check_body(
+        &self,
+        body: Option<&HashMap<String, Box<RawValue>>>,
+        runnable_validator: Option<&SchemaValidator>,
+    ) -> std::result::Result<(), RequestValidationFailed> {
+        into_result(self.body_issues(body, runnable_validator))
+    }
+
+    fn body_issues(
+        &self,
+        body: Option<&HashMap<String, Box<RawValue>>>,
+        runnable_validator: Option<&SchemaValidator>,
+    ) -> Vec<ValidationIssue> {
+        let mut errors = vec![];
+
+        let body_validator = match &self.compiled().body {
+            Some(Ok(validator)) => Some(validator),
+            Some(Err(e)) => {
+                push_issues(&mut errors, RequestPart::Body, vec![e.clone()]);
+                None
+            }
+            None => None,
+        };
+
+        if let Some(validator) = body_validator.or(runnable_validator) {
+            let messages = match body {
+                Some(body) => validator.validation_errors(body),
+                None => vec!["Request body should be a JSON object".to_string()],
+            };
+            push_issues(&mut errors, RequestPart::Body, messages);
+        }
+
+        errors
+    }
+
+    fn parameter_issues(
+        &self,
+        query: &HashMap<String, Box<RawValue>>,
+        headers: &HashMap<String, Box<RawValue>>,
+    ) -> Vec<ValidationIssue> {
+        let mut errors = vec![];
+        let compiled = self.compiled();
+
+        for (location, schema, validator, args, lowercase_keys) in [
+            (
+                RequestPart::Query,
+                &self.query,
+                &compiled.query,
+                query,
+                false,
+            ),
+            (
+                RequestPart::Headers,
+                &self.headers,
+                &compiled.headers,
+                headers,
+                true,
+            ),
+        ] {
+            let (Some(schema), Some(validator)) = (schema, validator) else {
+                continue;
+            };
+            let messages = match validator {
+                Ok(validator) => {
+                    let args = coerce_string_values(schema, args, lowercase_keys);
+                    validator.validation_errors(&args)
+                }
+                Err(e) => vec![e.clone()],
+            };
+            push_issues(&mut errors, location, messages);
+        }
+
+        errors
+    }
+}
+
+fn push_issues(errors: &mut Vec<ValidationIssue>, location: RequestPart, messages: Vec<String>) {
+    errors.extend(
+        messages
+            .into_iter()
+            .map(|message| ValidationIssue { location, message }),
+    );
+}
+
+fn into_result(errors: Vec<ValidationIssue>) -> std::result::Result<(), RequestValidationFailed> {
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(RequestValidationFailed { errors })
+    }
+}
+
+/// Latest schema of a script or flow, `None` if it has a preprocessor since its arguments
+/// are then not the request body.
+pub async fn get_runnable_schema(
+    db: &DB,
+    w_id: &str,
+    script_path: &str,
+    is_flow: bool,
+) -> Option<Value> {
+    if is_flow {
+        let row = sqlx::query!(
+            r#"SELECT
+                f.schema AS "schema: serde_json::Value",
+                fv.value->>'preprocessor_module' IS NOT NULL AS "has_preprocessor: bool"
+            FROM flow f
+            LEFT JOIN flow_version fv ON fv.id = f.versions[array_length(f.versions, 1)]
+                AND fv.workspace_id = f.workspace_id
+            WHERE f.path = $1 AND f.workspace_id = $2 AND NOT f.archived"#,
+            script_path,
+            w_id,
+        )
+        .fetch_optional(db)
+        .await
+        .map_err(|e| {
+            tracing::warn!("Failed to fetch flow schema for {script_path}: {e}");
+            e
+        })
+        .ok()
+        .flatten()?;
+
+        if row.has_preprocessor.unwrap_or(false) {
+            return None;
+        }
+        row.schema
+    } else {
+        let row = sqlx::query!(
+            r#"SELECT
+                schema AS "schema: serde_json::Value",
+                has_preprocessor
+            FROM script
+            WHERE path = $1 AND workspace_id = $2
+                AND NOT archived AND NOT deleted
+            ORDER BY created_at DESC
+            LIMIT 1"#,
+            script_path,
+            w_id,
+        )
+        .fetch_optional(db)
+        .await
+        .map_err(|e| {
+            tracing::warn!("Failed to fetch script schema for {script_path}: {e}");
+            e
+        })
+        .ok()
+        .flatten()?;
+
+        if row.has_preprocessor.unwrap_or(false) {
+            return None;
+        }
+        row.schema
+    }
+}
+
+/// Validator built from the runnable schema, cached for a minute so a deployment is picked
+/// up without querying the schema on every request.
+pub async fn get_runnable_validator(
+    db: &DB,
+    w_id: &str,
+    script_path: &str,
+    is_flow: bool,
+) -> Option<Arc<SchemaValidator>> {
+    let cache_key = (w_id.to_string(), script_path.to_string(), is_flow);
+
+    if let Some(entry) = RUNNABLE_VALIDATOR_CACHE.get(&cache_key) {
+        if entry.expiry > std::time::Instant::now() {
+            return entry.value;
+        }
+    }
+
+    let validator = get_runnable_schema(db, w_id, script_path, is_flow)
+        .await
+        .and_then(
+            |schema| match SchemaValidator::from_schema(&schema.to_string()) {
+                Ok(validator) => Some(Arc::new(validator)),
+                Err(e) => {
+                    tracing::warn!(
+                    "Skipping body validation of {script_path}, its schema is not supported: {e}"
+                );
+                    None
+                }
+            },
+        );
+
+    RUNNABLE_VALIDATOR_CACHE.insert(
+        cache_key,
+        ExpiringCacheEntry {
+            value: validator.clone(),
+            expiry: std::time::Instant::now() + Duration::from_secs(60),
+        },
+    );
+
+    validator
+}
+
+/// Status and content type of the response of a sync route, picked from the job result.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HttpResponseMapping {
+    /// Evaluated in order, the first matching rule applies. Without a match the response
+    /// is built as if no mapping was configured.
+    pub responses: Vec<HttpResponseRule>,
+    /// Filters of each rule, `None` when they do not compile. Shared by the clones of a
+    /// loaded route.
+    #[serde(skip)]
+    compiled: Arc<OnceLock<Vec<Option<CompiledFilters>>>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpResponseRule {
+    pub status: u16,
+    #[serde(default)]
+    pub content_type: Option<String>,
+    /// Match failed jobs, whose result is the error, instead of successful ones.
+    #[serde(default)]
+    pub on_failure: bool,
+    /// Same filters as the other triggers, tested against the job result. No filter
+    /// matches any result.
+    #[serde(default)]
+    pub filters: Vec<Value>,
+    #[serde(default)]
+    pub filter_logic_or: bool,
+    /// Documentation only: used in the generated OpenAPI document.
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub schema: Option<Value>,
+}
+
+#[derive(Deserialize)]
+struct DeclaredResponse {
+    #[serde(alias = "wm_status_code")]
+    windmill_status_code: Option<IgnoredAny>,
+    #[serde(alias = "wm_content_type")]
+    windmill_content_type: Option<IgnoredAny>,
+}
+
+impl HttpResponseRule {
+    fn compile_filters(&self) -> Result<CompiledFilters> {
+        let filters = self
+            .filters
+            .iter()
+            .map(|filter| serde_json::from_value::<Filter>(filter.clone()))
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .map_err(|e| Error::BadRequest(format!("Invalid response filter: {e}")))?;
+        CompiledFilters::new(filters, self.filter_logic_or)
+    }
+}
+
+impl HttpResponseMapping {
+    pub fn validate(&self, request_type: RequestType) -> Result<()> {
+        if request_type != RequestType::Sync {
+            return Err(Error::BadRequest(
+                "Response mapping is only available on sync routes".to_string(),
+            ));
+        }
+
+        for (index, rule) in self.responses.iter().enumerate() {
+            if !(100..=599).contains(&rule.status) || StatusCode::from_u16(rule.status).is_err() {
+                return Err(Error::BadRequest(format!(
+                    "Response #{}: invalid status code {}",
+                    index + 1,
+                    rule.status
+                )));
+            }
+            if let Some(content_type) = &rule.content_type {
+                HeaderValue::from_str(content_type).map_err(|_| {
+                    Error::BadRequest(format!(
+                        "Response #{}: invalid content type {}",
+                        index + 1,
+                        content_type
+                    ))
+                })?;
+            }
+            CompiledFilters::validate(&rule.filters)?;
+            rule.compile_filters()?;
+        }
+
+        Ok(())
+    }
+
+    /// Compile the filters of the rules ahead of the first response. Called when the route
+    /// is loaded, otherwise they are compiled by the first [`Self::apply`].
+    pub fn compile(&self) {
+        self.compiled();
+    }
+
+    fn compiled(&self) -> &[Option<CompiledFilters>] {
+        self.compiled.get_or_init(|| {
+            self.responses
+                .iter()
+                .map(|rule| match rule.compile_filters() {
+                    Ok(filters) => Some(filters),
+                    Err(e) => {
+                        tracing::error!("Ignoring response rule with invalid filters: {e}");
+                        None
+                    }
+                })
+                .collect()
+        })
+    }
+
+    /// Wrap `result` in the `windmill_status_code`/`windmill_content_type` envelope of the
+    /// first matching rule. A result that already sets them is left untouched.
+    pub fn apply(&self, result: Box<RawValue>, success: bool) -> Box<RawValue> {
+        if let Ok(DeclaredResponse { windmill_status_code, windmill_content_type }) =
+            serde_json::from_str(result.get())
+        {
+            if windmill_status_code.is_some() || windmill_content_type.is_some() {
+                return result;
+            }
+        }
+
+        let rule = self
+            .responses
+            .iter()
+            .zip(self.compiled())
+            .find(|(rule, filters)| {
+                rule.on_failure != success
+                    && filters
+                        .as_ref()
+                        .is_some_and(|filters| filters.matches(result.get()))
+            })
+            .map(|(rule, _)| rule);
+
+        match rule {
+            Some(rule) => to_raw_value(&json!({
+                "windmill_status_code": rule.status,
+                "windmill_content_type": rule.content_type,
+                "result": result,
+            })),
+            None => result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn raw_map(value: Value) -> HashMap<String, Box<RawValue>> {
+        value
+            .as_object()
+            .unwrap()
+            .iter()
+            .map(|(k, v)| (k.clone(), to_raw_value(v)))
+            .collect()
+    }
+
+    fn validation() -> HttpRequestValidation {
+        HttpRequestValidation {
+            body: Some(BodySchema::Custom {
+                schema: json!({
+                    "type": "object",
+                    "properties": { "name": { "type": "string" }, "age": { "type": "integer" } },
+                    "required": ["name"]
+                }),
+            }),
+            query: Some(json!({
+                "type": "object",
+                "properties": { "limit": { "type": "integer" } },
+                "required": ["limit"]
+            })),
+            headers: Some(json!({
+                "type": "object",
+                "properties": { "X-Tenant": { "type": "string" } },
+                "required": ["X-Tenant"]
+            })),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_request_validation_passes() {
+        let validation = validation();
+        validation.validate(false).unwrap();
+
+        let body = raw_map(json!({ "name": "alice", "age": 30 }));
+        let query = raw_map(json!({ "limit": "10" }));
+        let headers = raw_map(json!({ "x-tenant": "acme" }));
+
+        validation
+            .check(Some(&body), None, &query, &headers)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_request_validation_reports_every_part() {
+        let validation = validation();
+
+        let body = raw_map(json!({ "age": "thirty" }));
+        let query = raw_map(json!({ "limit": "ten" }));
+        let headers = raw_map(json!({}));
+
+        let failed = validation
+            .check(Some(&body), None, &query, &headers)
+            .unwrap_err();
+        let locations = failed
+            .errors
+            .iter()
+            .map(|issue| issue.location)
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            locations,
+            vec![
+                RequestPart::Body,
+                RequestPart::Body,
+                RequestPart::Query,
+                RequestPart::Headers
+            ]
+        );
+    }
+
+    #[test]
+    fn test_compiled_validators_are_shared_by_clones() {
+        let validation = validation();
+        validation.compile();
+
+        let route_copy = validation.clone();
+        assert!(Arc::ptr_eq(&validation.compiled, &route_copy.compiled));
+        assert!(route_copy.compiled.get().is_some());
+
+        let mapping = mapping();
+        assert!(mapping.compiled.get().is_none());
+        mapping.compile();
+        assert_eq!(mapping.clone().compiled.get().map(Vec::len), Some(3));
+    }
+
+    #[test]
+    fn test_parameters_are_checked_without_the_body() {
+        let validation = validation();
+
+        let query = raw_map(json!({ "limit": "ten" }));
+        let headers = raw_map(json!({ "x-tenant": "acme" }));
+
+        let failed = validation.check_parameters(&query, &headers).unwrap_err();
+        assert_eq!(failed.errors.len(), 1);
+        assert_eq!(failed.errors[0].location, RequestPart::Query);
+
+        let query = raw_map(json!({ "limit": "10" }));
+        validation.check_parameters(&query, &headers).unwrap();
+        assert!(validation.check_body(None, None).is_err());
+    }
+
+    #[test]
+    fn test_request_validation_rejects_non_object_body() {
+        let validation = HttpRequestValidation { body: validation().body, ..Default::default() };
+
+        let failed = validation
+            .check(None, None, &HashMap::new(), &HashMap::new())
+            .unwrap_err();
+        assert_eq!(failed.errors.len(), 1);
+        assert_eq!(failed.errors[0].location, RequestPart::Body);
+    }
+
+    #[test]
+    fn test_runnable_body_requires_unwrapped_body() {
+        let validation =
+            HttpRequestValidation { body: Some(BodySchema::Runnable), ..Default::default() };
+        assert!(validation.validate(true).is_err());
+        assert!(validation.validate(false).is_ok());
+    }
+
+    fn mapping() -> HttpResponseMapping {
+        serde_json::from_value(json!({
+            "responses": [
+                {
+                    "status": 404,
+                    "filters": [{ "key": "found", "value": false }]
+                },
+                {
+                    "status": 500,
+                    "on_failure": true
+                },
+                {
+                    "status": 200,
+                    "content_type": "text/csv"
+                }
+            ]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_response_mapping_picks_first_matching_rule() {
+        let mapping = mapping();
+        mapping.validate(RequestType::Sync).unwrap();
+        assert!(mapping.validate(RequestType::Async).is_err());
+
+        let mapped: Value = serde_json::from_str(
+            mapping
+                .apply(to_raw_value(&json!({ "found": false })), true)
+                .get(),
+        )
+        .unwrap();
+        assert_eq!(mapped["windmill_status_code"], 404);
+        assert_eq!(mapped["result"], json!({ "found": false }));
+
+        let mapped: Value =
+            serde_json::from_str(mapping.apply(to_raw_value(&json!("a,b")), true).get()).unwrap();
+        assert_eq!(mapped["windmill_status_code"], 200);
+        assert_eq!(mapped["windmill_content_type"], "text/csv");
+
+        let mapped: Value = serde_json::from_str(
+            mapping
+                .apply(to_raw_value(&json!({ "error": "boom" })), false)
+                .get(),
+        )
+        .unwrap();
+        assert_eq!(mapped["windmill_status_code"], 500);
+    }
+
+    #[test]
+    fn test_response_mapping_keeps_declared_status() {
+        let result = to_raw_value(&json!({ "windmill_status_code": 201, "result": 1 }));
+        let mapped = mapping().apply(result.clone(), true);
+        assert_eq!(mapped.get(), result.get());
+    }
+}
@@ -0,0 +1,268 @@
+//! Routes whose request body is streamed to the workspace object storage instead of being
+//! read into memory. The runnable receives `S3Object` references: the whole body as the
+//! `body` argument, or one argument per field of a `multipart/form-data` body with every
+//! file part uploaded on its own.
+
+use serde::{Deserialize, Serialize};
+use windmill_common::error::{Error, Result};
+
+use crate::AuthenticationMethod;
+
+const DEFAULT_UPLOAD_PREFIX: &str = "http_trigger_uploads";
+
+/// Most parts, text fields and files together, accepted in a multipart body.
+pub const MAX_MULTIPART_FIELDS: usize = 1000;
+/// Most files accepted in a multipart body. Bounds the whole body, with the text fields,
+/// when the route sets a maximum size.
+pub const MAX_MULTIPART_FILES: usize = 100;
+const MAX_EXTENSION_LEN: usize = 16;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HttpUploadConfig {
+    /// Workspace storage uploads are written to, the primary storage when unset.
+    #[serde(default)]
+    pub storage: Option<String>,
+    /// Key prefix of the uploaded objects.
+    #[serde(default)]
+    pub prefix: Option<String>,
+    /// Largest accepted object in bytes. Applies to each file of a multipart body.
+    #[serde(default)]
+    pub max_size_bytes: Option<u64>,
+    /// Accepted content types, `image/*` style wildcards included. Empty accepts any.
+    /// Checked against the request for a plain body and against each file part of a
+    /// multipart body.
+    #[serde(default)]
+    pub allowed_content_types: Vec<String>,
+}
+
+impl HttpUploadConfig {
+    pub fn validate(
+        &self,
+        authentication_method: AuthenticationMethod,
+        raw_string: bool,
+        serves_static_assets: bool,
+    ) -> Result<()> {
+        if serves_static_assets {
+            return Err(Error::BadRequest(
+                "Streaming uploads are not available on static asset routes".to_string(),
+            ));
+        }
+
+        if matches!(
+            authentication_method,
+            AuthenticationMethod::CustomScript | AuthenticationMethod::Signature
+        ) || raw_string
+        {
+            return Err(Error::BadRequest(
+                "Streaming uploads cannot be combined with the raw body, which custom script and signature authentication rely on".to_string(),
+            ));
+        }
+
+        if let Some(prefix) = &self.prefix {
+            if prefix.starts_with('/') || prefix.split('/').any(|segment| segment == "..") {
+                return Err(Error::BadRequest(format!(
+                    "Invalid upload prefix `{}`: it must be a relative path without `..` segments",
+                    prefix
+                )));
+            }
+        }
+
+        if self.max_size_bytes == Some(0) {
+            return Err(Error::BadRequest(
+                "Maximum upload size must be greater than 0".to_string(),
+            ));
+        }
+
+        for content_type in &self.allowed_content_types {
+            match content_type.split_once('/') {
+                Some((typ, subtype)) if !typ.is_empty() && !subtype.is_empty() => {}
+                _ => {
+                    return Err(Error::BadRequest(format!(
+                        "Invalid content type `{}` in upload allowlist",
+                        content_type
+                    )))
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Whether `content_type`, parameters ignored, is on the allowlist.
+    pub fn is_content_type_allowed(&self, content_type: &str) -> bool {
+        if self.allowed_content_types.is_empty() {
+            return true;
+        }
+
+        let essence = content_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+        let Some((typ, subtype)) = essence.split_once('/') else {
+            return false;
+        };
+
+        self.allowed_content_types.iter().any(|allowed| {
+            let allowed = allowed.trim().to_ascii_lowercase();
+            match allowed.split_once('/') {
+                Some(("*", "*")) => true,
+                Some((allowed_typ, "*")) => allowed_typ == typ,
+                Some((allowed_typ, allowed_subtype)) => {
+                    allowed_typ == typ && allowed_subtype == subtype
+                }
+                None => false,
+            }
+        })
+    }
+
+    /// Largest multipart body, parts headers aside: every file at the maximum size plus
+    /// the text fields, which share `text_limit`. Unbounded without a maximum size.
+    pub fn multipart_size_limit(&self, text_limit: usize) -> Option<u64> {
+        self.max_size_bytes.map(|max_size| {
+            max_size
+                .saturating_mul(MAX_MULTIPART_FILES as u64)
+                .saturating_add(text_limit as u64)
+        })
+    }
+
+    pub fn object_key(&self, name: &str) -> String {
+        format!(
+            "{}/{}",
+            self.prefix
+                .as_deref()
+                .map(|prefix| prefix.trim_end_matches('/'))
+                .filter(|prefix| !prefix.is_empty())
+                .unwrap_or(DEFAULT_UPLOAD_PREFIX),
+            name
+        )
+    }
+}
+
+/// Extension of the object uploaded for a client `filename`, with its dot. Only a short
+/// alphanumeric one is kept: the name is chosen by the client and ends up in the key.
+pub fn object_extension(filename: &str) -> String {
+    filename
+        .rsplit_once('.')
+        .map(|(_, extension)| extension)
+        .filter(|extension| {
+            (1..=MAX_EXTENSION_LEN).contains(&extension.len())
+                && extension.bytes().all(|b| b.is_ascii_alphanumeric())
+        })
+        .map(|extension| format!(".{extension}"))
+        .unwrap_or_default()
+}
+
+/// `Content-Disposition` of an uploaded object. The client filename is quoted, so quotes
+/// and backslashes are escaped and control characters, CR/LF included, are dropped.
+pub fn content_disposition(filename: Option<&str>) -> String {
+    match filename {
+        Some(filename) => {
+            let mut escaped = String::with_capacity(filename.len());
+            for c in filename.chars().filter(|c| !c.is_control()) {
+                if c == '"' || c == '\\' {
+                    escaped.push('\\');
+                }
+                escaped.push(c);
+            }
+            format!("inline; filename=\"{}\"", escaped)
+        }
+        None => "inline".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_type_allowlist() {
+        let config = HttpUploadConfig {
+            allowed_content_types: vec!["image/*".to_string(), "application/pdf".to_string()],
+            ..Default::default()
+        };
+
+        assert!(config.is_content_type_allowed("image/png"));
+        assert!(config.is_content_type_allowed("Image/JPEG"));
+        assert!(config.is_content_type_allowed("application/pdf; charset=binary"));
+        assert!(!config.is_content_type_allowed("application/json"));
+        assert!(!config.is_content_type_allowed("imagepng"));
+
+        assert!(HttpUploadConfig::default().is_content_type_allowed("anything/at-all"));
+    }
+
+    #[test]
+    fn test_upload_config_validation() {
+        let config = HttpUploadConfig::default();
+        assert!(config
+            .validate(AuthenticationMethod::ApiKey, false, false)
+            .is_ok());
+        assert!(config
+            .validate(AuthenticationMethod::Signature, false, false)
+            .is_err());
+        assert!(config
+            .validate(AuthenticationMethod::None, true, false)
+            .is_err());
+        assert!(config
+            .validate(AuthenticationMethod::None, false, true)
+            .is_err());
+
+        let config = HttpUploadConfig { prefix: Some("a/../b".to_string()), ..Default::default() };
+        assert!(config
+            .validate(AuthenticationMethod::None, false, false)
+            .is_err());
+
+        let config = HttpUploadConfig {
+            allowed_content_types: vec!["pdf".to_string()],
+            ..Default::default()
+        };
+        assert!(config
+            .validate(AuthenticationMethod::None, false, false)
+            .is_err());
+    }
+
+    #[test]
+    fn test_object_key() {
+        assert_eq!(
+            HttpUploadConfig::default().object_key("a.png"),
+            "http_trigger_uploads/a.png"
+        );
+        let config = HttpUploadConfig { prefix: Some("inbox/".to_string()), ..Default::default() };
+        assert_eq!(config.object_key("a.png"), "inbox/a.png");
+    }
+
+    #[test]
+    fn test_object_extension() {
+        assert_eq!(object_extension("report.final.csv"), ".csv");
+        assert_eq!(object_extension("photo.JPEG"), ".JPEG");
+        assert_eq!(object_extension("no_extension"), "");
+        assert_eq!(object_extension("trailing."), "");
+        assert_eq!(object_extension("a.b/../../etc"), "");
+        assert_eq!(object_extension("a.tar-gz"), "");
+        assert_eq!(object_extension("a.abcdefghijklmnopq"), "");
+    }
+
+    #[test]
+    fn test_content_disposition_escapes_filename() {
+        assert_eq!(content_disposition(None), "inline");
+        assert_eq!(
+            content_disposition(Some("a.csv")),
+            r#"inline; filename="a.csv""#
+        );
+        assert_eq!(
+            content_disposition(Some("a\"b\\c.csv\r\nX-Injected: 1")),
+            r#"inline; filename="a\"b\\c.csvX-Injected: 1""#
+        );
+    }
+
+    #[test]
+    fn test_multipart_size_limit() {
+        assert_eq!(HttpUploadConfig::default().multipart_size_limit(1024), None);
+        let config = HttpUploadConfig { max_size_bytes: Some(10), ..Default::default() };
+        assert_eq!(
+            config.multipart_size_limit(1024),
+            Some(10 * MAX_MULTIPART_FILES as u64 + 1024)
+        );
+    }
+}
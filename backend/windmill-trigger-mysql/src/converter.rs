@@ -0,0 +1,435 @@
+use std::{num::ParseIntError, str::FromStr, string::FromUtf8Error};
+
+use chrono::{DateTime, Utc};
+use mysql_async::Value as MysqlValue;
+use rust_decimal::Decimal;
+use serde_json::{Number, Value};
+use thiserror::Error;
+
+/// Binlog rows only carry the MySQL storage type of each column, which is not enough to
+/// tell e.g. `TEXT` from `BLOB` or signed from unsigned integers: the declared type is
+/// looked up in `information_schema` (see `relation.rs`) and values converted after it.
+#[derive(Debug, Error)]
+pub enum ConverterError {
+    #[error("invalid int value")]
+    InvalidInt(#[from] ParseIntError),
+
+    #[error("invalid decimal: {0}")]
+    InvalidNumeric(#[from] rust_decimal::Error),
+
+    #[error("invalid string value: {0}")]
+    InvalidStr(#[from] FromUtf8Error),
+
+    #[error("invalid json: {0}")]
+    InvalidJson(#[from] serde_json::Error),
+
+    #[error("{0}")]
+    Custom(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct ColumnInfo {
+    pub name: String,
+    /// `information_schema.COLUMNS.DATA_TYPE`, e.g. `int`.
+    pub data_type: String,
+    /// `information_schema.COLUMNS.COLUMN_TYPE`, e.g. `int unsigned` or `enum('a','b')`.
+    pub column_type: String,
+}
+
+impl ColumnInfo {
+    pub fn new(name: String, data_type: String, column_type: String) -> Self {
+        Self { name, data_type: data_type.to_lowercase(), column_type }
+    }
+
+    fn is_unsigned(&self) -> bool {
+        self.column_type.to_lowercase().contains("unsigned")
+    }
+
+    /// The members of an `enum(...)` or `set(...)` column, in declaration order.
+    fn members(&self) -> Vec<String> {
+        let Some(list) = self
+            .column_type
+            .find('(')
+            .zip(self.column_type.rfind(')'))
+            .map(|(start, end)| &self.column_type[start + 1..end])
+        else {
+            return vec![];
+        };
+
+        let mut members = vec![];
+        let mut member = String::new();
+        let mut chars = list.chars().peekable();
+        let mut in_quotes = false;
+        while let Some(c) = chars.next() {
+            match c {
+                '\'' if in_quotes && chars.peek() == Some(&'\'') => {
+                    chars.next();
+                    member.push('\'');
+                }
+                '\'' => {
+                    if in_quotes {
+                        members.push(std::mem::take(&mut member));
+                    }
+                    in_quotes = !in_quotes;
+                }
+                c if in_quotes => member.push(c),
+                _ => {}
+            }
+        }
+        members
+    }
+}
+
+fn f64_to_json_number(raw_val: f64) -> Result<Value, ConverterError> {
+    let temp = Number::from_f64(raw_val)
+        .ok_or(ConverterError::Custom("invalid json-float".to_string()))?;
+    Ok(Value::Number(temp))
+}
+
+/// Integers are logged with the width of their column, unsigned ones included: read
+/// back as signed they come out negative above the signed maximum.
+fn unsigned_from_signed(data_type: &str, n: i64) -> u64 {
+    match data_type {
+        "tinyint" => n as u8 as u64,
+        "smallint" => n as u16 as u64,
+        "mediumint" => (n as u32 & 0x00FF_FFFF) as u64,
+        "int" | "integer" => n as u32 as u64,
+        _ => n as u64,
+    }
+}
+
+fn format_date(year: u16, month: u8, day: u8) -> String {
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+fn format_time(hour: u32, minute: u8, second: u8, micros: u32) -> String {
+    if micros == 0 {
+        format!("{:02}:{:02}:{:02}", hour, minute, second)
+    } else {
+        format!("{:02}:{:02}:{:02}.{:06}", hour, minute, second, micros)
+    }
+}
+
+fn bytes_to_string(bytes: Vec<u8>) -> Result<String, ConverterError> {
+    Ok(String::from_utf8(bytes)?)
+}
+
+/// `TIMESTAMP` columns are stored in UTC as seconds since the epoch.
+fn timestamp_from_epoch(seconds: i64, micros: u32) -> Result<Value, ConverterError> {
+    let timestamp = DateTime::<Utc>::from_timestamp(seconds, micros * 1000)
+        .ok_or_else(|| ConverterError::Custom(format!("invalid timestamp: {}", seconds)))?;
+    Ok(Value::String(timestamp.to_string()))
+}
+
+pub struct Converter;
+
+impl Converter {
+    pub fn try_from_value(column: &ColumnInfo, value: MysqlValue) -> Result<Value, ConverterError> {
+        let data_type = column.data_type.as_str();
+        let value = match (data_type, value) {
+            (_, MysqlValue::NULL) => Value::Null,
+            ("tinyint" | "smallint" | "mediumint" | "int" | "integer" | "bigint", value) => {
+                match value {
+                    MysqlValue::Int(n) if column.is_unsigned() => {
+                        Value::Number(unsigned_from_signed(data_type, n).into())
+                    }
+                    MysqlValue::Int(n) => Value::Number(n.into()),
+                    MysqlValue::UInt(n) => Value::Number(n.into()),
+                    MysqlValue::Bytes(bytes) => {
+                        let str = bytes_to_string(bytes)?;
+                        if column.is_unsigned() {
+                            Value::Number(str.parse::<u64>()?.into())
+                        } else {
+                            Value::Number(str.parse::<i64>()?.into())
+                        }
+                    }
+                    value => return Err(unexpected(column, value)),
+                }
+            }
+            ("year", MysqlValue::Int(n)) => Value::Number(n.into()),
+            ("year", MysqlValue::UInt(n)) => Value::Number(n.into()),
+            ("bit", MysqlValue::Bytes(bytes)) => {
+                if bytes.len() > 8 {
+                    return Err(ConverterError::Custom(format!(
+                        "bit value of {} bytes does not fit in 64 bits",
+                        bytes.len()
+                    )));
+                }
+                let bits = bytes
+                    .iter()
+                    .fold(0u64, |acc, byte| (acc << 8) | *byte as u64);
+                Value::Number(bits.into())
+            }
+            ("bit", MysqlValue::Int(n)) => Value::Number((n as u64).into()),
+            ("bit", MysqlValue::UInt(n)) => Value::Number(n.into()),
+            ("decimal" | "numeric", MysqlValue::Bytes(bytes)) => {
+                serde_json::json!(Decimal::from_str(&bytes_to_string(bytes)?)?)
+            }
+            ("float" | "double" | "real", MysqlValue::Float(n)) => f64_to_json_number(n as f64)?,
+            ("float" | "double" | "real", MysqlValue::Double(n)) => f64_to_json_number(n)?,
+            ("date", MysqlValue::Date(year, month, day, ..)) => {
+                Value::String(format_date(year, month, day))
+            }
+            ("datetime", MysqlValue::Date(year, month, day, hour, minute, second, micros)) => {
+                Value::String(format!(
+                    "{} {}",
+                    format_date(year, month, day),
+                    format_time(hour as u32, minute, second, micros)
+                ))
+            }
+            ("timestamp", MysqlValue::Int(seconds)) => timestamp_from_epoch(seconds, 0)?,
+            ("timestamp", MysqlValue::UInt(seconds)) => timestamp_from_epoch(seconds as i64, 0)?,
+            ("timestamp", MysqlValue::Bytes(bytes)) => {
+                let str = bytes_to_string(bytes)?;
+                let (seconds, fraction) = str.split_once('.').unwrap_or((&str, ""));
+                let micros = if fraction.is_empty() {
+                    0
+                } else {
+                    format!("{:0<6}", fraction)[..6].parse::<u32>()?
+                };
+                timestamp_from_epoch(seconds.parse::<i64>()?, micros)?
+            }
+            ("timestamp", MysqlValue::Date(year, month, day, hour, minute, second, micros)) => {
+                Value::String(format!(
+                    "{} {} UTC",
+                    format_date(year, month, day),
+                    format_time(hour as u32, minute, second, micros)
+                ))
+            }
+            ("time", MysqlValue::Time(negative, days, hours, minutes, seconds, micros)) => {
+                Value::String(format!(
+                    "{}{}",
+                    if negative { "-" } else { "" },
+                    format_time(days * 24 + hours as u32, minutes, seconds, micros)
+                ))
+            }
+            ("enum", MysqlValue::Int(index)) => {
+                // 0 is the empty string MySQL stores for invalid values.
+                let member = usize::try_from(index)
+                    .ok()
+                    .and_then(|index| index.checked_sub(1))
+                    .and_then(|index| column.members().get(index).cloned())
+                    .unwrap_or_default();
+                Value::String(member)
+            }
+            ("set", MysqlValue::Int(mask)) => {
+                let members = column
+                    .members()
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(i, _)| *i < 64 && mask as u64 & (1 << i) != 0)
+                    .map(|(_, member)| member)
+                    .collect::<Vec<_>>();
+                Value::String(members.join(","))
+            }
+            ("json", MysqlValue::Bytes(bytes)) => serde_json::from_slice(&bytes)?,
+            (
+                "binary" | "varbinary" | "blob" | "tinyblob" | "mediumblob" | "longblob",
+                MysqlValue::Bytes(bytes),
+            ) => serde_json::to_value(bytes)?,
+            (_, MysqlValue::Bytes(bytes)) => match String::from_utf8(bytes) {
+                Ok(str) => Value::String(str),
+                Err(err) => serde_json::to_value(err.into_bytes())?,
+            },
+            (_, MysqlValue::Int(n)) => Value::Number(n.into()),
+            (_, MysqlValue::UInt(n)) => Value::Number(n.into()),
+            (_, MysqlValue::Float(n)) => f64_to_json_number(n as f64)?,
+            (_, MysqlValue::Double(n)) => f64_to_json_number(n)?,
+            (_, value) => return Err(unexpected(column, value)),
+        };
+
+        Ok(value)
+    }
+}
+
+fn unexpected(column: &ColumnInfo, value: MysqlValue) -> ConverterError {
+    ConverterError::Custom(format!(
+        "unexpected value {:?} for column {} of type {}",
+        value, column.name, column.column_type
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn column(data_type: &str, column_type: &str) -> ColumnInfo {
+        ColumnInfo::new(
+            "c".to_string(),
+            data_type.to_string(),
+            column_type.to_string(),
+        )
+    }
+
+    fn convert(data_type: &str, column_type: &str, value: MysqlValue) -> Value {
+        Converter::try_from_value(&column(data_type, column_type), value).unwrap()
+    }
+
+    #[test]
+    fn test_null() {
+        assert_eq!(convert("int", "int", MysqlValue::NULL), Value::Null);
+    }
+
+    #[test]
+    fn test_integers() {
+        assert_eq!(convert("int", "int", MysqlValue::Int(-42)), json!(-42));
+        assert_eq!(
+            convert("bigint", "bigint", MysqlValue::Int(9999999999)),
+            json!(9999999999i64)
+        );
+        assert_eq!(
+            convert("bigint", "bigint unsigned", MysqlValue::UInt(u64::MAX)),
+            json!(u64::MAX)
+        );
+    }
+
+    #[test]
+    fn test_unsigned_integers_logged_as_signed() {
+        assert_eq!(
+            convert("tinyint", "tinyint unsigned", MysqlValue::Int(-1)),
+            json!(255)
+        );
+        assert_eq!(
+            convert("smallint", "smallint unsigned", MysqlValue::Int(-1)),
+            json!(65535)
+        );
+        assert_eq!(
+            convert("mediumint", "mediumint unsigned", MysqlValue::Int(-1)),
+            json!(16777215)
+        );
+        assert_eq!(
+            convert("int", "int unsigned", MysqlValue::Int(-1)),
+            json!(4294967295u32)
+        );
+        assert_eq!(
+            convert("bigint", "bigint unsigned", MysqlValue::Int(-1)),
+            json!(u64::MAX)
+        );
+    }
+
+    #[test]
+    fn test_decimal() {
+        let result = convert(
+            "decimal",
+            "decimal(10,2)",
+            MysqlValue::Bytes(b"123.45".to_vec()),
+        );
+        assert_eq!(
+            result.to_string(),
+            json!(Decimal::from_str("123.45").unwrap()).to_string()
+        );
+    }
+
+    #[test]
+    fn test_floats() {
+        assert_eq!(
+            convert("double", "double", MysqlValue::Double(2.5)),
+            json!(2.5)
+        );
+        assert_eq!(
+            convert("float", "float", MysqlValue::Float(0.5)),
+            json!(0.5)
+        );
+        assert!(Converter::try_from_value(
+            &column("double", "double"),
+            MysqlValue::Double(f64::NAN)
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_bit() {
+        assert_eq!(
+            convert("bit", "bit(10)", MysqlValue::Bytes(vec![0x02, 0x01])),
+            json!(513)
+        );
+    }
+
+    #[test]
+    fn test_strings_and_bytes() {
+        assert_eq!(
+            convert(
+                "varchar",
+                "varchar(255)",
+                MysqlValue::Bytes(b"hello".to_vec())
+            ),
+            json!("hello")
+        );
+        assert_eq!(
+            convert("blob", "blob", MysqlValue::Bytes(b"Hello".to_vec())),
+            json!([72, 101, 108, 108, 111])
+        );
+        assert!(Converter::try_from_value(
+            &column("varchar", "varchar(255)"),
+            MysqlValue::Bytes(vec![0xff])
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_dates_and_times() {
+        assert_eq!(
+            convert("date", "date", MysqlValue::Date(2024, 1, 15, 0, 0, 0, 0)),
+            json!("2024-01-15")
+        );
+        assert_eq!(
+            convert(
+                "datetime",
+                "datetime(6)",
+                MysqlValue::Date(2024, 1, 15, 14, 30, 0, 250)
+            ),
+            json!("2024-01-15 14:30:00.000250")
+        );
+        assert_eq!(
+            convert(
+                "datetime",
+                "datetime",
+                MysqlValue::Date(0, 0, 0, 0, 0, 0, 0)
+            ),
+            json!("0000-00-00 00:00:00")
+        );
+        assert_eq!(
+            convert("time", "time", MysqlValue::Time(true, 1, 2, 3, 4, 0)),
+            json!("-26:03:04")
+        );
+        assert_eq!(
+            convert("timestamp", "timestamp", MysqlValue::Int(1705329000)),
+            json!("2024-01-15 14:30:00 UTC")
+        );
+        assert_eq!(
+            convert(
+                "timestamp",
+                "timestamp(3)",
+                MysqlValue::Bytes(b"1705329000.5".to_vec())
+            ),
+            json!("2024-01-15 14:30:00.500 UTC")
+        );
+    }
+
+    #[test]
+    fn test_enum_and_set() {
+        let enum_type = "enum('new','it''s paid','shipped')";
+        assert_eq!(
+            convert("enum", enum_type, MysqlValue::Int(2)),
+            json!("it's paid")
+        );
+        assert_eq!(convert("enum", enum_type, MysqlValue::Int(0)), json!(""));
+        assert_eq!(
+            convert("set", "set('a','b','c')", MysqlValue::Int(0b101)),
+            json!("a,c")
+        );
+    }
+
+    #[test]
+    fn test_json_text() {
+        assert_eq!(
+            convert(
+                "json",
+                "json",
+                MysqlValue::Bytes(br#"{"a": [1, 2]}"#.to_vec())
+            ),
+            json!({"a": [1, 2]})
+        );
+    }
+}
@@ -0,0 +1,206 @@
+use async_trait::async_trait;
+use sqlx::PgConnection;
+use windmill_api_auth::ApiAuthed;
+use windmill_common::DB;
+use windmill_common::{
+    db::UserDB,
+    error::{Error, Result},
+};
+use windmill_git_sync::DeployedObject;
+use windmill_trigger::{Trigger, TriggerCrud, TriggerData};
+
+use super::{
+    check_binlog_settings, get_raw_mysql_connection, parse_tables_to_track, resolve_mysql_resource,
+    MysqlConfig, MysqlConfigRequest, MysqlTrigger, TestMysqlConfig,
+};
+
+#[async_trait]
+impl TriggerCrud for MysqlTrigger {
+    type TriggerConfig = MysqlConfig;
+    type Trigger = Trigger<Self::TriggerConfig>;
+    type TriggerConfigRequest = MysqlConfigRequest;
+    type TestConnectionConfig = TestMysqlConfig;
+
+    const TABLE_NAME: &'static str = "mysql_trigger";
+    const TRIGGER_TYPE: &'static str = "mysql";
+    const DRAFT_KIND: windmill_common::user_drafts::UserDraftItemKind =
+        windmill_common::user_drafts::UserDraftItemKind::TriggerMysql;
+    const SUPPORTS_SERVER_STATE: bool = true;
+    const SUPPORTS_TEST_CONNECTION: bool = true;
+    const ROUTE_PREFIX: &'static str = "/mysql_triggers";
+    const DEPLOYMENT_NAME: &'static str = "MySQL trigger";
+    const ADDITIONAL_SELECT_FIELDS: &[&'static str] = &[
+        "mysql_resource_path",
+        "tables_to_track",
+        "transaction_to_track",
+        "binlog_file",
+        "binlog_position",
+        "last_gtid",
+    ];
+    const IS_ALLOWED_ON_CLOUD: bool = false;
+
+    fn get_deployed_object(path: String, parent_path: Option<String>) -> DeployedObject {
+        DeployedObject::MysqlTrigger { path, parent_path }
+    }
+
+    async fn validate_config(
+        &self,
+        _db: &DB,
+        config: &Self::TriggerConfigRequest,
+        _workspace_id: &str,
+    ) -> Result<()> {
+        if config.mysql_resource_path.trim().is_empty() {
+            return Err(Error::BadRequest(
+                "MySQL resource path cannot be empty".to_string(),
+            ));
+        }
+
+        parse_tables_to_track(&config.tables_to_track).map_err(Error::BadRequest)?;
+
+        if config.transaction_to_track.is_empty() {
+            return Err(Error::BadRequest(
+                "At least one transaction type to track is required".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    async fn create_trigger(
+        &self,
+        _db: &DB,
+        tx: &mut PgConnection,
+        authed: &ApiAuthed,
+        w_id: &str,
+        trigger: TriggerData<Self::TriggerConfigRequest>,
+    ) -> Result<()> {
+        let resolved_edited_by = trigger.base.resolve_edited_by(authed);
+        let resolved_permissioned_as = trigger.base.resolve_permissioned_as(authed);
+
+        sqlx::query!(
+            r#"
+            INSERT INTO mysql_trigger (
+                mysql_resource_path,
+                tables_to_track,
+                transaction_to_track,
+                workspace_id,
+                path,
+                script_path,
+                is_flow,
+                permissioned_as,
+                mode,
+                edited_by,
+                error_handler_path,
+                error_handler_args,
+                retry
+            )
+            VALUES (
+                $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13
+            )"#,
+            trigger.config.mysql_resource_path,
+            &trigger.config.tables_to_track,
+            &trigger.config.transaction_to_track,
+            w_id,
+            trigger.base.path,
+            trigger.base.script_path,
+            trigger.base.is_flow,
+            resolved_permissioned_as,
+            trigger.base.mode() as _,
+            &resolved_edited_by,
+            trigger.error_handling.error_handler_path,
+            trigger.error_handling.error_handler_args as _,
+            trigger.error_handling.retry as _
+        )
+        .execute(tx)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn update_trigger(
+        &self,
+        _db: &DB,
+        tx: &mut PgConnection,
+        authed: &ApiAuthed,
+        workspace_id: &str,
+        path: &str,
+        trigger: TriggerData<Self::TriggerConfigRequest>,
+    ) -> Result<()> {
+        let resolved_edited_by = trigger.base.resolve_edited_by(authed);
+        let resolved_permissioned_as = trigger.base.resolve_permissioned_as(authed);
+
+        // Important to set server_id to NULL to stop the current listener. The binlog
+        // position only makes sense on the server it was read from.
+        sqlx::query!(
+            r#"
+            UPDATE
+                mysql_trigger
+            SET
+                binlog_file = CASE WHEN mysql_resource_path = $1 THEN binlog_file END,
+                binlog_position = CASE WHEN mysql_resource_path = $1 THEN binlog_position END,
+                last_gtid = CASE WHEN mysql_resource_path = $1 THEN last_gtid END,
+                mysql_resource_path = $1,
+                tables_to_track = $2,
+                transaction_to_track = $3,
+                is_flow = $4,
+                edited_by = $5,
+                permissioned_as = $6,
+                script_path = $7,
+                path = $8,
+                edited_at = now(),
+                error = NULL,
+                server_id = NULL,
+                error_handler_path = $11,
+                error_handler_args = $12,
+                retry = $13
+            WHERE
+                workspace_id = $9 AND
+                path = $10
+            "#,
+            trigger.config.mysql_resource_path,
+            &trigger.config.tables_to_track,
+            &trigger.config.transaction_to_track,
+            trigger.base.is_flow,
+            &resolved_edited_by,
+            resolved_permissioned_as,
+            trigger.base.script_path,
+            trigger.base.path,
+            workspace_id,
+            path,
+            trigger.error_handling.error_handler_path,
+            trigger.error_handling.error_handler_args as _,
+            trigger.error_handling.retry as _
+        )
+        .execute(tx)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn test_connection(
+        &self,
+        db: &DB,
+        authed: &ApiAuthed,
+        user_db: &UserDB,
+        workspace_id: &str,
+        config: Self::TestConnectionConfig,
+    ) -> Result<()> {
+        let database = resolve_mysql_resource(
+            authed,
+            Some(user_db.clone()),
+            db,
+            &config.mysql_resource_path,
+            workspace_id,
+        )
+        .await?;
+
+        let mut conn = get_raw_mysql_connection(&database)
+            .await
+            .map_err(|err| Error::BadConfig(format!("Error connecting to MySQL: {}", err)))?;
+
+        let result = check_binlog_settings(&mut conn).await;
+        let _ = conn.disconnect().await;
+
+        result
+    }
+}
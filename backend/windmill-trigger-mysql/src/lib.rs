@@ -0,0 +1,394 @@
+use std::collections::HashMap;
+
+use itertools::Itertools;
+use mysql_async::{prelude::Queryable, Conn, Opts, OptsBuilder, Row, SslOpts};
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::value::RawValue;
+use sqlx::FromRow;
+use windmill_api_auth::ApiAuthed;
+use windmill_common::{
+    db::UserDB,
+    error::{to_anyhow, Error, Result},
+    triggers::TriggerKind,
+    DB,
+};
+use windmill_store::resources::try_get_resource_from_db_as;
+use windmill_trigger::trigger_helpers::TriggerJobArgs;
+
+mod converter;
+pub mod handler;
+pub mod listener;
+mod relation;
+
+#[derive(Clone, Copy)]
+pub struct MysqlTrigger;
+
+impl TriggerJobArgs for MysqlTrigger {
+    type Payload = HashMap<String, Box<RawValue>>;
+    const TRIGGER_KIND: TriggerKind = TriggerKind::Mysql;
+    fn v1_payload_fn(payload: &HashMap<String, Box<RawValue>>) -> HashMap<String, Box<RawValue>> {
+        payload.to_owned()
+    }
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct MysqlConfig {
+    pub mysql_resource_path: String,
+    pub tables_to_track: Vec<String>,
+    pub transaction_to_track: Vec<String>,
+    /// Binlog coordinates right after the last transaction that was fully handled, where
+    /// the listener resumes. Unset until the trigger first listens, in which case it starts
+    /// from the current end of the binlog.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub binlog_file: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub binlog_position: Option<i64>,
+    /// GTID of that transaction, when the server has GTIDs enabled.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_gtid: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MysqlConfigRequest {
+    pub mysql_resource_path: String,
+    pub tables_to_track: Vec<String>,
+    #[serde(deserialize_with = "check_if_valid_transaction_type")]
+    pub transaction_to_track: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestMysqlConfig {
+    pub mysql_resource_path: String,
+}
+
+#[derive(Deserialize)]
+pub struct Mysql {
+    pub host: String,
+    pub user: Option<String>,
+    pub password: Option<String>,
+    pub port: Option<u16>,
+    pub database: String,
+    pub ssl: Option<bool>,
+}
+
+fn check_if_valid_transaction_type<'de, D>(
+    transaction_type: D,
+) -> std::result::Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let transaction_type: Vec<String> = Vec::deserialize(transaction_type)?;
+    let mut transaction_type = transaction_type
+        .into_iter()
+        .map(|transaction| transaction.to_lowercase())
+        .collect_vec();
+    transaction_type.sort_unstable();
+    transaction_type.dedup();
+
+    for transaction in transaction_type.iter() {
+        match transaction.as_str() {
+            "insert" | "update" | "delete" => {}
+            _ => {
+                return Err(serde::de::Error::custom(
+                    "Only the following transaction types are allowed: Insert, Update and Delete (case insensitive)"
+                        .to_string(),
+                ))
+            }
+        }
+    }
+
+    Ok(transaction_type)
+}
+
+/// One entry of `tables_to_track`: `database.table`, or `database.*` for every table of
+/// the database. Names are compared as given, like MySQL does on case-sensitive file
+/// systems.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TableFilter {
+    pub database_name: String,
+    pub table_name: Option<String>,
+}
+
+impl TableFilter {
+    pub fn matches(&self, database_name: &str, table_name: &str) -> bool {
+        self.database_name == database_name
+            && self
+                .table_name
+                .as_ref()
+                .map_or(true, |tracked| tracked == table_name)
+    }
+}
+
+pub fn parse_tables_to_track(
+    tables_to_track: &[String],
+) -> std::result::Result<Vec<TableFilter>, String> {
+    if tables_to_track.is_empty() {
+        return Err("At least one table to track is required".to_string());
+    }
+
+    let filters = tables_to_track
+        .iter()
+        .map(|table| {
+            let (database_name, table_name) = table
+                .split_once('.')
+                .map(|(database_name, table_name)| (database_name.trim(), table_name.trim()))
+                .filter(|(database_name, table_name)| {
+                    !database_name.is_empty() && !table_name.is_empty()
+                })
+                .ok_or_else(|| {
+                    format!(
+                        "Invalid table to track: {}, expected `database.table` or `database.*`",
+                        table
+                    )
+                })?;
+            Ok(TableFilter {
+                database_name: database_name.to_string(),
+                table_name: (table_name != "*").then(|| table_name.to_string()),
+            })
+        })
+        .collect::<std::result::Result<Vec<_>, String>>()?;
+
+    if !filters.iter().all_unique() {
+        return Err("A table cannot be tracked more than once".to_string());
+    }
+
+    Ok(filters)
+}
+
+/// Where to read the binlog from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BinlogPosition {
+    pub file: String,
+    pub position: u64,
+    pub gtid: Option<String>,
+}
+
+impl BinlogPosition {
+    pub fn from_config(config: &MysqlConfig) -> Option<BinlogPosition> {
+        match (&config.binlog_file, config.binlog_position) {
+            (Some(file), Some(position)) => Some(BinlogPosition {
+                file: file.clone(),
+                position: position as u64,
+                gtid: config.last_gtid.clone(),
+            }),
+            _ => None,
+        }
+    }
+}
+
+pub fn mysql_opts(database: &Mysql) -> Opts {
+    let opts = OptsBuilder::default()
+        .db_name(Some(database.database.clone()))
+        .user(database.user.clone())
+        .pass(database.password.clone())
+        .ip_or_hostname(database.host.clone())
+        .tcp_port(database.port.unwrap_or(3306));
+
+    let opts = if database.ssl.unwrap_or(false) {
+        opts.ssl_opts(
+            SslOpts::default()
+                .with_danger_skip_domain_validation(true)
+                .with_danger_accept_invalid_certs(true),
+        )
+    } else {
+        opts
+    };
+
+    opts.into()
+}
+
+pub async fn get_raw_mysql_connection(database: &Mysql) -> Result<Conn> {
+    Conn::new(mysql_opts(database)).await.map_err(to_anyhow)
+}
+
+pub async fn resolve_mysql_resource(
+    authed: &ApiAuthed,
+    user_db: Option<UserDB>,
+    db: &DB,
+    mysql_resource_path: &str,
+    w_id: &str,
+) -> Result<Mysql> {
+    try_get_resource_from_db_as::<Mysql>(authed, user_db, db, mysql_resource_path, w_id).await
+}
+
+/// Row-based change data capture needs the binlog on, in `ROW` format, with full row
+/// images (otherwise updates and deletes only carry the columns that identify the row).
+pub async fn check_binlog_settings(conn: &mut Conn) -> Result<()> {
+    let settings: Option<(i64, String, String)> = conn
+        .query_first("SELECT @@log_bin, @@binlog_format, @@binlog_row_image")
+        .await
+        .map_err(to_anyhow)?;
+
+    let Some((log_bin, binlog_format, binlog_row_image)) = settings else {
+        return Err(Error::BadConfig(
+            "Could not read the binlog settings of the MySQL server".to_string(),
+        ));
+    };
+
+    if log_bin != 1 {
+        return Err(Error::BadConfig(
+            "Binary logging is disabled on the MySQL server, enable it with `log_bin`".to_string(),
+        ));
+    }
+    if !binlog_format.eq_ignore_ascii_case("ROW") {
+        return Err(Error::BadConfig(format!(
+            "The MySQL server uses binlog_format={}, MySQL triggers require binlog_format=ROW",
+            binlog_format
+        )));
+    }
+    if !binlog_row_image.eq_ignore_ascii_case("FULL") {
+        return Err(Error::BadConfig(format!(
+            "The MySQL server uses binlog_row_image={}, MySQL triggers require binlog_row_image=FULL",
+            binlog_row_image
+        )));
+    }
+
+    Ok(())
+}
+
+/// The current end of the binlog, i.e. where to start when there is nothing to resume.
+pub async fn current_binlog_position(conn: &mut Conn) -> Result<BinlogPosition> {
+    // `SHOW MASTER STATUS` was renamed in MySQL 8.4 (and dropped in 9), MariaDB only knows
+    // the old name.
+    let row: Option<Row> = match conn.query_first("SHOW BINARY LOG STATUS").await {
+        Ok(row) => row,
+        Err(_) => conn
+            .query_first("SHOW MASTER STATUS")
+            .await
+            .map_err(to_anyhow)?,
+    };
+
+    let row = row.ok_or_else(|| {
+        Error::BadConfig("Binary logging is disabled on the MySQL server".to_string())
+    })?;
+
+    let file = row
+        .get_opt::<String, _>("File")
+        .and_then(|file| file.ok())
+        .ok_or_else(|| Error::InternalErr("Missing binlog file in binlog status".to_string()))?;
+    let position = row
+        .get_opt::<u64, _>("Position")
+        .and_then(|position| position.ok())
+        .ok_or_else(|| {
+            Error::InternalErr("Missing binlog position in binlog status".to_string())
+        })?;
+
+    Ok(BinlogPosition { file, position, gtid: None })
+}
+
+pub async fn binlog_file_exists(conn: &mut Conn, file: &str) -> Result<bool> {
+    let files: Vec<Row> = conn.query("SHOW BINARY LOGS").await.map_err(to_anyhow)?;
+
+    Ok(files.into_iter().any(|row| {
+        row.get_opt::<String, _>("Log_name")
+            .and_then(|name| name.ok())
+            .is_some_and(|name| name == file)
+    }))
+}
+
+/// Bytes of binlog written past `position`: the rest of its file and every later one. `None`
+/// when its file is no longer listed.
+pub async fn binlog_lag(conn: &mut Conn, position: &BinlogPosition) -> Result<Option<i64>> {
+    let files: Vec<Row> = conn.query("SHOW BINARY LOGS").await.map_err(to_anyhow)?;
+    let files = files
+        .into_iter()
+        .filter_map(|row| {
+            let name = row.get_opt::<String, _>("Log_name")?.ok()?;
+            let size = row.get_opt::<u64, _>("File_size")?.ok()?;
+            Some((name, size))
+        })
+        .collect::<Vec<_>>();
+
+    Ok(lag_from_binlog_files(&files, position))
+}
+
+fn lag_from_binlog_files(files: &[(String, u64)], position: &BinlogPosition) -> Option<i64> {
+    let current = files.iter().position(|(name, _)| *name == position.file)?;
+    let lag = files[current].1.saturating_sub(position.position)
+        + files[current + 1..]
+            .iter()
+            .map(|(_, size)| size)
+            .sum::<u64>();
+    Some(lag as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tables(tables: &[&str]) -> Vec<String> {
+        tables.iter().map(|t| t.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_tables_to_track() {
+        let filters = parse_tables_to_track(&tables(&["shop.orders", "crm.*"])).unwrap();
+        assert_eq!(
+            filters,
+            vec![
+                TableFilter {
+                    database_name: "shop".to_string(),
+                    table_name: Some("orders".to_string())
+                },
+                TableFilter { database_name: "crm".to_string(), table_name: None },
+            ]
+        );
+        assert!(filters[0].matches("shop", "orders"));
+        assert!(!filters[0].matches("shop", "customers"));
+        assert!(!filters[0].matches("Shop", "orders"));
+        assert!(filters[1].matches("crm", "contacts"));
+    }
+
+    #[test]
+    fn test_parse_tables_to_track_rejects_invalid_entries() {
+        assert!(parse_tables_to_track(&[]).is_err());
+        assert!(parse_tables_to_track(&tables(&["orders"])).is_err());
+        assert!(parse_tables_to_track(&tables(&[".orders"])).is_err());
+        assert!(parse_tables_to_track(&tables(&["shop."])).is_err());
+        assert!(parse_tables_to_track(&tables(&["shop.orders", "shop.orders"])).is_err());
+    }
+
+    #[test]
+    fn test_transaction_type_is_normalized() {
+        let request: MysqlConfigRequest = serde_json::from_value(serde_json::json!({
+            "mysql_resource_path": "u/admin/mysql",
+            "tables_to_track": ["shop.orders"],
+            "transaction_to_track": ["Update", "insert", "INSERT"]
+        }))
+        .unwrap();
+        assert_eq!(request.transaction_to_track, vec!["insert", "update"]);
+
+        assert!(
+            serde_json::from_value::<MysqlConfigRequest>(serde_json::json!({
+                "mysql_resource_path": "u/admin/mysql",
+                "tables_to_track": ["shop.orders"],
+                "transaction_to_track": ["truncate"]
+            }))
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_lag_from_binlog_files() {
+        let files = vec![
+            ("binlog.000001".to_string(), 1000),
+            ("binlog.000002".to_string(), 500),
+            ("binlog.000003".to_string(), 200),
+        ];
+        let at =
+            |file: &str, position| BinlogPosition { file: file.to_string(), position, gtid: None };
+        assert_eq!(
+            lag_from_binlog_files(&files, &at("binlog.000003", 200)),
+            Some(0)
+        );
+        assert_eq!(
+            lag_from_binlog_files(&files, &at("binlog.000003", 150)),
+            Some(50)
+        );
+        assert_eq!(
+            lag_from_binlog_files(&files, &at("binlog.000002", 100)),
+            Some(600)
+        );
+        assert_eq!(lag_from_binlog_files(&files, &at("binlog.000000", 4)), None);
+    }
+}
@@ -0,0 +1,536 @@
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use futures::StreamExt;
+use mysql_async::{
+    binlog::events::EventData, prelude::Queryable, BinlogStream, BinlogStreamRequest, Conn,
+};
+use rand::Rng;
+use serde_json::value::RawValue;
+use tokio::sync::RwLock;
+use uuid::Uuid;
+use windmill_api_auth::ApiAuthed;
+use windmill_common::{
+    db::UserDB,
+    error::{to_anyhow, Error, Result},
+    jobs::JobTriggerKind,
+    utils::{report_critical_error, report_recovered_critical_error},
+    worker::to_raw_value,
+    DB,
+};
+
+use windmill_trigger::{
+    listener::ListeningTrigger, metrics::ListenerEvent, trigger_helpers::TriggerJobArgs, Listener,
+};
+
+use super::{
+    binlog_file_exists, binlog_lag, check_binlog_settings, current_binlog_position,
+    get_raw_mysql_connection, parse_tables_to_track,
+    relation::{fetch_table_relation, RelationConverter},
+    resolve_mysql_resource, BinlogPosition, MysqlConfig, MysqlTrigger, TableFilter,
+};
+
+const ERROR_BINLOG_FILE_PURGED: &str = r#"The binlog file this trigger was reading is no longer on the MySQL server (it was purged before the changes it holds were processed). Update the trigger to start again from the current end of the binlog"#;
+
+// Wait this long between reconnection attempts after a connection failure or a
+// dropped binlog stream. Matches the Postgres trigger listener's backoff.
+const RECONNECT_DELAY_SECS: u64 = 30;
+
+// The server sends a heartbeat whenever the binlog has been idle this long, so a stream
+// silent for a few periods is a dead connection.
+const HEARTBEAT_PERIOD_SECS: u64 = 30;
+
+// Progress within transactions that hold no tracked rows is saved at most this often.
+const POSITION_SAVE_INTERVAL_SECS: u64 = 10;
+
+// How far behind the end of the binlog the trigger is, measured at most this often.
+const LAG_INTERVAL_SECS: u64 = 15;
+
+/// Opens a binlog stream at `position`, or at the current end of the binlog when there
+/// is none, along with a second connection to look up the definition of tracked tables.
+///
+/// Returns `Error::BadConfig` when the server is not set up for row-based replication
+/// or the binlog file to resume from was purged: retrying cannot fix either. Any other
+/// error is treated as transient and retried by the caller.
+async fn connect_binlog_stream(
+    authed: &ApiAuthed,
+    db: &DB,
+    listening_trigger: &ListeningTrigger<MysqlConfig>,
+    position: Option<BinlogPosition>,
+) -> Result<(BinlogStream, Conn, BinlogPosition)> {
+    let ListeningTrigger { workspace_id, trigger_config, .. } = listening_trigger;
+
+    let database = resolve_mysql_resource(
+        authed,
+        Some(UserDB::new(db.clone())),
+        db,
+        &trigger_config.mysql_resource_path,
+        workspace_id,
+    )
+    .await?;
+
+    let mut metadata_conn = get_raw_mysql_connection(&database).await?;
+    check_binlog_settings(&mut metadata_conn).await?;
+
+    let position = match position {
+        Some(position) => {
+            if !binlog_file_exists(&mut metadata_conn, &position.file).await? {
+                return Err(Error::BadConfig(ERROR_BINLOG_FILE_PURGED.to_string()));
+            }
+            position
+        }
+        None => current_binlog_position(&mut metadata_conn).await?,
+    };
+
+    let mut stream_conn = get_raw_mysql_connection(&database).await?;
+    // Read by the server as `source_heartbeat_period` (`master_` before MySQL 8.4), in
+    // nanoseconds.
+    stream_conn
+        .query_drop(format!(
+            "SET @master_heartbeat_period = {0}, @source_heartbeat_period = {0}",
+            HEARTBEAT_PERIOD_SECS * 1_000_000_000
+        ))
+        .await
+        .map_err(to_anyhow)?;
+
+    // Every replica needs its own server id: pick one unlikely to clash with real
+    // replicas (or with another trigger reading the same server).
+    let replica_server_id = rand::rng().random_range(0x4000_0000..u32::MAX);
+    let stream = stream_conn
+        .get_binlog_stream(
+            BinlogStreamRequest::new(replica_server_id)
+                .with_filename(position.file.as_bytes())
+                .with_pos(position.position),
+        )
+        .await
+        .map_err(to_anyhow)?;
+
+    Ok((stream, metadata_conn, position))
+}
+
+async fn save_binlog_position(
+    db: &DB,
+    listening_trigger: &ListeningTrigger<MysqlConfig>,
+    position: &BinlogPosition,
+) -> Result<()> {
+    // Scoped to the resource the position was read from: if the trigger was just
+    // repointed, its reset position must not be overwritten.
+    sqlx::query!(
+        r#"
+        UPDATE
+            mysql_trigger
+        SET
+            binlog_file = $1,
+            binlog_position = $2,
+            last_gtid = $3
+        WHERE
+            workspace_id = $4 AND
+            path = $5 AND
+            mysql_resource_path = $6
+        "#,
+        position.file,
+        position.position as i64,
+        position.gtid,
+        listening_trigger.workspace_id,
+        listening_trigger.path,
+        listening_trigger.trigger_config.mysql_resource_path
+    )
+    .execute(db)
+    .await?;
+
+    Ok(())
+}
+
+fn is_tracked(tables: &[TableFilter], database_name: &str, table_name: &str) -> bool {
+    tables
+        .iter()
+        .any(|table| table.matches(database_name, table_name))
+}
+
+impl MysqlTrigger {
+    /// Reports a failed (re)connection, then waits before the next attempt. `None` when
+    /// the listener should stop.
+    async fn report_connection_failure(
+        &self,
+        db: &DB,
+        listening_trigger: &ListeningTrigger<MysqlConfig>,
+        err_message: Arc<RwLock<Option<String>>>,
+        tries: usize,
+        error: String,
+    ) -> Option<()> {
+        self.update_ping_and_loop_ping_status(
+            db,
+            listening_trigger,
+            err_message,
+            Some(format!(
+                "{} (attempt {}), retrying in {} seconds",
+                error,
+                tries + 1,
+                RECONNECT_DELAY_SECS
+            )),
+        )
+        .await?;
+
+        tracing::error!(
+            "MySQL trigger {}: {} (attempt {}), retrying in {} seconds",
+            &listening_trigger.path,
+            error,
+            tries + 1,
+            RECONNECT_DELAY_SECS
+        );
+
+        if tries % 10 == 0 && listening_trigger.trigger_mode {
+            report_critical_error(
+                format!(
+                    "MySQL trigger {}: {} (attempt {}), retrying in {} seconds. This alert will repeat every 10 failed attempts.",
+                    &listening_trigger.path,
+                    error,
+                    tries + 1,
+                    RECONNECT_DELAY_SECS
+                ),
+                db.clone(),
+                Some(&listening_trigger.workspace_id),
+                Some(&format!("mysql_trigger:{}", &listening_trigger.path)),
+            )
+            .await;
+        }
+
+        tokio::time::sleep(Duration::from_secs(RECONNECT_DELAY_SECS)).await;
+        Some(())
+    }
+}
+
+#[async_trait::async_trait]
+impl Listener for MysqlTrigger {
+    type Consumer = ApiAuthed;
+    type Extra = ();
+    type ExtraState = ();
+    const JOB_TRIGGER_KIND: JobTriggerKind = JobTriggerKind::Mysql;
+
+    async fn get_consumer(
+        &self,
+        db: &DB,
+        listening_trigger: &ListeningTrigger<Self::TriggerConfig>,
+        _err_message: Arc<RwLock<Option<String>>>,
+        _killpill_rx: tokio::sync::broadcast::Receiver<()>,
+    ) -> Result<Option<Self::Consumer>> {
+        // The binlog connection is established (and retried) inside `consume`. Here we
+        // only resolve the auth context that connection needs.
+        let authed = listening_trigger
+            .authed(db, &Self::TRIGGER_KIND.to_string())
+            .await?;
+
+        Ok(Some(authed))
+    }
+
+    /// Changes are read from the position saved after the last handled transaction, so a
+    /// transaction interrupted by a restart is read again: delivery is at least once.
+    async fn consume(
+        &self,
+        db: &DB,
+        consumer: Self::Consumer,
+        listening_trigger: &ListeningTrigger<Self::TriggerConfig>,
+        err_message: Arc<RwLock<Option<String>>>,
+        _killpill_rx: tokio::sync::broadcast::Receiver<()>,
+        _extra_state: Option<&Self::ExtraState>,
+    ) {
+        let authed = consumer;
+        let MysqlConfig { tables_to_track, transaction_to_track, .. } =
+            &listening_trigger.trigger_config;
+        let tables_to_track = match parse_tables_to_track(tables_to_track) {
+            Ok(tables_to_track) => tables_to_track,
+            Err(err) => {
+                self.disable_with_error(db, listening_trigger, err).await;
+                return;
+            }
+        };
+        let transaction_is_tracked =
+            |transaction_type: &str| transaction_to_track.iter().any(|t| t == transaction_type);
+
+        // Consecutive failed connection attempts. Reset to 0 once the stream delivers.
+        let mut tries = 0_usize;
+        let mut position = BinlogPosition::from_config(&listening_trigger.trigger_config);
+
+        loop {
+            let (mut stream, mut metadata_conn, start) =
+                match connect_binlog_stream(&authed, db, listening_trigger, position.clone()).await
+                {
+                    Ok(connection) => connection,
+                    Err(Error::BadConfig(err)) => {
+                        self.disable_with_error(db, listening_trigger, err).await;
+                        return;
+                    }
+                    Err(err) => {
+                        if self
+                            .report_connection_failure(
+                                db,
+                                listening_trigger,
+                                err_message.clone(),
+                                tries,
+                                format!("Failed to connect: {}", err),
+                            )
+                            .await
+                            .is_none()
+                        {
+                            return;
+                        }
+                        tries += 1;
+                        continue;
+                    }
+                };
+
+            tracing::info!(
+                "Starting to listen for MySQL trigger {} at {}:{}",
+                &listening_trigger.path,
+                &start.file,
+                start.position
+            );
+
+            let mut relations = RelationConverter::new();
+            let mut current_file = start.file.clone();
+            let mut last_gtid = start.gtid.clone();
+            position = Some(start);
+            let mut emitted_since_save = false;
+            let mut last_save = Instant::now();
+            let mut last_lag: Option<Instant> = None;
+
+            let failure = loop {
+                let event = match tokio::time::timeout(
+                    Duration::from_secs(HEARTBEAT_PERIOD_SECS * 3),
+                    stream.next(),
+                )
+                .await
+                {
+                    Ok(Some(Ok(event))) => event,
+                    Ok(Some(Err(err))) => break format!("Error reading the binlog: {}", err),
+                    Ok(None) => break "Binlog stream closed".to_string(),
+                    Err(_) => break "Binlog stream timed out".to_string(),
+                };
+
+                // First event after a (re)connection: the stream is making progress.
+                if tries > 0 {
+                    if self
+                        .update_ping_and_loop_ping_status(
+                            db,
+                            listening_trigger,
+                            err_message.clone(),
+                            None,
+                        )
+                        .await
+                        .is_none()
+                    {
+                        return;
+                    }
+                    if listening_trigger.trigger_mode {
+                        report_recovered_critical_error(
+                            format!("MySQL trigger {} reconnected", &listening_trigger.path),
+                            db.clone(),
+                            Some(&listening_trigger.workspace_id),
+                            Some(&format!("mysql_trigger:{}", &listening_trigger.path)),
+                        )
+                        .await;
+                    }
+                    tries = 0;
+                }
+
+                let log_pos = event.header().log_pos() as u64;
+                let data = match event.read_data() {
+                    Ok(Some(data)) => data,
+                    Ok(None) => continue,
+                    Err(err) => break format!("Error decoding a binlog event: {}", err),
+                };
+
+                let mut events: Vec<HashMap<String, Box<RawValue>>> = vec![];
+                let mut committed = false;
+                match data {
+                    EventData::RotateEvent(rotate) => {
+                        current_file = rotate.name().to_string();
+                    }
+                    EventData::GtidEvent(gtid) => {
+                        last_gtid =
+                            Some(format!("{}:{}", Uuid::from_bytes(gtid.sid()), gtid.gno()));
+                    }
+                    EventData::TableMapEvent(table_map) => {
+                        let table_id = table_map.table_id();
+                        let database_name = table_map.database_name();
+                        let table_name = table_map.table_name();
+                        if !relations.contains(table_id)
+                            && is_tracked(&tables_to_track, &database_name, &table_name)
+                        {
+                            match fetch_table_relation(
+                                &mut metadata_conn,
+                                &database_name,
+                                &table_name,
+                            )
+                            .await
+                            {
+                                Ok(relation) => relations.add_relation(table_id, relation),
+                                Err(err) => {
+                                    break format!(
+                                        "Failed to look up the definition of {}.{}: {}",
+                                        database_name, table_name, err
+                                    )
+                                }
+                            }
+                        }
+                    }
+                    EventData::RowsEvent(rows_event) => {
+                        let table_id = rows_event.table_id();
+                        // Only tracked tables are in `relations`.
+                        let Ok(relation) = relations.get_relation(table_id) else {
+                            continue;
+                        };
+                        let Some(table_map) = stream.get_tme(table_id) else {
+                            continue;
+                        };
+
+                        for row in rows_event.rows(table_map) {
+                            let (before, after) = match row {
+                                Ok(row) => row,
+                                Err(err) => {
+                                    tracing::error!(
+                                        "MySQL trigger {} failed to decode a row of {}.{}: {}",
+                                        &listening_trigger.path,
+                                        relation.database_name,
+                                        relation.table_name,
+                                        err
+                                    );
+                                    continue;
+                                }
+                            };
+                            let (transaction_type, old_row, row) = match (before, after) {
+                                (None, Some(after)) => ("insert", None, after),
+                                (Some(before), Some(after)) => ("update", Some(before), after),
+                                (Some(before), None) => ("delete", None, before),
+                                (None, None) => continue,
+                            };
+                            // Rows of tables the trigger does not track are not counted: the
+                            // binlog holds the changes of the whole server.
+                            if !transaction_is_tracked(transaction_type) {
+                                self.record_event(listening_trigger, ListenerEvent::Filtered);
+                                continue;
+                            }
+
+                            let old_row = old_row
+                                .map(|old_row| relations.row_to_json((table_id, old_row.unwrap())))
+                                .transpose();
+                            let row = relations.row_to_json((table_id, row.unwrap()));
+                            match (old_row, row) {
+                                (Ok(old_row), Ok(row)) => events.push(HashMap::from([
+                                    (
+                                        "database_name".to_string(),
+                                        to_raw_value(&relation.database_name),
+                                    ),
+                                    ("table_name".to_string(), to_raw_value(&relation.table_name)),
+                                    (
+                                        "transaction_type".to_string(),
+                                        to_raw_value(&transaction_type),
+                                    ),
+                                    ("old_row".to_string(), to_raw_value(&old_row)),
+                                    ("row".to_string(), to_raw_value(&row)),
+                                ])),
+                                (old_row, row) => {
+                                    for err in [old_row.err(), row.err()].into_iter().flatten() {
+                                        tracing::error!(
+                                            transaction_type = ?transaction_type,
+                                            database = %relation.database_name,
+                                            table = %relation.table_name,
+                                            error = %err,
+                                            "Failed to decode row for {} transaction on {}.{}",
+                                            transaction_type,
+                                            relation.database_name,
+                                            relation.table_name,
+                                        );
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    EventData::XidEvent(_) => committed = true,
+                    // Statements outside of row events: `BEGIN` opens a transaction, anything
+                    // else (DDL, `COMMIT` of non-transactional tables) ends one.
+                    EventData::QueryEvent(query) => {
+                        committed = !query.query().trim().eq_ignore_ascii_case("BEGIN");
+                    }
+                    _ => {}
+                }
+
+                let trigger_info = HashMap::from([
+                    ("binlog_file".to_string(), to_raw_value(&current_file)),
+                    ("binlog_position".to_string(), to_raw_value(&log_pos)),
+                    ("gtid".to_string(), to_raw_value(&last_gtid)),
+                ]);
+                for payload in events {
+                    emitted_since_save = true;
+                    let _ = self
+                        .handle_event(db, listening_trigger, payload, trigger_info.clone(), None)
+                        .await;
+                }
+
+                if committed && log_pos > 0 {
+                    let committed_position = BinlogPosition {
+                        file: current_file.clone(),
+                        position: log_pos,
+                        gtid: last_gtid.clone(),
+                    };
+                    if emitted_since_save
+                        || last_save.elapsed() >= Duration::from_secs(POSITION_SAVE_INTERVAL_SECS)
+                    {
+                        if let Err(err) =
+                            save_binlog_position(db, listening_trigger, &committed_position).await
+                        {
+                            tracing::warn!(
+                                "MySQL trigger {} failed to save its binlog position: {}",
+                                &listening_trigger.path,
+                                err
+                            );
+                        } else {
+                            emitted_since_save = false;
+                            last_save = Instant::now();
+                        }
+                    }
+                    position = Some(committed_position);
+                }
+
+                if listening_trigger.trigger_mode
+                    && last_lag.map_or(true, |t| t.elapsed().as_secs() >= LAG_INTERVAL_SECS)
+                {
+                    last_lag = Some(Instant::now());
+                    if let Some(position) = position.as_ref() {
+                        let lag = match binlog_lag(&mut metadata_conn, position).await {
+                            Ok(lag) => lag,
+                            Err(err) => {
+                                tracing::debug!(
+                                    "MySQL trigger {} could not measure its binlog lag: {}",
+                                    &listening_trigger.path,
+                                    err
+                                );
+                                None
+                            }
+                        };
+                        self.record_consumer_lag(listening_trigger, lag);
+                    }
+                }
+            };
+
+            let _ = metadata_conn.disconnect().await;
+            if self
+                .report_connection_failure(
+                    db,
+                    listening_trigger,
+                    err_message.clone(),
+                    tries,
+                    failure,
+                )
+                .await
+                .is_none()
+            {
+                return;
+            }
+            tries += 1;
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use std::collections::HashMap;
+
+use mysql_async::{binlog::value::BinlogValue, prelude::Queryable, Conn};
+use serde_json::{Map, Value};
+
+use super::converter::{ColumnInfo, Converter, ConverterError};
+
+#[derive(Debug, thiserror::Error)]
+pub enum RelationConversionError {
+    #[error("Could not find matching table")]
+    FailToFindMatchingTable,
+
+    #[error("Row has {row} columns but table {table} has {table_columns}, its definition probably changed since the row was written")]
+    ColumnCountMismatch { table: String, row: usize, table_columns: usize },
+
+    #[error("Partial JSON updates are not supported, set binlog_row_value_options to ''")]
+    JsonDiffNotSupported,
+
+    #[error("decode error: {0}")]
+    FromBytes(#[from] ConverterError),
+}
+
+#[derive(Debug, Clone)]
+pub struct TableRelation {
+    pub database_name: String,
+    pub table_name: String,
+    pub columns: Vec<ColumnInfo>,
+}
+
+/// Table definitions by binlog table id. The binlog announces each table it logs rows of
+/// with a table map event, whose id only lasts until the table definition is reloaded
+/// (e.g. by an `ALTER TABLE`): after that rows come with a new id, and the table is
+/// looked up again.
+pub struct RelationConverter(HashMap<u64, TableRelation>);
+
+impl RelationConverter {
+    pub fn new() -> Self {
+        Self(HashMap::new())
+    }
+
+    pub fn contains(&self, table_id: u64) -> bool {
+        self.0.contains_key(&table_id)
+    }
+
+    pub fn add_relation(&mut self, table_id: u64, relation: TableRelation) {
+        self.0.insert(table_id, relation);
+    }
+
+    pub fn get_relation(&self, table_id: u64) -> Result<&TableRelation, RelationConversionError> {
+        self.0
+            .get(&table_id)
+            .ok_or(RelationConversionError::FailToFindMatchingTable)
+    }
+
+    pub fn row_to_json(
+        &self,
+        to_decode: (u64, Vec<BinlogValue<'static>>),
+    ) -> Result<Map<String, Value>, RelationConversionError> {
+        let (table_id, row) = to_decode;
+        let relation = self.get_relation(table_id)?;
+
+        if row.len() != relation.columns.len() {
+            return Err(RelationConversionError::ColumnCountMismatch {
+                table: format!("{}.{}", relation.database_name, relation.table_name),
+                row: row.len(),
+                table_columns: relation.columns.len(),
+            });
+        }
+
+        let mut object: Map<String, Value> = Map::new();
+        for (column, value) in relation.columns.iter().zip(row) {
+            let value = match value {
+                BinlogValue::Value(value) => Converter::try_from_value(column, value)?,
+                BinlogValue::Jsonb(value) => Value::try_from(value)
+                    .map_err(|err| ConverterError::Custom(format!("invalid json: {}", err)))?,
+                BinlogValue::JsonDiff(_) => {
+                    return Err(RelationConversionError::JsonDiffNotSupported)
+                }
+            };
+
+            object.insert(column.name.clone(), value);
+        }
+        Ok(object)
+    }
+}
+
+pub async fn fetch_table_relation(
+    conn: &mut Conn,
+    database_name: &str,
+    table_name: &str,
+) -> mysql_async::Result<TableRelation> {
+    let columns = conn
+        .exec_map(
+            r#"
+            SELECT
+                COLUMN_NAME,
+                DATA_TYPE,
+                COLUMN_TYPE
+            FROM
+                information_schema.COLUMNS
+            WHERE
+                TABLE_SCHEMA = ? AND
+                TABLE_NAME = ?
+            ORDER BY
+                ORDINAL_POSITION
+            "#,
+            (database_name, table_name),
+            |(name, data_type, column_type): (String, String, String)| {
+                ColumnInfo::new(name, data_type, column_type)
+            },
+        )
+        .await?;
+
+    Ok(TableRelation {
+        database_name: database_name.to_string(),
+        table_name: table_name.to_string(),
+        columns,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mysql_async::Value as MysqlValue;
+    use serde_json::json;
+
+    fn relation(columns: &[(&str, &str, &str)]) -> TableRelation {
+        TableRelation {
+            database_name: "shop".to_string(),
+            table_name: "orders".to_string(),
+            columns: columns
+                .iter()
+                .map(|(name, data_type, column_type)| {
+                    ColumnInfo::new(
+                        name.to_string(),
+                        data_type.to_string(),
+                        column_type.to_string(),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_row_to_json() {
+        let mut converter = RelationConverter::new();
+        converter.add_relation(
+            1,
+            relation(&[
+                ("id", "int", "int unsigned"),
+                ("status", "enum", "enum('new','paid')"),
+                ("note", "text", "text"),
+            ]),
+        );
+
+        let row = vec![
+            BinlogValue::Value(MysqlValue::Int(42)),
+            BinlogValue::Value(MysqlValue::Int(2)),
+            BinlogValue::Value(MysqlValue::NULL),
+        ];
+
+        let result = converter.row_to_json((1, row)).unwrap();
+        assert_eq!(result["id"], json!(42));
+        assert_eq!(result["status"], json!("paid"));
+        assert_eq!(result["note"], Value::Null);
+    }
+
+    #[test]
+    fn test_missing_relation() {
+        let converter = RelationConverter::new();
+        assert!(matches!(
+            converter.row_to_json((999, vec![BinlogValue::Value(MysqlValue::NULL)])),
+            Err(RelationConversionError::FailToFindMatchingTable)
+        ));
+    }
+
+    #[test]
+    fn test_column_count_mismatch() {
+        let mut converter = RelationConverter::new();
+        converter.add_relation(1, relation(&[("id", "int", "int")]));
+
+        let row = vec![
+            BinlogValue::Value(MysqlValue::Int(1)),
+            BinlogValue::Value(MysqlValue::Int(2)),
+        ];
+        assert!(matches!(
+            converter.row_to_json((1, row)),
+            Err(RelationConversionError::ColumnCountMismatch { row: 2, table_columns: 1, .. })
+        ));
+    }
+}
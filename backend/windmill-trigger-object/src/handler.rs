@@ -0,0 +1,236 @@
+use async_trait::async_trait;
+use futures::TryStreamExt;
+use sqlx::PgConnection;
+use windmill_api_auth::ApiAuthed;
+use windmill_common::DB;
+use windmill_common::{
+    db::UserDB,
+    error::{Error, Result},
+};
+use windmill_git_sync::DeployedObject;
+use windmill_object_store::LargeFileStorage;
+use windmill_trigger::{Trigger, TriggerCrud, TriggerData};
+
+use super::{
+    list_root, resolve_storage, validate_object_config, workspace_storage, ObjectConfig,
+    ObjectConfigRequest, ObjectTrigger, TestObjectConfig, DELIVERY_NOTIFICATIONS,
+};
+
+#[async_trait]
+impl TriggerCrud for ObjectTrigger {
+    type TriggerConfig = ObjectConfig;
+    type Trigger = Trigger<Self::TriggerConfig>;
+    type TriggerConfigRequest = ObjectConfigRequest;
+    type TestConnectionConfig = TestObjectConfig;
+
+    const TABLE_NAME: &'static str = "object_trigger";
+    const TRIGGER_TYPE: &'static str = "object";
+    const DRAFT_KIND: windmill_common::user_drafts::UserDraftItemKind =
+        windmill_common::user_drafts::UserDraftItemKind::TriggerObject;
+    const SUPPORTS_SERVER_STATE: bool = true;
+    const SUPPORTS_TEST_CONNECTION: bool = true;
+    const ROUTE_PREFIX: &'static str = "/object_triggers";
+    const DEPLOYMENT_NAME: &'static str = "Object trigger";
+    const ADDITIONAL_SELECT_FIELDS: &[&'static str] = &[
+        "storage",
+        "prefix",
+        "delivery",
+        "poll_interval_secs",
+        "watermark",
+    ];
+    const IS_ALLOWED_ON_CLOUD: bool = false;
+
+    fn get_deployed_object(path: String, parent_path: Option<String>) -> DeployedObject {
+        DeployedObject::ObjectTrigger { path, parent_path }
+    }
+
+    async fn validate_config(
+        &self,
+        db: &DB,
+        config: &Self::TriggerConfigRequest,
+        workspace_id: &str,
+    ) -> Result<()> {
+        validate_object_config(&config.prefix, &config.delivery, config.poll_interval_secs)
+            .map_err(Error::BadRequest)?;
+
+        match workspace_storage(db, workspace_id, config.storage.as_deref()).await? {
+            LargeFileStorage::S3AwsOidc(_) | LargeFileStorage::AzureWorkloadIdentity(_) => {
+                Err(Error::BadRequest(
+                    "Object triggers cannot watch a storage authenticated with workload identity"
+                        .to_string(),
+                ))
+            }
+            LargeFileStorage::S3Storage(_) => Ok(()),
+            _ if config.delivery == DELIVERY_NOTIFICATIONS => Err(Error::BadRequest(
+                "Bucket notifications are only available on S3-compatible storage, use the list delivery instead"
+                    .to_string(),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    async fn create_trigger(
+        &self,
+        _db: &DB,
+        tx: &mut PgConnection,
+        authed: &ApiAuthed,
+        w_id: &str,
+        trigger: TriggerData<Self::TriggerConfigRequest>,
+    ) -> Result<()> {
+        let resolved_edited_by = trigger.base.resolve_edited_by(authed);
+        let resolved_permissioned_as = trigger.base.resolve_permissioned_as(authed);
+
+        sqlx::query!(
+            r#"
+            INSERT INTO object_trigger (
+                storage,
+                prefix,
+                delivery,
+                poll_interval_secs,
+                workspace_id,
+                path,
+                script_path,
+                is_flow,
+                permissioned_as,
+                mode,
+                edited_by,
+                error_handler_path,
+                error_handler_args,
+                retry
+            )
+            VALUES (
+                $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14
+            )"#,
+            trigger.config.storage,
+            trigger.config.prefix,
+            trigger.config.delivery,
+            trigger.config.poll_interval_secs,
+            w_id,
+            trigger.base.path,
+            trigger.base.script_path,
+            trigger.base.is_flow,
+            resolved_permissioned_as,
+            trigger.base.mode() as _,
+            &resolved_edited_by,
+            trigger.error_handling.error_handler_path,
+            trigger.error_handling.error_handler_args as _,
+            trigger.error_handling.retry as _
+        )
+        .execute(tx)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn update_trigger(
+        &self,
+        _db: &DB,
+        tx: &mut PgConnection,
+        authed: &ApiAuthed,
+        workspace_id: &str,
+        path: &str,
+        trigger: TriggerData<Self::TriggerConfigRequest>,
+    ) -> Result<()> {
+        let resolved_edited_by = trigger.base.resolve_edited_by(authed);
+        let resolved_permissioned_as = trigger.base.resolve_permissioned_as(authed);
+
+        // Watching another storage or prefix starts over: what was seen of the previous one
+        // is forgotten and the objects already there are not considered new.
+        sqlx::query!(
+            r#"
+            DELETE FROM
+                object_trigger_seen
+            USING
+                object_trigger
+            WHERE
+                object_trigger.workspace_id = $1 AND
+                object_trigger.path = $2 AND
+                (object_trigger.storage IS DISTINCT FROM $3 OR object_trigger.prefix <> $4) AND
+                object_trigger_seen.workspace_id = object_trigger.workspace_id AND
+                object_trigger_seen.trigger_path = object_trigger.path
+            "#,
+            workspace_id,
+            path,
+            trigger.config.storage,
+            trigger.config.prefix
+        )
+        .execute(&mut *tx)
+        .await?;
+
+        // Important to set server_id to NULL to stop the current object listener
+        sqlx::query!(
+            r#"
+            UPDATE
+                object_trigger
+            SET
+                watermark = CASE
+                    WHEN storage IS NOT DISTINCT FROM $1 AND prefix = $2 THEN watermark
+                END,
+                storage = $1,
+                prefix = $2,
+                delivery = $3,
+                poll_interval_secs = $4,
+                is_flow = $5,
+                edited_by = $6,
+                permissioned_as = $7,
+                script_path = $8,
+                path = $9,
+                edited_at = now(),
+                error = NULL,
+                server_id = NULL,
+                error_handler_path = $12,
+                error_handler_args = $13,
+                retry = $14
+            WHERE
+                workspace_id = $10 AND
+                path = $11
+            "#,
+            trigger.config.storage,
+            trigger.config.prefix,
+            trigger.config.delivery,
+            trigger.config.poll_interval_secs,
+            trigger.base.is_flow,
+            &resolved_edited_by,
+            resolved_permissioned_as,
+            trigger.base.script_path,
+            trigger.base.path,
+            workspace_id,
+            path,
+            trigger.error_handling.error_handler_path,
+            trigger.error_handling.error_handler_args as _,
+            trigger.error_handling.retry as _
+        )
+        .execute(tx)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn test_connection(
+        &self,
+        db: &DB,
+        authed: &ApiAuthed,
+        user_db: &UserDB,
+        workspace_id: &str,
+        config: Self::TestConnectionConfig,
+    ) -> Result<()> {
+        let storage = resolve_storage(
+            db,
+            authed,
+            user_db.clone(),
+            workspace_id,
+            config.storage.as_deref(),
+        )
+        .await?;
+
+        // One entry is enough to know the storage can be listed.
+        storage
+            .store
+            .list(list_root(&config.prefix).as_ref())
+            .try_next()
+            .await
+            .map_err(|err| Error::BadConfig(format!("Error listing the storage: {}", err)))?;
+
+        Ok(())
+    }
+}
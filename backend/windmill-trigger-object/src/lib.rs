@@ -0,0 +1,373 @@
+use std::{collections::HashMap, sync::Arc};
+
+use chrono::{DateTime, Utc};
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+use sqlx::FromRow;
+use windmill_api_auth::ApiAuthed;
+use windmill_common::{
+    db::UserDB,
+    error::{Error, Result},
+    triggers::TriggerKind,
+    worker::to_raw_value,
+    DB,
+};
+use windmill_object_store::{
+    build_filesystem_client, build_object_store_client, lfs_to_object_store_resource,
+    object_store_error_to_error,
+    object_store_reexports::{ObjectMeta, ObjectStore, Path},
+    LargeFileStorage, ObjectStoreResource, S3Resource, DEFAULT_STORAGE,
+};
+use windmill_store::resources::try_get_resource_from_db_as;
+use windmill_trigger::trigger_helpers::TriggerJobArgs;
+
+pub mod handler;
+pub mod listener;
+mod notifications;
+mod sigv4;
+
+#[derive(Clone, Copy)]
+pub struct ObjectTrigger;
+
+/// A new or changed object under the watched prefix.
+#[derive(Debug, Clone, Serialize)]
+pub struct ObjectEvent {
+    pub key: String,
+    pub size: u64,
+    pub etag: Option<String>,
+    pub last_modified: DateTime<Utc>,
+    /// Name of the workspace storage the object is in, unset for the primary storage.
+    pub storage: Option<String>,
+}
+
+impl ObjectEvent {
+    fn from_meta(meta: ObjectMeta, storage: Option<&str>) -> Self {
+        ObjectEvent {
+            key: meta.location.to_string(),
+            size: meta.size,
+            etag: meta.e_tag.as_deref().map(normalize_etag),
+            last_modified: meta.last_modified,
+            storage: storage.map(str::to_string),
+        }
+    }
+
+    /// Identifies this version of the object: its etag, or its modification time and size
+    /// on stores that don't return one.
+    pub fn version(&self) -> String {
+        match &self.etag {
+            Some(etag) => etag.clone(),
+            None => format!("{}:{}", self.last_modified.timestamp_micros(), self.size),
+        }
+    }
+}
+
+/// Listings return the etag quoted, bucket notifications don't.
+fn normalize_etag(etag: &str) -> String {
+    etag.trim_matches('"').to_string()
+}
+
+impl TriggerJobArgs for ObjectTrigger {
+    type Payload = ObjectEvent;
+    const TRIGGER_KIND: TriggerKind = TriggerKind::Object;
+
+    fn v1_payload_fn(payload: &ObjectEvent) -> HashMap<String, Box<RawValue>> {
+        // `file` is the object as an S3 object argument, so it can be passed on to the
+        // storage helpers of the clients as is.
+        let mut file = serde_json::Map::new();
+        file.insert("s3".to_string(), serde_json::json!(payload.key));
+        if let Some(storage) = &payload.storage {
+            file.insert("storage".to_string(), serde_json::json!(storage));
+        }
+
+        HashMap::from([
+            ("key".to_string(), to_raw_value(&payload.key)),
+            ("size".to_string(), to_raw_value(&payload.size)),
+            ("etag".to_string(), to_raw_value(&payload.etag)),
+            (
+                "last_modified".to_string(),
+                to_raw_value(&payload.last_modified),
+            ),
+            ("file".to_string(), to_raw_value(&file)),
+        ])
+    }
+}
+
+pub const DELIVERY_LIST: &str = "list";
+pub const DELIVERY_NOTIFICATIONS: &str = "notifications";
+
+const DEFAULT_POLL_INTERVAL_SECS: i32 = 60;
+const MIN_POLL_INTERVAL_SECS: i32 = 10;
+const MAX_POLL_INTERVAL_SECS: i32 = 86_400;
+
+fn default_delivery() -> String {
+    DELIVERY_LIST.to_string()
+}
+
+fn default_poll_interval_secs() -> i32 {
+    DEFAULT_POLL_INTERVAL_SECS
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct ObjectConfig {
+    pub storage: Option<String>,
+    #[serde(default)]
+    pub prefix: String,
+    #[serde(default = "default_delivery")]
+    pub delivery: String,
+    #[serde(default = "default_poll_interval_secs")]
+    pub poll_interval_secs: i32,
+    /// Modification time of the newest object the trigger has seen, where the next listing
+    /// resumes. Unset until the trigger first lists the storage, in which case the objects
+    /// already there are not considered new.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub watermark: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObjectConfigRequest {
+    pub storage: Option<String>,
+    #[serde(default)]
+    pub prefix: String,
+    #[serde(default = "default_delivery")]
+    pub delivery: String,
+    #[serde(default = "default_poll_interval_secs")]
+    pub poll_interval_secs: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestObjectConfig {
+    pub storage: Option<String>,
+    #[serde(default)]
+    pub prefix: String,
+}
+
+/// Shared validation for the config, used by both the CRUD handler and the listener (which
+/// also covers capture configs, that bypass CRUD validation).
+pub fn validate_object_config(
+    prefix: &str,
+    delivery: &str,
+    poll_interval_secs: i32,
+) -> std::result::Result<(), String> {
+    if prefix.starts_with('/') {
+        return Err("Prefix must be relative to the root of the storage".to_string());
+    }
+    if prefix
+        .split('/')
+        .any(|segment| segment == "." || segment == "..")
+    {
+        return Err("Prefix cannot contain `.` or `..` segments".to_string());
+    }
+
+    match delivery {
+        DELIVERY_LIST | DELIVERY_NOTIFICATIONS => {}
+        other => {
+            return Err(format!(
+                "Invalid delivery {}: expected `{}` or `{}`",
+                other, DELIVERY_LIST, DELIVERY_NOTIFICATIONS
+            ))
+        }
+    }
+
+    if !(MIN_POLL_INTERVAL_SECS..=MAX_POLL_INTERVAL_SECS).contains(&poll_interval_secs) {
+        return Err(format!(
+            "Poll interval must be between {} and {} seconds",
+            MIN_POLL_INTERVAL_SECS, MAX_POLL_INTERVAL_SECS
+        ));
+    }
+
+    Ok(())
+}
+
+/// The storage a trigger watches, resolved from the workspace storage settings.
+pub struct WatchedStorage {
+    pub store: Arc<dyn ObjectStore>,
+    /// The resource of an S3-compatible storage, which bucket notifications are requested
+    /// with.
+    pub s3_resource: Option<S3Resource>,
+}
+
+async fn workspace_storage(
+    db: &DB,
+    workspace_id: &str,
+    storage: Option<&str>,
+) -> Result<LargeFileStorage> {
+    let lfs_json = sqlx::query_scalar!(
+        "SELECT large_file_storage FROM workspace_settings WHERE workspace_id = $1",
+        workspace_id
+    )
+    .fetch_optional(db)
+    .await?
+    .flatten()
+    .ok_or_else(|| Error::BadConfig("Workspace has no object storage configured".to_string()))?;
+
+    // Named storages live under `secondary_storage`; `None`/`_default_` is the primary.
+    let entry = match storage.filter(|s| *s != DEFAULT_STORAGE) {
+        None => lfs_json,
+        Some(name) => lfs_json
+            .get("secondary_storage")
+            .and_then(|s| s.get(name))
+            .cloned()
+            .ok_or_else(|| {
+                Error::BadConfig(format!("Workspace has no object storage named {}", name))
+            })?,
+    };
+
+    serde_json::from_value(entry)
+        .map_err(|e| Error::internal_err(format!("Error parsing workspace storage: {e}")))
+}
+
+pub async fn resolve_storage(
+    db: &DB,
+    authed: &ApiAuthed,
+    user_db: UserDB,
+    workspace_id: &str,
+    storage: Option<&str>,
+) -> Result<WatchedStorage> {
+    let lfs = workspace_storage(db, workspace_id, storage).await?;
+
+    match &lfs {
+        LargeFileStorage::FilesystemStorage(fs) => {
+            Ok(
+                WatchedStorage {
+                    store: build_filesystem_client(&fs.root_path)?,
+                    s3_resource: None,
+                },
+            )
+        }
+        LargeFileStorage::S3AwsOidc(_) | LargeFileStorage::AzureWorkloadIdentity(_) => {
+            Err(Error::BadConfig(
+                "Object triggers cannot watch a storage authenticated with workload identity"
+                    .to_string(),
+            ))
+        }
+        _ => {
+            let resource_path = lfs.get_s3_resource_path();
+            let resource_path = resource_path.strip_prefix("$res:").unwrap_or(resource_path);
+            let resource_value = try_get_resource_from_db_as::<serde_json::Value>(
+                authed,
+                Some(user_db),
+                db,
+                resource_path,
+                workspace_id,
+            )
+            .await?;
+
+            let resource = lfs_to_object_store_resource(&lfs, resource_value)?;
+            let s3_resource = match &resource {
+                ObjectStoreResource::S3(s3_resource) => Some(s3_resource.clone()),
+                _ => None,
+            };
+
+            Ok(WatchedStorage { store: build_object_store_client(&resource).await?, s3_resource })
+        }
+    }
+}
+
+/// The directory `prefix` lies in. Listing is done per directory, so a prefix that ends in
+/// the middle of a name is listed from its parent and filtered afterwards.
+fn list_root(prefix: &str) -> Option<Path> {
+    match prefix.rsplit_once('/') {
+        Some((dir, _)) if !dir.is_empty() => Some(Path::from(dir)),
+        _ => None,
+    }
+}
+
+/// Every object whose key starts with `prefix`.
+pub async fn list_objects(
+    store: &dyn ObjectStore,
+    prefix: &str,
+    storage: Option<&str>,
+) -> Result<Vec<ObjectEvent>> {
+    let root = list_root(prefix);
+    let objects: Vec<ObjectMeta> = store
+        .list(root.as_ref())
+        .try_filter(|meta| futures::future::ready(meta.location.as_ref().starts_with(prefix)))
+        .try_collect()
+        .await
+        .map_err(object_store_error_to_error)?;
+
+    Ok(objects
+        .into_iter()
+        .map(|meta| ObjectEvent::from_meta(meta, storage))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_object_config() {
+        assert!(validate_object_config("", DELIVERY_LIST, 60).is_ok());
+        assert!(validate_object_config("incoming/", DELIVERY_NOTIFICATIONS, 10).is_ok());
+        assert!(validate_object_config("/incoming", DELIVERY_LIST, 60).is_err());
+        assert!(validate_object_config("incoming/../other", DELIVERY_LIST, 60).is_err());
+        assert!(validate_object_config("incoming/", "webhook", 60).is_err());
+        assert!(validate_object_config("incoming/", DELIVERY_LIST, 1).is_err());
+    }
+
+    #[test]
+    fn test_list_root() {
+        assert_eq!(list_root(""), None);
+        assert_eq!(list_root("report_"), None);
+        assert_eq!(list_root("incoming/"), Some(Path::from("incoming")));
+        assert_eq!(
+            list_root("incoming/2024/img_"),
+            Some(Path::from("incoming/2024"))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_objects_filters_on_prefix() {
+        use windmill_object_store::object_store_reexports::{InMemory, PutPayload};
+
+        let store = InMemory::new();
+        for key in [
+            "incoming/a.csv",
+            "incoming/b.json",
+            "incomingx/c.csv",
+            "other/d.csv",
+        ] {
+            store
+                .put(&Path::from(key), PutPayload::from_static(b"data"))
+                .await
+                .unwrap();
+        }
+
+        let mut keys = list_objects(&store, "incoming/", None)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|object| object.key)
+            .collect::<Vec<_>>();
+        keys.sort();
+        assert_eq!(keys, vec!["incoming/a.csv", "incoming/b.json"]);
+
+        let objects = list_objects(&store, "incoming/a", Some("archive"))
+            .await
+            .unwrap();
+        assert_eq!(objects.len(), 1);
+        assert_eq!(objects[0].size, 4);
+        assert_eq!(objects[0].storage.as_deref(), Some("archive"));
+        assert!(objects[0]
+            .etag
+            .as_ref()
+            .is_some_and(|etag| !etag.contains('"')));
+    }
+
+    #[test]
+    fn test_version_falls_back_to_modification_time() {
+        let last_modified = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        let mut event = ObjectEvent {
+            key: "a.csv".to_string(),
+            size: 4,
+            etag: Some("abc".to_string()),
+            last_modified,
+            storage: None,
+        };
+        assert_eq!(event.version(), "abc");
+        event.etag = None;
+        assert_eq!(event.version(), "1700000000000000:4");
+    }
+}
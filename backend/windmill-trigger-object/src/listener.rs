@@ -0,0 +1,523 @@
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use tokio::sync::RwLock;
+use windmill_common::{
+    db::UserDB,
+    error::{Error, Result},
+    jobs::JobTriggerKind,
+    utils::{report_critical_error, report_recovered_critical_error},
+    worker::to_raw_value,
+    DB,
+};
+
+use windmill_trigger::listener::ListeningTrigger;
+use windmill_trigger::trigger_helpers::TriggerJobArgs;
+use windmill_trigger::Listener;
+
+use super::{
+    list_objects, notifications, resolve_storage, validate_object_config, ObjectConfig,
+    ObjectEvent, ObjectTrigger, WatchedStorage, DELIVERY_NOTIFICATIONS,
+};
+
+const RECONNECT_BACKOFF_SECS: u64 = 30;
+// An object can show up in a listing well after its modification time: S3 dates it from the
+// start of its upload. Objects modified up to this long before the watermark are still
+// considered, the versions already handled being skipped.
+const LOOKBACK_SECS: i64 = 15 * 60;
+// How long the versions handled are remembered, past which they can't be listed as new.
+const SEEN_RETENTION_SECS: i64 = 2 * LOOKBACK_SECS;
+
+/// The versions of objects the trigger already handled.
+enum SeenObjects {
+    /// In `object_trigger_seen`, shared by every server so that a version starts a single job.
+    Table,
+    /// Captures have no trigger row to attach them to, they are kept for the life of the
+    /// listener.
+    Memory(HashMap<(String, String), DateTime<Utc>>),
+}
+
+impl SeenObjects {
+    fn new(listening_trigger: &ListeningTrigger<ObjectConfig>) -> Self {
+        if listening_trigger.trigger_mode {
+            SeenObjects::Table
+        } else {
+            SeenObjects::Memory(HashMap::new())
+        }
+    }
+
+    /// Records that `object` is handled. Returns false if it already was, by this or
+    /// another server.
+    async fn claim(
+        &mut self,
+        db: &DB,
+        listening_trigger: &ListeningTrigger<ObjectConfig>,
+        object: &ObjectEvent,
+    ) -> Result<bool> {
+        match self {
+            SeenObjects::Table => {
+                let claimed = sqlx::query_scalar!(
+                    r#"
+                    INSERT INTO object_trigger_seen (
+                        workspace_id,
+                        trigger_path,
+                        object_key,
+                        version,
+                        last_modified
+                    )
+                    VALUES ($1, $2, $3, $4, $5)
+                    ON CONFLICT DO NOTHING
+                    RETURNING 1
+                    "#,
+                    listening_trigger.workspace_id,
+                    listening_trigger.path,
+                    object.key,
+                    object.version(),
+                    object.last_modified
+                )
+                .fetch_optional(db)
+                .await?;
+                Ok(claimed.is_some())
+            }
+            SeenObjects::Memory(seen) => Ok(seen
+                .insert((object.key.clone(), object.version()), object.last_modified)
+                .is_none()),
+        }
+    }
+
+    /// Forgets `object`, so that it is handled again.
+    async fn release(
+        &mut self,
+        db: &DB,
+        listening_trigger: &ListeningTrigger<ObjectConfig>,
+        object: &ObjectEvent,
+    ) -> Result<()> {
+        match self {
+            SeenObjects::Table => {
+                sqlx::query!(
+                    r#"
+                    DELETE FROM
+                        object_trigger_seen
+                    WHERE
+                        workspace_id = $1 AND
+                        trigger_path = $2 AND
+                        object_key = $3 AND
+                        version = $4
+                    "#,
+                    listening_trigger.workspace_id,
+                    listening_trigger.path,
+                    object.key,
+                    object.version()
+                )
+                .execute(db)
+                .await?;
+            }
+            SeenObjects::Memory(seen) => {
+                seen.remove(&(object.key.clone(), object.version()));
+            }
+        }
+        Ok(())
+    }
+
+    async fn prune(
+        &mut self,
+        db: &DB,
+        listening_trigger: &ListeningTrigger<ObjectConfig>,
+        before: DateTime<Utc>,
+    ) -> Result<()> {
+        match self {
+            SeenObjects::Table => {
+                sqlx::query!(
+                    r#"
+                    DELETE FROM
+                        object_trigger_seen
+                    WHERE
+                        workspace_id = $1 AND
+                        trigger_path = $2 AND
+                        last_modified < $3
+                    "#,
+                    listening_trigger.workspace_id,
+                    listening_trigger.path,
+                    before
+                )
+                .execute(db)
+                .await?;
+            }
+            SeenObjects::Memory(seen) => {
+                seen.retain(|_, last_modified| *last_modified >= before);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Where the trigger is in the storage, saved as it goes so that another server or a restart
+/// resumes from there.
+struct Progress {
+    watermark: Option<DateTime<Utc>>,
+    seen: SeenObjects,
+}
+
+impl Progress {
+    fn since(&self) -> Option<DateTime<Utc>> {
+        self.watermark
+            .map(|watermark| watermark - chrono::Duration::seconds(LOOKBACK_SECS))
+    }
+
+    async fn advance(
+        &mut self,
+        db: &DB,
+        listening_trigger: &ListeningTrigger<ObjectConfig>,
+        watermark: DateTime<Utc>,
+    ) -> Result<()> {
+        if self.watermark.is_some_and(|current| current >= watermark) {
+            return Ok(());
+        }
+        self.watermark = Some(watermark);
+
+        if listening_trigger.trigger_mode {
+            // Scoped to the storage and prefix the objects were listed from: if the trigger
+            // was just repointed, its reset watermark must not be overwritten.
+            sqlx::query!(
+                r#"
+                UPDATE
+                    object_trigger
+                SET
+                    watermark = $1
+                WHERE
+                    workspace_id = $2 AND
+                    path = $3 AND
+                    storage IS NOT DISTINCT FROM $4 AND
+                    prefix = $5
+                "#,
+                watermark,
+                listening_trigger.workspace_id,
+                listening_trigger.path,
+                listening_trigger.trigger_config.storage,
+                listening_trigger.trigger_config.prefix
+            )
+            .execute(db)
+            .await?;
+        }
+
+        self.seen
+            .prune(
+                db,
+                listening_trigger,
+                watermark - chrono::Duration::seconds(SEEN_RETENTION_SECS),
+            )
+            .await
+    }
+}
+
+impl ObjectTrigger {
+    async fn build_storage(
+        &self,
+        db: &DB,
+        listening_trigger: &ListeningTrigger<ObjectConfig>,
+    ) -> Result<WatchedStorage> {
+        let ObjectConfig { storage, prefix, delivery, poll_interval_secs, .. } =
+            &listening_trigger.trigger_config;
+
+        validate_object_config(prefix, delivery, *poll_interval_secs).map_err(Error::BadConfig)?;
+
+        let authed = listening_trigger
+            .authed(db, &Self::TRIGGER_KIND.to_string())
+            .await?;
+
+        let storage = resolve_storage(
+            db,
+            &authed,
+            UserDB::new(db.clone()),
+            &listening_trigger.workspace_id,
+            storage.as_deref(),
+        )
+        .await?;
+
+        if delivery == DELIVERY_NOTIFICATIONS && storage.s3_resource.is_none() {
+            return Err(Error::BadConfig(
+                "Bucket notifications are only available on S3-compatible storage, use the list delivery instead"
+                    .to_string(),
+            ));
+        }
+
+        Ok(storage)
+    }
+
+    /// Starts a job for `object` unless it was already handled. An object whose job could not
+    /// be started is released, to be picked up again by the next listing.
+    async fn dispatch_object(
+        &self,
+        db: &DB,
+        listening_trigger: &ListeningTrigger<ObjectConfig>,
+        progress: &mut Progress,
+        object: ObjectEvent,
+    ) -> Result<()> {
+        if !progress.seen.claim(db, listening_trigger, &object).await? {
+            return Ok(());
+        }
+
+        let trigger_info = HashMap::from([
+            (
+                "delivery".to_string(),
+                to_raw_value(&listening_trigger.trigger_config.delivery),
+            ),
+            (
+                "prefix".to_string(),
+                to_raw_value(&listening_trigger.trigger_config.prefix),
+            ),
+        ]);
+
+        if self
+            .handle_event(db, listening_trigger, object.clone(), trigger_info, None)
+            .await
+            .is_err()
+        {
+            progress
+                .seen
+                .release(db, listening_trigger, &object)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Lists the storage and starts a job for every object modified since the watermark. The
+    /// first listing only records what is already there.
+    async fn list_once(
+        &self,
+        db: &DB,
+        listening_trigger: &ListeningTrigger<ObjectConfig>,
+        storage: &WatchedStorage,
+        progress: &mut Progress,
+    ) -> Result<()> {
+        let ObjectConfig { storage: storage_name, prefix, .. } = &listening_trigger.trigger_config;
+        let listed_at = Utc::now();
+        let mut objects = list_objects(&*storage.store, prefix, storage_name.as_deref()).await?;
+        objects.sort_by_key(|object| object.last_modified);
+
+        let newest = objects.last().map(|object| object.last_modified);
+
+        match progress.since() {
+            Some(since) => {
+                for object in objects.into_iter().filter(|o| o.last_modified >= since) {
+                    self.dispatch_object(db, listening_trigger, progress, object)
+                        .await?;
+                }
+            }
+            None => {
+                let since = newest.unwrap_or(listed_at) - chrono::Duration::seconds(LOOKBACK_SECS);
+                for object in objects.iter().filter(|o| o.last_modified >= since) {
+                    progress.seen.claim(db, listening_trigger, object).await?;
+                }
+            }
+        }
+
+        let watermark = progress.watermark.max(newest).unwrap_or(listed_at);
+        progress.advance(db, listening_trigger, watermark).await
+    }
+
+    /// Only returns once listing fails.
+    async fn poll_objects(
+        &self,
+        db: &DB,
+        listening_trigger: &ListeningTrigger<ObjectConfig>,
+        storage: &WatchedStorage,
+        progress: &mut Progress,
+    ) -> Result<()> {
+        let interval =
+            Duration::from_secs(listening_trigger.trigger_config.poll_interval_secs as u64);
+        loop {
+            let started = Instant::now();
+            self.list_once(db, listening_trigger, storage, progress)
+                .await?;
+            tokio::time::sleep(interval.saturating_sub(started.elapsed())).await;
+        }
+    }
+
+    /// Only returns once the subscription ends.
+    async fn consume_notifications(
+        &self,
+        db: &DB,
+        listening_trigger: &ListeningTrigger<ObjectConfig>,
+        storage: &WatchedStorage,
+        progress: &mut Progress,
+    ) -> Result<()> {
+        let ObjectConfig { storage: storage_name, prefix, .. } = &listening_trigger.trigger_config;
+        let Some(s3_resource) = storage.s3_resource.as_ref() else {
+            return Err(Error::BadConfig(
+                "Bucket notifications are only available on S3-compatible storage".to_string(),
+            ));
+        };
+
+        // Subscribe before catching up on what changed while unsubscribed, so that no object
+        // falls in between: the events received meanwhile wait in the response.
+        let mut stream =
+            notifications::listen(s3_resource, prefix, storage_name.as_deref()).await?;
+        self.list_once(db, listening_trigger, storage, progress)
+            .await?;
+
+        while let Some(objects) = stream.next_events().await? {
+            let newest = objects.iter().map(|object| object.last_modified).max();
+            for object in objects {
+                self.dispatch_object(db, listening_trigger, progress, object)
+                    .await?;
+            }
+            if let Some(newest) = newest {
+                progress.advance(db, listening_trigger, newest).await?;
+            }
+        }
+
+        Err(Error::internal_err(
+            "The server ended the bucket notification subscription".to_string(),
+        ))
+    }
+}
+
+#[async_trait]
+impl Listener for ObjectTrigger {
+    type Consumer = ();
+    type Extra = ();
+    type ExtraState = ();
+    const JOB_TRIGGER_KIND: JobTriggerKind = JobTriggerKind::Object;
+
+    async fn get_consumer(
+        &self,
+        _db: &DB,
+        _listening_trigger: &ListeningTrigger<Self::TriggerConfig>,
+        _err_message: Arc<RwLock<Option<String>>>,
+        _killpill_rx: tokio::sync::broadcast::Receiver<()>,
+    ) -> Result<Option<Self::Consumer>> {
+        // The storage is resolved (and resolved again) in `consume` so that an unreachable
+        // storage retries with backoff instead of disabling the trigger.
+        Ok(Some(()))
+    }
+
+    /// Every new or changed object under the prefix starts one job, across servers. An
+    /// object whose job could not be started is picked up again by the next listing.
+    async fn consume(
+        &self,
+        db: &DB,
+        _consumer: Self::Consumer,
+        listening_trigger: &ListeningTrigger<Self::TriggerConfig>,
+        err_message: Arc<RwLock<Option<String>>>,
+        _killpill_rx: tokio::sync::broadcast::Receiver<()>,
+        _extra_state: Option<&Self::ExtraState>,
+    ) {
+        let path = &listening_trigger.path;
+        let workspace_id = &listening_trigger.workspace_id;
+        let alert_id = format!("object_trigger:{}", path);
+        let mut progress = Progress {
+            watermark: listening_trigger.trigger_config.watermark,
+            seen: SeenObjects::new(listening_trigger),
+        };
+        let mut tries = 0_usize;
+
+        loop {
+            let storage = match self.build_storage(db, listening_trigger).await {
+                Ok(storage) => storage,
+                Err(e) => {
+                    let status = format!(
+                        "Failed to access the storage (attempt {}), retrying in {}s: {}",
+                        tries + 1,
+                        RECONNECT_BACKOFF_SECS,
+                        e
+                    );
+                    if self
+                        .update_ping_and_loop_ping_status(
+                            db,
+                            listening_trigger,
+                            err_message.clone(),
+                            Some(status),
+                        )
+                        .await
+                        .is_none()
+                    {
+                        return;
+                    }
+                    tracing::error!(
+                        "Object trigger {} failed to access the storage (attempt {}): {}",
+                        path,
+                        tries + 1,
+                        e
+                    );
+                    if tries % 10 == 0 && listening_trigger.trigger_mode {
+                        report_critical_error(
+                            format!(
+                                "Object trigger {} failed to access its storage (attempt {}), retrying every {}s. This alert repeats every 10 failed attempts. Error: {}",
+                                path, tries + 1, RECONNECT_BACKOFF_SECS, e
+                            ),
+                            db.clone(),
+                            Some(workspace_id),
+                            Some(&alert_id),
+                        )
+                        .await;
+                    }
+                    tries += 1;
+                    tokio::time::sleep(Duration::from_secs(RECONNECT_BACKOFF_SECS)).await;
+                    continue;
+                }
+            };
+
+            if self
+                .update_ping_and_loop_ping_status(db, listening_trigger, err_message.clone(), None)
+                .await
+                .is_none()
+            {
+                return;
+            }
+            if tries > 0 {
+                tracing::info!(
+                    "Object trigger {} accessed its storage after {} attempts",
+                    path,
+                    tries
+                );
+                if listening_trigger.trigger_mode {
+                    report_recovered_critical_error(
+                        format!("Object trigger {} accessed its storage again", path),
+                        db.clone(),
+                        Some(workspace_id),
+                        Some(&alert_id),
+                    )
+                    .await;
+                }
+                tries = 0;
+            }
+
+            let result = if listening_trigger.trigger_config.delivery == DELIVERY_NOTIFICATIONS {
+                self.consume_notifications(db, listening_trigger, &storage, &mut progress)
+                    .await
+            } else {
+                self.poll_objects(db, listening_trigger, &storage, &mut progress)
+                    .await
+            };
+
+            if let Err(err) = result {
+                tracing::warn!(
+                    "Object trigger {} stopped watching its storage, resuming in {}s: {}",
+                    path,
+                    RECONNECT_BACKOFF_SECS,
+                    err
+                );
+                if self
+                    .update_ping_and_loop_ping_status(
+                        db,
+                        listening_trigger,
+                        err_message.clone(),
+                        Some(err.to_string()),
+                    )
+                    .await
+                    .is_none()
+                {
+                    return;
+                }
+                tokio::time::sleep(Duration::from_secs(RECONNECT_BACKOFF_SECS)).await;
+            }
+        }
+    }
+}
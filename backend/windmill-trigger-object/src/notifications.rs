@@ -0,0 +1,238 @@
+//! Bucket notifications through the `ListenBucketNotification` API of MinIO, which streams
+//! the events of a bucket over a long-lived GET as newline-delimited JSON.
+
+use chrono::{DateTime, Utc};
+use reqwest::{Response, Url};
+use serde::Deserialize;
+use windmill_common::{
+    error::{Error, Result},
+    utils::HTTP_CLIENT_STREAMING,
+};
+use windmill_object_store::{render_endpoint, S3Resource};
+
+use super::{
+    normalize_etag,
+    sigv4::{sign_get, Credentials},
+    ObjectEvent,
+};
+
+const OBJECT_CREATED_EVENTS: &str = "s3:ObjectCreated:*";
+
+#[derive(Deserialize)]
+struct Notification {
+    #[serde(rename = "Records", default)]
+    records: Vec<NotificationRecord>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NotificationRecord {
+    event_name: String,
+    event_time: DateTime<Utc>,
+    s3: NotificationEntity,
+}
+
+#[derive(Deserialize)]
+struct NotificationEntity {
+    object: NotificationObject,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NotificationObject {
+    key: String,
+    #[serde(default)]
+    size: u64,
+    e_tag: Option<String>,
+}
+
+fn listen_url(resource: &S3Resource, prefix: &str) -> Result<Url> {
+    let endpoint = render_endpoint(
+        resource.endpoint.clone(),
+        resource.use_ssl,
+        resource.port,
+        resource.path_style,
+        resource.bucket.clone(),
+    );
+    let mut url = Url::parse(&endpoint)
+        .map_err(|e| Error::BadConfig(format!("Invalid storage endpoint {}: {}", endpoint, e)))?;
+
+    if resource.path_style.unwrap_or(true) {
+        url.set_path(&format!("/{}", resource.bucket));
+    } else {
+        url.set_path("/");
+    }
+    url.query_pairs_mut()
+        .append_pair("events", OBJECT_CREATED_EVENTS)
+        .append_pair("prefix", prefix);
+
+    Ok(url)
+}
+
+/// A subscription to the objects created in a bucket.
+pub struct NotificationStream {
+    response: Response,
+    buffer: Vec<u8>,
+    storage: Option<String>,
+}
+
+pub async fn listen(
+    resource: &S3Resource,
+    prefix: &str,
+    storage: Option<&str>,
+) -> Result<NotificationStream> {
+    let (Some(access_key), Some(secret_key)) = (
+        resource.access_key.as_deref(),
+        resource.secret_key.as_deref(),
+    ) else {
+        return Err(Error::BadConfig(
+            "Bucket notifications require a storage resource with an access key and a secret key"
+                .to_string(),
+        ));
+    };
+
+    let url = listen_url(resource, prefix)?;
+    let region = if resource.region.is_empty() {
+        "us-east-1"
+    } else {
+        &resource.region
+    };
+    let credentials =
+        Credentials { access_key, secret_key, session_token: resource.token.as_deref(), region };
+
+    let mut request = HTTP_CLIENT_STREAMING.get(url.clone());
+    for (name, value) in sign_get(&url, &credentials, Utc::now()) {
+        request = request.header(name, value);
+    }
+
+    let response = request.send().await.map_err(|e| {
+        Error::BadConfig(format!("Error subscribing to bucket notifications: {}", e))
+    })?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let body = response.text().await.unwrap_or_default();
+        return Err(Error::BadConfig(format!(
+            "Error subscribing to bucket notifications ({}), the server must support the MinIO ListenBucketNotification API: {}",
+            status, body
+        )));
+    }
+
+    Ok(NotificationStream { response, buffer: Vec::new(), storage: storage.map(str::to_string) })
+}
+
+impl NotificationStream {
+    /// Waits for the next objects to be created. Returns `None` once the server ends the
+    /// subscription.
+    pub async fn next_events(&mut self) -> Result<Option<Vec<ObjectEvent>>> {
+        loop {
+            if let Some(end) = self.buffer.iter().position(|b| *b == b'\n') {
+                let line = self.buffer.drain(..=end).collect::<Vec<_>>();
+                let events = parse_notification_line(&line, self.storage.as_deref())?;
+                if !events.is_empty() {
+                    return Ok(Some(events));
+                }
+                continue;
+            }
+
+            match self.response.chunk().await {
+                Ok(Some(chunk)) => self.buffer.extend_from_slice(&chunk),
+                Ok(None) => return Ok(None),
+                Err(e) => {
+                    return Err(Error::internal_err(format!(
+                        "Error reading bucket notifications: {}",
+                        e
+                    )))
+                }
+            }
+        }
+    }
+}
+
+/// The objects created according to one line of the notification stream. The server sends
+/// blank lines to keep the connection open.
+fn parse_notification_line(line: &[u8], storage: Option<&str>) -> Result<Vec<ObjectEvent>> {
+    let line = String::from_utf8_lossy(line);
+    let line = line.trim();
+    if line.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let notification = serde_json::from_str::<Notification>(line)
+        .map_err(|e| Error::internal_err(format!("Invalid bucket notification: {}", e)))?;
+
+    notification
+        .records
+        .into_iter()
+        .filter(|record| record.event_name.starts_with("s3:ObjectCreated:"))
+        .map(|record| {
+            // Keys are form-encoded: a space is sent as `+` and a literal `+` as `%2B`.
+            let key = urlencoding::decode(&record.s3.object.key.replace('+', " "))
+                .map_err(|e| {
+                    Error::internal_err(format!("Invalid object key in notification: {}", e))
+                })?
+                .into_owned();
+            Ok(ObjectEvent {
+                key,
+                size: record.s3.object.size,
+                etag: record.s3.object.e_tag.as_deref().map(normalize_etag),
+                last_modified: record.event_time,
+                storage: storage.map(str::to_string),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn resource(path_style: Option<bool>) -> S3Resource {
+        S3Resource {
+            bucket: "uploads".to_string(),
+            region: "".to_string(),
+            endpoint: "localhost".to_string(),
+            use_ssl: false,
+            access_key: Some("minioadmin".to_string()),
+            secret_key: Some("minioadmin".to_string()),
+            path_style,
+            token: None,
+            expiration: None,
+            port: Some(9000),
+        }
+    }
+
+    #[test]
+    fn test_listen_url() {
+        assert_eq!(
+            listen_url(&resource(None), "incoming/").unwrap().as_str(),
+            "http://localhost:9000/uploads?events=s3%3AObjectCreated%3A*&prefix=incoming%2F"
+        );
+        assert_eq!(
+            listen_url(&resource(Some(false)), "").unwrap().as_str(),
+            "http://uploads.localhost:9000/?events=s3%3AObjectCreated%3A*&prefix="
+        );
+    }
+
+    #[test]
+    fn test_parse_notification_line() {
+        assert!(parse_notification_line(b" \n", None).unwrap().is_empty());
+
+        let line = br#"{"Records":[
+            {"eventName":"s3:ObjectCreated:Put","eventTime":"2024-05-01T10:00:00.000Z",
+             "s3":{"bucket":{"name":"uploads"},"object":{"key":"incoming/monthly+report%2B2024.csv","size":42,"eTag":"d41d8cd98f00b204e9800998ecf8427e","sequencer":"17CB0B6E7B3B5E0A"}}},
+            {"eventName":"s3:ObjectRemoved:Delete","eventTime":"2024-05-01T10:00:01.000Z",
+             "s3":{"bucket":{"name":"uploads"},"object":{"key":"incoming/old.csv","sequencer":"17CB0B6E7B3B5E0B"}}}
+        ]}"#;
+        let events = parse_notification_line(line, Some("archive")).unwrap();
+
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].key, "incoming/monthly report+2024.csv");
+        assert_eq!(events[0].size, 42);
+        assert_eq!(
+            events[0].etag.as_deref(),
+            Some("d41d8cd98f00b204e9800998ecf8427e")
+        );
+        assert_eq!(events[0].storage.as_deref(), Some("archive"));
+    }
+}
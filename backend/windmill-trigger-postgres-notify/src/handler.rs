@@ -0,0 +1,193 @@
+use async_trait::async_trait;
+use sqlx::PgConnection;
+use windmill_api_auth::ApiAuthed;
+use windmill_common::DB;
+use windmill_common::{
+    db::UserDB,
+    error::{Error, Result},
+};
+use windmill_git_sync::DeployedObject;
+use windmill_trigger::{Trigger, TriggerCrud, TriggerData};
+use windmill_trigger_postgres::{get_raw_postgres_connection, resolve_postgres_resource};
+
+use super::{
+    validate_channels, PostgresNotifyConfig, PostgresNotifyConfigRequest, PostgresNotifyTrigger,
+    TestPostgresNotifyConfig,
+};
+
+#[async_trait]
+impl TriggerCrud for PostgresNotifyTrigger {
+    type TriggerConfig = PostgresNotifyConfig;
+    type Trigger = Trigger<Self::TriggerConfig>;
+    type TriggerConfigRequest = PostgresNotifyConfigRequest;
+    type TestConnectionConfig = TestPostgresNotifyConfig;
+
+    const TABLE_NAME: &'static str = "postgres_notify_trigger";
+    const TRIGGER_TYPE: &'static str = "postgres_notify";
+    const DRAFT_KIND: windmill_common::user_drafts::UserDraftItemKind =
+        windmill_common::user_drafts::UserDraftItemKind::TriggerPostgresNotify;
+    const SUPPORTS_SERVER_STATE: bool = true;
+    const SUPPORTS_TEST_CONNECTION: bool = true;
+    const ROUTE_PREFIX: &'static str = "/postgres_notify_triggers";
+    const DEPLOYMENT_NAME: &'static str = "Postgres NOTIFY trigger";
+    const ADDITIONAL_SELECT_FIELDS: &[&'static str] =
+        &["postgres_resource_path", "channels", "parse_json"];
+    const IS_ALLOWED_ON_CLOUD: bool = false;
+
+    fn get_deployed_object(path: String, parent_path: Option<String>) -> DeployedObject {
+        DeployedObject::PostgresNotifyTrigger { path, parent_path }
+    }
+
+    async fn validate_config(
+        &self,
+        _db: &DB,
+        config: &Self::TriggerConfigRequest,
+        _workspace_id: &str,
+    ) -> Result<()> {
+        if config.postgres_resource_path.trim().is_empty() {
+            return Err(Error::BadRequest(
+                "Postgres resource path cannot be empty".to_string(),
+            ));
+        }
+
+        validate_channels(&config.channels).map_err(Error::BadRequest)?;
+
+        Ok(())
+    }
+
+    async fn create_trigger(
+        &self,
+        _db: &DB,
+        tx: &mut PgConnection,
+        authed: &ApiAuthed,
+        w_id: &str,
+        trigger: TriggerData<Self::TriggerConfigRequest>,
+    ) -> Result<()> {
+        let resolved_edited_by = trigger.base.resolve_edited_by(authed);
+        let resolved_permissioned_as = trigger.base.resolve_permissioned_as(authed);
+
+        sqlx::query!(
+            r#"
+            INSERT INTO postgres_notify_trigger (
+                postgres_resource_path,
+                channels,
+                parse_json,
+                workspace_id,
+                path,
+                script_path,
+                is_flow,
+                permissioned_as,
+                mode,
+                edited_by,
+                error_handler_path,
+                error_handler_args,
+                retry
+            )
+            VALUES (
+                $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13
+            )"#,
+            trigger.config.postgres_resource_path,
+            &trigger.config.channels,
+            trigger.config.parse_json,
+            w_id,
+            trigger.base.path,
+            trigger.base.script_path,
+            trigger.base.is_flow,
+            resolved_permissioned_as,
+            trigger.base.mode() as _,
+            &resolved_edited_by,
+            trigger.error_handling.error_handler_path,
+            trigger.error_handling.error_handler_args as _,
+            trigger.error_handling.retry as _
+        )
+        .execute(tx)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn update_trigger(
+        &self,
+        _db: &DB,
+        tx: &mut PgConnection,
+        authed: &ApiAuthed,
+        workspace_id: &str,
+        path: &str,
+        trigger: TriggerData<Self::TriggerConfigRequest>,
+    ) -> Result<()> {
+        let resolved_edited_by = trigger.base.resolve_edited_by(authed);
+        let resolved_permissioned_as = trigger.base.resolve_permissioned_as(authed);
+
+        // Important to set server_id to NULL to stop the current listener
+        sqlx::query!(
+            r#"
+            UPDATE
+                postgres_notify_trigger
+            SET
+                postgres_resource_path = $1,
+                channels = $2,
+                parse_json = $3,
+                is_flow = $4,
+                edited_by = $5,
+                permissioned_as = $6,
+                script_path = $7,
+                path = $8,
+                edited_at = now(),
+                error = NULL,
+                server_id = NULL,
+                error_handler_path = $11,
+                error_handler_args = $12,
+                retry = $13
+            WHERE
+                workspace_id = $9 AND
+                path = $10
+            "#,
+            trigger.config.postgres_resource_path,
+            &trigger.config.channels,
+            trigger.config.parse_json,
+            trigger.base.is_flow,
+            &resolved_edited_by,
+            resolved_permissioned_as,
+            trigger.base.script_path,
+            trigger.base.path,
+            workspace_id,
+            path,
+            trigger.error_handling.error_handler_path,
+            trigger.error_handling.error_handler_args as _,
+            trigger.error_handling.retry as _
+        )
+        .execute(tx)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn test_connection(
+        &self,
+        db: &DB,
+        authed: &ApiAuthed,
+        user_db: &UserDB,
+        workspace_id: &str,
+        config: Self::TestConnectionConfig,
+    ) -> Result<()> {
+        let database = resolve_postgres_resource(
+            authed,
+            Some(user_db.clone()),
+            db,
+            &config.postgres_resource_path,
+            workspace_id,
+        )
+        .await?;
+
+        let client = get_raw_postgres_connection(&database, false)
+            .await
+            .map_err(|err| Error::BadConfig(format!("Error connecting to Postgres: {}", err)))?;
+
+        client
+            .simple_query("SELECT 1")
+            .await
+            .map_err(|err| Error::BadConfig(format!("Error connecting to Postgres: {}", err)))?;
+
+        Ok(())
+    }
+}
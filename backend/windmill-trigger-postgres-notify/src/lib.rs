@@ -0,0 +1,206 @@
+use std::collections::{HashMap, HashSet};
+
+use futures::{stream, StreamExt};
+use itertools::Itertools;
+use pg_escape::quote_identifier;
+use rust_postgres::{AsyncMessage, Client, Connection, NoTls, Notification};
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+use sqlx::FromRow;
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    sync::mpsc,
+};
+use windmill_common::{
+    error::{to_anyhow, Result},
+    triggers::TriggerKind,
+    worker::to_raw_value,
+};
+use windmill_trigger::trigger_helpers::TriggerJobArgs;
+use windmill_trigger_postgres::{postgres_connection_config, Postgres};
+
+pub mod handler;
+pub mod listener;
+
+#[derive(Clone, Copy)]
+pub struct PostgresNotifyTrigger;
+
+impl TriggerJobArgs for PostgresNotifyTrigger {
+    /// The notification payload, already parsed as JSON or kept as a string depending on
+    /// the trigger's `parse_json`.
+    type Payload = Box<RawValue>;
+    const TRIGGER_KIND: TriggerKind = TriggerKind::PostgresNotify;
+
+    fn v1_payload_fn(payload: &Self::Payload) -> HashMap<String, Box<RawValue>> {
+        HashMap::from([("payload".to_string(), payload.clone())])
+    }
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct PostgresNotifyConfig {
+    pub postgres_resource_path: String,
+    pub channels: Vec<String>,
+    pub parse_json: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PostgresNotifyConfigRequest {
+    pub postgres_resource_path: String,
+    pub channels: Vec<String>,
+    pub parse_json: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestPostgresNotifyConfig {
+    pub postgres_resource_path: String,
+}
+
+/// Postgres truncates identifiers, channel names included, to `NAMEDATALEN - 1` bytes.
+const MAX_CHANNEL_NAME_BYTES: usize = 63;
+
+/// Channels are LISTENed on as quoted identifiers, i.e. exactly as given: a channel
+/// notified with `NOTIFY Orders` (unquoted) is `orders`.
+pub fn validate_channels(channels: &[String]) -> std::result::Result<(), String> {
+    if channels.is_empty() {
+        return Err("At least one channel is required".to_string());
+    }
+
+    let mut seen = HashSet::new();
+    for channel in channels {
+        if channel.trim().is_empty() {
+            return Err("Channel names cannot be empty".to_string());
+        }
+        if channel.len() > MAX_CHANNEL_NAME_BYTES {
+            return Err(format!(
+                "Channel name {} is longer than {} bytes",
+                channel, MAX_CHANNEL_NAME_BYTES
+            ));
+        }
+        if !seen.insert(channel) {
+            return Err(format!("Channel {} is listed more than once", channel));
+        }
+    }
+
+    Ok(())
+}
+
+fn listen_statement(channels: &[String]) -> String {
+    channels
+        .iter()
+        .map(|channel| format!("LISTEN {}", quote_identifier(channel)))
+        .join("; ")
+}
+
+/// With `parse_json`, a payload that is valid JSON is passed on as such; anything else,
+/// and every payload without it, is passed on as a string.
+pub fn notification_payload(payload: &str, parse_json: bool) -> Box<RawValue> {
+    if parse_json {
+        if let Ok(value) = RawValue::from_string(payload.to_string()) {
+            return value;
+        }
+    }
+    to_raw_value(&payload)
+}
+
+pub type NotificationResult = std::result::Result<Notification, rust_postgres::Error>;
+
+/// A connection LISTENing on the trigger's channels.
+///
+/// Notifications are only handed out to whoever polls the connection, so unlike the
+/// other connections of the Postgres triggers it is not simply spawned: a task drives it
+/// and forwards them to `notifications`, which ends (after the error, if any) once the
+/// connection is gone.
+pub struct NotifyConnection {
+    pub client: Client,
+    pub notifications: mpsc::UnboundedReceiver<NotificationResult>,
+}
+
+pub async fn listen(database: &Postgres, channels: &[String]) -> Result<NotifyConnection> {
+    let (config, connector) = postgres_connection_config(database, false)?;
+    let (sender, notifications) = mpsc::unbounded_channel();
+
+    let client = if let Some(connector) = connector {
+        let (client, connection) = config.connect(connector).await.map_err(to_anyhow)?;
+        tokio::spawn(forward_notifications(connection, sender));
+        client
+    } else {
+        let (client, connection) = config.connect(NoTls).await.map_err(to_anyhow)?;
+        tokio::spawn(forward_notifications(connection, sender));
+        client
+    };
+
+    client
+        .batch_execute(&listen_statement(channels))
+        .await
+        .map_err(to_anyhow)?;
+
+    Ok(NotifyConnection { client, notifications })
+}
+
+async fn forward_notifications<S, T>(
+    mut connection: Connection<S, T>,
+    sender: mpsc::UnboundedSender<NotificationResult>,
+) where
+    S: AsyncRead + AsyncWrite + Unpin,
+    T: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut messages = stream::poll_fn(move |cx| connection.poll_message(cx));
+    while let Some(message) = messages.next().await {
+        match message {
+            Ok(AsyncMessage::Notification(notification)) => {
+                if sender.send(Ok(notification)).is_err() {
+                    break;
+                }
+            }
+            Ok(_) => {}
+            Err(err) => {
+                let _ = sender.send(Err(err));
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn channels(channels: &[&str]) -> Vec<String> {
+        channels.iter().map(|c| c.to_string()).collect()
+    }
+
+    #[test]
+    fn test_listen_statement_quotes_channels() {
+        assert_eq!(
+            listen_statement(&channels(&["orders", "New Orders", "a\"b"])),
+            r#"LISTEN orders; LISTEN "New Orders"; LISTEN "a""b""#
+        );
+    }
+
+    #[test]
+    fn test_validate_channels() {
+        assert!(validate_channels(&channels(&["orders", "Orders"])).is_ok());
+        assert!(validate_channels(&[]).is_err());
+        assert!(validate_channels(&channels(&["orders", " "])).is_err());
+        assert!(validate_channels(&channels(&["orders", "orders"])).is_err());
+        assert!(validate_channels(&["a".repeat(MAX_CHANNEL_NAME_BYTES)]).is_ok());
+        assert!(validate_channels(&["a".repeat(MAX_CHANNEL_NAME_BYTES + 1)]).is_err());
+    }
+
+    #[test]
+    fn test_notification_payload() {
+        assert_eq!(
+            notification_payload(r#"{"id": 1}"#, true).get(),
+            r#"{"id": 1}"#
+        );
+        assert_eq!(
+            notification_payload(r#"{"id": 1}"#, false).get(),
+            r#""{\"id\": 1}""#
+        );
+        assert_eq!(
+            notification_payload("not json", true).get(),
+            r#""not json""#
+        );
+        assert_eq!(notification_payload("", true).get(), r#""""#);
+    }
+}
@@ -0,0 +1,232 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use tokio::sync::RwLock;
+use windmill_common::{
+    db::UserDB,
+    error::Result,
+    jobs::JobTriggerKind,
+    utils::{report_critical_error, report_recovered_critical_error},
+    worker::to_raw_value,
+    DB,
+};
+
+use windmill_trigger::listener::ListeningTrigger;
+use windmill_trigger::trigger_helpers::TriggerJobArgs;
+use windmill_trigger::Listener;
+use windmill_trigger_postgres::resolve_postgres_resource;
+
+use super::{
+    listen, notification_payload, NotifyConnection, PostgresNotifyConfig, PostgresNotifyTrigger,
+};
+
+const RECONNECT_BACKOFF_SECS: u64 = 30;
+// A connection that died without the socket noticing (e.g. behind a NAT that dropped the
+// flow) never errors and never delivers again, so it is probed while idle.
+const HEALTH_CHECK_INTERVAL_SECS: u64 = 60;
+const HEALTH_CHECK_TIMEOUT_SECS: u64 = 10;
+
+impl PostgresNotifyTrigger {
+    async fn listen_on_channels(
+        &self,
+        db: &DB,
+        listening_trigger: &ListeningTrigger<PostgresNotifyConfig>,
+    ) -> Result<NotifyConnection> {
+        let PostgresNotifyConfig { postgres_resource_path, channels, .. } =
+            &listening_trigger.trigger_config;
+
+        let authed = listening_trigger
+            .authed(db, &Self::TRIGGER_KIND.to_string())
+            .await?;
+
+        let database = resolve_postgres_resource(
+            &authed,
+            Some(UserDB::new(db.clone())),
+            db,
+            postgres_resource_path,
+            &listening_trigger.workspace_id,
+        )
+        .await?;
+
+        listen(&database, channels).await
+    }
+}
+
+#[async_trait]
+impl Listener for PostgresNotifyTrigger {
+    type Consumer = ();
+    type Extra = ();
+    type ExtraState = ();
+    const JOB_TRIGGER_KIND: JobTriggerKind = JobTriggerKind::PostgresNotify;
+
+    async fn get_consumer(
+        &self,
+        _db: &DB,
+        _listening_trigger: &ListeningTrigger<Self::TriggerConfig>,
+        _err_message: Arc<RwLock<Option<String>>>,
+        _killpill_rx: tokio::sync::broadcast::Receiver<()>,
+    ) -> Result<Option<Self::Consumer>> {
+        // The connection is established (and re-established) in `consume` so that an
+        // unreachable database retries with backoff instead of disabling the trigger.
+        Ok(Some(()))
+    }
+
+    /// Postgres does not keep notifications for a session that is not listening: those
+    /// sent while the connection is down are lost, delivery is at most once.
+    async fn consume(
+        &self,
+        db: &DB,
+        _consumer: Self::Consumer,
+        listening_trigger: &ListeningTrigger<Self::TriggerConfig>,
+        err_message: Arc<RwLock<Option<String>>>,
+        mut killpill_rx: tokio::sync::broadcast::Receiver<()>,
+        _extra_state: Option<&Self::ExtraState>,
+    ) {
+        let path = &listening_trigger.path;
+        let workspace_id = &listening_trigger.workspace_id;
+        let parse_json = listening_trigger.trigger_config.parse_json.unwrap_or(false);
+        let alert_id = format!("postgres_notify_trigger:{}", path);
+        let mut tries = 0_usize;
+
+        loop {
+            let connection = tokio::select! {
+                biased;
+                _ = killpill_rx.recv() => return,
+                connection = self.listen_on_channels(db, listening_trigger) => connection,
+            };
+            let mut connection = match connection {
+                Ok(connection) => connection,
+                Err(e) => {
+                    let status = format!(
+                        "Failed to connect (attempt {}), retrying in {}s: {}",
+                        tries + 1,
+                        RECONNECT_BACKOFF_SECS,
+                        e
+                    );
+                    if self
+                        .update_ping_and_loop_ping_status(
+                            db,
+                            listening_trigger,
+                            err_message.clone(),
+                            Some(status),
+                        )
+                        .await
+                        .is_none()
+                    {
+                        return;
+                    }
+                    tracing::error!(
+                        "Postgres NOTIFY trigger {} failed to connect (attempt {}): {}",
+                        path,
+                        tries + 1,
+                        e
+                    );
+                    if tries % 10 == 0 && listening_trigger.trigger_mode {
+                        report_critical_error(
+                            format!(
+                                "Failed to connect Postgres NOTIFY trigger {} (attempt {}), retrying every {}s. This alert repeats every 10 failed attempts. Error: {}",
+                                path, tries + 1, RECONNECT_BACKOFF_SECS, e
+                            ),
+                            db.clone(),
+                            Some(workspace_id),
+                            Some(&alert_id),
+                        )
+                        .await;
+                    }
+                    tries += 1;
+                    tokio::select! {
+                        biased;
+                        _ = killpill_rx.recv() => return,
+                        _ = tokio::time::sleep(Duration::from_secs(RECONNECT_BACKOFF_SECS)) => continue,
+                    }
+                }
+            };
+
+            if self
+                .update_ping_and_loop_ping_status(db, listening_trigger, err_message.clone(), None)
+                .await
+                .is_none()
+            {
+                return;
+            }
+            if tries > 0 {
+                tracing::info!(
+                    "Postgres NOTIFY trigger {} reconnected after {} attempts",
+                    path,
+                    tries
+                );
+                if listening_trigger.trigger_mode {
+                    report_recovered_critical_error(
+                        format!("Postgres NOTIFY trigger {} reconnected", path),
+                        db.clone(),
+                        Some(workspace_id),
+                        Some(&alert_id),
+                    )
+                    .await;
+                }
+                tries = 0;
+            }
+
+            let mut health_check =
+                tokio::time::interval(Duration::from_secs(HEALTH_CHECK_INTERVAL_SECS));
+            health_check.reset();
+
+            loop {
+                tokio::select! {
+                    biased;
+                    _ = killpill_rx.recv() => {
+                        tracing::info!("Postgres NOTIFY trigger {} stopping on shutdown", path);
+                        return;
+                    }
+                    notification = connection.notifications.recv() => match notification {
+                        Some(Ok(notification)) => {
+                            health_check.reset();
+                            let trigger_info = HashMap::from([
+                                ("channel".to_string(), to_raw_value(&notification.channel())),
+                                ("pid".to_string(), to_raw_value(&notification.process_id())),
+                            ]);
+                            let _ = self
+                                .handle_event(
+                                    db,
+                                    listening_trigger,
+                                    notification_payload(notification.payload(), parse_json),
+                                    trigger_info,
+                                    None,
+                                )
+                                .await;
+                        }
+                        Some(Err(err)) => {
+                            tracing::warn!(
+                                "Postgres NOTIFY trigger {} connection error, reconnecting: {}",
+                                path,
+                                err
+                            );
+                            break;
+                        }
+                        None => {
+                            tracing::warn!(
+                                "Postgres NOTIFY trigger {} connection closed, reconnecting",
+                                path
+                            );
+                            break;
+                        }
+                    },
+                    _ = health_check.tick() => {
+                        let probe = tokio::time::timeout(
+                            Duration::from_secs(HEALTH_CHECK_TIMEOUT_SECS),
+                            connection.client.simple_query("SELECT 1"),
+                        )
+                        .await;
+                        if !matches!(probe, Ok(Ok(_))) {
+                            tracing::warn!(
+                                "Postgres NOTIFY trigger {} connection is unresponsive, reconnecting",
+                                path
+                            );
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -57,6 +57,8 @@ impl TriggerCrud for PostgresTrigger {
         "replication_slot_name",
         "publication_name",
         "NULL::text AS basic_mode",
+        "initial_snapshot",
+        "snapshot_completed_at",
     ];
     const IS_ALLOWED_ON_CLOUD: bool = false;
 
@@ -79,9 +81,11 @@ impl TriggerCrud for PostgresTrigger {
             publication_name,
             replication_slot_name,
             publication,
+            initial_snapshot,
         } = trigger.config;
 
-        let (pub_name, slot_name) =
+        // Only a slot created here may later be dropped, by the initial snapshot
+        let (pub_name, slot_name, slot_owned) =
             if publication_name.is_empty() && replication_slot_name.is_empty() {
                 if publication.is_none() {
                     return Err(Error::BadRequest("publication must be set".to_string()));
@@ -98,7 +102,7 @@ impl TriggerCrud for PostgresTrigger {
                     )
                     .await?;
 
-                (publication_name, replication_slot_name)
+                (publication_name, replication_slot_name, true)
             } else {
                 if publication_name.is_empty() {
                     return Err(Error::BadRequest(
@@ -109,7 +113,7 @@ impl TriggerCrud for PostgresTrigger {
                         "Replication slot name must not be empty".to_string(),
                     ));
                 }
-                (publication_name, replication_slot_name)
+                (publication_name, replication_slot_name, false)
             };
 
         sqlx::query!(
@@ -128,9 +132,11 @@ impl TriggerCrud for PostgresTrigger {
                 edited_at,
                 error_handler_path,
                 error_handler_args,
-                retry
+                retry,
+                initial_snapshot,
+                replication_slot_owned
             ) VALUES (
-                $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, now(), $11, $12, $13
+                $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, now(), $11, $12, $13, $14, $15
             )
             "#,
             w_id,
@@ -145,7 +151,9 @@ impl TriggerCrud for PostgresTrigger {
             resolved_permissioned_as,
             trigger.error_handling.error_handler_path,
             trigger.error_handling.error_handler_args as _,
-            trigger.error_handling.retry as _
+            trigger.error_handling.retry as _,
+            initial_snapshot,
+            slot_owned
         )
         .execute(tx)
         .await?;
@@ -168,6 +176,7 @@ impl TriggerCrud for PostgresTrigger {
             publication_name,
             postgres_resource_path,
             publication,
+            initial_snapshot,
         } = trigger.config;
 
         let mut pg_connection = get_default_pg_connection(
@@ -230,7 +239,13 @@ impl TriggerCrud for PostgresTrigger {
                 error = NULL,
                 error_handler_path = $11,
                 error_handler_args = $12,
-                retry = $13
+                retry = $13,
+                initial_snapshot = $14,
+                replication_slot_owned = CASE
+                    WHEN $15 THEN TRUE
+                    WHEN replication_slot_name = $2::VARCHAR THEN replication_slot_owned
+                    ELSE FALSE
+                END
             WHERE
                 workspace_id = $9 AND path = $10
             "#,
@@ -246,7 +261,9 @@ impl TriggerCrud for PostgresTrigger {
             path,
             trigger.error_handling.error_handler_path,
             trigger.error_handling.error_handler_args as _,
-            trigger.error_handling.retry as _
+            trigger.error_handling.retry as _,
+            initial_snapshot,
+            !exists
         )
         .execute(tx)
         .await?;
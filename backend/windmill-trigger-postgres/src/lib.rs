@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use itertools::Itertools;
 use native_tls::{Certificate, TlsConnector};
 use pg_escape::quote_identifier;
@@ -30,6 +30,7 @@ pub mod listener;
 mod mapper;
 mod relation;
 mod replication_message;
+mod snapshot;
 
 #[derive(Clone, Copy)]
 pub struct PostgresTrigger;
@@ -49,6 +50,12 @@ pub struct PostgresConfig {
     pub publication_name: String,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub basic_mode: Option<bool>,
+    /// Emit every existing row of the tracked tables as an `insert` before streaming changes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub initial_snapshot: Option<bool>,
+    /// Set once that snapshot has been fully emitted, so it is never taken twice.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot_completed_at: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -59,6 +66,8 @@ pub struct PostgresConfigRequest {
     #[serde(default)]
     publication_name: String,
     publication: Option<PublicationData>,
+    #[serde(default)]
+    initial_snapshot: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -313,10 +322,12 @@ fn build_tls_connector(
     )))
 }
 
-pub async fn get_raw_postgres_connection(
+/// Connection settings for `database`, with the TLS connector its ssl mode calls for
+/// (`None` when TLS is disabled).
+pub fn postgres_connection_config(
     database: &Postgres,
     logical_mode: bool,
-) -> Result<Client> {
+) -> Result<(Config, Option<MakeTlsConnector>)> {
     let ssl_mode = match database.sslmode.as_ref() {
             "disable" => SslMode::Disable,
             "" | "prefer" | "allow" => SslMode::Prefer,
@@ -350,6 +361,15 @@ pub async fn get_raw_postgres_connection(
     }
 
     let connector = build_tls_connector(ssl_mode, database.root_certificate_pem.as_ref())?;
+
+    Ok((config, connector))
+}
+
+pub async fn get_raw_postgres_connection(
+    database: &Postgres,
+    logical_mode: bool,
+) -> Result<Client> {
+    let (config, connector) = postgres_connection_config(database, logical_mode)?;
     let client = if let Some(connector) = connector {
         let (client, connection) = config.connect(connector).await.map_err(to_anyhow)?;
         tokio::spawn(async move {
@@ -693,6 +713,8 @@ mod tests {
             replication_slot_name: "slot_1".to_string(),
             publication_name: "pub_1".to_string(),
             basic_mode: Some(false),
+            initial_snapshot: None,
+            snapshot_completed_at: None,
         };
         let json = serde_json::to_value(&config).unwrap();
         assert_eq!(json["postgres_resource_path"], "f/db/postgres");
@@ -27,8 +27,9 @@ use super::{
         LogicalReplicationMessage::{Begin, Commit, Delete, Insert, Relation, Type, Update},
         ReplicationMessage,
     },
-    resolve_postgres_resource, Postgres, PostgresConfig, PostgresTrigger,
-    ERROR_PUBLICATION_NAME_NOT_EXISTS,
+    resolve_postgres_resource,
+    snapshot::{snapshot_pending, take_initial_snapshot},
+    Postgres, PostgresConfig, PostgresTrigger, ERROR_PUBLICATION_NAME_NOT_EXISTS,
 };
 
 const ERROR_REPLICATION_SLOT_NOT_EXISTS: &str = r#"The replication slot associated with this trigger no longer exists. Recreate a new replication slot or select an existing one in the advanced tab, or delete and recreate a new trigger"#;
@@ -140,11 +141,18 @@ impl PostgresSimpleClient {
 /// (connection refused, network interruption, ...) and is retried by the caller.
 /// The resource is re-resolved on every call so credential rotations are picked
 /// up across reconnections.
+///
+/// A trigger also gets a regular connection to measure the lag of its slot, the
+/// replication one being taken by the stream. Failing to open it only loses the lag.
 async fn connect_logical_replication_stream(
     authed: &ApiAuthed,
     db: &DB,
     listening_trigger: &ListeningTrigger<PostgresConfig>,
-) -> Result<(CopyBothDuplex<Bytes>, LogicalReplicationSettings)> {
+) -> Result<(
+    CopyBothDuplex<Bytes>,
+    LogicalReplicationSettings,
+    Option<Client>,
+)> {
     let ListeningTrigger { workspace_id, trigger_config, .. } = listening_trigger;
     let PostgresConfig { postgres_resource_path, publication_name, replication_slot_name, .. } =
         trigger_config;
@@ -188,9 +196,49 @@ async fn connect_logical_replication_stream(
         ));
     }
 
-    client
+    let lag_client = if listening_trigger.trigger_mode {
+        match get_raw_postgres_connection(&database, false).await {
+            Ok(lag_client) => Some(lag_client),
+            Err(err) => {
+                tracing::warn!(
+                    "Postgres trigger {} could not open a connection to measure its slot lag: {}",
+                    &listening_trigger.path,
+                    err
+                );
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    let (stream, settings) = client
         .get_logical_replication_stream(publication_name, replication_slot_name)
-        .await
+        .await?;
+    Ok((stream, settings, lag_client))
+}
+
+/// WAL the replication slot retains past what the trigger confirmed, in bytes. `None` when
+/// the server cannot tell, e.g. a standby where `pg_current_wal_lsn` is not available.
+async fn replication_slot_lag(client: &Client, replication_slot_name: &str) -> Option<i64> {
+    let row = client
+        .query_opt(
+            "SELECT pg_wal_lsn_diff(pg_current_wal_lsn(), confirmed_flush_lsn)::bigint
+             FROM pg_replication_slots WHERE slot_name = $1",
+            &[&replication_slot_name],
+        )
+        .await;
+    match row {
+        Ok(row) => row.and_then(|row| row.get::<_, Option<i64>>(0)),
+        Err(err) => {
+            tracing::debug!(
+                "Could not measure the lag of replication slot {}: {}",
+                replication_slot_name,
+                err
+            );
+            None
+        }
+    }
 }
 
 #[async_trait::async_trait]
@@ -228,10 +276,26 @@ impl Listener for PostgresTrigger {
         // Consecutive failed connection attempts. Reset to 0 once the replication
         // stream is (re)established.
         let mut tries = 0_usize;
+        let mut snapshot_pending = snapshot_pending(listening_trigger);
 
         loop {
-            let (logical_replication_stream, logical_replication_settings) =
-                match connect_logical_replication_stream(&authed, db, listening_trigger).await {
+            // The snapshot (re)creates the slot the stream then reads from, so it has to
+            // come first. Its failures are retried like a failed connection.
+            let snapshot = if snapshot_pending {
+                take_initial_snapshot(self, &authed, db, listening_trigger).await
+            } else {
+                Ok(())
+            };
+            let replication_stream = match snapshot {
+                Ok(()) => {
+                    snapshot_pending = false;
+                    connect_logical_replication_stream(&authed, db, listening_trigger).await
+                }
+                Err(err) => Err(err),
+            };
+
+            let (logical_replication_stream, logical_replication_settings, lag_client) =
+                match replication_stream {
                     Ok(stream) => stream,
                     // Publication or replication slot missing: retrying cannot fix
                     // this, so disable the trigger as before.
@@ -326,6 +390,14 @@ impl Listener for PostgresTrigger {
                             &mut logical_replication_stream,
                         )
                         .await;
+                        if let Some(lag_client) = lag_client.as_ref() {
+                            let lag = replication_slot_lag(
+                                lag_client,
+                                &listening_trigger.trigger_config.replication_slot_name,
+                            )
+                            .await;
+                            self.record_consumer_lag(listening_trigger, lag);
+                        }
                         continue;
                     }
                     Some(message) => message,
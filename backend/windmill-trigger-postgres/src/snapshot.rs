@@ -0,0 +1,389 @@
+use std::collections::HashMap;
+
+use itertools::Itertools;
+use pg_escape::{quote_identifier, quote_literal};
+use rust_postgres::{Client, SimpleQueryMessage};
+use serde_json::value::RawValue;
+use windmill_api_auth::ApiAuthed;
+use windmill_common::{
+    db::UserDB,
+    error::{to_anyhow, Error, Result},
+    worker::to_raw_value,
+    DB,
+};
+use windmill_trigger::{listener::ListeningTrigger, Listener};
+
+use super::{
+    get_raw_postgres_connection, resolve_postgres_resource, PostgresConfig, PostgresTrigger,
+    ERROR_PUBLICATION_NAME_NOT_EXISTS,
+};
+
+/// Rows fetched from the snapshot cursor per round trip. Each row still becomes its own
+/// event; the chunk only bounds how much of a large table is held in memory at once.
+const SNAPSHOT_CHUNK_SIZE: i64 = 1000;
+
+const SNAPSHOT_CURSOR: &str = "windmill_trigger_snapshot";
+
+/// A table of the publication as the snapshot reads it: only the columns and rows the
+/// publication itself would replicate.
+#[derive(Debug, PartialEq)]
+struct PublishedTable {
+    schema_name: String,
+    table_name: String,
+    /// `None` when every column is published (and always before Postgres 15).
+    columns: Option<Vec<String>>,
+    /// The publication's row filter, as deparsed by Postgres (15+ only).
+    row_filter: Option<String>,
+}
+
+impl PublishedTable {
+    /// The row filter comes from `pg_publication_tables`, i.e. from an expression Postgres
+    /// already parsed and stored, so it is spliced in as is. The query goes through
+    /// `Client::execute` all the same, which refuses more than one command.
+    fn snapshot_query(&self) -> String {
+        let columns = self.columns.as_ref().map_or_else(
+            || "*".to_string(),
+            |columns| {
+                columns
+                    .iter()
+                    .map(|column| quote_identifier(column))
+                    .join(", ")
+            },
+        );
+        let mut query = format!(
+            "DECLARE {} NO SCROLL CURSOR FOR SELECT row_to_json(t)::text FROM (SELECT {} FROM {}.{}",
+            SNAPSHOT_CURSOR,
+            columns,
+            quote_identifier(&self.schema_name),
+            quote_identifier(&self.table_name)
+        );
+        if let Some(row_filter) = &self.row_filter {
+            query.push_str(" WHERE ");
+            query.push_str(row_filter);
+        }
+        query.push_str(") t");
+        query
+    }
+}
+
+/// Whether the trigger still owes its initial snapshot. Captures never take one: they
+/// only sample what arrives while they listen.
+pub fn snapshot_pending(listening_trigger: &ListeningTrigger<PostgresConfig>) -> bool {
+    let PostgresConfig { initial_snapshot, snapshot_completed_at, .. } =
+        &listening_trigger.trigger_config;
+    listening_trigger.trigger_mode
+        && initial_snapshot.unwrap_or(false)
+        && snapshot_completed_at.is_none()
+}
+
+/// Emits every row that exists when the replication slot is created as an `insert`
+/// event, then leaves the slot for the regular stream to pick up.
+///
+/// The slot is (re)created with `EXPORT_SNAPSHOT`, and the rows are read in a transaction
+/// importing that snapshot: it sees exactly what was committed before the slot's
+/// consistent point, and the slot streams exactly what is committed after it, so there
+/// is neither a gap nor a duplicate between the two. Rows are encoded with `row_to_json`
+/// rather than the replication protocol's converter, so a few types (e.g. `bytea`) are
+/// spelled the way Postgres renders them to JSON.
+///
+/// A slot that already exists is only recreated if the trigger created it itself:
+/// otherwise the snapshot is refused as a configuration error.
+///
+/// Completion is recorded on the trigger. A snapshot interrupted before that recreates
+/// the slot and starts over, so its rows may be emitted again.
+pub async fn take_initial_snapshot(
+    trigger: &PostgresTrigger,
+    authed: &ApiAuthed,
+    db: &DB,
+    listening_trigger: &ListeningTrigger<PostgresConfig>,
+) -> Result<()> {
+    let ListeningTrigger { workspace_id, path, trigger_config, .. } = listening_trigger;
+    let PostgresConfig { postgres_resource_path, publication_name, replication_slot_name, .. } =
+        trigger_config;
+
+    let database = resolve_postgres_resource(
+        authed,
+        Some(UserDB::new(db.clone())),
+        db,
+        postgres_resource_path,
+        workspace_id,
+    )
+    .await?;
+
+    tracing::info!(
+        "Taking initial snapshot for postgres trigger {} on slot {}",
+        path,
+        replication_slot_name
+    );
+
+    let client = get_raw_postgres_connection(&database, false).await?;
+
+    // Otherwise there is nothing to read, and the snapshot would be recorded as done
+    let publication = client
+        .query_opt(
+            "SELECT pubname FROM pg_publication WHERE pubname = $1",
+            &[&publication_name],
+        )
+        .await
+        .map_err(to_anyhow)?;
+    if publication.is_none() {
+        return Err(Error::BadConfig(
+            ERROR_PUBLICATION_NAME_NOT_EXISTS.to_string(),
+        ));
+    }
+
+    // Whatever the slot retained is older than the snapshot about to be exported, which
+    // already reflects it. Only a slot the trigger created itself is dropped for that: one
+    // picked among existing slots may be someone else's, and is left alone. Unlike the
+    // cleanup of a trigger, a slot some other consumer is streaming from is not taken
+    // over either: dropping it fails and the listener retries.
+    let slot_exists = client
+        .query_opt(
+            "SELECT slot_name FROM pg_replication_slots WHERE slot_name = $1",
+            &[&replication_slot_name],
+        )
+        .await
+        .map_err(to_anyhow)?
+        .is_some();
+    let slot_owned = sqlx::query_scalar!(
+        "SELECT replication_slot_owned FROM postgres_trigger WHERE workspace_id = $1 AND path = $2",
+        workspace_id,
+        path
+    )
+    .fetch_one(db)
+    .await?;
+    if slot_exists {
+        if !slot_owned {
+            return Err(Error::BadConfig(format!(
+                "The initial snapshot starts from a replication slot the trigger creates itself, but slot {} already existed. Pick a new slot name or disable the initial snapshot",
+                replication_slot_name
+            )));
+        }
+        client
+            .execute(
+                "SELECT pg_drop_replication_slot($1)",
+                &[&replication_slot_name],
+            )
+            .await
+            .map_err(to_anyhow)?;
+    }
+
+    // The exported snapshot only lives until this connection runs another command, so it
+    // is kept idle until the reading transaction has imported it
+    let replication_client = get_raw_postgres_connection(&database, true).await?;
+    let snapshot_name =
+        create_slot_exporting_snapshot(&replication_client, replication_slot_name).await?;
+
+    // Recorded only once the slot exists: if that is lost, the slot is left alone and
+    // the trigger reports it, rather than possibly dropping a slot it does not own
+    if !slot_owned {
+        sqlx::query!(
+            "UPDATE postgres_trigger SET replication_slot_owned = true WHERE workspace_id = $1 AND path = $2",
+            workspace_id,
+            path
+        )
+        .execute(db)
+        .await?;
+    }
+
+    client
+        .batch_execute(&format!(
+            "BEGIN ISOLATION LEVEL REPEATABLE READ READ ONLY; SET TRANSACTION SNAPSHOT {}",
+            quote_literal(&snapshot_name)
+        ))
+        .await
+        .map_err(to_anyhow)?;
+    drop(replication_client);
+
+    let (mut emitted, mut failed) = (0_usize, 0_usize);
+    for table in published_tables(&client, publication_name).await? {
+        let (table_emitted, table_failed) =
+            emit_table(trigger, &client, db, listening_trigger, &table).await?;
+        emitted += table_emitted;
+        failed += table_failed;
+    }
+
+    client.batch_execute("COMMIT").await.map_err(to_anyhow)?;
+
+    sqlx::query!(
+        "UPDATE postgres_trigger SET snapshot_completed_at = now() WHERE workspace_id = $1 AND path = $2",
+        workspace_id,
+        path
+    )
+    .execute(db)
+    .await?;
+
+    if failed > 0 {
+        tracing::error!(
+            "Initial snapshot of postgres trigger {} done, {} rows emitted, {} of which failed to trigger a job",
+            path,
+            emitted,
+            failed
+        );
+    } else {
+        tracing::info!(
+            "Initial snapshot of postgres trigger {} done, {} rows emitted",
+            path,
+            emitted
+        );
+    }
+
+    Ok(())
+}
+
+async fn create_slot_exporting_snapshot(
+    replication_client: &Client,
+    replication_slot_name: &str,
+) -> Result<String> {
+    let messages = replication_client
+        .simple_query(&format!(
+            "CREATE_REPLICATION_SLOT {} LOGICAL pgoutput EXPORT_SNAPSHOT",
+            quote_identifier(replication_slot_name)
+        ))
+        .await
+        .map_err(to_anyhow)?;
+
+    messages
+        .iter()
+        .find_map(|message| match message {
+            SimpleQueryMessage::Row(row) => row.get("snapshot_name").map(str::to_string),
+            _ => None,
+        })
+        .ok_or_else(|| {
+            Error::InternalErr(format!(
+                "Creating replication slot {} returned no snapshot",
+                replication_slot_name
+            ))
+        })
+}
+
+/// Read through `to_jsonb` so the same query runs on Postgres 14, whose view has no
+/// `attnames` nor `rowfilter` column: there both simply come back null.
+async fn published_tables(client: &Client, publication_name: &str) -> Result<Vec<PublishedTable>> {
+    let rows = client
+        .query(
+            r#"
+            SELECT
+                schemaname::text,
+                tablename::text,
+                (to_jsonb(p) -> 'attnames')::text,
+                to_jsonb(p) ->> 'rowfilter'
+            FROM
+                pg_publication_tables p
+            WHERE
+                pubname = $1
+            ORDER BY
+                schemaname, tablename
+            "#,
+            &[&publication_name],
+        )
+        .await
+        .map_err(to_anyhow)?;
+
+    rows.into_iter()
+        .map(|row| {
+            let columns = row
+                .get::<_, Option<String>>(2)
+                .map(|columns| serde_json::from_str::<Option<Vec<String>>>(&columns))
+                .transpose()?
+                .flatten();
+            Ok(PublishedTable {
+                schema_name: row.get(0),
+                table_name: row.get(1),
+                columns,
+                row_filter: row.get(3),
+            })
+        })
+        .collect()
+}
+
+/// Returns how many rows were emitted, and how many of those failed to trigger a job.
+/// Each failure is already reported by `handle_event`; like a change of the stream whose
+/// job fails, the row is not retried.
+async fn emit_table(
+    trigger: &PostgresTrigger,
+    client: &Client,
+    db: &DB,
+    listening_trigger: &ListeningTrigger<PostgresConfig>,
+    table: &PublishedTable,
+) -> Result<(usize, usize)> {
+    client
+        .execute(&table.snapshot_query(), &[])
+        .await
+        .map_err(to_anyhow)?;
+
+    let fetch = format!(
+        "FETCH FORWARD {} FROM {}",
+        SNAPSHOT_CHUNK_SIZE, SNAPSHOT_CURSOR
+    );
+    let (mut emitted, mut failed) = (0, 0);
+    loop {
+        let rows = client.query(&fetch, &[]).await.map_err(to_anyhow)?;
+        if rows.is_empty() {
+            break;
+        }
+
+        for row in &rows {
+            let row = RawValue::from_string(row.get::<_, String>(0))?;
+            let database_info = HashMap::from([
+                ("schema_name".to_string(), to_raw_value(&table.schema_name)),
+                ("table_name".to_string(), to_raw_value(&table.table_name)),
+                ("transaction_type".to_string(), to_raw_value(&"insert")),
+                (
+                    "old_row".to_string(),
+                    to_raw_value(&serde_json::Value::Null),
+                ),
+                ("row".to_string(), row),
+            ]);
+            if trigger
+                .handle_event(db, listening_trigger, database_info, HashMap::new(), None)
+                .await
+                .is_err()
+            {
+                failed += 1;
+            }
+        }
+        emitted += rows.len();
+    }
+
+    client
+        .batch_execute(&format!("CLOSE {}", SNAPSHOT_CURSOR))
+        .await
+        .map_err(to_anyhow)?;
+
+    Ok((emitted, failed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(columns: Option<&[&str]>, row_filter: Option<&str>) -> PublishedTable {
+        PublishedTable {
+            schema_name: "public".to_string(),
+            table_name: "Orders".to_string(),
+            columns: columns.map(|columns| columns.iter().map(|c| c.to_string()).collect()),
+            row_filter: row_filter.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn test_snapshot_query_reads_whole_table() {
+        assert_eq!(
+            table(None, None).snapshot_query(),
+            r#"DECLARE windmill_trigger_snapshot NO SCROLL CURSOR FOR SELECT row_to_json(t)::text FROM (SELECT * FROM public."Orders") t"#
+        );
+    }
+
+    #[test]
+    fn test_snapshot_query_keeps_to_published_columns_and_rows() {
+        let query = table(Some(&["id", "Total"]), Some("(status = 'paid'::text)")).snapshot_query();
+        assert!(
+            query.ends_with(
+                r#"(SELECT id, "Total" FROM public."Orders" WHERE (status = 'paid'::text)) t"#
+            ),
+            "got: {}",
+            query
+        );
+    }
+}
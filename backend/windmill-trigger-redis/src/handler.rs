@@ -0,0 +1,237 @@
+use async_trait::async_trait;
+use itertools::Itertools;
+use serde_json::value::RawValue;
+use sqlx::{types::Json as SqlxJson, PgConnection};
+use windmill_api_auth::ApiAuthed;
+use windmill_common::DB;
+use windmill_common::{
+    db::UserDB,
+    error::{Error, Result},
+};
+use windmill_git_sync::DeployedObject;
+use windmill_store::resources::try_get_resource_from_db_as;
+use windmill_trigger::{filter::CompiledFilters, Trigger, TriggerCrud, TriggerData};
+
+use super::{
+    connect, validate_redis_options, validate_streams, RedisConfig, RedisConfigRequest,
+    RedisResource, RedisStreamOptions, RedisTrigger, TestRedisConfig,
+};
+
+#[async_trait]
+impl TriggerCrud for RedisTrigger {
+    type TriggerConfig = RedisConfig;
+    type Trigger = Trigger<Self::TriggerConfig>;
+    type TriggerConfigRequest = RedisConfigRequest;
+    type TestConnectionConfig = TestRedisConfig;
+
+    const TABLE_NAME: &'static str = "redis_trigger";
+    const TRIGGER_TYPE: &'static str = "redis";
+    const DRAFT_KIND: windmill_common::user_drafts::UserDraftItemKind =
+        windmill_common::user_drafts::UserDraftItemKind::TriggerRedis;
+    const SUPPORTS_SERVER_STATE: bool = true;
+    const SUPPORTS_TEST_CONNECTION: bool = true;
+    const ROUTE_PREFIX: &'static str = "/redis_triggers";
+    const DEPLOYMENT_NAME: &'static str = "Redis trigger";
+    const ADDITIONAL_SELECT_FIELDS: &[&'static str] = &[
+        "redis_resource_path",
+        "streams",
+        "consumer_group",
+        "options",
+        "filters",
+        "filter_logic",
+    ];
+    const IS_ALLOWED_ON_CLOUD: bool = false;
+
+    fn get_deployed_object(path: String, parent_path: Option<String>) -> DeployedObject {
+        DeployedObject::RedisTrigger { path, parent_path }
+    }
+
+    async fn validate_config(
+        &self,
+        _db: &DB,
+        config: &Self::TriggerConfigRequest,
+        _workspace_id: &str,
+    ) -> Result<()> {
+        if config.redis_resource_path.trim().is_empty() {
+            return Err(Error::BadRequest(
+                "Redis resource path cannot be empty".to_string(),
+            ));
+        }
+
+        validate_streams(&config.streams).map_err(Error::BadRequest)?;
+
+        if config.consumer_group.trim().is_empty() {
+            return Err(Error::BadRequest(
+                "Consumer group cannot be empty".to_string(),
+            ));
+        }
+
+        validate_redis_options(config.options.as_ref()).map_err(Error::BadRequest)?;
+
+        CompiledFilters::validate(&config.filters)?;
+
+        Ok(())
+    }
+
+    async fn create_trigger(
+        &self,
+        _db: &DB,
+        tx: &mut PgConnection,
+        authed: &ApiAuthed,
+        w_id: &str,
+        trigger: TriggerData<Self::TriggerConfigRequest>,
+    ) -> Result<()> {
+        let resolved_edited_by = trigger.base.resolve_edited_by(authed);
+        let resolved_permissioned_as = trigger.base.resolve_permissioned_as(authed);
+        let options = trigger.config.options.map(SqlxJson);
+        let filters = trigger
+            .config
+            .filters
+            .into_iter()
+            .map(|v| SqlxJson(serde_json::value::to_raw_value(&v).unwrap()))
+            .collect_vec();
+
+        sqlx::query!(
+            r#"
+            INSERT INTO redis_trigger (
+                redis_resource_path,
+                streams,
+                consumer_group,
+                options,
+                filters,
+                filter_logic,
+                workspace_id,
+                path,
+                script_path,
+                is_flow,
+                permissioned_as,
+                mode,
+                edited_by,
+                error_handler_path,
+                error_handler_args,
+                retry
+            )
+            VALUES (
+                $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16
+            )"#,
+            trigger.config.redis_resource_path,
+            &trigger.config.streams,
+            trigger.config.consumer_group,
+            options as Option<SqlxJson<RedisStreamOptions>>,
+            filters.as_slice() as &[SqlxJson<Box<RawValue>>],
+            trigger.config.filter_logic,
+            w_id,
+            trigger.base.path,
+            trigger.base.script_path,
+            trigger.base.is_flow,
+            resolved_permissioned_as,
+            trigger.base.mode() as _,
+            &resolved_edited_by,
+            trigger.error_handling.error_handler_path,
+            trigger.error_handling.error_handler_args as _,
+            trigger.error_handling.retry as _
+        )
+        .execute(tx)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn update_trigger(
+        &self,
+        _db: &DB,
+        tx: &mut PgConnection,
+        authed: &ApiAuthed,
+        workspace_id: &str,
+        path: &str,
+        trigger: TriggerData<Self::TriggerConfigRequest>,
+    ) -> Result<()> {
+        let resolved_edited_by = trigger.base.resolve_edited_by(authed);
+        let resolved_permissioned_as = trigger.base.resolve_permissioned_as(authed);
+        let options = trigger.config.options.map(SqlxJson);
+        let filters = trigger
+            .config
+            .filters
+            .into_iter()
+            .map(|v| SqlxJson(serde_json::value::to_raw_value(&v).unwrap()))
+            .collect_vec();
+
+        // Important to set server_id to NULL to stop the current redis listener
+        sqlx::query!(
+            r#"
+            UPDATE
+                redis_trigger
+            SET
+                redis_resource_path = $1,
+                streams = $2,
+                consumer_group = $3,
+                options = $4,
+                filters = $5,
+                filter_logic = $6,
+                is_flow = $7,
+                edited_by = $8,
+                permissioned_as = $9,
+                script_path = $10,
+                path = $11,
+                edited_at = now(),
+                error = NULL,
+                server_id = NULL,
+                error_handler_path = $14,
+                error_handler_args = $15,
+                retry = $16
+            WHERE
+                workspace_id = $12 AND
+                path = $13
+            "#,
+            trigger.config.redis_resource_path,
+            &trigger.config.streams,
+            trigger.config.consumer_group,
+            options as Option<SqlxJson<RedisStreamOptions>>,
+            filters.as_slice() as &[SqlxJson<Box<RawValue>>],
+            trigger.config.filter_logic,
+            trigger.base.is_flow,
+            &resolved_edited_by,
+            resolved_permissioned_as,
+            trigger.base.script_path,
+            trigger.base.path,
+            workspace_id,
+            path,
+            trigger.error_handling.error_handler_path,
+            trigger.error_handling.error_handler_args as _,
+            trigger.error_handling.retry as _
+        )
+        .execute(tx)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn test_connection(
+        &self,
+        db: &DB,
+        authed: &ApiAuthed,
+        user_db: &UserDB,
+        workspace_id: &str,
+        config: Self::TestConnectionConfig,
+    ) -> Result<()> {
+        let redis_resource = try_get_resource_from_db_as::<RedisResource>(
+            authed,
+            Some(user_db.clone()),
+            db,
+            &config.redis_resource_path,
+            workspace_id,
+        )
+        .await?;
+
+        let mut connection = connect(&redis_resource)
+            .await
+            .map_err(|err| Error::BadConfig(format!("Error connecting to Redis: {}", err)))?;
+
+        redis::cmd("PING")
+            .query_async::<String>(&mut connection)
+            .await
+            .map_err(|err| Error::BadConfig(format!("Error connecting to Redis: {}", err)))?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,336 @@
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use tokio::sync::RwLock;
+use windmill_common::{
+    db::UserDB,
+    error::{Error, Result},
+    jobs::JobTriggerKind,
+    utils::{report_critical_error, report_recovered_critical_error},
+    worker::to_raw_value,
+    DB,
+};
+
+use windmill_store::resources::try_get_resource_from_db_as;
+use windmill_trigger::filter::CompiledFilters;
+use windmill_trigger::listener::ListeningTrigger;
+use windmill_trigger::metrics::ListenerEvent;
+use windmill_trigger::trigger_helpers::TriggerJobArgs;
+use windmill_trigger::Listener;
+
+use super::{
+    entry_payload, RedisConfig, RedisResource, RedisStreamConsumer, RedisTrigger, StreamEntry,
+};
+
+const RECONNECT_BACKOFF_SECS: u64 = 30;
+// Reads block server-side for at most this long, so the claim of idle entries below is
+// never delayed by more than that on a quiet stream.
+const READ_BLOCK_MS: usize = 5_000;
+const CLAIM_INTERVAL_SECS: u64 = 30;
+const LAG_INTERVAL_SECS: u64 = 15;
+
+impl RedisTrigger {
+    async fn build_redis_consumer(
+        &self,
+        db: &DB,
+        listening_trigger: &ListeningTrigger<RedisConfig>,
+    ) -> Result<RedisStreamConsumer> {
+        let RedisConfig { redis_resource_path, streams, consumer_group, options, .. } =
+            &listening_trigger.trigger_config;
+
+        let authed = listening_trigger
+            .authed(db, &Self::TRIGGER_KIND.to_string())
+            .await?;
+
+        let redis_resource = try_get_resource_from_db_as::<RedisResource>(
+            &authed,
+            Some(UserDB::new(db.clone())),
+            db,
+            redis_resource_path,
+            &listening_trigger.workspace_id,
+        )
+        .await?;
+
+        RedisStreamConsumer::new(
+            &redis_resource,
+            streams,
+            consumer_group,
+            options.as_ref().map(|o| &o.0),
+        )
+        .await
+        .map_err(|e| Error::BadConfig(format!("Failed to build Redis consumer: {}", e)))
+    }
+
+    /// Dispatches the entries and acknowledges those that were. An entry that failed stays
+    /// pending in the group and is delivered again once claimed. Errors only when the
+    /// connection is unusable.
+    async fn dispatch_entries(
+        &self,
+        db: &DB,
+        listening_trigger: &ListeningTrigger<RedisConfig>,
+        consumer: &mut RedisStreamConsumer,
+        filters: &CompiledFilters,
+        parse_json: bool,
+        entries: Vec<StreamEntry>,
+    ) -> std::result::Result<(), super::RedisError> {
+        for StreamEntry { stream, entry, claimed } in entries {
+            let payload = entry_payload(&entry, parse_json);
+
+            // An entry the filters reject is acknowledged right away: it would be rejected
+            // again on every delivery.
+            if filters.matches(payload.get()) {
+                let trigger_info = HashMap::from([
+                    ("stream".to_string(), to_raw_value(&stream)),
+                    ("id".to_string(), to_raw_value(&entry.id)),
+                    (
+                        "consumer_group".to_string(),
+                        to_raw_value(&consumer.group()),
+                    ),
+                    (
+                        "consumer_name".to_string(),
+                        to_raw_value(&consumer.consumer_name()),
+                    ),
+                    ("claimed".to_string(), to_raw_value(&claimed)),
+                ]);
+
+                if self
+                    .handle_event(db, listening_trigger, payload, trigger_info, None)
+                    .await
+                    .is_err()
+                {
+                    continue;
+                }
+            } else {
+                self.record_event(listening_trigger, ListenerEvent::Filtered);
+            }
+
+            consumer.ack(&stream, &entry.id).await?;
+        }
+        Ok(())
+    }
+
+    /// Only returns once the connection fails.
+    async fn consume_entries(
+        &self,
+        db: &DB,
+        listening_trigger: &ListeningTrigger<RedisConfig>,
+        consumer: &mut RedisStreamConsumer,
+        filters: &CompiledFilters,
+        parse_json: bool,
+    ) -> std::result::Result<(), super::RedisError> {
+        // Our own unacknowledged entries first: they were delivered to this consumer before
+        // the reconnect and reading new entries never returns them again.
+        loop {
+            let pending = consumer.read_own_pending().await?;
+            if pending.is_empty() {
+                break;
+            }
+            self.dispatch_entries(
+                db,
+                listening_trigger,
+                consumer,
+                filters,
+                parse_json,
+                pending,
+            )
+            .await?;
+        }
+
+        let mut last_claim: Option<Instant> = None;
+        let mut last_lag: Option<Instant> = None;
+        loop {
+            if listening_trigger.trigger_mode
+                && last_lag.map_or(true, |t| t.elapsed().as_secs() >= LAG_INTERVAL_SECS)
+            {
+                last_lag = Some(Instant::now());
+                let lag = consumer.lag().await?;
+                self.record_consumer_lag(listening_trigger, lag);
+            }
+
+            if consumer.claims_pending()
+                && last_claim.map_or(true, |t| t.elapsed().as_secs() >= CLAIM_INTERVAL_SECS)
+            {
+                last_claim = Some(Instant::now());
+                let claimed = consumer.claim_idle().await?;
+                self.dispatch_entries(
+                    db,
+                    listening_trigger,
+                    consumer,
+                    filters,
+                    parse_json,
+                    claimed,
+                )
+                .await?;
+            }
+
+            let entries = consumer.read_new(READ_BLOCK_MS).await?;
+            self.dispatch_entries(
+                db,
+                listening_trigger,
+                consumer,
+                filters,
+                parse_json,
+                entries,
+            )
+            .await?;
+        }
+    }
+}
+
+#[async_trait]
+impl Listener for RedisTrigger {
+    type Consumer = ();
+    type Extra = ();
+    type ExtraState = ();
+    const JOB_TRIGGER_KIND: JobTriggerKind = JobTriggerKind::Redis;
+
+    async fn get_consumer(
+        &self,
+        _db: &DB,
+        _listening_trigger: &ListeningTrigger<Self::TriggerConfig>,
+        _err_message: Arc<RwLock<Option<String>>>,
+        _killpill_rx: tokio::sync::broadcast::Receiver<()>,
+    ) -> Result<Option<Self::Consumer>> {
+        // The connection is established (and re-established) in `consume` so that an
+        // unreachable server retries with backoff instead of disabling the trigger.
+        Ok(Some(()))
+    }
+
+    /// Entries are acknowledged once their job is pushed, so delivery is at least once:
+    /// an entry whose dispatch failed, or that was being dispatched when the connection
+    /// dropped, is delivered again.
+    async fn consume(
+        &self,
+        db: &DB,
+        _consumer: Self::Consumer,
+        listening_trigger: &ListeningTrigger<Self::TriggerConfig>,
+        err_message: Arc<RwLock<Option<String>>>,
+        _killpill_rx: tokio::sync::broadcast::Receiver<()>,
+        _extra_state: Option<&Self::ExtraState>,
+    ) {
+        let path = &listening_trigger.path;
+        let workspace_id = &listening_trigger.workspace_id;
+        let parse_json = listening_trigger
+            .trigger_config
+            .options
+            .as_ref()
+            .and_then(|o| o.parse_json)
+            .unwrap_or(false);
+        let filters = if listening_trigger.trigger_mode {
+            CompiledFilters::parse(
+                listening_trigger
+                    .trigger_config
+                    .filters
+                    .iter()
+                    .map(|m| m.get()),
+                listening_trigger.trigger_config.filter_logic == "or",
+                path,
+            )
+        } else {
+            CompiledFilters::default()
+        };
+        let alert_id = format!("redis_trigger:{}", path);
+        let mut tries = 0_usize;
+
+        loop {
+            let mut consumer = match self.build_redis_consumer(db, listening_trigger).await {
+                Ok(consumer) => consumer,
+                Err(e) => {
+                    let status = format!(
+                        "Failed to connect (attempt {}), retrying in {}s: {}",
+                        tries + 1,
+                        RECONNECT_BACKOFF_SECS,
+                        e
+                    );
+                    if self
+                        .update_ping_and_loop_ping_status(
+                            db,
+                            listening_trigger,
+                            err_message.clone(),
+                            Some(status),
+                        )
+                        .await
+                        .is_none()
+                    {
+                        return;
+                    }
+                    tracing::error!(
+                        "Redis trigger {} failed to connect (attempt {}): {}",
+                        path,
+                        tries + 1,
+                        e
+                    );
+                    if tries % 10 == 0 && listening_trigger.trigger_mode {
+                        report_critical_error(
+                            format!(
+                                "Failed to connect Redis trigger {} (attempt {}), retrying every {}s. This alert repeats every 10 failed attempts. Error: {}",
+                                path, tries + 1, RECONNECT_BACKOFF_SECS, e
+                            ),
+                            db.clone(),
+                            Some(workspace_id),
+                            Some(&alert_id),
+                        )
+                        .await;
+                    }
+                    tries += 1;
+                    tokio::time::sleep(Duration::from_secs(RECONNECT_BACKOFF_SECS)).await;
+                    continue;
+                }
+            };
+
+            if self
+                .update_ping_and_loop_ping_status(db, listening_trigger, err_message.clone(), None)
+                .await
+                .is_none()
+            {
+                return;
+            }
+            if tries > 0 {
+                tracing::info!(
+                    "Redis trigger {} reconnected after {} attempts",
+                    path,
+                    tries
+                );
+                if listening_trigger.trigger_mode {
+                    report_recovered_critical_error(
+                        format!("Redis trigger {} reconnected", path),
+                        db.clone(),
+                        Some(workspace_id),
+                        Some(&alert_id),
+                    )
+                    .await;
+                }
+                tries = 0;
+            }
+
+            if let Err(err) = self
+                .consume_entries(db, listening_trigger, &mut consumer, &filters, parse_json)
+                .await
+            {
+                tracing::warn!(
+                    "Redis trigger {} stopped consuming, reconnecting in {}s: {}",
+                    path,
+                    RECONNECT_BACKOFF_SECS,
+                    err
+                );
+                if self
+                    .update_ping_and_loop_ping_status(
+                        db,
+                        listening_trigger,
+                        err_message.clone(),
+                        Some(err.to_string()),
+                    )
+                    .await
+                    .is_none()
+                {
+                    return;
+                }
+                tokio::time::sleep(Duration::from_secs(RECONNECT_BACKOFF_SECS)).await;
+            }
+        }
+    }
+}
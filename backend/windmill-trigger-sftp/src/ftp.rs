@@ -0,0 +1,181 @@
+use std::{
+    path::Path,
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use native_tls::TlsConnector;
+use suppaftp::{
+    list::File, types::FileType, FtpError, FtpResult, FtpStream, NativeTlsConnector,
+    NativeTlsFtpStream,
+};
+use windmill_common::error::{Error, Result};
+
+use super::remote::{remote_error, RemoteFile, RemoteFs, SftpResource};
+
+const DEFAULT_PORT: u16 = 21;
+
+enum FtpConnection {
+    Plain(FtpStream),
+    Tls(NativeTlsFtpStream),
+}
+
+/// Both streams expose the same methods but have distinct types.
+macro_rules! with_stream {
+    ($connection:expr, $stream:ident => $body:expr) => {
+        match $connection {
+            FtpConnection::Plain($stream) => $body,
+            FtpConnection::Tls($stream) => $body,
+        }
+    };
+}
+
+/// suppaftp is blocking, every command runs on the blocking pool.
+pub struct FtpClient {
+    connection: Arc<Mutex<FtpConnection>>,
+}
+
+impl FtpClient {
+    pub async fn connect(resource: &SftpResource) -> Result<Self> {
+        let host = resource.host.clone();
+        let address = format!("{}:{}", host, resource.port.unwrap_or(DEFAULT_PORT));
+        let user = resource.user.clone();
+        let password = resource.password.clone().unwrap_or_default();
+        let tls = resource.tls;
+
+        let connection = tokio::task::spawn_blocking(move || -> FtpResult<FtpConnection> {
+            let mut connection = if tls {
+                let connector =
+                    TlsConnector::new().map_err(|e| FtpError::SecureError(e.to_string()))?;
+                FtpConnection::Tls(
+                    NativeTlsFtpStream::connect(&address)?
+                        .into_secure(NativeTlsConnector::from(connector), &host)?,
+                )
+            } else {
+                FtpConnection::Plain(FtpStream::connect(&address)?)
+            };
+            with_stream!(&mut connection, stream => {
+                stream.login(&user, &password)?;
+                stream.transfer_type(FileType::Binary)?;
+            });
+            Ok(connection)
+        })
+        .await
+        .map_err(|e| Error::internal_err(format!("FTP connection task failed: {}", e)))?
+        .map_err(|e| Error::BadConfig(format!("Error connecting to {}: {}", resource.host, e)))?;
+
+        Ok(FtpClient { connection: Arc::new(Mutex::new(connection)) })
+    }
+
+    async fn run<T, F>(&self, f: F) -> FtpResult<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut FtpConnection) -> FtpResult<T> + Send + 'static,
+    {
+        let connection = self.connection.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut connection = connection.lock().expect("FTP connection lock poisoned");
+            f(&mut connection)
+        })
+        .await
+        .unwrap_or_else(|e| Err(FtpError::ConnectionError(std::io::Error::other(e))))
+    }
+}
+
+fn to_remote_file(file: File) -> RemoteFile {
+    RemoteFile {
+        name: file.name().to_string(),
+        size: file.size() as u64,
+        modified: Some(DateTime::<Utc>::from(file.modified())),
+    }
+}
+
+#[async_trait]
+impl RemoteFs for FtpClient {
+    async fn list(&mut self, directory: &str) -> Result<Vec<RemoteFile>> {
+        let dir = directory.to_string();
+        let lines = self
+            .run(move |connection| with_stream!(connection, stream => stream.list(Some(&dir))))
+            .await
+            .map_err(|e| remote_error("list", directory, e))?;
+
+        Ok(lines
+            .iter()
+            .filter_map(|line| File::from_str(line).ok())
+            .filter(File::is_file)
+            .map(to_remote_file)
+            .collect())
+    }
+
+    async fn download(&mut self, path: &str, destination: &Path) -> Result<u64> {
+        let remote_path = path.to_string();
+        let destination = destination.to_path_buf();
+        self.run(move |connection| {
+            with_stream!(connection, stream => {
+                let mut reader = stream.retr_as_stream(&remote_path)?;
+                let copied = std::fs::File::create(&destination)
+                    .and_then(|mut local| std::io::copy(&mut reader, &mut local))
+                    .map_err(FtpError::ConnectionError)?;
+                stream.finalize_retr_stream(reader)?;
+                Ok(copied)
+            })
+        })
+        .await
+        .map_err(|e| remote_error("download", path, e))
+    }
+
+    async fn exists(&mut self, path: &str) -> Result<bool> {
+        let remote_path = path.to_string();
+        self.run(
+            move |connection| match with_stream!(connection, stream => stream.size(&remote_path)) {
+                Ok(_) => Ok(true),
+                Err(FtpError::UnexpectedResponse(_)) => Ok(false),
+                Err(e) => Err(e),
+            },
+        )
+        .await
+        .map_err(|e| remote_error("stat", path, e))
+    }
+
+    async fn rename(&mut self, from: &str, to: &str) -> Result<()> {
+        let (source, target) = (from.to_string(), to.to_string());
+        self.run(
+            move |connection| with_stream!(connection, stream => stream.rename(&source, &target)),
+        )
+        .await
+        .map_err(|e| remote_error("move", &format!("{} to {}", from, to), e))
+    }
+
+    async fn remove(&mut self, path: &str) -> Result<()> {
+        let remote_path = path.to_string();
+        self.run(move |connection| with_stream!(connection, stream => stream.rm(&remote_path)))
+            .await
+            .map_err(|e| remote_error("delete", path, e))
+    }
+
+    async fn ensure_directory(&mut self, directory: &str) -> Result<()> {
+        let dir = directory.to_string();
+        self.run(move |connection| {
+            with_stream!(connection, stream => {
+                // FTP has no stat for directories: entering it tells whether it exists, then
+                // the working directory is restored since the other paths may be relative.
+                let home = stream.pwd()?;
+                if stream.cwd(&dir).is_ok() {
+                    stream.cwd(&home)
+                } else {
+                    stream.mkdir(&dir)
+                }
+            })
+        })
+        .await
+        .map_err(|e| remote_error("create", directory, e))
+    }
+
+    async fn close(&mut self) {
+        let _ = self
+            .run(|connection| with_stream!(connection, stream => stream.quit()))
+            .await;
+    }
+}
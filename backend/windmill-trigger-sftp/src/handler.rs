@@ -0,0 +1,232 @@
+use async_trait::async_trait;
+use sqlx::PgConnection;
+use windmill_api_auth::ApiAuthed;
+use windmill_common::DB;
+use windmill_common::{
+    db::UserDB,
+    error::{Error, Result},
+};
+use windmill_git_sync::DeployedObject;
+use windmill_store::resources::try_get_resource_from_db_as;
+use windmill_trigger::{Trigger, TriggerCrud, TriggerData};
+
+use super::{
+    connect, validate_sftp_config, SftpConfig, SftpConfigRequest, SftpResource, SftpTrigger,
+    TestSftpConfig,
+};
+
+#[async_trait]
+impl TriggerCrud for SftpTrigger {
+    type TriggerConfig = SftpConfig;
+    type Trigger = Trigger<Self::TriggerConfig>;
+    type TriggerConfigRequest = SftpConfigRequest;
+    type TestConnectionConfig = TestSftpConfig;
+
+    const TABLE_NAME: &'static str = "sftp_trigger";
+    const TRIGGER_TYPE: &'static str = "sftp";
+    const DRAFT_KIND: windmill_common::user_drafts::UserDraftItemKind =
+        windmill_common::user_drafts::UserDraftItemKind::TriggerSftp;
+    const SUPPORTS_SERVER_STATE: bool = true;
+    const SUPPORTS_TEST_CONNECTION: bool = true;
+    const ROUTE_PREFIX: &'static str = "/sftp_triggers";
+    const DEPLOYMENT_NAME: &'static str = "SFTP trigger";
+    const ADDITIONAL_SELECT_FIELDS: &[&'static str] = &[
+        "sftp_resource_path",
+        "protocol",
+        "directory",
+        "file_pattern",
+        "poll_interval_secs",
+        "on_success",
+        "on_failure",
+        "processed_directory",
+        "failed_directory",
+        "storage",
+    ];
+    const IS_ALLOWED_ON_CLOUD: bool = false;
+
+    fn get_deployed_object(path: String, parent_path: Option<String>) -> DeployedObject {
+        DeployedObject::SftpTrigger { path, parent_path }
+    }
+
+    async fn validate_config(
+        &self,
+        _db: &DB,
+        config: &Self::TriggerConfigRequest,
+        _workspace_id: &str,
+    ) -> Result<()> {
+        if config.sftp_resource_path.trim().is_empty() {
+            return Err(Error::BadRequest(
+                "SFTP resource path cannot be empty".to_string(),
+            ));
+        }
+
+        validate_sftp_config(
+            &config.protocol,
+            &config.directory,
+            config.file_pattern.as_deref(),
+            config.poll_interval_secs,
+            &[&config.on_success, &config.on_failure],
+        )
+        .map_err(Error::BadRequest)
+    }
+
+    async fn create_trigger(
+        &self,
+        _db: &DB,
+        tx: &mut PgConnection,
+        authed: &ApiAuthed,
+        w_id: &str,
+        trigger: TriggerData<Self::TriggerConfigRequest>,
+    ) -> Result<()> {
+        let resolved_edited_by = trigger.base.resolve_edited_by(authed);
+        let resolved_permissioned_as = trigger.base.resolve_permissioned_as(authed);
+
+        sqlx::query!(
+            r#"
+            INSERT INTO sftp_trigger (
+                sftp_resource_path,
+                protocol,
+                directory,
+                file_pattern,
+                poll_interval_secs,
+                on_success,
+                on_failure,
+                processed_directory,
+                failed_directory,
+                storage,
+                workspace_id,
+                path,
+                script_path,
+                is_flow,
+                permissioned_as,
+                mode,
+                edited_by,
+                error_handler_path,
+                error_handler_args,
+                retry
+            )
+            VALUES (
+                $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20
+            )"#,
+            trigger.config.sftp_resource_path,
+            trigger.config.protocol,
+            trigger.config.directory,
+            trigger.config.file_pattern,
+            trigger.config.poll_interval_secs,
+            trigger.config.on_success,
+            trigger.config.on_failure,
+            trigger.config.processed_directory,
+            trigger.config.failed_directory,
+            trigger.config.storage,
+            w_id,
+            trigger.base.path,
+            trigger.base.script_path,
+            trigger.base.is_flow,
+            resolved_permissioned_as,
+            trigger.base.mode() as _,
+            &resolved_edited_by,
+            trigger.error_handling.error_handler_path,
+            trigger.error_handling.error_handler_args as _,
+            trigger.error_handling.retry as _
+        )
+        .execute(tx)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn update_trigger(
+        &self,
+        _db: &DB,
+        tx: &mut PgConnection,
+        authed: &ApiAuthed,
+        workspace_id: &str,
+        path: &str,
+        trigger: TriggerData<Self::TriggerConfigRequest>,
+    ) -> Result<()> {
+        let resolved_edited_by = trigger.base.resolve_edited_by(authed);
+        let resolved_permissioned_as = trigger.base.resolve_permissioned_as(authed);
+
+        // Important to set server_id to NULL to stop the current sftp listener
+        sqlx::query!(
+            r#"
+            UPDATE
+                sftp_trigger
+            SET
+                sftp_resource_path = $1,
+                protocol = $2,
+                directory = $3,
+                file_pattern = $4,
+                poll_interval_secs = $5,
+                on_success = $6,
+                on_failure = $7,
+                processed_directory = $8,
+                failed_directory = $9,
+                storage = $10,
+                is_flow = $11,
+                edited_by = $12,
+                permissioned_as = $13,
+                script_path = $14,
+                path = $15,
+                edited_at = now(),
+                error = NULL,
+                server_id = NULL,
+                error_handler_path = $18,
+                error_handler_args = $19,
+                retry = $20
+            WHERE
+                workspace_id = $16 AND
+                path = $17
+            "#,
+            trigger.config.sftp_resource_path,
+            trigger.config.protocol,
+            trigger.config.directory,
+            trigger.config.file_pattern,
+            trigger.config.poll_interval_secs,
+            trigger.config.on_success,
+            trigger.config.on_failure,
+            trigger.config.processed_directory,
+            trigger.config.failed_directory,
+            trigger.config.storage,
+            trigger.base.is_flow,
+            &resolved_edited_by,
+            resolved_permissioned_as,
+            trigger.base.script_path,
+            trigger.base.path,
+            workspace_id,
+            path,
+            trigger.error_handling.error_handler_path,
+            trigger.error_handling.error_handler_args as _,
+            trigger.error_handling.retry as _
+        )
+        .execute(tx)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn test_connection(
+        &self,
+        db: &DB,
+        authed: &ApiAuthed,
+        user_db: &UserDB,
+        workspace_id: &str,
+        config: Self::TestConnectionConfig,
+    ) -> Result<()> {
+        let resource = try_get_resource_from_db_as::<SftpResource>(
+            authed,
+            Some(user_db.clone()),
+            db,
+            &config.sftp_resource_path,
+            workspace_id,
+        )
+        .await?;
+
+        let mut remote = connect(&resource, &config.protocol).await?;
+        let listed = remote.list(&config.directory).await;
+        remote.close().await;
+        listed.map_err(|err| Error::BadConfig(err.to_string()))?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,273 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use globset::{Glob, GlobMatcher};
+use serde::{Deserialize, Serialize};
+use serde_json::value::RawValue;
+use sqlx::FromRow;
+use windmill_common::{triggers::TriggerKind, worker::to_raw_value};
+use windmill_trigger::trigger_helpers::TriggerJobArgs;
+use windmill_types::s3::S3Object;
+
+mod ftp;
+pub mod handler;
+pub mod listener;
+mod remote;
+mod sftp;
+
+pub use remote::{connect, RemoteFile, RemoteFs, SftpResource};
+
+#[derive(Clone, Copy)]
+pub struct SftpTrigger;
+
+/// A file picked up in the watched directory, once copied to the object storage.
+#[derive(Debug, Clone, Serialize)]
+pub struct SftpFile {
+    /// Path of the file on the server.
+    pub path: String,
+    pub name: String,
+    pub size: u64,
+    pub modified: Option<DateTime<Utc>>,
+    /// The copy of the file in the workspace object storage.
+    pub file: S3Object,
+}
+
+impl TriggerJobArgs for SftpTrigger {
+    type Payload = SftpFile;
+    const TRIGGER_KIND: TriggerKind = TriggerKind::Sftp;
+
+    fn v1_payload_fn(payload: &SftpFile) -> HashMap<String, Box<RawValue>> {
+        HashMap::from([
+            ("path".to_string(), to_raw_value(&payload.path)),
+            ("name".to_string(), to_raw_value(&payload.name)),
+            ("size".to_string(), to_raw_value(&payload.size)),
+            ("modified".to_string(), to_raw_value(&payload.modified)),
+            ("file".to_string(), to_raw_value(&payload.file)),
+        ])
+    }
+}
+
+pub const PROTOCOL_SFTP: &str = "sftp";
+pub const PROTOCOL_FTP: &str = "ftp";
+
+/// What happens to a file on the server once its jobs completed.
+pub const ACTION_MOVE: &str = "move";
+pub const ACTION_DELETE: &str = "delete";
+pub const ACTION_KEEP: &str = "keep";
+
+const DEFAULT_PROCESSED_DIRECTORY: &str = "processed";
+const DEFAULT_FAILED_DIRECTORY: &str = "failed";
+
+const DEFAULT_POLL_INTERVAL_SECS: i32 = 60;
+const MIN_POLL_INTERVAL_SECS: i32 = 10;
+const MAX_POLL_INTERVAL_SECS: i32 = 86_400;
+
+fn default_protocol() -> String {
+    PROTOCOL_SFTP.to_string()
+}
+
+fn default_action() -> String {
+    ACTION_MOVE.to_string()
+}
+
+fn default_poll_interval_secs() -> i32 {
+    DEFAULT_POLL_INTERVAL_SECS
+}
+
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct SftpConfig {
+    pub sftp_resource_path: String,
+    #[serde(default = "default_protocol")]
+    pub protocol: String,
+    pub directory: String,
+    pub file_pattern: Option<String>,
+    #[serde(default = "default_poll_interval_secs")]
+    pub poll_interval_secs: i32,
+    #[serde(default = "default_action")]
+    pub on_success: String,
+    #[serde(default = "default_action")]
+    pub on_failure: String,
+    pub processed_directory: Option<String>,
+    pub failed_directory: Option<String>,
+    /// Name of the workspace storage files are copied to, unset for the primary storage.
+    pub storage: Option<String>,
+}
+
+impl SftpConfig {
+    /// Where a file is moved once its jobs succeeded or failed, `processed/` and `failed/`
+    /// under the watched directory unless set.
+    pub fn destination_directory(&self, success: bool) -> String {
+        let (directory, default) = if success {
+            (&self.processed_directory, DEFAULT_PROCESSED_DIRECTORY)
+        } else {
+            (&self.failed_directory, DEFAULT_FAILED_DIRECTORY)
+        };
+        match directory.as_deref().filter(|d| !d.trim().is_empty()) {
+            Some(directory) => directory.trim_end_matches('/').to_string(),
+            None => join_path(&self.directory, default),
+        }
+    }
+
+    pub fn action(&self, success: bool) -> &str {
+        if success {
+            &self.on_success
+        } else {
+            &self.on_failure
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SftpConfigRequest {
+    pub sftp_resource_path: String,
+    #[serde(default = "default_protocol")]
+    pub protocol: String,
+    pub directory: String,
+    pub file_pattern: Option<String>,
+    #[serde(default = "default_poll_interval_secs")]
+    pub poll_interval_secs: i32,
+    #[serde(default = "default_action")]
+    pub on_success: String,
+    #[serde(default = "default_action")]
+    pub on_failure: String,
+    pub processed_directory: Option<String>,
+    pub failed_directory: Option<String>,
+    pub storage: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestSftpConfig {
+    pub sftp_resource_path: String,
+    #[serde(default = "default_protocol")]
+    pub protocol: String,
+    pub directory: String,
+}
+
+/// Shared validation for the config, used by both the CRUD handler and the listener (which
+/// also covers capture configs, that bypass CRUD validation).
+pub fn validate_sftp_config(
+    protocol: &str,
+    directory: &str,
+    file_pattern: Option<&str>,
+    poll_interval_secs: i32,
+    actions: &[&str],
+) -> std::result::Result<(), String> {
+    match protocol {
+        PROTOCOL_SFTP | PROTOCOL_FTP => {}
+        other => {
+            return Err(format!(
+                "Invalid protocol {}: expected `{}` or `{}`",
+                other, PROTOCOL_SFTP, PROTOCOL_FTP
+            ))
+        }
+    }
+
+    if directory.trim().is_empty() {
+        return Err("Directory cannot be empty".to_string());
+    }
+
+    if let Some(pattern) = file_pattern {
+        file_matcher(Some(pattern))?;
+    }
+
+    if !(MIN_POLL_INTERVAL_SECS..=MAX_POLL_INTERVAL_SECS).contains(&poll_interval_secs) {
+        return Err(format!(
+            "Poll interval must be between {} and {} seconds",
+            MIN_POLL_INTERVAL_SECS, MAX_POLL_INTERVAL_SECS
+        ));
+    }
+
+    for action in actions {
+        match *action {
+            ACTION_MOVE | ACTION_DELETE | ACTION_KEEP => {}
+            other => {
+                return Err(format!(
+                    "Invalid action {}: expected `{}`, `{}` or `{}`",
+                    other, ACTION_MOVE, ACTION_DELETE, ACTION_KEEP
+                ))
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Matches the names of the files to pick up, every file when no pattern is set.
+pub fn file_matcher(pattern: Option<&str>) -> std::result::Result<Option<GlobMatcher>, String> {
+    match pattern.map(str::trim).filter(|p| !p.is_empty()) {
+        None => Ok(None),
+        Some(pattern) => Glob::new(pattern)
+            .map(|glob| Some(glob.compile_matcher()))
+            .map_err(|e| format!("Invalid file pattern {}: {}", pattern, e)),
+    }
+}
+
+pub fn join_path(directory: &str, name: &str) -> String {
+    match directory.trim_end_matches('/') {
+        "" if directory.starts_with('/') => format!("/{}", name),
+        "" => name.to_string(),
+        directory => format!("{}/{}", directory, name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(processed_directory: Option<&str>) -> SftpConfig {
+        SftpConfig {
+            sftp_resource_path: "f/partners/sftp".to_string(),
+            protocol: PROTOCOL_SFTP.to_string(),
+            directory: "/upload/".to_string(),
+            file_pattern: Some("*.csv".to_string()),
+            poll_interval_secs: 60,
+            on_success: ACTION_MOVE.to_string(),
+            on_failure: ACTION_KEEP.to_string(),
+            processed_directory: processed_directory.map(str::to_string),
+            failed_directory: None,
+            storage: None,
+        }
+    }
+
+    #[test]
+    fn test_validate_sftp_config() {
+        assert!(validate_sftp_config("sftp", "/upload", Some("*.csv"), 60, &["move"]).is_ok());
+        assert!(validate_sftp_config("ftp", "upload", None, 10, &["delete", "keep"]).is_ok());
+        assert!(validate_sftp_config("scp", "/upload", None, 60, &[]).is_err());
+        assert!(validate_sftp_config("sftp", " ", None, 60, &[]).is_err());
+        assert!(validate_sftp_config("sftp", "/upload", Some("[a-"), 60, &[]).is_err());
+        assert!(validate_sftp_config("sftp", "/upload", None, 5, &[]).is_err());
+        assert!(validate_sftp_config("sftp", "/upload", None, 60, &["archive"]).is_err());
+    }
+
+    #[test]
+    fn test_file_matcher() {
+        assert!(file_matcher(None).unwrap().is_none());
+        assert!(file_matcher(Some(" ")).unwrap().is_none());
+        let matcher = file_matcher(Some("orders_*.csv")).unwrap().unwrap();
+        assert!(matcher.is_match("orders_2024-05-01.csv"));
+        assert!(!matcher.is_match("orders_2024-05-01.csv.part"));
+    }
+
+    #[test]
+    fn test_destination_directory() {
+        assert_eq!(
+            config(None).destination_directory(true),
+            "/upload/processed"
+        );
+        assert_eq!(config(None).destination_directory(false), "/upload/failed");
+        assert_eq!(
+            config(Some("/archive/")).destination_directory(true),
+            "/archive"
+        );
+        assert_eq!(config(None).action(false), ACTION_KEEP);
+    }
+
+    #[test]
+    fn test_join_path() {
+        assert_eq!(join_path("/upload", "a.csv"), "/upload/a.csv");
+        assert_eq!(join_path("upload/", "a.csv"), "upload/a.csv");
+        assert_eq!(join_path("/", "a.csv"), "/a.csv");
+        assert_eq!(join_path("", "a.csv"), "a.csv");
+    }
+}
@@ -0,0 +1,735 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+use chrono::Utc;
+use globset::GlobMatcher;
+use serde_json::value::RawValue;
+use tokio::{io::AsyncReadExt, sync::RwLock};
+use uuid::Uuid;
+use windmill_common::{
+    db::UserDB,
+    error::{Error, Result},
+    jobs::JobTriggerKind,
+    triggers::TriggerMetadata,
+    utils::{report_critical_error, report_recovered_critical_error},
+    worker::to_raw_value,
+    DB,
+};
+use windmill_object_store::object_store_reexports::{Path as ObjectPath, WriteMultipart};
+use windmill_store::resources::try_get_resource_from_db_as;
+use windmill_trigger::listener::ListeningTrigger;
+use windmill_trigger::trigger_helpers::{trigger_runnables, TriggerJobArgs};
+use windmill_trigger::Listener;
+use windmill_trigger_object::{resolve_storage, WatchedStorage};
+use windmill_types::s3::S3Object;
+
+use super::{
+    connect, file_matcher, join_path, validate_sftp_config, RemoteFile, RemoteFs, SftpConfig,
+    SftpFile, SftpResource, SftpTrigger, ACTION_DELETE, ACTION_MOVE,
+};
+
+const RECONNECT_BACKOFF_SECS: u64 = 30;
+// A file is claimed before its jobs are started: if the server stops in between, the claim
+// is given up on after this long and the file handled as failed.
+const DISPATCH_TIMEOUT_SECS: i64 = 10 * 60;
+const UPLOAD_CHUNK_SIZE: usize = 8 * 1024 * 1024;
+const UPLOAD_PARTS_IN_FLIGHT: usize = 4;
+
+const STATE_DISPATCHING: &str = "dispatching";
+const STATE_RUNNING: &str = "running";
+const STATE_DONE: &str = "done";
+
+const OUTCOME_SUCCESS: &str = "success";
+const OUTCOME_FAILURE: &str = "failure";
+
+/// The ids of the jobs started for a file, collected as `handle_trigger` starts them.
+pub type DispatchedJobs = Arc<Mutex<Vec<Uuid>>>;
+
+/// What the listener remembers between two listings.
+#[derive(Default)]
+struct Watch {
+    /// The version of every file in the previous listing. A file is only picked up once two
+    /// listings agree on it, so that a file still being uploaded is left alone.
+    previous: HashMap<String, String>,
+    /// Captures have no trigger row to record files in, they are kept for the life of the
+    /// listener.
+    captured: HashSet<(String, String)>,
+}
+
+struct Connection {
+    remote: Box<dyn RemoteFs>,
+    storage: WatchedStorage,
+}
+
+fn file_name(remote_path: &str) -> &str {
+    remote_path.rsplit('/').next().unwrap_or(remote_path)
+}
+
+async fn upload(storage: &WatchedStorage, key: &str, local: &Path) -> Result<()> {
+    let mut file = tokio::fs::File::open(local)
+        .await
+        .map_err(|e| Error::internal_err(format!("Failed to open {:?}: {}", local, e)))?;
+    let mut writer = WriteMultipart::new(
+        storage
+            .store
+            .put_multipart(&ObjectPath::from(key))
+            .await
+            .map_err(|e| {
+                Error::internal_err(format!("Failed to start upload of {}: {}", key, e))
+            })?,
+    );
+    let mut chunk = vec![0u8; UPLOAD_CHUNK_SIZE];
+    loop {
+        let read = file
+            .read(&mut chunk)
+            .await
+            .map_err(|e| Error::internal_err(format!("Failed to read {:?}: {}", local, e)))?;
+        if read == 0 {
+            break;
+        }
+        writer
+            .wait_for_capacity(UPLOAD_PARTS_IN_FLIGHT)
+            .await
+            .map_err(|e| Error::internal_err(format!("Failed to upload {}: {}", key, e)))?;
+        writer.write(&chunk[..read]);
+    }
+    writer
+        .finish()
+        .await
+        .map_err(|e| Error::internal_err(format!("Failed to upload {}: {}", key, e)))?;
+    Ok(())
+}
+
+impl SftpTrigger {
+    async fn connect_server(
+        &self,
+        db: &DB,
+        listening_trigger: &ListeningTrigger<SftpConfig>,
+    ) -> Result<Connection> {
+        let config = &listening_trigger.trigger_config;
+        validate_sftp_config(
+            &config.protocol,
+            &config.directory,
+            config.file_pattern.as_deref(),
+            config.poll_interval_secs,
+            &[&config.on_success, &config.on_failure],
+        )
+        .map_err(Error::BadConfig)?;
+
+        let authed = listening_trigger
+            .authed(db, &Self::TRIGGER_KIND.to_string())
+            .await?;
+
+        let resource = try_get_resource_from_db_as::<SftpResource>(
+            &authed,
+            Some(UserDB::new(db.clone())),
+            db,
+            &config.sftp_resource_path,
+            &listening_trigger.workspace_id,
+        )
+        .await?;
+
+        let storage = resolve_storage(
+            db,
+            &authed,
+            UserDB::new(db.clone()),
+            &listening_trigger.workspace_id,
+            config.storage.as_deref(),
+        )
+        .await?;
+
+        let remote = connect(&resource, &config.protocol).await?;
+
+        Ok(Connection { remote, storage })
+    }
+
+    /// Copies `file` to the workspace storage and starts its jobs, returns their ids and the
+    /// key of the copy.
+    async fn dispatch_file(
+        &self,
+        db: &DB,
+        listening_trigger: &ListeningTrigger<SftpConfig>,
+        connection: &mut Connection,
+        file: &RemoteFile,
+    ) -> Result<(Vec<Uuid>, String)> {
+        let config = &listening_trigger.trigger_config;
+        let remote_path = join_path(&config.directory, &file.name);
+
+        let local = tempfile::NamedTempFile::new().map_err(|e| {
+            Error::internal_err(format!("Failed to create a temporary file: {}", e))
+        })?;
+        connection
+            .remote
+            .download(&remote_path, local.path())
+            .await?;
+
+        let key = format!(
+            "sftp_trigger/{}/{}/{}",
+            listening_trigger.path,
+            Uuid::new_v4(),
+            file.name
+        );
+        upload(&connection.storage, &key, local.path()).await?;
+
+        let payload = SftpFile {
+            path: remote_path,
+            name: file.name.clone(),
+            size: file.size,
+            modified: file.modified,
+            file: S3Object {
+                s3: key.clone(),
+                storage: config.storage.clone(),
+                filename: Some(file.name.clone()),
+                presigned: None,
+            },
+        };
+        let trigger_info = HashMap::from([
+            ("protocol".to_string(), to_raw_value(&config.protocol)),
+            ("directory".to_string(), to_raw_value(&config.directory)),
+        ]);
+
+        let job_ids: DispatchedJobs = Arc::new(Mutex::new(vec![]));
+        self.handle_event(
+            db,
+            listening_trigger,
+            payload,
+            trigger_info,
+            Some(job_ids.clone()),
+        )
+        .await?;
+
+        let job_ids = job_ids.lock().expect("job ids lock poisoned").clone();
+        Ok((job_ids, key))
+    }
+
+    /// Claims the file so that it starts its jobs once, then records the jobs to wait for. A
+    /// file whose jobs could not be started is released, to be picked up again.
+    async fn claim_and_dispatch(
+        &self,
+        db: &DB,
+        listening_trigger: &ListeningTrigger<SftpConfig>,
+        connection: &mut Connection,
+        file: &RemoteFile,
+    ) -> Result<()> {
+        let remote_path = join_path(&listening_trigger.trigger_config.directory, &file.name);
+        let version = file.version();
+
+        let claimed = sqlx::query_scalar!(
+            r#"
+            INSERT INTO sftp_trigger_file (
+                workspace_id,
+                trigger_path,
+                remote_path,
+                version
+            )
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT DO NOTHING
+            RETURNING 1
+            "#,
+            listening_trigger.workspace_id,
+            listening_trigger.path,
+            remote_path,
+            version
+        )
+        .fetch_optional(db)
+        .await?;
+        if claimed.is_none() {
+            return Ok(());
+        }
+
+        match self
+            .dispatch_file(db, listening_trigger, connection, file)
+            .await
+        {
+            Ok((job_ids, object_key)) => {
+                sqlx::query!(
+                    r#"
+                    UPDATE
+                        sftp_trigger_file
+                    SET
+                        state = $5,
+                        job_ids = $6,
+                        object_key = $7,
+                        updated_at = now()
+                    WHERE
+                        workspace_id = $1 AND
+                        trigger_path = $2 AND
+                        remote_path = $3 AND
+                        version = $4
+                    "#,
+                    listening_trigger.workspace_id,
+                    listening_trigger.path,
+                    remote_path,
+                    version,
+                    STATE_RUNNING,
+                    &job_ids,
+                    object_key
+                )
+                .execute(db)
+                .await?;
+            }
+            Err(err) => {
+                tracing::warn!(
+                    "SFTP trigger {} could not dispatch {}, retrying on the next poll: {}",
+                    listening_trigger.path,
+                    remote_path,
+                    err
+                );
+                sqlx::query!(
+                    r#"
+                    DELETE FROM
+                        sftp_trigger_file
+                    WHERE
+                        workspace_id = $1 AND
+                        trigger_path = $2 AND
+                        remote_path = $3 AND
+                        version = $4
+                    "#,
+                    listening_trigger.workspace_id,
+                    listening_trigger.path,
+                    remote_path,
+                    version
+                )
+                .execute(db)
+                .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Moves, deletes or keeps the file on the server depending on the outcome of its jobs.
+    async fn apply_action(
+        &self,
+        listening_trigger: &ListeningTrigger<SftpConfig>,
+        connection: &mut Connection,
+        remote_path: &str,
+        success: bool,
+    ) -> Result<()> {
+        let config = &listening_trigger.trigger_config;
+        let remote = &mut connection.remote;
+
+        if !remote.exists(remote_path).await? {
+            return Ok(());
+        }
+
+        match config.action(success) {
+            ACTION_DELETE => remote.remove(remote_path).await,
+            ACTION_MOVE => {
+                let directory = config.destination_directory(success);
+                remote.ensure_directory(&directory).await?;
+
+                let name = file_name(remote_path);
+                let mut target = join_path(&directory, name);
+                // Never overwrite a file already moved there under the same name.
+                if remote.exists(&target).await? {
+                    target = join_path(
+                        &directory,
+                        &format!("{}_{}", Utc::now().format("%Y%m%dT%H%M%S%3f"), name),
+                    );
+                }
+                remote.rename(remote_path, &target).await
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Applies the action of every file whose jobs all completed.
+    async fn settle_files(
+        &self,
+        db: &DB,
+        listening_trigger: &ListeningTrigger<SftpConfig>,
+        connection: &mut Connection,
+    ) -> Result<()> {
+        let pending = sqlx::query!(
+            r#"
+            SELECT
+                remote_path,
+                version,
+                state,
+                job_ids,
+                updated_at
+            FROM
+                sftp_trigger_file
+            WHERE
+                workspace_id = $1 AND
+                trigger_path = $2 AND
+                state <> $3
+            "#,
+            listening_trigger.workspace_id,
+            listening_trigger.path,
+            STATE_DONE
+        )
+        .fetch_all(db)
+        .await?;
+
+        for file in pending {
+            let success = if file.state == STATE_DISPATCHING {
+                let claimed_for = Utc::now() - file.updated_at;
+                if claimed_for.num_seconds() < DISPATCH_TIMEOUT_SECS {
+                    continue;
+                }
+                false
+            } else {
+                let completed = sqlx::query!(
+                    r#"
+                    SELECT
+                        COUNT(*) AS "completed!",
+                        COUNT(*) FILTER (
+                            WHERE status NOT IN ('success', 'skipped')
+                        ) AS "failed!"
+                    FROM
+                        v2_job_completed
+                    WHERE
+                        workspace_id = $1 AND
+                        id = ANY($2)
+                    "#,
+                    listening_trigger.workspace_id,
+                    &file.job_ids
+                )
+                .fetch_one(db)
+                .await?;
+                if (completed.completed as usize) < file.job_ids.len() {
+                    continue;
+                }
+                completed.failed == 0
+            };
+
+            self.apply_action(listening_trigger, connection, &file.remote_path, success)
+                .await?;
+
+            sqlx::query!(
+                r#"
+                UPDATE
+                    sftp_trigger_file
+                SET
+                    state = $5,
+                    outcome = $6,
+                    updated_at = now()
+                WHERE
+                    workspace_id = $1 AND
+                    trigger_path = $2 AND
+                    remote_path = $3 AND
+                    version = $4
+                "#,
+                listening_trigger.workspace_id,
+                listening_trigger.path,
+                file.remote_path,
+                file.version,
+                STATE_DONE,
+                if success {
+                    OUTCOME_SUCCESS
+                } else {
+                    OUTCOME_FAILURE
+                }
+            )
+            .execute(db)
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Settles the files whose jobs completed, then dispatches the files that are new or
+    /// changed and no longer being written to.
+    async fn poll_once(
+        &self,
+        db: &DB,
+        listening_trigger: &ListeningTrigger<SftpConfig>,
+        connection: &mut Connection,
+        matcher: Option<&GlobMatcher>,
+        watch: &mut Watch,
+    ) -> Result<()> {
+        let config = &listening_trigger.trigger_config;
+
+        if listening_trigger.trigger_mode {
+            self.settle_files(db, listening_trigger, connection).await?;
+        }
+
+        let files: Vec<RemoteFile> = connection
+            .remote
+            .list(&config.directory)
+            .await?
+            .into_iter()
+            .filter(|file| matcher.map_or(true, |m| m.is_match(&file.name)))
+            .collect();
+
+        let current: HashMap<String, String> = files
+            .iter()
+            .map(|file| (file.name.clone(), file.version()))
+            .collect();
+        let stable = files
+            .iter()
+            .filter(|file| watch.previous.get(&file.name) == Some(&file.version()));
+
+        for file in stable {
+            if listening_trigger.trigger_mode {
+                self.claim_and_dispatch(db, listening_trigger, connection, file)
+                    .await?;
+            } else if watch.captured.insert((file.name.clone(), file.version())) {
+                if let Err(err) = self
+                    .dispatch_file(db, listening_trigger, connection, file)
+                    .await
+                {
+                    watch.captured.remove(&(file.name.clone(), file.version()));
+                    tracing::warn!(
+                        "SFTP capture {} could not capture {}: {}",
+                        listening_trigger.path,
+                        file.name,
+                        err
+                    );
+                }
+            }
+        }
+
+        if listening_trigger.trigger_mode {
+            // A file that was handled and is gone from the directory can't come back under
+            // the same version, unless it is uploaded again and should then be picked up.
+            let listed: Vec<String> = files
+                .iter()
+                .map(|file| join_path(&config.directory, &file.name))
+                .collect();
+            sqlx::query!(
+                r#"
+                DELETE FROM
+                    sftp_trigger_file
+                WHERE
+                    workspace_id = $1 AND
+                    trigger_path = $2 AND
+                    state = $3 AND
+                    NOT (remote_path = ANY($4))
+                "#,
+                listening_trigger.workspace_id,
+                listening_trigger.path,
+                STATE_DONE,
+                &listed
+            )
+            .execute(db)
+            .await?;
+        }
+
+        watch.previous = current;
+        Ok(())
+    }
+
+    /// Only returns once the server can't be reached anymore.
+    async fn poll_files(
+        &self,
+        db: &DB,
+        listening_trigger: &ListeningTrigger<SftpConfig>,
+        connection: &mut Connection,
+        watch: &mut Watch,
+    ) -> Result<()> {
+        let config = &listening_trigger.trigger_config;
+        let matcher = file_matcher(config.file_pattern.as_deref()).map_err(Error::BadConfig)?;
+        let interval = Duration::from_secs(config.poll_interval_secs as u64);
+        loop {
+            let started = Instant::now();
+            self.poll_once(db, listening_trigger, connection, matcher.as_ref(), watch)
+                .await?;
+            tokio::time::sleep(interval.saturating_sub(started.elapsed())).await;
+        }
+    }
+}
+
+#[async_trait]
+impl Listener for SftpTrigger {
+    type Consumer = ();
+    type Extra = DispatchedJobs;
+    type ExtraState = ();
+    const JOB_TRIGGER_KIND: JobTriggerKind = JobTriggerKind::Sftp;
+
+    async fn get_consumer(
+        &self,
+        _db: &DB,
+        _listening_trigger: &ListeningTrigger<Self::TriggerConfig>,
+        _err_message: Arc<RwLock<Option<String>>>,
+        _killpill_rx: tokio::sync::broadcast::Receiver<()>,
+    ) -> Result<Option<Self::Consumer>> {
+        // The server is connected to (and connected to again) in `consume` so that an
+        // unreachable server retries with backoff instead of disabling the trigger.
+        Ok(Some(()))
+    }
+
+    /// Every new or changed file in the directory starts its jobs once, across servers and
+    /// restarts. The file is moved, deleted or kept once all of them completed.
+    async fn consume(
+        &self,
+        db: &DB,
+        _consumer: Self::Consumer,
+        listening_trigger: &ListeningTrigger<Self::TriggerConfig>,
+        err_message: Arc<RwLock<Option<String>>>,
+        _killpill_rx: tokio::sync::broadcast::Receiver<()>,
+        _extra_state: Option<&Self::ExtraState>,
+    ) {
+        let path = &listening_trigger.path;
+        let workspace_id = &listening_trigger.workspace_id;
+        let alert_id = format!("sftp_trigger:{}", path);
+        let mut watch = Watch::default();
+        let mut tries = 0_usize;
+
+        loop {
+            let mut connection = match self.connect_server(db, listening_trigger).await {
+                Ok(connection) => connection,
+                Err(e) => {
+                    let status = format!(
+                        "Failed to connect to the server (attempt {}), retrying in {}s: {}",
+                        tries + 1,
+                        RECONNECT_BACKOFF_SECS,
+                        e
+                    );
+                    if self
+                        .update_ping_and_loop_ping_status(
+                            db,
+                            listening_trigger,
+                            err_message.clone(),
+                            Some(status),
+                        )
+                        .await
+                        .is_none()
+                    {
+                        return;
+                    }
+                    tracing::error!(
+                        "SFTP trigger {} failed to connect to the server (attempt {}): {}",
+                        path,
+                        tries + 1,
+                        e
+                    );
+                    if tries % 10 == 0 && listening_trigger.trigger_mode {
+                        report_critical_error(
+                            format!(
+                                "SFTP trigger {} failed to connect to its server (attempt {}), retrying every {}s. This alert repeats every 10 failed attempts. Error: {}",
+                                path, tries + 1, RECONNECT_BACKOFF_SECS, e
+                            ),
+                            db.clone(),
+                            Some(workspace_id),
+                            Some(&alert_id),
+                        )
+                        .await;
+                    }
+                    tries += 1;
+                    tokio::time::sleep(Duration::from_secs(RECONNECT_BACKOFF_SECS)).await;
+                    continue;
+                }
+            };
+
+            if self
+                .update_ping_and_loop_ping_status(db, listening_trigger, err_message.clone(), None)
+                .await
+                .is_none()
+            {
+                connection.remote.close().await;
+                return;
+            }
+            if tries > 0 {
+                tracing::info!(
+                    "SFTP trigger {} connected to its server after {} attempts",
+                    path,
+                    tries
+                );
+                if listening_trigger.trigger_mode {
+                    report_recovered_critical_error(
+                        format!("SFTP trigger {} connected to its server again", path),
+                        db.clone(),
+                        Some(workspace_id),
+                        Some(&alert_id),
+                    )
+                    .await;
+                }
+                tries = 0;
+            }
+
+            let result = self
+                .poll_files(db, listening_trigger, &mut connection, &mut watch)
+                .await;
+            connection.remote.close().await;
+
+            if let Err(err) = result {
+                tracing::warn!(
+                    "SFTP trigger {} lost its connection, reconnecting in {}s: {}",
+                    path,
+                    RECONNECT_BACKOFF_SECS,
+                    err
+                );
+                if self
+                    .update_ping_and_loop_ping_status(
+                        db,
+                        listening_trigger,
+                        err_message.clone(),
+                        Some(err.to_string()),
+                    )
+                    .await
+                    .is_none()
+                {
+                    return;
+                }
+                tokio::time::sleep(Duration::from_secs(RECONNECT_BACKOFF_SECS)).await;
+            }
+        }
+    }
+
+    /// Same as the default, except that the job ids are chosen upfront and collected so that
+    /// the file can be settled once they all completed.
+    async fn handle_trigger(
+        &self,
+        db: &DB,
+        listening_trigger: &ListeningTrigger<Self::TriggerConfig>,
+        payload: Self::Payload,
+        mut trigger_info: HashMap<String, Box<RawValue>>,
+        extra: Option<Self::Extra>,
+    ) -> Result<()> {
+        trigger_info.insert(
+            "trigger_path".to_string(),
+            to_raw_value(&listening_trigger.path),
+        );
+        let jobs_args = listening_trigger
+            .transform_or_build_job_args::<Self>(db, payload, trigger_info)
+            .await?;
+
+        let authed = listening_trigger
+            .authed(db, &Self::TRIGGER_KIND.to_string())
+            .await?;
+
+        let (retry, error_handler_path, error_handler_args) =
+            match listening_trigger.error_handling.as_ref() {
+                Some(error_handling) => (
+                    error_handling.retry.as_ref(),
+                    error_handling.error_handler_path.as_deref(),
+                    error_handling.error_handler_args.as_ref(),
+                ),
+                None => (None, None, None),
+            };
+
+        let job_ids = trigger_runnables(
+            db,
+            None,
+            authed,
+            &listening_trigger.workspace_id,
+            &listening_trigger.script_path,
+            listening_trigger.is_flow,
+            jobs_args,
+            retry,
+            error_handler_path,
+            error_handler_args,
+            format!("{}_trigger/{}", Self::TRIGGER_KIND, listening_trigger.path),
+            listening_trigger.suspended_mode,
+            TriggerMetadata::new(Some(listening_trigger.path.clone()), Self::JOB_TRIGGER_KIND),
+        )
+        .await?;
+
+        if let Some(running) = extra.as_ref() {
+            running
+                .lock()
+                .expect("job ids lock poisoned")
+                .extend(job_ids);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,106 @@
+use std::path::Path;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use windmill_common::error::{Error, Result};
+
+use super::{ftp::FtpClient, sftp::SftpClient, PROTOCOL_FTP, PROTOCOL_SFTP};
+
+/// The server a trigger watches. Both protocols share the resource, `private_key` and
+/// `host_key_fingerprint` only apply to SFTP and `tls` only to FTP.
+#[derive(Deserialize)]
+pub struct SftpResource {
+    pub host: String,
+    pub port: Option<u16>,
+    #[serde(alias = "username")]
+    pub user: String,
+    pub password: Option<String>,
+    pub private_key: Option<String>,
+    pub passphrase: Option<String>,
+    /// SHA256 fingerprint of the host key, as printed by `ssh-keygen -lf`. When unset, any
+    /// host key is accepted.
+    pub host_key_fingerprint: Option<String>,
+    /// Upgrade the FTP control and data connections to TLS (explicit FTPS).
+    #[serde(default)]
+    pub tls: bool,
+}
+
+/// A regular file directly in the watched directory.
+#[derive(Debug, Clone)]
+pub struct RemoteFile {
+    pub name: String,
+    pub size: u64,
+    pub modified: Option<DateTime<Utc>>,
+}
+
+impl RemoteFile {
+    /// Identifies this version of the file: a file that is rewritten is picked up again.
+    pub fn version(&self) -> String {
+        match self.modified {
+            Some(modified) => format!("{}:{}", modified.timestamp(), self.size),
+            None => format!("-:{}", self.size),
+        }
+    }
+}
+
+/// The operations the trigger needs from a file server.
+#[async_trait]
+pub trait RemoteFs: Send {
+    async fn list(&mut self, directory: &str) -> Result<Vec<RemoteFile>>;
+
+    /// Copies the file at `path` to the local `destination`, returns the bytes copied.
+    async fn download(&mut self, path: &str, destination: &Path) -> Result<u64>;
+
+    async fn exists(&mut self, path: &str) -> Result<bool>;
+
+    async fn rename(&mut self, from: &str, to: &str) -> Result<()>;
+
+    async fn remove(&mut self, path: &str) -> Result<()>;
+
+    /// Creates `directory` unless it already exists.
+    async fn ensure_directory(&mut self, directory: &str) -> Result<()>;
+
+    async fn close(&mut self);
+}
+
+pub async fn connect(resource: &SftpResource, protocol: &str) -> Result<Box<dyn RemoteFs>> {
+    match protocol {
+        PROTOCOL_SFTP => Ok(Box::new(SftpClient::connect(resource).await?)),
+        PROTOCOL_FTP => Ok(Box::new(FtpClient::connect(resource).await?)),
+        other => Err(Error::BadConfig(format!("Unsupported protocol {}", other))),
+    }
+}
+
+pub(crate) fn remote_error(action: &str, target: &str, err: impl std::fmt::Display) -> Error {
+    Error::internal_err(format!("Error trying to {} {}: {}", action, target, err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remote_file_version() {
+        let mut file = RemoteFile {
+            name: "orders.csv".to_string(),
+            size: 42,
+            modified: DateTime::from_timestamp(1_700_000_000, 0),
+        };
+        assert_eq!(file.version(), "1700000000:42");
+        file.modified = None;
+        assert_eq!(file.version(), "-:42");
+    }
+
+    #[test]
+    fn test_resource_accepts_username() {
+        let resource: SftpResource = serde_json::from_value(serde_json::json!({
+            "host": "localhost",
+            "username": "partner",
+            "password": "secret"
+        }))
+        .unwrap();
+        assert_eq!(resource.user, "partner");
+        assert!(!resource.tls);
+    }
+}
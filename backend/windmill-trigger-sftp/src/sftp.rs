@@ -0,0 +1,246 @@
+use std::{path::Path, sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use chrono::DateTime;
+use russh::{
+    client,
+    keys::{
+        decode_secret_key,
+        ssh_key::{HashAlg, PublicKey},
+        PrivateKeyWithHashAlg,
+    },
+    Disconnect,
+};
+use russh_sftp::client::SftpSession;
+use tokio::io::AsyncWriteExt;
+use windmill_common::error::{Error, Result};
+
+use super::remote::{remote_error, RemoteFile, RemoteFs, SftpResource};
+
+const DEFAULT_PORT: u16 = 22;
+const INACTIVITY_TIMEOUT_SECS: u64 = 300;
+
+struct HostKeyCheck {
+    expected_fingerprint: Option<String>,
+}
+
+impl client::Handler for HostKeyCheck {
+    type Error = russh::Error;
+
+    async fn check_server_key(
+        &mut self,
+        server_public_key: &PublicKey,
+    ) -> std::result::Result<bool, Self::Error> {
+        Ok(match &self.expected_fingerprint {
+            Some(expected) => fingerprint_matches(
+                expected,
+                &server_public_key.fingerprint(HashAlg::Sha256).to_string(),
+            ),
+            None => true,
+        })
+    }
+}
+
+/// Compares fingerprints with or without their `SHA256:` prefix and base64 padding.
+fn fingerprint_matches(expected: &str, actual: &str) -> bool {
+    fn normalize(fingerprint: &str) -> &str {
+        let fingerprint = fingerprint.trim();
+        fingerprint
+            .strip_prefix("SHA256:")
+            .unwrap_or(fingerprint)
+            .trim_end_matches('=')
+    }
+    normalize(expected) == normalize(actual)
+}
+
+pub struct SftpClient {
+    session: client::Handle<HostKeyCheck>,
+    sftp: SftpSession,
+}
+
+impl SftpClient {
+    pub async fn connect(resource: &SftpResource) -> Result<Self> {
+        let config = Arc::new(client::Config {
+            inactivity_timeout: Some(Duration::from_secs(INACTIVITY_TIMEOUT_SECS)),
+            ..Default::default()
+        });
+        let host_key_check = HostKeyCheck {
+            expected_fingerprint: resource
+                .host_key_fingerprint
+                .clone()
+                .filter(|f| !f.trim().is_empty()),
+        };
+        let port = resource.port.unwrap_or(DEFAULT_PORT);
+
+        let mut session = client::connect(config, (resource.host.as_str(), port), host_key_check)
+            .await
+            .map_err(|e| match e {
+                russh::Error::UnknownKey => Error::BadConfig(format!(
+                    "The host key of {} does not match the configured fingerprint",
+                    resource.host
+                )),
+                e => Error::BadConfig(format!(
+                    "Error connecting to {}:{}: {}",
+                    resource.host, port, e
+                )),
+            })?;
+
+        let private_key = resource
+            .private_key
+            .as_deref()
+            .filter(|k| !k.trim().is_empty());
+        let auth = match (private_key, resource.password.as_deref()) {
+            (Some(private_key), _) => {
+                let key = decode_secret_key(private_key, resource.passphrase.as_deref())
+                    .map_err(|e| Error::BadConfig(format!("Invalid private key: {}", e)))?;
+                let hash_alg = session
+                    .best_supported_rsa_hash()
+                    .await
+                    .map_err(|e| remote_error("negotiate with", &resource.host, e))?
+                    .flatten();
+                session
+                    .authenticate_publickey(
+                        &resource.user,
+                        PrivateKeyWithHashAlg::new(Arc::new(key), hash_alg),
+                    )
+                    .await
+            }
+            (None, Some(password)) => {
+                session
+                    .authenticate_password(&resource.user, password)
+                    .await
+            }
+            (None, None) => {
+                return Err(Error::BadConfig(
+                    "The SFTP resource needs a password or a private key".to_string(),
+                ))
+            }
+        }
+        .map_err(|e| remote_error("authenticate to", &resource.host, e))?;
+
+        if !auth.success() {
+            return Err(Error::BadConfig(format!(
+                "Authentication to {} as {} was refused",
+                resource.host, resource.user
+            )));
+        }
+
+        let channel = session
+            .channel_open_session()
+            .await
+            .map_err(|e| remote_error("open a session on", &resource.host, e))?;
+        channel
+            .request_subsystem(true, "sftp")
+            .await
+            .map_err(|e| remote_error("start SFTP on", &resource.host, e))?;
+        let sftp = SftpSession::new(channel.into_stream())
+            .await
+            .map_err(|e| remote_error("start SFTP on", &resource.host, e))?;
+
+        Ok(SftpClient { session, sftp })
+    }
+}
+
+#[async_trait]
+impl RemoteFs for SftpClient {
+    async fn list(&mut self, directory: &str) -> Result<Vec<RemoteFile>> {
+        let entries = self
+            .sftp
+            .read_dir(directory)
+            .await
+            .map_err(|e| remote_error("list", directory, e))?;
+
+        Ok(entries
+            .filter(|entry| entry.file_type().is_file())
+            .map(|entry| {
+                let metadata = entry.metadata();
+                RemoteFile {
+                    name: entry.file_name(),
+                    size: metadata.size.unwrap_or(0),
+                    modified: metadata
+                        .mtime
+                        .and_then(|mtime| DateTime::from_timestamp(mtime as i64, 0)),
+                }
+            })
+            .collect())
+    }
+
+    async fn download(&mut self, path: &str, destination: &Path) -> Result<u64> {
+        let mut remote = self
+            .sftp
+            .open(path)
+            .await
+            .map_err(|e| remote_error("open", path, e))?;
+        let mut local = tokio::fs::File::create(destination)
+            .await
+            .map_err(|e| remote_error("create", &destination.display().to_string(), e))?;
+
+        let copied = tokio::io::copy(&mut remote, &mut local)
+            .await
+            .map_err(|e| remote_error("download", path, e))?;
+        local
+            .flush()
+            .await
+            .map_err(|e| remote_error("download", path, e))?;
+
+        Ok(copied)
+    }
+
+    async fn exists(&mut self, path: &str) -> Result<bool> {
+        self.sftp
+            .try_exists(path)
+            .await
+            .map_err(|e| remote_error("stat", path, e))
+    }
+
+    async fn rename(&mut self, from: &str, to: &str) -> Result<()> {
+        self.sftp
+            .rename(from, to)
+            .await
+            .map_err(|e| remote_error("move", &format!("{} to {}", from, to), e))
+    }
+
+    async fn remove(&mut self, path: &str) -> Result<()> {
+        self.sftp
+            .remove_file(path)
+            .await
+            .map_err(|e| remote_error("delete", path, e))
+    }
+
+    async fn ensure_directory(&mut self, directory: &str) -> Result<()> {
+        if self.exists(directory).await? {
+            return Ok(());
+        }
+        self.sftp
+            .create_dir(directory)
+            .await
+            .map_err(|e| remote_error("create", directory, e))
+    }
+
+    async fn close(&mut self) {
+        let _ = self.sftp.close().await;
+        let _ = self
+            .session
+            .disconnect(Disconnect::ByApplication, "", "en")
+            .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprint_matches() {
+        let actual = "SHA256:nThbg6kXUpJWGl7E1IGOCspRomTxdCARLviKw6E5SY8";
+        assert!(fingerprint_matches(actual, actual));
+        assert!(fingerprint_matches(
+            " nThbg6kXUpJWGl7E1IGOCspRomTxdCARLviKw6E5SY8= ",
+            actual
+        ));
+        assert!(!fingerprint_matches(
+            "SHA256:uNiVztksCsDhcc0u9e8BujQXVUpKZIDTMczCvj3tD2s",
+            actual
+        ));
+    }
+}
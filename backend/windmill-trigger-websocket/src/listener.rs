@@ -23,9 +23,10 @@ use windmill_common::{
 use windmill_queue::PushArgsOwned;
 use windmill_trigger::filter::CompiledFilters;
 use windmill_trigger::listener::{update_rw_lock, ListeningTrigger};
+use windmill_trigger::metrics::ListenerEvent;
 use windmill_trigger::trigger_helpers::{
-    trigger_runnable, trigger_runnable_and_wait_for_raw_result,
-    trigger_runnable_and_wait_for_raw_result_with_error_ctx, TriggerJobArgs,
+    trigger_runnable_and_wait_for_raw_result,
+    trigger_runnable_and_wait_for_raw_result_with_error_ctx, trigger_runnables, TriggerJobArgs,
 };
 use windmill_trigger::Listener;
 
@@ -395,6 +396,8 @@ impl Listener for WebsocketTrigger {
                                                 ("url".to_string(), to_raw_value(&listening_trigger.trigger_config.url)),
                                             ]);
                                             let _ = self.handle_event(db, listening_trigger, text, trigger_info, return_message_channels.clone()).await;
+                                        } else {
+                                            self.record_event(listening_trigger, ListenerEvent::Filtered);
                                         }
                                     },
                                     a @ _ => {
@@ -442,22 +445,9 @@ impl Listener for WebsocketTrigger {
         let WebsocketConfig { url, .. } = trigger_config;
 
         trigger_info.insert("trigger_path".to_string(), to_raw_value(path));
-        let args = WebsocketTrigger::build_job_args(
-            &script_path,
-            *is_flow,
-            workspace_id,
-            db,
-            payload,
-            trigger_info,
-        )
-        .await;
-
-        let args = match args {
-            Ok(args) => args,
-            Err(err) => {
-                return Err(err);
-            }
-        };
+        let jobs_args = listening_trigger
+            .transform_or_build_job_args::<WebsocketTrigger>(db, payload, trigger_info)
+            .await?;
 
         let authed = listening_trigger.authed(db, "ws").await?;
 
@@ -472,74 +462,81 @@ impl Listener for WebsocketTrigger {
         let trigger = TriggerMetadata::new(Some(path.to_owned()), Self::JOB_TRIGGER_KIND);
         let can_return_message = trigger_config.can_return_message;
         if *suspended_mode || extra.is_none() || !can_return_message {
-            trigger_runnable(
+            trigger_runnables(
                 db,
                 None,
                 authed,
                 &workspace_id,
                 &script_path,
                 *is_flow,
-                args,
+                jobs_args,
                 retry,
                 error_handler_path,
                 error_handler_args,
                 format!("websocket_trigger/{}", listening_trigger.path),
-                None,
                 *suspended_mode,
                 trigger,
             )
             .await?;
-        } else if let Some(ReturnMessageChannels { send_message_tx, mut killpill_rx }) = extra {
-            let db_ = db.clone();
-            let url = url.to_owned();
-            let script_path = script_path.to_owned();
-            let is_flow = *is_flow;
-            let w_id = workspace_id.to_owned();
-            let retry = retry.cloned();
-            let error_handler_path = error_handler_path.map(|s| s.to_string());
-            let error_handler_args = error_handler_args.cloned();
-            let trigger_path = path.clone();
-            let can_return_error_result = trigger_config.can_return_error_result;
-            let handle_response_f = async move {
-                tokio::select! {
-                    _ = killpill_rx.recv() => {
-                        return;
-                    },
-                    result = trigger_runnable_and_wait_for_raw_result(
-                        &db_,
-                        None,
-                        authed,
-                        &w_id,
-                        &script_path,
-                        is_flow,
-                        args,
-                        retry.as_ref(),
-                        error_handler_path.as_deref(),
-                        error_handler_args.as_ref(),
-                        format!("websocket_trigger/{}", trigger_path),
-                        trigger,
-                    ) => {
-                        if let Ok((result, success)) = result {
-                            if !success && !can_return_error_result {
-                                return;
-                            }
-                            let result = result.get().to_owned();
-                            // only send the result if it's not null
-                            if result != "null" {
-                                tracing::info!("Sending job result to WebSocket {}", url);
-                                // if the `result` was just a single string, the below removes the surrounding quotes by parsing it as a string.
-                                // it falls back to the original serialized JSON if it doesn't work.
-                                let result = serde_json::from_str::<String>(result.as_str()).unwrap_or(result);
-                                if let Err(err) = send_message_tx.send(result).await {
-                                    report_critical_error(format!("Could not send runnable result to WebSocket {} because of error: {}", url, err), db_.clone(), Some(&w_id), None).await;
+            return Ok(());
+        }
+
+        for args in jobs_args {
+            if let Some(ReturnMessageChannels { send_message_tx, mut killpill_rx }) = extra.clone()
+            {
+                let db_ = db.clone();
+                let authed = authed.clone();
+                let trigger = trigger.clone();
+                let url = url.to_owned();
+                let script_path = script_path.to_owned();
+                let is_flow = *is_flow;
+                let w_id = workspace_id.to_owned();
+                let retry = retry.cloned();
+                let error_handler_path = error_handler_path.map(|s| s.to_string());
+                let error_handler_args = error_handler_args.cloned();
+                let trigger_path = path.clone();
+                let can_return_error_result = trigger_config.can_return_error_result;
+                let handle_response_f = async move {
+                    tokio::select! {
+                        _ = killpill_rx.recv() => {
+                            return;
+                        },
+                        result = trigger_runnable_and_wait_for_raw_result(
+                            &db_,
+                            None,
+                            authed,
+                            &w_id,
+                            &script_path,
+                            is_flow,
+                            args,
+                            retry.as_ref(),
+                            error_handler_path.as_deref(),
+                            error_handler_args.as_ref(),
+                            format!("websocket_trigger/{}", trigger_path),
+                            trigger,
+                        ) => {
+                            if let Ok((result, success)) = result {
+                                if !success && !can_return_error_result {
+                                    return;
+                                }
+                                let result = result.get().to_owned();
+                                // only send the result if it's not null
+                                if result != "null" {
+                                    tracing::info!("Sending job result to WebSocket {}", url);
+                                    // if the `result` was just a single string, the below removes the surrounding quotes by parsing it as a string.
+                                    // it falls back to the original serialized JSON if it doesn't work.
+                                    let result = serde_json::from_str::<String>(result.as_str()).unwrap_or(result);
+                                    if let Err(err) = send_message_tx.send(result).await {
+                                        report_critical_error(format!("Could not send runnable result to WebSocket {} because of error: {}", url, err), db_.clone(), Some(&w_id), None).await;
+                                    }
                                 }
                             }
                         }
-                    }
+                    };
                 };
-            };
 
-            tokio::spawn(handle_response_f);
+                tokio::spawn(handle_response_f);
+            }
         }
 
         Ok(())
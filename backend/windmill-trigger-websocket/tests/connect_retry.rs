@@ -88,6 +88,8 @@ fn trigger(url: String, trigger_mode: bool) -> ListeningTrigger<WebsocketConfig>
         trigger_mode,
         error_handling: None,
         suspended_mode: false,
+        payload_transform: None,
+        metrics: Default::default(),
     }
 }
 
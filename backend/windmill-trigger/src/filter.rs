@@ -1,23 +1,85 @@
+use regex::Regex;
 use serde::{
     de::{self, MapAccess, Visitor},
     Deserialize, Deserializer,
 };
 use serde_json::{value::RawValue, Value};
-use std::{collections::HashMap, fmt};
+use std::{cmp::Ordering, collections::HashMap, fmt};
+
+/// How a leaf filter tests the field it addresses against its `value`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FilterOp {
+    /// Equality, or superset when `value` is an object or array.
+    #[default]
+    Eq,
+    /// Numbers compare numerically, strings lexicographically (so ISO 8601 timestamps sort
+    /// chronologically). Any other pairing never matches.
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+    /// The field is a string the pattern matches somewhere; anchor it to match the whole.
+    Regex,
+    /// The field is present, or absent when `value` is `false`. Whatever it holds, null
+    /// included, counts as present.
+    Exists,
+    /// The field matches one of the elements of `value`, as [`FilterOp::Eq`] would.
+    In,
+    /// The field is an array with an element matching `value`, or a string containing it.
+    Contains,
+    /// The field is a string starting with `value`.
+    Prefix,
+}
+
+impl FilterOp {
+    fn name(self) -> &'static str {
+        match self {
+            FilterOp::Eq => "eq",
+            FilterOp::Gt => "gt",
+            FilterOp::Gte => "gte",
+            FilterOp::Lt => "lt",
+            FilterOp::Lte => "lte",
+            FilterOp::Regex => "regex",
+            FilterOp::Exists => "exists",
+            FilterOp::In => "in",
+            FilterOp::Contains => "contains",
+            FilterOp::Prefix => "prefix",
+        }
+    }
+}
 
 #[derive(Debug, Deserialize)]
 pub struct JsonFilter {
     pub key: String,
-    pub value: Value,
+    #[serde(default)]
+    pub op: FilterOp,
+    /// Optional only for [`FilterOp::Exists`]; every other op rejects a filter without it.
+    #[serde(default, deserialize_with = "present")]
+    pub value: Option<Value>,
 }
 
-/// Same comparison as [`JsonFilter`], but the field is addressed by a dotted path into
-/// nested objects. A separate field rather than dots in `key`, because a `key` containing
-/// a dot already means the top-level field spelled that way.
+/// Same comparison as [`JsonFilter`], but the field is addressed by a path into the message.
+/// A separate field rather than dots in `key`, because a `key` containing a dot already
+/// means the top-level field spelled that way.
+///
+/// Segments are separated by dots and each may be followed by array subscripts:
+/// `items[0].id` reads the first element, `items[*].id` any element, and a `*` segment
+/// any member of an object. A path through a wildcard matches when any of the values it
+/// spans does; wrap it in `none_of` with the opposite op to require all of them.
 #[derive(Debug, Deserialize)]
 pub struct PathFilter {
     pub path: String,
-    pub value: Value,
+    #[serde(default)]
+    pub op: FilterOp,
+    #[serde(default, deserialize_with = "present")]
+    pub value: Option<Value>,
+}
+
+/// Tells an explicit `"value": null` apart from a missing value, which the default
+/// `Option` deserialization would conflate.
+fn present<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Value>, D::Error> {
+    Value::deserialize(deserializer).map(Some)
 }
 
 /// Boolean group of nested filters, externally tagged (`{"any_of": [...]}`) so it is
@@ -38,44 +100,275 @@ pub enum Filter {
     Group(FilterGroup),
 }
 
-/// The scanned top-level key, and whatever is left to walk inside its value.
-fn split_path(path: &str) -> (&str, &str) {
-    path.split_once('.').unwrap_or((path, ""))
+#[derive(Debug, PartialEq)]
+enum Segment {
+    Field(String),
+    Index(usize),
+    AnyElement,
+    AnyMember,
 }
 
-/// Objects only: `Value::get` yields nothing for a string index into an array, so a path
-/// through one simply does not match rather than guessing an element.
-fn resolve<'v>(root: &'v Value, rest: &str) -> Option<&'v Value> {
-    let mut current = root;
-    if !rest.is_empty() {
-        for segment in rest.split('.') {
-            current = current.get(segment)?;
+/// Splits a [`PathFilter`] path into the top-level key, which the scan looks up, and the
+/// segments walked inside its value.
+fn parse_path(path: &str) -> Result<(String, Vec<Segment>), String> {
+    let mut root = None;
+    let mut segments = Vec::new();
+
+    for part in path.split('.') {
+        let (name, mut subscripts) = part.split_at(part.find('[').unwrap_or(part.len()));
+
+        // An empty segment addresses no field, so the filter could only ever reject everything
+        if name.is_empty() {
+            return Err(format!("path {:?} has an empty segment", path));
+        }
+        match (&root, name) {
+            // Every top-level key would have to be kept by the scan, one of which is
+            // usually the whole payload
+            (None, "*") => {
+                return Err(format!(
+                    "path {:?} starts with a wildcard; the top-level field must be named",
+                    path
+                ))
+            }
+            (None, _) => root = Some(name.to_string()),
+            (Some(_), "*") => segments.push(Segment::AnyMember),
+            (Some(_), _) => segments.push(Segment::Field(name.to_string())),
+        }
+
+        if name.contains(']') {
+            return Err(format!(
+                "path {:?} has an unclosed or stray subscript",
+                path
+            ));
         }
+
+        while !subscripts.is_empty() {
+            let (subscript, rest) = subscripts
+                .strip_prefix('[')
+                .and_then(|inner| inner.split_once(']'))
+                .ok_or_else(|| format!("path {:?} has an unclosed or stray subscript", path))?;
+            segments.push(match subscript {
+                "*" => Segment::AnyElement,
+                index => Segment::Index(index.parse().map_err(|_| {
+                    format!(
+                        "path {:?} has subscript [{}]; expected an index or *",
+                        path, index
+                    )
+                })?),
+            });
+            subscripts = rest;
+        }
+    }
+
+    // `split` yields at least one part, and an empty one has already been rejected
+    Ok((root.unwrap_or_default(), segments))
+}
+
+/// Whether `test` holds for any value `segments` leads to from `current`. A segment that
+/// does not fit the value it is applied to, such as a field name into an array, leads
+/// nowhere rather than guessing an element.
+fn any_at(current: &Value, segments: &[Segment], test: &dyn Fn(&Value) -> bool) -> bool {
+    let Some((segment, rest)) = segments.split_first() else {
+        return test(current);
+    };
+    match segment {
+        Segment::Field(name) => current
+            .as_object()
+            .and_then(|object| object.get(name))
+            .is_some_and(|next| any_at(next, rest, test)),
+        Segment::Index(index) => current
+            .as_array()
+            .and_then(|array| array.get(*index))
+            .is_some_and(|next| any_at(next, rest, test)),
+        Segment::AnyElement => current
+            .as_array()
+            .is_some_and(|array| array.iter().any(|next| any_at(next, rest, test))),
+        Segment::AnyMember => current
+            .as_object()
+            .is_some_and(|object| object.values().any(|next| any_at(next, rest, test))),
     }
-    Some(current)
 }
 
-/// Filters prepared for repeated evaluation against a stream of messages. The set of
-/// top-level keys the whole tree references is computed once, so each message is scanned
-/// in a single pass instead of once per leaf filter.
+/// A leaf's op and value, checked and prepared (regex compiled) once per trigger.
+#[derive(Debug)]
+enum Condition {
+    Superset(Value),
+    Compare { op: FilterOp, bound: Value },
+    Regex(Regex),
+    Exists(bool),
+    In(Vec<Value>),
+    Contains(Value),
+    Prefix(String),
+}
+
+impl Condition {
+    fn new(op: FilterOp, value: Option<Value>) -> Result<Self, String> {
+        let expects = |what: &str| Err(format!("op {} expects {} as value", op.name(), what));
+
+        let Some(value) = value else {
+            return match op {
+                FilterOp::Exists => Ok(Condition::Exists(true)),
+                _ => Err(format!("op {} needs a value", op.name())),
+            };
+        };
+
+        match (op, value) {
+            (FilterOp::Eq, value) => Ok(Condition::Superset(value)),
+            (FilterOp::Contains, value) => Ok(Condition::Contains(value)),
+            (FilterOp::Exists, Value::Bool(present)) => Ok(Condition::Exists(present)),
+            (FilterOp::Exists, _) => expects("a boolean, or none"),
+            (
+                FilterOp::Gt | FilterOp::Gte | FilterOp::Lt | FilterOp::Lte,
+                bound @ (Value::Number(_) | Value::String(_)),
+            ) => Ok(Condition::Compare { op, bound }),
+            (FilterOp::Gt | FilterOp::Gte | FilterOp::Lt | FilterOp::Lte, _) => {
+                expects("a number or a string")
+            }
+            (FilterOp::Regex, Value::String(pattern)) => Regex::new(&pattern)
+                .map(Condition::Regex)
+                .map_err(|err| format!("invalid regex {:?}: {}", pattern, err)),
+            (FilterOp::Regex, _) => expects("a string"),
+            (FilterOp::In, Value::Array(candidates)) => Ok(Condition::In(candidates)),
+            (FilterOp::In, _) => expects("an array"),
+            (FilterOp::Prefix, Value::String(prefix)) => Ok(Condition::Prefix(prefix)),
+            (FilterOp::Prefix, _) => expects("a string"),
+        }
+    }
+
+    /// Whether a value the path reached satisfies the condition. Presence is settled by
+    /// reaching it at all, see [`Leaf::eval`].
+    fn holds(&self, found: &Value) -> bool {
+        match self {
+            Condition::Superset(value) => is_superset(found, value),
+            Condition::Compare { op, bound } => matches!(
+                (op, compare(found, bound)),
+                (FilterOp::Gt, Some(Ordering::Greater))
+                    | (FilterOp::Gte, Some(Ordering::Greater | Ordering::Equal))
+                    | (FilterOp::Lt, Some(Ordering::Less))
+                    | (FilterOp::Lte, Some(Ordering::Less | Ordering::Equal))
+            ),
+            Condition::Regex(regex) => found.as_str().is_some_and(|text| regex.is_match(text)),
+            Condition::Exists(_) => true,
+            Condition::In(candidates) => candidates
+                .iter()
+                .any(|candidate| is_superset(found, candidate)),
+            Condition::Contains(value) => match (found, value) {
+                (Value::Array(items), value) => items.iter().any(|item| is_superset(item, value)),
+                (Value::String(text), Value::String(needle)) => text.contains(needle.as_str()),
+                _ => false,
+            },
+            Condition::Prefix(prefix) => found
+                .as_str()
+                .is_some_and(|text| text.starts_with(prefix.as_str())),
+        }
+    }
+}
+
+fn compare(found: &Value, bound: &Value) -> Option<Ordering> {
+    match (found, bound) {
+        (Value::Number(found), Value::Number(bound)) => {
+            found.as_f64()?.partial_cmp(&bound.as_f64()?)
+        }
+        (Value::String(found), Value::String(bound)) => Some(found.as_str().cmp(bound)),
+        _ => None,
+    }
+}
+
+#[derive(Debug)]
+struct Leaf {
+    key: String,
+    segments: Vec<Segment>,
+    condition: Condition,
+}
+
+impl Leaf {
+    fn eval(&self, values: &HashMap<&str, &RawValue>) -> bool {
+        let Some(raw) = values.get(self.key.as_str()) else {
+            return matches!(self.condition, Condition::Exists(false));
+        };
+
+        if let (Condition::Exists(expected), []) = (&self.condition, self.segments.as_slice()) {
+            return *expected;
+        }
+
+        // Parsed here rather than during the scan so that `any`/`all` short-circuiting keeps
+        // a large field the verdict never depends on from being materialized at all.
+        let Ok(found) = serde_json::from_str::<Value>(raw.get()) else {
+            return false;
+        };
+        match &self.condition {
+            Condition::Exists(expected) => any_at(&found, &self.segments, &|_| true) == *expected,
+            condition => any_at(&found, &self.segments, &|at| condition.holds(at)),
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Node {
+    Leaf(Leaf),
+    AnyOf(Vec<Node>),
+    AllOf(Vec<Node>),
+    NoneOf(Vec<Node>),
+}
+
+/// `None` for a group left with no criterion. Such a group cannot evaluate to a constant:
+/// `true` makes an `or` list accept every message, `false` mutes an `and` list. Dropping
+/// it instead leaves its siblings in force, which is what a group left empty in the editor
+/// should mean.
+fn compile(filter: Filter) -> Result<Option<Node>, String> {
+    let (key, segments, op, value) = match filter {
+        Filter::JsonFilter(JsonFilter { key, op, value }) => (key, Vec::new(), op, value),
+        Filter::PathFilter(PathFilter { path, op, value }) => {
+            let (key, segments) = parse_path(&path)?;
+            (key, segments, op, value)
+        }
+        Filter::Group(group) => {
+            let (rebuild, nested): (fn(Vec<Node>) -> Node, _) = match group {
+                FilterGroup::AnyOf(nested) => (Node::AnyOf, nested),
+                FilterGroup::AllOf(nested) => (Node::AllOf, nested),
+                FilterGroup::NoneOf(nested) => (Node::NoneOf, nested),
+            };
+            let nested = compile_all(nested)?;
+            return Ok((!nested.is_empty()).then(|| rebuild(nested)));
+        }
+    };
+    let condition = Condition::new(op, value)?;
+    Ok(Some(Node::Leaf(Leaf { key, segments, condition })))
+}
+
+fn compile_all(filters: Vec<Filter>) -> Result<Vec<Node>, String> {
+    filters
+        .into_iter()
+        .filter_map(|filter| compile(filter).transpose())
+        .collect()
+}
+
+/// Filters prepared for repeated evaluation against a stream of messages. Paths are split,
+/// values checked against their op and regexes compiled once, and the set of top-level
+/// keys the whole tree references is computed so each message is scanned in a single pass
+/// instead of once per leaf filter.
 #[derive(Debug, Default)]
 pub struct CompiledFilters {
-    filters: Vec<Filter>,
+    filters: Vec<Node>,
     use_or_logic: bool,
     keys: Vec<String>,
 }
 
 impl CompiledFilters {
-    pub fn new(filters: Vec<Filter>, use_or_logic: bool) -> Self {
-        let filters = drop_empty_groups(filters);
+    pub fn new(filters: Vec<Filter>, use_or_logic: bool) -> windmill_common::error::Result<Self> {
+        let filters = compile_all(filters).map_err(windmill_common::error::Error::BadRequest)?;
+        Ok(Self::from_nodes(filters, use_or_logic))
+    }
+
+    fn from_nodes(filters: Vec<Node>, use_or_logic: bool) -> Self {
         let mut keys = Vec::new();
         collect_keys(&filters, &mut keys);
         Self { filters, use_or_logic, keys }
     }
 
     /// Build from the raw JSON of each filter as stored in the trigger config. An entry
-    /// that fails to parse is skipped rather than dropping the other filters, but it
-    /// widens what the trigger accepts, so it is reported.
+    /// that fails to parse or compile is skipped rather than dropping the other filters,
+    /// but it widens what the trigger accepts, so it is reported.
     pub fn parse<'a>(
         raw_filters: impl IntoIterator<Item = &'a str>,
         use_or_logic: bool,
@@ -83,20 +376,25 @@ impl CompiledFilters {
     ) -> Self {
         let filters = raw_filters
             .into_iter()
-            .filter_map(|raw| match serde_json::from_str::<Filter>(raw) {
-                Ok(filter) => Some(filter),
-                Err(err) => {
-                    tracing::error!(
-                        "Ignoring unparseable filter of trigger {}: {} ({})",
-                        trigger_path,
-                        raw,
-                        err
-                    );
-                    None
+            .filter_map(|raw| {
+                let compiled = serde_json::from_str::<Filter>(raw)
+                    .map_err(|err| err.to_string())
+                    .and_then(compile);
+                match compiled {
+                    Ok(node) => node,
+                    Err(err) => {
+                        tracing::error!(
+                            "Ignoring unparseable filter of trigger {}: {} ({})",
+                            trigger_path,
+                            raw,
+                            err
+                        );
+                        None
+                    }
                 }
             })
             .collect();
-        Self::new(filters, use_or_logic)
+        Self::from_nodes(filters, use_or_logic)
     }
 
     pub fn is_empty(&self) -> bool {
@@ -174,85 +472,44 @@ fn validate_filter(filter: &Value, path: &str) -> windmill_common::error::Result
 
     let parsed = serde_json::from_value::<Filter>(filter.clone()).map_err(|err| {
         windmill_common::error::Error::BadRequest(format!(
-            "{} is neither a {{key, value}} / {{path, value}} criterion nor an any_of/all_of/none_of group: {}",
+            "{} is neither a {{key, op, value}} / {{path, op, value}} criterion nor an any_of/all_of/none_of group: {}",
             path, err
         ))
     })?;
 
-    // An empty segment addresses no field, so the filter could only ever reject everything
-    if let Filter::PathFilter(PathFilter { path: dotted, .. }) = &parsed {
-        if dotted.split('.').any(|segment| segment.is_empty()) {
-            return Err(windmill_common::error::Error::BadRequest(format!(
-                "{}: path {:?} has an empty segment",
-                path, dotted
-            )));
-        }
-    }
+    // A leaf, so this only checks its path, op and value
+    compile(parsed)
+        .map_err(|err| windmill_common::error::Error::BadRequest(format!("{}: {}", path, err)))?;
 
     Ok(())
 }
 
-/// A group with no criterion cannot evaluate to a constant: `true` makes an `or` list
-/// accept every message, `false` mutes an `and` list. Dropping it instead leaves its
-/// siblings in force, which is what a group left empty in the editor should mean.
-fn drop_empty_groups(filters: Vec<Filter>) -> Vec<Filter> {
-    filters
-        .into_iter()
-        .filter_map(|filter| {
-            let (rebuild, nested): (fn(Vec<Filter>) -> FilterGroup, _) = match filter {
-                Filter::Group(FilterGroup::AnyOf(nested)) => (FilterGroup::AnyOf, nested),
-                Filter::Group(FilterGroup::AllOf(nested)) => (FilterGroup::AllOf, nested),
-                Filter::Group(FilterGroup::NoneOf(nested)) => (FilterGroup::NoneOf, nested),
-                leaf => return Some(leaf),
-            };
-            let nested = drop_empty_groups(nested);
-            (!nested.is_empty()).then(|| Filter::Group(rebuild(nested)))
-        })
-        .collect()
-}
-
 fn push_key(keys: &mut Vec<String>, key: &str) {
     if !keys.iter().any(|k| k == key) {
         keys.push(key.to_string());
     }
 }
 
-fn collect_keys(filters: &[Filter], keys: &mut Vec<String>) {
+fn collect_keys(filters: &[Node], keys: &mut Vec<String>) {
     for filter in filters {
         match filter {
-            Filter::JsonFilter(JsonFilter { key, .. }) => push_key(keys, key),
-            Filter::PathFilter(PathFilter { path, .. }) => push_key(keys, split_path(path).0),
-            Filter::Group(
-                FilterGroup::AnyOf(nested)
-                | FilterGroup::AllOf(nested)
-                | FilterGroup::NoneOf(nested),
-            ) => collect_keys(nested, keys),
+            Node::Leaf(leaf) => push_key(keys, &leaf.key),
+            Node::AnyOf(nested) | Node::AllOf(nested) | Node::NoneOf(nested) => {
+                collect_keys(nested, keys)
+            }
         }
     }
 }
 
 /// `filters` is never empty: the top level is short-circuited by [`CompiledFilters::matches`],
-/// and [`drop_empty_groups`] removes empty groups.
-fn eval_all(filters: &[Filter], use_or_logic: bool, values: &HashMap<&str, &RawValue>) -> bool {
-    let eval = |filter: &Filter| match filter {
-        // Parsed here rather than during the scan so that `any`/`all` short-circuiting keeps
-        // a large field the verdict never depends on from being materialized at all.
-        Filter::JsonFilter(JsonFilter { key, value }) => values
-            .get(key.as_str())
-            .and_then(|raw| serde_json::from_str::<Value>(raw.get()).ok())
-            .map_or(false, |found| is_superset(&found, value)),
-        Filter::PathFilter(PathFilter { path, value }) => {
-            let (root, rest) = split_path(path);
-            values
-                .get(root)
-                .and_then(|raw| serde_json::from_str::<Value>(raw.get()).ok())
-                .and_then(|found| resolve(&found, rest).map(|at| is_superset(at, value)))
-                .unwrap_or(false)
-        }
-        Filter::Group(FilterGroup::AnyOf(nested)) => eval_all(nested, true, values),
-        Filter::Group(FilterGroup::AllOf(nested)) => eval_all(nested, false, values),
+/// and [`compile`] drops empty groups.
+fn eval_all(filters: &[Node], use_or_logic: bool, values: &HashMap<&str, &RawValue>) -> bool {
+    let eval = |filter: &Node| match filter {
+        Node::Leaf(leaf) => leaf.eval(values),
+        Node::AnyOf(nested) => eval_all(nested, true, values),
+        Node::AllOf(nested) => eval_all(nested, false, values),
         // A key the message does not carry satisfies a negation: nothing there can match.
-        Filter::Group(FilterGroup::NoneOf(nested)) => !eval_all(nested, true, values),
+        Node::NoneOf(nested) => !eval_all(nested, true, values),
     };
 
     if use_or_logic {
@@ -325,7 +582,9 @@ mod tests {
 
     fn matches(payload: &str, filters: serde_json::Value, use_or_logic: bool) -> bool {
         let filters: Vec<Filter> = serde_json::from_value(filters).unwrap();
-        CompiledFilters::new(filters, use_or_logic).matches(payload)
+        CompiledFilters::new(filters, use_or_logic)
+            .unwrap()
+            .matches(payload)
     }
 
     #[test]
@@ -513,13 +772,270 @@ mod tests {
     }
 
     #[test]
-    fn test_path_does_not_traverse_arrays() {
-        // Deliberately unsupported for now: an element index is not implied
+    fn test_path_traverses_arrays_only_through_subscripts() {
+        let payload = r#"{"items": [{"id": 1}, {"id": 2}]}"#;
+        // An element index is not implied by a plain field segment
         assert!(!matches(
-            r#"{"items": [{"id": 1}]}"#,
+            payload,
             json!([{"path": "items.id", "value": 1}]),
             false
         ));
+        assert!(matches(
+            payload,
+            json!([{"path": "items[1].id", "value": 2}]),
+            false
+        ));
+        assert!(!matches(
+            payload,
+            json!([{"path": "items[0].id", "value": 2}]),
+            false
+        ));
+        // out of bounds is a miss
+        assert!(!matches(
+            payload,
+            json!([{"path": "items[2].id", "value": 1}]),
+            false
+        ));
+    }
+
+    #[test]
+    fn test_wildcards_match_when_any_value_does() {
+        let payload = r#"{"items": [{"id": 1}, {"id": 2}], "labels": {"a": "x", "b": "y"}}"#;
+        assert!(matches(
+            payload,
+            json!([{"path": "items[*].id", "value": 2}]),
+            false
+        ));
+        assert!(!matches(
+            payload,
+            json!([{"path": "items[*].id", "value": 3}]),
+            false
+        ));
+        assert!(matches(
+            payload,
+            json!([{"path": "labels.*", "value": "y"}]),
+            false
+        ));
+        // "every item" is a none_of over the opposite condition
+        assert!(matches(
+            payload,
+            json!([{"none_of": [{"path": "items[*].id", "op": "gt", "value": 2}]}]),
+            false
+        ));
+        assert!(!matches(
+            payload,
+            json!([{"none_of": [{"path": "items[*].id", "op": "gt", "value": 1}]}]),
+            false
+        ));
+    }
+
+    #[test]
+    fn test_nested_subscripts() {
+        assert!(matches(
+            r#"{"grid": [[1, 2], [3, 4]]}"#,
+            json!([{"path": "grid[1][0]", "value": 3}]),
+            false
+        ));
+    }
+
+    // --- operators ---
+
+    #[test]
+    fn test_comparison_operators() {
+        let payload = r#"{"n": 5, "at": "2024-05-01T10:00:00Z"}"#;
+        for (op, value, expected) in [
+            ("gt", json!(4), true),
+            ("gt", json!(5), false),
+            ("gte", json!(5), true),
+            ("lt", json!(5.5), true),
+            ("lte", json!(4.9), false),
+        ] {
+            assert_eq!(
+                matches(
+                    payload,
+                    json!([{"key": "n", "op": op, "value": value}]),
+                    false
+                ),
+                expected,
+                "n {} {}",
+                op,
+                value
+            );
+        }
+        // strings compare lexicographically, which orders ISO 8601 timestamps
+        assert!(matches(
+            payload,
+            json!([{"key": "at", "op": "gte", "value": "2024-01-01T00:00:00Z"}]),
+            false
+        ));
+        // mismatched types never match, in either direction
+        assert!(!matches(
+            payload,
+            json!([{"key": "n", "op": "gt", "value": "1"}]),
+            false
+        ));
+        assert!(!matches(
+            payload,
+            json!([{"key": "n", "op": "lte", "value": "9"}]),
+            false
+        ));
+    }
+
+    #[test]
+    fn test_regex_and_prefix() {
+        let payload = r#"{"ref": "refs/heads/release-1.2", "n": 1}"#;
+        assert!(matches(
+            payload,
+            json!([{"key": "ref", "op": "regex", "value": "release-\\d+"}]),
+            false
+        ));
+        assert!(!matches(
+            payload,
+            json!([{"key": "ref", "op": "regex", "value": "^release"}]),
+            false
+        ));
+        assert!(matches(
+            payload,
+            json!([{"key": "ref", "op": "prefix", "value": "refs/heads/"}]),
+            false
+        ));
+        // neither applies to a non-string
+        assert!(!matches(
+            payload,
+            json!([{"key": "n", "op": "regex", "value": ".*"}]),
+            false
+        ));
+        assert!(!matches(
+            payload,
+            json!([{"key": "n", "op": "prefix", "value": "1"}]),
+            false
+        ));
+    }
+
+    #[test]
+    fn test_exists() {
+        let payload = r#"{"a": null, "b": {"c": 1}}"#;
+        assert!(matches(
+            payload,
+            json!([{"key": "a", "op": "exists"}]),
+            false
+        ));
+        assert!(!matches(
+            payload,
+            json!([{"key": "z", "op": "exists"}]),
+            false
+        ));
+        assert!(matches(
+            payload,
+            json!([{"key": "z", "op": "exists", "value": false}]),
+            false
+        ));
+        assert!(matches(
+            payload,
+            json!([{"path": "b.c", "op": "exists"}]),
+            false
+        ));
+        assert!(matches(
+            payload,
+            json!([{"path": "b.d", "op": "exists", "value": false}]),
+            false
+        ));
+    }
+
+    #[test]
+    fn test_in_and_contains() {
+        let payload = r#"{"status": "open", "tags": ["bug", "p1"], "title": "crash on save"}"#;
+        assert!(matches(
+            payload,
+            json!([{"key": "status", "op": "in", "value": ["open", "reopened"]}]),
+            false
+        ));
+        assert!(!matches(
+            payload,
+            json!([{"key": "status", "op": "in", "value": ["closed"]}]),
+            false
+        ));
+        assert!(matches(
+            payload,
+            json!([{"key": "tags", "op": "contains", "value": "p1"}]),
+            false
+        ));
+        assert!(!matches(
+            payload,
+            json!([{"key": "tags", "op": "contains", "value": "p2"}]),
+            false
+        ));
+        assert!(matches(
+            payload,
+            json!([{"key": "title", "op": "contains", "value": "crash"}]),
+            false
+        ));
+    }
+
+    #[test]
+    fn test_explicit_eq_and_null_value() {
+        assert!(matches(
+            r#"{"a": null}"#,
+            json!([{"key": "a", "op": "eq", "value": null}]),
+            false
+        ));
+        assert!(!matches(
+            r#"{"a": 1}"#,
+            json!([{"key": "a", "value": null}]),
+            false
+        ));
+    }
+
+    #[test]
+    fn test_validate_rejects_values_the_op_cannot_use() {
+        for bad in [
+            json!({"key": "a", "op": "gt", "value": [1]}),
+            json!({"key": "a", "op": "regex", "value": "("}),
+            json!({"key": "a", "op": "in", "value": "open"}),
+            json!({"key": "a", "op": "prefix", "value": 1}),
+            json!({"key": "a", "op": "exists", "value": "yes"}),
+            json!({"key": "a", "op": "contains"}),
+            json!({"key": "a", "op": "like", "value": "x"}),
+        ] {
+            assert!(
+                CompiledFilters::validate(std::slice::from_ref(&bad)).is_err(),
+                "{} should be rejected",
+                bad
+            );
+        }
+        assert!(CompiledFilters::validate(&[json!({"key": "a", "op": "exists"})]).is_ok());
+    }
+
+    #[test]
+    fn test_validate_rejects_malformed_subscripts() {
+        for path in ["*.a", "a[", "a[x]", "a[0]b", "a]"] {
+            assert!(
+                CompiledFilters::validate(&[json!({"path": path, "value": 1})]).is_err(),
+                "path {:?} should be rejected",
+                path
+            );
+        }
+        for path in ["a[0]", "a[*].b", "a.*.b", "a[0][1]"] {
+            assert!(
+                CompiledFilters::validate(&[json!({"path": path, "value": 1})]).is_ok(),
+                "path {:?} should be accepted",
+                path
+            );
+        }
+    }
+
+    #[test]
+    fn test_listener_skips_an_entry_that_does_not_compile() {
+        let filters = CompiledFilters::parse(
+            [
+                r#"{"key": "a", "op": "regex", "value": "("}"#,
+                r#"{"key": "b", "value": 2}"#,
+            ],
+            false,
+            "u/admin/trigger",
+        );
+        assert!(filters.matches(r#"{"b": 2}"#));
+        assert!(!filters.matches(r#"{"b": 3}"#));
     }
 
     #[test]
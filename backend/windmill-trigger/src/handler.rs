@@ -6,7 +6,10 @@
  * LICENSE-AGPL for a copy of the license.
  */
 
-use crate::types::{HasPath, StandardTriggerQuery, TriggerData, TriggerMode};
+use crate::{
+    transform::PayloadTransform,
+    types::{HasPath, StandardTriggerQuery, TriggerData, TriggerMode},
+};
 use async_trait::async_trait;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use sql_builder::{bind::Bind, SqlBuilder};
@@ -199,6 +202,7 @@ pub trait TriggerCrud: Send + Sync + 'static {
             "extra_perms",
             "mode",
             "labels",
+            "payload_transform",
         ];
 
         if Self::SUPPORTS_SERVER_STATE {
@@ -413,6 +417,7 @@ pub trait TriggerCrud: Send + Sync + 'static {
             "extra_perms",
             "mode",
             "labels",
+            "payload_transform",
         ];
 
         if Self::SUPPORTS_SERVER_STATE {
@@ -475,6 +480,27 @@ pub trait TriggerCrud: Send + Sync + 'static {
     }
 }
 
+/// Kind-agnostic, like `labels`: every trigger table carries the column, so the per-kind
+/// `create_trigger` / `update_trigger` impls do not each have to persist it.
+async fn set_payload_transform<T: TriggerCrud>(
+    tx: &mut PgConnection,
+    workspace_id: &str,
+    path: &str,
+    payload_transform: Option<String>,
+) -> Result<()> {
+    // SAFETY: T::TABLE_NAME is a compile-time constant.
+    sqlx::query(&format!(
+        "UPDATE {} SET payload_transform = $1 WHERE workspace_id = $2 AND path = $3",
+        T::TABLE_NAME
+    ))
+    .bind(payload_transform)
+    .bind(workspace_id)
+    .bind(path)
+    .execute(&mut *tx)
+    .await?;
+    Ok(())
+}
+
 /// Append this mutation to `trigger_history`, diffing the row at `path` against
 /// `before`.
 ///
@@ -569,6 +595,8 @@ async fn create_trigger<T: TriggerCrud>(
 
     let new_path = new_trigger.base.path.clone();
     let labels = new_trigger.base.labels.clone();
+    let payload_transform =
+        PayloadTransform::check(new_trigger.base.payload_transform.as_deref()).await?;
 
     // If the caller did not preserve a value but the user can preserve, fall back
     // to the folder's default_permissioned_as rule (create-time only).
@@ -617,6 +645,10 @@ async fn create_trigger<T: TriggerCrud>(
         .await?;
     }
 
+    if payload_transform.is_some() {
+        set_payload_transform::<T>(&mut *tx, &workspace_id, &new_path, payload_transform).await?;
+    }
+
     record_trigger_history::<T>(
         &mut *tx,
         &authed,
@@ -846,6 +878,8 @@ async fn update_trigger<T: TriggerCrud>(
 
     let new_path = edit_trigger.base.path.to_string();
     let labels = edit_trigger.base.labels.clone();
+    let payload_transform =
+        PayloadTransform::check(edit_trigger.base.payload_transform.as_deref()).await?;
 
     // Reject a forged superadmin run identity in a preserved permissioned_as
     // (the sentinel guard; a trigger's email is derived from it at execution).
@@ -879,6 +913,9 @@ async fn update_trigger<T: TriggerCrud>(
         .await?;
     }
 
+    // Unlike labels, an absent transform is written too: it is how one is removed.
+    set_payload_transform::<T>(&mut *tx, &workspace_id, &new_path, payload_transform).await?;
+
     // Recorded at the new path, so a rename reads as one event there with
     // `path` among the changed fields rather than a delete plus a create.
     record_trigger_history::<T>(
@@ -943,6 +980,7 @@ async fn update_trigger<T: TriggerCrud>(
     // On rename the old-path draft orphans (no SQL FK); clear the deployer's own
     // (+ legacy NULL) there, teammates keep theirs (StaleDraftModal). See scripts.rs.
     if path != new_path {
+        forget_trigger_metrics::<T>(&db, &workspace_id, path).await;
         delete_own_draft_for_path(
             &db,
             &workspace_id,
@@ -1057,9 +1095,26 @@ async fn delete_trigger<T: TriggerCrud>(
     // Trigger gone for everyone: wipe ALL users' drafts at this path; see scripts.rs.
     delete_all_drafts_for_path(&db, &workspace_id, T::user_draft_item_kind(), path).await?;
 
+    forget_trigger_metrics::<T>(&db, &workspace_id, path).await;
+
     Ok(format!("Trigger '{}' deleted", path))
 }
 
+/// The listener drops the metrics of a trigger once it stops, this covers a trigger no server
+/// was listening to. Through the admin pool, users only being able to read them.
+async fn forget_trigger_metrics<T: TriggerCrud>(db: &DB, workspace_id: &str, path: &str) {
+    if let Err(err) =
+        crate::metrics::delete_trigger_metrics(db, T::TRIGGER_TYPE, workspace_id, path).await
+    {
+        tracing::warn!(
+            "Error deleting metrics of {} trigger {}: {:?}",
+            T::TRIGGER_TYPE,
+            path,
+            err
+        );
+    }
+}
+
 async fn exists_trigger<T: TriggerCrud>(
     Extension(handler): Extension<Arc<T>>,
     authed: ApiAuthed,
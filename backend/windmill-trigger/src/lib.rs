@@ -11,6 +11,8 @@ pub mod filter;
 pub mod global_handler;
 pub mod handler;
 pub mod listener;
+pub mod metrics;
+pub mod transform;
 pub mod trigger_helpers;
 pub mod types;
 
@@ -3,7 +3,9 @@ use std::{collections::HashMap, fmt::Debug, sync::Arc};
 use crate::{
     capture::insert_capture_payload,
     handler::TriggerCrud,
-    trigger_helpers::{trigger_runnable, TriggerJobArgs},
+    metrics::{ListenerEvent, ListenerMetrics},
+    transform::PayloadTransform,
+    trigger_helpers::{trigger_runnables, TriggerJobArgs},
     types::{Trigger, TriggerErrorHandling, TriggerMode},
 };
 use async_trait::async_trait;
@@ -24,6 +26,7 @@ use windmill_common::{
     worker::to_raw_value,
     DB, INSTANCE_NAME,
 };
+use windmill_queue::PushArgsOwned;
 
 #[allow(unused)]
 #[async_trait]
@@ -70,6 +73,7 @@ pub trait Listener: TriggerCrud + TriggerJobArgs {
             "error_handler_args",
             "retry",
             "labels",
+            "payload_transform",
         ];
 
         fields.extend_from_slice(Self::ADDITIONAL_SELECT_FIELDS);
@@ -106,6 +110,8 @@ pub trait Listener: TriggerCrud + TriggerJobArgs {
                 error_handling: Some(trigger.error_handling),
                 trigger_mode: true,
                 suspended_mode: trigger.base.mode == TriggerMode::Suspended,
+                payload_transform: trigger.base.payload_transform,
+                metrics: Default::default(),
             })
             .collect_vec();
 
@@ -158,6 +164,8 @@ pub trait Listener: TriggerCrud + TriggerJobArgs {
                     is_flow: capture.is_flow,
                     error_handling: None,
                     suspended_mode: false,
+                    payload_transform: None,
+                    metrics: Default::default(),
                 }
             })
             .collect_vec();
@@ -193,6 +201,7 @@ pub trait Listener: TriggerCrud + TriggerJobArgs {
             {
                 return;
             }
+            self.flush_metrics(db, listening_trigger).await;
             tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
         }
     }
@@ -517,6 +526,117 @@ pub trait Listener: TriggerCrud + TriggerJobArgs {
         }
     }
 
+    /// Counts an event of a trigger, captures not being measured.
+    fn record_event(
+        &self,
+        listening_trigger: &ListeningTrigger<Self::TriggerConfig>,
+        event: ListenerEvent,
+    ) {
+        if !listening_trigger.trigger_mode {
+            return;
+        }
+        listening_trigger.metrics.record(event);
+        #[cfg(feature = "prometheus")]
+        crate::metrics::export_event(
+            &Self::TRIGGER_KIND.to_key(),
+            &listening_trigger.workspace_id,
+            &listening_trigger.path,
+            event,
+        );
+    }
+
+    /// For protocols that expose how far behind the consumer is, `None` when it cannot
+    /// be known.
+    fn record_consumer_lag(
+        &self,
+        listening_trigger: &ListeningTrigger<Self::TriggerConfig>,
+        lag: Option<i64>,
+    ) {
+        if !listening_trigger.trigger_mode {
+            return;
+        }
+        listening_trigger.metrics.set_consumer_lag(lag);
+        #[cfg(feature = "prometheus")]
+        crate::metrics::export_consumer_lag(
+            &Self::TRIGGER_KIND.to_key(),
+            &listening_trigger.workspace_id,
+            &listening_trigger.path,
+            lag,
+        );
+    }
+
+    async fn flush_metrics(
+        &self,
+        db: &DB,
+        listening_trigger: &ListeningTrigger<Self::TriggerConfig>,
+    ) {
+        if !listening_trigger.trigger_mode {
+            return;
+        }
+        if let Err(err) = listening_trigger
+            .metrics
+            .flush(
+                db,
+                &Self::TRIGGER_KIND.to_key(),
+                &listening_trigger.workspace_id,
+                &listening_trigger.path,
+            )
+            .await
+        {
+            tracing::warn!(
+                "Error saving metrics of {} trigger {}: {:?}",
+                Self::TRIGGER_KIND,
+                listening_trigger.path,
+                err
+            );
+        }
+    }
+
+    /// Once the listener stopped. The series of a trigger are exported by the server that
+    /// listens to it, and its persisted metrics go once it no longer exists at its path.
+    async fn forget_metrics(
+        &self,
+        db: &DB,
+        listening_trigger: &ListeningTrigger<Self::TriggerConfig>,
+    ) {
+        if !listening_trigger.trigger_mode {
+            return;
+        }
+        #[cfg(feature = "prometheus")]
+        crate::metrics::remove_series(
+            &Self::TRIGGER_KIND.to_key(),
+            &listening_trigger.workspace_id,
+            &listening_trigger.path,
+        );
+
+        // SAFETY: Self::TABLE_NAME is a compile-time constant, not user input.
+        let exists = sqlx::query_scalar::<_, bool>(&format!(
+            "SELECT EXISTS(SELECT 1 FROM {} WHERE workspace_id = $1 AND path = $2)",
+            Self::TABLE_NAME
+        ))
+        .bind(&listening_trigger.workspace_id)
+        .bind(&listening_trigger.path)
+        .fetch_one(db)
+        .await;
+        if let Ok(false) = exists {
+            if let Err(err) = crate::metrics::delete_trigger_metrics(
+                db,
+                &Self::TRIGGER_KIND.to_key(),
+                &listening_trigger.workspace_id,
+                &listening_trigger.path,
+            )
+            .await
+            {
+                tracing::warn!(
+                    "Error deleting metrics of {} trigger {}: {:?}",
+                    Self::TRIGGER_KIND,
+                    listening_trigger.path,
+                    err
+                );
+            }
+        }
+    }
+
     async fn handle_trigger(
         &self,
         db: &DB,
@@ -529,15 +649,9 @@ pub trait Listener: TriggerCrud + TriggerJobArgs {
             "trigger_path".to_string(),
             to_raw_value(&listening_trigger.path),
         );
-        let args = Self::build_job_args(
-            &listening_trigger.script_path,
-            listening_trigger.is_flow,
-            &listening_trigger.workspace_id,
-            db,
-            payload,
-            trigger_info,
-        )
-        .await?;
+        let jobs_args = listening_trigger
+            .transform_or_build_job_args::<Self>(db, payload, trigger_info)
+            .await?;
 
         let authed = listening_trigger
             .authed(db, &Self::TRIGGER_KIND.to_string())
@@ -554,29 +668,31 @@ pub trait Listener: TriggerCrud + TriggerJobArgs {
             };
 
         tracing::debug!(
-            "Triggering job from {} event {} with args {:?}",
+            "Triggering {} job(s) from {} event {} with args {:?}",
+            jobs_args.len(),
             Self::TRIGGER_KIND,
             listening_trigger.path,
-            args
+            jobs_args
         );
 
-        trigger_runnable(
+        let jobs_pushed = trigger_runnables(
             db,
             None,
             authed,
             &listening_trigger.workspace_id,
             &listening_trigger.script_path,
             listening_trigger.is_flow,
-            args,
+            jobs_args,
             retry,
             error_handler_path.as_deref(),
             error_handler_args,
             format!("{}_trigger/{}", Self::TRIGGER_KIND, listening_trigger.path),
-            None,
             listening_trigger.suspended_mode,
             TriggerMetadata::new(Some(listening_trigger.path.clone()), Self::JOB_TRIGGER_KIND),
         )
-        .await?;
+        .await?
+        .len();
+        self.record_event(listening_trigger, ListenerEvent::JobsPushed(jobs_pushed));
 
         Ok(())
     }
@@ -590,6 +706,7 @@ pub trait Listener: TriggerCrud + TriggerJobArgs {
         extra: Option<Self::Extra>,
     ) -> Result<()> {
         if listening_trigger.trigger_mode {
+            self.record_event(listening_trigger, ListenerEvent::Received);
             if let Err(err) = self
                 .handle_trigger(db, listening_trigger, payload, trigger_info, extra)
                 .await
@@ -649,53 +766,60 @@ pub async fn listening<T: Listener>(
     let loop_ping_status = Arc::new(RwLock::new(None));
     let extra_state = listener.get_extra_state().await;
     let path = listening_trigger.path.clone();
-    tokio::select! {
-        biased;
-        _ = killpill_rx.recv() => {
-            let _ = listener.cleanup(&db, &listening_trigger, extra_state.as_ref()).await;
-        }
-        _ = listener.loop_ping(&db, &listening_trigger, loop_ping_status.clone(), Some("Connecting...".to_string())) => {
-            let _ = listener.cleanup(&db, &listening_trigger, extra_state.as_ref()).await;
-        }
-        consumer = {
-            tracing::info!("[{}] Getting consumer for trigger {}", T::TRIGGER_KIND, path);
-            listener.get_consumer(&db, &listening_trigger, loop_ping_status.clone(), killpill_rx_get_consumer)
-        } => {
-            tokio::select! {
-                biased;
-                _ = killpill_rx.recv() => {
-                    tracing::info!("[{}] Killing pill received, stopping consumer for trigger {}", T::TRIGGER_KIND, path);
-                    let _ = listener.cleanup(&db, &listening_trigger, extra_state.as_ref()).await;
-                    return;
-                }
-                _ = listener.loop_ping(&db, &listening_trigger, loop_ping_status.clone(), None) => {
-                    tracing::info!("[{}] Loop ping exited, stopping consumer for trigger {}", T::TRIGGER_KIND, path);
-                    let _ = listener.cleanup(&db, &listening_trigger, extra_state.as_ref()).await;
-                    return;
-                }
-                _ = async {
-                    match consumer {
-                        Ok(Some(consumer)) => {
-                            listener.update_ping_and_loop_ping_status(&db, &listening_trigger, loop_ping_status.clone(), None).await;
-                            tracing::info!("[{}] Starting consumer for trigger {}", T::TRIGGER_KIND, path);
-                            listener.consume(&db, consumer, &listening_trigger, loop_ping_status.clone(), killpill_rx_consumer, extra_state.as_ref()).await;
-                            tracing::info!("[{}] Consumer stopped for trigger {}", T::TRIGGER_KIND, path);
-                        }
-                        Err(error) => {
-                            tracing::error!("[{}] Disabling trigger {} due to consumer error: {}", T::TRIGGER_KIND, path, error);
-                            listener.disable_with_error(&db, &listening_trigger, error.to_string()).await;
-                        }
-                        Ok(None) => {
-                            tracing::error!("[{}] Consumer is None for trigger {}", T::TRIGGER_KIND, path);
+
+    // Returns as soon as the trigger stops being listened to, for whatever reason.
+    async {
+        tokio::select! {
+            biased;
+            _ = killpill_rx.recv() => {
+                let _ = listener.cleanup(&db, &listening_trigger, extra_state.as_ref()).await;
+            }
+            _ = listener.loop_ping(&db, &listening_trigger, loop_ping_status.clone(), Some("Connecting...".to_string())) => {
+                let _ = listener.cleanup(&db, &listening_trigger, extra_state.as_ref()).await;
+            }
+            consumer = {
+                tracing::info!("[{}] Getting consumer for trigger {}", T::TRIGGER_KIND, path);
+                listener.get_consumer(&db, &listening_trigger, loop_ping_status.clone(), killpill_rx_get_consumer)
+            } => {
+                tokio::select! {
+                    biased;
+                    _ = killpill_rx.recv() => {
+                        tracing::info!("[{}] Killing pill received, stopping consumer for trigger {}", T::TRIGGER_KIND, path);
+                        let _ = listener.cleanup(&db, &listening_trigger, extra_state.as_ref()).await;
+                        return;
+                    }
+                    _ = listener.loop_ping(&db, &listening_trigger, loop_ping_status.clone(), None) => {
+                        tracing::info!("[{}] Loop ping exited, stopping consumer for trigger {}", T::TRIGGER_KIND, path);
+                        let _ = listener.cleanup(&db, &listening_trigger, extra_state.as_ref()).await;
+                        return;
+                    }
+                    _ = async {
+                        match consumer {
+                            Ok(Some(consumer)) => {
+                                listener.update_ping_and_loop_ping_status(&db, &listening_trigger, loop_ping_status.clone(), None).await;
+                                tracing::info!("[{}] Starting consumer for trigger {}", T::TRIGGER_KIND, path);
+                                listener.consume(&db, consumer, &listening_trigger, loop_ping_status.clone(), killpill_rx_consumer, extra_state.as_ref()).await;
+                                tracing::info!("[{}] Consumer stopped for trigger {}", T::TRIGGER_KIND, path);
+                            }
+                            Err(error) => {
+                                tracing::error!("[{}] Disabling trigger {} due to consumer error: {}", T::TRIGGER_KIND, path, error);
+                                listener.disable_with_error(&db, &listening_trigger, error.to_string()).await;
+                            }
+                            Ok(None) => {
+                                tracing::error!("[{}] Consumer is None for trigger {}", T::TRIGGER_KIND, path);
+                            }
                         }
+                    } => {
+                        let _ = listener.cleanup(&db, &listening_trigger, extra_state.as_ref()).await;
+                        return;
                     }
-                } => {
-                    let _ = listener.cleanup(&db, &listening_trigger, extra_state.as_ref()).await;
-                    return;
                 }
             }
         }
     }
+    .await;
+
+    listener.forget_metrics(&db, &listening_trigger).await;
 }
 
 #[allow(unused)]
@@ -881,6 +1005,10 @@ pub struct ListeningTrigger<T> {
     pub trigger_mode: bool,
     pub error_handling: Option<TriggerErrorHandling>,
     pub suspended_mode: bool,
+    #[serde(default)]
+    pub payload_transform: Option<String>,
+    #[serde(skip)]
+    pub metrics: Arc<ListenerMetrics>,
 }
 
 impl<T> ListeningTrigger<T> {
@@ -900,6 +1028,34 @@ impl<T> ListeningTrigger<T> {
         )
         .await
     }
+
+    /// The args of every job a message starts: those of its payload transform when the
+    /// trigger has one, otherwise the single job the runnable's format calls for. A
+    /// transform replaces the format detection, since it decides the shape itself.
+    pub async fn transform_or_build_job_args<L: TriggerJobArgs>(
+        &self,
+        db: &DB,
+        payload: L::Payload,
+        trigger_info: HashMap<String, Box<RawValue>>,
+    ) -> Result<Vec<PushArgsOwned>> {
+        match PayloadTransform::new(self.payload_transform.as_deref()) {
+            Some(transform) => {
+                transform
+                    .apply(L::build_event(&payload, trigger_info))
+                    .await
+            }
+            None => L::build_job_args(
+                &self.script_path,
+                self.is_flow,
+                &self.workspace_id,
+                db,
+                payload,
+                trigger_info,
+            )
+            .await
+            .map(|args| vec![args]),
+        }
+    }
 }
 
 #[allow(unused)]
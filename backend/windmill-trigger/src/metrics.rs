@@ -0,0 +1,611 @@
+/*
+ * Author: Windmill Labs, Inc
+ * Copyright: Windmill Labs, Inc 2024
+ * This file and its contents are licensed under the AGPLv3 License.
+ * Please see the included NOTICE for copyright information and
+ * LICENSE-AGPL for a copy of the license.
+ */
+
+//! Throughput of listener triggers. Each listener counts what it consumes in a rolling
+//! window of one-minute buckets, which the ping loop persists to
+//! `trigger_listener_metrics` so that any server can answer for a trigger listened to
+//! on another one.
+
+use std::{collections::VecDeque, sync::Mutex};
+
+use axum::{
+    extract::{Extension, Path},
+    Json,
+};
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use serde::{Deserialize, Serialize};
+use windmill_api_auth::{check_scopes, ApiAuthed};
+use windmill_common::{
+    db::UserDB,
+    error::{Error, JsonResult, Result},
+    DB, INSTANCE_NAME,
+};
+
+/// Minutes of history kept, and over which the rates are computed.
+pub const WINDOW_MINUTES: i64 = 60;
+
+#[cfg(feature = "prometheus")]
+lazy_static::lazy_static! {
+    static ref TRIGGER_MESSAGES_RECEIVED: Option<prometheus::IntCounterVec> =
+        if windmill_common::METRICS_ENABLED.load(std::sync::atomic::Ordering::Relaxed) {
+            Some(prometheus::register_int_counter_vec!(
+                "trigger_messages_received_total",
+                "Messages received by a listener trigger, filtered out ones included",
+                &["trigger_kind", "workspace_id", "path"]
+            ).unwrap())
+        } else {
+            None
+        };
+
+    static ref TRIGGER_MESSAGES_FILTERED: Option<prometheus::IntCounterVec> =
+        if windmill_common::METRICS_ENABLED.load(std::sync::atomic::Ordering::Relaxed) {
+            Some(prometheus::register_int_counter_vec!(
+                "trigger_messages_filtered_total",
+                "Messages of a listener trigger rejected by its filters",
+                &["trigger_kind", "workspace_id", "path"]
+            ).unwrap())
+        } else {
+            None
+        };
+
+    static ref TRIGGER_JOBS_PUSHED: Option<prometheus::IntCounterVec> =
+        if windmill_common::METRICS_ENABLED.load(std::sync::atomic::Ordering::Relaxed) {
+            Some(prometheus::register_int_counter_vec!(
+                "trigger_jobs_pushed_total",
+                "Jobs pushed by a listener trigger",
+                &["trigger_kind", "workspace_id", "path"]
+            ).unwrap())
+        } else {
+            None
+        };
+
+    static ref TRIGGER_LAST_MESSAGE: Option<prometheus::IntGaugeVec> =
+        if windmill_common::METRICS_ENABLED.load(std::sync::atomic::Ordering::Relaxed) {
+            Some(prometheus::register_int_gauge_vec!(
+                "trigger_last_message_timestamp_seconds",
+                "Unix time of the last message received by a listener trigger",
+                &["trigger_kind", "workspace_id", "path"]
+            ).unwrap())
+        } else {
+            None
+        };
+
+    static ref TRIGGER_CONSUMER_LAG: Option<prometheus::IntGaugeVec> =
+        if windmill_common::METRICS_ENABLED.load(std::sync::atomic::Ordering::Relaxed) {
+            Some(prometheus::register_int_gauge_vec!(
+                "trigger_consumer_lag",
+                "How far behind a listener trigger is, where the protocol exposes it: entries or messages for streams and queues, bytes of WAL or binlog for databases",
+                &["trigger_kind", "workspace_id", "path"]
+            ).unwrap())
+        } else {
+            None
+        };
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum ListenerEvent {
+    Received,
+    /// A message the filters rejected, counted as received as well.
+    Filtered,
+    JobsPushed(u64),
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct MetricsTotals {
+    pub received: i64,
+    pub filtered: i64,
+    pub jobs_pushed: i64,
+}
+
+impl MetricsTotals {
+    fn add(&mut self, event: ListenerEvent) {
+        match event {
+            ListenerEvent::Received => self.received += 1,
+            ListenerEvent::Filtered => {
+                self.received += 1;
+                self.filtered += 1;
+            }
+            ListenerEvent::JobsPushed(count) => self.jobs_pushed += count as i64,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MetricsBucket {
+    pub minute: DateTime<Utc>,
+    #[serde(flatten)]
+    pub totals: MetricsTotals,
+}
+
+#[derive(Debug)]
+struct MetricsWindow {
+    started_at: DateTime<Utc>,
+    total: MetricsTotals,
+    last_message_at: Option<DateTime<Utc>>,
+    consumer_lag: Option<i64>,
+    buckets: VecDeque<MetricsBucket>,
+    /// Whether anything changed since the last flush.
+    dirty: bool,
+}
+
+impl MetricsWindow {
+    fn new(now: DateTime<Utc>) -> Self {
+        Self {
+            started_at: now,
+            total: MetricsTotals::default(),
+            last_message_at: None,
+            consumer_lag: None,
+            buckets: VecDeque::new(),
+            dirty: false,
+        }
+    }
+
+    fn record(&mut self, event: ListenerEvent, now: DateTime<Utc>) {
+        let minute = truncate_to_minute(now);
+        if self.buckets.back().map_or(true, |b| b.minute != minute) {
+            self.buckets
+                .push_back(MetricsBucket { minute, totals: MetricsTotals::default() });
+        }
+        if let Some(bucket) = self.buckets.back_mut() {
+            bucket.totals.add(event);
+        }
+        self.prune(now);
+
+        self.total.add(event);
+        if !matches!(event, ListenerEvent::JobsPushed(_)) {
+            self.last_message_at = Some(now);
+        }
+        self.dirty = true;
+    }
+
+    fn prune(&mut self, now: DateTime<Utc>) {
+        let oldest = window_start(now);
+        while self.buckets.front().is_some_and(|b| b.minute < oldest) {
+            self.buckets.pop_front();
+        }
+    }
+}
+
+/// In-memory counters of one listener, shared by its consumer and its ping loop.
+#[derive(Debug)]
+pub struct ListenerMetrics {
+    window: Mutex<MetricsWindow>,
+}
+
+impl Default for ListenerMetrics {
+    fn default() -> Self {
+        Self { window: Mutex::new(MetricsWindow::new(Utc::now())) }
+    }
+}
+
+impl ListenerMetrics {
+    pub fn record(&self, event: ListenerEvent) {
+        if let Ok(mut window) = self.window.lock() {
+            window.record(event, Utc::now());
+        }
+    }
+
+    pub fn set_consumer_lag(&self, lag: Option<i64>) {
+        if let Ok(mut window) = self.window.lock() {
+            if window.consumer_lag != lag {
+                window.consumer_lag = lag;
+                window.dirty = true;
+            }
+        }
+    }
+
+    /// Persists the window if it changed since the last call.
+    pub async fn flush(
+        &self,
+        db: &DB,
+        trigger_kind: &str,
+        workspace_id: &str,
+        path: &str,
+    ) -> Result<()> {
+        let (started_at, total, last_message_at, consumer_lag, buckets) = {
+            let Ok(mut window) = self.window.lock() else {
+                return Ok(());
+            };
+            if !window.dirty {
+                return Ok(());
+            }
+            window.dirty = false;
+            window.prune(Utc::now());
+            (
+                window.started_at,
+                window.total.clone(),
+                window.last_message_at,
+                window.consumer_lag,
+                window.buckets.iter().cloned().collect::<Vec<_>>(),
+            )
+        };
+
+        let flushed = sqlx::query!(
+            r#"
+            INSERT INTO trigger_listener_metrics (
+                workspace_id,
+                trigger_kind,
+                path,
+                server_id,
+                started_at,
+                received,
+                filtered,
+                jobs_pushed,
+                last_message_at,
+                consumer_lag,
+                buckets,
+                updated_at
+            )
+            VALUES (
+                $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, now()
+            )
+            ON CONFLICT (workspace_id, trigger_kind, path) DO UPDATE SET
+                server_id = EXCLUDED.server_id,
+                started_at = EXCLUDED.started_at,
+                received = EXCLUDED.received,
+                filtered = EXCLUDED.filtered,
+                jobs_pushed = EXCLUDED.jobs_pushed,
+                last_message_at = EXCLUDED.last_message_at,
+                consumer_lag = EXCLUDED.consumer_lag,
+                buckets = EXCLUDED.buckets,
+                updated_at = now()
+            "#,
+            workspace_id,
+            trigger_kind,
+            path,
+            &*INSTANCE_NAME,
+            started_at,
+            total.received,
+            total.filtered,
+            total.jobs_pushed,
+            last_message_at,
+            consumer_lag,
+            sqlx::types::Json(buckets) as _
+        )
+        .execute(db)
+        .await;
+
+        if let Err(err) = flushed {
+            // Flushed again on the next ping.
+            if let Ok(mut window) = self.window.lock() {
+                window.dirty = true;
+            }
+            return Err(err.into());
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "prometheus")]
+pub fn export_event(trigger_kind: &str, workspace_id: &str, path: &str, event: ListenerEvent) {
+    let labels = [trigger_kind, workspace_id, path];
+    match event {
+        ListenerEvent::Received | ListenerEvent::Filtered => {
+            if let Some(counter) = TRIGGER_MESSAGES_RECEIVED.as_ref() {
+                counter.with_label_values(&labels).inc();
+            }
+            if let Some(gauge) = TRIGGER_LAST_MESSAGE.as_ref() {
+                gauge.with_label_values(&labels).set(Utc::now().timestamp());
+            }
+            if let (ListenerEvent::Filtered, Some(counter)) =
+                (event, TRIGGER_MESSAGES_FILTERED.as_ref())
+            {
+                counter.with_label_values(&labels).inc();
+            }
+        }
+        ListenerEvent::JobsPushed(count) => {
+            if let Some(counter) = TRIGGER_JOBS_PUSHED.as_ref() {
+                counter.with_label_values(&labels).inc_by(count);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "prometheus")]
+pub fn export_consumer_lag(trigger_kind: &str, workspace_id: &str, path: &str, lag: Option<i64>) {
+    if let Some(gauge) = TRIGGER_CONSUMER_LAG.as_ref() {
+        let labels = [trigger_kind, workspace_id, path];
+        match lag {
+            Some(lag) => gauge.with_label_values(&labels).set(lag),
+            None => {
+                let _ = gauge.remove_label_values(&labels);
+            }
+        }
+    }
+}
+
+/// Stops exporting the series of a trigger, once this server no longer listens to it.
+#[cfg(feature = "prometheus")]
+pub fn remove_series(trigger_kind: &str, workspace_id: &str, path: &str) {
+    let labels = [trigger_kind, workspace_id, path];
+    for counter in [
+        TRIGGER_MESSAGES_RECEIVED.as_ref(),
+        TRIGGER_MESSAGES_FILTERED.as_ref(),
+        TRIGGER_JOBS_PUSHED.as_ref(),
+    ]
+    .into_iter()
+    .flatten()
+    {
+        let _ = counter.remove_label_values(&labels);
+    }
+    for gauge in [TRIGGER_LAST_MESSAGE.as_ref(), TRIGGER_CONSUMER_LAG.as_ref()]
+        .into_iter()
+        .flatten()
+    {
+        let _ = gauge.remove_label_values(&labels);
+    }
+}
+
+/// Drops the persisted metrics of a trigger that was deleted or moved to another path.
+pub async fn delete_trigger_metrics(
+    db: &DB,
+    trigger_kind: &str,
+    workspace_id: &str,
+    path: &str,
+) -> Result<()> {
+    sqlx::query(
+        "DELETE FROM trigger_listener_metrics
+         WHERE workspace_id = $1 AND trigger_kind = $2 AND path = $3",
+    )
+    .bind(workspace_id)
+    .bind(trigger_kind)
+    .bind(path)
+    .execute(db)
+    .await?;
+    Ok(())
+}
+
+fn truncate_to_minute(time: DateTime<Utc>) -> DateTime<Utc> {
+    time.duration_trunc(Duration::minutes(1)).unwrap_or(time)
+}
+
+fn window_start(now: DateTime<Utc>) -> DateTime<Utc> {
+    truncate_to_minute(now) - Duration::minutes(WINDOW_MINUTES - 1)
+}
+
+#[derive(Debug, Serialize)]
+pub struct TriggerMetrics {
+    /// The server listening to the trigger when the metrics were last persisted.
+    pub server_id: String,
+    /// When that server started listening, since which `total` is counted.
+    pub started_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub last_message_at: Option<DateTime<Utc>>,
+    pub consumer_lag: Option<i64>,
+    pub total: MetricsTotals,
+    pub window_minutes: i64,
+    /// Sum of the buckets of the last `window_minutes`.
+    pub window: MetricsTotals,
+    pub received_per_minute: f64,
+    pub jobs_pushed_per_minute: f64,
+    pub buckets: Vec<MetricsBucket>,
+}
+
+impl TriggerMetrics {
+    fn from_row(row: TriggerMetricsRow, now: DateTime<Utc>) -> Self {
+        let oldest = window_start(now);
+        let buckets = row
+            .buckets
+            .0
+            .into_iter()
+            .filter(|bucket| bucket.minute >= oldest)
+            .collect::<Vec<_>>();
+
+        let mut window = MetricsTotals::default();
+        for bucket in &buckets {
+            window.received += bucket.totals.received;
+            window.filtered += bucket.totals.filtered;
+            window.jobs_pushed += bucket.totals.jobs_pushed;
+        }
+
+        // A listener that started within the window is only averaged over the minutes
+        // it has been listening for.
+        let listened_minutes = (now - row.started_at.max(oldest)).num_seconds() as f64 / 60.0;
+        let listened_minutes = listened_minutes.clamp(1.0, WINDOW_MINUTES as f64);
+
+        Self {
+            server_id: row.server_id,
+            started_at: row.started_at,
+            updated_at: row.updated_at,
+            last_message_at: row.last_message_at,
+            consumer_lag: row.consumer_lag,
+            total: MetricsTotals {
+                received: row.received,
+                filtered: row.filtered,
+                jobs_pushed: row.jobs_pushed,
+            },
+            window_minutes: WINDOW_MINUTES,
+            received_per_minute: window.received as f64 / listened_minutes,
+            jobs_pushed_per_minute: window.jobs_pushed as f64 / listened_minutes,
+            window,
+            buckets,
+        }
+    }
+}
+
+struct TriggerMetricsRow {
+    server_id: String,
+    started_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    last_message_at: Option<DateTime<Utc>>,
+    consumer_lag: Option<i64>,
+    received: i64,
+    filtered: i64,
+    jobs_pushed: i64,
+    buckets: sqlx::types::Json<Vec<MetricsBucket>>,
+}
+
+/// Reads through the RLS pool, the metrics of a trigger being visible to whoever can see
+/// its path, and behind the scope of the trigger itself.
+pub async fn get_trigger_metrics(
+    authed: ApiAuthed,
+    Extension(user_db): Extension<UserDB>,
+    Path((w_id, trigger_kind, trigger_path)): Path<(String, String, String)>,
+) -> JsonResult<TriggerMetrics> {
+    check_scopes(&authed, || {
+        format!("{}_triggers:read:{}", trigger_kind, trigger_path)
+    })?;
+
+    let mut tx = user_db.begin(&authed).await?;
+    let row = sqlx::query_as!(
+        TriggerMetricsRow,
+        r#"
+        SELECT
+            server_id,
+            started_at,
+            updated_at,
+            last_message_at,
+            consumer_lag,
+            received,
+            filtered,
+            jobs_pushed,
+            buckets AS "buckets!: _"
+        FROM trigger_listener_metrics
+        WHERE
+            workspace_id = $1 AND
+            trigger_kind = $2 AND
+            path = $3
+        "#,
+        w_id,
+        trigger_kind,
+        trigger_path
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+    tx.commit().await?;
+
+    let row = row.ok_or_else(|| {
+        Error::NotFound(format!(
+            "No metrics for {} trigger {}, it has not received any message yet",
+            trigger_kind, trigger_path
+        ))
+    })?;
+
+    Ok(Json(TriggerMetrics::from_row(row, Utc::now())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn at(minute: u32, second: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2026, 10, 19, 12, minute, second)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_record_buckets() {
+        let mut window = MetricsWindow::new(at(0, 0));
+        window.record(ListenerEvent::Received, at(0, 10));
+        window.record(ListenerEvent::JobsPushed(2), at(0, 10));
+        window.record(ListenerEvent::Filtered, at(0, 50));
+        window.record(ListenerEvent::Received, at(1, 5));
+
+        assert_eq!(window.buckets.len(), 2);
+        assert_eq!(
+            window.buckets[0].totals,
+            MetricsTotals { received: 2, filtered: 1, jobs_pushed: 2 }
+        );
+        assert_eq!(window.buckets[1].minute, at(1, 0));
+        assert_eq!(
+            window.total,
+            MetricsTotals { received: 3, filtered: 1, jobs_pushed: 2 }
+        );
+        assert_eq!(window.last_message_at, Some(at(1, 5)));
+        assert!(window.dirty);
+    }
+
+    #[test]
+    fn test_window_is_pruned() {
+        let mut window = MetricsWindow::new(at(0, 0));
+        window.record(ListenerEvent::Received, at(0, 0));
+        window.record(ListenerEvent::Received, at(30, 0));
+        window.record(
+            ListenerEvent::Received,
+            at(0, 0) + Duration::minutes(WINDOW_MINUTES),
+        );
+
+        assert_eq!(window.buckets.len(), 2);
+        assert_eq!(window.buckets[0].minute, at(30, 0));
+        assert_eq!(window.total.received, 3);
+    }
+
+    #[test]
+    fn test_rates_over_listened_minutes() {
+        let bucket = |minute, received| MetricsBucket {
+            minute: at(minute, 0),
+            totals: MetricsTotals { received, filtered: 0, jobs_pushed: received },
+        };
+        let row = TriggerMetricsRow {
+            server_id: "server".to_string(),
+            started_at: at(0, 0),
+            updated_at: at(10, 0),
+            last_message_at: Some(at(9, 30)),
+            consumer_lag: Some(4),
+            received: 40,
+            filtered: 0,
+            jobs_pushed: 40,
+            buckets: sqlx::types::Json(vec![bucket(0, 10), bucket(9, 30)]),
+        };
+
+        let metrics = TriggerMetrics::from_row(row, at(10, 0));
+        assert_eq!(metrics.window.received, 40);
+        assert_eq!(metrics.received_per_minute, 4.0);
+        assert_eq!(metrics.buckets.len(), 2);
+
+        let later = at(0, 0) + Duration::minutes(WINDOW_MINUTES + 5);
+        let row = TriggerMetricsRow {
+            server_id: "server".to_string(),
+            started_at: at(0, 0),
+            updated_at: at(10, 0),
+            last_message_at: None,
+            consumer_lag: None,
+            received: 40,
+            filtered: 0,
+            jobs_pushed: 40,
+            buckets: sqlx::types::Json(vec![bucket(0, 10), bucket(9, 30)]),
+        };
+        let metrics = TriggerMetrics::from_row(row, later);
+        assert_eq!(metrics.buckets.len(), 1);
+        assert_eq!(metrics.window.received, 30);
+        // The window spans the current minute and the 59 before it.
+        assert!((metrics.received_per_minute - 30.0 / 59.0).abs() < 1e-9);
+        assert_eq!(metrics.total.received, 40);
+    }
+
+    #[sqlx::test(migrations = "../migrations")]
+    async fn test_delete_trigger_metrics(db: DB) {
+        sqlx::query(
+            "INSERT INTO workspace (id, name, owner) VALUES ('test-workspace', 'test-workspace', 'test-user')",
+        )
+        .execute(&db)
+        .await
+        .unwrap();
+
+        for path in ["f/test/kept", "f/test/deleted"] {
+            let metrics = ListenerMetrics::default();
+            metrics.record(ListenerEvent::Received);
+            metrics
+                .flush(&db, "redis", "test-workspace", path)
+                .await
+                .unwrap();
+        }
+
+        delete_trigger_metrics(&db, "redis", "test-workspace", "f/test/deleted")
+            .await
+            .unwrap();
+
+        let paths = sqlx::query_scalar::<_, String>("SELECT path FROM trigger_listener_metrics")
+            .fetch_all(&db)
+            .await
+            .unwrap();
+        assert_eq!(paths, vec!["f/test/kept".to_string()]);
+    }
+}
@@ -0,0 +1,201 @@
+/*
+ * Author: Windmill Labs, Inc
+ * Copyright: Windmill Labs, Inc 2024
+ * This file and its contents are licensed under the AGPLv3 License.
+ * Please see the included NOTICE for copyright information and
+ * LICENSE-AGPL for a copy of the license.
+ */
+
+use std::collections::HashMap;
+
+use serde_json::value::RawValue;
+use windmill_common::error::{Error, Result};
+use windmill_queue::PushArgsOwned;
+
+/// Upper bound on the jobs one message may be split into. A transform that maps a batch
+/// message to one job per item is the intended use; one that returns an unbounded array
+/// is far more likely a mistake than a batch of that size.
+pub const MAX_TRANSFORM_JOBS: usize = 1000;
+
+/// JavaScript expression evaluated on every message of a trigger, in place of the per-kind
+/// mapping to job args. It sees the message as `event`, the same object a v2 preprocessor
+/// receives, and its value is the args of the job(s) to push:
+///
+/// - an object: the args of one job;
+/// - an array of objects: one job per element;
+/// - `null` / `undefined`: no job, the message is dropped.
+#[derive(Debug, Clone)]
+pub struct PayloadTransform {
+    expr: String,
+}
+
+impl PayloadTransform {
+    /// A blank expression is the same as none, so clearing the field in the editor turns
+    /// the transform off rather than dropping every message.
+    pub fn new(expr: Option<&str>) -> Option<Self> {
+        expr.map(str::trim)
+            .filter(|expr| !expr.is_empty())
+            .map(|expr| Self { expr: expr.to_string() })
+    }
+
+    /// What to store for the transform of a trigger being saved. The expression is
+    /// compiled so that a syntax error, or a server built without QuickJS, is reported
+    /// on save instead of failing every message of the trigger.
+    pub async fn check(expr: Option<&str>) -> Result<Option<String>> {
+        let Some(transform) = Self::new(expr) else {
+            return Ok(None);
+        };
+        windmill_jseval::check_simple_js(&transform.expr, &["event"])
+            .await
+            .map_err(|err| Error::BadRequest(format!("Invalid payload transform: {err:#}")))?;
+        Ok(Some(transform.expr))
+    }
+
+    pub async fn apply(&self, event: HashMap<String, Box<RawValue>>) -> Result<Vec<PushArgsOwned>> {
+        let event = serde_json::to_value(&event)?;
+        let result = windmill_jseval::eval_simple_js(
+            self.expr.clone(),
+            HashMap::from([("event".to_string(), event)]),
+        )
+        .await
+        .map_err(|err| Error::ExecutionErr(format!("Payload transform failed: {err:#}")))?;
+
+        split_jobs(result.get())
+    }
+}
+
+/// The transform result as the args of each job to push, in order.
+fn split_jobs(result: &str) -> Result<Vec<PushArgsOwned>> {
+    let invalid = |what: &str| {
+        Error::ExecutionErr(format!(
+            "Payload transform must evaluate to an object, an array of objects or null, got {}",
+            what
+        ))
+    };
+
+    let result = serde_json::from_str::<Option<&RawValue>>(result)?;
+    let Some(result) = result else {
+        return Ok(vec![]);
+    };
+
+    let items = match result.get().trim_start().as_bytes().first() {
+        Some(b'{') => vec![result],
+        Some(b'[') => serde_json::from_str::<Vec<&RawValue>>(result.get())?,
+        Some(b'"') => return Err(invalid("a string")),
+        Some(b't' | b'f') => return Err(invalid("a boolean")),
+        _ => return Err(invalid("a number")),
+    };
+
+    if items.len() > MAX_TRANSFORM_JOBS {
+        return Err(Error::ExecutionErr(format!(
+            "Payload transform produced {} jobs, more than the limit of {}",
+            items.len(),
+            MAX_TRANSFORM_JOBS
+        )));
+    }
+
+    items
+        .into_iter()
+        .enumerate()
+        .map(|(index, item)| {
+            serde_json::from_str::<HashMap<String, Box<RawValue>>>(item.get())
+                .map(|args| PushArgsOwned { args, extra: None })
+                .map_err(|_| invalid(&format!("a non-object at index {}", index)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn arg(args: &PushArgsOwned, key: &str) -> String {
+        args.args
+            .get(key)
+            .map(|v| v.get().to_string())
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn test_blank_expression_is_no_transform() {
+        assert!(PayloadTransform::new(None).is_none());
+        assert!(PayloadTransform::new(Some("  \n ")).is_none());
+    }
+
+    #[cfg(feature = "quickjs")]
+    #[tokio::test]
+    async fn test_check_compiles_the_expression() {
+        assert_eq!(PayloadTransform::check(Some(" ")).await.unwrap(), None);
+        assert_eq!(
+            PayloadTransform::check(Some(" event.body "))
+                .await
+                .unwrap()
+                .as_deref(),
+            Some("event.body")
+        );
+        let err = PayloadTransform::check(Some("event.body.map(x =>"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::BadRequest(_)), "{err:?}");
+    }
+
+    #[cfg(not(feature = "quickjs"))]
+    #[tokio::test]
+    async fn test_check_rejects_transform_without_quickjs() {
+        assert_eq!(PayloadTransform::check(None).await.unwrap(), None);
+        let err = PayloadTransform::check(Some("event.body"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, Error::BadRequest(_)), "{err:?}");
+    }
+
+    #[test]
+    fn test_object_is_one_job() {
+        let jobs = split_jobs(r#"{"id": 1, "kind": "kafka"}"#).unwrap();
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(arg(&jobs[0], "id"), "1");
+        assert_eq!(arg(&jobs[0], "kind"), r#""kafka""#);
+        assert!(jobs[0].extra.is_none());
+    }
+
+    #[test]
+    fn test_array_is_one_job_per_element() {
+        let jobs = split_jobs(r#"[{"id": 1}, {"id": 2}, {}]"#).unwrap();
+        assert_eq!(jobs.len(), 3);
+        assert_eq!(arg(&jobs[1], "id"), "2");
+        assert!(jobs[2].args.is_empty());
+    }
+
+    #[test]
+    fn test_null_or_empty_array_drops_the_message() {
+        assert!(split_jobs("null").unwrap().is_empty());
+        assert!(split_jobs("[]").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_non_object_results_are_rejected() {
+        for (result, expected) in [
+            (r#""text""#, "a string"),
+            ("true", "a boolean"),
+            ("42", "a number"),
+            (r#"[{"id": 1}, 2]"#, "a non-object at index 1"),
+            ("[[]]", "a non-object at index 0"),
+        ] {
+            let err = split_jobs(result).unwrap_err().to_string();
+            assert!(
+                err.contains(expected),
+                "{} should be rejected, got: {}",
+                result,
+                err
+            );
+        }
+    }
+
+    #[test]
+    fn test_split_is_bounded() {
+        let result = format!("[{}]", vec!["{}"; MAX_TRANSFORM_JOBS + 1].join(","));
+        assert!(split_jobs(&result).is_err());
+        let result = format!("[{}]", vec!["{}"; MAX_TRANSFORM_JOBS].join(","));
+        assert_eq!(split_jobs(&result).unwrap().len(), MAX_TRANSFORM_JOBS);
+    }
+}
@@ -411,22 +411,31 @@ pub trait TriggerJobArgs {
         Self::v1_payload_fn(payload)
     }
 
+    /// The message as a v2 preprocessor receives it in `event`.
+    fn build_event(
+        payload: &Self::Payload,
+        info: HashMap<String, Box<RawValue>>,
+    ) -> HashMap<String, Box<RawValue>> {
+        let mut event = Self::v2_payload_fn(payload);
+        event.insert(
+            "kind".to_string(),
+            to_raw_value(&Self::TRIGGER_KIND.to_key()),
+        );
+        event.extend(info);
+        event
+    }
+
     fn build_job_args_v2(
         has_preprocessor: bool,
         payload: &Self::Payload,
         info: HashMap<String, Box<RawValue>>,
     ) -> PushArgsOwned {
-        let mut args = Self::v2_payload_fn(payload);
         if has_preprocessor {
-            args.insert(
-                "kind".to_string(),
-                to_raw_value(&Self::TRIGGER_KIND.to_key()),
-            );
-            args.extend(info);
-            let args = HashMap::from([("event".to_string(), to_raw_value(&args))]);
+            let event = Self::build_event(payload, info);
+            let args = HashMap::from([("event".to_string(), to_raw_value(&event))]);
             PushArgsOwned { args, extra: None }
         } else {
-            PushArgsOwned { args, extra: None }
+            PushArgsOwned { args: Self::v2_payload_fn(payload), extra: None }
         }
     }
 
@@ -622,6 +631,57 @@ pub async fn trigger_runnable(
     Ok((StatusCode::CREATED, uuid.to_string()).into_response())
 }
 
+/// Pushes one job per args in a single transaction, so that a failure partway through a
+/// fan-out pushes none of them and a retry of the event does not duplicate jobs.
+#[allow(dead_code)]
+pub async fn trigger_runnables(
+    db: &DB,
+    user_db: Option<UserDB>,
+    authed: ApiAuthed,
+    workspace_id: &str,
+    runnable_path: &str,
+    is_flow: bool,
+    jobs_args: Vec<PushArgsOwned>,
+    retry: Option<&sqlx::types::Json<Retry>>,
+    error_handler_path: Option<&str>,
+    error_handler_args: Option<&sqlx::types::Json<HashMap<String, serde_json::Value>>>,
+    trigger_path: String,
+    suspended_mode: bool,
+    trigger: TriggerMetadata,
+) -> Result<Vec<Uuid>> {
+    let user_db = user_db.unwrap_or_else(|| UserDB::new(db.clone()));
+    let mut tx = user_db.clone().begin(&authed).await?;
+    let mut uuids = Vec::with_capacity(jobs_args.len());
+    for args in jobs_args {
+        let (uuid, _, _, _, tx_o) = trigger_runnable_inner(
+            db,
+            Some(tx),
+            Some(user_db.clone()),
+            authed.clone(),
+            workspace_id,
+            runnable_path,
+            is_flow,
+            args,
+            retry,
+            error_handler_path,
+            error_handler_args,
+            trigger_path.clone(),
+            None,
+            trigger.clone(),
+            Some(suspended_mode),
+        )
+        .await?;
+        tx = tx_o.ok_or_else(|| {
+            windmill_common::error::Error::internal_err(
+                "Transaction should be returned when passed in".to_string(),
+            )
+        })?;
+        uuids.push(uuid);
+    }
+    tx.commit().await?;
+    Ok(uuids)
+}
+
 #[allow(dead_code)]
 pub async fn trigger_runnable_and_wait_for_result(
     db: &DB,
@@ -884,27 +944,33 @@ async fn trigger_script_with_retry_and_error_handler<'c>(
 
     let return_tx = tx_o.is_some();
 
-    let (email, permissioned_as, push_authed, tx) = if let Some(tx) = tx_o {
-        (
+    // A job pushed in the transaction of the caller runs on behalf of the same user as
+    // one pushed on its own.
+    let (email, permissioned_as, push_authed, tx) = match (tx_o, on_behalf_of.as_ref()) {
+        (Some(tx), Some(on_behalf_of)) => (
+            on_behalf_of.email.as_str(),
+            on_behalf_of.permissioned_as.clone(),
+            None,
+            PushIsolationLevel::Transaction(tx),
+        ),
+        (Some(tx), None) => (
             authed.email.as_str(),
             username_to_permissioned_as(&authed.username),
             Some(authed.clone().into()),
             PushIsolationLevel::Transaction(tx),
-        )
-    } else if let Some(on_behalf_of) = on_behalf_of.as_ref() {
-        (
+        ),
+        (None, Some(on_behalf_of)) => (
             on_behalf_of.email.as_str(),
             on_behalf_of.permissioned_as.clone(),
             None,
             PushIsolationLevel::IsolatedRoot(db.clone()),
-        )
-    } else {
-        (
+        ),
+        (None, None) => (
             authed.email.as_str(),
             username_to_permissioned_as(&authed.username),
             Some(authed.clone().into()),
             PushIsolationLevel::Isolated(user_db, authed.clone().into()),
-        )
+        ),
     };
 
     let push_args = PushArgs { args: &args.args, extra: args.extra };
@@ -46,6 +46,11 @@ pub struct BaseTrigger {
     pub extra_perms: Option<serde_json::Value>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub labels: Option<Vec<String>>,
+    /// JavaScript expression mapping each message to job args, see
+    /// [`crate::transform::PayloadTransform`].
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[sqlx(default)]
+    pub payload_transform: Option<String>,
     /// True when this row is a per-user draft with no deployed trigger
     /// at the same path. Set by `list_triggers` when the response
     /// includes synthesized draft-only rows (gated on
@@ -156,6 +161,8 @@ pub struct BaseTriggerData {
     pub preserve_permissioned_as: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub labels: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub payload_transform: Option<String>,
 }
 
 impl BaseTriggerData {
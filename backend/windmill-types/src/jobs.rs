@@ -33,6 +33,14 @@ pub enum JobTriggerKind {
     Nats,
     Mqtt,
     Amqp,
+    #[serde(rename = "postgres_notify")]
+    #[sqlx(rename = "postgres_notify")]
+    PostgresNotify,
+    Mysql,
+    Redis,
+    Object,
+    Sftp,
+    Grpc,
     Sqs,
     Postgres,
     Schedule,
@@ -74,6 +82,12 @@ impl JobTriggerKind {
             JobTriggerKind::Nats => "nats",
             JobTriggerKind::Mqtt => "mqtt",
             JobTriggerKind::Amqp => "amqp",
+            JobTriggerKind::PostgresNotify => "postgres_notify",
+            JobTriggerKind::Mysql => "mysql",
+            JobTriggerKind::Redis => "redis",
+            JobTriggerKind::Object => "object",
+            JobTriggerKind::Sftp => "sftp",
+            JobTriggerKind::Grpc => "grpc",
             JobTriggerKind::Sqs => "sqs",
             JobTriggerKind::Postgres => "postgres",
             JobTriggerKind::Schedule => "schedule",
@@ -67,6 +67,10 @@ pub enum ScriptLang {
     Ruby,
     Rlang,
     Dbt,
+    Lua,
+    Kotlin,
+    Perl,
+    Wasm,
     // for related places search: ADD_NEW_LANG
 }
 
@@ -98,6 +102,10 @@ impl ScriptLang {
             ScriptLang::Ruby => "ruby",
             ScriptLang::Rlang => "rlang",
             ScriptLang::Dbt => "dbt",
+            ScriptLang::Lua => "lua",
+            ScriptLang::Kotlin => "kotlin",
+            ScriptLang::Perl => "perl",
+            ScriptLang::Wasm => "wasm",
             // for related places search: ADD_NEW_LANG
         }
     }
@@ -129,15 +137,17 @@ impl ScriptLang {
                 | ScriptLang::Mssql
                 | ScriptLang::Bigquery
                 | ScriptLang::OracleDB
+                | ScriptLang::Lua
+                | ScriptLang::Wasm
         )
     }
 
     pub fn as_comment_lit(&self) -> String {
         use ScriptLang::*;
         match self {
-            Nativets | Bun | Bunnative | Deno | Go | Php | CSharp | Java => "//",
-            Python3 | Bash | Powershell | Graphql | Ansible | Nu | Ruby | Rlang | Dbt => "#",
-            Postgresql | Mysql | Bigquery | Snowflake | Mssql | OracleDB | DuckDb => "--",
+            Nativets | Bun | Bunnative | Deno | Go | Php | CSharp | Java | Kotlin | Wasm => "//",
+            Python3 | Bash | Powershell | Graphql | Ansible | Nu | Ruby | Rlang | Dbt | Perl => "#",
+            Postgresql | Mysql | Bigquery | Snowflake | Mssql | OracleDB | DuckDb | Lua => "--",
             Rust => "//!",
             // for related places search: ADD_NEW_LANG
         }
@@ -173,6 +183,10 @@ impl FromStr for ScriptLang {
             "ruby" => ScriptLang::Ruby,
             "rlang" => ScriptLang::Rlang,
             "dbt" => ScriptLang::Dbt,
+            "lua" => ScriptLang::Lua,
+            "kotlin" => ScriptLang::Kotlin,
+            "perl" => ScriptLang::Perl,
+            "wasm" => ScriptLang::Wasm,
             // for related places search: ADD_NEW_LANG
             language => return Err(anyhow::anyhow!("{} is currently not supported", language)),
         };
@@ -17,6 +17,12 @@ pub enum TriggerKind {
     Nats,
     Mqtt,
     Amqp,
+    PostgresNotify,
+    Mysql,
+    Redis,
+    Object,
+    Sftp,
+    Grpc,
     Sqs,
     Postgres,
     Gcp,
@@ -38,6 +44,12 @@ impl TriggerKind {
             TriggerKind::Nats => "nats".to_string(),
             TriggerKind::Mqtt => "mqtt".to_string(),
             TriggerKind::Amqp => "amqp".to_string(),
+            TriggerKind::PostgresNotify => "postgres_notify".to_string(),
+            TriggerKind::Mysql => "mysql".to_string(),
+            TriggerKind::Redis => "redis".to_string(),
+            TriggerKind::Object => "object".to_string(),
+            TriggerKind::Sftp => "sftp".to_string(),
+            TriggerKind::Grpc => "grpc".to_string(),
             TriggerKind::Sqs => "sqs".to_string(),
             TriggerKind::Postgres => "postgres".to_string(),
             TriggerKind::Gcp => "gcp".to_string(),
@@ -61,6 +73,12 @@ impl fmt::Display for TriggerKind {
             TriggerKind::Nats => "nats",
             TriggerKind::Mqtt => "mqtt",
             TriggerKind::Amqp => "amqp",
+            TriggerKind::PostgresNotify => "postgres_notify",
+            TriggerKind::Mysql => "mysql",
+            TriggerKind::Redis => "redis",
+            TriggerKind::Object => "object",
+            TriggerKind::Sftp => "sftp",
+            TriggerKind::Grpc => "grpc",
             TriggerKind::Sqs => "sqs",
             TriggerKind::Postgres => "postgres",
             TriggerKind::Gcp => "gcp",
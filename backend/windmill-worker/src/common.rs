@@ -965,11 +965,67 @@ pub fn build_command_with_isolation(program: &str, args: &[&str]) -> Command {
 }
 
 pub async fn start_child_process(
-    cmd: Command,
+    #[allow(unused_mut)] mut cmd: Command,
     executable: &str,
     disable_process_group: bool,
 ) -> Result<Box<dyn TokioChildWrapper>, Error> {
     use process_wrap::tokio::*;
+
+    // nsjail enforces its own limits, every other process of a job joins the job's cgroup.
+    // The child joins it before exec so nothing it forks can escape it.
+    #[cfg(target_os = "linux")]
+    let job_cgroup = (executable != crate::NSJAIL_PATH.as_str())
+        .then(crate::job_cgroup::JobCgroup::for_current_job)
+        .flatten();
+    #[cfg(target_os = "linux")]
+    if let Some(cgroup) = job_cgroup.as_ref() {
+        let procs = cgroup.procs_path();
+        // SAFETY: the hook only issues open/write/close syscalls on a path allocated before fork
+        unsafe {
+            cmd.pre_exec(move || crate::job_cgroup::JobCgroup::enter_from_child(&procs));
+        }
+    }
+
+    // Registered after the cgroup hook, since joining a user namespace loses the rights on
+    // cgroupfs, and before Landlock, which would deny the writes to /proc.
+    #[cfg(target_os = "linux")]
+    let job_netns = crate::egress_proxy::JobNetns::for_current_job()?;
+    #[cfg(target_os = "linux")]
+    if let Some(netns) = job_netns.as_ref() {
+        if executable == crate::NSJAIL_PATH.as_str() {
+            return Err(Error::ExecutionErr(
+                "Egress allowlists are not supported with nsjail sandboxing, restrict the \
+                network in the nsjail config instead"
+                    .to_string(),
+            ));
+        }
+        let proxy = netns.proxy_url();
+        for var in ["HTTP_PROXY", "HTTPS_PROXY", "ALL_PROXY"] {
+            cmd.env(var, &proxy).env(var.to_lowercase(), &proxy);
+        }
+        cmd.env("NO_PROXY", netns.no_proxy())
+            .env("no_proxy", netns.no_proxy());
+        let setup = netns.child_setup();
+        // SAFETY: everything the hook needs is allocated before fork, it only issues syscalls
+        unsafe {
+            cmd.pre_exec(move || setup.enter_from_child());
+        }
+    }
+
+    // Registered after the cgroup hook: Landlock would deny the write to cgroupfs
+    #[cfg(target_os = "linux")]
+    if executable != crate::NSJAIL_PATH.as_str() {
+        if let Some(mut sandbox) = crate::landlock_sandbox::Sandbox::for_current_job()? {
+            if !cmd.as_std().get_envs().any(|(k, _)| k == "TMPDIR") {
+                cmd.env("TMPDIR", sandbox.job_dir());
+            }
+            // SAFETY: the ruleset and filters are built before fork, the hook only applies them
+            unsafe {
+                cmd.pre_exec(move || sandbox.enter_from_child());
+            }
+        }
+    }
+
     let mut cmd = TokioCommandWrap::from(cmd);
 
     // On Windows, put the child in its own console process group so a CTRL_BREAK_EVENT
@@ -1003,9 +1059,37 @@ pub async fn start_child_process(
         }
     }
 
-    let child: Box<dyn TokioChildWrapper> = cmd
-        .spawn()
-        .map_err(|err| tentatively_improve_error(err.into(), executable))?;
+    let child: Box<dyn TokioChildWrapper> = cmd.spawn().map_err(|err| {
+        #[cfg(target_os = "linux")]
+        if let Some(Err(e)) = job_cgroup.as_ref().map(|cgroup| cgroup.check_joinable()) {
+            tracing::error!(
+                "could not move {executable} into the cgroup of its job: {err}, {e}. The job \
+                fails rather than run without its resource limits"
+            );
+            return Error::ExecutionErr(format!(
+                "Could not move {executable} into the cgroup of the job to enforce its resource \
+                limits ({err})"
+            ));
+        }
+        #[cfg(target_os = "linux")]
+        if job_netns.is_some() && err.kind() == std::io::ErrorKind::PermissionDenied {
+            return Error::ExecutionErr(format!(
+                "Could not isolate the network of {executable} to enforce the egress allowlist \
+                ({err}): the worker needs CAP_SYS_ADMIN or unprivileged user namespaces"
+            ));
+        }
+        tentatively_improve_error(err.into(), executable)
+    })?;
+
+    #[cfg(target_os = "linux")]
+    if let (Some(cgroup), Some(pid)) = (job_cgroup, child.id()) {
+        crate::job_cgroup::register(pid, cgroup);
+    }
+
+    #[cfg(target_os = "linux")]
+    if let Some(netns) = job_netns {
+        netns.serve()?;
+    }
 
     // On Windows, assign the child to a job object. KILL_ON_JOB_CLOSE makes the OS reap
     // the whole child tree when the worker drops the handle or dies, so jobs aren't
@@ -0,0 +1,601 @@
+//! Content-addressed cache of compilation artifacts, shared between workers through the
+//! instance object store.
+//!
+//! [`global_cache`](crate::global_cache) caches the final binary of a script under a hash of
+//! its code, so two scripts that differ by one line share nothing and each recompiles all of
+//! its dependencies. The artifacts cached here are keyed on what produced them instead — the
+//! toolchain version, the target and the dependency lock — and never hold the script itself:
+//!
+//! - Rust: the dependency crates compiled into a fresh cargo target directory,
+//! - Go: the build cache entries of the standard library and the module dependencies,
+//! - C#: the NuGet packages extracted into the global packages folder,
+//! - Java and Kotlin: the jars coursier fetched into the maven repository.
+//!
+//! An artifact is a tar kept under [`COMPILATION_CACHE_DIR`] on the worker, and under
+//! `compilation_cache/<target>/` in the object store when there is one. Restoring never
+//! overwrites a file that is already there: every artifact is content-addressed, so an
+//! existing file already holds the same content and may be in use by a concurrent build.
+//!
+//! Both tiers are bounded in size (`COMPILATION_CACHE_MAX_SIZE`, default 10GB, and
+//! `COMPILATION_CACHE_OBJECT_STORE_MAX_SIZE`, default 100GB). The local tier evicts the least
+//! recently used artifacts; the object store does not record reads, so it evicts the oldest
+//! uploads. `DISABLE_COMPILATION_CACHE=true` turns the cache off.
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::{Component, Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, Instant, SystemTime},
+};
+
+use sha2::{Digest, Sha256};
+use tokio::process::Command;
+use windmill_common::error::{self, Error};
+
+use crate::{global_cache::TARGET, parse_byte_size, COMPILATION_CACHE_DIR};
+
+/// Eviction lists the whole tier, so it runs at most this often per worker process.
+const EVICTION_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+lazy_static::lazy_static! {
+    static ref DISABLE_COMPILATION_CACHE: bool = std::env::var("DISABLE_COMPILATION_CACHE")
+        .ok()
+        .map(|flag| flag == "true")
+        .unwrap_or(false);
+    static ref LOCAL_MAX_SIZE: u64 = max_size_from_env("COMPILATION_CACHE_MAX_SIZE", 10 << 30);
+    static ref OBJECT_STORE_MAX_SIZE: u64 =
+        max_size_from_env("COMPILATION_CACHE_OBJECT_STORE_MAX_SIZE", 100 << 30);
+    static ref LAST_EVICTION: Mutex<Option<Instant>> = Mutex::new(None);
+    /// `--version` outputs by toolchain binary and modification time, so that a toolchain
+    /// upgraded in place is asked again.
+    static ref TOOLCHAIN_VERSIONS: Mutex<HashMap<(String, Option<SystemTime>), String>> =
+        Mutex::new(HashMap::new());
+}
+
+#[cfg(feature = "prometheus")]
+lazy_static::lazy_static! {
+    static ref COMPILATION_CACHE_REQUESTS: Option<prometheus::IntCounterVec> =
+        if windmill_common::METRICS_ENABLED.load(std::sync::atomic::Ordering::Relaxed) {
+            Some(prometheus::register_int_counter_vec!(
+                "compilation_cache_requests_total",
+                "Compilation artifacts looked up, by whether they were found on the worker, in the object store or not at all",
+                &["kind", "result"]
+            ).unwrap())
+        } else {
+            None
+        };
+
+    static ref COMPILATION_CACHE_STORED_BYTES: Option<prometheus::IntCounterVec> =
+        if windmill_common::METRICS_ENABLED.load(std::sync::atomic::Ordering::Relaxed) {
+            Some(prometheus::register_int_counter_vec!(
+                "compilation_cache_stored_bytes_total",
+                "Size of the compilation artifacts stored after a miss",
+                &["kind"]
+            ).unwrap())
+        } else {
+            None
+        };
+
+    static ref COMPILATION_CACHE_EVICTED_BYTES: Option<prometheus::IntCounterVec> =
+        if windmill_common::METRICS_ENABLED.load(std::sync::atomic::Ordering::Relaxed) {
+            Some(prometheus::register_int_counter_vec!(
+                "compilation_cache_evicted_bytes_total",
+                "Size of the compilation artifacts evicted to stay under the size limit",
+                &["tier"]
+            ).unwrap())
+        } else {
+            None
+        };
+}
+
+fn max_size_from_env(var: &str, default: u64) -> u64 {
+    match std::env::var(var) {
+        Ok(v) => parse_byte_size(&v).map(|s| s as u64).unwrap_or_else(|| {
+            tracing::warn!("{var}={v:?} is not a byte size (e.g. 10GB), using the default");
+            default
+        }),
+        Err(_) => default,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArtifactKind {
+    #[cfg(feature = "rust")]
+    RustDeps,
+    GoBuild,
+    #[cfg(feature = "csharp")]
+    NugetPackage,
+    #[cfg(feature = "java")]
+    JavaDeps,
+}
+
+impl ArtifactKind {
+    fn as_str(&self) -> &'static str {
+        match self {
+            #[cfg(feature = "rust")]
+            ArtifactKind::RustDeps => "rust_deps",
+            ArtifactKind::GoBuild => "go_build",
+            #[cfg(feature = "csharp")]
+            ArtifactKind::NugetPackage => "nuget_package",
+            #[cfg(feature = "java")]
+            ArtifactKind::JavaDeps => "java_deps",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArtifactKey {
+    kind: ArtifactKind,
+    hash: String,
+}
+
+impl ArtifactKey {
+    /// The key of the artifacts `toolchain` compiles from `inputs` on this platform. Inputs
+    /// are length-prefixed so that moving bytes from one to the next changes the key.
+    pub fn new(kind: ArtifactKind, toolchain: &str, inputs: &[&str]) -> Self {
+        let mut hasher = Sha256::new();
+        for part in [kind.as_str(), TARGET, toolchain].iter().chain(inputs) {
+            hasher.update((part.len() as u64).to_le_bytes());
+            hasher.update(part.as_bytes());
+        }
+        ArtifactKey { kind, hash: hex::encode(hasher.finalize()) }
+    }
+
+    fn local_path(&self) -> String {
+        format!(
+            "{}/{}/{}.tar",
+            *COMPILATION_CACHE_DIR,
+            self.kind.as_str(),
+            self.hash
+        )
+    }
+
+    #[cfg(all(feature = "enterprise", feature = "parquet"))]
+    fn remote_path(&self) -> String {
+        format!(
+            "{}{}/{}.tar",
+            remote_prefix(),
+            self.kind.as_str(),
+            self.hash
+        )
+    }
+}
+
+#[cfg(all(feature = "enterprise", feature = "parquet"))]
+fn remote_prefix() -> String {
+    format!("compilation_cache/{TARGET}/")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CacheHit {
+    Local,
+    ObjectStore,
+}
+
+impl CacheHit {
+    /// A line for the job logs.
+    pub fn describe(&self, what: &str) -> String {
+        match self {
+            CacheHit::Local => format!("\nrestored {what} from the compilation cache\n"),
+            CacheHit::ObjectStore => {
+                format!("\nrestored {what} from the compilation cache of the object store\n")
+            }
+        }
+    }
+}
+
+fn record_request(_kind: ArtifactKind, _result: &str) {
+    #[cfg(feature = "prometheus")]
+    if let Some(counter) = COMPILATION_CACHE_REQUESTS.as_ref() {
+        counter.with_label_values(&[_kind.as_str(), _result]).inc();
+    }
+}
+
+fn record_evicted(_tier: &str, _bytes: u64) {
+    #[cfg(feature = "prometheus")]
+    if let Some(counter) = COMPILATION_CACHE_EVICTED_BYTES.as_ref() {
+        counter.with_label_values(&[_tier]).inc_by(_bytes);
+    }
+}
+
+/// The version a toolchain reports, e.g. `rustc -vV`. `None` when it cannot be run, in which
+/// case nothing should be cached: an artifact is only reusable by the toolchain that built it.
+pub async fn toolchain_version(
+    program: &str,
+    args: &[&str],
+    envs: &[(&str, &str)],
+) -> Option<String> {
+    if *DISABLE_COMPILATION_CACHE {
+        return None;
+    }
+    let modified = tokio::fs::metadata(program)
+        .await
+        .ok()
+        .and_then(|m| m.modified().ok());
+    let memo_key = (format!("{program} {}", args.join(" ")), modified);
+    if let Some(version) = TOOLCHAIN_VERSIONS.lock().unwrap().get(&memo_key) {
+        return Some(version.clone());
+    }
+    let output = Command::new(program)
+        .env_clear()
+        .envs(envs.iter().copied())
+        .args(args)
+        .output()
+        .await;
+    let version = match output {
+        Ok(output) if output.status.success() => {
+            String::from_utf8_lossy(&output.stdout).trim().to_string()
+        }
+        Ok(output) => {
+            tracing::warn!(
+                "compilation cache disabled for {program}: `{program} {}` exited with {}",
+                args.join(" "),
+                output.status
+            );
+            return None;
+        }
+        Err(e) => {
+            tracing::warn!("compilation cache disabled for {program}: {e}");
+            return None;
+        }
+    };
+    TOOLCHAIN_VERSIONS
+        .lock()
+        .unwrap()
+        .insert(memo_key, version.clone());
+    Some(version)
+}
+
+/// Unpacks the artifact of `key` into `dest`, from the worker's disk or else from the object
+/// store. `None` on a miss.
+pub async fn restore(key: &ArtifactKey, dest: &str) -> Option<CacheHit> {
+    if *DISABLE_COMPILATION_CACHE {
+        return None;
+    }
+    let local_path = key.local_path();
+    let hit = if tokio::fs::metadata(&local_path).await.is_ok() {
+        CacheHit::Local
+    } else if fetch_from_object_store(key, &local_path).await {
+        CacheHit::ObjectStore
+    } else {
+        record_request(key.kind, "miss");
+        return None;
+    };
+
+    let (tar_path, dest_dir) = (local_path.clone(), dest.to_string());
+    let unpacked =
+        tokio::task::spawn_blocking(move || unpack_skipping_existing(&tar_path, &dest_dir)).await;
+    match unpacked {
+        Ok(Ok(_)) => {
+            // Marks the artifact as recently used for the eviction of the local tier
+            if let Ok(file) = std::fs::File::options().append(true).open(&local_path) {
+                let _ = file.set_modified(SystemTime::now());
+            }
+            record_request(
+                key.kind,
+                match hit {
+                    CacheHit::Local => "local_hit",
+                    CacheHit::ObjectStore => "object_store_hit",
+                },
+            );
+            Some(hit)
+        }
+        Ok(Err(e)) => {
+            tracing::error!("could not unpack compilation artifact {local_path}: {e:?}");
+            // A truncated artifact would fail every later restore as well
+            let _ = tokio::fs::remove_file(&local_path).await;
+            record_request(key.kind, "miss");
+            None
+        }
+        Err(e) => {
+            tracing::error!("could not unpack compilation artifact {local_path}: {e:?}");
+            record_request(key.kind, "miss");
+            None
+        }
+    }
+}
+
+#[cfg(all(feature = "enterprise", feature = "parquet"))]
+async fn fetch_from_object_store(key: &ArtifactKey, local_path: &str) -> bool {
+    let Some(os) = windmill_object_store::get_object_store().await else {
+        return false;
+    };
+    let Ok(mut bytes) = windmill_object_store::attempt_fetch_bytes(os, &key.remote_path()).await
+    else {
+        return false;
+    };
+    let written = std::fs::create_dir_all(Path::new(local_path).parent().unwrap())
+        .map_err(Error::from)
+        .and_then(|_| windmill_common::worker::write_binary_file(local_path, &mut bytes));
+    if let Err(e) = written {
+        tracing::error!("could not write compilation artifact {local_path}: {e:?}");
+        return false;
+    }
+    schedule_eviction();
+    true
+}
+
+#[cfg(not(all(feature = "enterprise", feature = "parquet")))]
+async fn fetch_from_object_store(_key: &ArtifactKey, _local_path: &str) -> bool {
+    false
+}
+
+/// Stores the files and directories at `paths`, relative to `root`, as the artifact of `key`
+/// on the worker and in the object store, then evicts the oldest artifacts if a tier
+/// outgrew its limit.
+pub async fn store(key: &ArtifactKey, root: &str, paths: Vec<String>) -> error::Result<()> {
+    if *DISABLE_COMPILATION_CACHE || paths.is_empty() {
+        return Ok(());
+    }
+    let local_path = key.local_path();
+    let tmp_path = format!("{local_path}.tmp.{}", uuid::Uuid::new_v4());
+    let (tar_path, root) = (tmp_path.clone(), root.to_string());
+    let packed = tokio::task::spawn_blocking(move || pack(&root, &paths, &tar_path))
+        .await
+        .map_err(|e| Error::internal_err(format!("packing compilation artifact: {e}")))
+        .and_then(|r| r)
+        .and_then(|_| std::fs::rename(&tmp_path, &local_path).map_err(Error::from));
+    if let Err(e) = packed {
+        let _ = tokio::fs::remove_file(&tmp_path).await;
+        return Err(e);
+    }
+
+    let _size = tokio::fs::metadata(&local_path).await?.len();
+    #[cfg(feature = "prometheus")]
+    if let Some(counter) = COMPILATION_CACHE_STORED_BYTES.as_ref() {
+        counter
+            .with_label_values(&[key.kind.as_str()])
+            .inc_by(_size);
+    }
+
+    #[cfg(all(feature = "enterprise", feature = "parquet"))]
+    if let Some(os) = windmill_object_store::get_object_store().await {
+        use windmill_object_store::object_store_reexports::Path as ObjectPath;
+        if let Err(e) = os
+            .put(
+                &ObjectPath::from(key.remote_path()),
+                tokio::fs::read(&local_path).await?.into(),
+            )
+            .await
+        {
+            tracing::error!(
+                "could not push compilation artifact {} to the object store: {e:?}",
+                key.remote_path()
+            );
+        }
+    }
+
+    schedule_eviction();
+    Ok(())
+}
+
+fn schedule_eviction() {
+    {
+        let mut last = LAST_EVICTION.lock().unwrap();
+        if last.is_some_and(|last| last.elapsed() < EVICTION_INTERVAL) {
+            return;
+        }
+        *last = Some(Instant::now());
+    }
+    tokio::spawn(async {
+        let evicted = tokio::task::spawn_blocking(|| {
+            evict_local(Path::new(COMPILATION_CACHE_DIR.as_str()), *LOCAL_MAX_SIZE)
+        })
+        .await;
+        match evicted {
+            Ok(Ok(evicted)) => record_evicted("local", evicted),
+            Ok(Err(e)) => tracing::error!("could not evict local compilation artifacts: {e:?}"),
+            Err(e) => tracing::error!("could not evict local compilation artifacts: {e:?}"),
+        }
+        #[cfg(all(feature = "enterprise", feature = "parquet"))]
+        if let Err(e) = evict_object_store(*OBJECT_STORE_MAX_SIZE).await {
+            tracing::error!("could not evict compilation artifacts from the object store: {e:?}");
+        }
+    });
+}
+
+/// Removes the least recently used artifacts under `dir` until they fit in `max_size`.
+/// Returns the bytes removed.
+fn evict_local(dir: &Path, max_size: u64) -> std::io::Result<u64> {
+    let mut artifacts = vec![];
+    for kind_dir in std::fs::read_dir(dir)? {
+        let kind_dir = kind_dir?;
+        if !kind_dir.file_type()?.is_dir() {
+            continue;
+        }
+        for entry in std::fs::read_dir(kind_dir.path())? {
+            let entry = entry?;
+            // Artifacts being written are named `<hash>.tar.tmp.<uuid>`
+            if entry.path().extension().is_none_or(|ext| ext != "tar") {
+                continue;
+            }
+            let metadata = entry.metadata()?;
+            artifacts.push((metadata.modified()?, metadata.len(), entry.path()));
+        }
+    }
+    let mut total = artifacts.iter().map(|(_, size, _)| size).sum::<u64>();
+    artifacts.sort();
+    let mut evicted = 0;
+    for (_, size, path) in artifacts {
+        if total <= max_size {
+            break;
+        }
+        std::fs::remove_file(&path)?;
+        total -= size;
+        evicted += size;
+    }
+    Ok(evicted)
+}
+
+#[cfg(all(feature = "enterprise", feature = "parquet"))]
+async fn evict_object_store(max_size: u64) -> error::Result<()> {
+    use futures::TryStreamExt;
+    use windmill_object_store::object_store_reexports::Path as ObjectPath;
+
+    let Some(os) = windmill_object_store::get_object_store().await else {
+        return Ok(());
+    };
+    let mut artifacts = os
+        .list(Some(&ObjectPath::from(remote_prefix())))
+        .try_collect::<Vec<_>>()
+        .await
+        .map_err(windmill_object_store::object_store_error_to_error)?;
+    let mut total = artifacts.iter().map(|meta| meta.size).sum::<u64>();
+    artifacts.sort_by_key(|meta| meta.last_modified);
+    let mut evicted = 0;
+    for meta in artifacts {
+        if total <= max_size {
+            break;
+        }
+        os.delete(&meta.location)
+            .await
+            .map_err(windmill_object_store::object_store_error_to_error)?;
+        total -= meta.size;
+        evicted += meta.size;
+    }
+    record_evicted("object_store", evicted);
+    Ok(())
+}
+
+fn pack(root: &str, paths: &[String], tar_path: &str) -> error::Result<()> {
+    std::fs::create_dir_all(Path::new(tar_path).parent().unwrap())?;
+    let mut tar = tar::Builder::new(std::fs::File::create(tar_path)?);
+    for path in paths {
+        let source = Path::new(root).join(path);
+        if source.is_dir() {
+            tar.append_dir_all(path, &source)?;
+        } else {
+            tar.append_path_with_name(&source, path)?;
+        }
+    }
+    // Writes the trailing blocks before the archive is renamed into place
+    tar.into_inner()?;
+    Ok(())
+}
+
+/// Unpacks the regular files of an artifact into `dest`, keeping their modification time
+/// (cargo compares it to decide what to rebuild). Files already there are left untouched and
+/// the others are renamed into place, so a concurrent build only ever sees whole files.
+fn unpack_skipping_existing(tar_path: &str, dest: &str) -> error::Result<usize> {
+    let mut archive = tar::Archive::new(std::fs::File::open(tar_path)?);
+    let mut created_dirs = HashSet::new();
+    let mut unpacked = 0;
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let relative = entry.path()?.into_owned();
+        // Only relative paths going down, as `tar::Entry::unpack_in` requires as well
+        let mut target = PathBuf::from(dest);
+        for component in relative.components() {
+            match component {
+                Component::Normal(part) => target.push(part),
+                Component::CurDir => {}
+                _ => {
+                    return Err(Error::ExecutionErr(format!(
+                        "compilation artifact {tar_path} holds an invalid path: {}",
+                        relative.display()
+                    )))
+                }
+            }
+        }
+        if target.exists() {
+            continue;
+        }
+        let parent = target.parent().unwrap().to_path_buf();
+        if !created_dirs.contains(&parent) {
+            std::fs::create_dir_all(&parent)?;
+            created_dirs.insert(parent);
+        }
+        let tmp = target.with_file_name(format!(
+            ".{}.tmp.{}",
+            target.file_name().unwrap().to_string_lossy(),
+            uuid::Uuid::new_v4()
+        ));
+        entry.set_preserve_mtime(true);
+        if let Err(e) = entry
+            .unpack(&tmp)
+            .and_then(|_| std::fs::rename(&tmp, &target))
+        {
+            let _ = std::fs::remove_file(&tmp);
+            return Err(e.into());
+        }
+        unpacked += 1;
+    }
+    Ok(unpacked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(path: &Path, content: &str) {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+
+    #[test]
+    fn keys_change_with_every_input() {
+        let key = |toolchain: &str, inputs: &[&str]| {
+            ArtifactKey::new(ArtifactKind::GoBuild, toolchain, inputs).hash
+        };
+        let base = key("go version go1.22.1", &["go.mod", "go.sum"]);
+        assert_eq!(base, key("go version go1.22.1", &["go.mod", "go.sum"]));
+        assert_ne!(base, key("go version go1.22.2", &["go.mod", "go.sum"]));
+        assert_ne!(base, key("go version go1.22.1", &["go.mo", "dgo.sum"]));
+        assert_ne!(base, key("", &["go version go1.22.1", "go.mod", "go.sum"]));
+    }
+
+    #[test]
+    fn unpacking_keeps_existing_files() {
+        let tmp = tempfile::tempdir().unwrap();
+        let (src, dest) = (tmp.path().join("src"), tmp.path().join("dest"));
+        write(&src.join("deps/liba.rlib"), "a");
+        write(&src.join(".fingerprint/a-1/lib-a"), "fingerprint");
+        write(&src.join("deps/libb.rlib"), "b");
+        let tar_path = tmp.path().join("artifact.tar");
+        let tar_path = tar_path.to_str().unwrap();
+        pack(
+            src.to_str().unwrap(),
+            &["deps/liba.rlib".to_string(), ".fingerprint".to_string()],
+            tar_path,
+        )
+        .unwrap();
+
+        write(&dest.join("deps/liba.rlib"), "in use");
+        let unpacked = unpack_skipping_existing(tar_path, dest.to_str().unwrap()).unwrap();
+        assert_eq!(unpacked, 1);
+        assert_eq!(
+            std::fs::read_to_string(dest.join("deps/liba.rlib")).unwrap(),
+            "in use"
+        );
+        assert_eq!(
+            std::fs::read_to_string(dest.join(".fingerprint/a-1/lib-a")).unwrap(),
+            "fingerprint"
+        );
+        assert!(!dest.join("deps/libb.rlib").exists());
+    }
+
+    #[test]
+    fn eviction_removes_the_least_recently_used_first() {
+        let tmp = tempfile::tempdir().unwrap();
+        let now = SystemTime::now();
+        for (name, age) in [("old", 30), ("recent", 10), ("newest", 0)] {
+            let path = tmp.path().join(format!("rust_deps/{name}.tar"));
+            write(&path, "0123456789");
+            let file = std::fs::File::options().append(true).open(&path).unwrap();
+            file.set_modified(now - Duration::from_secs(age)).unwrap();
+        }
+        write(
+            &tmp.path().join("rust_deps/partial.tar.tmp.1"),
+            "0123456789",
+        );
+
+        assert_eq!(evict_local(tmp.path(), 25).unwrap(), 10);
+        assert!(!tmp.path().join("rust_deps/old.tar").exists());
+        assert!(tmp.path().join("rust_deps/recent.tar").exists());
+        assert!(tmp.path().join("rust_deps/partial.tar.tmp.1").exists());
+        assert_eq!(evict_local(tmp.path(), 25).unwrap(), 0);
+    }
+}
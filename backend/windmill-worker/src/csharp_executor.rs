@@ -15,6 +15,8 @@ use tokio::{fs::File, io::AsyncReadExt, process::Command};
 #[cfg(feature = "csharp")]
 use windmill_common::{utils::calculate_hash, worker::write_file};
 
+#[cfg(feature = "csharp")]
+use crate::compilation_cache::{self, ArtifactKey, ArtifactKind, CacheHit};
 #[cfg(feature = "csharp")]
 use crate::global_cache::save_cache;
 use windmill_common::error::{self, Error};
@@ -348,6 +350,85 @@ namespace WindmillScriptCSharpInternal {{
     Ok(())
 }
 
+/// A package pinned by `packages.lock.json`, with the folder of the global packages folder
+/// NuGet extracts it to.
+#[cfg(feature = "csharp")]
+struct NugetPackage {
+    key: ArtifactKey,
+    folder: String,
+}
+
+#[cfg(feature = "csharp")]
+impl NugetPackage {
+    /// Restores the folder next to its final place and then moves it there, as NuGet takes a
+    /// folder holding `.nupkg.metadata` for a complete one.
+    async fn restore(&self) -> Option<CacheHit> {
+        let tmp_folder = format!("{}.tmp.{}", self.folder, Uuid::new_v4());
+        let hit = compilation_cache::restore(&self.key, &tmp_folder).await;
+        if hit.is_some() && tokio::fs::rename(&tmp_folder, &self.folder).await.is_ok() {
+            return hit;
+        }
+        let _ = tokio::fs::remove_dir_all(&tmp_folder).await;
+        None
+    }
+
+    async fn store(&self) -> error::Result<()> {
+        if tokio::fs::metadata(format!("{}/.nupkg.metadata", self.folder))
+            .await
+            .is_err()
+        {
+            return Ok(());
+        }
+        compilation_cache::store(&self.key, &self.folder, vec![".".to_string()]).await
+    }
+}
+
+/// The packages of the lockfile missing from the global packages folder. A package is keyed
+/// on the `contentHash` the lockfile pins rather than on the SDK version: what NuGet extracts
+/// does not depend on the toolchain.
+#[cfg(feature = "csharp")]
+async fn missing_nuget_packages(job_dir: &str) -> Vec<NugetPackage> {
+    let Ok(lock) = tokio::fs::read_to_string(format!("{job_dir}/packages.lock.json")).await else {
+        return vec![];
+    };
+    let Ok(lock) = serde_json::from_str::<serde_json::Value>(&lock) else {
+        return vec![];
+    };
+    let mut packages: Vec<NugetPackage> = vec![];
+    let frameworks = lock.get("dependencies").and_then(|d| d.as_object());
+    for (name, dep) in frameworks
+        .into_iter()
+        .flat_map(|frameworks| frameworks.values())
+        .filter_map(|deps| deps.as_object())
+        .flatten()
+    {
+        let (Some(version), Some(content_hash)) = (
+            dep.get("resolved").and_then(|v| v.as_str()),
+            dep.get("contentHash").and_then(|v| v.as_str()),
+        ) else {
+            continue;
+        };
+        let (name, version) = (name.to_lowercase(), version.to_lowercase());
+        let folder = format!("{}/nuget/{name}/{version}", *CSHARP_CACHE_DIR);
+        if packages.iter().any(|p| p.folder == folder)
+            || tokio::fs::metadata(format!("{folder}/.nupkg.metadata"))
+                .await
+                .is_ok()
+        {
+            continue;
+        }
+        packages.push(NugetPackage {
+            key: ArtifactKey::new(
+                ArtifactKind::NugetPackage,
+                "",
+                &[&name, &version, content_hash],
+            ),
+            folder,
+        });
+    }
+    packages
+}
+
 #[cfg(feature = "csharp")]
 async fn build_cs_proj(
     job_id: &Uuid,
@@ -376,6 +457,20 @@ async fn build_cs_proj(
         }
     }
 
+    let missing_packages = missing_nuget_packages(job_dir).await;
+    let restored_packages =
+        futures::future::join_all(missing_packages.iter().map(|package| package.restore())).await;
+    let restored_count = restored_packages.iter().filter(|r| r.is_some()).count();
+    if restored_count > 0 {
+        append_logs(
+            job_id,
+            w_id,
+            format!("\nrestored {restored_count} NuGet packages from the compilation cache\n"),
+            conn,
+        )
+        .await;
+    }
+
     let mut build_cs_cmd = Command::new(DOTNET_PATH.as_str());
     build_cs_cmd
         .current_dir(job_dir)
@@ -444,6 +539,13 @@ async fn build_cs_proj(
     )
     .await?;
     append_logs(job_id, w_id, "\n\n", conn).await;
+    for (package, restored) in missing_packages.iter().zip(restored_packages) {
+        if restored.is_none() {
+            if let Err(e) = package.store().await {
+                tracing::error!("could not store NuGet package {}: {e:?}", package.folder);
+            }
+        }
+    }
     if let Err(e) = std::fs::remove_file(Path::new(job_dir).join("nuget.config")) {
         if e.kind() != io::ErrorKind::NotFound {
             Err(anyhow!("Error erasing nuget.config: {}", e))?;
@@ -0,0 +1,668 @@
+//! Enforcement of egress allowlists (see `windmill_common::egress`) for the processes of a job.
+//!
+//! Every process a restricted job spawns gets its own network namespace, set up between fork and
+//! exec, where only the loopback interface exists. Before exec the child binds the listening
+//! sockets of a proxy on that loopback and hands them to the worker over a socketpair: the worker
+//! accepts the connections and dials out from its own namespace, so the only way out of the
+//! namespace is through the proxy, whatever the job does with `HTTP(S)_PROXY`.
+//!
+//! The proxy speaks HTTP `CONNECT` and plain absolute-form HTTP requests, resolves the target
+//! once, checks it against the job's policy and connects to the checked addresses only. Blocked
+//! attempts are written to the job logs and to the audit logs. The internal url of the worker is
+//! always reachable: if it is on the loopback, its port is forwarded into the namespace as is.
+//!
+//! The namespace needs CAP_SYS_ADMIN or unprivileged user namespaces, and doesn't go along with
+//! nsjail, which sets up its own.
+//!
+//! Languages the worker runs itself (nativets, Lua, WebAssembly and the SQL and GraphQL
+//! executors) connect from the worker's namespace, their jobs are refused when restricted.
+
+use std::collections::HashSet;
+use std::ffi::CString;
+use std::future::Future;
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use nix::libc;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::{JoinHandle, JoinSet};
+use uuid::Uuid;
+use windmill_audit::audit_oss::audit_log;
+use windmill_audit::ActionKind;
+use windmill_common::audit::AuditAuthor;
+use windmill_common::egress::EgressPolicy;
+use windmill_common::error::Error;
+use windmill_common::ssrf::resolve_host;
+use windmill_common::worker::Connection;
+use windmill_queue::{append_logs, MiniPulledJob};
+
+pub const PROXY_PORT: u16 = 3128;
+
+const MAX_HEAD_SIZE: usize = 64 * 1024;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+tokio::task_local! {
+    static CURRENT_JOB: Arc<JobEgress>;
+}
+
+pub struct JobEgress {
+    job_id: Uuid,
+    w_id: String,
+    conn: Connection,
+    author: AuditAuthor,
+    policy: EgressPolicy,
+    /// Host and port of the worker's internal url, always reachable
+    internal: Option<(String, u16)>,
+    tasks: Mutex<Vec<JoinHandle<()>>>,
+    blocked: Mutex<HashSet<(String, u16)>>,
+}
+
+impl JobEgress {
+    pub fn new(
+        job: &MiniPulledJob,
+        conn: &Connection,
+        base_internal_url: &str,
+        policy: EgressPolicy,
+    ) -> Self {
+        let internal = url::Url::parse(base_internal_url).ok().and_then(|u| {
+            let host = u.host_str()?.trim_matches(|c| c == '[' || c == ']');
+            Some((host.to_ascii_lowercase(), u.port_or_known_default()?))
+        });
+        Self {
+            job_id: job.id,
+            w_id: job.workspace_id.clone(),
+            conn: conn.clone(),
+            author: AuditAuthor {
+                username: job.permissioned_as.trim_start_matches("u/").to_string(),
+                email: job.permissioned_as_email.clone(),
+                username_override: None,
+                token_prefix: None,
+            },
+            policy,
+            internal,
+            tasks: Mutex::new(vec![]),
+            blocked: Mutex::new(HashSet::new()),
+        }
+    }
+
+    fn is_internal(&self, host: &str, port: u16) -> bool {
+        self.internal
+            .as_ref()
+            .is_some_and(|(h, p)| *p == port && h.eq_ignore_ascii_case(host))
+    }
+
+    /// The internal url's port, if it has to be forwarded into the namespace.
+    fn forwarded_port(&self) -> Option<u16> {
+        let (host, port) = self.internal.as_ref()?;
+        let loopback = host == "localhost"
+            || host
+                .parse::<IpAddr>()
+                .is_ok_and(|ip| ip.is_loopback() || ip.is_unspecified());
+        loopback.then_some(*port)
+    }
+
+    fn spawn(&self, task: impl Future<Output = ()> + Send + 'static) {
+        self.tasks.lock().unwrap().push(tokio::spawn(task));
+    }
+
+    async fn report_blocked(&self, host: &str, port: u16, reason: &str) {
+        if !self
+            .blocked
+            .lock()
+            .unwrap()
+            .insert((host.to_string(), port))
+        {
+            return;
+        }
+        tracing::warn!(job_id = %self.job_id, "egress to {host}:{port} blocked: {reason}");
+        append_logs(
+            &self.job_id,
+            &self.w_id,
+            format!("\n[egress] blocked connection to {host}:{port}: {reason}\n"),
+            &self.conn,
+        )
+        .await;
+        if let Connection::Sql(db) = &self.conn {
+            let port = port.to_string();
+            let job_id = self.job_id.to_string();
+            let params = [("host", host), ("port", port.as_str()), ("reason", reason)];
+            if let Err(e) = audit_log(
+                db,
+                &self.author,
+                "jobs.egress_blocked",
+                ActionKind::Execute,
+                &self.w_id,
+                Some(&job_id),
+                Some(params.into_iter().collect()),
+            )
+            .await
+            {
+                tracing::error!(job_id = %self.job_id, "could not audit blocked egress: {e:#}");
+            }
+        }
+    }
+}
+
+/// Runs a job's executor so that the processes it spawns can only reach what `egress` allows.
+pub async fn scope<F: Future>(egress: JobEgress, f: F) -> F::Output {
+    if !egress.policy.is_restricted() {
+        return f.await;
+    }
+    // The proxy tasks keep the egress alive, so they are aborted when the job is done
+    let egress = Arc::new(egress);
+    let _guard = AbortOnDrop(egress.clone());
+    CURRENT_JOB.scope(egress, f).await
+}
+
+struct AbortOnDrop(Arc<JobEgress>);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        for task in self.0.tasks.lock().unwrap().drain(..) {
+            task.abort();
+        }
+    }
+}
+
+/// Whether the processes spawned by the current job have their egress filtered.
+pub fn is_restricted() -> bool {
+    CURRENT_JOB.try_with(|_| ()).is_ok()
+}
+
+/// The network namespace of the next process spawned by the current job.
+pub struct JobNetns {
+    job: Arc<JobEgress>,
+    parent_sock: OwnedFd,
+    child_sock: OwnedFd,
+    proxy_port: u16,
+    forwarded_port: Option<u16>,
+}
+
+impl JobNetns {
+    /// Prepares the namespace of the next process spawned by the current job, if the job runs
+    /// within [`scope`].
+    pub fn for_current_job() -> Result<Option<Self>, Error> {
+        let Ok(job) = CURRENT_JOB.try_with(|job| job.clone()) else {
+            return Ok(None);
+        };
+        let mut fds = [0; 2];
+        // SAFETY: fds has room for the two descriptors
+        let r = unsafe {
+            libc::socketpair(
+                libc::AF_UNIX,
+                libc::SOCK_DGRAM | libc::SOCK_CLOEXEC,
+                0,
+                fds.as_mut_ptr(),
+            )
+        };
+        if r != 0 {
+            return Err(Error::ExecutionErr(format!(
+                "Could not set up the egress proxy: {}",
+                io::Error::last_os_error()
+            )));
+        }
+        // SAFETY: both descriptors were just created and are owned by nobody else
+        let (parent_sock, child_sock) =
+            unsafe { (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) };
+        let forwarded_port = job.forwarded_port();
+        let proxy_port = if forwarded_port == Some(PROXY_PORT) {
+            PROXY_PORT + 1
+        } else {
+            PROXY_PORT
+        };
+        Ok(Some(Self {
+            job,
+            parent_sock,
+            child_sock,
+            proxy_port,
+            forwarded_port,
+        }))
+    }
+
+    pub fn proxy_url(&self) -> String {
+        format!("http://127.0.0.1:{}", self.proxy_port)
+    }
+
+    /// Hosts the job must reach without the proxy.
+    pub fn no_proxy(&self) -> &'static str {
+        if self.forwarded_port.is_some() {
+            "localhost,127.0.0.1,::1"
+        } else {
+            ""
+        }
+    }
+
+    /// What the child needs between fork and exec, allocated beforehand.
+    pub fn child_setup(&self) -> ChildSetup {
+        // SAFETY: getuid and getgid can't fail
+        let (uid, gid) = unsafe { (libc::getuid(), libc::getgid()) };
+        let map = |s: String| CString::new(s).expect("no nul byte in an id map");
+        ChildSetup {
+            sock: self.child_sock.as_raw_fd(),
+            ports: [Some(self.proxy_port), self.forwarded_port],
+            uid_map: map(format!("{uid} {uid} 1")),
+            gid_map: map(format!("{gid} {gid} 1")),
+        }
+    }
+
+    /// Starts serving the sockets the child bound in its namespace. Called once the child is
+    /// spawned, which means the hook ran and sent them.
+    pub fn serve(self) -> Result<(), Error> {
+        drop(self.child_sock);
+        let mut listeners = recv_listeners(self.parent_sock.as_raw_fd())
+            .and_then(|fds| {
+                fds.into_iter()
+                    .map(|fd| {
+                        let listener = std::net::TcpListener::from(fd);
+                        listener.set_nonblocking(true)?;
+                        TcpListener::from_std(listener)
+                    })
+                    .collect::<io::Result<Vec<_>>>()
+            })
+            .map_err(|e| Error::ExecutionErr(format!("Could not set up the egress proxy: {e}")))?
+            .into_iter();
+
+        let job = self.job;
+        if let Some(listener) = listeners.next() {
+            let job2 = job.clone();
+            job.spawn(accept_loop(listener, move |stream| {
+                handle_proxy_conn(job2.clone(), stream)
+            }));
+        }
+        if let (Some(listener), Some((host, port))) = (listeners.next(), job.internal.clone()) {
+            job.spawn(accept_loop(listener, move |mut stream| {
+                let host = host.clone();
+                async move {
+                    if let Ok(mut upstream) = TcpStream::connect((host.as_str(), port)).await {
+                        let _ = tokio::io::copy_bidirectional(&mut stream, &mut upstream).await;
+                    }
+                }
+            }));
+        }
+        Ok(())
+    }
+}
+
+pub struct ChildSetup {
+    sock: RawFd,
+    ports: [Option<u16>; 2],
+    uid_map: CString,
+    gid_map: CString,
+}
+
+impl ChildSetup {
+    /// Moves the calling process to a new network namespace and sends the listening sockets of
+    /// the proxy to the worker. Meant to run between fork and exec: it only issues syscalls on
+    /// memory allocated before fork.
+    pub fn enter_from_child(&self) -> io::Result<()> {
+        // SAFETY: every pointer handed to libc points to memory owned by self or the stack
+        unsafe {
+            if libc::unshare(libc::CLONE_NEWNET) != 0 {
+                // Without CAP_SYS_ADMIN, a user namespace grants it over the new network one
+                if libc::unshare(libc::CLONE_NEWUSER | libc::CLONE_NEWNET) != 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                write_file(c"/proc/self/setgroups", c"deny")?;
+                write_file(c"/proc/self/uid_map", &self.uid_map)?;
+                write_file(c"/proc/self/gid_map", &self.gid_map)?;
+            }
+            loopback_up()?;
+
+            let mut fds = [-1; 2];
+            let mut n = 0;
+            for port in self.ports.iter().flatten() {
+                fds[n] = listen_loopback(*port)?;
+                n += 1;
+            }
+            send_fds(self.sock, &fds[..n])
+        }
+    }
+}
+
+unsafe fn write_file(path: &std::ffi::CStr, content: &std::ffi::CStr) -> io::Result<()> {
+    let fd = libc::open(path.as_ptr(), libc::O_WRONLY | libc::O_CLOEXEC);
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let len = content.to_bytes().len();
+    let written = libc::write(fd, content.as_ptr().cast(), len);
+    libc::close(fd);
+    if written != len as isize {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+unsafe fn loopback_up() -> io::Result<()> {
+    let fd = libc::socket(libc::AF_INET, libc::SOCK_DGRAM | libc::SOCK_CLOEXEC, 0);
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let mut req: libc::ifreq = std::mem::zeroed();
+    req.ifr_name[..2].copy_from_slice(&[b'l' as libc::c_char, b'o' as libc::c_char]);
+    let mut r = libc::ioctl(fd, libc::SIOCGIFFLAGS as _, &mut req);
+    if r == 0 {
+        req.ifr_ifru.ifru_flags |= (libc::IFF_UP | libc::IFF_RUNNING) as libc::c_short;
+        r = libc::ioctl(fd, libc::SIOCSIFFLAGS as _, &req);
+    }
+    let err = io::Error::last_os_error();
+    libc::close(fd);
+    if r != 0 {
+        return Err(err);
+    }
+    Ok(())
+}
+
+unsafe fn listen_loopback(port: u16) -> io::Result<RawFd> {
+    let fd = libc::socket(libc::AF_INET, libc::SOCK_STREAM | libc::SOCK_CLOEXEC, 0);
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    let mut addr: libc::sockaddr_in = std::mem::zeroed();
+    addr.sin_family = libc::AF_INET as libc::sa_family_t;
+    addr.sin_port = port.to_be();
+    addr.sin_addr.s_addr = u32::from(Ipv4Addr::LOCALHOST).to_be();
+    let len = std::mem::size_of::<libc::sockaddr_in>() as libc::socklen_t;
+    if libc::bind(fd, &addr as *const _ as *const libc::sockaddr, len) != 0
+        || libc::listen(fd, 128) != 0
+    {
+        let err = io::Error::last_os_error();
+        libc::close(fd);
+        return Err(err);
+    }
+    Ok(fd)
+}
+
+/// Room for the control message of up to 2 descriptors, aligned for cmsghdr.
+type CmsgBuf = [u64; 8];
+
+unsafe fn send_fds(sock: RawFd, fds: &[RawFd]) -> io::Result<()> {
+    let mut data = [0u8; 1];
+    let mut iov = libc::iovec { iov_base: data.as_mut_ptr().cast(), iov_len: 1 };
+    let mut cmsg_buf: CmsgBuf = [0; 8];
+    let fds_len = std::mem::size_of_val(fds) as u32;
+    let mut msg: libc::msghdr = std::mem::zeroed();
+    msg.msg_iov = &mut iov;
+    msg.msg_iovlen = 1;
+    msg.msg_control = cmsg_buf.as_mut_ptr().cast();
+    msg.msg_controllen = libc::CMSG_SPACE(fds_len) as _;
+    let cmsg = libc::CMSG_FIRSTHDR(&msg);
+    (*cmsg).cmsg_level = libc::SOL_SOCKET;
+    (*cmsg).cmsg_type = libc::SCM_RIGHTS;
+    (*cmsg).cmsg_len = libc::CMSG_LEN(fds_len) as _;
+    std::ptr::copy_nonoverlapping(fds.as_ptr(), libc::CMSG_DATA(cmsg).cast(), fds.len());
+    if libc::sendmsg(sock, &msg, 0) < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+fn recv_listeners(sock: RawFd) -> io::Result<Vec<OwnedFd>> {
+    let mut data = [0u8; 1];
+    let mut iov = libc::iovec { iov_base: data.as_mut_ptr().cast(), iov_len: 1 };
+    let mut cmsg_buf: CmsgBuf = [0; 8];
+    // SAFETY: the message points to buffers that outlive the call, and the descriptors of the
+    // control message are fresh ones the kernel installed for us
+    unsafe {
+        let mut msg: libc::msghdr = std::mem::zeroed();
+        msg.msg_iov = &mut iov;
+        msg.msg_iovlen = 1;
+        msg.msg_control = cmsg_buf.as_mut_ptr().cast();
+        msg.msg_controllen = std::mem::size_of::<CmsgBuf>() as _;
+        if libc::recvmsg(sock, &mut msg, libc::MSG_DONTWAIT | libc::MSG_CMSG_CLOEXEC) < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let mut fds = vec![];
+        let mut cmsg = libc::CMSG_FIRSTHDR(&msg);
+        while !cmsg.is_null() {
+            if (*cmsg).cmsg_level == libc::SOL_SOCKET && (*cmsg).cmsg_type == libc::SCM_RIGHTS {
+                let len = (*cmsg).cmsg_len as usize - libc::CMSG_LEN(0) as usize;
+                let data = libc::CMSG_DATA(cmsg) as *const RawFd;
+                for i in 0..len / std::mem::size_of::<RawFd>() {
+                    fds.push(OwnedFd::from_raw_fd(data.add(i).read_unaligned()));
+                }
+            }
+            cmsg = libc::CMSG_NXTHDR(&msg, cmsg);
+        }
+        if fds.is_empty() {
+            return Err(io::Error::other("no socket received"));
+        }
+        Ok(fds)
+    }
+}
+
+/// The connections are served by tasks of the loop, aborted with it when the job is done.
+async fn accept_loop<H, Fut>(listener: TcpListener, handler: H)
+where
+    H: Fn(TcpStream) -> Fut,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let mut conns = JoinSet::new();
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                while conns.try_join_next().is_some() {}
+                conns.spawn(handler(stream));
+            }
+            Err(e) => {
+                tracing::warn!("egress proxy stopped accepting connections: {e}");
+                return;
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum ProxyRequest {
+    Connect {
+        host: String,
+        port: u16,
+    },
+    /// A plain HTTP request, with its head rewritten to be sent to the origin
+    Forward {
+        host: String,
+        port: u16,
+        head: Vec<u8>,
+    },
+}
+
+/// Parses the head of a request sent to the proxy, up to and including the empty line.
+fn parse_request(head: &[u8]) -> Result<ProxyRequest, &'static str> {
+    let head = std::str::from_utf8(head).map_err(|_| "request head is not valid UTF-8")?;
+    let mut lines = head.split("\r\n");
+    let request_line = lines.next().ok_or("empty request")?;
+    let mut parts = request_line.split(' ');
+    let (method, target, version) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(m), Some(t), Some(v)) if parts.next().is_none() => (m, t, v),
+        _ => return Err("malformed request line"),
+    };
+
+    if method.eq_ignore_ascii_case("CONNECT") {
+        let (host, port) = split_host_port(target, None).ok_or("malformed CONNECT target")?;
+        return Ok(ProxyRequest::Connect { host, port });
+    }
+
+    let rest = target
+        .strip_prefix("http://")
+        .ok_or("only http:// urls can be proxied, use CONNECT for https")?;
+    let (authority, path) = match rest.find(['/', '?']) {
+        Some(i) => (&rest[..i], &rest[i..]),
+        None => (rest, "/"),
+    };
+    let authority = authority.rsplit('@').next().unwrap_or(authority);
+    let (host, port) = split_host_port(authority, Some(80)).ok_or("malformed url")?;
+    let path = if path.starts_with('?') {
+        format!("/{path}")
+    } else {
+        path.to_string()
+    };
+
+    let mut out = format!("{method} {path} {version}\r\n");
+    for line in lines.filter(|l| !l.is_empty()) {
+        let name = line.split(':').next().unwrap_or_default().trim();
+        if [
+            "proxy-connection",
+            "proxy-authorization",
+            "connection",
+            "keep-alive",
+        ]
+        .iter()
+        .any(|h| name.eq_ignore_ascii_case(h))
+        {
+            continue;
+        }
+        out.push_str(line);
+        out.push_str("\r\n");
+    }
+    // One request per connection, so that each target goes through the checks
+    out.push_str("Connection: close\r\n\r\n");
+    Ok(ProxyRequest::Forward { host, port, head: out.into_bytes() })
+}
+
+fn split_host_port(authority: &str, default_port: Option<u16>) -> Option<(String, u16)> {
+    let (host, port) = if let Some(rest) = authority.strip_prefix('[') {
+        let (host, rest) = rest.split_once(']')?;
+        (host, rest.strip_prefix(':'))
+    } else {
+        match authority.rsplit_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (authority, None),
+        }
+    };
+    let port = match port {
+        Some(p) => p.parse().ok()?,
+        None => default_port?,
+    };
+    (!host.is_empty()).then(|| (host.to_ascii_lowercase(), port))
+}
+
+async fn handle_proxy_conn(job: Arc<JobEgress>, mut stream: TcpStream) {
+    let mut buf = Vec::with_capacity(4096);
+    let head_len = loop {
+        if let Some(i) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+            break i + 4;
+        }
+        if buf.len() >= MAX_HEAD_SIZE {
+            return respond(&mut stream, "431 Request Header Fields Too Large").await;
+        }
+        let mut chunk = [0u8; 4096];
+        match stream.read(&mut chunk).await {
+            Ok(0) | Err(_) => return,
+            Ok(n) => buf.extend_from_slice(&chunk[..n]),
+        }
+    };
+    let request = match parse_request(&buf[..head_len]) {
+        Ok(r) => r,
+        Err(e) => {
+            tracing::debug!(job_id = %job.job_id, "egress proxy: bad request: {e}");
+            return respond(&mut stream, "400 Bad Request").await;
+        }
+    };
+    let (host, port) = match &request {
+        ProxyRequest::Connect { host, port } | ProxyRequest::Forward { host, port, .. } => {
+            (host.clone(), *port)
+        }
+    };
+
+    let addrs: Vec<SocketAddr> = match host.parse::<IpAddr>() {
+        Ok(ip) => vec![SocketAddr::new(ip, port)],
+        Err(_) => match resolve_host(&host, port).await {
+            Ok(addrs) => addrs,
+            Err(e) => {
+                tracing::debug!(job_id = %job.job_id, "egress proxy: {e}");
+                return respond(&mut stream, "502 Bad Gateway").await;
+            }
+        },
+    };
+    if !job.is_internal(&host, port) {
+        let ips: Vec<IpAddr> = addrs.iter().map(|a| a.ip()).collect();
+        if !job.policy.permits(&host, port, &ips) {
+            respond(&mut stream, "403 Forbidden").await;
+            return job
+                .report_blocked(&host, port, "not in the egress allowlist")
+                .await;
+        }
+    }
+
+    let mut upstream =
+        match tokio::time::timeout(CONNECT_TIMEOUT, TcpStream::connect(&addrs[..])).await {
+            Ok(Ok(upstream)) => upstream,
+            _ => return respond(&mut stream, "502 Bad Gateway").await,
+        };
+    let pending = match request {
+        ProxyRequest::Connect { .. } => {
+            if stream
+                .write_all(b"HTTP/1.1 200 Connection established\r\n\r\n")
+                .await
+                .is_err()
+            {
+                return;
+            }
+            buf[head_len..].to_vec()
+        }
+        ProxyRequest::Forward { mut head, .. } => {
+            head.extend_from_slice(&buf[head_len..]);
+            head
+        }
+    };
+    if upstream.write_all(&pending).await.is_ok() {
+        let _ = tokio::io::copy_bidirectional(&mut stream, &mut upstream).await;
+    }
+}
+
+async fn respond(stream: &mut TcpStream, status: &str) {
+    let response = format!("HTTP/1.1 {status}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n");
+    let _ = stream.write_all(response.as_bytes()).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_connect() {
+        assert_eq!(
+            parse_request(b"CONNECT api.github.com:443 HTTP/1.1\r\nHost: api.github.com\r\n\r\n"),
+            Ok(ProxyRequest::Connect { host: "api.github.com".to_string(), port: 443 })
+        );
+        assert_eq!(
+            parse_request(b"CONNECT [::1]:8443 HTTP/1.1\r\n\r\n"),
+            Ok(ProxyRequest::Connect { host: "::1".to_string(), port: 8443 })
+        );
+        assert!(parse_request(b"CONNECT api.github.com HTTP/1.1\r\n\r\n").is_err());
+    }
+
+    #[test]
+    fn rewrites_absolute_form_requests() {
+        let head = b"GET http://Example.com:8080/a/b?c=d HTTP/1.1\r\nHost: example.com:8080\r\n\
+            Proxy-Connection: keep-alive\r\nProxy-Authorization: Basic eA==\r\nAccept: */*\r\n\r\n";
+        assert_eq!(
+            parse_request(head),
+            Ok(ProxyRequest::Forward {
+                host: "example.com".to_string(),
+                port: 8080,
+                head: b"GET /a/b?c=d HTTP/1.1\r\nHost: example.com:8080\r\nAccept: */*\r\n\
+                    Connection: close\r\n\r\n"
+                    .to_vec(),
+            })
+        );
+        match parse_request(b"GET http://user:pw@example.com?x HTTP/1.0\r\n\r\n") {
+            Ok(ProxyRequest::Forward { host, port, head }) => {
+                assert_eq!((host.as_str(), port), ("example.com", 80));
+                assert!(head.starts_with(b"GET /?x HTTP/1.0\r\n"));
+            }
+            r => panic!("unexpected {r:?}"),
+        }
+    }
+
+    #[test]
+    fn rejects_origin_form_and_https() {
+        assert!(parse_request(b"GET /index.html HTTP/1.1\r\nHost: example.com\r\n\r\n").is_err());
+        assert!(parse_request(b"GET https://example.com/ HTTP/1.1\r\n\r\n").is_err());
+        assert!(parse_request(b"garbage\r\n\r\n").is_err());
+    }
+}
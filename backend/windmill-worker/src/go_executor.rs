@@ -1,7 +1,12 @@
 use crate::{common::MaybeLock, get_proxy_envs_for_lang};
-use std::{collections::HashMap, fs::DirBuilder, process::Stdio};
+use std::{
+    collections::{HashMap, HashSet},
+    fs::DirBuilder,
+    process::Stdio,
+};
 use windmill_common::scripts::ScriptLang;
 
+use crate::compilation_cache::{self, toolchain_version, ArtifactKey, ArtifactKind};
 use crate::global_cache::save_cache;
 use itertools::Itertools;
 use serde_json::value::RawValue;
@@ -234,6 +239,126 @@ fn go_cache_key(code: &str, maybe_lock: &MaybeLock) -> String {
     calculate_hash(&format!("{}{:?}v2", code, maybe_lock))
 }
 
+/// Key of the build cache entries of the dependencies of a module, which are the same for
+/// every script with the same `go.mod` and `go.sum`.
+async fn go_build_artifact_key(job_dir: &str) -> Option<ArtifactKey> {
+    let toolchain = toolchain_version(
+        GO_PATH.as_str(),
+        &["version"],
+        &[("PATH", PATH_ENV.as_str()), ("HOME", HOME_ENV.as_str())],
+    )
+    .await?;
+    let go_mod = fs::read_to_string(format!("{job_dir}/go.mod")).await.ok()?;
+    let go_sum = fs::read_to_string(format!("{job_dir}/go.sum"))
+        .await
+        .unwrap_or_default();
+    Some(ArtifactKey::new(
+        ArtifactKind::GoBuild,
+        &toolchain,
+        &[&go_mod, &go_sum],
+    ))
+}
+
+/// Stores the build cache entries of the standard library and module packages the build
+/// compiled, leaving out the script's own. `go list -export` names their outputs, and the
+/// action entries pointing to those are what a later build looks up.
+async fn store_go_build(key: &ArtifactKey, job_dir: &str) -> error::Result<()> {
+    let mut list_cmd = Command::new(GO_PATH.as_str());
+    list_cmd
+        .current_dir(job_dir)
+        .env_clear()
+        .env("PATH", PATH_ENV.as_str())
+        .env("GOPATH", {
+            #[cfg(unix)]
+            {
+                GO_CACHE_DIR.as_str()
+            }
+            #[cfg(windows)]
+            {
+                &windows_gopath()
+            }
+        })
+        .env("HOME", HOME_ENV.as_str())
+        .env("GOCACHE", GO_CACHE_DIR.as_str())
+        // The build just downloaded every module it needs
+        .env("GOPROXY", "off")
+        .args([
+            "list",
+            "-deps",
+            "-export",
+            "-f",
+            "{{if or .Standard (and .Module (not .Module.Main))}}{{.Export}}{{end}}",
+            "main.go",
+        ]);
+    #[cfg(windows)]
+    set_windows_env_vars(&mut list_cmd);
+
+    let output = list_cmd.output().await?;
+    if !output.status.success() {
+        return Err(Error::ExecutionErr(format!(
+            "go list -export failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+    let cache_dir = std::path::Path::new(GO_CACHE_DIR.as_str());
+    let mut paths = vec![];
+    let mut output_ids = HashSet::new();
+    for export in String::from_utf8_lossy(&output.stdout).lines() {
+        let Ok(relative) = std::path::Path::new(export.trim()).strip_prefix(cache_dir) else {
+            continue;
+        };
+        if let Some(id) = relative
+            .file_name()
+            .and_then(|name| name.to_str()?.strip_suffix("-d"))
+        {
+            output_ids.insert(id.to_string());
+        }
+        paths.push(relative.to_string_lossy().to_string());
+    }
+    let actions =
+        tokio::task::spawn_blocking(move || go_action_entries(GO_CACHE_DIR.as_str(), &output_ids))
+            .await
+            .map_err(|e| Error::internal_err(format!("listing go build cache: {e}")))??;
+    paths.extend(actions);
+    compilation_cache::store(key, GO_CACHE_DIR.as_str(), paths).await
+}
+
+/// The action entries of the build cache pointing to one of `output_ids`. They are the
+/// `<action id>-a` files of its two hex digit shards, holding
+/// `v1 <action id> <output id> <size> <time>`.
+fn go_action_entries(
+    cache_dir: &str,
+    output_ids: &HashSet<String>,
+) -> std::io::Result<Vec<String>> {
+    let mut entries = vec![];
+    for shard in std::fs::read_dir(cache_dir)? {
+        let shard = shard?;
+        let shard_name = shard.file_name().to_string_lossy().to_string();
+        if shard_name.len() != 2
+            || !shard_name.chars().all(|c| c.is_ascii_hexdigit())
+            || !shard.file_type()?.is_dir()
+        {
+            continue;
+        }
+        for entry in std::fs::read_dir(shard.path())? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !name.ends_with("-a") {
+                continue;
+            }
+            let action = std::fs::read_to_string(entry.path()).unwrap_or_default();
+            if action
+                .split_whitespace()
+                .nth(2)
+                .is_some_and(|id| output_ids.contains(id))
+            {
+                entries.push(format!("{shard_name}/{name}"));
+            }
+        }
+    }
+    Ok(entries)
+}
+
 /// Install the deps, generate the entrypoint wrapper, `go build`, and push the binary to
 /// the shared cache. `job_dir` must already be the `go` subdirectory the module lives in.
 async fn build_go_binary(
@@ -355,6 +480,21 @@ return main({spread})
         }
     }
 
+    let artifact_key = go_build_artifact_key(job_dir).await;
+    let restored = match &artifact_key {
+        Some(key) => compilation_cache::restore(key, GO_CACHE_DIR.as_str()).await,
+        None => None,
+    };
+    if let Some(hit) = restored {
+        append_logs(
+            &job.id,
+            &job.workspace_id,
+            hit.describe("the compiled packages"),
+            conn,
+        )
+        .await;
+    }
+
     let toolchain_envs = go_toolchain_envs(GoToolchainStep::Build);
     let build_args = [
         vec!["build".to_string()],
@@ -409,6 +549,12 @@ return main({spread})
     )
     .await?;
 
+    if let (Some(key), None) = (&artifact_key, restored) {
+        if let Err(e) = store_go_build(key, job_dir).await {
+            tracing::error!("could not store the compiled packages of {hash}: {e:?}");
+        }
+    }
+
     #[cfg(unix)]
     let executable_path = format!("{job_dir}/main");
     #[cfg(windows)]
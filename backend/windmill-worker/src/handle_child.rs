@@ -136,6 +136,12 @@ pub async fn handle_child(
 
     let pid = child.id();
     #[cfg(target_os = "linux")]
+    let job_cgroup = pid.and_then(crate::job_cgroup::take);
+    #[cfg(target_os = "linux")]
+    let job_cgroup_ref = job_cgroup.as_deref();
+    #[cfg(target_os = "linux")]
+    let oom_kills_before = job_cgroup.as_ref().map_or(0, |cgroup| cgroup.oom_kills());
+    #[cfg(target_os = "linux")]
     if let Some(pid) = pid {
         let oom_score_adj = *windmill_common::worker::JOB_OOM_SCORE_ADJ;
         // procfs handles writes synchronously in-kernel; no fsync (it returns
@@ -171,6 +177,10 @@ pub async fn handle_child(
         mem_peak,
         canceled_by_ref,
         Box::pin(stream::unfold((), move |_| async move {
+            #[cfg(target_os = "linux")]
+            if let Some(cgroup) = job_cgroup_ref {
+                return Some((cgroup.memory_peak_kb(), ()));
+            }
             Some((get_mem_peak(pid, nsjail).await, ()))
         })),
         worker,
@@ -347,6 +357,26 @@ pub async fn handle_child(
     let success = wait_result.is_ok()
         && wait_result.as_ref().unwrap().is_ok()
         && wait_result.as_ref().unwrap().as_ref().unwrap().success();
+
+    #[cfg(target_os = "linux")]
+    if let Some(cgroup) = job_cgroup.as_ref() {
+        *mem_peak = (*mem_peak).max(cgroup.memory_peak_kb());
+        if let Some(cpu_usec) = cgroup.cpu_usage_usec() {
+            record_job_cpu_time(conn, w_id, job_id, cpu_usec).await;
+        }
+        if !success && cgroup.oom_kills() > oom_kills_before {
+            let limit = cgroup
+                .limits
+                .memory_max_mb
+                .map(|mb| format!(" of {mb}MB"))
+                .unwrap_or_default();
+            return Err(Error::ExecutionErr(format!(
+                "job process '{child_name}' was killed by the OOM killer after reaching its memory \
+                limit{limit} (peak memory: {}MB)",
+                *mem_peak / 1024
+            )));
+        }
+    }
     tracing::info!(%job_id, %success, %mem_peak, %worker, "child process '{child_name}' took {}ms", start.elapsed().as_millis());
 
     match wait_result {
@@ -371,6 +401,40 @@ pub async fn handle_child(
 
 pub const WAC_STEP_PREFIX: &str = "WM_WAC_STEP: ";
 
+/// Stores the CPU time the job's processes used so far, as measured by their cgroups.
+#[cfg(target_os = "linux")]
+async fn record_job_cpu_time(conn: &Connection, w_id: &str, job_id: Uuid, cpu_usec: u64) {
+    let Connection::Sql(db) = conn else {
+        return;
+    };
+    if job_id == Uuid::nil() {
+        return;
+    }
+    let cpu_ms = i32::try_from(cpu_usec / 1000).unwrap_or(i32::MAX);
+    let recorded = async {
+        let metric_id = job_metrics::register_metric_for_job(
+            db,
+            w_id.to_string(),
+            job_id,
+            "cpu_time_ms".to_string(),
+            job_metrics::MetricKind::ScalarInt,
+            Some("Job CPU Time (ms)".to_string()),
+        )
+        .await?;
+        job_metrics::record_metric(
+            db,
+            w_id.to_string(),
+            job_id,
+            metric_id,
+            job_metrics::MetricNumericValue::Integer(cpu_ms),
+        )
+        .await
+    };
+    if let Err(err) = recorded.await {
+        tracing::error!(%job_id, "Unable to save cpu time for job {job_id}: {err:?}");
+    }
+}
+
 async fn write_lines(
     output: impl stream::Stream<Item = io::Result<OutputLine>> + Send,
     job_id: &Uuid,
@@ -4,6 +4,7 @@ use std::{
     process::Stdio,
 };
 
+use crate::compilation_cache::{self, toolchain_version, ArtifactKey, ArtifactKind};
 use crate::global_cache::save_cache;
 use anyhow::{anyhow, bail};
 use async_recursion::async_recursion;
@@ -50,6 +51,33 @@ lazy_static::lazy_static! {
 
 const NSJAIL_CONFIG_RUN_JAVA_CONTENT: &str = include_str!("../nsjail/run.java.config.proto");
 
+/// The languages run on the JVM toolchain: they share dependency resolution,
+/// the maven repository and the `java` launcher, and differ in the compiler.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum JvmLang {
+    Java,
+    #[cfg(feature = "kotlin")]
+    Kotlin,
+}
+
+impl JvmLang {
+    fn name(self) -> &'static str {
+        match self {
+            JvmLang::Java => "java",
+            #[cfg(feature = "kotlin")]
+            JvmLang::Kotlin => "kotlin",
+        }
+    }
+
+    fn lockfile_resolution(self) -> &'static str {
+        match self {
+            JvmLang::Java => "java lockfile resolution",
+            #[cfg(feature = "kotlin")]
+            JvmLang::Kotlin => "kotlin lockfile resolution",
+        }
+    }
+}
+
 #[allow(dead_code)]
 pub(crate) struct JobHandlerInput<'a> {
     pub base_internal_url: &'a str,
@@ -86,7 +114,7 @@ pub async fn handle_java_job<'a>(mut args: JobHandlerInput<'a>) -> Result<Box<Ra
 
     // --- Install ---
 
-    let classpath = install(&mut args, deps).await?;
+    let classpath = install(&mut args, deps, "target").await?;
 
     // --- Build .java files ---
     {
@@ -94,7 +122,7 @@ pub async fn handle_java_job<'a>(mut args: JobHandlerInput<'a>) -> Result<Box<Ra
     }
     // --- Run ---
     {
-        run(&mut args, &classpath).await?;
+        run(&mut args, &classpath, JvmLang::Java).await?;
     }
     // --- Retrieve results ---
     {
@@ -185,6 +213,19 @@ pub async fn resolve<'a>(
     job_dir: &str,
     conn: &Connection,
     w_id: &str,
+) -> Result<String, Error> {
+    resolve_jvm(job_id, code, job_dir, conn, w_id, JvmLang::Java).await
+}
+
+/// Resolves the `//requirements:` of a script into the lockfile of every
+/// artifact to fetch, transitive ones included.
+pub(crate) async fn resolve_jvm(
+    job_id: &Uuid,
+    code: &str,
+    job_dir: &str,
+    conn: &Connection,
+    w_id: &str,
+    lang: JvmLang,
 ) -> Result<String, Error> {
     let deps = {
         let find_requirements = code.lines().find_position(|x| {
@@ -215,7 +256,7 @@ pub async fn resolve<'a>(
     };
 
     let ws_suffix = crate::workspace_registry_cache_suffix(w_id).await;
-    let req_hash = format!("java-{}{ws_suffix}", calculate_hash(&deps));
+    let req_hash = format!("{}-{}{ws_suffix}", lang.name(), calculate_hash(&deps));
     if let Connection::Sql(db) = conn {
         if let Some(cached) = sqlx::query_scalar!(
             "SELECT lockfile FROM pip_resolution_cache WHERE hash = $1",
@@ -305,12 +346,13 @@ pub async fn resolve<'a>(
         // wedged registry connection cannot park the job in `running` forever.
         cmd.kill_on_drop(true);
         let (timeout, ..) = resolve_job_timeout(conn, w_id, *job_id, None).await;
-        let _heartbeat = JobPingHeartbeat::start(conn, *job_id, "java lockfile resolution");
+        let _heartbeat = JobPingHeartbeat::start(conn, *job_id, lang.lockfile_resolution());
         let output = tokio::time::timeout(timeout, cmd.output())
             .await
             .map_err(|_| {
                 Error::ExecutionErr(format!(
-                    "resolving the java lockfile timed out after {}s",
+                    "resolving the {} lockfile timed out after {}s",
+                    lang.name(),
                     timeout.as_secs()
                 ))
             })??;
@@ -338,11 +380,14 @@ pub async fn resolve<'a>(
     Ok(lock)
 }
 
-async fn install<'a>(
+/// Fetches the resolved artifacts into the maven repository and returns the
+/// classpath to run with, ending with `target`, where the compiled script is.
+pub(crate) async fn install<'a>(
     JobHandlerInput { worker_name, job, conn, job_dir, .. }: &mut JobHandlerInput<'a>,
-    deps: String,
+    lock: String,
+    target: &str,
 ) -> Result<String, Error> {
-    let deps = deps
+    let deps = lock
         .lines()
         .map(|line| {
             let unparsed_dep = line.replace(":jar", "").replace(":lib", "");
@@ -373,7 +418,8 @@ async fn install<'a>(
         .map(|RequiredDependency { path, .. }| path + "/*")
         .collect_vec()
         .join(":")
-        + ":target";
+        + ":"
+        + target;
 
     #[cfg(windows)]
     let classpath = classpath.replace(":", ";");
@@ -388,6 +434,33 @@ async fn install<'a>(
         get_no_default(job.id, &job.workspace_id, conn).await,
         metadata(TRUST_STORE_PATH.clone()).await,
     );
+
+    // Only restored when a jar is missing, and then only stored if none was restored: the
+    // jars already in the repository may have been fetched for other lockfiles.
+    let mut any_missing = false;
+    for RequiredDependency { path, .. } in &deps {
+        any_missing |= metadata(format!("{path}.valid.windmill")).await.is_err();
+    }
+    let artifact_key = if any_missing {
+        java_deps_artifact_key(&lock, &repos, &no_default).await
+    } else {
+        None
+    };
+    let restored = match &artifact_key {
+        Some(key) => compilation_cache::restore(key, JAVA_REPOSITORY_DIR.as_str()).await,
+        None => None,
+    };
+    if let Some(hit) = restored {
+        append_logs(
+            &job.id,
+            &job.workspace_id,
+            hit.describe("the dependency jars"),
+            conn,
+        )
+        .await;
+    }
+    let required = deps.clone();
+
     let job_dir = job_dir.to_owned();
     let fetch_dir = format!("{}/tmp-fetch-{}", *JAVA_CACHE_DIR, Uuid::new_v4());
     let (cmd_fetch_dir, postinstall_fetch_dir) = (fetch_dir.clone(), fetch_dir.clone());
@@ -492,9 +565,63 @@ async fn install<'a>(
         _ => {}
     }
     installed?;
+
+    if let (Some(key), None) = (&artifact_key, restored) {
+        if let Err(e) = store_java_deps(key, &required).await {
+            tracing::error!("could not store the dependency jars of the java lockfile: {e:?}");
+        }
+    }
     Ok(classpath)
 }
 
+/// Key of the jars fetched for a lockfile, which are the same for every script with the same
+/// lockfile and repositories. `None` when the java version cannot be read.
+async fn java_deps_artifact_key(
+    lock: &str,
+    repos: &[String],
+    no_default: &str,
+) -> Option<ArtifactKey> {
+    // `java -version` prints to stderr, `--version` the same to stdout
+    let toolchain = toolchain_version(
+        if cfg!(windows) {
+            "java"
+        } else {
+            JAVA_PATH.as_str()
+        },
+        &["--version"],
+        &[("PATH", PATH_ENV.as_str())],
+    )
+    .await?;
+    Some(ArtifactKey::new(
+        ArtifactKind::JavaDeps,
+        &toolchain,
+        &[lock, &repos.join(" "), no_default],
+    ))
+}
+
+/// Stores the directories of the dependencies in the maven repository, then their markers so
+/// that a restored dependency is only marked valid once its jars are in place.
+async fn store_java_deps(key: &ArtifactKey, deps: &[RequiredDependency<()>]) -> error::Result<()> {
+    let prefix = format!("{}/", *JAVA_REPOSITORY_DIR);
+    let relative = deps
+        .iter()
+        .filter_map(|RequiredDependency { path, .. }| path.strip_prefix(&prefix))
+        .collect_vec();
+    let mut paths = vec![];
+    for path in &relative {
+        if metadata(format!("{prefix}{path}")).await.is_ok() {
+            paths.push(path.to_string());
+        }
+    }
+    for path in &relative {
+        let marker = format!("{path}.valid.windmill");
+        if metadata(format!("{prefix}{marker}")).await.is_ok() {
+            paths.push(marker);
+        }
+    }
+    compilation_cache::store(key, JAVA_REPOSITORY_DIR.as_str(), paths).await
+}
+
 /// Copies every fetched artifact out of coursier's cache into the `<group as path>/<artifact>/
 /// <version>` location the classpath is built from. Coursier's cache is laid out as
 /// `<scheme>/<host>/<registry url path>/<group as path>/...`, so the depth at which the maven
@@ -736,7 +863,7 @@ async fn compile<'a>(
 
     Ok(())
 }
-async fn run<'a>(
+pub(crate) async fn run<'a>(
     JobHandlerInput {
         occupancy_metrics,
         mem_peak,
@@ -753,6 +880,7 @@ async fn run<'a>(
         ..
     }: &mut JobHandlerInput<'a>,
     classpath: &'a str,
+    lang: JvmLang,
 ) -> Result<(), Error> {
     let reserved_variables =
         get_reserved_variables(job, &client.token, conn, parent_runnable_path.clone()).await?;
@@ -761,7 +889,10 @@ async fn run<'a>(
         append_logs(
             &job.id,
             &job.workspace_id,
-            format!("\n--- ISOLATED JAVA CODE EXECUTION ---\n"),
+            format!(
+                "\n--- ISOLATED {} CODE EXECUTION ---\n",
+                lang.name().to_uppercase()
+            ),
             &conn,
         )
         .await;
@@ -831,7 +962,7 @@ async fn run<'a>(
         append_logs(
             &job.id,
             &job.workspace_id,
-            format!("\n--- JAVA CODE EXECUTION ---\n"),
+            format!("\n--- {} CODE EXECUTION ---\n", lang.name().to_uppercase()),
             &conn,
         )
         .await;
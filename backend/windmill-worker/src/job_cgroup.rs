@@ -0,0 +1,415 @@
+//! Per-job cgroup v2 limits for jobs that are not sandboxed by nsjail.
+//!
+//! Once the worker delegated its controllers (see `delegate_to_jobs` in the windmill binary),
+//! every process a job spawns is started in the job's cgroup under the `jobs` cgroup, with
+//! `memory.max`, `cpu.max` and `pids.max` set from the worker group and script limits, so the
+//! limits apply to all the processes of the job together. The cgroup also gives the job's peak
+//! memory, CPU time and OOM kills without polling `/proc`. Without delegation, processes are
+//! spawned as before and share the worker's cgroup.
+
+use std::collections::HashMap;
+use std::ffi::CString;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+
+use uuid::Uuid;
+use windmill_common::worker::JobResourceLimits;
+
+const CPU_PERIOD_USEC: u64 = 100_000;
+
+static JOBS_ROOT: OnceLock<PathBuf> = OnceLock::new();
+
+lazy_static::lazy_static! {
+    /// Cgroups of the spawned processes, until `handle_child` picks theirs up by pid.
+    static ref CHILD_CGROUPS: Mutex<HashMap<u32, Arc<JobCgroup>>> = Mutex::new(HashMap::new());
+}
+
+tokio::task_local! {
+    static CURRENT_JOB: JobContext;
+}
+
+#[derive(Clone)]
+struct JobContext {
+    job_id: Uuid,
+    limits: JobResourceLimits,
+    /// Created by the first process the job spawns.
+    cgroup: Arc<OnceLock<Option<Arc<JobCgroup>>>>,
+}
+
+/// Turns on per-job cgroups, with `jobs_root` the cgroup the job cgroups are created in.
+pub fn enable(jobs_root: PathBuf) {
+    let _ = JOBS_ROOT.set(jobs_root);
+}
+
+pub fn is_enabled() -> bool {
+    JOBS_ROOT.get().is_some()
+}
+
+/// Runs a job's executor so that the processes it spawns get the job's limits.
+pub async fn scope<F: Future>(job_id: Uuid, limits: JobResourceLimits, f: F) -> F::Output {
+    if !is_enabled() {
+        if !limits.is_empty() {
+            tracing::warn!(
+                %job_id,
+                "job resource limits {limits:?} are not applied: per-job cgroups are not enabled \
+                on this worker"
+            );
+        }
+        return f.await;
+    }
+    let cgroup = Arc::new(OnceLock::new());
+    let ctx = JobContext { job_id, limits, cgroup: cgroup.clone() };
+    let output = CURRENT_JOB.scope(ctx, f).await;
+    if let Some(Some(cgroup)) = cgroup.get() {
+        cgroup.cleanup().await;
+    }
+    output
+}
+
+pub struct JobCgroup {
+    path: PathBuf,
+    procs: CString,
+    pub limits: JobResourceLimits,
+    removed: AtomicBool,
+}
+
+impl JobCgroup {
+    /// The cgroup of the current job, created on the first call, if the job runs within
+    /// [`scope`]. Every process the job spawns shares it.
+    pub fn for_current_job() -> Option<Arc<Self>> {
+        CURRENT_JOB
+            .try_with(|ctx| {
+                ctx.cgroup
+                    .get_or_init(|| {
+                        Self::create(JOBS_ROOT.get()?, ctx.job_id, ctx.limits).map(Arc::new)
+                    })
+                    .clone()
+            })
+            .ok()
+            .flatten()
+    }
+
+    fn create(jobs_root: &Path, job_id: Uuid, limits: JobResourceLimits) -> Option<Self> {
+        let path = jobs_root.join(format!("job-{job_id}"));
+
+        // Left over by a worker that died while running the job
+        if path.exists() {
+            remove_cgroup(&path);
+        }
+        if let Err(e) = std::fs::create_dir(&path) {
+            tracing::warn!(%job_id, "could not create job cgroup {path:?}: {e}");
+            return None;
+        }
+        let procs = CString::new(path.join("cgroup.procs").to_string_lossy().as_bytes()).ok()?;
+        let cgroup = Self { path, procs, limits, removed: AtomicBool::new(false) };
+        cgroup.apply_limits();
+        Some(cgroup)
+    }
+
+    fn apply_limits(&self) {
+        let JobResourceLimits { memory_max_mb, cpu_max, pids_max } = self.limits;
+        let mut controls = vec![];
+        if let Some(mb) = memory_max_mb {
+            controls.push(("memory.max", (mb * 1024 * 1024).to_string()));
+            // Swapping would only let a job exceed its limit slowly
+            controls.push(("memory.swap.max", "0".to_string()));
+        }
+        if let Some(cpus) = cpu_max {
+            let quota = ((cpus * CPU_PERIOD_USEC as f64) as u64).max(1000);
+            controls.push(("cpu.max", format!("{quota} {CPU_PERIOD_USEC}")));
+        }
+        if let Some(pids) = pids_max {
+            controls.push(("pids.max", pids.to_string()));
+        }
+        for (file, value) in controls {
+            if let Err(e) = std::fs::write(self.path.join(file), &value) {
+                tracing::warn!(
+                    "could not set {file}={value} on job cgroup {:?}: {e}",
+                    self.path
+                );
+            }
+        }
+    }
+
+    /// Moves the calling process into the cgroup. Meant to run between fork and exec, so it
+    /// only issues syscalls on the pre-allocated path. A failure fails the spawn, a job is
+    /// never run without its limits.
+    pub fn enter_from_child(procs: &CString) -> std::io::Result<()> {
+        use nix::fcntl::{open, OFlag};
+        use nix::sys::stat::Mode;
+        let fd = open(procs.as_c_str(), OFlag::O_WRONLY, Mode::empty())?;
+        let written = nix::unistd::write(fd, b"0");
+        let _ = nix::unistd::close(fd);
+        written?;
+        Ok(())
+    }
+
+    /// Whether the worker can still move processes into the cgroup, to tell a failed spawn
+    /// caused by [`Self::enter_from_child`] from one of the executable.
+    pub fn check_joinable(&self) -> std::io::Result<()> {
+        std::fs::OpenOptions::new()
+            .write(true)
+            .open(self.path.join("cgroup.procs"))
+            .map(|_| ())
+    }
+
+    pub fn procs_path(&self) -> CString {
+        self.procs.clone()
+    }
+
+    fn read(&self, file: &str) -> Option<String> {
+        std::fs::read_to_string(self.path.join(file)).ok()
+    }
+
+    fn stat(&self, file: &str, key: &str) -> Option<u64> {
+        parse_stat(&self.read(file)?, key)
+    }
+
+    /// Peak memory of the job in kB, falling back to the current usage on kernels
+    /// older than 5.19 that have no `memory.peak`.
+    pub fn memory_peak_kb(&self) -> i32 {
+        self.read("memory.peak")
+            .or_else(|| self.read("memory.current"))
+            .and_then(|x| x.trim().parse::<u64>().ok())
+            .map(|bytes| i32::try_from(bytes / 1024).unwrap_or(i32::MAX))
+            .unwrap_or(-1)
+    }
+
+    /// CPU time used by all the processes of the job so far.
+    pub fn cpu_usage_usec(&self) -> Option<u64> {
+        self.stat("cpu.stat", "usage_usec")
+    }
+
+    /// OOM kills in the job so far, compare with the count before a process started to tell
+    /// whether it was killed.
+    pub fn oom_kills(&self) -> u64 {
+        self.stat("memory.events", "oom_kill").unwrap_or(0)
+    }
+
+    /// Kills what is left of the job's processes and removes the cgroup, off the runtime
+    /// since the removal is retried until the killed processes are gone.
+    pub async fn cleanup(&self) {
+        if self.removed.swap(true, Ordering::Relaxed) {
+            return;
+        }
+        let path = self.path.clone();
+        if let Err(e) = tokio::task::spawn_blocking(move || remove_cgroup(&path)).await {
+            tracing::warn!("could not remove job cgroup {:?}: {e}", self.path);
+        }
+    }
+}
+
+impl Drop for JobCgroup {
+    /// Only when the job's executor was dropped before [`scope`] could clean up.
+    fn drop(&mut self) {
+        if self.removed.swap(true, Ordering::Relaxed) {
+            return;
+        }
+        let path = std::mem::take(&mut self.path);
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn_blocking(move || remove_cgroup(&path));
+            }
+            Err(_) => remove_cgroup(&path),
+        }
+    }
+}
+
+/// Blocks for up to 100ms while the killed processes exit.
+fn remove_cgroup(path: &Path) {
+    // Leftover background processes would keep the cgroup busy, kill them first. cgroupfs
+    // has no file creation, only kernels from 5.14 have the file.
+    if let Ok(mut kill) = std::fs::OpenOptions::new()
+        .write(true)
+        .open(path.join("cgroup.kill"))
+    {
+        let _ = std::io::Write::write_all(&mut kill, b"1");
+    }
+    for _ in 0..10 {
+        match std::fs::remove_dir(path) {
+            Ok(()) => return,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return,
+            Err(_) => std::thread::sleep(std::time::Duration::from_millis(10)),
+        }
+    }
+    tracing::warn!("could not remove job cgroup {path:?}");
+}
+
+/// Keeps the cgroup of a spawned process until [`take`] is called with its pid.
+pub fn register(pid: u32, cgroup: Arc<JobCgroup>) {
+    let mut cgroups = CHILD_CGROUPS.lock().unwrap();
+    // Processes that never reached `handle_child`
+    cgroups.retain(|pid, _| std::path::Path::new(&format!("/proc/{pid}")).exists());
+    cgroups.insert(pid, cgroup);
+}
+
+pub fn take(pid: u32) -> Option<Arc<JobCgroup>> {
+    CHILD_CGROUPS.lock().unwrap().remove(&pid)
+}
+
+fn parse_stat(content: &str, key: &str) -> Option<u64> {
+    content.lines().find_map(|line| {
+        let (k, v) = line.split_once(' ')?;
+        if k == key {
+            v.trim().parse().ok()
+        } else {
+            None
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_flat_keyed_cgroup_files() {
+        let events = "low 0\nhigh 0\nmax 12\noom 1\noom_kill 1\noom_group_kill 0\n";
+        assert_eq!(parse_stat(events, "oom_kill"), Some(1));
+        assert_eq!(parse_stat(events, "oom"), Some(1));
+        let cpu = "usage_usec 1234567\nuser_usec 1000000\nsystem_usec 234567\n";
+        assert_eq!(parse_stat(cpu, "usage_usec"), Some(1234567));
+        assert_eq!(parse_stat(cpu, "nr_throttled"), None);
+    }
+
+    fn limits(memory_max_mb: u64, cpu_max: f64, pids_max: u64) -> JobResourceLimits {
+        JobResourceLimits {
+            memory_max_mb: Some(memory_max_mb),
+            cpu_max: Some(cpu_max),
+            pids_max: Some(pids_max),
+        }
+    }
+
+    #[test]
+    fn writes_limits_to_the_job_cgroup() {
+        let root = tempfile::tempdir().unwrap();
+        let job_id = Uuid::new_v4();
+        let cgroup = JobCgroup::create(root.path(), job_id, limits(256, 0.5, 64)).unwrap();
+
+        assert_eq!(cgroup.path, root.path().join(format!("job-{job_id}")));
+        let read = |file: &str| cgroup.read(file).unwrap();
+        assert_eq!(read("memory.max"), (256 * 1024 * 1024).to_string());
+        assert_eq!(read("memory.swap.max"), "0");
+        assert_eq!(read("cpu.max"), "50000 100000");
+        assert_eq!(read("pids.max"), "64");
+        assert_eq!(
+            cgroup.procs_path().to_str().unwrap(),
+            cgroup.path.join("cgroup.procs").to_str().unwrap()
+        );
+        cgroup.removed.store(true, Ordering::Relaxed);
+    }
+
+    #[test]
+    fn cpu_quota_has_a_floor() {
+        let root = tempfile::tempdir().unwrap();
+        let cgroup = JobCgroup::create(
+            root.path(),
+            Uuid::new_v4(),
+            JobResourceLimits { cpu_max: Some(0.001), ..Default::default() },
+        )
+        .unwrap();
+        assert_eq!(cgroup.read("cpu.max").unwrap(), "1000 100000");
+        assert!(cgroup.read("memory.max").is_none());
+        cgroup.removed.store(true, Ordering::Relaxed);
+    }
+
+    #[test]
+    fn reads_oom_kills_and_usage() {
+        let root = tempfile::tempdir().unwrap();
+        let cgroup = JobCgroup::create(root.path(), Uuid::new_v4(), Default::default()).unwrap();
+        assert_eq!(cgroup.oom_kills(), 0);
+        assert_eq!(cgroup.memory_peak_kb(), -1);
+        assert_eq!(cgroup.cpu_usage_usec(), None);
+
+        let write = |file: &str, content: &str| std::fs::write(cgroup.path.join(file), content);
+        write("memory.events", "low 0\nhigh 0\nmax 3\noom 1\noom_kill 1\n").unwrap();
+        write("memory.current", "2048000\n").unwrap();
+        write(
+            "cpu.stat",
+            "usage_usec 1500\nuser_usec 1000\nsystem_usec 500\n",
+        )
+        .unwrap();
+        assert_eq!(cgroup.oom_kills(), 1);
+        // Kernels older than 5.19 have no memory.peak
+        assert_eq!(cgroup.memory_peak_kb(), 2000);
+        assert_eq!(cgroup.cpu_usage_usec(), Some(1500));
+
+        write("memory.peak", "4096000\n").unwrap();
+        assert_eq!(cgroup.memory_peak_kb(), 4000);
+        cgroup.removed.store(true, Ordering::Relaxed);
+    }
+
+    #[tokio::test]
+    async fn removes_the_job_cgroup() {
+        let root = tempfile::tempdir().unwrap();
+        let job_id = Uuid::new_v4();
+        let path = root.path().join(format!("job-{job_id}"));
+
+        // Left over by a worker that died while running the job
+        std::fs::create_dir(&path).unwrap();
+        let cgroup = JobCgroup::create(root.path(), job_id, Default::default()).unwrap();
+        assert!(path.is_dir());
+
+        cgroup.cleanup().await;
+        assert!(!path.exists());
+        // A second cleanup, or the drop, is a no-op
+        cgroup.cleanup().await;
+        drop(cgroup);
+
+        let cgroup = JobCgroup::create(root.path(), job_id, Default::default()).unwrap();
+        drop(cgroup);
+        assert!(!path.exists());
+    }
+
+    #[tokio::test]
+    async fn falls_back_without_cgroup_v2() {
+        // No delegated hierarchy to create the cgroup in
+        let root = tempfile::tempdir().unwrap();
+        assert!(JobCgroup::create(
+            &root.path().join("missing"),
+            Uuid::new_v4(),
+            Default::default()
+        )
+        .is_none());
+
+        // Per-job cgroups are never enabled in the tests: the job runs as before
+        assert!(!is_enabled());
+        let output = scope(Uuid::new_v4(), limits(256, 1.0, 64), async {
+            JobCgroup::for_current_job().is_none()
+        })
+        .await;
+        assert!(output);
+    }
+
+    /// Runs against a real cgroup v2 hierarchy: the worker's `jobs` cgroup, or any cgroup
+    /// delegated to the user running the test with the memory controller enabled for its
+    /// children, given in `JOB_CGROUP_TEST_ROOT`.
+    #[tokio::test]
+    #[ignore = "needs a delegated cgroup v2 hierarchy, set JOB_CGROUP_TEST_ROOT"]
+    async fn kills_a_job_over_its_memory_limit() {
+        use std::os::unix::process::CommandExt;
+
+        let root = PathBuf::from(std::env::var("JOB_CGROUP_TEST_ROOT").unwrap());
+        let limits = JobResourceLimits { memory_max_mb: Some(32), ..Default::default() };
+        let cgroup = JobCgroup::create(&root, Uuid::new_v4(), limits).unwrap();
+        let path = cgroup.path.clone();
+
+        let procs = cgroup.procs_path();
+        let mut cmd = std::process::Command::new("sh");
+        // tail keeps the last line in memory, /dev/zero is a single line without end
+        cmd.args(["-c", "head -c 256M /dev/zero | tail > /dev/null"]);
+        // SAFETY: the hook only issues open/write/close syscalls on a path allocated before fork
+        unsafe {
+            cmd.pre_exec(move || JobCgroup::enter_from_child(&procs));
+        }
+        let status = cmd.status().unwrap();
+
+        assert!(!status.success());
+        assert!(cgroup.oom_kills() >= 1);
+        assert!(cgroup.memory_peak_kb() > 0);
+        cgroup.cleanup().await;
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,339 @@
+//! Kotlin scripts, on the toolchain Java scripts use: dependencies are resolved
+//! and fetched by `java_executor` from the same `//requirements:` annotations,
+//! and the compiled script runs on the same `java` launcher. Only compilation
+//! differs: `kotlinc` builds a jar that bundles the Kotlin runtime, and that jar
+//! is what gets cached, under the versions of the compiler and of the JDK it
+//! runs on.
+
+use std::process::Stdio;
+
+use itertools::Itertools;
+use serde_json::value::RawValue;
+use tokio::{
+    fs::{create_dir_all, File},
+    io::AsyncWriteExt,
+    process::Command,
+};
+use uuid::Uuid;
+use windmill_common::{error::Error, utils::calculate_hash, worker::Connection};
+use windmill_parser::Arg;
+use windmill_parser_kotlin::parse_kotlin_signature;
+use windmill_queue::append_logs;
+
+use crate::{
+    common::{create_args_and_out_file, get_reserved_variables, read_result, start_child_process},
+    global_cache::{load_cache, save_cache},
+    handle_child, is_sandboxing_enabled,
+    java_executor::{install, resolve_jvm, run, JobHandlerInput, JvmLang},
+    JAVA_HOME_DIR, KOTLIN_CACHE_DIR, PATH_ENV, PROXY_ENVS,
+};
+
+lazy_static::lazy_static! {
+    static ref KOTLINC_PATH: String = std::env::var("KOTLINC_PATH").unwrap_or_else(|_| "/usr/bin/kotlinc".to_string());
+}
+
+const SRC_DIR: &str = "src/main/kotlin/net/script";
+/// Relative to the job dir, which is where `java` runs.
+const SCRIPT_JAR: &str = "target/script.jar";
+
+pub async fn handle_kotlin_job<'a>(mut args: JobHandlerInput<'a>) -> Result<Box<RawValue>, Error> {
+    // --- Prepare ---
+    {
+        prepare(&mut args).await?;
+    }
+    // --- Generate Lockfile ---
+
+    let deps = resolve(
+        &args.job.id,
+        &args.inner_content,
+        &args.job_dir,
+        &args.conn,
+        &args.job.workspace_id,
+    )
+    .await?;
+
+    // --- Install ---
+
+    let classpath = install(&mut args, deps, SCRIPT_JAR).await?;
+
+    // --- Build .kt files ---
+    {
+        compile(&mut args, &classpath).await?;
+    }
+    // --- Run ---
+    {
+        run(&mut args, &classpath, JvmLang::Kotlin).await?;
+    }
+    // --- Retrieve results ---
+    {
+        read_result(&args.job_dir, None).await
+    }
+}
+
+pub async fn resolve(
+    job_id: &Uuid,
+    code: &str,
+    job_dir: &str,
+    conn: &Connection,
+    w_id: &str,
+) -> Result<String, Error> {
+    resolve_jvm(job_id, code, job_dir, conn, w_id, JvmLang::Kotlin).await
+}
+
+async fn prepare<'a>(
+    JobHandlerInput { job, conn, job_dir, client, inner_content, .. }: &mut JobHandlerInput<'a>,
+) -> Result<(), Error> {
+    create_args_and_out_file(&client, job, job_dir, conn).await?;
+    let src_dir = format!("{job_dir}/{SRC_DIR}");
+    create_dir_all(&src_dir).await?;
+    create_dir_all(format!("{job_dir}/target")).await?;
+    for (file, content) in [
+        ("Main.kt", format!("package net.script\n\n{inner_content}")),
+        ("App.kt", wrap(inner_content)?),
+        ("Wmill.kt", MINI_CLIENT.to_string()),
+    ] {
+        File::create(format!("{src_dir}/{file}"))
+            .await?
+            .write_all(content.as_bytes())
+            .await?;
+    }
+    Ok(())
+}
+
+async fn compile<'a>(
+    JobHandlerInput {
+        occupancy_metrics,
+        mem_peak,
+        canceled_by,
+        worker_name,
+        job,
+        conn,
+        job_dir,
+        client,
+        envs,
+        base_internal_url,
+        inner_content,
+        requirements_o,
+        parent_runnable_path,
+        ..
+    }: &mut JobHandlerInput<'a>,
+    classpath: &str,
+) -> Result<(), Error> {
+    let ws_suffix = crate::workspace_registry_cache_suffix(&job.workspace_id).await;
+    // A jar is only reused by the compiler that built it, never cached without its version
+    let cache_paths = kotlinc_version().await.map(|toolchain| {
+        let hash = format!(
+            "{}{ws_suffix}",
+            calculate_hash(&format!(
+                "{toolchain}{inner_content}{}",
+                requirements_o.map(|x| x.as_str()).unwrap_or_default()
+            ))
+        );
+        (
+            format!("{}/{hash}.jar", *KOTLIN_CACHE_DIR),
+            format!("kotlin_jar/{hash}"),
+        )
+    });
+    let jar_path = format!("{job_dir}/{SCRIPT_JAR}");
+
+    if let Some((bin_path, remote_path)) = &cache_paths {
+        let (cache, logs) = load_cache(bin_path, remote_path, false).await;
+        if cache {
+            append_logs(&job.id, &job.workspace_id, logs, conn).await;
+            // Copied rather than linked: the sandbox only mounts the java cache.
+            tokio::fs::copy(bin_path, &jar_path).await.map_err(|e| {
+                Error::ExecutionErr(format!(
+                    "could not copy cached jar from {bin_path} to {jar_path}: {e:?}"
+                ))
+            })?;
+            return Ok(());
+        }
+    }
+
+    append_logs(
+        &job.id,
+        &job.workspace_id,
+        "\n--- COMPILING .KT FILES ---\n".to_string(),
+        conn,
+    )
+    .await;
+    let reserved_variables =
+        get_reserved_variables(job, &client.token, conn, parent_runnable_path.clone()).await?;
+    // The jar itself is not on the classpath yet, only the dependencies are.
+    let deps_classpath = classpath
+        .strip_suffix(SCRIPT_JAR)
+        .unwrap_or(classpath)
+        .trim_end_matches([':', ';']);
+
+    let mut cmd = Command::new(kotlinc_path());
+    cmd.env_clear()
+        .current_dir(job_dir.to_owned())
+        .env("PATH", PATH_ENV.as_str())
+        .env("HOME", &*JAVA_HOME_DIR)
+        .env("BASE_INTERNAL_URL", base_internal_url)
+        .envs(envs)
+        .envs(reserved_variables)
+        .envs(PROXY_ENVS.clone())
+        .args(["-classpath", deps_classpath])
+        .args(["Main.kt", "App.kt", "Wmill.kt"].map(|f| format!("{SRC_DIR}/{f}")))
+        .args(["-include-runtime", "-d", SCRIPT_JAR])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if let Ok(java_home) = std::env::var("JAVA_HOME") {
+        cmd.env("JAVA_HOME", java_home);
+    }
+
+    #[cfg(windows)]
+    {
+        cmd.env("SystemRoot", crate::SYSTEM_ROOT.as_str())
+            .env("USERPROFILE", crate::USERPROFILE_ENV.as_str())
+            .env(
+                "TMP",
+                std::env::var("TMP").unwrap_or_else(|_| String::from("/tmp")),
+            );
+    }
+    let child = start_child_process(cmd, "kotlinc", false).await?;
+    handle_child::handle_child(
+        &job.id,
+        conn,
+        mem_peak,
+        canceled_by,
+        child,
+        is_sandboxing_enabled(),
+        worker_name,
+        &job.workspace_id,
+        "kotlinc",
+        job.timeout,
+        false,
+        &mut Some(occupancy_metrics),
+        None,
+        None,
+    )
+    .await?;
+
+    if let Some((bin_path, remote_path)) = &cache_paths {
+        match save_cache(bin_path, remote_path, &jar_path, false).await {
+            Err(e) => tracing::error!("could not save {jar_path} to kotlin cache: {e:?}"),
+            Ok(logs) => tracing::trace!(logs),
+        }
+    }
+    Ok(())
+}
+
+fn kotlinc_path() -> &'static str {
+    if cfg!(windows) {
+        "kotlinc"
+    } else {
+        KOTLINC_PATH.as_str()
+    }
+}
+
+/// What `kotlinc -version` prints, e.g. `info: kotlinc-jvm 2.0.0 (JRE 17.0.9+9)`: the
+/// versions of the compiler and of the JDK it runs on. Read once per worker, `None` when
+/// kotlinc cannot be run.
+async fn kotlinc_version() -> Option<&'static str> {
+    static VERSION: tokio::sync::OnceCell<Option<String>> = tokio::sync::OnceCell::const_new();
+    VERSION
+        .get_or_init(|| async {
+            let mut cmd = Command::new(kotlinc_path());
+            cmd.env_clear()
+                .env("PATH", PATH_ENV.as_str())
+                .env("HOME", &*JAVA_HOME_DIR)
+                .arg("-version");
+            if let Ok(java_home) = std::env::var("JAVA_HOME") {
+                cmd.env("JAVA_HOME", java_home);
+            }
+            #[cfg(windows)]
+            cmd.env("SystemRoot", crate::SYSTEM_ROOT.as_str());
+            match cmd.output().await {
+                // Printed to stderr, unlike `java --version`
+                Ok(output) if output.status.success() => Some(format!(
+                    "{}{}",
+                    String::from_utf8_lossy(&output.stdout).trim(),
+                    String::from_utf8_lossy(&output.stderr).trim()
+                )),
+                Ok(output) => {
+                    tracing::warn!(
+                        "kotlin jars are not cached: `kotlinc -version` exited with {}",
+                        output.status
+                    );
+                    None
+                }
+                Err(e) => {
+                    tracing::warn!("kotlin jars are not cached: could not run kotlinc: {e}");
+                    None
+                }
+            }
+        })
+        .await
+        .as_deref()
+}
+
+/// The entrypoint, in `App.kt`: reads args.json, converts every argument to the
+/// type `main` declares, and writes what `main` returns to result.json.
+fn wrap(inner_content: &str) -> Result<String, Error> {
+    let sig = parse_kotlin_signature(inner_content)?;
+    let spread = sig
+        .args
+        .into_iter()
+        .map(|Arg { name, otyp, .. }| {
+            let otyp = otyp.unwrap_or_else(|| "Any?".to_string());
+            format!(
+                "            `{name}` = mapper.convertValue(parsedArgs[\"{name}\"], object : TypeReference<{otyp}>() {{}}),"
+            )
+        })
+        .join("\n");
+    Ok(r#"package net.script
+
+import com.fasterxml.jackson.core.type.TypeReference
+import com.fasterxml.jackson.databind.ObjectMapper
+import java.io.File
+
+object App {
+    @JvmStatic
+    fun main(args: Array<String>) {
+        val mapper = ObjectMapper()
+        val parsedArgs: Map<String, Any?> =
+            mapper.readValue(File("args.json"), object : TypeReference<Map<String, Any?>>() {})
+        val res: Any? = net.script.main(
+SPREAD
+        )
+        mapper.writeValue(File("result.json"), if (res is Unit) null else res)
+    }
+}
+"#
+    .replace("SPREAD", &spread))
+}
+
+const MINI_CLIENT: &str = r#"package net.script
+
+import java.net.URI
+import java.net.URLEncoder
+import java.net.http.HttpClient
+import java.net.http.HttpRequest
+import java.net.http.HttpResponse
+
+object Wmill {
+    private fun get(route: String): String {
+        val baseUrl = System.getenv("BASE_INTERNAL_URL")
+        val workspace = System.getenv("WM_WORKSPACE")
+        val request = HttpRequest.newBuilder()
+            .uri(URI.create("$baseUrl/api/w/$workspace/$route"))
+            .header("Authorization", "Bearer " + System.getenv("WM_TOKEN"))
+            .GET()
+            .build()
+        val response = HttpClient.newHttpClient().send(request, HttpResponse.BodyHandlers.ofString())
+        if (response.statusCode() >= 300) {
+            throw RuntimeException("$route failed with ${response.statusCode()}: ${response.body()}")
+        }
+        return response.body()
+    }
+
+    fun getVariable(path: String): String = get("variables/get_value/$path")
+
+    fun getResource(path: String): String = get(
+        "resources/get_value_interpolated/$path?job_id=" +
+            URLEncoder.encode(System.getenv("WM_JOB_ID") ?: "", Charsets.UTF_8)
+    )
+}
+"#;
@@ -0,0 +1,391 @@
+//! Built-in job sandbox based on Landlock and seccomp, for workers that can't ship or run nsjail.
+//!
+//! With `job_isolation` set to `landlock` (instance-wide or in the worker group config), every
+//! process a job spawns is restricted between fork and exec:
+//! - Landlock limits the filesystem to the job directory and the worker caches (read-write) and
+//!   to the system directories (read-only), so a job can't see the directories of other jobs.
+//! - A seccomp filter denies the syscalls that could inspect other processes or escape the
+//!   sandbox (ptrace, mount, kernel modules, bpf, new user namespaces, ...).
+//! - no_new_privs keeps setuid binaries from regaining privileges.
+//!
+//! None of it needs privileges or an external binary, but Landlock needs Linux 5.13 or later:
+//! on older kernels the jobs fail instead of running unconfined. Since the worker directories
+//! usually live in /tmp, /tmp is not granted and `TMPDIR` points to the job directory instead.
+//! Other paths, like a HOME jobs need, are granted with `LANDLOCK_EXTRA_READ_PATHS` and
+//! `LANDLOCK_EXTRA_WRITE_PATHS` (colon separated).
+
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::path::{Path, PathBuf};
+
+use landlock::{
+    path_beneath_rules, Access, AccessFs, CompatLevel, Compatible, Ruleset, RulesetAttr,
+    RulesetCreated, RulesetCreatedAttr, RulesetError, RulesetStatus, Scope, ABI,
+};
+use nix::libc;
+use seccompiler::{
+    BackendError, BpfProgram, SeccompAction, SeccompCmpArgLen, SeccompCmpOp, SeccompCondition,
+    SeccompFilter, SeccompRule, TargetArch,
+};
+use windmill_common::error::Error;
+use windmill_common::worker::{ROOT_CACHE_DIR, ROOT_CACHE_NOMOUNT_DIR};
+
+/// The most recent ABI the ruleset asks for, anything the kernel doesn't know is dropped.
+const TARGET_ABI: ABI = ABI::V6;
+
+const SYSTEM_READ_PATHS: &[&str] = &[
+    "/bin", "/sbin", "/usr", "/lib", "/lib32", "/lib64", "/etc", "/opt", "/nix", "/proc", "/sys",
+    "/run",
+];
+
+const DEVICE_PATHS: &[&str] = &[
+    "/dev/null",
+    "/dev/zero",
+    "/dev/full",
+    "/dev/random",
+    "/dev/urandom",
+    "/dev/tty",
+    "/dev/ptmx",
+    "/dev/pts",
+];
+
+lazy_static::lazy_static! {
+    static ref EXTRA_READ_PATHS: Vec<String> = paths_from_env("LANDLOCK_EXTRA_READ_PATHS");
+    static ref EXTRA_WRITE_PATHS: Vec<String> = paths_from_env("LANDLOCK_EXTRA_WRITE_PATHS");
+
+    static ref SECCOMP_FILTERS: Result<[BpfProgram; 2], BackendError> = build_seccomp_filters();
+
+    /// Whether the kernel enforces Landlock, probed once with a throwaway ruleset.
+    pub static ref LANDLOCK_SUPPORTED: bool = match build_ruleset(&[], &[]) {
+        Ok(_) => true,
+        Err(e) => {
+            tracing::error!(
+                "Landlock is not supported by this kernel ({e}). Landlock sandboxing will NOT \
+                be available. If job_isolation is set to 'landlock', jobs will fail."
+            );
+            false
+        }
+    };
+}
+
+tokio::task_local! {
+    static JOB_DIR: PathBuf;
+}
+
+fn paths_from_env(var: &str) -> Vec<String> {
+    std::env::var(var)
+        .ok()
+        .map(|x| {
+            x.split(':')
+                .filter(|p| !p.trim().is_empty())
+                .map(|p| p.trim().to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Runs a job's executor so that the processes it spawns are sandboxed to `job_dir`.
+pub async fn scope<F: Future>(job_dir: &str, f: F) -> F::Output {
+    if !crate::is_landlock_enabled() {
+        return f.await;
+    }
+    JOB_DIR.scope(PathBuf::from(job_dir), f).await
+}
+
+pub struct Sandbox {
+    job_dir: PathBuf,
+    ruleset: Option<RulesetCreated>,
+    seccomp: &'static [BpfProgram; 2],
+}
+
+impl Sandbox {
+    /// Prepares the sandbox of the next process spawned by the current job, if the job runs
+    /// within [`scope`].
+    pub fn for_current_job() -> Result<Option<Self>, Error> {
+        match JOB_DIR.try_with(|job_dir| job_dir.clone()) {
+            Ok(job_dir) => Self::new(&job_dir).map(Some),
+            Err(_) => Ok(None),
+        }
+    }
+
+    pub fn new(job_dir: &Path) -> Result<Self, Error> {
+        let mut write_paths = vec![
+            job_dir.to_path_buf(),
+            PathBuf::from(ROOT_CACHE_DIR.as_str()),
+            PathBuf::from(ROOT_CACHE_NOMOUNT_DIR.as_str()),
+        ];
+        write_paths.extend(EXTRA_WRITE_PATHS.iter().map(PathBuf::from));
+        let mut read_paths: Vec<PathBuf> = SYSTEM_READ_PATHS.iter().map(PathBuf::from).collect();
+        read_paths.extend(EXTRA_READ_PATHS.iter().map(PathBuf::from));
+
+        let ruleset = build_ruleset(&read_paths, &write_paths).map_err(|e| {
+            Error::ExecutionErr(format!("Could not set up the Landlock sandbox: {e}"))
+        })?;
+        let seccomp = SECCOMP_FILTERS.as_ref().map_err(|e| {
+            Error::ExecutionErr(format!("Could not set up the seccomp filters: {e}"))
+        })?;
+        Ok(Self { job_dir: job_dir.to_path_buf(), ruleset: Some(ruleset), seccomp })
+    }
+
+    pub fn job_dir(&self) -> &Path {
+        &self.job_dir
+    }
+
+    /// Restricts the calling process. Meant to run between fork and exec: the ruleset and the
+    /// filter are built before fork, so this only issues prctl, landlock_restrict_self and
+    /// seccomp, and fails the spawn rather than letting the process run unconfined.
+    pub fn enter_from_child(&mut self) -> std::io::Result<()> {
+        let ruleset = self
+            .ruleset
+            .take()
+            .ok_or_else(|| std::io::Error::from_raw_os_error(libc::EINVAL))?;
+        match ruleset.restrict_self() {
+            Ok(status) if status.ruleset != RulesetStatus::NotEnforced => {}
+            Ok(_) => return Err(std::io::Error::from_raw_os_error(libc::ENOSYS)),
+            Err(_) => return Err(std::io::Error::last_os_error()),
+        }
+        for filter in self.seccomp {
+            seccompiler::apply_filter(filter).map_err(|_| std::io::Error::last_os_error())?;
+        }
+        Ok(())
+    }
+}
+
+fn build_ruleset(
+    read_paths: &[PathBuf],
+    write_paths: &[PathBuf],
+) -> Result<RulesetCreated, RulesetError> {
+    // Paths missing on this host (e.g. /nix, /lib32) are skipped rather than failing the job
+    let existing = |paths: &[PathBuf]| -> Vec<PathBuf> {
+        paths.iter().filter(|p| p.exists()).cloned().collect()
+    };
+    Ruleset::default()
+        // Fail on kernels without Landlock, then take whatever the kernel supports on top
+        .set_compatibility(CompatLevel::HardRequirement)
+        .handle_access(AccessFs::from_all(ABI::V1))?
+        .set_compatibility(CompatLevel::BestEffort)
+        .handle_access(AccessFs::from_all(TARGET_ABI))?
+        // Nor signals nor abstract unix sockets reach processes outside of the job's sandbox
+        .scope(Scope::from_all(TARGET_ABI))?
+        .create()?
+        .add_rules(path_beneath_rules(
+            existing(read_paths),
+            AccessFs::from_read(TARGET_ABI),
+        ))?
+        .add_rules(path_beneath_rules(
+            DEVICE_PATHS.iter().map(Path::new).filter(|p| p.exists()),
+            AccessFs::from_read(TARGET_ABI) | AccessFs::WriteFile,
+        ))?
+        .add_rules(path_beneath_rules(
+            existing(write_paths),
+            AccessFs::from_all(TARGET_ABI),
+        ))
+}
+
+/// Syscalls denied to jobs, with EPERM so well-behaved programs can fall back.
+fn denied_syscalls() -> Vec<i64> {
+    let mut syscalls = vec![
+        // Other processes' memory, including other jobs of the same user
+        libc::SYS_ptrace,
+        libc::SYS_process_vm_readv,
+        libc::SYS_process_vm_writev,
+        libc::SYS_kcmp,
+        // Filesystem and namespace changes
+        libc::SYS_mount,
+        libc::SYS_umount2,
+        libc::SYS_pivot_root,
+        libc::SYS_chroot,
+        libc::SYS_setns,
+        libc::SYS_unshare,
+        libc::SYS_open_tree,
+        libc::SYS_move_mount,
+        libc::SYS_fsopen,
+        libc::SYS_fsconfig,
+        libc::SYS_fsmount,
+        libc::SYS_fspick,
+        libc::SYS_name_to_handle_at,
+        libc::SYS_open_by_handle_at,
+        // Kernel and host state
+        libc::SYS_init_module,
+        libc::SYS_finit_module,
+        libc::SYS_delete_module,
+        libc::SYS_kexec_load,
+        libc::SYS_kexec_file_load,
+        libc::SYS_reboot,
+        libc::SYS_swapon,
+        libc::SYS_swapoff,
+        libc::SYS_acct,
+        libc::SYS_quotactl,
+        libc::SYS_syslog,
+        libc::SYS_settimeofday,
+        libc::SYS_clock_settime,
+        libc::SYS_clock_adjtime,
+        libc::SYS_adjtimex,
+        // Kernel attack surface that jobs have no use for
+        libc::SYS_bpf,
+        libc::SYS_perf_event_open,
+        libc::SYS_userfaultfd,
+        libc::SYS_keyctl,
+        libc::SYS_add_key,
+        libc::SYS_request_key,
+    ];
+    #[cfg(target_arch = "x86_64")]
+    syscalls.extend([libc::SYS_iopl, libc::SYS_ioperm]);
+    syscalls
+}
+
+fn compile_filter(
+    rules: BTreeMap<i64, Vec<SeccompRule>>,
+    errno: i32,
+    arch: TargetArch,
+) -> Result<BpfProgram, BackendError> {
+    SeccompFilter::new(
+        rules,
+        SeccompAction::Allow,
+        SeccompAction::Errno(errno as u32),
+        arch,
+    )?
+    .try_into()
+}
+
+/// Builds the filters installed in each job process, the denied syscalls and `clone3`.
+fn build_seccomp_filters() -> Result<[BpfProgram; 2], BackendError> {
+    let arch = TargetArch::try_from(std::env::consts::ARCH)?;
+    let mut rules: BTreeMap<i64, Vec<SeccompRule>> =
+        denied_syscalls().into_iter().map(|s| (s, vec![])).collect();
+
+    // clone may not create user namespaces, in which the job would get back the capabilities
+    // no_new_privs dropped. The flags of clone3 live behind a pointer seccomp can't follow, so
+    // clone3 reports ENOSYS instead and libc falls back to clone.
+    let new_user_ns = SeccompCondition::new(
+        0,
+        SeccompCmpArgLen::Qword,
+        SeccompCmpOp::MaskedEq(libc::CLONE_NEWUSER as u64),
+        libc::CLONE_NEWUSER as u64,
+    )?;
+    rules.insert(libc::SYS_clone, vec![SeccompRule::new(vec![new_user_ns])?]);
+
+    Ok([
+        compile_filter(rules, libc::EPERM, arch)?,
+        compile_filter(
+            BTreeMap::from([(libc::SYS_clone3, vec![])]),
+            libc::ENOSYS,
+            arch,
+        )?,
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::process::CommandExt;
+    use std::process::Command;
+
+    fn sandbox(job_dir: &Path) -> Sandbox {
+        if let Err(e) = build_ruleset(&[], &[]) {
+            panic!("Landlock is not supported by this kernel: {e}");
+        }
+        Sandbox::new(job_dir).unwrap()
+    }
+
+    /// Runs `script` with sh in a process sandboxed to `job_dir`, returning whether it succeeded.
+    fn run_sandboxed(job_dir: &Path, script: &str) -> bool {
+        let mut sandbox = sandbox(job_dir);
+        let mut cmd = Command::new("/bin/sh");
+        cmd.args(["-c", script]).current_dir(job_dir);
+        // SAFETY: the hook only issues the syscalls of `enter_from_child`
+        unsafe {
+            cmd.pre_exec(move || sandbox.enter_from_child());
+        }
+        cmd.status().unwrap().success()
+    }
+
+    fn job_dirs() -> (tempfile::TempDir, PathBuf, PathBuf) {
+        let worker_dir = tempfile::tempdir().unwrap();
+        let job_a = worker_dir.path().join("job-a");
+        let job_b = worker_dir.path().join("job-b");
+        std::fs::create_dir(&job_a).unwrap();
+        std::fs::create_dir(&job_b).unwrap();
+        std::fs::write(job_b.join("secret.txt"), "from job b").unwrap();
+        (worker_dir, job_a, job_b)
+    }
+
+    #[test]
+    #[ignore = "needs a kernel with Landlock (Linux 5.13+), run with --ignored"]
+    fn job_can_use_its_own_directory() {
+        let (_worker_dir, job_a, _) = job_dirs();
+        assert!(run_sandboxed(
+            &job_a,
+            "echo ok > main.txt && mkdir sub && mv main.txt sub/ && cat sub/main.txt"
+        ));
+        assert_eq!(
+            std::fs::read_to_string(job_a.join("sub/main.txt")).unwrap(),
+            "ok\n"
+        );
+        assert!(run_sandboxed(
+            &job_a,
+            "cat /etc/hostname > /dev/null || cat /etc/passwd > /dev/null"
+        ));
+    }
+
+    #[test]
+    #[ignore = "needs a kernel with Landlock (Linux 5.13+), run with --ignored"]
+    fn job_cannot_read_other_job_directory() {
+        let (_worker_dir, job_a, job_b) = job_dirs();
+        let secret = job_b.join("secret.txt");
+        assert!(!run_sandboxed(&job_a, &format!("cat {}", secret.display())));
+        assert!(!run_sandboxed(&job_a, &format!("ls {}", job_b.display())));
+        assert!(!run_sandboxed(
+            &job_a,
+            &format!("ls {}", job_a.parent().unwrap().display())
+        ));
+        // Nor through a symlink from its own directory
+        std::os::unix::fs::symlink(&secret, job_a.join("link")).unwrap();
+        assert!(!run_sandboxed(&job_a, "cat link"));
+    }
+
+    #[test]
+    #[ignore = "needs a kernel with Landlock (Linux 5.13+), run with --ignored"]
+    fn job_cannot_write_outside_its_directory() {
+        let (_worker_dir, job_a, job_b) = job_dirs();
+        assert!(!run_sandboxed(
+            &job_a,
+            &format!("echo x > {}/injected", job_b.display())
+        ));
+        assert!(!run_sandboxed(
+            &job_a,
+            &format!("rm {}/secret.txt", job_b.display())
+        ));
+        assert!(!run_sandboxed(
+            &job_a,
+            &format!("mv {}/secret.txt .", job_b.display())
+        ));
+        assert!(!job_b.join("injected").exists());
+        assert!(job_b.join("secret.txt").exists());
+    }
+
+    #[test]
+    #[ignore = "needs a kernel with Landlock (Linux 5.13+), run with --ignored"]
+    fn seccomp_denies_dangerous_syscalls() {
+        let (_worker_dir, job_a, _) = job_dirs();
+        let mut sandbox = sandbox(&job_a);
+        let mut cmd = Command::new("/bin/sh");
+        cmd.args(["-c", "exit 0"]);
+        // SAFETY: the hook only issues raw syscalls
+        unsafe {
+            cmd.pre_exec(move || {
+                sandbox.enter_from_child()?;
+                // The checks run in the child: report a syscall that went through as a failure
+                let ptrace = libc::ptrace(libc::PTRACE_TRACEME, 0, 0, 0);
+                let unshare = libc::unshare(libc::CLONE_NEWUSER);
+                let clone3 = libc::syscall(libc::SYS_clone3, 0, 0);
+                let clone3_errno = *libc::__errno_location();
+                if ptrace == 0 || unshare == 0 || clone3 != -1 || clone3_errno != libc::ENOSYS {
+                    return Err(std::io::Error::from_raw_os_error(libc::EPERM));
+                }
+                Ok(())
+            });
+        }
+        assert!(cmd.status().unwrap().success());
+    }
+}
@@ -14,12 +14,18 @@ mod pwsh_executor;
 #[cfg(feature = "java")]
 mod java_executor;
 
+#[cfg(feature = "kotlin")]
+mod kotlin_executor;
+
 #[cfg(feature = "ruby")]
 mod ruby_executor;
 
 #[cfg(feature = "rlang")]
 mod r_executor;
 
+#[cfg(feature = "perl")]
+mod perl_executor;
+
 mod ai;
 mod ai_executor;
 
@@ -30,6 +36,7 @@ mod ai_executor;
 pub use ai::utils::{load_mcp_tools, McpResourceConfig};
 mod bun_executor;
 pub mod common;
+mod compilation_cache;
 mod config;
 mod csharp_executor;
 
@@ -45,15 +52,25 @@ mod docker_v2;
 mod duckdb_executor;
 #[cfg(all(feature = "duckdb", feature = "private"))]
 mod duckdb_isolation_ee;
+#[cfg(target_os = "linux")]
+pub mod egress_proxy;
 mod global_cache;
 mod go_executor;
 mod graphql_executor;
 mod handle_child;
+#[cfg(target_os = "linux")]
+pub mod job_cgroup;
 pub mod job_logger;
 #[cfg(feature = "private")]
 pub mod job_logger_ee;
 mod job_logger_oss;
 mod js_eval;
+#[cfg(target_os = "linux")]
+pub mod landlock_sandbox;
+#[cfg(feature = "lua")]
+mod lua_executor;
+#[cfg(feature = "wasm")]
+mod wasm_executor;
 pub mod memory_common;
 #[cfg(feature = "private")]
 pub mod memory_ee;
@@ -0,0 +1,998 @@
+//! Lua scripts, run in-process on an embedded Lua 5.4 VM.
+//!
+//! Like nativets, a Lua job never spawns a process, so the VM is what bounds
+//! it: scripts get a fixed instruction budget and a memory limit, and only the
+//! side-effect free parts of the standard library. There is no `io`, `os`
+//! beyond time and env, `package`, `debug` or `coroutine`; the instruction hook
+//! only counts the main thread, so a coroutine would run unmetered.
+//!
+//! The hook doesn't run within C functions either, so the pattern functions of
+//! `string` (`find`, `match`, `gmatch` and `gsub`), whose backtracking grows
+//! with the length of the pattern and of the subject, only take patterns of up to
+//! 256 bytes and subjects of up to 1 MiB. `string.find` with `plain` is not
+//! limited.
+//!
+//! `require("wmill")` gives the script variables, resources and state through
+//! the job's token, with the same semantics as the TypeScript client.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::{
+    atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+    Arc,
+};
+
+use futures::stream;
+use mlua::{
+    DeserializeOptions, HookTriggers, Lua, LuaOptions, LuaSerdeExt, MultiValue, SerializeOptions,
+    StdLib, Value as LuaValue,
+};
+use reqwest::{Method, StatusCode};
+use serde_json::{json, value::RawValue, Value};
+use tokio::{runtime::Handle, sync::mpsc};
+use windmill_common::{
+    client::AuthedClient,
+    error::{Error, Result},
+    utils::HTTP_CLIENT,
+    worker::{to_raw_value, Connection},
+};
+use windmill_parser::Arg;
+use windmill_queue::{append_logs, CanceledBy, MiniPulledJob};
+
+use crate::{
+    common::{build_args_map, get_reserved_variables, OccupancyMetrics},
+    handle_child::run_future_with_polling_update_job_poller,
+};
+
+lazy_static::lazy_static! {
+    static ref LUA_MAX_INSTRUCTIONS: u64 = std::env::var("LUA_MAX_INSTRUCTIONS")
+        .ok()
+        .and_then(|x| x.parse().ok())
+        .unwrap_or(1_000_000_000);
+    static ref LUA_MEMORY_LIMIT_MB: usize = std::env::var("LUA_MEMORY_LIMIT_MB")
+        .ok()
+        .and_then(|x| x.parse().ok())
+        .unwrap_or(256);
+}
+
+/// How often the hook runs, in VM instructions. The budget is checked at this
+/// granularity, and so is cancellation.
+const HOOK_EVERY_N_INSTRUCTIONS: u32 = 10_000;
+
+const INSTRUCTION_LIMIT_REACHED: &str = "Instruction limit reached";
+const MAX_PATTERN_LEN: usize = 256;
+const MAX_PATTERN_SUBJECT_LEN: usize = 1024 * 1024;
+/// Lines printed but not yet appended to the job logs: `print` blocks the
+/// script once they pile up.
+const LOG_CHANNEL_CAPACITY: usize = 256;
+const JOB_INTERRUPTED: &str = "Job was interrupted";
+
+/// Runs before the script. An error raised by the hook is an ordinary Lua
+/// error, so `pcall` and `xpcall` re-raise it once the job has to stop, or a
+/// script could catch it and carry on. `load` only accepts source, since
+/// precompiled chunks can break out of the VM.
+const PRELUDE: &str = r#"
+local tripped, wmill, max_pattern, max_subject = ...
+local raw_load, raw_pcall, raw_xpcall, error = load, pcall, xpcall, error
+local raw_find, raw_match, raw_gmatch, raw_gsub =
+  string.find, string.match, string.gmatch, string.gsub
+
+local function rethrow(...)
+  local reason = tripped()
+  if reason then error(reason, 0) end
+  return ...
+end
+
+function pcall(...) return rethrow(raw_pcall(...)) end
+function xpcall(...) return rethrow(raw_xpcall(...)) end
+function load(chunk, name, _, ...) return raw_load(chunk, name, "t", ...) end
+function require(name)
+  if name == "wmill" then return wmill end
+  error("module '" .. tostring(name) .. "' not found: only 'wmill' can be required", 2)
+end
+
+local function check_pattern(s, pattern)
+  if type(pattern) == "string" and #pattern > max_pattern then
+    error("patterns are limited to " .. max_pattern .. " bytes", 3)
+  end
+  if type(s) == "string" and #s > max_subject then
+    error("pattern matching is limited to strings of " .. max_subject .. " bytes", 3)
+  end
+end
+
+function string.find(s, pattern, init, plain)
+  if not plain then check_pattern(s, pattern) end
+  return raw_find(s, pattern, init, plain)
+end
+function string.match(s, pattern, ...)
+  check_pattern(s, pattern)
+  return raw_match(s, pattern, ...)
+end
+function string.gmatch(s, pattern, ...)
+  check_pattern(s, pattern)
+  return raw_gmatch(s, pattern, ...)
+end
+function string.gsub(s, pattern, ...)
+  check_pattern(s, pattern)
+  return raw_gsub(s, pattern, ...)
+end
+"#;
+
+#[allow(clippy::too_many_arguments)]
+pub async fn do_lua(
+    job: &MiniPulledJob,
+    client: &AuthedClient,
+    code: &str,
+    conn: &Connection,
+    mem_peak: &mut i32,
+    canceled_by: &mut Option<CanceledBy>,
+    worker_name: &str,
+    occupancy_metrics: &mut OccupancyMetrics,
+    parent_runnable_path: Option<String>,
+) -> Result<Box<RawValue>> {
+    let sig = windmill_parser_lua::parse_lua_signature(code)
+        .map_err(|e| Error::ExecutionErr(e.to_string()))?;
+    let args = build_args_map(job, client, conn).await?;
+    let job_args = args.as_ref().or(job.args.as_ref().map(|x| &x.0));
+    let args = spread_args(&sig.args, job_args)?;
+
+    let mut env = get_reserved_variables(job, &client.token, conn, parent_runnable_path).await?;
+    env.insert("BASE_URL".to_string(), client.base_internal_url.clone());
+    env.insert(
+        "BASE_INTERNAL_URL".to_string(),
+        client.base_internal_url.clone(),
+    );
+
+    let (log_tx, mut log_rx) = mpsc::channel::<String>(LOG_CHANNEL_CAPACITY);
+    let log_job_id = job.id;
+    let log_w_id = job.workspace_id.clone();
+    let log_conn = conn.clone();
+    let log_task = tokio::spawn(async move {
+        while let Some(line) = log_rx.recv().await {
+            append_logs(&log_job_id, &log_w_id, line, &log_conn).await;
+        }
+    });
+
+    let interrupted = Arc::new(AtomicBool::new(false));
+    let peak_memory = Arc::new(AtomicUsize::new(0));
+    let vm = LuaJob {
+        code: code.to_string(),
+        args,
+        env,
+        client: client.clone(),
+        job_id: job.id.to_string(),
+        log_tx,
+        interrupted: interrupted.clone(),
+        hooks: Arc::new(AtomicU64::new(0)),
+        peak_memory: peak_memory.clone(),
+        runtime: Handle::current(),
+        max_instructions: *LUA_MAX_INSTRUCTIONS,
+        memory_limit: *LUA_MEMORY_LIMIT_MB * 1024 * 1024,
+    };
+
+    let result_f = async {
+        // The poller drops this future on cancel or timeout, which stops the VM
+        // at its next hook.
+        let _interrupt = InterruptOnDrop(interrupted.clone());
+        tokio::task::spawn_blocking(move || vm.run())
+            .await
+            .map_err(|e| Error::ExecutionErr(format!("Lua VM panicked: {e}")))?
+    };
+
+    let result = run_future_with_polling_update_job_poller(
+        job.id,
+        job.timeout,
+        conn,
+        mem_peak,
+        canceled_by,
+        result_f,
+        worker_name,
+        &job.workspace_id,
+        &mut Some(occupancy_metrics),
+        Box::pin(stream::once(async { 0 })),
+    )
+    .await;
+
+    let _ = log_task.await;
+    *mem_peak = (peak_memory.load(Ordering::Relaxed) / 1024) as i32;
+    result
+}
+
+/// Lines the job's arguments up with the parameters of `main`.
+fn spread_args(
+    sig_args: &[Arg],
+    job_args: Option<&HashMap<String, Box<RawValue>>>,
+) -> Result<Vec<Value>> {
+    sig_args
+        .iter()
+        .map(|arg| match job_args.and_then(|args| args.get(&arg.name)) {
+            Some(v) => serde_json::from_str(v.get()).map_err(|e| {
+                Error::ExecutionErr(format!("Invalid value for argument {}: {e}", arg.name))
+            }),
+            None => Ok(arg.default.clone().unwrap_or(Value::Null)),
+        })
+        .collect()
+}
+
+struct InterruptOnDrop(Arc<AtomicBool>);
+
+impl Drop for InterruptOnDrop {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+struct LuaJob {
+    code: String,
+    args: Vec<Value>,
+    env: HashMap<String, String>,
+    client: AuthedClient,
+    job_id: String,
+    log_tx: mpsc::Sender<String>,
+    interrupted: Arc<AtomicBool>,
+    hooks: Arc<AtomicU64>,
+    peak_memory: Arc<AtomicUsize>,
+    runtime: Handle,
+    max_instructions: u64,
+    /// In bytes
+    memory_limit: usize,
+}
+
+impl LuaJob {
+    fn run(self) -> Result<Box<RawValue>> {
+        let memory_limit = self.memory_limit;
+        let lua = Lua::new_with(
+            StdLib::STRING | StdLib::TABLE | StdLib::MATH | StdLib::UTF8,
+            LuaOptions::new(),
+        )
+        .map_err(|e| Error::ExecutionErr(format!("Could not start the Lua VM: {e}")))?;
+        lua.set_memory_limit(memory_limit)
+            .map_err(|e| Error::ExecutionErr(format!("Could not limit Lua memory: {e}")))?;
+
+        let result = self.setup(&lua).and_then(|()| self.call_main(&lua));
+        let result = result.map_err(|e| to_execution_error(e, memory_limit, self.max_instructions));
+
+        self.peak_memory
+            .fetch_max(lua.used_memory(), Ordering::Relaxed);
+        result
+    }
+
+    fn setup(&self, lua: &Lua) -> mlua::Result<()> {
+        let peak_memory = self.peak_memory.clone();
+        let tripped = self.tripped_check();
+        lua.set_hook(
+            HookTriggers::new().every_nth_instruction(HOOK_EVERY_N_INSTRUCTIONS),
+            move |lua, _| {
+                peak_memory.fetch_max(lua.used_memory(), Ordering::Relaxed);
+                match tripped(true) {
+                    Some(reason) => Err(mlua::Error::runtime(reason)),
+                    None => Ok(()),
+                }
+            },
+        );
+
+        let globals = lua.globals();
+        globals.raw_remove("dofile")?;
+        globals.raw_remove("loadfile")?;
+
+        let log_tx = self.log_tx.clone();
+        globals.set(
+            "print",
+            lua.create_function(move |lua, values: MultiValue| {
+                let tostring: mlua::Function = lua.globals().get("tostring")?;
+                let line = values
+                    .into_iter()
+                    .map(|v| tostring.call::<_, String>(v))
+                    .collect::<mlua::Result<Vec<_>>>()?
+                    .join("\t");
+                let _ = log_tx.blocking_send(format!("{line}\n"));
+                Ok(())
+            })?,
+        )?;
+
+        globals.set("os", self.os_table(lua)?)?;
+
+        let tripped = self.tripped_check();
+        let tripped = lua.create_function(move |_, ()| Ok(tripped(false)))?;
+        lua.load(PRELUDE)
+            .set_name("prelude")
+            .set_mode(mlua::ChunkMode::Text)
+            .call((
+                tripped,
+                self.wmill_table(lua)?,
+                MAX_PATTERN_LEN,
+                MAX_PATTERN_SUBJECT_LEN,
+            ))
+    }
+
+    /// Why the script must stop, if it must. With `count`, this also spends one
+    /// hook's worth of the instruction budget.
+    fn tripped_check(&self) -> impl Fn(bool) -> Option<&'static str> + Send + 'static {
+        let interrupted = self.interrupted.clone();
+        let hooks = self.hooks.clone();
+        let budget = (self.max_instructions / HOOK_EVERY_N_INSTRUCTIONS as u64).max(1);
+        move |count| {
+            if interrupted.load(Ordering::Relaxed) {
+                return Some(JOB_INTERRUPTED);
+            }
+            let spent = if count {
+                hooks.fetch_add(1, Ordering::Relaxed) + 1
+            } else {
+                hooks.load(Ordering::Relaxed)
+            };
+            (spent > budget).then_some(INSTRUCTION_LIMIT_REACHED)
+        }
+    }
+
+    fn os_table<'lua>(&self, lua: &'lua Lua) -> mlua::Result<mlua::Table<'lua>> {
+        let os = lua.create_table()?;
+        let env = self.env.clone();
+        os.set(
+            "getenv",
+            lua.create_function(move |_, name: String| Ok(env.get(&name).cloned()))?,
+        )?;
+        os.set(
+            "time",
+            lua.create_function(|_, ()| Ok(chrono::Utc::now().timestamp()))?,
+        )?;
+        let started = std::time::Instant::now();
+        os.set(
+            "clock",
+            lua.create_function(move |_, ()| Ok(started.elapsed().as_secs_f64()))?,
+        )?;
+        os.set(
+            "date",
+            lua.create_function(|_, (format, time): (Option<String>, Option<i64>)| {
+                let time = match time {
+                    Some(t) => chrono::DateTime::from_timestamp(t, 0)
+                        .ok_or_else(|| mlua::Error::runtime("time out of range"))?,
+                    None => chrono::Utc::now(),
+                };
+                let format = format.unwrap_or_else(|| "%c".to_string());
+                let format = format.strip_prefix('!').unwrap_or(&format);
+                // An invalid specifier makes chrono's Display fail, which
+                // `to_string` would turn into a panic.
+                let mut out = String::new();
+                write!(out, "{}", time.format(format))
+                    .map_err(|_| mlua::Error::runtime(format!("invalid date format: {format}")))?;
+                Ok(out)
+            })?,
+        )?;
+        Ok(os)
+    }
+
+    fn wmill_table<'lua>(&self, lua: &'lua Lua) -> mlua::Result<mlua::Table<'lua>> {
+        let wmill = lua.create_table()?;
+        let api = Api {
+            client: self.client.clone(),
+            job_id: self.job_id.clone(),
+            runtime: self.runtime.clone(),
+        };
+
+        let workspace = self.client.workspace.clone();
+        wmill.set(
+            "get_workspace",
+            lua.create_function(move |_, ()| Ok(workspace.clone()))?,
+        )?;
+
+        let a = api.clone();
+        wmill.set(
+            "get_variable",
+            lua.create_function(move |_, path: String| {
+                a.block_on(a.client.get_variable_value(&path))
+            })?,
+        )?;
+
+        let a = api.clone();
+        wmill.set(
+            "set_variable",
+            lua.create_function(
+                move |_, (path, value, is_secret): (String, String, Option<bool>)| {
+                    a.set_variable(&path, &value, is_secret.unwrap_or(false))
+                },
+            )?,
+        )?;
+
+        let a = api.clone();
+        wmill.set(
+            "get_resource",
+            lua.create_function(move |lua, path: String| {
+                let value = a.block_on(
+                    a.client
+                        .get_resource_value_interpolated::<Value>(&path, Some(a.job_id.clone())),
+                )?;
+                to_lua(lua, &value)
+            })?,
+        )?;
+
+        let a = api.clone();
+        wmill.set(
+            "set_resource",
+            lua.create_function(
+                move |lua, (path, value, resource_type): (String, LuaValue, Option<String>)| {
+                    let value = from_lua(lua, value)?;
+                    a.set_resource(&path, value, resource_type.as_deref().unwrap_or("any"))
+                },
+            )?,
+        )?;
+
+        let state_path = self
+            .env
+            .get("WM_STATE_PATH_NEW")
+            .or_else(|| self.env.get("WM_STATE_PATH"))
+            .cloned()
+            .unwrap_or_default();
+
+        let path = state_path.clone();
+        wmill.set(
+            "get_state_path",
+            lua.create_function(move |_, ()| Ok(path.clone()))?,
+        )?;
+
+        let (a, path) = (api.clone(), state_path.clone());
+        wmill.set(
+            "get_state",
+            lua.create_function(move |lua, ()| match a.get_state(&path)? {
+                Some(value) => to_lua(lua, &value),
+                None => Ok(LuaValue::Nil),
+            })?,
+        )?;
+
+        let (a, path) = (api, state_path);
+        wmill.set(
+            "set_state",
+            lua.create_function(move |lua, value: LuaValue| {
+                let value = from_lua(lua, value)?;
+                a.set_resource(&path, value, "state")
+            })?,
+        )?;
+
+        Ok(wmill)
+    }
+
+    fn call_main(&self, lua: &Lua) -> mlua::Result<Box<RawValue>> {
+        let chunk: LuaValue = lua
+            .load(self.code.as_str())
+            .set_name("main.lua")
+            .set_mode(mlua::ChunkMode::Text)
+            .call(())?;
+        let main: mlua::Function = match chunk {
+            LuaValue::Function(f) => f,
+            _ => match lua.globals().get::<_, LuaValue>("main")? {
+                LuaValue::Function(f) => f,
+                _ => {
+                    return Err(mlua::Error::runtime(
+                        "main.lua must define a global function main or return one",
+                    ))
+                }
+            },
+        };
+
+        let args = self
+            .args
+            .iter()
+            .map(|v| to_lua(lua, v))
+            .collect::<mlua::Result<Vec<_>>>()?;
+        let ret: MultiValue = main.call(MultiValue::from_vec(args))?;
+        let value = match ret.into_iter().next() {
+            Some(v) => from_lua(lua, v)?,
+            None => Value::Null,
+        };
+        Ok(to_raw_value(&value))
+    }
+}
+
+fn to_lua<'lua>(lua: &'lua Lua, value: &Value) -> mlua::Result<LuaValue<'lua>> {
+    lua.to_value_with(
+        value,
+        SerializeOptions::new()
+            .serialize_none_to_null(false)
+            .serialize_unit_to_null(false),
+    )
+}
+
+fn from_lua(lua: &Lua, value: LuaValue) -> mlua::Result<Value> {
+    lua.from_value_with(
+        value,
+        DeserializeOptions::new()
+            .deny_unsupported_types(false)
+            .sort_keys(true),
+    )
+}
+
+fn to_execution_error(e: mlua::Error, memory_limit: usize, max_instructions: u64) -> Error {
+    match &e {
+        mlua::Error::MemoryError(_) => Error::ExecutionErr(format!(
+            "Memory limit of {} MB reached",
+            memory_limit / 1024 / 1024
+        )),
+        e if root_cause(e).contains(INSTRUCTION_LIMIT_REACHED) => Error::ExecutionErr(format!(
+            "{INSTRUCTION_LIMIT_REACHED} ({max_instructions} instructions)"
+        )),
+        e if root_cause(e).contains(JOB_INTERRUPTED) => {
+            Error::ExecutionErr(JOB_INTERRUPTED.to_string())
+        }
+        e => Error::ExecutionErr(e.to_string()),
+    }
+}
+
+fn root_cause(e: &mlua::Error) -> String {
+    match e {
+        mlua::Error::CallbackError { cause, .. } => root_cause(cause),
+        e => e.to_string(),
+    }
+}
+
+/// The workspace API, called from the VM thread by blocking on the worker's
+/// runtime.
+#[derive(Clone)]
+struct Api {
+    client: AuthedClient,
+    job_id: String,
+    runtime: Handle,
+}
+
+impl Api {
+    fn block_on<T>(
+        &self,
+        f: impl std::future::Future<Output = anyhow::Result<T>>,
+    ) -> mlua::Result<T> {
+        self.runtime
+            .block_on(f)
+            .map_err(|e| mlua::Error::runtime(e.to_string()))
+    }
+
+    async fn request(
+        &self,
+        method: Method,
+        route: &str,
+        query: &[(&str, &str)],
+        body: Option<Value>,
+    ) -> anyhow::Result<(StatusCode, String)> {
+        let url = format!(
+            "{}/api/w/{}/{route}",
+            self.client.base_internal_url, self.client.workspace
+        );
+        let mut req = self
+            .client
+            .force_client
+            .as_ref()
+            .unwrap_or(&HTTP_CLIENT)
+            .request(method, url)
+            .query(query)
+            .bearer_auth(&self.client.token);
+        if let Some(body) = body {
+            req = req.json(&body);
+        }
+        let response = req.send().await?;
+        Ok((response.status(), response.text().await?))
+    }
+
+    async fn expect_success(
+        &self,
+        method: Method,
+        route: &str,
+        query: &[(&str, &str)],
+        body: Option<Value>,
+    ) -> anyhow::Result<String> {
+        match self.request(method, route, query, body).await? {
+            (status, text) if status.is_success() => Ok(text),
+            (status, text) => Err(anyhow::anyhow!("{route} failed with {status}: {text}")),
+        }
+    }
+
+    fn set_variable(&self, path: &str, value: &str, is_secret: bool) -> mlua::Result<()> {
+        self.block_on(async {
+            let exists = self
+                .expect_success(Method::GET, &format!("variables/exists/{path}"), &[], None)
+                .await?;
+            if exists.trim() == "true" {
+                self.expect_success(
+                    Method::POST,
+                    &format!("variables/update/{path}"),
+                    &[],
+                    Some(json!({ "value": value })),
+                )
+                .await?;
+            } else {
+                self.expect_success(
+                    Method::POST,
+                    "variables/create",
+                    &[],
+                    Some(json!({
+                        "path": path,
+                        "value": value,
+                        "is_secret": is_secret,
+                        "description": "",
+                    })),
+                )
+                .await?;
+            }
+            Ok(())
+        })
+    }
+
+    fn set_resource(&self, path: &str, value: Value, resource_type: &str) -> mlua::Result<()> {
+        self.block_on(async {
+            self.expect_success(
+                Method::POST,
+                "resources/create",
+                &[("update_if_exists", "true")],
+                Some(json!({
+                    "path": path,
+                    "value": value,
+                    "resource_type": resource_type,
+                })),
+            )
+            .await?;
+            Ok(())
+        })
+    }
+
+    fn get_state(&self, path: &str) -> mlua::Result<Option<Value>> {
+        self.block_on(async {
+            match self
+                .request(
+                    Method::GET,
+                    &format!("resources/get_value/{path}"),
+                    &[],
+                    None,
+                )
+                .await?
+            {
+                (StatusCode::NOT_FOUND, _) => Ok(None),
+                (status, text) if status.is_success() => Ok(serde_json::from_str(&text)?),
+                (status, text) => Err(anyhow::anyhow!(
+                    "getting state failed with {status}: {text}"
+                )),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    use super::*;
+
+    struct TestJob {
+        code: &'static str,
+        base_url: String,
+        env: HashMap<String, String>,
+        max_instructions: u64,
+        memory_limit_mb: usize,
+        interrupted: bool,
+    }
+
+    impl TestJob {
+        fn new(code: &'static str) -> Self {
+            Self {
+                code,
+                base_url: "http://127.0.0.1:1".to_string(),
+                env: HashMap::new(),
+                max_instructions: 10_000_000,
+                memory_limit_mb: 64,
+                interrupted: false,
+            }
+        }
+
+        /// Runs the job, returning its result and the lines it printed.
+        async fn run(self) -> (Result<Value>, Vec<String>) {
+            let (log_tx, mut log_rx) = mpsc::channel(LOG_CHANNEL_CAPACITY);
+            let client = AuthedClient::new(
+                self.base_url,
+                "test-workspace".to_string(),
+                "token".to_string(),
+                Some(reqwest::Client::builder().no_proxy().build().unwrap()),
+            );
+            let job = LuaJob {
+                code: self.code.to_string(),
+                args: vec![],
+                env: self.env,
+                client,
+                job_id: uuid::Uuid::nil().to_string(),
+                log_tx,
+                interrupted: Arc::new(AtomicBool::new(self.interrupted)),
+                hooks: Arc::new(AtomicU64::new(0)),
+                peak_memory: Arc::new(AtomicUsize::new(0)),
+                runtime: Handle::current(),
+                max_instructions: self.max_instructions,
+                memory_limit: self.memory_limit_mb * 1024 * 1024,
+            };
+            let logs = tokio::spawn(async move {
+                let mut lines = vec![];
+                while let Some(line) = log_rx.recv().await {
+                    lines.push(line);
+                }
+                lines
+            });
+            let result = tokio::task::spawn_blocking(move || job.run())
+                .await
+                .unwrap()
+                .map(|raw| serde_json::from_str(raw.get()).unwrap());
+            (result, logs.await.unwrap())
+        }
+    }
+
+    async fn run(code: &'static str) -> Result<Value> {
+        TestJob::new(code).run().await.0
+    }
+
+    fn error_message(result: Result<Value>) -> String {
+        match result {
+            Err(Error::ExecutionErr(e)) => e,
+            other => panic!("expected an execution error, got {other:?}"),
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn runs_main_and_prints() {
+        let (result, logs) = TestJob::new(
+            r#"
+function main()
+  print("hello", 1, true)
+  return { sum = 1 + 2, items = { "a", "b" } }
+end
+"#,
+        )
+        .run()
+        .await;
+        assert_eq!(result.unwrap(), json!({ "sum": 3, "items": ["a", "b"] }));
+        assert_eq!(logs, vec!["hello\t1\ttrue\n".to_string()]);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn stops_at_the_instruction_budget() {
+        let mut job = TestJob::new("function main() while true do end end");
+        job.max_instructions = 100_000;
+        assert_eq!(
+            error_message(job.run().await.0),
+            "Instruction limit reached (100000 instructions)"
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn stops_at_the_memory_limit() {
+        let mut job = TestJob::new(
+            r#"
+function main()
+  local t = {}
+  for i = 1, 1e9 do t[i] = string.rep("x", 1024) .. i end
+end
+"#,
+        );
+        job.memory_limit_mb = 16;
+        job.max_instructions = u64::MAX;
+        assert_eq!(
+            error_message(job.run().await.0),
+            "Memory limit of 16 MB reached"
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn pcall_does_not_catch_the_budget_or_an_interruption() {
+        for code in [
+            r#"function main()
+  pcall(function() while true do end end)
+  return "caught"
+end"#,
+            r#"function main()
+  xpcall(function() while true do end end, function(e) return e end)
+  return "caught"
+end"#,
+        ] {
+            let mut job = TestJob::new(code);
+            job.max_instructions = 100_000;
+            assert_eq!(
+                error_message(job.run().await.0),
+                "Instruction limit reached (100000 instructions)"
+            );
+
+            let mut job = TestJob::new(code);
+            job.max_instructions = u64::MAX;
+            job.interrupted = true;
+            assert_eq!(error_message(job.run().await.0), JOB_INTERRUPTED);
+        }
+
+        // Other errors are caught as usual
+        let result = run(r#"function main() return pcall(error, "boom", 0) end"#).await;
+        assert_eq!(result.unwrap(), json!(false));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn only_loads_source_and_wmill() {
+        let result = run(r#"
+function main()
+  local ok, err = pcall(require, "os")
+  local chunk, load_err = load(string.dump(function() return 1 end))
+  return {
+    require_err = err,
+    binary_chunk = chunk == nil,
+    load_err = load_err,
+    source = load("return 1 + 1")(),
+    missing = { io == nil, package == nil, debug == nil, coroutine == nil,
+                dofile == nil, loadfile == nil, os.execute == nil },
+  }
+end
+"#)
+        .await
+        .unwrap();
+        assert!(result["require_err"]
+            .as_str()
+            .unwrap()
+            .contains("only 'wmill' can be required"));
+        assert_eq!(result["binary_chunk"], json!(true));
+        assert!(result["load_err"]
+            .as_str()
+            .unwrap()
+            .contains("attempt to load a binary chunk"));
+        assert_eq!(result["source"], json!(2));
+        assert_eq!(result["missing"], Value::Array(vec![json!(true); 7]));
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn limits_pattern_matching() {
+        let result = run(r#"
+function main()
+  local long = string.rep("a", 1024 * 1024 + 1)
+  local ok_find, find_err = pcall(string.find, long, "a+b")
+  local ok_gsub, gsub_err = pcall(function() return long:gsub("a", "b") end)
+  local ok_pattern, pattern_err = pcall(string.match, "abc", string.rep("a?", 200))
+  return {
+    ok = { ok_find, ok_gsub, ok_pattern },
+    find_err = find_err,
+    pattern_err = pattern_err,
+    plain = long:find("aa", 1, true),
+    short = ("key=value"):gsub("(%w+)=(%w+)", "%2=%1"),
+  }
+end
+"#)
+        .await
+        .unwrap();
+        assert_eq!(result["ok"], json!([false, false, false]));
+        assert!(result["find_err"]
+            .as_str()
+            .unwrap()
+            .contains("pattern matching is limited to strings of 1048576 bytes"));
+        assert!(result["pattern_err"]
+            .as_str()
+            .unwrap()
+            .contains("patterns are limited to 256 bytes"));
+        assert_eq!(result["plain"], json!(1));
+        assert_eq!(result["short"], json!("value=key"));
+    }
+
+    /// Serves `routes` over HTTP, recording the request line and body of every request.
+    async fn mock_api(
+        routes: fn(&str) -> (u16, &'static str),
+    ) -> (String, Arc<Mutex<Vec<(String, String)>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(vec![]));
+        let recorded = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let mut buf = vec![];
+                let mut chunk = [0u8; 4096];
+                let head_len = loop {
+                    let n = stream.read(&mut chunk).await.unwrap();
+                    buf.extend_from_slice(&chunk[..n]);
+                    if let Some(i) = buf.windows(4).position(|w| w == b"\r\n\r\n") {
+                        break i + 4;
+                    }
+                    assert!(n > 0, "connection closed before the end of the head");
+                };
+                let head = String::from_utf8_lossy(&buf[..head_len]).to_string();
+                let body_len = head
+                    .lines()
+                    .find_map(|line| {
+                        let (name, value) = line.split_once(':')?;
+                        name.eq_ignore_ascii_case("content-length")
+                            .then(|| value.trim().parse::<usize>().unwrap())
+                    })
+                    .unwrap_or(0);
+                while buf.len() < head_len + body_len {
+                    let n = stream.read(&mut chunk).await.unwrap();
+                    buf.extend_from_slice(&chunk[..n]);
+                }
+                let request_line = head.lines().next().unwrap_or_default().to_string();
+                let body = String::from_utf8_lossy(&buf[head_len..head_len + body_len]);
+                recorded
+                    .lock()
+                    .unwrap()
+                    .push((request_line.clone(), body.to_string()));
+                let (status, response) = routes(&request_line);
+                let _ = stream
+                    .write_all(
+                        format!(
+                            "HTTP/1.1 {status} OK\r\ncontent-type: application/json\r\n\
+                            content-length: {}\r\nconnection: close\r\n\r\n{response}",
+                            response.len()
+                        )
+                        .as_bytes(),
+                    )
+                    .await;
+            }
+        });
+        (url, requests)
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn wmill_calls_the_workspace_api() {
+        let (base_url, requests) =
+            mock_api(
+                |request_line| match request_line.split(' ').nth(1).unwrap_or_default() {
+                    "/api/w/test-workspace/variables/get_value/u/test/secret" => {
+                        (200, r#""s3cr3t""#)
+                    }
+                    "/api/w/test-workspace/resources/create?update_if_exists=true" => {
+                        (200, r#""ok""#)
+                    }
+                    "/api/w/test-workspace/resources/get_value/u/test/state" => {
+                        (200, r#"{"count":1}"#)
+                    }
+                    _ => (404, r#""not found""#),
+                },
+            )
+            .await;
+
+        let mut job = TestJob::new(
+            r#"
+local wmill = require("wmill")
+function main()
+  wmill.set_state({ count = 1 })
+  local ok, err = pcall(wmill.get_variable, "u/test/missing")
+  return {
+    workspace = wmill.get_workspace(),
+    variable = wmill.get_variable("u/test/secret"),
+    state = wmill.get_state(),
+    state_path = wmill.get_state_path(),
+    env = os.getenv("WM_STATE_PATH"),
+    missing = ok,
+  }
+end
+"#,
+        );
+        job.base_url = base_url;
+        job.env
+            .insert("WM_STATE_PATH".to_string(), "u/test/state".to_string());
+        let result = job.run().await.0.unwrap();
+
+        assert_eq!(
+            result,
+            json!({
+                "workspace": "test-workspace",
+                "variable": "s3cr3t",
+                "state": { "count": 1 },
+                "state_path": "u/test/state",
+                "env": "u/test/state",
+                "missing": false,
+            })
+        );
+        let requests = requests.lock().unwrap();
+        let (request_line, body) = &requests[0];
+        assert_eq!(
+            request_line,
+            "POST /api/w/test-workspace/resources/create?update_if_exists=true HTTP/1.1"
+        );
+        assert_eq!(
+            serde_json::from_str::<Value>(body).unwrap(),
+            json!({ "path": "u/test/state", "value": { "count": 1 }, "resource_type": "state" })
+        );
+        assert_eq!(
+            requests[1..]
+                .iter()
+                .map(|(request_line, _)| request_line.as_str())
+                .collect::<Vec<_>>(),
+            [
+                "GET /api/w/test-workspace/variables/get_value/u/test/missing HTTP/1.1",
+                "GET /api/w/test-workspace/variables/get_value/u/test/secret HTTP/1.1",
+                "GET /api/w/test-workspace/resources/get_value/u/test/state HTTP/1.1",
+            ]
+        );
+    }
+}
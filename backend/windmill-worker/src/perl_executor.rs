@@ -0,0 +1,482 @@
+//! Perl scripts. The `# requires 'Module', 'version';` comments of a script make
+//! up its cpanfile, which is also its lockfile: cpanm installs it into a
+//! local::lib cached per hash of the lockfile, shared by every script with the
+//! same requirements.
+
+use std::{collections::HashMap, process::Stdio};
+
+use itertools::Itertools;
+use tokio::{fs::File, io::AsyncWriteExt, process::Command};
+use uuid::Uuid;
+use windmill_common::{
+    client::AuthedClient,
+    error::Error,
+    utils::calculate_hash,
+    worker::{write_file, Connection},
+};
+use windmill_parser_perl::{parse_perl_main_params, parse_perl_requirements, MainParam, Sigil};
+use windmill_queue::{append_logs, CanceledBy, MiniPulledJob};
+
+use crate::{
+    common::{
+        build_command_with_isolation, create_args_and_out_file, get_reserved_variables,
+        read_result, resolve_nsjail_timeout, resolve_nsjail_tmp_mount_block, start_child_process,
+        OccupancyMetrics, DEV_CONF_NSJAIL,
+    },
+    get_proxy_envs_for_lang,
+    handle_child::{self},
+    is_sandboxing_enabled,
+    universal_pkg_installer::{
+        par_install_language_dependencies_seq, InstallDeps, RequiredDependency,
+    },
+    DISABLE_NUSER, NSJAIL_PATH, PATH_ENV, PERL_CACHE_DIR, PROXY_ENVS, TRACING_PROXY_CA_CERT_PATH,
+};
+use windmill_common::scripts::ScriptLang;
+
+lazy_static::lazy_static! {
+    static ref PERL_PATH: String = std::env::var("PERL_PATH").unwrap_or_else(|_| "/usr/bin/perl".to_string());
+    static ref CPANM_PATH: String = std::env::var("CPANM_PATH").unwrap_or_else(|_| "/usr/bin/cpanm".to_string());
+    static ref CPAN_MIRROR: Option<String> = std::env::var("CPAN_MIRROR").ok().filter(|m| !m.is_empty());
+    static ref PERL_PROXY_ENVS: Vec<(String, String)> = {
+        PROXY_ENVS
+            .clone()
+            .into_iter()
+            .map(|(k, v)| (k.to_lowercase(), v))
+            .collect()
+    };
+}
+
+const NSJAIL_CONFIG_RUN_PERL_CONTENT: &str = include_str!("../nsjail/run.perl.config.proto");
+const NSJAIL_CONFIG_INSTALL_PERL_CONTENT: &str =
+    include_str!("../nsjail/install.perl.config.proto");
+
+#[allow(dead_code)]
+pub(crate) struct JobHandlerInput<'a> {
+    pub base_internal_url: &'a str,
+    pub canceled_by: &'a mut Option<CanceledBy>,
+    pub client: &'a AuthedClient,
+    pub parent_runnable_path: Option<String>,
+    pub conn: &'a Connection,
+    pub envs: HashMap<String, String>,
+    pub inner_content: &'a str,
+    pub job: &'a MiniPulledJob,
+    pub job_dir: &'a str,
+    pub mem_peak: &'a mut i32,
+    pub occupancy_metrics: &'a mut OccupancyMetrics,
+    pub requirements_o: Option<&'a String>,
+    pub shared_mount: &'a str,
+    pub worker_name: &'a str,
+}
+
+pub async fn handle_perl_job<'a>(
+    mut args: JobHandlerInput<'a>,
+) -> Result<Box<sqlx::types::JsonRawValue>, Error> {
+    // --- Prepare ---
+    {
+        prepare(&args).await?;
+    }
+    // --- Gen Lockfile ---
+
+    let lockfile = match args.requirements_o {
+        Some(lockfile) => lockfile.clone(),
+        None => resolve(args.inner_content)?,
+    };
+
+    // --- Install ---
+
+    let local_lib = install(&mut args, &lockfile).await?;
+
+    // --- Execute ---
+    {
+        run(&mut args, local_lib).await?;
+    }
+    // --- Retrieve results ---
+    {
+        read_result(&args.job_dir, None).await
+    }
+}
+
+async fn prepare<'a>(
+    JobHandlerInput { job, conn, job_dir, inner_content, client, .. }: &JobHandlerInput<'a>,
+) -> Result<(), Error> {
+    create_args_and_out_file(&client, job, job_dir, conn).await?;
+    for (file, content) in [
+        ("main.pl", wrap(inner_content)?),
+        ("script.pl", inner_content.to_string()),
+        ("Wmill.pm", MINI_CLIENT.to_string()),
+    ] {
+        File::create(format!("{job_dir}/{file}"))
+            .await?
+            .write_all(content.as_bytes())
+            .await?;
+    }
+    Ok(())
+}
+
+/// The lockfile of a script: its normalized cpanfile.
+pub fn resolve(inner_content: &str) -> Result<String, Error> {
+    Ok(parse_perl_requirements(inner_content)?)
+}
+
+/// Installs the lockfile into its local::lib, unless it already is, and returns
+/// the path of that local::lib, if the script requires any module.
+async fn install<'a>(
+    JobHandlerInput { worker_name, job, conn, job_dir, .. }: &mut JobHandlerInput<'a>,
+    lockfile: &str,
+) -> Result<Option<String>, Error> {
+    let modules = lockfile
+        .lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty())
+        .collect_vec();
+    if modules.is_empty() {
+        return Ok(None);
+    }
+    File::create(format!("{job_dir}/cpanfile"))
+        .await?
+        .write_all(lockfile.as_bytes())
+        .await?;
+
+    let hash = calculate_hash(lockfile);
+    let local_lib = format!("{}/lib/{hash}", *PERL_CACHE_DIR);
+    let dependency = RequiredDependency {
+        path: local_lib.clone(),
+        _s3_handle: hash.clone(),
+        display_name: format!("local::lib {} ({} modules)", &hash[..8], modules.len()),
+        custom_payload: (),
+    };
+
+    let job_dir = job_dir.to_owned();
+    let jailed = !cfg!(windows) && is_sandboxing_enabled();
+    let nsjail_tmp_mount_block = resolve_nsjail_tmp_mount_block(&job_dir).await;
+    par_install_language_dependencies_seq(
+        InstallDeps::Flat(vec![dependency]),
+        "perl",
+        "cpanm",
+        false,
+        1,
+        move |dependency| {
+            std::fs::create_dir_all(&dependency.path)?;
+            let mut cmd = if jailed {
+                let nsjail_proto = format!("{}.install.config.proto", Uuid::new_v4());
+                let _ = write_file(
+                    &job_dir,
+                    &nsjail_proto,
+                    &NSJAIL_CONFIG_INSTALL_PERL_CONTENT
+                        .replace("{JOB_DIR}", &job_dir)
+                        .replace("{TARGET}", &dependency.path)
+                        .replace("{CLONE_NEWUSER}", &(!*DISABLE_NUSER).to_string())
+                        .replace("{TRACING_PROXY_CA_CERT_PATH}", &*TRACING_PROXY_CA_CERT_PATH)
+                        .replace("{TMP_MOUNT_BLOCK}", &nsjail_tmp_mount_block)
+                        .replace("#{DEV}", DEV_CONF_NSJAIL),
+                )?;
+                let mut cmd = Command::new(NSJAIL_PATH.as_str());
+                cmd.args(vec!["--config", &nsjail_proto, "--", CPANM_PATH.as_str()]);
+                cmd
+            } else {
+                Command::new(if cfg!(windows) {
+                    "cpanm.bat"
+                } else {
+                    CPANM_PATH.as_str()
+                })
+            };
+            cmd.env_clear()
+                .current_dir(&job_dir)
+                .envs(vec![
+                    ("PATH".to_owned(), PATH_ENV.clone()),
+                    // Keep cpanm's build directories out of the actual home
+                    ("HOME".to_owned(), PERL_CACHE_DIR.to_owned()),
+                    (
+                        "PERL_CPANM_HOME".to_owned(),
+                        if jailed {
+                            "/tmp/.cpanm".to_owned()
+                        } else {
+                            format!("{job_dir}/.cpanm")
+                        },
+                    ),
+                ])
+                .envs(PERL_PROXY_ENVS.clone())
+                .args([
+                    "--notest",
+                    "--quiet",
+                    // Install every non-core dependency in the local::lib, even
+                    // the ones the system perl already has
+                    "--local-lib-contained",
+                    dependency.path.as_str(),
+                    "--cpanfile",
+                    "cpanfile",
+                    "--installdeps",
+                    ".",
+                ])
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped());
+            if let Some(mirror) = CPAN_MIRROR.as_ref() {
+                cmd.args(["--mirror", mirror.as_str(), "--mirror-only"]);
+            }
+
+            #[cfg(windows)]
+            {
+                cmd.env("SystemRoot", crate::SYSTEM_ROOT.as_str())
+                    .env("USERPROFILE", crate::USERPROFILE_ENV.as_str())
+                    .env(
+                        "TMP",
+                        std::env::var("TMP").unwrap_or_else(|_| String::from("/tmp")),
+                    );
+            }
+
+            Ok(cmd)
+        },
+        None,
+        &job.id,
+        &job.workspace_id,
+        worker_name,
+        jailed,
+        conn,
+    )
+    .await?;
+
+    Ok(Some(local_lib))
+}
+
+async fn run<'a>(
+    JobHandlerInput {
+        occupancy_metrics,
+        mem_peak,
+        canceled_by,
+        worker_name,
+        job,
+        conn,
+        job_dir,
+        shared_mount,
+        client,
+        envs,
+        base_internal_url,
+        parent_runnable_path,
+        ..
+    }: &mut JobHandlerInput<'a>,
+    local_lib: Option<String>,
+) -> Result<(), Error> {
+    let reserved_variables =
+        get_reserved_variables(job, &client.token, conn, parent_runnable_path.clone()).await?;
+    let perl5lib = local_lib
+        .as_ref()
+        .map(|l| format!("{l}/lib/perl5"))
+        .unwrap_or_default();
+    let proxy_envs = get_proxy_envs_for_lang(
+        &ScriptLang::Perl,
+        job.kind,
+        &job.id,
+        &job.workspace_id,
+        conn,
+    )
+    .await?;
+
+    let child = if !cfg!(windows) && is_sandboxing_enabled() {
+        append_logs(
+            &job.id,
+            &job.workspace_id,
+            format!("\n--- ISOLATED PERL CODE EXECUTION ---\n"),
+            conn,
+        )
+        .await;
+        let shared_deps = local_lib
+            .map(|l| {
+                format!(
+                    r#"
+mount {{
+    src: "{l}"
+    dst: "{l}"
+    is_bind: true
+    rw: false
+}}
+                "#
+                )
+            })
+            .unwrap_or_default();
+
+        let nsjail_timeout =
+            resolve_nsjail_timeout(conn, &job.workspace_id, job.id, job.timeout).await;
+        write_file(
+            job_dir,
+            "run.config.proto",
+            &NSJAIL_CONFIG_RUN_PERL_CONTENT
+                .replace("{JOB_DIR}", job_dir)
+                .replace("{SHARED_MOUNT}", &shared_mount)
+                .replace("{SHARED_DEPENDENCIES}", &shared_deps)
+                .replace("{TRACING_PROXY_CA_CERT_PATH}", &*TRACING_PROXY_CA_CERT_PATH)
+                .replace("#{DEV}", DEV_CONF_NSJAIL)
+                .replace("{CLONE_NEWUSER}", &(!*DISABLE_NUSER).to_string())
+                .replace(
+                    "{TMP_MOUNT_BLOCK}",
+                    &resolve_nsjail_tmp_mount_block(job_dir).await,
+                )
+                .replace("{TIMEOUT}", &nsjail_timeout),
+        )?;
+        let mut cmd = Command::new(NSJAIL_PATH.as_str());
+        cmd.env_clear()
+            .current_dir(job_dir)
+            .env("PATH", PATH_ENV.as_str())
+            .env("BASE_INTERNAL_URL", base_internal_url)
+            .env("PERL5LIB", perl5lib.as_str())
+            .envs(envs)
+            .envs(reserved_variables)
+            .envs(PERL_PROXY_ENVS.clone())
+            .envs(proxy_envs)
+            .args(vec![
+                "--config",
+                "run.config.proto",
+                "--",
+                PERL_PATH.as_str(),
+                "-I.",
+                "main.pl",
+            ]);
+        cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+
+        start_child_process(cmd, NSJAIL_PATH.as_str(), false).await?
+    } else {
+        append_logs(
+            &job.id,
+            &job.workspace_id,
+            format!("\n--- PERL CODE EXECUTION ---\n"),
+            conn,
+        )
+        .await;
+
+        let perl_executable = if cfg!(windows) {
+            "perl.exe"
+        } else {
+            PERL_PATH.as_str()
+        };
+
+        let args = vec!["-I.", "main.pl"];
+        let mut cmd = build_command_with_isolation(perl_executable, &args);
+
+        cmd.env_clear()
+            .current_dir(job_dir.to_owned())
+            .env("PATH", PATH_ENV.as_str())
+            .env("PERL5LIB", perl5lib.as_str())
+            .env("BASE_INTERNAL_URL", base_internal_url)
+            .envs(reserved_variables)
+            .envs(PERL_PROXY_ENVS.clone())
+            .envs(proxy_envs)
+            .envs(envs);
+
+        cmd.stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+
+        #[cfg(windows)]
+        {
+            cmd.env("SystemRoot", crate::SYSTEM_ROOT.as_str())
+                .env("USERPROFILE", crate::USERPROFILE_ENV.as_str())
+                .env(
+                    "TMP",
+                    std::env::var("TMP").unwrap_or_else(|_| String::from("/tmp")),
+                );
+        }
+        start_child_process(cmd, perl_executable, false).await?
+    };
+    handle_child::handle_child(
+        &job.id,
+        conn,
+        mem_peak,
+        canceled_by,
+        child,
+        is_sandboxing_enabled(),
+        worker_name,
+        &job.workspace_id,
+        "perl",
+        job.timeout,
+        false,
+        &mut Some(occupancy_metrics),
+        None,
+        None,
+    )
+    .await?;
+    Ok(())
+}
+
+/// The entrypoint, `main.pl`: loads the script from `script.pl`, so that its
+/// `__END__` or final `1;` cannot cut the wrapper short, then calls `main` with
+/// the arguments in the order it unpacks them.
+fn wrap(inner_content: &str) -> Result<String, Error> {
+    let spread = parse_perl_main_params(inner_content)?
+        .unwrap_or_default()
+        .into_iter()
+        .map(|MainParam { name, sigil, .. }| match sigil {
+            Sigil::Scalar => format!("$args->{{'{name}'}}"),
+            Sigil::Array => format!("@{{ $args->{{'{name}'}} // [] }}"),
+            Sigil::Hash => format!("%{{ $args->{{'{name}'}} // {{}} }}"),
+        })
+        .join(", ");
+    Ok(r#"use strict;
+use warnings;
+
+package Windmill::Wrapper;
+
+use JSON::PP ();
+
+my $json = JSON::PP->new->utf8->allow_nonref->allow_blessed->convert_blessed->canonical;
+
+sub write_result {
+    my ($value) = @_;
+    open(my $fh, '>', 'result.json') or die "could not open result.json: $!";
+    print $fh $json->encode($value);
+    close($fh);
+}
+
+my $args = do {
+    open(my $fh, '<', 'args.json') or die "could not open args.json: $!";
+    local $/;
+    $json->decode(<$fh>);
+};
+
+package main;
+
+do './script.pl';
+die $@ if $@;
+
+my @res = eval { main::main(SPREAD) };
+if (my $err = $@) {
+    Windmill::Wrapper::write_result({
+        name => ref($err) || 'Error',
+        message => "$err",
+        stack => "$err",
+    });
+    die $err;
+}
+Windmill::Wrapper::write_result(@res == 1 ? $res[0] : @res ? \@res : undef);
+"#
+    .replace("SPREAD", &spread))
+}
+
+const MINI_CLIENT: &str = r#"package Wmill;
+
+use strict;
+use warnings;
+
+use HTTP::Tiny;
+use JSON::PP ();
+
+sub _get {
+    my ($route) = @_;
+    my $url = "$ENV{BASE_INTERNAL_URL}/api/w/$ENV{WM_WORKSPACE}/$route";
+    my $res = HTTP::Tiny->new->get($url, {
+        headers => { Authorization => "Bearer $ENV{WM_TOKEN}" },
+    });
+    die "$route failed with $res->{status}: $res->{content}\n" unless $res->{success};
+    return JSON::PP->new->utf8->allow_nonref->decode($res->{content});
+}
+
+sub get_variable {
+    my ($path) = @_;
+    return _get("variables/get_value/$path");
+}
+
+sub get_resource {
+    my ($path) = @_;
+    return _get("resources/get_value_interpolated/$path");
+}
+
+1;
+"#;
@@ -5,6 +5,7 @@ use std::{collections::HashMap, process::Stdio};
 use uuid::Uuid;
 use windmill_parser_rust::parse_rust_deps_into_manifest;
 
+use crate::compilation_cache::{self, toolchain_version, ArtifactKey, ArtifactKind};
 use crate::global_cache::save_cache;
 use itertools::Itertools;
 use tokio::{
@@ -469,6 +470,35 @@ pub async fn build_rust_crate(
 
     let build_dir = get_build_dir(job, job_dir, conn, worker_name, is_preview).await?;
 
+    // The compiled dependencies are only restored into and stored from a target directory no
+    // build has used yet: a shared preview build directory already holds them, along with
+    // those of every other script built there.
+    let profile_dir = format!(
+        "{build_dir}/target/{}",
+        if is_preview { "debug" } else { "release" }
+    );
+    let artifact_key = if tokio::fs::metadata(format!("{profile_dir}/.fingerprint"))
+        .await
+        .is_err()
+    {
+        rust_deps_artifact_key(job_dir, is_preview).await
+    } else {
+        None
+    };
+    let restored = match &artifact_key {
+        Some(key) => compilation_cache::restore(key, &profile_dir).await,
+        None => None,
+    };
+    if let Some(hit) = restored {
+        append_logs(
+            &job.id,
+            &job.workspace_id,
+            hit.describe("the compiled dependencies"),
+            conn,
+        )
+        .await;
+    }
+
     let child = if is_sandboxing_enabled() {
         let _ = write_file(
             job_dir,
@@ -581,6 +611,12 @@ pub async fn build_rust_crate(
         ))
     })?;
 
+    if let (Some(key), None) = (&artifact_key, restored) {
+        if let Err(e) = store_rust_deps(key, &profile_dir).await {
+            tracing::error!("could not store the compiled dependencies of {hash}: {e:?}");
+        }
+    }
+
     match save_cache(
         &bin_path,
         &format!("{RUST_OBJECT_STORE_PREFIX}{hash}"),
@@ -600,6 +636,65 @@ pub async fn build_rust_crate(
     }
 }
 
+/// Key of the dependencies compiled for a crate, which are the same for every script with
+/// the same manifest, lockfile and registries. `None` without a lockfile, as cargo would then
+/// resolve the dependencies anew.
+async fn rust_deps_artifact_key(job_dir: &str, is_preview: bool) -> Option<ArtifactKey> {
+    let rustc = std::path::Path::new(CARGO_PATH.as_str()).with_file_name(if cfg!(windows) {
+        "rustc.exe"
+    } else {
+        "rustc"
+    });
+    let toolchain = toolchain_version(
+        rustc.to_str()?,
+        &["-vV"],
+        &[
+            ("PATH", PATH_ENV.as_str()),
+            ("HOME", HOME_ENV.as_str()),
+            ("CARGO_HOME", CARGO_HOME.as_str()),
+            ("RUSTUP_HOME", RUSTUP_HOME.as_str()),
+        ],
+    )
+    .await?;
+    let manifest = tokio::fs::read_to_string(format!("{job_dir}/Cargo.toml"))
+        .await
+        .ok()?;
+    let lock = tokio::fs::read_to_string(format!("{job_dir}/Cargo.lock"))
+        .await
+        .ok()?;
+    let registries = tokio::fs::read_to_string(format!("{job_dir}/.cargo/config.toml"))
+        .await
+        .unwrap_or_default();
+    Some(ArtifactKey::new(
+        ArtifactKind::RustDeps,
+        &toolchain,
+        &[
+            if is_preview { "debug" } else { "release" },
+            &manifest,
+            &lock,
+            &registries,
+        ],
+    ))
+}
+
+/// Stores what cargo keeps in a profile directory for the dependencies, leaving out the
+/// script's own `main` crate.
+async fn store_rust_deps(key: &ArtifactKey, profile_dir: &str) -> error::Result<()> {
+    let mut paths = vec![];
+    for dir in ["deps", "build", ".fingerprint"] {
+        let Ok(mut entries) = tokio::fs::read_dir(format!("{profile_dir}/{dir}")).await else {
+            continue;
+        };
+        while let Some(entry) = entries.next_entry().await? {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name != "main" && !name.starts_with("main-") && !name.starts_with("main.") {
+                paths.push(format!("{dir}/{name}"));
+            }
+        }
+    }
+    compilation_cache::store(key, profile_dir, paths).await
+}
+
 /// Cache key of a Rust build. The run path and the deploy-time prebuild must derive it
 /// the same way or the prebuilt binary is never found and gets rebuilt on first run.
 async fn rust_cache_key(code: &str, requirements_o: Option<&String>, w_id: &str) -> String {
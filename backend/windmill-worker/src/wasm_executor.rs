@@ -0,0 +1,1127 @@
+//! WebAssembly component scripts, run in-process on wasmtime.
+//!
+//! The script is a WIT world whose exported `main` is the entrypoint, and a
+//! `// component: <path>` line points at the compiled component in the
+//! workspace object store. Like Lua, a job never spawns a process: fuel bounds
+//! how much the component computes, a resource limiter bounds its linear
+//! memories, and the engine's epoch is how a canceled job is stopped. WASI
+//! gives it clocks, randomness, env and stdio, but no preopened directories
+//! and no sockets. Its stdout and stderr go to the job logs, which stop at the
+//! size of a result. A component blocked in a host call, like a WASI sleep, only
+//! notices cancellation once that call returns.
+//!
+//! Arguments and results are JSON mapped through the types the component
+//! itself declares for `main`: records are objects, variants and results are
+//! `{"case": payload}` (or the bare case name), flags are arrays of names and
+//! a `list<u8>` argument also takes base64. An `err` result fails the job.
+
+use std::collections::HashMap;
+use std::sync::{
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+    Arc, Mutex, OnceLock,
+};
+use std::time::Duration;
+
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+use bytes::Bytes;
+use futures::stream;
+use serde_json::{json, value::RawValue, Map, Number, Value};
+use sha2::{Digest, Sha256};
+use tokio::sync::mpsc;
+use wasmtime::{
+    component::{Component, Func, Linker, ResourceTable, Type, Val},
+    Config, Engine, ResourceLimiter, Store, Trap, UpdateDeadline,
+};
+use wasmtime_wasi::{
+    IoView, OutputStream, Pollable, StdoutStream, StreamError, WasiCtx, WasiCtxBuilder, WasiView,
+};
+use windmill_common::{
+    client::AuthedClient,
+    error::{Error, Result},
+    worker::{to_raw_value, Connection},
+};
+use windmill_queue::{append_logs, CanceledBy, MiniPulledJob};
+
+use crate::{
+    common::{build_args_map, get_reserved_variables, OccupancyMetrics},
+    handle_child::run_future_with_polling_update_job_poller,
+};
+
+lazy_static::lazy_static! {
+    static ref WASM_MAX_FUEL: u64 = std::env::var("WASM_MAX_FUEL")
+        .ok()
+        .and_then(|x| x.parse().ok())
+        .unwrap_or(10_000_000_000);
+    static ref WASM_MEMORY_LIMIT_MB: usize = std::env::var("WASM_MEMORY_LIMIT_MB")
+        .ok()
+        .and_then(|x| x.parse().ok())
+        .unwrap_or(256);
+    static ref COMPONENTS: Mutex<HashMap<String, Component>> = Mutex::new(HashMap::new());
+}
+
+/// How often the engine's epoch advances. Cancellation is checked at every
+/// tick, so this is how long a canceled job may keep running.
+const EPOCH_TICK: Duration = Duration::from_millis(10);
+
+/// Compiled components kept in memory. Past this, the cache starts over.
+const MAX_CACHED_COMPONENTS: usize = 64;
+
+const MAX_TABLE_ELEMENTS: usize = 1 << 20;
+
+/// Pieces of logs waiting to be appended. A component writing faster than
+/// its logs are stored waits for room.
+const LOG_CHANNEL_CAPACITY: usize = 256;
+
+/// The most a component may write in one go.
+const MAX_LOG_WRITE: usize = 64 * 1024;
+
+/// What a job logs past this is dropped.
+const MAX_LOG_SIZE: usize = crate::MAX_RESULT_SIZE;
+
+const FUEL_LIMIT_REACHED: &str = "Fuel limit reached";
+const JOB_INTERRUPTED: &str = "Job was interrupted";
+
+#[allow(clippy::too_many_arguments)]
+pub async fn do_wasm(
+    job: &MiniPulledJob,
+    client: &AuthedClient,
+    code: &str,
+    conn: &Connection,
+    mem_peak: &mut i32,
+    canceled_by: &mut Option<CanceledBy>,
+    worker_name: &str,
+    occupancy_metrics: &mut OccupancyMetrics,
+    parent_runnable_path: Option<String>,
+) -> Result<Box<RawValue>> {
+    let location = windmill_parser_wit::parse_wit_component(code).ok_or_else(|| {
+        Error::ExecutionErr(
+            "The script must point at its compiled component with a `// component: <path>` line"
+                .to_string(),
+        )
+    })?;
+    let args = build_args_map(job, client, conn).await?;
+    let job_args = args.as_ref().or(job.args.as_ref().map(|x| &x.0));
+    let args = job_args
+        .into_iter()
+        .flatten()
+        .map(|(name, v)| {
+            serde_json::from_str(v.get())
+                .map(|v| (name.clone(), v))
+                .map_err(|e| Error::ExecutionErr(format!("Invalid value for argument {name}: {e}")))
+        })
+        .collect::<Result<HashMap<_, _>>>()?;
+
+    let mut env = get_reserved_variables(job, &client.token, conn, parent_runnable_path).await?;
+    env.insert("BASE_URL".to_string(), client.base_internal_url.clone());
+    env.insert(
+        "BASE_INTERNAL_URL".to_string(),
+        client.base_internal_url.clone(),
+    );
+
+    let (log_tx, mut log_rx) = mpsc::channel::<String>(LOG_CHANNEL_CAPACITY);
+    let log_job_id = job.id;
+    let log_w_id = job.workspace_id.clone();
+    let log_conn = conn.clone();
+    let log_task = tokio::spawn(async move {
+        while let Some(line) = log_rx.recv().await {
+            append_logs(&log_job_id, &log_w_id, line, &log_conn).await;
+        }
+    });
+
+    let interrupted = Arc::new(AtomicBool::new(false));
+    let peak_memory = Arc::new(AtomicUsize::new(0));
+    let result_f = async {
+        // The poller drops this future on cancel or timeout, which stops the
+        // component at the next epoch tick.
+        let _interrupt = InterruptOnDrop(interrupted.clone());
+        let bytes = client
+            .download_s3_file(&job.workspace_id, &location.path, location.storage.clone())
+            .await
+            .map_err(|e| {
+                Error::ExecutionErr(format!(
+                    "Could not download component {}: {e}",
+                    location.path
+                ))
+            })?;
+        let wasm = WasmJob {
+            bytes,
+            args,
+            env,
+            log: LogPipe::new(log_tx, MAX_LOG_SIZE),
+            interrupted: interrupted.clone(),
+            max_fuel: *WASM_MAX_FUEL,
+            memory_limit: *WASM_MEMORY_LIMIT_MB * 1024 * 1024,
+        };
+        let (result, peak) = tokio::task::spawn_blocking(move || wasm.run())
+            .await
+            .map_err(|e| Error::ExecutionErr(format!("wasm component panicked: {e}")))?;
+        peak_memory.store(peak, Ordering::Relaxed);
+        result.map(|value| to_raw_value(&value))
+    };
+
+    let result = run_future_with_polling_update_job_poller(
+        job.id,
+        job.timeout,
+        conn,
+        mem_peak,
+        canceled_by,
+        result_f,
+        worker_name,
+        &job.workspace_id,
+        &mut Some(occupancy_metrics),
+        Box::pin(stream::once(async { 0 })),
+    )
+    .await;
+
+    let _ = log_task.await;
+    *mem_peak = (peak_memory.load(Ordering::Relaxed) / 1024) as i32;
+    result
+}
+
+struct InterruptOnDrop(Arc<AtomicBool>);
+
+impl Drop for InterruptOnDrop {
+    fn drop(&mut self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
+
+/// The engine all jobs share, and the thread that drives its epoch.
+fn engine() -> Result<&'static Engine> {
+    static ENGINE: OnceLock<std::result::Result<Engine, String>> = OnceLock::new();
+    ENGINE
+        .get_or_init(|| {
+            let mut config = Config::new();
+            config
+                .wasm_component_model(true)
+                .consume_fuel(true)
+                .epoch_interruption(true);
+            let engine = Engine::new(&config).map_err(|e| e.to_string())?;
+            let ticker = engine.clone();
+            std::thread::Builder::new()
+                .name("wasm-epoch".to_string())
+                .spawn(move || loop {
+                    std::thread::sleep(EPOCH_TICK);
+                    ticker.increment_epoch();
+                })
+                .map_err(|e| e.to_string())?;
+            Ok(engine)
+        })
+        .as_ref()
+        .map_err(|e| Error::ExecutionErr(format!("Could not start the wasm engine: {e}")))
+}
+
+/// Compiles the component, or reuses the compilation of identical bytes.
+/// Compiled code is only ever kept in memory: loading it back from disk would
+/// run whatever native code is found there.
+fn load_component(engine: &Engine, bytes: &[u8]) -> Result<Component> {
+    let hash = format!("{:x}", Sha256::digest(bytes));
+    if let Some(component) = COMPONENTS.lock().unwrap().get(&hash) {
+        return Ok(component.clone());
+    }
+    let component = Component::new(engine, bytes)
+        .map_err(|e| Error::ExecutionErr(format!("Invalid wasm component: {e:#}")))?;
+    let mut components = COMPONENTS.lock().unwrap();
+    if components.len() >= MAX_CACHED_COMPONENTS {
+        components.clear();
+    }
+    components.insert(hash, component.clone());
+    Ok(component)
+}
+
+/// Caps the linear memories of the whole component, not each one.
+struct MemoryLimiter {
+    limit: usize,
+    used: usize,
+    peak: usize,
+    exceeded: bool,
+}
+
+impl ResourceLimiter for MemoryLimiter {
+    fn memory_growing(
+        &mut self,
+        current: usize,
+        desired: usize,
+        _maximum: Option<usize>,
+    ) -> anyhow::Result<bool> {
+        let used = self.used + desired.saturating_sub(current);
+        if used > self.limit {
+            self.exceeded = true;
+            return Ok(false);
+        }
+        self.used = used;
+        self.peak = self.peak.max(used);
+        Ok(true)
+    }
+
+    fn table_growing(
+        &mut self,
+        _current: usize,
+        desired: usize,
+        _maximum: Option<usize>,
+    ) -> anyhow::Result<bool> {
+        Ok(desired <= MAX_TABLE_ELEMENTS)
+    }
+}
+
+struct JobState {
+    wasi: WasiCtx,
+    table: ResourceTable,
+    memory: MemoryLimiter,
+    interrupted: Arc<AtomicBool>,
+}
+
+impl IoView for JobState {
+    fn table(&mut self) -> &mut ResourceTable {
+        &mut self.table
+    }
+}
+
+impl WasiView for JobState {
+    fn ctx(&mut self) -> &mut WasiCtx {
+        &mut self.wasi
+    }
+}
+
+/// Sends what the component writes to stdout and stderr to the job logs as it
+/// comes, up to `limit` bytes for both.
+#[derive(Clone)]
+struct LogPipe {
+    tx: mpsc::Sender<String>,
+    limit: usize,
+    remaining: Arc<AtomicUsize>,
+    truncated: Arc<AtomicBool>,
+}
+
+impl LogPipe {
+    fn new(tx: mpsc::Sender<String>, limit: usize) -> Self {
+        Self {
+            tx,
+            limit,
+            remaining: Arc::new(AtomicUsize::new(limit)),
+            truncated: Arc::new(AtomicBool::new(false)),
+        }
+    }
+}
+
+impl StdoutStream for LogPipe {
+    fn stream(&self) -> Box<dyn OutputStream> {
+        Box::new(self.clone())
+    }
+
+    fn isatty(&self) -> bool {
+        false
+    }
+}
+
+#[async_trait::async_trait]
+impl Pollable for LogPipe {
+    async fn ready(&mut self) {
+        // Room in the channel is what `check_write` reports.
+        let _ = self.tx.reserve().await;
+    }
+}
+
+impl OutputStream for LogPipe {
+    fn write(&mut self, bytes: Bytes) -> std::result::Result<(), StreamError> {
+        let remaining = self.remaining.load(Ordering::Relaxed);
+        let kept = bytes.len().min(remaining);
+        self.remaining.store(remaining - kept, Ordering::Relaxed);
+        let mut logs = String::from_utf8_lossy(&bytes[..kept]).into_owned();
+        if kept < bytes.len() && !self.truncated.swap(true, Ordering::Relaxed) {
+            logs.push_str(&format!("\n[logs truncated at {} bytes]\n", self.limit));
+        }
+        if !logs.is_empty() {
+            // `check_write` only allows a write once there is room for it.
+            let _ = self.tx.try_send(logs);
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> std::result::Result<(), StreamError> {
+        Ok(())
+    }
+
+    fn check_write(&mut self) -> std::result::Result<usize, StreamError> {
+        let dropped = self.truncated.load(Ordering::Relaxed) || self.tx.is_closed();
+        if !dropped && self.tx.capacity() == 0 {
+            return Ok(0);
+        }
+        Ok(MAX_LOG_WRITE)
+    }
+}
+
+struct WasmJob {
+    bytes: Bytes,
+    args: HashMap<String, Value>,
+    env: HashMap<String, String>,
+    log: LogPipe,
+    interrupted: Arc<AtomicBool>,
+    max_fuel: u64,
+    memory_limit: usize,
+}
+
+impl WasmJob {
+    /// Runs `main`, returning its result and the peak size of the
+    /// component's memories.
+    fn run(self) -> (Result<Value>, usize) {
+        let engine = match engine() {
+            Ok(engine) => engine,
+            Err(e) => return (Err(e), 0),
+        };
+        let component = match load_component(engine, &self.bytes) {
+            Ok(component) => component,
+            Err(e) => return (Err(e), 0),
+        };
+
+        let env = self.env.iter().collect::<Vec<_>>();
+        let wasi = WasiCtxBuilder::new()
+            .stdout(self.log.clone())
+            .stderr(self.log.clone())
+            .envs(&env)
+            .args(&["main"])
+            .build();
+        let mut store = Store::new(
+            engine,
+            JobState {
+                wasi,
+                table: ResourceTable::new(),
+                memory: MemoryLimiter {
+                    limit: self.memory_limit,
+                    used: 0,
+                    peak: 0,
+                    exceeded: false,
+                },
+                interrupted: self.interrupted.clone(),
+            },
+        );
+        store.limiter(|state| &mut state.memory);
+        store.epoch_deadline_callback(|store| {
+            if store.data().interrupted.load(Ordering::Relaxed) {
+                Err(anyhow::anyhow!(JOB_INTERRUPTED))
+            } else {
+                Ok(UpdateDeadline::Continue(1))
+            }
+        });
+        store.set_epoch_deadline(1);
+
+        let result = store
+            .set_fuel(self.max_fuel)
+            .and_then(|()| self.call_main(&component, &mut store));
+        let state = store.data();
+        let result = match result {
+            Ok(Ok(value)) => Ok(value),
+            Ok(Err(e)) => Err(e),
+            Err(e) => Err(to_execution_error(e, &state.memory, self.max_fuel)),
+        };
+        (result, state.memory.peak)
+    }
+
+    /// The outer error is a trap or a failure to link; the inner one is the
+    /// job's own error, from bad arguments or an `err` result.
+    fn call_main(
+        &self,
+        component: &Component,
+        store: &mut Store<JobState>,
+    ) -> anyhow::Result<Result<Value>> {
+        let mut linker = Linker::new(store.engine());
+        wasmtime_wasi::add_to_linker_sync(&mut linker)?;
+        let instance = linker.instantiate(&mut *store, component)?;
+        let Some(main) = instance.get_func(&mut *store, "main") else {
+            return Ok(Err(Error::ExecutionErr(
+                "The component must export a function named main".to_string(),
+            )));
+        };
+
+        let params = match self.params(&main, store) {
+            Ok(params) => params,
+            Err(e) => return Ok(Err(e)),
+        };
+        let result_types = main.results(&*store);
+        let mut results = vec![Val::Bool(false); result_types.len()];
+        main.call(&mut *store, &params, &mut results)?;
+        main.post_return(&mut *store)?;
+
+        Ok(match results.as_slice() {
+            [] => Ok(Value::Null),
+            [Val::Result(Err(e))] => {
+                Err(Error::ExecutionErr(match e.as_deref().map(val_to_json) {
+                    Some(Value::String(s)) => s,
+                    Some(e) => e.to_string(),
+                    None => "main returned an error".to_string(),
+                }))
+            }
+            [Val::Result(Ok(v))] => Ok(v.as_deref().map(val_to_json).unwrap_or(Value::Null)),
+            [v] => Ok(val_to_json(v)),
+            vs => Ok(Value::Array(vs.iter().map(val_to_json).collect())),
+        })
+    }
+
+    /// Lines the job's arguments up with the parameters of `main`, converted
+    /// to the types the component declares.
+    fn params(&self, main: &Func, store: &Store<JobState>) -> Result<Vec<Val>> {
+        main.params(store)
+            .iter()
+            .map(|(name, ty)| {
+                let value = match (self.args.get(name), ty) {
+                    (Some(v), _) => json_to_val(v, ty),
+                    (None, Type::Option(_)) => Ok(Val::Option(None)),
+                    (None, _) => Err("missing".to_string()),
+                };
+                value.map_err(|e| Error::ExecutionErr(format!("Invalid argument {name}: {e}")))
+            })
+            .collect()
+    }
+}
+
+fn to_execution_error(e: anyhow::Error, memory: &MemoryLimiter, max_fuel: u64) -> Error {
+    if memory.exceeded {
+        return Error::ExecutionErr(format!(
+            "Memory limit of {} MB reached",
+            memory.limit / 1024 / 1024
+        ));
+    }
+    if let Some(Trap::OutOfFuel) = e.downcast_ref::<Trap>() {
+        return Error::ExecutionErr(format!("{FUEL_LIMIT_REACHED} ({max_fuel} units)"));
+    }
+    if let Some(exit) = e.downcast_ref::<wasmtime_wasi::I32Exit>() {
+        return Error::ExecutionErr(format!("The component exited with code {}", exit.0));
+    }
+    if e.chain().any(|cause| cause.to_string() == JOB_INTERRUPTED) {
+        return Error::ExecutionErr(JOB_INTERRUPTED.to_string());
+    }
+    Error::ExecutionErr(format!("{e:?}"))
+}
+
+/// Converts a JSON argument to a component value of type `ty`. A `list<u8>`
+/// also takes a base64 string, which is how bytes arguments are sent.
+fn json_to_val(v: &Value, ty: &Type) -> std::result::Result<Val, String> {
+    macro_rules! int {
+        ($variant:ident, $t:ty) => {
+            v.as_i64()
+                .and_then(|n| <$t>::try_from(n).ok())
+                .or_else(|| v.as_u64().and_then(|n| <$t>::try_from(n).ok()))
+                .map(Val::$variant)
+                .ok_or_else(|| mismatch(ty, v))
+        };
+    }
+    match ty {
+        Type::Bool => v.as_bool().map(Val::Bool).ok_or_else(|| mismatch(ty, v)),
+        Type::S8 => int!(S8, i8),
+        Type::U8 => int!(U8, u8),
+        Type::S16 => int!(S16, i16),
+        Type::U16 => int!(U16, u16),
+        Type::S32 => int!(S32, i32),
+        Type::U32 => int!(U32, u32),
+        Type::S64 => int!(S64, i64),
+        Type::U64 => int!(U64, u64),
+        Type::Float32 => v
+            .as_f64()
+            .map(|f| Val::Float32(f as f32))
+            .ok_or_else(|| mismatch(ty, v)),
+        Type::Float64 => v.as_f64().map(Val::Float64).ok_or_else(|| mismatch(ty, v)),
+        Type::Char => {
+            let mut chars = v.as_str().map(str::chars).ok_or_else(|| mismatch(ty, v))?;
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => Ok(Val::Char(c)),
+                _ => Err(mismatch(ty, v)),
+            }
+        }
+        Type::String => v
+            .as_str()
+            .map(|s| Val::String(s.to_string()))
+            .ok_or_else(|| mismatch(ty, v)),
+        Type::List(list) => match (v, list.ty()) {
+            (Value::String(s), Type::U8) => BASE64
+                .decode(s)
+                .map(|bytes| Val::List(bytes.into_iter().map(Val::U8).collect()))
+                .map_err(|e| format!("invalid base64: {e}")),
+            (Value::Array(items), elem) => items
+                .iter()
+                .map(|item| json_to_val(item, &elem))
+                .collect::<std::result::Result<_, _>>()
+                .map(Val::List),
+            _ => Err(mismatch(ty, v)),
+        },
+        Type::Record(record) => {
+            let object = v.as_object().ok_or_else(|| mismatch(ty, v))?;
+            record
+                .fields()
+                .map(|field| {
+                    let value = match (object.get(field.name), &field.ty) {
+                        (Some(v), ty) => json_to_val(v, ty),
+                        (None, Type::Option(_)) => Ok(Val::Option(None)),
+                        (None, _) => Err("missing".to_string()),
+                    };
+                    value
+                        .map(|value| (field.name.to_string(), value))
+                        .map_err(|e| format!("field {}: {e}", field.name))
+                })
+                .collect::<std::result::Result<_, _>>()
+                .map(Val::Record)
+        }
+        Type::Tuple(tuple) => match v.as_array() {
+            Some(items) if items.len() == tuple.types().len() => items
+                .iter()
+                .zip(tuple.types())
+                .map(|(item, ty)| json_to_val(item, &ty))
+                .collect::<std::result::Result<_, _>>()
+                .map(Val::Tuple),
+            _ => Err(mismatch(ty, v)),
+        },
+        Type::Variant(variant) => {
+            let (name, payload) = single_case(v).ok_or_else(|| mismatch(ty, v))?;
+            let case = variant
+                .cases()
+                .find(|case| case.name == name)
+                .ok_or_else(|| format!("unknown case {name}"))?;
+            let payload = match (case.ty, payload) {
+                (Some(ty), Some(payload)) => Some(Box::new(json_to_val(payload, &ty)?)),
+                (Some(_), None) => return Err(format!("case {name} needs a value")),
+                (None, _) => None,
+            };
+            Ok(Val::Variant(name.to_string(), payload))
+        }
+        Type::Enum(enum_) => match v.as_str() {
+            Some(s) if enum_.names().any(|name| name == s) => Ok(Val::Enum(s.to_string())),
+            _ => Err(mismatch(ty, v)),
+        },
+        Type::Option(option) => match v {
+            Value::Null => Ok(Val::Option(None)),
+            v => Ok(Val::Option(Some(Box::new(json_to_val(v, &option.ty())?)))),
+        },
+        Type::Result(result) => {
+            let (name, payload) = single_case(v).ok_or_else(|| mismatch(ty, v))?;
+            let (ty, wrap): (_, fn(_) -> _) = match name {
+                "ok" => (result.ok(), Ok),
+                "err" => (result.err(), Err),
+                _ => return Err(mismatch(ty, v)),
+            };
+            let payload = match (ty, payload) {
+                (Some(ty), Some(payload)) => Some(Box::new(json_to_val(payload, &ty)?)),
+                (Some(_), None) => return Err(format!("{name} needs a value")),
+                (None, _) => None,
+            };
+            Ok(Val::Result(wrap(payload)))
+        }
+        Type::Flags(flags) => {
+            let items = v.as_array().ok_or_else(|| mismatch(ty, v))?;
+            items
+                .iter()
+                .map(|item| match item.as_str() {
+                    Some(s) if flags.names().any(|name| name == s) => Ok(s.to_string()),
+                    _ => Err(format!("unknown flag {item}")),
+                })
+                .collect::<std::result::Result<_, _>>()
+                .map(Val::Flags)
+        }
+        Type::Own(_) | Type::Borrow(_) => Err("resources cannot be passed to main".to_string()),
+    }
+}
+
+/// A variant or result case, written either as its bare name or as an object
+/// with the case name as its single key.
+fn single_case(v: &Value) -> Option<(&str, Option<&Value>)> {
+    match v {
+        Value::String(name) => Some((name, None)),
+        Value::Object(object) if object.len() == 1 => object
+            .iter()
+            .next()
+            .map(|(name, v)| (name.as_str(), Some(v))),
+        _ => None,
+    }
+}
+
+fn mismatch(ty: &Type, v: &Value) -> String {
+    let expected = match ty {
+        Type::Bool => "bool",
+        Type::S8 | Type::U8 | Type::S16 | Type::U16 => "a small integer",
+        Type::S32 | Type::U32 | Type::S64 | Type::U64 => "an integer",
+        Type::Float32 | Type::Float64 => "a number",
+        Type::Char => "a single character",
+        Type::String | Type::Enum(_) => "a string",
+        Type::List(_) | Type::Tuple(_) | Type::Flags(_) => "an array",
+        Type::Record(_) => "an object",
+        Type::Variant(_) | Type::Result(_) => "a case name or a single key object",
+        Type::Option(_) => "a value or null",
+        Type::Own(_) | Type::Borrow(_) => "a resource",
+    };
+    format!("expected {expected}, got {v}")
+}
+
+fn val_to_json(v: &Val) -> Value {
+    match v {
+        Val::Bool(b) => json!(b),
+        Val::S8(n) => json!(n),
+        Val::U8(n) => json!(n),
+        Val::S16(n) => json!(n),
+        Val::U16(n) => json!(n),
+        Val::S32(n) => json!(n),
+        Val::U32(n) => json!(n),
+        Val::S64(n) => json!(n),
+        Val::U64(n) => json!(n),
+        Val::Float32(f) => float_to_json(*f as f64),
+        Val::Float64(f) => float_to_json(*f),
+        Val::Char(c) => json!(c),
+        Val::String(s) => json!(s),
+        Val::List(items) | Val::Tuple(items) => items.iter().map(val_to_json).collect(),
+        Val::Record(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(name, v)| (name.clone(), val_to_json(v)))
+                .collect(),
+        ),
+        Val::Variant(name, None) | Val::Enum(name) => json!(name),
+        Val::Variant(name, Some(payload)) => json!({ name: val_to_json(payload) }),
+        Val::Option(v) => v.as_deref().map(val_to_json).unwrap_or(Value::Null),
+        Val::Result(result) => {
+            let (name, payload) = match result {
+                Ok(payload) => ("ok", payload),
+                Err(payload) => ("err", payload),
+            };
+            let mut object = Map::new();
+            object.insert(
+                name.to_string(),
+                payload.as_deref().map(val_to_json).unwrap_or(Value::Null),
+            );
+            Value::Object(object)
+        }
+        Val::Flags(names) => json!(names),
+        Val::Resource(_) => Value::Null,
+    }
+}
+
+fn float_to_json(f: f64) -> Value {
+    Number::from_f64(f)
+        .map(Value::Number)
+        .unwrap_or(Value::Null)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOUBLE: &str = r#"
+(component
+  (core module $m
+    (func (export "main") (param i32) (result i32)
+      local.get 0
+      i32.const 2
+      i32.mul))
+  (core instance $i (instantiate $m))
+  (func (export "main") (param "n" u32) (result u32)
+    (canon lift (core func $i "main"))))
+"#;
+
+    const LOOP: &str = r#"
+(component
+  (core module $m
+    (func (export "main")
+      (loop $l
+        (br $l))))
+  (core instance $i (instantiate $m))
+  (func (export "main")
+    (canon lift (core func $i "main"))))
+"#;
+
+    /// Grows its memory by 1 MiB until it can't, then traps.
+    const GROW: &str = r#"
+(component
+  (core module $m
+    (memory 1)
+    (func (export "main")
+      (loop $l
+        (br_if $l (i32.ne (memory.grow (i32.const 16)) (i32.const -1))))
+      unreachable))
+  (core instance $i (instantiate $m))
+  (func (export "main")
+    (canon lift (core func $i "main"))))
+"#;
+
+    /// A `main` taking one parameter of each kind of compound type.
+    const ARGS: &str = r#"
+(component
+  (core module $m
+    (memory (export "memory") 1)
+    (func (export "realloc") (param i32 i32 i32 i32) (result i32)
+      i32.const 0)
+    (func (export "main")
+      (param i32 i32 i32 i32 i32 i32 i32 i64 i32 i32 i32 i32 i32 i32 i32)))
+  (core instance $i (instantiate $m))
+  (alias core export $i "memory" (core memory $memory))
+  (alias core export $i "realloc" (core func $realloc))
+  (type $point' (record (field "x" u32) (field "y" (option u32))))
+  (export $point "point" (type $point'))
+  (type $shape' (variant (case "empty") (case "circle" u32)))
+  (export $shape "shape" (type $shape'))
+  (type $color' (enum "red" "green"))
+  (export $color "color" (type $color'))
+  (type $perms' (flags "read" "write"))
+  (export $perms "perms" (type $perms'))
+  (func (export "main")
+    (param "bytes" (list u8))
+    (param "maybe" (option u32))
+    (param "outcome" (result u32 (error u8)))
+    (param "pair" (tuple u8 s64))
+    (param "point" $point)
+    (param "shape" $shape)
+    (param "color" $color)
+    (param "perms" $perms)
+    (canon lift (core func $i "main") (memory $memory) (realloc $realloc))))
+"#;
+
+    struct TestJob {
+        wat: &'static str,
+        args: Value,
+        max_fuel: u64,
+        memory_limit_mb: usize,
+        interrupted: bool,
+    }
+
+    impl TestJob {
+        fn new(wat: &'static str) -> Self {
+            Self {
+                wat,
+                args: json!({}),
+                max_fuel: 10_000_000,
+                memory_limit_mb: 64,
+                interrupted: false,
+            }
+        }
+
+        fn job(self) -> WasmJob {
+            let (log_tx, _) = mpsc::channel(LOG_CHANNEL_CAPACITY);
+            WasmJob {
+                bytes: wat::parse_str(self.wat).unwrap().into(),
+                args: serde_json::from_value(self.args).unwrap(),
+                env: HashMap::new(),
+                log: LogPipe::new(log_tx, MAX_LOG_SIZE),
+                interrupted: Arc::new(AtomicBool::new(self.interrupted)),
+                max_fuel: self.max_fuel,
+                memory_limit: self.memory_limit_mb * 1024 * 1024,
+            }
+        }
+
+        /// Runs the job, returning its result and its peak memory.
+        fn run(self) -> (Result<Value>, usize) {
+            self.job().run()
+        }
+    }
+
+    fn error_message(result: Result<Value>) -> String {
+        match result {
+            Err(Error::ExecutionErr(e)) => e,
+            other => panic!("expected an execution error, got {other:?}"),
+        }
+    }
+
+    /// The parameters of `main` for the job's arguments, as `run` passes them.
+    fn params(job: TestJob) -> Result<Vec<Val>> {
+        let job = job.job();
+        let engine = engine().unwrap();
+        let component = load_component(engine, &job.bytes).unwrap();
+        let mut store = Store::new(
+            engine,
+            JobState {
+                wasi: WasiCtxBuilder::new().build(),
+                table: ResourceTable::new(),
+                memory: MemoryLimiter {
+                    limit: job.memory_limit,
+                    used: 0,
+                    peak: 0,
+                    exceeded: false,
+                },
+                interrupted: job.interrupted.clone(),
+            },
+        );
+        let mut linker = Linker::new(engine);
+        wasmtime_wasi::add_to_linker_sync(&mut linker).unwrap();
+        let instance = linker.instantiate(&mut store, &component).unwrap();
+        let main = instance.get_func(&mut store, "main").unwrap();
+        job.params(&main, &store)
+    }
+
+    #[test]
+    fn runs_main() {
+        let mut job = TestJob::new(DOUBLE);
+        job.args = json!({ "n": 21 });
+        assert_eq!(job.run().0.unwrap(), json!(42));
+
+        let mut job = TestJob::new(DOUBLE);
+        job.args = json!({ "n": -1 });
+        assert_eq!(
+            error_message(job.run().0),
+            "Invalid argument n: expected an integer, got -1"
+        );
+        assert_eq!(
+            error_message(TestJob::new(DOUBLE).run().0),
+            "Invalid argument n: missing"
+        );
+    }
+
+    #[test]
+    fn stops_when_out_of_fuel() {
+        let mut job = TestJob::new(LOOP);
+        job.max_fuel = 100_000;
+        assert_eq!(
+            error_message(job.run().0),
+            "Fuel limit reached (100000 units)"
+        );
+    }
+
+    #[test]
+    fn stops_at_the_memory_limit() {
+        let mut job = TestJob::new(GROW);
+        job.memory_limit_mb = 4;
+        let (result, peak) = job.run();
+        assert_eq!(error_message(result), "Memory limit of 4 MB reached");
+        // The initial page and three more MiB, the fourth one going past the limit.
+        assert_eq!(peak, 64 * 1024 + 3 * 1024 * 1024);
+    }
+
+    #[test]
+    fn stops_when_interrupted() {
+        let mut job = TestJob::new(LOOP);
+        job.max_fuel = 1 << 62;
+        job.interrupted = true;
+        assert_eq!(error_message(job.run().0), JOB_INTERRUPTED);
+    }
+
+    #[test]
+    fn limits_all_memories_together() {
+        let mut limiter = MemoryLimiter { limit: 100, used: 0, peak: 0, exceeded: false };
+        assert!(limiter.memory_growing(0, 60, None).unwrap());
+        assert!(limiter.memory_growing(60, 90, None).unwrap());
+        // A second memory counts towards the same limit.
+        assert!(!limiter.memory_growing(0, 20, None).unwrap());
+        assert!(limiter.exceeded);
+        assert_eq!((limiter.used, limiter.peak), (90, 90));
+        assert!(limiter.memory_growing(0, 10, None).unwrap());
+        assert_eq!((limiter.used, limiter.peak), (100, 100));
+
+        assert!(limiter.table_growing(0, MAX_TABLE_ELEMENTS, None).unwrap());
+        assert!(!limiter
+            .table_growing(0, MAX_TABLE_ELEMENTS + 1, None)
+            .unwrap());
+    }
+
+    #[test]
+    fn converts_scalar_arguments() {
+        assert_eq!(json_to_val(&json!(true), &Type::Bool), Ok(Val::Bool(true)));
+        assert_eq!(json_to_val(&json!(255), &Type::U8), Ok(Val::U8(255)));
+        assert_eq!(
+            json_to_val(&json!(256), &Type::U8),
+            Err("expected a small integer, got 256".to_string())
+        );
+        assert_eq!(json_to_val(&json!(-128), &Type::S8), Ok(Val::S8(-128)));
+        assert_eq!(
+            json_to_val(&json!(u64::MAX), &Type::U64),
+            Ok(Val::U64(u64::MAX))
+        );
+        assert_eq!(
+            json_to_val(&json!("1"), &Type::U32),
+            Err(r#"expected an integer, got "1""#.to_string())
+        );
+        assert_eq!(
+            json_to_val(&json!(1.5), &Type::Float64),
+            Ok(Val::Float64(1.5))
+        );
+        assert_eq!(
+            json_to_val(&json!(2), &Type::Float32),
+            Ok(Val::Float32(2.0))
+        );
+        assert_eq!(json_to_val(&json!("é"), &Type::Char), Ok(Val::Char('é')));
+        assert_eq!(
+            json_to_val(&json!("ab"), &Type::Char),
+            Err(r#"expected a single character, got "ab""#.to_string())
+        );
+        assert_eq!(
+            json_to_val(&json!("hi"), &Type::String),
+            Ok(Val::String("hi".to_string()))
+        );
+    }
+
+    fn valid_args() -> Value {
+        json!({
+            "bytes": "aGk=",
+            "outcome": { "err": 3 },
+            "pair": [1, -2],
+            "point": { "x": 1 },
+            "shape": { "circle": 2 },
+            "color": "green",
+            "perms": ["read"],
+        })
+    }
+
+    #[test]
+    fn converts_compound_arguments() {
+        let mut job = TestJob::new(ARGS);
+        job.args = valid_args();
+        assert_eq!(
+            params(job).unwrap(),
+            vec![
+                Val::List(vec![Val::U8(b'h'), Val::U8(b'i')]),
+                Val::Option(None),
+                Val::Result(Err(Some(Box::new(Val::U8(3))))),
+                Val::Tuple(vec![Val::U8(1), Val::S64(-2)]),
+                Val::Record(vec![
+                    ("x".to_string(), Val::U32(1)),
+                    ("y".to_string(), Val::Option(None)),
+                ]),
+                Val::Variant("circle".to_string(), Some(Box::new(Val::U32(2)))),
+                Val::Enum("green".to_string()),
+                Val::Flags(vec!["read".to_string()]),
+            ]
+        );
+
+        let mut job = TestJob::new(ARGS);
+        job.args = valid_args();
+        job.args["bytes"] = json!([1, 2]);
+        job.args["maybe"] = json!(7);
+        job.args["outcome"] = json!({ "ok": 5 });
+        job.args["shape"] = json!("empty");
+        let params = params(job).unwrap();
+        assert_eq!(params[0], Val::List(vec![Val::U8(1), Val::U8(2)]));
+        assert_eq!(params[1], Val::Option(Some(Box::new(Val::U32(7)))));
+        assert_eq!(params[2], Val::Result(Ok(Some(Box::new(Val::U32(5))))));
+        assert_eq!(params[5], Val::Variant("empty".to_string(), None));
+    }
+
+    #[test]
+    fn rejects_invalid_compound_arguments() {
+        let cases = [
+            (
+                "bytes",
+                json!("not base64!"),
+                "Invalid argument bytes: invalid base64",
+            ),
+            (
+                "outcome",
+                json!({ "maybe": 1 }),
+                "Invalid argument outcome: expected a case name",
+            ),
+            (
+                "outcome",
+                json!("ok"),
+                "Invalid argument outcome: ok needs a value",
+            ),
+            (
+                "pair",
+                json!([1]),
+                "Invalid argument pair: expected an array, got [1]",
+            ),
+            (
+                "point",
+                json!({ "y": 1 }),
+                "Invalid argument point: field x: missing",
+            ),
+            (
+                "shape",
+                json!("square"),
+                "Invalid argument shape: unknown case square",
+            ),
+            (
+                "shape",
+                json!("circle"),
+                "Invalid argument shape: case circle needs a value",
+            ),
+            (
+                "color",
+                json!("blue"),
+                r#"Invalid argument color: expected a string, got "blue""#,
+            ),
+            (
+                "perms",
+                json!(["exec"]),
+                r#"Invalid argument perms: unknown flag "exec""#,
+            ),
+        ];
+        for (name, value, expected) in cases {
+            let mut job = TestJob::new(ARGS);
+            job.args = valid_args();
+            job.args[name] = value;
+            let message = match params(job) {
+                Err(Error::ExecutionErr(e)) => e,
+                other => panic!("expected an execution error for {name}, got {other:?}"),
+            };
+            assert!(message.starts_with(expected), "{message}");
+        }
+    }
+
+    #[test]
+    fn converts_results_to_json() {
+        assert_eq!(
+            val_to_json(&Val::Record(vec![
+                ("x".to_string(), Val::U32(1)),
+                ("y".to_string(), Val::Option(None)),
+            ])),
+            json!({ "x": 1, "y": null })
+        );
+        assert_eq!(
+            val_to_json(&Val::Variant(
+                "circle".to_string(),
+                Some(Box::new(Val::U32(2)))
+            )),
+            json!({ "circle": 2 })
+        );
+        assert_eq!(
+            val_to_json(&Val::Variant("empty".to_string(), None)),
+            json!("empty")
+        );
+        assert_eq!(val_to_json(&Val::Enum("green".to_string())), json!("green"));
+        assert_eq!(val_to_json(&Val::Result(Ok(None))), json!({ "ok": null }));
+        assert_eq!(
+            val_to_json(&Val::Result(Err(Some(Box::new(Val::String(
+                "no".to_string()
+            )))))),
+            json!({ "err": "no" })
+        );
+        assert_eq!(
+            val_to_json(&Val::Flags(vec!["read".to_string()])),
+            json!(["read"])
+        );
+        assert_eq!(
+            val_to_json(&Val::Tuple(vec![Val::Char('a'), Val::Float64(f64::NAN)])),
+            json!(["a", null])
+        );
+        assert_eq!(
+            val_to_json(&Val::List(vec![Val::S64(-1), Val::U64(u64::MAX)])),
+            json!([-1, u64::MAX])
+        );
+    }
+
+    fn received(rx: &mut mpsc::Receiver<String>) -> Vec<String> {
+        let mut logs = vec![];
+        while let Ok(logs_piece) = rx.try_recv() {
+            logs.push(logs_piece);
+        }
+        logs
+    }
+
+    #[test]
+    fn truncates_the_logs() {
+        let (tx, mut rx) = mpsc::channel(LOG_CHANNEL_CAPACITY);
+        let mut stdout = LogPipe::new(tx, 10);
+        let mut stderr = stdout.clone();
+        stdout.write(Bytes::from_static(b"hello ")).unwrap();
+        stderr.write(Bytes::from_static(b"world!")).unwrap();
+        stdout.write(Bytes::from_static(b"dropped")).unwrap();
+        assert_eq!(
+            received(&mut rx),
+            vec!["hello ", "worl\n[logs truncated at 10 bytes]\n"]
+        );
+        // Once truncated, writes never wait for the logs.
+        assert_eq!(stdout.check_write().unwrap(), MAX_LOG_WRITE);
+    }
+
+    #[test]
+    fn waits_for_room_in_the_log_channel() {
+        let (tx, mut rx) = mpsc::channel(1);
+        let mut pipe = LogPipe::new(tx, MAX_LOG_SIZE);
+        assert_eq!(pipe.check_write().unwrap(), MAX_LOG_WRITE);
+        pipe.write(Bytes::from_static(b"a")).unwrap();
+        assert_eq!(pipe.check_write().unwrap(), 0);
+        assert_eq!(received(&mut rx), vec!["a"]);
+        assert_eq!(pipe.check_write().unwrap(), MAX_LOG_WRITE);
+    }
+}
@@ -168,12 +168,18 @@ use crate::nu_executor::{handle_nu_job, JobHandlerInput as JobHandlerInputNu};
 #[cfg(feature = "java")]
 use crate::java_executor::{handle_java_job, JobHandlerInput as JobHandlerInputJava};
 
+#[cfg(feature = "kotlin")]
+use crate::kotlin_executor::handle_kotlin_job;
+
 #[cfg(feature = "ruby")]
 use crate::ruby_executor::{handle_ruby_job, JobHandlerInput as JobHandlerInputRuby};
 
 #[cfg(feature = "rlang")]
 use crate::r_executor::{handle_r_job, JobHandlerInput as JobHandlerInputRlang};
 
+#[cfg(feature = "perl")]
+use crate::perl_executor::{handle_perl_job, JobHandlerInput as JobHandlerInputPerl};
+
 #[cfg(feature = "php")]
 use crate::php_executor::handle_php_job;
 
@@ -206,6 +212,11 @@ use crate::mssql_executor::do_mssql;
 #[cfg(feature = "bigquery")]
 use crate::bigquery_executor::do_bigquery;
 
+#[cfg(feature = "lua")]
+use crate::lua_executor::do_lua;
+#[cfg(feature = "wasm")]
+use crate::wasm_executor::do_wasm;
+
 #[cfg(feature = "benchmark")]
 use windmill_common::bench::{benchmark_init, benchmark_verify, BenchmarkInfo, BenchmarkIter};
 
@@ -237,18 +248,25 @@ lazy_static::lazy_static! {
     pub static ref JAVA_REPOSITORY_DIR: String = format!("{}/repository", *JAVA_CACHE_DIR);
     pub static ref JAVA_HOME_DIR: String = format!("{}/home", *JAVA_CACHE_DIR);
 
+    // Kotlin
+    pub static ref KOTLIN_CACHE_DIR: String = format!("{}kotlin", *ROOT_CACHE_DIR);
+
     // Ruby
     pub static ref RUBY_CACHE_DIR: String = format!("{}ruby", *ROOT_CACHE_DIR);
 
     // R
     pub static ref R_CACHE_DIR: String = format!("{}rlang", *ROOT_CACHE_DIR);
 
+    // Perl
+    pub static ref PERL_CACHE_DIR: String = format!("{}perl", *ROOT_CACHE_DIR);
+
     // for related places search: ADD_NEW_LANG
     pub static ref BUN_CACHE_DIR: String = format!("{}bun", *ROOT_CACHE_NOMOUNT_DIR);
     pub static ref BUN_BUNDLE_CACHE_DIR: String = format!("{}bun", *ROOT_CACHE_DIR);
     pub static ref BUN_CODEBASE_BUNDLE_CACHE_DIR: String = format!("{}script_bundle", *ROOT_CACHE_NOMOUNT_DIR);
 
     pub static ref GO_BIN_CACHE_DIR: String = format!("{}gobin", *ROOT_CACHE_DIR);
+    pub static ref COMPILATION_CACHE_DIR: String = format!("{}compilation", *ROOT_CACHE_DIR);
     pub static ref POWERSHELL_CACHE_DIR: String = format!("{}powershell", *ROOT_CACHE_DIR);
     pub static ref COMPOSER_CACHE_DIR: String = format!("{}composer", *ROOT_CACHE_DIR);
 
@@ -787,6 +805,8 @@ pub enum JobIsolationLevel {
     Unshare = 2,
     /// Full nsjail sandboxing
     NsjailSandboxing = 3,
+    /// Built-in Landlock + seccomp sandbox, see `landlock_sandbox`
+    Landlock = 4,
 }
 
 impl JobIsolationLevel {
@@ -795,6 +815,7 @@ impl JobIsolationLevel {
             1 => Self::None,
             2 => Self::Unshare,
             3 => Self::NsjailSandboxing,
+            4 => Self::Landlock,
             _ => Self::Undefined,
         }
     }
@@ -804,6 +825,7 @@ impl JobIsolationLevel {
             "none" => Self::None,
             "unshare" => Self::Unshare,
             "nsjail_sandboxing" => Self::NsjailSandboxing,
+            "landlock" => Self::Landlock,
             _ => Self::Undefined,
         }
     }
@@ -813,6 +835,16 @@ pub fn get_job_isolation() -> JobIsolationLevel {
     JobIsolationLevel::from_u8(JOB_ISOLATION.load(Ordering::Relaxed))
 }
 
+/// Whether the worker group's `job_isolation` selects the Landlock sandbox. It is the only
+/// mode a worker group can select, nsjail and unshare follow the instance-wide setting.
+fn is_landlock_selected_by_worker_group() -> bool {
+    WORKER_CONFIG
+        .load()
+        .job_isolation
+        .as_deref()
+        .is_some_and(|x| JobIsolationLevel::from_str(x) == JobIsolationLevel::Landlock)
+}
+
 /// Returns true if nsjail sandboxing should be used for job execution.
 /// DISABLE_NSJAIL=false forces nsjail regardless of the global setting.
 pub async fn read_ee_registry<T>(
@@ -974,6 +1006,35 @@ pub fn is_unshare_enabled() -> bool {
     }
 }
 
+/// Returns true if job processes should run in the built-in Landlock + seccomp sandbox.
+/// nsjail and unshare take precedence, the unshare binary would be denied its syscall.
+pub fn is_landlock_enabled() -> bool {
+    !is_sandboxing_enabled()
+        && !is_unshare_enabled()
+        && (get_job_isolation() == JobIsolationLevel::Landlock
+            || is_landlock_selected_by_worker_group())
+}
+
+/// Returns true if the kernel supports the Landlock sandbox.
+pub fn is_landlock_available() -> bool {
+    #[cfg(target_os = "linux")]
+    {
+        *crate::landlock_sandbox::LANDLOCK_SUPPORTED
+    }
+    #[cfg(not(target_os = "linux"))]
+    false
+}
+
+/// Returns true if the processes spawned by the current job go through the egress proxy.
+pub fn is_egress_restricted() -> bool {
+    #[cfg(target_os = "linux")]
+    {
+        crate::egress_proxy::is_restricted()
+    }
+    #[cfg(not(target_os = "linux"))]
+    false
+}
+
 /// Check if OTEL tracing proxy is enabled for a specific language (EE only)
 pub async fn is_otel_tracing_proxy_enabled_for_lang(lang: &ScriptLang) -> bool {
     cfg!(all(feature = "private", feature = "enterprise")) && {
@@ -1044,6 +1105,9 @@ pub fn get_otel_context_envs(job_id: &uuid::Uuid) -> Vec<(&'static str, String)>
 /// stock corporate proxy. Routing git's HTTPS through the local MITM breaks TLS for
 /// GitHub/GitLab in chained-upstream-proxy setups, and we don't need HTTP spans for the
 /// system git sync script anyway.
+///
+/// Jobs with an egress allowlist bypass it too: their traffic goes through the egress proxy
+/// instead, and the tracing proxy's CA would then break their TLS.
 pub async fn get_proxy_envs_for_lang(
     lang: &ScriptLang,
     job_kind: JobKind,
@@ -1055,6 +1119,7 @@ pub async fn get_proxy_envs_for_lang(
     let mut envs;
     #[cfg(all(feature = "private", feature = "enterprise"))]
     if !matches!(job_kind, JobKind::DeploymentCallback)
+        && !is_egress_restricted()
         && is_otel_tracing_proxy_enabled_for_lang(lang).await
     {
         envs = get_otel_tracing_proxy_envs(job_id, w_id, conn).await?;
@@ -1304,7 +1369,7 @@ const MIN_GO_BUILD_MEMLIMIT: usize = 128 * 1024 * 1024;
 /// the duckdb error is rendered by the FFI crate's own copy of that helper, which
 /// rounds to a fraction above 1 GiB, and every limit an error quotes is meant to
 /// be usable as a setting verbatim.
-fn parse_byte_size(v: &str) -> Option<usize> {
+pub(crate) fn parse_byte_size(v: &str) -> Option<usize> {
     let upper = v.trim().to_ascii_uppercase();
     // Longest-first: `GB` would otherwise swallow `GIB`, and `B` every other suffix.
     let (digits, mult) = [
@@ -2586,6 +2651,13 @@ pub async fn run_worker(
             See errors above for the specific reason nsjail initialization failed."
         );
     }
+    if is_landlock_enabled() && !is_landlock_available() {
+        tracing::error!(
+            worker = %worker_name, hostname = %hostname,
+            "Worker is configured to use the Landlock sandbox but Landlock is NOT available \
+            (Linux 5.13 or later with Landlock enabled is required). Jobs will fail."
+        );
+    }
 
     let start_time = Instant::now();
 
@@ -5473,8 +5545,31 @@ async fn handle_code_execution_job(
     )
     .await?;
 
+    let egress = windmill_common::egress::EgressPolicy::for_job(
+        code,
+        WORKER_CONFIG.load().egress_allowlist.as_ref(),
+    )
+    .map_err(Error::ExecutionErr)?;
+    if let Some(lang) = language
+        .as_ref()
+        .filter(|lang| egress.is_restricted() && runs_in_worker_process(lang))
+    {
+        return Err(Error::ExecutionErr(format!(
+            "Egress allowlists cannot be enforced for {} scripts, which run within the worker \
+            process rather than in a process of their own. Remove the allowlist of the script \
+            or run it on a worker group without one",
+            lang.as_str()
+        )));
+    }
+    #[cfg(not(target_os = "linux"))]
+    if egress.is_restricted() {
+        return Err(Error::ExecutionErr(
+            "Egress allowlists are only enforced on Linux workers".to_string(),
+        ));
+    }
+
     let language = language.clone();
-    run_language_executor(
+    let executor = run_language_executor(
         job,
         conn,
         client,
@@ -5499,8 +5594,38 @@ async fn handle_code_execution_job(
         &modules,
         false,
         in_pipeline,
+    );
+
+    #[cfg(target_os = "linux")]
+    {
+        let limits = windmill_common::worker::JobResourceLimits::from_code(code)
+            .within(WORKER_CONFIG.load().job_resource_limits);
+        let executor = crate::job_cgroup::scope(job.id, limits, executor);
+        let egress = crate::egress_proxy::JobEgress::new(job, conn, base_internal_url, egress);
+        let executor = crate::egress_proxy::scope(egress, executor);
+        crate::landlock_sandbox::scope(job_dir, executor).await
+    }
+    #[cfg(not(target_os = "linux"))]
+    executor.await
+}
+
+/// Languages executed by the worker itself: their connections can't be moved to the network
+/// namespace of the egress proxy.
+fn runs_in_worker_process(language: &ScriptLang) -> bool {
+    matches!(
+        language,
+        ScriptLang::Nativets
+            | ScriptLang::Postgresql
+            | ScriptLang::Mysql
+            | ScriptLang::Bigquery
+            | ScriptLang::Snowflake
+            | ScriptLang::Mssql
+            | ScriptLang::OracleDB
+            | ScriptLang::DuckDb
+            | ScriptLang::Graphql
+            | ScriptLang::Lua
+            | ScriptLang::Wasm
     )
-    .await
 }
 
 /// True when `path` contains only `Normal`/`CurDir` components, i.e. it cannot
@@ -6016,6 +6141,72 @@ pub async fn run_language_executor(
             occupancy_metrics,
         ))
         .await;
+    } else if language == Some(ScriptLang::Lua) {
+        #[cfg(not(feature = "lua"))]
+        return Err(Error::internal_err(
+            "Lua requires the lua feature to be enabled".to_string(),
+        ));
+
+        #[cfg(feature = "lua")]
+        {
+            if run_inline {
+                return Err(Error::internal_err(
+                    "Inline execution is not yet supported for this language".to_string(),
+                ));
+            }
+            append_logs(
+                &job.id,
+                &job.workspace_id,
+                "\n--- LUA CODE EXECUTION ---\n",
+                conn,
+            )
+            .await;
+            return Box::pin(do_lua(
+                job,
+                &client,
+                &code,
+                conn,
+                mem_peak,
+                canceled_by,
+                worker_name,
+                occupancy_metrics,
+                parent_runnable_path,
+            ))
+            .await;
+        }
+    } else if language == Some(ScriptLang::Wasm) {
+        #[cfg(not(feature = "wasm"))]
+        return Err(Error::internal_err(
+            "WebAssembly components require the wasm feature to be enabled".to_string(),
+        ));
+
+        #[cfg(feature = "wasm")]
+        {
+            if run_inline {
+                return Err(Error::internal_err(
+                    "Inline execution is not yet supported for this language".to_string(),
+                ));
+            }
+            append_logs(
+                &job.id,
+                &job.workspace_id,
+                "\n--- WASM COMPONENT EXECUTION ---\n",
+                conn,
+            )
+            .await;
+            return Box::pin(do_wasm(
+                job,
+                &client,
+                &code,
+                conn,
+                mem_peak,
+                canceled_by,
+                worker_name,
+                occupancy_metrics,
+                parent_runnable_path,
+            ))
+            .await;
+        }
     } else if language == Some(ScriptLang::Nativets) {
         if run_inline {
             return Err(Error::internal_err(
@@ -6635,6 +6826,39 @@ mount {{
                 .await
             }
         }
+        ScriptLang::Kotlin => {
+            #[cfg(not(feature = "kotlin"))]
+            return Err(anyhow::anyhow!(
+                "Kotlin is not available because the feature is not enabled"
+            )
+            .into());
+
+            #[cfg(feature = "kotlin")]
+            {
+                if run_inline {
+                    return Err(Error::internal_err(
+                        "Inline execution is not yet supported for this language".to_string(),
+                    ));
+                }
+                Box::pin(handle_kotlin_job(JobHandlerInputJava {
+                    mem_peak,
+                    canceled_by,
+                    job,
+                    conn,
+                    client,
+                    parent_runnable_path,
+                    inner_content: &code,
+                    job_dir,
+                    requirements_o: lock.as_ref(),
+                    shared_mount: &shared_mount,
+                    base_internal_url,
+                    worker_name,
+                    envs,
+                    occupancy_metrics,
+                }))
+                .await
+            }
+        }
         ScriptLang::Ruby => {
             #[cfg(not(feature = "ruby"))]
             return Err(anyhow::anyhow!(
@@ -6668,6 +6892,39 @@ mount {{
                 .await
             }
         }
+        ScriptLang::Perl => {
+            #[cfg(not(feature = "perl"))]
+            return Err(anyhow::anyhow!(
+                "Perl is not available because the feature is not enabled"
+            )
+            .into());
+
+            #[cfg(feature = "perl")]
+            {
+                if run_inline {
+                    return Err(Error::internal_err(
+                        "Inline execution is not yet supported for this language".to_string(),
+                    ));
+                }
+                Box::pin(handle_perl_job(JobHandlerInputPerl {
+                    mem_peak,
+                    canceled_by,
+                    job,
+                    conn,
+                    client,
+                    parent_runnable_path,
+                    inner_content: &code,
+                    job_dir,
+                    requirements_o: lock.as_ref(),
+                    shared_mount: &shared_mount,
+                    base_internal_url,
+                    worker_name,
+                    envs,
+                    occupancy_metrics,
+                }))
+                .await
+            }
+        }
         ScriptLang::Rlang => {
             #[cfg(not(feature = "rlang"))]
             return Err(
@@ -6853,6 +7110,14 @@ pub fn parse_sig_of_lang(
             ScriptLang::Java => Some(windmill_parser_java::parse_java_signature(code)?),
             #[cfg(not(feature = "java"))]
             ScriptLang::Java => None,
+            #[cfg(feature = "kotlin")]
+            ScriptLang::Kotlin => Some(windmill_parser_kotlin::parse_kotlin_signature(code)?),
+            #[cfg(not(feature = "kotlin"))]
+            ScriptLang::Kotlin => None,
+            #[cfg(feature = "perl")]
+            ScriptLang::Perl => Some(windmill_parser_perl::parse_perl_signature(code)?),
+            #[cfg(not(feature = "perl"))]
+            ScriptLang::Perl => None,
             #[cfg(feature = "ruby")]
             ScriptLang::Ruby => Some(windmill_parser_ruby::parse_ruby_signature(code)?),
             #[cfg(not(feature = "ruby"))]
@@ -6862,6 +7127,14 @@ pub fn parse_sig_of_lang(
             #[cfg(not(feature = "rlang"))]
             ScriptLang::Rlang => None,
             ScriptLang::Dbt => Some(windmill_parser_yaml::parse_dbt_sig(code)?),
+            #[cfg(feature = "lua")]
+            ScriptLang::Lua => Some(windmill_parser_lua::parse_lua_signature(code)?),
+            #[cfg(not(feature = "lua"))]
+            ScriptLang::Lua => None,
+            #[cfg(feature = "wasm")]
+            ScriptLang::Wasm => Some(windmill_parser_wit::parse_wit_signature(code)?),
+            #[cfg(not(feature = "wasm"))]
+            ScriptLang::Wasm => None,
             // for related places search: ADD_NEW_LANG
         }
     } else {
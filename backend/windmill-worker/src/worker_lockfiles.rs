@@ -63,6 +63,11 @@ use crate::csharp_executor::generate_nuget_lockfile;
 #[cfg(feature = "java")]
 use crate::java_executor;
 
+#[cfg(feature = "kotlin")]
+use crate::kotlin_executor;
+
+#[cfg(feature = "perl")]
+use crate::perl_executor;
 #[cfg(feature = "rlang")]
 use crate::r_executor;
 #[cfg(feature = "ruby")]
@@ -3258,6 +3263,17 @@ async fn capture_dependency_job(
             )
             .await?
         }
+        #[cfg(feature = "kotlin")]
+        ScriptLang::Kotlin => {
+            kotlin_executor::resolve(
+                job_id,
+                job_raw_code,
+                job_dir,
+                &Connection::Sql(db.clone()),
+                w_id,
+            )
+            .await?
+        }
         #[cfg(feature = "ruby")]
         ScriptLang::Ruby => {
             ruby_executor::resolve(
@@ -3272,6 +3288,8 @@ async fn capture_dependency_job(
             )
             .await?
         }
+        #[cfg(feature = "perl")]
+        ScriptLang::Perl => perl_executor::resolve(job_raw_code)?,
         #[cfg(feature = "rlang")]
         ScriptLang::Rlang => {
             r_executor::resolve(
@@ -3292,6 +3310,8 @@ async fn capture_dependency_job(
         _ => "".to_owned(),
     };
 
+    windmill_common::vulnerabilities::check_deployment(db, w_id, job_language, &lock).await?;
+
     let mut lines = vec![];
     add_lock_header(&mut lines, workspace_dependencies, *job_language, w_id, db).await?;
     Ok(if lines.is_empty() {
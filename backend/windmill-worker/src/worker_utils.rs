@@ -199,6 +199,8 @@ pub async fn insert_ping(
 
     let job_isolation = if crate::is_sandboxing_enabled() && crate::NSJAIL_AVAILABLE.is_some() {
         Some("nsjail".to_string())
+    } else if crate::is_landlock_enabled() && crate::is_landlock_available() {
+        Some("landlock".to_string())
     } else if crate::is_unshare_enabled() && crate::UNSHARE_PATH.is_some() {
         Some("unshare".to_string())
     } else {
@@ -279,6 +281,8 @@ pub async fn update_worker_ping_from_job(
 
     let job_isolation = if crate::is_sandboxing_enabled() && crate::NSJAIL_AVAILABLE.is_some() {
         Some("nsjail".to_string())
+    } else if crate::is_landlock_enabled() && crate::is_landlock_available() {
+        Some("landlock".to_string())
     } else if crate::is_unshare_enabled() && crate::UNSHARE_PATH.is_some() {
         Some("unshare".to_string())
     } else {
@@ -300,7 +300,7 @@ export const settings: Record<string, Setting[]> = {
 			key: 'job_isolation',
 			fieldType: 'select',
 			description:
-				'Isolation mode for job execution. None: no isolation. Unshare: PID namespace isolation via unshare. Nsjail: full nsjail sandboxing. <a href="https://www.windmill.dev/docs/advanced/security_isolation">Learn more</a>',
+				'Isolation mode for job execution. None: no isolation. Unshare: PID namespace isolation via unshare. Nsjail: full nsjail sandboxing. Landlock: built-in Landlock + seccomp sandbox restricting jobs to their own directory, without nsjail (Linux 5.13+). Landlock can also be selected per worker group with `job_isolation: landlock` in the worker group config. <a href="https://www.windmill.dev/docs/advanced/security_isolation">Learn more</a>',
 			storage: 'setting',
 			select_items: [
 				{
@@ -314,6 +314,10 @@ export const settings: Record<string, Setting[]> = {
 				{
 					label: 'Nsjail',
 					value: 'nsjail_sandboxing'
+				},
+				{
+					label: 'Landlock',
+					value: 'landlock'
 				}
 			]
 		},